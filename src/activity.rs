@@ -0,0 +1,499 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write as _,
+    sync::Mutex,
+};
+
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::retention::RetentionPolicy,
+    types::{
+        ActivityEntryOut, ActivityErrorOut, ActivityHistoryOut, ActivityReportOut,
+        MaintenanceItemOut, MethodStatsOut, SwapEntryOut, SwapVolumeOut,
+    },
+};
+
+/// Errors listed individually in a report.
+const RECENT_ERRORS: usize = 10;
+/// Swaps listed individually in a report.
+const RECENT_SWAPS: usize = 20;
+const MAX_MEMO_CHARS: usize = 280;
+const MAX_TAGS: usize = 8;
+const MAX_TAG_CHARS: usize = 32;
+
+#[derive(Debug, Clone)]
+struct CallRecord {
+    at: u64,
+    method: String,
+    error: Option<(i32, String)>,
+}
+
+/// A successful swap simulation, amounts in human units.
+#[derive(Debug, Clone)]
+pub struct SwapRecord {
+    pub at: u64,
+    pub from_symbol: String,
+    pub to_symbol: String,
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+    pub gas_estimate: u64,
+    pub memo: Option<String>,
+    /// Lowercased and deduplicated by [`annotations`].
+    pub tags: Vec<String>,
+}
+
+/// A successful write-tool call other than `swap_tokens`, e.g. a transfer or a swap plan.
+#[derive(Debug, Clone)]
+pub struct WriteRecord {
+    pub at: u64,
+    pub tool: String,
+    /// What the call would do, in one line.
+    pub summary: String,
+    pub memo: Option<String>,
+    /// Lowercased and deduplicated by [`annotations`].
+    pub tags: Vec<String>,
+}
+
+/// In-memory journal of tool calls, swap simulations and other write-tool calls since the
+/// server started, trimmed to its retention policy as entries arrive.
+#[derive(Debug, Default)]
+pub struct ActivityLog {
+    calls: Mutex<VecDeque<CallRecord>>,
+    swaps: Mutex<VecDeque<SwapRecord>>,
+    writes: Mutex<VecDeque<WriteRecord>>,
+    policy: RetentionPolicy,
+}
+
+impl ActivityLog {
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            policy,
+            ..Self::default()
+        }
+    }
+
+    pub fn record_call(&self, at: u64, method: &str, error: Option<(i32, &str)>) {
+        let mut calls = self.calls.lock().expect("activity lock poisoned");
+        calls.push_back(CallRecord {
+            at,
+            method: method.to_string(),
+            error: error.map(|(code, message)| (code, message.to_string())),
+        });
+        self.policy.prune(&mut calls, |call| call.at, at);
+    }
+
+    pub fn record_swap(&self, swap: SwapRecord) {
+        let at = swap.at;
+        let mut swaps = self.swaps.lock().expect("activity lock poisoned");
+        swaps.push_back(swap);
+        self.policy.prune(&mut swaps, |swap| swap.at, at);
+    }
+
+    pub fn record_write(&self, write: WriteRecord) {
+        let at = write.at;
+        let mut writes = self.writes.lock().expect("activity lock poisoned");
+        writes.push_back(write);
+        self.policy.prune(&mut writes, |write| write.at, at);
+    }
+
+    /// Apply the retention policy at `now`; returns how many calls, swaps and other writes were
+    /// dropped.
+    pub fn prune(&self, now: u64) -> (usize, usize, usize) {
+        let calls = self.policy.prune(
+            &mut self.calls.lock().expect("activity lock poisoned"),
+            |call| call.at,
+            now,
+        );
+        let swaps = self.policy.prune(
+            &mut self.swaps.lock().expect("activity lock poisoned"),
+            |swap| swap.at,
+            now,
+        );
+        let writes = self.policy.prune(
+            &mut self.writes.lock().expect("activity lock poisoned"),
+            |write| write.at,
+            now,
+        );
+        (calls, swaps, writes)
+    }
+
+    pub fn status(&self, removed: (usize, usize, usize)) -> [MaintenanceItemOut; 3] {
+        let calls = self.calls.lock().expect("activity lock poisoned").len();
+        let swaps = self.swaps.lock().expect("activity lock poisoned").len();
+        let writes = self.writes.lock().expect("activity lock poisoned").len();
+        [
+            MaintenanceItemOut::log("activity_calls", calls, &self.policy, removed.0),
+            MaintenanceItemOut::log("activity_swaps", swaps, &self.policy, removed.1),
+            MaintenanceItemOut::log("activity_writes", writes, &self.policy, removed.2),
+        ]
+    }
+
+    /// Every journaled write-tool call in `[since, now]`, newest first, only those tagged `tag`
+    /// when given, capped at `limit`.
+    pub fn history(
+        &self,
+        since: u64,
+        now: u64,
+        tag: Option<&str>,
+        limit: usize,
+    ) -> ActivityHistoryOut {
+        let tag = tag.map(|tag| tag.trim().to_lowercase());
+        let tagged = |tags: &[String]| tag.as_ref().is_none_or(|tag| tags.contains(tag));
+        let mut entries: Vec<ActivityEntryOut> = self
+            .swaps
+            .lock()
+            .expect("activity lock poisoned")
+            .iter()
+            .filter(|swap| swap.at >= since && tagged(&swap.tags))
+            .map(|swap| ActivityEntryOut {
+                at: swap.at,
+                tool: "swap_tokens".to_string(),
+                summary: format!(
+                    "swap {} {} for {} {}",
+                    swap.amount_in.normalize(),
+                    swap.from_symbol,
+                    swap.amount_out.normalize(),
+                    swap.to_symbol
+                ),
+                memo: swap.memo.clone(),
+                tags: swap.tags.clone(),
+            })
+            .collect();
+        entries.extend(
+            self.writes
+                .lock()
+                .expect("activity lock poisoned")
+                .iter()
+                .filter(|write| write.at >= since && tagged(&write.tags))
+                .map(|write| ActivityEntryOut {
+                    at: write.at,
+                    tool: write.tool.clone(),
+                    summary: write.summary.clone(),
+                    memo: write.memo.clone(),
+                    tags: write.tags.clone(),
+                }),
+        );
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.at));
+        let total = entries.len();
+        entries.truncate(limit);
+        ActivityHistoryOut {
+            period_start: since,
+            period_end: now,
+            total,
+            entries,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Summarise activity in `[since, now]`, counting only swaps tagged `tag` when given.
+    pub fn report(&self, since: u64, now: u64, tag: Option<&str>) -> ActivityReportOut {
+        let tag = tag.map(|tag| tag.trim().to_lowercase());
+        let calls: Vec<CallRecord> = self
+            .calls
+            .lock()
+            .expect("activity lock poisoned")
+            .iter()
+            .filter(|call| call.at >= since)
+            .cloned()
+            .collect();
+        let swaps: Vec<SwapRecord> = self
+            .swaps
+            .lock()
+            .expect("activity lock poisoned")
+            .iter()
+            .filter(|swap| swap.at >= since)
+            .filter(|swap| tag.as_ref().is_none_or(|tag| swap.tags.contains(tag)))
+            .cloned()
+            .collect();
+
+        let mut calls_by_method: BTreeMap<String, MethodStatsOut> = BTreeMap::new();
+        for call in &calls {
+            let stats = calls_by_method
+                .entry(call.method.clone())
+                .or_insert(MethodStatsOut {
+                    calls: 0,
+                    errors: 0,
+                });
+            stats.calls += 1;
+            stats.errors += usize::from(call.error.is_some());
+        }
+        let errors = calls.iter().filter(|call| call.error.is_some()).count();
+        let recent_errors: Vec<ActivityErrorOut> = calls
+            .iter()
+            .rev()
+            .filter_map(|call| {
+                call.error.as_ref().map(|(code, message)| ActivityErrorOut {
+                    at: call.at,
+                    method: call.method.clone(),
+                    code: *code,
+                    message: message.clone(),
+                })
+            })
+            .take(RECENT_ERRORS)
+            .collect();
+
+        let mut volume: BTreeMap<(String, String), (usize, Decimal, Decimal)> = BTreeMap::new();
+        for swap in &swaps {
+            let entry = volume
+                .entry((swap.from_symbol.clone(), swap.to_symbol.clone()))
+                .or_default();
+            entry.0 += 1;
+            entry.1 = entry.1.saturating_add(swap.amount_in);
+            entry.2 = entry.2.saturating_add(swap.amount_out);
+        }
+        let swap_volume: Vec<SwapVolumeOut> = volume
+            .into_iter()
+            .map(
+                |((from_token, to_token), (swaps, amount_in, amount_out))| SwapVolumeOut {
+                    from_token,
+                    to_token,
+                    swaps,
+                    amount_in: amount_in.normalize().to_string(),
+                    amount_out: amount_out.normalize().to_string(),
+                },
+            )
+            .collect();
+        let recent_swaps: Vec<SwapEntryOut> = swaps
+            .iter()
+            .rev()
+            .take(RECENT_SWAPS)
+            .map(|swap| SwapEntryOut {
+                at: swap.at,
+                from_token: swap.from_symbol.clone(),
+                to_token: swap.to_symbol.clone(),
+                amount_in: swap.amount_in.normalize().to_string(),
+                amount_out: swap.amount_out.normalize().to_string(),
+                memo: swap.memo.clone(),
+                tags: swap.tags.clone(),
+            })
+            .collect();
+        let estimated_gas: u64 = swaps.iter().map(|swap| swap.gas_estimate).sum();
+
+        let mut report = ActivityReportOut {
+            period_start: since,
+            period_end: now,
+            total_calls: calls.len(),
+            errors,
+            calls_by_method,
+            recent_errors,
+            simulated_swaps: swaps.len(),
+            swap_volume,
+            recent_swaps,
+            estimated_gas,
+            broadcast_transactions: 0,
+            fees_paid_wei: "0".to_string(),
+            summary: String::new(),
+            warnings: Vec::new(),
+        };
+        report.summary = render_summary(&report);
+        report
+    }
+}
+
+/// Validate a write tool's `memo` / `tags`: trimmed, tags lowercased and deduplicated.
+pub fn annotations(
+    memo: Option<String>,
+    tags: Vec<String>,
+) -> AppResult<(Option<String>, Vec<String>)> {
+    let memo = memo
+        .map(|memo| memo.trim().to_string())
+        .filter(|memo| !memo.is_empty());
+    if memo
+        .as_ref()
+        .is_some_and(|memo| memo.chars().count() > MAX_MEMO_CHARS)
+    {
+        return Err(AppError::InvalidInput(format!(
+            "memo must be at most {MAX_MEMO_CHARS} characters"
+        )));
+    }
+
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS {
+            return Err(AppError::InvalidInput(format!(
+                "tags must be 1 to {MAX_TAG_CHARS} characters"
+            )));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::InvalidInput(format!(
+            "at most {MAX_TAGS} tags per call"
+        )));
+    }
+    Ok((memo, normalized))
+}
+
+/// Plain-text rendering an agent can relay to the user verbatim.
+fn render_summary(report: &ActivityReportOut) -> String {
+    let secs = report.period_end.saturating_sub(report.period_start);
+    let period = if secs.is_multiple_of(3_600) {
+        format!("{}h", secs / 3_600)
+    } else {
+        format!("{}m", secs / 60)
+    };
+    let mut text = format!("Activity over the last {period}:\n");
+
+    let methods = report
+        .calls_by_method
+        .iter()
+        .map(|(method, stats)| format!("{method} {}", stats.calls))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(
+        text,
+        "- {} tool calls, {} failed{}",
+        report.total_calls,
+        report.errors,
+        if methods.is_empty() {
+            String::new()
+        } else {
+            format!(" ({methods})")
+        }
+    );
+
+    let _ = writeln!(text, "- {} swap simulations", report.simulated_swaps);
+    for pair in &report.swap_volume {
+        let _ = writeln!(
+            text,
+            "  - {} -> {}: {} swaps, {} {} in, {} {} out",
+            pair.from_token,
+            pair.to_token,
+            pair.swaps,
+            pair.amount_in,
+            pair.from_token,
+            pair.amount_out,
+            pair.to_token
+        );
+    }
+    let _ = writeln!(
+        text,
+        "- {} gas estimated across simulations; nothing was broadcast, so no fees were paid",
+        report.estimated_gas
+    );
+
+    if !report.recent_errors.is_empty() {
+        let _ = writeln!(text, "- Recent errors:");
+        for error in &report.recent_errors {
+            let _ = writeln!(text, "  - {}: {}", error.method, error.message);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_covers_only_the_requested_period() {
+        let log = ActivityLog::default();
+        log.record_call(50, "get_balance", None);
+        log.record_call(150, "get_token_price", None);
+        log.record_call(
+            160,
+            "swap_tokens",
+            Some((-32020, "quote returned zero output amount")),
+        );
+        log.record_call(170, "swap_tokens", None);
+        log.record_swap(SwapRecord {
+            at: 170,
+            from_symbol: "WETH".into(),
+            to_symbol: "USDC".into(),
+            amount_in: Decimal::new(15, 1),
+            amount_out: Decimal::from(3_000),
+            gas_estimate: 120_000,
+            memo: None,
+            tags: Vec::new(),
+        });
+
+        let report = log.report(100, 200, None);
+        assert_eq!(report.total_calls, 3);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.calls_by_method["swap_tokens"].calls, 2);
+        assert_eq!(report.calls_by_method["swap_tokens"].errors, 1);
+        assert!(!report.calls_by_method.contains_key("get_balance"));
+        assert_eq!(report.recent_errors[0].code, -32020);
+        assert_eq!(report.swap_volume[0].amount_in, "1.5");
+        assert_eq!(report.estimated_gas, 120_000);
+        assert!(report.summary.contains("WETH -> USDC: 1 swaps"));
+    }
+
+    #[test]
+    fn tag_filter_narrows_swaps() {
+        let log = ActivityLog::default();
+        let (memo, tags) = annotations(
+            Some("  rebalance leg 1 ".into()),
+            vec!["Momentum".into(), "momentum".into()],
+        )
+        .unwrap();
+        assert_eq!(memo.as_deref(), Some("rebalance leg 1"));
+        assert_eq!(tags, ["momentum"]);
+        for (at, tags) in [(10, tags), (20, Vec::new())] {
+            log.record_swap(SwapRecord {
+                at,
+                from_symbol: "WETH".into(),
+                to_symbol: "USDC".into(),
+                amount_in: Decimal::ONE,
+                amount_out: Decimal::from(3_000),
+                gas_estimate: 100_000,
+                memo: memo.clone(),
+                tags,
+            });
+        }
+
+        let report = log.report(0, 100, Some("MOMENTUM"));
+        assert_eq!(report.simulated_swaps, 1);
+        assert_eq!(report.recent_swaps[0].at, 10);
+        assert_eq!(
+            report.recent_swaps[0].memo.as_deref(),
+            Some("rebalance leg 1")
+        );
+        assert_eq!(log.report(0, 100, None).recent_swaps[0].at, 20);
+
+        assert!(annotations(None, vec![" ".into()]).is_err());
+        assert!(annotations(Some("x".repeat(281)), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn history_merges_writes_and_filters_by_tag() {
+        let log = ActivityLog::default();
+        log.record_swap(SwapRecord {
+            at: 10,
+            from_symbol: "WETH".into(),
+            to_symbol: "USDC".into(),
+            amount_in: Decimal::ONE,
+            amount_out: Decimal::from(3_000),
+            gas_estimate: 100_000,
+            memo: None,
+            tags: vec!["momentum".into()],
+        });
+        for (at, tool, tags) in [
+            (20, "transfer_from", vec!["payroll".to_string()]),
+            (30, "plan_swap", vec!["momentum".to_string()]),
+        ] {
+            log.record_write(WriteRecord {
+                at,
+                tool: tool.into(),
+                summary: format!("{tool} call"),
+                memo: Some("leg".into()),
+                tags,
+            });
+        }
+
+        let history = log.history(0, 100, Some("Momentum"), 10);
+        assert_eq!(history.total, 2);
+        assert_eq!(history.entries[0].tool, "plan_swap");
+        assert_eq!(history.entries[1].summary, "swap 1 WETH for 3000 USDC");
+
+        let latest = log.history(15, 100, None, 1);
+        assert_eq!(latest.total, 2);
+        assert_eq!(latest.entries.len(), 1);
+        assert_eq!(latest.entries[0].at, 30);
+    }
+}
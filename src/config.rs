@@ -1,25 +1,279 @@
-use crate::error::{AppError, AppResult};
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        block_scope::DefaultBlockTag,
+        ens::DEFAULT_ENS_CACHE_TTL_SECS,
+        fixture::FixtureMode,
+        gas_worth::GasWorthinessMode,
+        param_defaults::ParamDefaultRule,
+        price::{DEFAULT_DEPEG_THRESHOLD_BPS, SymbolCollisionPolicy, UsdQuoteMode},
+        provider_pool::ChainEndpoints,
+        quote_id::DEFAULT_QUOTE_TTL_SECS,
+        retention::{DEFAULT_CACHE_MAX_ENTRIES, DEFAULT_LOG_MAX_ROWS},
+        rpc::{BalanceStrategy, RpcEndpointConfig},
+        stuck_tx::{DEFAULT_FEE_BUMP_PERCENT, DEFAULT_STUCK_TX_BLOCKS, StuckTxPolicy},
+        v2::V2Venue,
+        whitelist::DEFAULT_WHITELIST_DELAY_SECS,
+    },
+    secrets::{SecretString, SecretsBackend},
+};
 use dotenvy::dotenv;
 use serde::Deserialize;
-use std::{env, fs, path::Path};
+use std::{collections::HashMap, env, fs, path::Path};
 
 const DEFAULT_CONFIG_PATH: &str = "Config.toml";
 const DEFAULT_CHAIN_ID: u64 = 1;
+const DEFAULT_KEYRING_SERVICE: &str = "walletmcp";
+pub const DEFAULT_GAS_BUFFER_PERCENT: u32 = 20;
 
 /// Strongly-typed configuration derived from a `Config.toml` or environment variables.
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     pub eth_rpc_url: String,
+    /// Extra read endpoints balanced alongside `eth_rpc_url`, which stays the write primary.
     #[serde(default)]
-    pub private_key: Option<String>,
+    pub rpc_endpoints: Vec<RpcEndpointConfig>,
+    #[serde(default)]
+    pub rpc_strategy: BalanceStrategy,
+    /// `record` every RPC exchange to `rpc_fixture_path`, or `replay` them from it offline.
+    #[serde(default)]
+    pub rpc_fixture_mode: Option<FixtureMode>,
+    #[serde(default)]
+    pub rpc_fixture_path: Option<String>,
+    /// Block tag for reads that do not pass `block`: `latest`, `safe`, or `finalized`.
+    #[serde(default)]
+    pub default_block_tag: DefaultBlockTag,
+    /// Optional WebSocket endpoint; required for subscription-based tools such as `watch_mempool`.
+    #[serde(default)]
+    pub eth_ws_url: Option<String>,
+    /// Other chains' endpoints (`[[chains]]` with `chain_id`, `rpc_url`, `ws_url`), connected
+    /// on first use.
+    #[serde(default)]
+    pub chains: Vec<ChainEndpoints>,
+    #[serde(default)]
+    pub private_key: Option<SecretString>,
+    /// Backend the signer key is read from (`config`, `env`, or `keyring`).
+    #[serde(default)]
+    pub secrets_backend: SecretsBackend,
+    /// Keyring service name used by the `keyring` secrets backend.
+    #[serde(default = "default_keyring_service")]
+    pub keyring_service: String,
+    /// Directory holding encrypted keystores of `create_burner_wallet`; burners are off when unset.
+    #[serde(default)]
+    pub burner_keystore_dir: Option<String>,
+    /// Password the burner keystores are encrypted with, read through the secrets backend.
+    #[serde(default)]
+    pub burner_password: Option<SecretString>,
     #[serde(default = "default_chain_id")]
     pub default_chain_id: u64,
+    /// Percentage added on top of `eth_estimateGas` when deriving a transaction gas limit.
+    #[serde(default = "default_gas_buffer_percent")]
+    pub gas_buffer_percent: u32,
+    /// Share of a swap's output value (bps) its gas may cost before `gas_worthiness_mode` applies.
+    #[serde(default)]
+    pub gas_worthiness_bps: Option<u32>,
+    /// `warn` flags uneconomic swaps; `refuse` fails them.
+    #[serde(default)]
+    pub gas_worthiness_mode: GasWorthinessMode,
+    /// Blocks a watched transaction may stay pending below the market fee before it is stuck.
+    #[serde(default = "default_stuck_tx_blocks")]
+    pub stuck_tx_blocks: u64,
+    /// `notify` reports stuck transactions; `bump` also signs a replacement for the wallet's own.
+    #[serde(default)]
+    pub stuck_tx_policy: StuckTxPolicy,
+    /// Percent a replacement raises a stuck transaction's fees by, at least.
+    #[serde(default = "default_fee_bump_percent")]
+    pub fee_bump_percent: u32,
+    /// Seconds a `swap_tokens` `quote_id` stays valid.
+    #[serde(default = "default_quote_ttl_secs")]
+    pub quote_ttl_secs: u64,
+    /// Forward internal events (price ticks, deposits, halts) as MCP `notifications/message`.
+    #[serde(default)]
+    pub event_notifications: bool,
+    /// Sign every tool result with the server wallet, under `attestation`.
+    #[serde(default)]
+    pub sign_responses: bool,
+    /// Defaults for params callers leave out, per tool and/or token (`[[param_defaults]]` with
+    /// `tool`, `token` and a `params` table).
+    #[serde(default)]
+    pub param_defaults: Vec<ParamDefaultRule>,
+    /// JSON file the paper-trading portfolio is kept in; in memory only when unset.
+    #[serde(default)]
+    pub paper_portfolio_path: Option<String>,
+    /// Stablecoins (registry symbols) standing in for USD in Uniswap quotes, in preference order.
+    #[serde(default = "default_usd_quote_tokens")]
+    pub usd_quote_tokens: Vec<String>,
+    /// `first` uses the first stablecoin that quotes; `median` takes the median across all of them.
+    #[serde(default)]
+    pub usd_quote_mode: UsdQuoteMode,
+    /// Deviation from $1 (bps) beyond which a USD stablecoin proxy is treated as depegged.
+    #[serde(default = "default_depeg_threshold_bps")]
+    pub depeg_threshold_bps: u32,
+    /// USD notional (e.g. `1000`) the Uniswap price fallback quotes; unset quotes one whole token.
+    #[serde(default)]
+    pub uniswap_reference_usd: Option<u64>,
+    /// Token a bare symbol resolves to when several registered tokens share it.
+    #[serde(default)]
+    pub symbol_collision_policy: SymbolCollisionPolicy,
+    /// Extra token names, e.g. `BTC = "WBTC"`; targets are symbols, `SYMBOL:address` or addresses.
+    #[serde(default)]
+    pub symbol_aliases: HashMap<String, String>,
+    /// Read the native symbol (`ETH`) as the wrapped token in price and swap tools.
+    #[serde(default = "default_native_symbol_alias")]
+    pub native_symbol_alias: bool,
+    /// Uniswap-style token list used to cross-check on-chain `decimals()`.
+    #[serde(default)]
+    pub token_list_path: Option<String>,
+    /// Local denylist (one address per line or a JSON array) screened before swaps.
+    #[serde(default)]
+    pub denylist_path: Option<String>,
+    /// Chainalysis-style sanctions API base URL (`GET {url}/{address}`).
+    #[serde(default)]
+    pub sanctions_api_url: Option<String>,
+    #[serde(default)]
+    pub sanctions_api_key: Option<SecretString>,
+    /// 0x API key; enables `gasless_swap`.
+    #[serde(default)]
+    pub gasless_api_key: Option<SecretString>,
+    /// 0x API base URL for gasless quotes; defaults to `https://api.0x.org`.
+    #[serde(default)]
+    pub gasless_api_url: Option<String>,
+    /// 0x Settler contracts a gasless order may be signed for, besides Permit2.
+    #[serde(default)]
+    pub gasless_settlers: Vec<String>,
+    /// Whether callers may bypass compliance hits with `compliance_override`.
+    #[serde(default)]
+    pub allow_compliance_override: bool,
+    /// Whether `sign_authorization` may sign EIP-7702 delegations of the wallet.
+    #[serde(default)]
+    pub allow_delegation_signing: bool,
+    /// JSON file of whitelisted swap recipients; when set, recipients other than the signer
+    /// must be whitelisted.
+    #[serde(default)]
+    pub recipient_whitelist_path: Option<String>,
+    /// Seconds a newly whitelisted recipient waits before it can be used.
+    #[serde(default = "default_whitelist_delay_secs")]
+    pub whitelist_delay_secs: u64,
+    /// Named swap recipients, e.g. `treasury = "0x..."`.
+    #[serde(default)]
+    pub recipient_aliases: HashMap<String, String>,
+    /// Recipient (address or alias) used when a swap does not name one; defaults to the signer.
+    #[serde(default)]
+    pub default_recipient: Option<String>,
+    /// Only allow the signer and alias addresses as swap recipients.
+    #[serde(default)]
+    pub restrict_recipients_to_aliases: bool,
+    /// Secret required by `resume` to lift a `panic_stop`; without it a halt lasts until restart.
+    #[serde(default)]
+    pub admin_secret: Option<SecretString>,
+    /// Disperse contract used by `distribute_tokens` in `disperse` mode.
+    #[serde(default)]
+    pub disperse_address: Option<String>,
+    /// Attach reverse-resolved ENS names to addresses in responses.
+    #[serde(default)]
+    pub ens_reverse_lookup: bool,
+    #[serde(default = "default_ens_cache_ttl_secs")]
+    pub ens_cache_ttl_secs: u64,
+    /// Entries each bounded cache (ENS names, tokens learned at runtime) keeps before evicting
+    /// the least recently used.
+    #[serde(default = "default_cache_max_entries")]
+    pub cache_max_entries: usize,
+    /// Rows the activity log and the paper trade history each keep.
+    #[serde(default = "default_log_max_rows")]
+    pub log_max_rows: usize,
+    /// Drop activity and paper trade rows older than this many days.
+    #[serde(default)]
+    pub log_retention_days: Option<u64>,
+    /// Uniswap V3 contract overrides; required on chains without a built-in deployment.
+    #[serde(default)]
+    pub uniswap_factory: Option<String>,
+    #[serde(default)]
+    pub uniswap_quoter: Option<String>,
+    #[serde(default)]
+    pub uniswap_router: Option<String>,
+    /// Uniswap V2 forks (`[[v2_venues]]` with `name`, `factory`, `router`) used as price fallbacks.
+    #[serde(default)]
+    pub v2_venues: Vec<V2Venue>,
+    /// `BASE/QUOTE` pairs the background arbitrage scanner compares across venues; off when empty.
+    #[serde(default)]
+    pub arb_pairs: Vec<String>,
+    /// Seconds between background arbitrage scans.
+    #[serde(default = "default_arb_scan_interval_secs")]
+    pub arb_scan_interval_secs: u64,
+    /// USD notional each pair is quoted at when looking for arbitrage.
+    #[serde(default = "default_arb_trade_usd")]
+    pub arb_trade_usd: u64,
+    /// Native coin symbol (e.g. `MATIC`); defaults to the built-in table for the chain.
+    #[serde(default)]
+    pub native_symbol: Option<String>,
+    /// Wrapped native token (WETH, WMATIC, ...) used as the `ETH` quote token.
+    #[serde(default)]
+    pub wrapped_native: Option<String>,
+    /// Chainlink native/USD feed backing `get_native_price`.
+    #[serde(default)]
+    pub native_usd_feed: Option<String>,
 }
 
 fn default_chain_id() -> u64 {
     DEFAULT_CHAIN_ID
 }
 
+fn default_keyring_service() -> String {
+    DEFAULT_KEYRING_SERVICE.to_string()
+}
+
+fn default_gas_buffer_percent() -> u32 {
+    DEFAULT_GAS_BUFFER_PERCENT
+}
+
+fn default_stuck_tx_blocks() -> u64 {
+    DEFAULT_STUCK_TX_BLOCKS
+}
+
+fn default_fee_bump_percent() -> u32 {
+    DEFAULT_FEE_BUMP_PERCENT
+}
+
+fn default_quote_ttl_secs() -> u64 {
+    DEFAULT_QUOTE_TTL_SECS
+}
+
+fn default_depeg_threshold_bps() -> u32 {
+    DEFAULT_DEPEG_THRESHOLD_BPS
+}
+
+fn default_arb_scan_interval_secs() -> u64 {
+    60
+}
+
+fn default_arb_trade_usd() -> u64 {
+    1_000
+}
+
+fn default_usd_quote_tokens() -> Vec<String> {
+    vec!["USDC".to_string()]
+}
+
+fn default_native_symbol_alias() -> bool {
+    true
+}
+
+fn default_whitelist_delay_secs() -> u64 {
+    DEFAULT_WHITELIST_DELAY_SECS
+}
+
+fn default_ens_cache_ttl_secs() -> u64 {
+    DEFAULT_ENS_CACHE_TTL_SECS
+}
+
+fn default_cache_max_entries() -> usize {
+    DEFAULT_CACHE_MAX_ENTRIES
+}
+
+fn default_log_max_rows() -> usize {
+    DEFAULT_LOG_MAX_ROWS
+}
+
 impl AppConfig {
     /// Load configuration, preferring a user-provided config file and falling back to env vars.
     pub fn load() -> AppResult<Self> {
@@ -46,16 +300,318 @@ impl AppConfig {
         let eth_rpc_url = env::var("ETH_RPC_URL")
             .map_err(|_| AppError::Config("ETH_RPC_URL missing (config file not found)".into()))?;
 
-        let private_key = env::var("PRIVATE_KEY").ok();
+        let rpc_endpoints = env::var("ETH_RPC_ENDPOINTS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(str::parse::<RpcEndpointConfig>)
+                    .collect::<AppResult<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let rpc_strategy = match env::var("RPC_BALANCE_STRATEGY") {
+            Ok(value) if !value.is_empty() => value.parse::<BalanceStrategy>()?,
+            _ => BalanceStrategy::default(),
+        };
+        let rpc_fixture_mode = env::var("RPC_FIXTURE_MODE")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<FixtureMode>())
+            .transpose()?;
+        let rpc_fixture_path = env::var("RPC_FIXTURE_PATH").ok().filter(|v| !v.is_empty());
+        let default_block_tag = match env::var("DEFAULT_BLOCK_TAG") {
+            Ok(value) if !value.is_empty() => value.parse::<DefaultBlockTag>()?,
+            _ => DefaultBlockTag::default(),
+        };
+        let eth_ws_url = env::var("ETH_WS_URL").ok().filter(|v| !v.is_empty());
+        let chain_rpc_urls = env::var("CHAIN_RPC_URLS")
+            .ok()
+            .map(|v| parse_pairs("CHAIN_RPC_URLS", "chain_id=url", &v))
+            .transpose()?
+            .unwrap_or_default();
+        let chain_ws_urls = env::var("CHAIN_WS_URLS")
+            .ok()
+            .map(|v| parse_pairs("CHAIN_WS_URLS", "chain_id=url", &v))
+            .transpose()?
+            .unwrap_or_default();
+        let chains = ChainEndpoints::from_pairs(chain_rpc_urls, chain_ws_urls)?;
+        let private_key = env::var("PRIVATE_KEY").ok().map(SecretString::from);
+        let secrets_backend = match env::var("SECRETS_BACKEND") {
+            Ok(value) if !value.is_empty() => value.parse::<SecretsBackend>()?,
+            _ => SecretsBackend::default(),
+        };
+        let keyring_service =
+            env::var("KEYRING_SERVICE").unwrap_or_else(|_| DEFAULT_KEYRING_SERVICE.to_string());
+        let burner_keystore_dir = env::var("BURNER_KEYSTORE_DIR")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let burner_password = env::var("BURNER_PASSWORD")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(SecretString::from);
         let default_chain_id = env::var("DEFAULT_CHAIN_ID")
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(DEFAULT_CHAIN_ID);
+        let gas_buffer_percent = env::var("GAS_BUFFER_PERCENT")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_GAS_BUFFER_PERCENT);
+        let gas_worthiness_bps = env::var("GAS_WORTHINESS_BPS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|bps| (1..=10_000).contains(bps));
+        let gas_worthiness_mode = match env::var("GAS_WORTHINESS_MODE") {
+            Ok(value) if !value.is_empty() => value.parse::<GasWorthinessMode>()?,
+            _ => GasWorthinessMode::default(),
+        };
+        let stuck_tx_blocks = env::var("STUCK_TX_BLOCKS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|blocks| *blocks > 0)
+            .unwrap_or(DEFAULT_STUCK_TX_BLOCKS);
+        let stuck_tx_policy = match env::var("STUCK_TX_POLICY") {
+            Ok(value) if !value.is_empty() => value.parse::<StuckTxPolicy>()?,
+            _ => StuckTxPolicy::default(),
+        };
+        let fee_bump_percent = env::var("FEE_BUMP_PERCENT")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_FEE_BUMP_PERCENT);
+        let quote_ttl_secs = env::var("QUOTE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_QUOTE_TTL_SECS);
+        let event_notifications = env::var("EVENT_NOTIFICATIONS")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let sign_responses = env::var("SIGN_RESPONSES")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let param_defaults = match env::var("PARAM_DEFAULTS") {
+            Ok(value) if !value.trim().is_empty() => {
+                serde_json::from_str(&value).map_err(|err| {
+                    AppError::Config(format!(
+                        "PARAM_DEFAULTS must be a JSON array of rules: {err}"
+                    ))
+                })?
+            }
+            _ => Vec::new(),
+        };
+        let paper_portfolio_path = env::var("PAPER_PORTFOLIO_PATH")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let usd_quote_tokens = env::var("USD_QUOTE_TOKENS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|symbol| symbol.trim().to_string())
+                    .filter(|symbol| !symbol.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|symbols| !symbols.is_empty())
+            .unwrap_or_else(default_usd_quote_tokens);
+        let usd_quote_mode = match env::var("USD_QUOTE_MODE") {
+            Ok(value) if !value.is_empty() => value.parse::<UsdQuoteMode>()?,
+            _ => UsdQuoteMode::default(),
+        };
+        let depeg_threshold_bps = env::var("DEPEG_THRESHOLD_BPS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(DEFAULT_DEPEG_THRESHOLD_BPS);
+        let uniswap_reference_usd = env::var("UNISWAP_REFERENCE_USD")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|usd| *usd > 0);
+        let symbol_collision_policy = match env::var("SYMBOL_COLLISION_POLICY") {
+            Ok(value) if !value.is_empty() => value.parse::<SymbolCollisionPolicy>()?,
+            _ => SymbolCollisionPolicy::default(),
+        };
+        let symbol_aliases = env::var("SYMBOL_ALIASES")
+            .ok()
+            .map(|v| parse_pairs("SYMBOL_ALIASES", "alias=token", &v))
+            .transpose()?
+            .unwrap_or_default();
+        let native_symbol_alias = env::var("NATIVE_SYMBOL_ALIAS")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(true);
+        let token_list_path = env::var("TOKEN_LIST_PATH").ok().filter(|v| !v.is_empty());
+        let denylist_path = env::var("DENYLIST_PATH").ok().filter(|v| !v.is_empty());
+        let sanctions_api_url = env::var("SANCTIONS_API_URL").ok().filter(|v| !v.is_empty());
+        let sanctions_api_key = env::var("SANCTIONS_API_KEY")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(SecretString::from);
+        let gasless_api_key = env::var("GASLESS_API_KEY")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(SecretString::from);
+        let gasless_api_url = env::var("GASLESS_API_URL").ok().filter(|v| !v.is_empty());
+        let gasless_settlers = env::var("GASLESS_SETTLERS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|address| address.trim().to_string())
+                    .filter(|address| !address.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let allow_compliance_override = env::var("ALLOW_COMPLIANCE_OVERRIDE")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let allow_delegation_signing = env::var("ALLOW_DELEGATION_SIGNING")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let recipient_whitelist_path = env::var("RECIPIENT_WHITELIST_PATH")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let whitelist_delay_secs = env::var("WHITELIST_DELAY_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_WHITELIST_DELAY_SECS);
+        let recipient_aliases = env::var("RECIPIENT_ALIASES")
+            .ok()
+            .map(|v| parse_pairs("RECIPIENT_ALIASES", "name=address", &v))
+            .transpose()?
+            .unwrap_or_default();
+        let default_recipient = env::var("DEFAULT_RECIPIENT").ok().filter(|v| !v.is_empty());
+        let restrict_recipients_to_aliases = env::var("RESTRICT_RECIPIENTS_TO_ALIASES")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let admin_secret = env::var("ADMIN_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(SecretString::from);
+        let disperse_address = env::var("DISPERSE_ADDRESS").ok().filter(|v| !v.is_empty());
+        let ens_reverse_lookup = env::var("ENS_REVERSE_LOOKUP")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false);
+        let ens_cache_ttl_secs = env::var("ENS_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ENS_CACHE_TTL_SECS);
+        let cache_max_entries = env::var("CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_CACHE_MAX_ENTRIES);
+        let log_max_rows = env::var("LOG_MAX_ROWS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_LOG_MAX_ROWS);
+        let log_retention_days = env::var("LOG_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok());
+        let uniswap_factory = env::var("UNISWAP_FACTORY").ok().filter(|v| !v.is_empty());
+        let uniswap_quoter = env::var("UNISWAP_QUOTER").ok().filter(|v| !v.is_empty());
+        let uniswap_router = env::var("UNISWAP_ROUTER").ok().filter(|v| !v.is_empty());
+        let v2_venues = env::var("V2_VENUES")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(str::parse::<V2Venue>)
+                    .collect::<AppResult<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        let arb_pairs = env::var("ARB_PAIRS")
+            .ok()
+            .map(|v| {
+                v.split(',')
+                    .map(|pair| pair.trim().to_string())
+                    .filter(|pair| !pair.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let arb_scan_interval_secs = env::var("ARB_SCAN_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or_else(default_arb_scan_interval_secs);
+        let arb_trade_usd = env::var("ARB_TRADE_USD")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|usd| *usd > 0)
+            .unwrap_or_else(default_arb_trade_usd);
+        let native_symbol = env::var("NATIVE_SYMBOL").ok().filter(|v| !v.is_empty());
+        let wrapped_native = env::var("WRAPPED_NATIVE").ok().filter(|v| !v.is_empty());
+        let native_usd_feed = env::var("NATIVE_USD_FEED").ok().filter(|v| !v.is_empty());
 
         Ok(Self {
             eth_rpc_url,
+            rpc_endpoints,
+            rpc_strategy,
+            rpc_fixture_mode,
+            rpc_fixture_path,
+            default_block_tag,
+            eth_ws_url,
+            chains,
             private_key,
+            secrets_backend,
+            keyring_service,
+            burner_keystore_dir,
+            burner_password,
             default_chain_id,
+            gas_buffer_percent,
+            gas_worthiness_bps,
+            gas_worthiness_mode,
+            stuck_tx_blocks,
+            stuck_tx_policy,
+            fee_bump_percent,
+            quote_ttl_secs,
+            event_notifications,
+            sign_responses,
+            param_defaults,
+            paper_portfolio_path,
+            usd_quote_tokens,
+            usd_quote_mode,
+            depeg_threshold_bps,
+            uniswap_reference_usd,
+            symbol_collision_policy,
+            symbol_aliases,
+            native_symbol_alias,
+            token_list_path,
+            denylist_path,
+            sanctions_api_url,
+            sanctions_api_key,
+            gasless_api_key,
+            gasless_api_url,
+            gasless_settlers,
+            allow_compliance_override,
+            allow_delegation_signing,
+            recipient_whitelist_path,
+            whitelist_delay_secs,
+            recipient_aliases,
+            default_recipient,
+            restrict_recipients_to_aliases,
+            admin_secret,
+            disperse_address,
+            ens_reverse_lookup,
+            ens_cache_ttl_secs,
+            cache_max_entries,
+            log_max_rows,
+            log_retention_days,
+            uniswap_factory,
+            uniswap_quoter,
+            uniswap_router,
+            v2_venues,
+            arb_pairs,
+            arb_scan_interval_secs,
+            arb_trade_usd,
+            native_symbol,
+            wrapped_native,
+            native_usd_feed,
         })
     }
 
@@ -66,3 +622,19 @@ impl AppConfig {
         }
     }
 }
+
+/// Parse comma-separated `name=value` pairs from env var `var`; `shape` names them in errors.
+fn parse_pairs(var: &str, shape: &str, raw: &str) -> AppResult<HashMap<String, String>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .split_once('=')
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| {
+                    AppError::Config(format!("{var} entries must be {shape}, got {entry}"))
+                })
+        })
+        .collect()
+}
@@ -1,3 +1,4 @@
+use crate::redact::{redact, redact_value};
 use ethers::providers::ProviderError;
 use serde_json::{Value, json};
 use std::{fmt, io};
@@ -19,12 +20,35 @@ pub enum AppError {
     Swap(String),
     #[error("wallet error: {0}")]
     Wallet(String),
+    /// The wallet's native balance cannot pay for a write it is about to be handed; amounts are
+    /// decimal wei.
+    #[error("insufficient funds for gas: {message}")]
+    InsufficientFunds {
+        message: String,
+        required_wei: String,
+        balance_wei: String,
+        missing_wei: String,
+    },
+    #[error("compliance error: {0}")]
+    Compliance(String),
+    #[error("halted: {0}")]
+    Halted(String),
     #[error("io error: {0}")]
     Io(String),
     #[error("serialization error: {0}")]
     Serialization(String),
     #[error("internal error: {0}")]
     Internal(String),
+    /// `error` caused by a contract revert; reported under `error`'s code with the decoded reason.
+    #[error("{error} ({revert_reason})")]
+    Reverted {
+        error: Box<AppError>,
+        revert_reason: String,
+        revert_data: String,
+    },
+    /// `error` with the `debug_traceCall` call tree of the failed simulation under `data.trace`.
+    #[error("{error}")]
+    Traced { error: Box<AppError>, trace: Value },
 }
 
 #[derive(Debug)]
@@ -45,7 +69,16 @@ impl JsonRpcErrorPayload {
 }
 
 impl AppError {
+    /// Convert into a JSON-RPC error object; message and data are redacted before leaving the
+    /// process.
     pub fn to_json_rpc(&self) -> JsonRpcErrorPayload {
+        let mut payload = self.to_json_rpc_unredacted();
+        payload.message = redact(&payload.message).into_owned();
+        redact_value(&mut payload.data);
+        payload
+    }
+
+    fn to_json_rpc_unredacted(&self) -> JsonRpcErrorPayload {
         match self {
             AppError::Config(msg) => JsonRpcErrorPayload::new(-32001, msg.clone()),
             AppError::InvalidInput(msg) => JsonRpcErrorPayload::new(-32602, msg.clone()),
@@ -53,9 +86,48 @@ impl AppError {
             AppError::Price(msg) => JsonRpcErrorPayload::new(-32010, msg.clone()),
             AppError::Swap(msg) => JsonRpcErrorPayload::new(-32020, msg.clone()),
             AppError::Wallet(msg) => JsonRpcErrorPayload::new(-32030, msg.clone()),
+            AppError::InsufficientFunds {
+                message,
+                required_wei,
+                balance_wei,
+                missing_wei,
+            } => {
+                let mut payload = JsonRpcErrorPayload::new(
+                    -32031,
+                    format!("insufficient funds for gas: {message}"),
+                );
+                payload.data = json!({
+                    "required_wei": required_wei,
+                    "balance_wei": balance_wei,
+                    "missing_wei": missing_wei,
+                });
+                payload
+            }
+            AppError::Compliance(msg) => JsonRpcErrorPayload::new(-32035, msg.clone()),
+            AppError::Halted(msg) => JsonRpcErrorPayload::new(-32036, msg.clone()),
             AppError::Io(msg) => JsonRpcErrorPayload::new(-32040, msg.clone()),
             AppError::Serialization(msg) => JsonRpcErrorPayload::new(-32700, msg.clone()),
             AppError::Internal(msg) => JsonRpcErrorPayload::new(-32603, msg.clone()),
+            AppError::Reverted {
+                error,
+                revert_reason,
+                revert_data,
+            } => {
+                let mut payload = error.to_json_rpc_unredacted();
+                payload.message = format!("{} ({revert_reason})", payload.message);
+                payload.data = json!({
+                    "revert_reason": revert_reason,
+                    "revert_data": revert_data,
+                });
+                payload
+            }
+            AppError::Traced { error, trace } => {
+                let mut payload = error.to_json_rpc_unredacted();
+                if let Some(data) = payload.data.as_object_mut() {
+                    data.insert("trace".into(), trace.clone());
+                }
+                payload
+            }
         }
     }
 }
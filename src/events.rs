@@ -0,0 +1,277 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{
+    implementations::retention::RetentionPolicy,
+    types::{
+        ArbOpportunityOut, DepositOut, EventsOut, MaintenanceItemOut, MempoolMatch, PriceOut,
+        SpeedUpOut, SpreadSampleOut, TokenTransferOut,
+    },
+};
+
+/// Events kept for `get_events`; older ones are dropped first.
+pub const EVENT_LOG_CAPACITY: usize = 1_000;
+/// Every `type` an event can have.
+pub const EVENT_KINDS: &[&str] = &[
+    "price_tick",
+    "deposit",
+    "pending_tx",
+    "token_transfer",
+    "stuck_tx",
+    "spread_alert",
+    "arb_opportunity",
+    "halted",
+    "resumed",
+];
+/// Events a slow subscriber may fall behind by before it starts missing some.
+const SUBSCRIBER_CAPACITY: usize = 256;
+
+/// Something a subsystem observed, published for every other one to react to.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// A price read by `get_token_price`.
+    PriceTick {
+        base: String,
+        quote: String,
+        price: String,
+        source: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        block_number: Option<u64>,
+    },
+    /// A mined deposit into a watched wallet.
+    Deposit {
+        wallet: String,
+        #[serde(flatten)]
+        deposit: DepositOut,
+    },
+    /// A pending transaction touching a watched wallet.
+    PendingTx {
+        wallet: String,
+        #[serde(flatten)]
+        tx: MempoolMatch,
+    },
+    /// A `Transfer` of a token watched by `watch_token`.
+    TokenTransfer {
+        token: String,
+        symbol: String,
+        /// The watched address.
+        address: String,
+        #[serde(flatten)]
+        transfer: TokenTransferOut,
+    },
+    /// A transaction watched by `watch_transaction` pending below the market fee.
+    StuckTx {
+        tx_hash: String,
+        from: String,
+        nonce: String,
+        blocks_pending: u64,
+        speed_up: SpeedUpOut,
+    },
+    /// A pair watched by `watch_spread` trading outside its band.
+    SpreadAlert {
+        base: String,
+        quote: String,
+        target_ratio: String,
+        band_bps: u32,
+        #[serde(flatten)]
+        sample: SpreadSampleOut,
+    },
+    /// A round trip found by the arbitrage scanner; `get_arbitrage_plan` has its legs.
+    ArbOpportunity {
+        #[serde(flatten)]
+        opportunity: ArbOpportunityOut,
+    },
+    /// `panic_stop` suspended write operations.
+    Halted { reason: String },
+    /// `resume` lifted a halt.
+    Resumed,
+}
+
+impl ServerEvent {
+    pub fn price_tick(price: &PriceOut) -> Self {
+        ServerEvent::PriceTick {
+            base: price.base.clone(),
+            quote: price.quote.clone(),
+            price: price.price.clone(),
+            source: price.source.clone(),
+            block_number: price.block_number,
+        }
+    }
+
+    /// The `type` tag, for filtering.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerEvent::PriceTick { .. } => "price_tick",
+            ServerEvent::Deposit { .. } => "deposit",
+            ServerEvent::PendingTx { .. } => "pending_tx",
+            ServerEvent::TokenTransfer { .. } => "token_transfer",
+            ServerEvent::StuckTx { .. } => "stuck_tx",
+            ServerEvent::SpreadAlert { .. } => "spread_alert",
+            ServerEvent::ArbOpportunity { .. } => "arb_opportunity",
+            ServerEvent::Halted { .. } => "halted",
+            ServerEvent::Resumed => "resumed",
+        }
+    }
+}
+
+/// A published event with its place in the log.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct EventRecord {
+    /// Starts at 1 and increases by one per event, with no gaps.
+    pub seq: u64,
+    /// Unix time the event was published.
+    pub at: u64,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+#[derive(Debug)]
+struct EventLog {
+    records: VecDeque<EventRecord>,
+    next_seq: u64,
+}
+
+/// Append-only event bus shared by every subsystem: publishers append to a bounded log and
+/// fan out to live subscribers, so features do not each own ad-hoc channels.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventRecord>,
+    log: Mutex<EventLog>,
+    capacity: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_LOG_CAPACITY)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+        Self {
+            sender,
+            log: Mutex::new(EventLog {
+                records: VecDeque::new(),
+                next_seq: 1,
+            }),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append `event` and hand it to every subscriber; returns its sequence number.
+    pub fn publish(&self, event: ServerEvent, now: u64) -> u64 {
+        // Held while sending so subscribers see events in sequence order.
+        let mut log = self.log.lock().expect("event log lock poisoned");
+        let record = EventRecord {
+            seq: log.next_seq,
+            at: now,
+            event,
+        };
+        log.next_seq += 1;
+        if log.records.len() == self.capacity {
+            log.records.pop_front();
+        }
+        log.records.push_back(record.clone());
+        // No subscribers is not an error: the log still has it.
+        let _ = self.sender.send(record.clone());
+        record.seq
+    }
+
+    /// Events published from now on. A subscriber more than a few hundred events behind gets
+    /// `RecvError::Lagged` and skips ahead; `since` recovers what it missed while still logged.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventRecord> {
+        self.sender.subscribe()
+    }
+
+    pub fn status(&self) -> MaintenanceItemOut {
+        let log = self.log.lock().expect("event log lock poisoned");
+        let policy = RetentionPolicy {
+            max_rows: self.capacity,
+            max_age_secs: None,
+        };
+        MaintenanceItemOut::log("events", log.records.len(), &policy, 0)
+    }
+
+    /// Up to `limit` logged events after `after_seq`, oldest first, optionally only `kinds`.
+    pub fn since(&self, after_seq: u64, limit: usize, kinds: &[String]) -> EventsOut {
+        let log = self.log.lock().expect("event log lock poisoned");
+        let first_seq = log
+            .records
+            .front()
+            .map_or(log.next_seq, |record| record.seq);
+        let events: Vec<EventRecord> = log
+            .records
+            .iter()
+            .filter(|record| record.seq > after_seq)
+            .filter(|record| {
+                kinds.is_empty() || kinds.iter().any(|kind| kind == record.event.kind())
+            })
+            .take(limit)
+            .cloned()
+            .collect();
+        let last_seq = log.next_seq - 1;
+        EventsOut {
+            // Everything up to here has been seen, matching or not, unless the limit cut it short.
+            next_seq: match events.last() {
+                Some(record) if events.len() == limit => record.seq,
+                _ => last_seq,
+            },
+            missed: first_seq.saturating_sub(after_seq.saturating_add(1)),
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn halted(reason: &str) -> ServerEvent {
+        ServerEvent::Halted {
+            reason: reason.into(),
+        }
+    }
+
+    #[test]
+    fn log_keeps_the_latest_events_in_order() {
+        let bus = EventBus::new(3);
+        for n in 0..5 {
+            assert_eq!(bus.publish(halted(&n.to_string()), 100 + n), n + 1);
+        }
+        let all = bus.since(0, 10, &[]);
+        let seqs: Vec<u64> = all.events.iter().map(|record| record.seq).collect();
+        assert_eq!(seqs, [3, 4, 5]);
+        assert_eq!(all.missed, 2);
+        assert_eq!(all.next_seq, 5);
+
+        let page = bus.since(3, 1, &[]);
+        assert_eq!(page.events[0].seq, 4);
+        assert_eq!(page.next_seq, 4);
+        assert_eq!(page.missed, 0);
+
+        bus.publish(ServerEvent::Resumed, 200);
+        let resumed = bus.since(0, 10, &["resumed".to_string()]);
+        assert_eq!(resumed.events.len(), 1);
+        assert_eq!(resumed.next_seq, 6);
+        assert_eq!(
+            serde_json::to_value(&resumed.events[0]).unwrap(),
+            serde_json::json!({ "seq": 6, "at": 200, "type": "resumed" })
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_published_after_they_join() {
+        let bus = EventBus::default();
+        bus.publish(halted("before"), 1);
+        let mut events = bus.subscribe();
+        bus.publish(ServerEvent::Resumed, 2);
+        let record = events.recv().await.unwrap();
+        assert_eq!(record.seq, 2);
+        assert_eq!(record.event, ServerEvent::Resumed);
+    }
+}
@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use ethers::{
+    abi::AbiEncode,
+    types::{Address, U256},
+};
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        erc20::ApproveCall,
+        price::{TokenInfo, TokenRegistry},
+        uniswap::UniswapDeployment,
+        validation,
+    },
+    types::{ApprovalSpenderOut, ApprovalTemplateOut, ApprovalTemplatesOut},
+};
+
+/// Canonical Permit2, deployed at the same address on every chain.
+pub static PERMIT2: Lazy<Address> =
+    Lazy::new(|| Address::from_str("0x000000000022D473030F116dDEE9F6B43aC78BA3").unwrap());
+
+/// A contract the tools of this server route tokens through, and so ask wallets to approve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemplateSpender {
+    pub protocol: &'static str,
+    pub name: &'static str,
+    pub address: Address,
+}
+
+/// Curated spenders on the active chain: the Uniswap V3 router `swap_tokens` and `plan_swap`
+/// build for, Permit2, and the Disperse contract when `distribute_tokens` is configured for it.
+/// Spenders come from the verified deployment and config, never from caller input, so a
+/// policy check can trust an approval whose spender is one of these.
+#[derive(Debug, Clone)]
+pub struct ApprovalTemplates {
+    spenders: Vec<TemplateSpender>,
+}
+
+impl ApprovalTemplates {
+    pub fn new(uniswap: UniswapDeployment, disperse: Option<Address>) -> Self {
+        let mut spenders = vec![
+            TemplateSpender {
+                protocol: "uniswap_v3_router",
+                name: "Uniswap V3 SwapRouter",
+                address: uniswap.router,
+            },
+            TemplateSpender {
+                protocol: "permit2",
+                name: "Permit2",
+                address: *PERMIT2,
+            },
+        ];
+        if let Some(address) = disperse {
+            spenders.push(TemplateSpender {
+                protocol: "disperse",
+                name: "Disperse",
+                address,
+            });
+        }
+        Self { spenders }
+    }
+
+    pub fn spenders(&self) -> &[TemplateSpender] {
+        &self.spenders
+    }
+
+    /// Whether `address` is one of the curated spenders.
+    pub fn is_template_spender(&self, address: Address) -> bool {
+        self.spenders
+            .iter()
+            .any(|spender| spender.address == address)
+    }
+
+    /// Templates for `tokens` (the registry's listed tokens when `None`), optionally for one
+    /// `protocol`, with `approve` calldata when `amount` is given.
+    pub fn list(
+        &self,
+        registry: &TokenRegistry,
+        chain_id: u64,
+        tokens: Option<Vec<&TokenInfo>>,
+        protocol: Option<&str>,
+        amount: Option<U256>,
+    ) -> AppResult<ApprovalTemplatesOut> {
+        let spenders: Vec<&TemplateSpender> = self
+            .spenders
+            .iter()
+            .filter(|spender| protocol.is_none_or(|protocol| spender.protocol == protocol))
+            .collect();
+        if let Some(protocol) = protocol
+            && spenders.is_empty()
+        {
+            return Err(AppError::InvalidInput(format!(
+                "no approval templates for protocol {protocol}{}",
+                validation::explain(
+                    protocol,
+                    self.spenders.iter().map(|spender| spender.protocol)
+                )
+            )));
+        }
+        let tokens = tokens.unwrap_or_else(|| registry.listed_tokens());
+
+        let templates = spenders
+            .iter()
+            .flat_map(|spender| {
+                tokens
+                    .iter()
+                    .map(move |token| template(spender, token, amount))
+            })
+            .collect();
+        Ok(ApprovalTemplatesOut {
+            chain_id,
+            spenders: spenders
+                .iter()
+                .map(|spender| ApprovalSpenderOut {
+                    protocol: spender.protocol.to_string(),
+                    name: spender.name.to_string(),
+                    address: format!("{:#x}", spender.address),
+                })
+                .collect(),
+            templates,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+fn template(
+    spender: &TemplateSpender,
+    token: &TokenInfo,
+    amount: Option<U256>,
+) -> ApprovalTemplateOut {
+    let calldata = amount.map(|amount| {
+        let call = ApproveCall {
+            spender: spender.address,
+            amount,
+        };
+        format!("0x{}", hex::encode(call.encode()))
+    });
+    ApprovalTemplateOut {
+        id: format!("{}:{}", spender.protocol, token.symbol),
+        description: format!("Approve {} for {}", token.symbol, spender.name),
+        protocol: spender.protocol.to_string(),
+        spender: format!("{:#x}", spender.address),
+        token: token.symbol.clone(),
+        token_address: format!("{:#x}", token.address),
+        amount_wei: amount.map(|amount| amount.to_string()),
+        calldata_hex: calldata,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> ApprovalTemplates {
+        ApprovalTemplates::new(UniswapDeployment::default(), None)
+    }
+
+    #[test]
+    fn lists_listed_tokens_for_every_spender() {
+        let registry = TokenRegistry::with_defaults();
+        let out = templates().list(&registry, 1, None, None, None).unwrap();
+        let ids: Vec<&str> = out.templates.iter().map(|t| t.id.as_str()).collect();
+        assert!(ids.contains(&"uniswap_v3_router:USDC"));
+        assert!(ids.contains(&"permit2:WETH"));
+        assert_eq!(out.spenders.len(), 2);
+        assert!(out.templates.iter().all(|t| t.calldata_hex.is_none()));
+
+        let permit2 = out
+            .templates
+            .iter()
+            .find(|t| t.id == "permit2:WETH")
+            .unwrap();
+        assert_eq!(permit2.description, "Approve WETH for Permit2");
+        assert_eq!(
+            permit2.spender,
+            "0x000000000022d473030f116ddee9f6b43ac78ba3"
+        );
+        assert!(templates().is_template_spender(*PERMIT2));
+        assert!(!templates().is_template_spender(Address::from_low_u64_be(1)));
+    }
+
+    #[test]
+    fn filters_by_protocol_and_encodes_calldata() {
+        let registry = TokenRegistry::with_defaults();
+        let usdc = registry.info_by_symbol("USDC").unwrap();
+        let out = templates()
+            .list(
+                &registry,
+                1,
+                Some(vec![usdc]),
+                Some("uniswap_v3_router"),
+                Some(U256::from(1_000_000u64)),
+            )
+            .unwrap();
+        assert_eq!(out.templates.len(), 1);
+        let calldata = out.templates[0].calldata_hex.as_deref().unwrap();
+        // approve(address,uint256) selector, then the router and the amount.
+        assert!(calldata.starts_with("0x095ea7b3"));
+        assert!(calldata.contains("e592427a0aece92de3edee1f18e0157c05861564"));
+        assert!(calldata.ends_with("f4240"));
+
+        let err = templates()
+            .list(&registry, 1, None, Some("permit"), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("did you mean permit2?"));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance, gas_worth,
+        plan::ROUTER_OVERHEAD_GAS,
+        price::{self, TokenInfo, TokenRegistry},
+        quotes::FEE_TIERS,
+        rebalance::usd,
+        swap::apply_slippage,
+        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+        v2::V2Venue,
+    },
+    types::{ArbLegOut, ArbOpportunityOut, ArbPlanOut, QuoteCurrency},
+};
+
+/// Gas of a swap through a Uniswap V2 router, which has no quoter to estimate it.
+pub const V2_SWAP_GAS: u64 = 120_000;
+/// Slippage tolerance behind each leg's `amount_out_min` (0.5%).
+pub const ARB_SLIPPAGE_BPS: u32 = 50;
+/// Pairs one scan may compare; each costs one quote per venue plus one per other venue.
+pub const MAX_ARB_PAIRS: usize = 10;
+/// Plans kept for `get_arbitrage_plan`; the oldest are dropped first.
+pub const ARB_BOOK_CAPACITY: usize = 100;
+const FULL_BPS: u64 = 10_000;
+
+/// Where a leg can trade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Venue {
+    UniswapV3 { fee: u32 },
+    V2(V2Venue),
+}
+
+impl Venue {
+    /// Every Uniswap V3 fee tier, then the configured V2 forks.
+    pub fn all(v2_venues: &[V2Venue]) -> Vec<Self> {
+        FEE_TIERS
+            .iter()
+            .map(|fee| Venue::UniswapV3 { fee: *fee })
+            .chain(v2_venues.iter().cloned().map(Venue::V2))
+            .collect()
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Venue::UniswapV3 { fee } => format!("uniswap_v3 (fee {fee})"),
+            Venue::V2(venue) => format!("{}_v2", venue.name),
+        }
+    }
+
+    /// Output and gas of selling `amount_in` of `token_in`; gas includes the router's overhead.
+    async fn quote<M>(
+        &self,
+        provider: Arc<M>,
+        contracts: &ArbContracts,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> AppResult<(U256, U256)>
+    where
+        M: Middleware + 'static,
+    {
+        match self {
+            Venue::UniswapV3 { fee } => {
+                let (amount_out, _, _, gas) = UniswapQuoterV2::new(contracts.quoter, provider)
+                    .quote_exact_input_single(QuoteExactInputSingleParams {
+                        token_in,
+                        token_out,
+                        amount_in,
+                        fee: *fee,
+                        sqrt_price_limit_x96: U256::zero(),
+                    })
+                    .call()
+                    .await
+                    .map_err(|err| AppError::Swap(format!("uniswap quoter call failed: {err}")))?;
+                if amount_out.is_zero() {
+                    return Err(AppError::Swap("quote returned zero output amount".into()));
+                }
+                Ok((amount_out, gas + U256::from(ROUTER_OVERHEAD_GAS)))
+            }
+            Venue::V2(venue) => {
+                let amount_out = venue
+                    .amount_out(provider, amount_in, token_in, token_out)
+                    .await?;
+                Ok((amount_out, U256::from(V2_SWAP_GAS)))
+            }
+        }
+    }
+
+    fn router(&self, contracts: &ArbContracts) -> Address {
+        match self {
+            Venue::UniswapV3 { .. } => contracts.router,
+            Venue::V2(venue) => venue.router,
+        }
+    }
+
+    fn fee(&self) -> Option<u32> {
+        match self {
+            Venue::UniswapV3 { fee } => Some(*fee),
+            Venue::V2(_) => None,
+        }
+    }
+}
+
+/// Uniswap V3 contracts the V3 legs quote through and would trade on.
+#[derive(Debug, Clone, Copy)]
+pub struct ArbContracts {
+    pub quoter: Address,
+    pub router: Address,
+}
+
+/// What every pair of one scan is measured with.
+#[derive(Debug, Clone)]
+pub struct ArbMarket {
+    pub block_number: u64,
+    pub found_at: u64,
+    pub trade_usd: Decimal,
+    pub max_fee_per_gas: U256,
+    pub native_usd: Decimal,
+}
+
+/// How one pair's scan came out.
+#[derive(Debug)]
+pub enum PairScan {
+    Found(Box<ArbPlanOut>),
+    /// Why the best round trip is not worth taking.
+    NoEdge(String),
+}
+
+/// `BASE` and `QUOTE` of a `BASE/QUOTE` pair.
+pub fn split_pair(pair: &str) -> AppResult<(&str, &str)> {
+    pair.split_once('/')
+        .map(|(base, quote)| (base.trim(), quote.trim()))
+        .filter(|(base, quote)| !base.is_empty() && !quote.is_empty())
+        .ok_or_else(|| AppError::InvalidInput(format!("pairs are BASE/QUOTE, got {pair}")))
+}
+
+/// Look for a round trip on `base`/`quote` worth more than its gas.
+///
+/// `trade_usd` of `base` is sold on every venue; the venue paying the most `quote` is the sell
+/// leg, and the proceeds are quoted back into `base` on each other venue. The best buy-back is
+/// an opportunity when the `base` it returns beyond the starting amount is worth more in USD
+/// than both legs' gas at the node's max fee per gas. Venues without a pool are passed over.
+pub async fn scan_pair<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    contracts: &ArbContracts,
+    venues: &[Venue],
+    market: &ArbMarket,
+    base: &TokenInfo,
+    quote: &TokenInfo,
+) -> AppResult<PairScan>
+where
+    M: Middleware + 'static,
+{
+    let pair = format!("{}/{}", base.symbol, quote.symbol);
+    let base_usd =
+        price::resolve_token_price(provider.clone(), registry, base.address, QuoteCurrency::USD)
+            .await?;
+    let base_usd = gas_worth::decimal(&base_usd.price)?;
+    if base_usd <= Decimal::ZERO {
+        return Err(AppError::Price(format!("{} has no USD price", base.symbol)));
+    }
+    let amount_in =
+        balance::parse_with_decimals(market.trade_usd / base_usd, base.decimals as u32)?;
+    if amount_in.is_zero() {
+        return Err(AppError::InvalidInput(format!(
+            "${} buys no {}",
+            market.trade_usd, base.symbol
+        )));
+    }
+
+    let mut sells = Vec::new();
+    for (index, venue) in venues.iter().enumerate() {
+        if let Ok((amount_out, gas)) = venue
+            .quote(
+                provider.clone(),
+                contracts,
+                base.address,
+                quote.address,
+                amount_in,
+            )
+            .await
+        {
+            sells.push((index, amount_out, gas));
+        }
+    }
+    let Some((sell, proceeds, sell_gas)) = sells.iter().copied().max_by_key(|(_, out, _)| *out)
+    else {
+        return Ok(PairScan::NoEdge(format!("no venue quotes {pair}")));
+    };
+
+    let mut buys = Vec::new();
+    for (index, venue) in venues.iter().enumerate() {
+        if index == sell {
+            continue;
+        }
+        if let Ok((amount_back, gas)) = venue
+            .quote(
+                provider.clone(),
+                contracts,
+                quote.address,
+                base.address,
+                proceeds,
+            )
+            .await
+        {
+            buys.push((index, amount_back, gas));
+        }
+    }
+    let Some((buy, amount_back, buy_gas)) = buys.iter().copied().max_by_key(|(_, out, _)| *out)
+    else {
+        return Ok(PairScan::NoEdge(format!(
+            "only {} quotes {pair}",
+            venues[sell].name()
+        )));
+    };
+
+    let human = |amount: &U256| balance::format_with_decimals(amount, base.decimals as u32);
+    if amount_back <= amount_in {
+        let loss = (amount_in - amount_back) * U256::from(FULL_BPS) / amount_in;
+        return Ok(PairScan::NoEdge(format!(
+            "selling on {} and buying back on {} loses {loss} bps",
+            venues[sell].name(),
+            venues[buy].name()
+        )));
+    }
+    let gained = amount_back - amount_in;
+    let profit_usd = gas_worth::decimal(&human(&gained))? * base_usd;
+    let gas_cost_usd = gas_worth::gas_cost_usd(
+        (sell_gas + buy_gas) * market.max_fee_per_gas,
+        market.native_usd,
+    )?;
+    let net_profit_usd = profit_usd - gas_cost_usd;
+    if net_profit_usd <= Decimal::ZERO {
+        return Ok(PairScan::NoEdge(format!(
+            "selling on {} and buying back on {} gains ${}, less than ${} of gas",
+            venues[sell].name(),
+            venues[buy].name(),
+            usd(profit_usd),
+            usd(gas_cost_usd)
+        )));
+    }
+
+    let leg = |venue: &Venue,
+               from: &TokenInfo,
+               to: &TokenInfo,
+               amount_in: U256,
+               amount_out: U256,
+               gas: U256|
+     -> AppResult<ArbLegOut> {
+        Ok(ArbLegOut {
+            venue: venue.name(),
+            router: format!("{:#x}", venue.router(contracts)),
+            fee: venue.fee(),
+            token_in: from.symbol.clone(),
+            token_out: to.symbol.clone(),
+            amount_in_wei: amount_in.to_string(),
+            amount_in: balance::format_with_decimals(&amount_in, from.decimals as u32),
+            amount_out_estimate: balance::format_with_decimals(&amount_out, to.decimals as u32),
+            amount_out_min: balance::format_with_decimals(
+                &apply_slippage(amount_out, ARB_SLIPPAGE_BPS)?,
+                to.decimals as u32,
+            ),
+            gas_estimate: gas.to_string(),
+        })
+    };
+    Ok(PairScan::Found(Box::new(ArbPlanOut {
+        opportunity: ArbOpportunityOut {
+            id: String::new(),
+            pair,
+            block_number: market.block_number,
+            found_at: market.found_at,
+            sell_venue: venues[sell].name(),
+            buy_venue: venues[buy].name(),
+            amount_in: human(&amount_in),
+            amount_back: human(&amount_back),
+            spread_bps: (gained * U256::from(FULL_BPS) / amount_in).low_u64(),
+            profit_usd: usd(profit_usd),
+            gas_cost_usd: usd(gas_cost_usd),
+            net_profit_usd: usd(net_profit_usd),
+        },
+        legs: vec![
+            leg(&venues[sell], base, quote, amount_in, proceeds, sell_gas)?,
+            leg(&venues[buy], quote, base, proceeds, amount_back, buy_gas)?,
+        ],
+        warnings: Vec::new(),
+    })))
+}
+
+/// Recent opportunities by id, so a scan or event can stay short and the plan be fetched later.
+#[derive(Debug, Default)]
+pub struct ArbitrageBook {
+    plans: Mutex<VecDeque<ArbPlanOut>>,
+    next_id: AtomicU64,
+}
+
+impl ArbitrageBook {
+    /// Give `plan` the next id and keep it, dropping the oldest beyond capacity.
+    pub fn record(&self, mut plan: ArbPlanOut) -> ArbPlanOut {
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        plan.opportunity.id = format!("arb-{seq}");
+        let mut plans = self.plans.lock().unwrap_or_else(|err| err.into_inner());
+        if plans.len() == ARB_BOOK_CAPACITY {
+            plans.pop_front();
+        }
+        plans.push_back(plan.clone());
+        plan
+    }
+
+    pub fn get(&self, id: &str) -> Option<ArbPlanOut> {
+        self.plans
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .find(|plan| plan.opportunity.id == id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(pair: &str) -> ArbPlanOut {
+        ArbPlanOut {
+            opportunity: ArbOpportunityOut {
+                id: String::new(),
+                pair: pair.into(),
+                block_number: 1,
+                found_at: 0,
+                sell_venue: "uniswap_v3 (fee 500)".into(),
+                buy_venue: "sushiswap_v2".into(),
+                amount_in: "1".into(),
+                amount_back: "1.01".into(),
+                spread_bps: 100,
+                profit_usd: "25".into(),
+                gas_cost_usd: "5".into(),
+                net_profit_usd: "20".into(),
+            },
+            legs: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn book_keeps_the_latest_plans_by_id() {
+        let book = ArbitrageBook::default();
+        let first = book.record(plan("WETH/USDC"));
+        assert_eq!(first.opportunity.id, "arb-1");
+        assert_eq!(book.get("arb-1"), Some(first));
+
+        for _ in 0..ARB_BOOK_CAPACITY {
+            book.record(plan("WBTC/WETH"));
+        }
+        assert_eq!(book.get("arb-1"), None);
+        let last = format!("arb-{}", ARB_BOOK_CAPACITY + 1);
+        assert_eq!(book.get(&last).unwrap().opportunity.pair, "WBTC/WETH");
+    }
+
+    #[test]
+    fn venues_cover_every_tier_then_the_forks() {
+        let sushi = V2Venue {
+            name: "sushiswap".into(),
+            factory: Address::from_low_u64_be(0xf1),
+            router: Address::from_low_u64_be(0xa1),
+        };
+        let names: Vec<String> = Venue::all(&[sushi]).iter().map(Venue::name).collect();
+        assert_eq!(
+            names,
+            [
+                "uniswap_v3 (fee 100)",
+                "uniswap_v3 (fee 500)",
+                "uniswap_v3 (fee 3000)",
+                "uniswap_v3 (fee 10000)",
+                "sushiswap_v2",
+            ]
+        );
+        assert_eq!(split_pair("WETH / USDC").unwrap(), ("WETH", "USDC"));
+        assert!(split_pair("WETH").is_err());
+        assert!(split_pair("/USDC").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, H256, RecoveryMessage, Signature},
+    utils::{hash_message, keccak256},
+};
+use serde_json::Value;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    types::AttestationOut,
+    wallet::WalletManager,
+};
+
+/// Key of the attestation inside a signed result; everything else in the result is covered.
+pub const ATTESTATION_KEY: &str = "attestation";
+
+/// Signs tool results with the server wallet so downstream components can check a quote or
+/// receipt came from this server instance.
+#[derive(Debug, Clone)]
+pub struct ResponseAttestor {
+    signer: LocalWallet,
+}
+
+impl ResponseAttestor {
+    pub fn new(signer: LocalWallet) -> Self {
+        Self { signer }
+    }
+
+    /// `None` unless `sign_responses` is on, which needs a signer.
+    pub fn from_config(config: &AppConfig, wallet: &WalletManager) -> AppResult<Option<Self>> {
+        if !config.sign_responses {
+            return Ok(None);
+        }
+        let signer = wallet.signer().ok_or_else(|| {
+            AppError::Config("sign_responses requires a private key to sign with".into())
+        })?;
+        Ok(Some(Self::new(signer)))
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Add an `attestation` to an object `result`, signed over the result as it stands and
+    /// `timestamp`.
+    pub fn attest(&self, timestamp: u64, result: &mut Value) -> AppResult<()> {
+        let Some(object) = result.as_object_mut() else {
+            return Ok(());
+        };
+        object.remove(ATTESTATION_KEY);
+        let result_hash = result_hash(result)?;
+        let signature = self
+            .signer
+            .sign_hash(hash_message(digest(result_hash, timestamp)))
+            .map_err(|err| AppError::Wallet(format!("failed to sign response: {err}")))?;
+        let attestation = AttestationOut {
+            signer: format!("{:#x}", self.address()),
+            timestamp,
+            result_hash: format!("{result_hash:#x}"),
+            signature: format!("0x{signature}"),
+        };
+        let attestation = serde_json::to_value(attestation)
+            .map_err(|err| AppError::Internal(format!("attestation serialization: {err}")))?;
+        if let Some(object) = result.as_object_mut() {
+            object.insert(ATTESTATION_KEY.to_string(), attestation);
+        }
+        Ok(())
+    }
+}
+
+/// keccak256 of `result` without its `attestation`, serialized as compact JSON with object keys
+/// sorted, which is how `serde_json` writes it.
+pub fn result_hash(result: &Value) -> AppResult<H256> {
+    let mut unsigned = result.clone();
+    if let Some(object) = unsigned.as_object_mut() {
+        object.remove(ATTESTATION_KEY);
+    }
+    let bytes = serde_json::to_vec(&unsigned)
+        .map_err(|err| AppError::Internal(format!("attestation serialization: {err}")))?;
+    Ok(H256(keccak256(bytes)))
+}
+
+/// The 32 bytes signed as an EIP-191 personal message: keccak256 of the result hash followed by
+/// the timestamp as a big-endian `uint64`.
+pub fn digest(result_hash: H256, timestamp: u64) -> H256 {
+    let mut packed = [0u8; 40];
+    packed[..32].copy_from_slice(result_hash.as_bytes());
+    packed[32..].copy_from_slice(&timestamp.to_be_bytes());
+    H256(keccak256(packed))
+}
+
+/// The address that signed an attested `result`, after checking the signature covers the result
+/// as it stands.
+pub fn verify(result: &Value) -> AppResult<Address> {
+    let attestation = result
+        .get(ATTESTATION_KEY)
+        .ok_or_else(|| AppError::InvalidInput("result carries no attestation".into()))?;
+    let field = |name: &str| {
+        attestation[name]
+            .as_str()
+            .ok_or_else(|| AppError::InvalidInput(format!("attestation is missing {name}")))
+    };
+    let timestamp = attestation["timestamp"]
+        .as_u64()
+        .ok_or_else(|| AppError::InvalidInput("attestation is missing timestamp".into()))?;
+    let result_hash = result_hash(result)?;
+    if field("result_hash")? != format!("{result_hash:#x}") {
+        return Err(AppError::InvalidInput(
+            "result does not match its attestation".into(),
+        ));
+    }
+    let signature: Signature = field("signature")?
+        .parse()
+        .map_err(|err| AppError::InvalidInput(format!("invalid attestation signature: {err}")))?;
+    let signer = signature
+        .recover(RecoveryMessage::Data(
+            digest(result_hash, timestamp).as_bytes().to_vec(),
+        ))
+        .map_err(|err| AppError::InvalidInput(format!("invalid attestation signature: {err}")))?;
+    if field("signer")?.parse::<Address>().ok() != Some(signer) {
+        return Err(AppError::InvalidInput(
+            "attestation was not signed by its signer".into(),
+        ));
+    }
+    Ok(signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attestor() -> ResponseAttestor {
+        ResponseAttestor::new(
+            "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+                .parse()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn attested_results_verify_until_changed() {
+        let attestor = attestor();
+        let mut result = json!({
+            "amount_out": "1500000000",
+            "meta": { "chain_id": 1, "server_time": 1_700_000_000 },
+        });
+        attestor.attest(1_700_000_000, &mut result).unwrap();
+        assert_eq!(result["attestation"]["timestamp"], 1_700_000_000);
+        assert_eq!(verify(&result).unwrap(), attestor.address());
+
+        // Re-attesting replaces the old attestation rather than signing over it.
+        attestor.attest(1_700_000_001, &mut result).unwrap();
+        assert_eq!(verify(&result).unwrap(), attestor.address());
+
+        let mut tampered = result.clone();
+        tampered["amount_out"] = json!("1600000000");
+        assert!(verify(&tampered).is_err());
+
+        let mut retimed = result.clone();
+        retimed["attestation"]["timestamp"] = json!(1_700_000_002);
+        assert!(verify(&retimed).is_err());
+    }
+
+    #[test]
+    fn non_object_results_are_left_alone() {
+        let mut result = json!(["a", "b"]);
+        attestor().attest(1, &mut result).unwrap();
+        assert_eq!(result, json!(["a", "b"]));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, BlockNumber},
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{block_scope::BlockScoped, price, price::TokenRegistry},
+    progress::ProgressReporter,
+    types::{BacktestOut, BacktestPositionOut, BacktestSide, BacktestTradeOut, QuoteCurrency},
+};
+
+/// Upper bound on sampled blocks per backtest; each sample is a full historical price lookup.
+pub const MAX_BACKTEST_SAMPLES: usize = 500;
+
+/// All-in/all-out rule: buy `base` with every `quote` unit once the price is at or below
+/// `buy_below`, and sell all of it once the price is at or above `sell_above`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdRule {
+    pub buy_below: Option<Decimal>,
+    pub sell_above: Option<Decimal>,
+}
+
+impl ThresholdRule {
+    pub fn parse(buy_below: Option<&str>, sell_above: Option<&str>) -> AppResult<Self> {
+        let buy_below = buy_below
+            .map(|raw| parse_positive("buy_below", raw))
+            .transpose()?;
+        let sell_above = sell_above
+            .map(|raw| parse_positive("sell_above", raw))
+            .transpose()?;
+        match (buy_below, sell_above) {
+            (None, None) => Err(AppError::InvalidInput(
+                "set buy_below, sell_above, or both".into(),
+            )),
+            (Some(buy), Some(sell)) if buy >= sell => Err(AppError::InvalidInput(format!(
+                "buy_below ({buy}) must be below sell_above ({sell})"
+            ))),
+            _ => Ok(Self {
+                buy_below,
+                sell_above,
+            }),
+        }
+    }
+}
+
+/// `base`'s price in the quote currency at one sampled block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PricePoint {
+    pub block_number: u64,
+    pub price: Decimal,
+    pub source: String,
+}
+
+/// Holdings in human units of the base token and the quote currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub base: Decimal,
+    pub quote: Decimal,
+}
+
+impl Position {
+    fn value(&self, price: Decimal) -> AppResult<Decimal> {
+        checked(self.base.checked_mul(price))?
+            .checked_add(self.quote)
+            .ok_or_else(overflow)
+    }
+
+    fn out(&self, price: Decimal) -> AppResult<BacktestPositionOut> {
+        Ok(BacktestPositionOut {
+            base: text(self.base),
+            quote: text(self.quote),
+            value: text(self.value(price)?),
+        })
+    }
+}
+
+/// Blocks `from..=to` every `step`, always ending on `to` so the result reflects the end of the
+/// range.
+pub fn sample_blocks(from: u64, to: u64, step: u64) -> AppResult<Vec<u64>> {
+    if step == 0 {
+        return Err(AppError::InvalidInput(
+            "step_blocks must be greater than zero".into(),
+        ));
+    }
+    if from > to {
+        return Err(AppError::InvalidInput(format!(
+            "from_block {from} is after to_block {to}"
+        )));
+    }
+    let samples = (to - from) / step + 1 + u64::from(!(to - from).is_multiple_of(step));
+    if samples > MAX_BACKTEST_SAMPLES as u64 {
+        return Err(AppError::InvalidInput(format!(
+            "{from}..={to} every {step} blocks is {samples} samples; at most {MAX_BACKTEST_SAMPLES} are allowed, so raise step_blocks or narrow the range"
+        )));
+    }
+    let mut blocks: Vec<u64> = (from..=to).step_by(step as usize).collect();
+    if blocks.last() != Some(&to) {
+        blocks.push(to);
+    }
+    Ok(blocks)
+}
+
+/// `base`'s price at every block in `blocks`, oldest first, through the same Chainlink-first
+/// lookup as `get_token_price`. Reports how many blocks have been read to `progress`.
+pub async fn price_history<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base: Address,
+    quote: QuoteCurrency,
+    blocks: &[u64],
+    progress: &ProgressReporter,
+) -> AppResult<Vec<PricePoint>>
+where
+    M: Middleware + 'static,
+{
+    let total = blocks.len() as u64;
+    let mut points = Vec::with_capacity(blocks.len());
+    for (index, block) in blocks.iter().copied().enumerate() {
+        let requested = BlockId::Number(BlockNumber::Number(block.into()));
+        let scoped = Arc::new(BlockScoped::pin(provider.clone(), Some(requested)).await?);
+        let out = price::resolve_token_price(scoped, registry, base, quote)
+            .await
+            .map_err(|err| {
+                AppError::Price(format!(
+                    "no price at block {block} ({err}); historical blocks need an archive node"
+                ))
+            })?;
+        let price = Decimal::from_str(&out.price)
+            .map_err(|err| AppError::Internal(format!("invalid price {}: {err}", out.price)))?;
+        points.push(PricePoint {
+            block_number: block,
+            price,
+            source: out.source,
+        });
+        progress.report(
+            index as u64 + 1,
+            Some(total),
+            format!("priced block {block}"),
+        );
+    }
+    Ok(points)
+}
+
+/// Replay `rule` over `points` starting from `start`, paying `fee_bps` of every trade.
+pub fn run(
+    points: &[PricePoint],
+    rule: ThresholdRule,
+    start: Position,
+    fee_bps: u32,
+) -> AppResult<(Position, Vec<BacktestTradeOut>)> {
+    if fee_bps >= 10_000 {
+        return Err(AppError::InvalidInput("fee_bps must be below 10000".into()));
+    }
+    let keep = Decimal::ONE - Decimal::from(fee_bps) / Decimal::from(10_000);
+    let mut position = start;
+    let mut trades = Vec::new();
+    for point in points {
+        let price = point.price;
+        if rule.buy_below.is_some_and(|limit| price <= limit) && !position.quote.is_zero() {
+            let spent = position.quote;
+            let fee = checked(spent.checked_mul(Decimal::ONE - keep))?;
+            let bought = checked(checked(spent.checked_mul(keep))?.checked_div(price))?;
+            position = Position {
+                base: checked(position.base.checked_add(bought))?,
+                quote: Decimal::ZERO,
+            };
+            trades.push(trade(point, BacktestSide::Buy, bought, spent, fee));
+        } else if rule.sell_above.is_some_and(|limit| price >= limit) && !position.base.is_zero() {
+            let sold = position.base;
+            let gross = checked(sold.checked_mul(price))?;
+            let fee = checked(gross.checked_mul(Decimal::ONE - keep))?;
+            let received = gross - fee;
+            position = Position {
+                base: Decimal::ZERO,
+                quote: checked(position.quote.checked_add(received))?,
+            };
+            trades.push(trade(point, BacktestSide::Sell, sold, received, fee));
+        }
+    }
+    Ok((position, trades))
+}
+
+/// The report for a finished run: P&L against both the starting value and simply holding the
+/// starting position.
+pub fn summarize(
+    base: String,
+    quote: String,
+    step_blocks: u64,
+    points: &[PricePoint],
+    start: Position,
+    end: Position,
+    trades: Vec<BacktestTradeOut>,
+) -> AppResult<BacktestOut> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Err(AppError::InvalidInput("no blocks to backtest".into()));
+    };
+    let start_value = start.value(first.price)?;
+    let end_value = end.value(last.price)?;
+    let hold_value = start.value(last.price)?;
+    let pnl = end_value - start_value;
+    let pnl_pct = (!start_value.is_zero())
+        .then(|| {
+            pnl.checked_mul(Decimal::ONE_HUNDRED)?
+                .checked_div(start_value)
+        })
+        .flatten()
+        .map(|pct| text(pct.round_dp(4)));
+    let mut sources: Vec<String> = Vec::new();
+    for point in points {
+        if !sources.contains(&point.source) {
+            sources.push(point.source.clone());
+        }
+    }
+    let prices = points.iter().map(|point| point.price);
+
+    Ok(BacktestOut {
+        base,
+        quote,
+        from_block: first.block_number,
+        to_block: last.block_number,
+        step_blocks,
+        samples: points.len(),
+        first_price: text(first.price),
+        last_price: text(last.price),
+        min_price: text(prices.clone().min().unwrap_or_default()),
+        max_price: text(prices.max().unwrap_or_default()),
+        start: start.out(first.price)?,
+        end: end.out(last.price)?,
+        pnl: text(pnl),
+        pnl_pct,
+        hold_value: text(hold_value),
+        hold_pnl: text(hold_value - start_value),
+        trades,
+        sources,
+        warnings: Vec::new(),
+    })
+}
+
+fn trade(
+    point: &PricePoint,
+    side: BacktestSide,
+    base_amount: Decimal,
+    quote_amount: Decimal,
+    fee: Decimal,
+) -> BacktestTradeOut {
+    BacktestTradeOut {
+        block_number: point.block_number,
+        side,
+        price: text(point.price),
+        base_amount: text(base_amount),
+        quote_amount: text(quote_amount),
+        fee: text(fee),
+    }
+}
+
+/// A positive decimal such as a threshold or starting amount.
+pub fn parse_positive(field: &str, raw: &str) -> AppResult<Decimal> {
+    let value = Decimal::from_str(raw.trim())
+        .map_err(|_| AppError::InvalidInput(format!("invalid {field}: {raw}")))?;
+    if value.is_sign_negative() {
+        return Err(AppError::InvalidInput(format!(
+            "{field} cannot be negative"
+        )));
+    }
+    Ok(value)
+}
+
+fn checked(value: Option<Decimal>) -> AppResult<Decimal> {
+    value.ok_or_else(overflow)
+}
+
+fn overflow() -> AppError {
+    AppError::InvalidInput("backtest amounts are too large".into())
+}
+
+fn text(value: Decimal) -> String {
+    value.round_dp(18).normalize().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(prices: &[i64]) -> Vec<PricePoint> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(index, price)| PricePoint {
+                block_number: 100 + index as u64 * 10,
+                price: Decimal::from(*price),
+                source: "chainlink".into(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn samples_always_end_on_the_last_block() {
+        assert_eq!(sample_blocks(100, 125, 10).unwrap(), [100, 110, 120, 125]);
+        assert_eq!(sample_blocks(100, 120, 10).unwrap(), [100, 110, 120]);
+        assert_eq!(sample_blocks(7, 7, 300).unwrap(), [7]);
+        assert!(sample_blocks(0, 10_000, 10).is_err());
+        assert!(sample_blocks(10, 5, 1).is_err());
+    }
+
+    #[test]
+    fn threshold_rule_trades_on_crossings() {
+        let rule = ThresholdRule::parse(Some("1800"), Some("2200")).unwrap();
+        let history = points(&[2000, 1750, 1900, 2300, 2100, 1700]);
+        let start = Position {
+            base: Decimal::ZERO,
+            quote: Decimal::from(1_750),
+        };
+        let (end, trades) = run(&history, rule, start, 0).unwrap();
+        let sides: Vec<BacktestSide> = trades.iter().map(|trade| trade.side).collect();
+        assert_eq!(
+            sides,
+            [BacktestSide::Buy, BacktestSide::Sell, BacktestSide::Buy]
+        );
+        // 1750 -> 1 base at 1750 -> 2300 quote -> 2300/1700 base.
+        assert_eq!(trades[1].quote_amount, "2300");
+        assert_eq!(end.quote, Decimal::ZERO);
+
+        let out = summarize(
+            "WETH".into(),
+            "USD".into(),
+            10,
+            &history,
+            start,
+            end,
+            trades,
+        )
+        .unwrap();
+        assert_eq!(out.end.value, "2300");
+        assert_eq!(out.pnl, "550");
+        assert_eq!(out.hold_pnl, "0");
+        assert_eq!(out.min_price, "1700");
+
+        assert!(ThresholdRule::parse(None, None).is_err());
+        assert!(ThresholdRule::parse(Some("2200"), Some("1800")).is_err());
+    }
+
+    #[test]
+    fn fees_come_out_of_every_trade() {
+        let rule = ThresholdRule::parse(Some("100"), None).unwrap();
+        let start = Position {
+            base: Decimal::ZERO,
+            quote: Decimal::from(1_000),
+        };
+        let (end, trades) = run(&points(&[100]), rule, start, 30).unwrap();
+        assert_eq!(trades[0].fee, "3");
+        assert_eq!(end.base, Decimal::from_str("9.97").unwrap());
+    }
+}
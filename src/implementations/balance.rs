@@ -4,6 +4,7 @@ use ethers::{
     providers::Middleware,
     types::{Address, U256},
 };
+use rust_decimal::Decimal;
 
 use crate::{
     error::{AppError, AppResult},
@@ -11,22 +12,28 @@ use crate::{
     types::BalanceOut,
 };
 
-/// Resolve ETH or ERC-20 balances depending on whether a token address is supplied.
+/// Resolve native-coin or ERC-20 balances depending on whether a token address is supplied;
+/// native balances are labelled `native_symbol`.
 pub async fn resolve_balance<M>(
     provider: Arc<M>,
     address: Address,
     token: Option<Address>,
+    native_symbol: &str,
 ) -> AppResult<BalanceOut>
 where
     M: Middleware + 'static,
 {
     match token {
         Some(token_addr) => resolve_erc20_balance(provider, address, token_addr).await,
-        None => resolve_eth_balance(provider, address).await,
+        None => resolve_eth_balance(provider, address, native_symbol).await,
     }
 }
 
-async fn resolve_eth_balance<M>(provider: Arc<M>, address: Address) -> AppResult<BalanceOut>
+async fn resolve_eth_balance<M>(
+    provider: Arc<M>,
+    address: Address,
+    native_symbol: &str,
+) -> AppResult<BalanceOut>
 where
     M: Middleware + 'static,
 {
@@ -38,10 +45,13 @@ where
     let formatted = format_with_decimals(&raw_balance, 18);
 
     Ok(BalanceOut {
-        symbol: "ETH".to_string(),
+        symbol: native_symbol.to_string(),
         raw: raw_balance.to_string(),
         decimals: 18,
         formatted,
+        block_number: None,
+        decimals_warning: None,
+        warnings: Vec::new(),
     })
 }
 
@@ -62,41 +72,84 @@ where
         raw: raw.to_string(),
         decimals: metadata.decimals as u32,
         formatted,
+        block_number: None,
+        decimals_warning: None,
+        warnings: Vec::new(),
     })
 }
 
+/// Inverse of [`format_with_decimals`]: truncate `amount` to `decimals` places and return the
+/// raw token units.
+pub fn parse_with_decimals(amount: Decimal, decimals: u32) -> AppResult<U256> {
+    let amount = amount.trunc_with_scale(decimals).normalize();
+    parse_formatted(&amount.to_string(), decimals)
+        .map_err(|_| AppError::Internal(format!("cannot convert {amount} to token units")))
+}
+
+/// Exact inverse of [`format_with_decimals`] for any `U256`: parse a plain decimal string such
+/// as `"123.456"` into raw token units. Signs, exponents, more than `decimals` significant
+/// fractional digits and values above `U256::MAX` are rejected rather than rounded or wrapped.
+pub fn parse_formatted(text: &str, decimals: u32) -> AppResult<U256> {
+    let invalid =
+        |reason: String| AppError::InvalidInput(format!("invalid amount {text:?}: {reason}"));
+    let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid("expected digits".into()));
+    }
+    let all_digits = integer
+        .bytes()
+        .chain(fraction.bytes())
+        .all(|b| b.is_ascii_digit());
+    if !all_digits {
+        return Err(invalid("expected digits and at most one point".into()));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    let fraction_len = u32::try_from(fraction.len()).unwrap_or(u32::MAX);
+    if fraction_len > decimals {
+        return Err(invalid(format!("more than {decimals} decimal places")));
+    }
+    scaled(integer, decimals)
+        .zip(scaled(fraction, decimals - fraction_len))
+        .and_then(|(integer, fraction)| integer.checked_add(fraction))
+        .ok_or_else(|| invalid("exceeds the largest uint256".into()))
+}
+
+/// `digits × 10^exponent`, or `None` on overflow.
+fn scaled(digits: &str, exponent: u32) -> Option<U256> {
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some(U256::zero());
+    }
+    U256::from_dec_str(digits)
+        .ok()?
+        .checked_mul(pow10(exponent)?)
+}
+
+/// `10^exponent`, or `None` past 10^77, the largest power of ten a `U256` holds.
+pub fn pow10(exponent: u32) -> Option<U256> {
+    U256::from(10u64).checked_pow(U256::from(exponent))
+}
+
 /// Format a `U256` amount into a decimal string using the provided number of decimals.
+/// Decimals beyond 77 are allowed; every digit of `raw` then falls after the point.
 pub fn format_with_decimals(raw: &U256, decimals: u32) -> String {
     if decimals == 0 {
         return raw.to_string();
     }
 
-    let ten = U256::from(10u64);
-    let power = ten.pow(U256::from(decimals));
-    if power.is_zero() {
-        return raw.to_string();
-    }
-
-    let integer = raw / power;
-    let fraction = raw % power;
+    let (integer, fraction) = match pow10(decimals) {
+        Some(power) => (raw / power, raw % power),
+        None => (U256::zero(), *raw),
+    };
 
     if fraction.is_zero() {
         return integer.to_string();
     }
 
-    let mut fraction_str = fraction.to_string();
-    if fraction_str.len() < decimals as usize {
-        let padding = decimals as usize - fraction_str.len();
-        let prefix = "0".repeat(padding);
-        fraction_str = format!("{prefix}{fraction_str}");
-    }
-
-    let trimmed_fraction = fraction_str.trim_end_matches('0').to_string();
-    if trimmed_fraction.is_empty() {
-        integer.to_string()
-    } else {
-        format!("{}.{}", integer, trimmed_fraction)
-    }
+    let fraction_str = fraction.to_string();
+    let padding = (decimals as usize).saturating_sub(fraction_str.len());
+    let trimmed_fraction = fraction_str.trim_end_matches('0');
+    format!("{integer}.{}{trimmed_fraction}", "0".repeat(padding))
 }
 
 #[cfg(test)]
@@ -106,8 +159,10 @@ mod tests {
         core::abi::{encode, Token},
         providers::{Http, MockProvider, Provider},
     };
+    use proptest::prelude::*;
     use std::sync::Arc;
     use std::env;
+    use std::str::FromStr;
 
     #[test]
     fn formats_without_decimals() {
@@ -127,6 +182,90 @@ mod tests {
         assert_eq!(format_with_decimals(&value, 18), "1");
     }
 
+    #[test]
+    fn parses_decimals_truncating_excess_precision() {
+        let amount = Decimal::from(100) / Decimal::from(3);
+        assert_eq!(
+            parse_with_decimals(amount, 6).unwrap(),
+            U256::from(33_333_333u64)
+        );
+    }
+
+    #[test]
+    fn formats_and_parses_extreme_decimals() {
+        let max = U256::MAX.to_string();
+        assert_eq!(format_with_decimals(&U256::MAX, 0), max);
+        assert_eq!(
+            format_with_decimals(&U256::MAX, 36),
+            format!("{}.{}", &max[..42], &max[42..])
+        );
+        // 10^78 does not fit a U256, so every digit lands after the point.
+        assert_eq!(format_with_decimals(&U256::MAX, 78), format!("0.{max}"));
+        assert_eq!(
+            format_with_decimals(&U256::one(), 80),
+            format!("0.{}1", "0".repeat(79))
+        );
+        assert_eq!(parse_formatted(&format!("0.{max}"), 78).unwrap(), U256::MAX);
+        assert_eq!(parse_formatted("0.000", 255).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn rejects_malformed_and_oversized_amounts() {
+        for text in ["", ".", "-1", "+1", "1e18", "1.2.3", " 1", "0x10", "1,000"] {
+            assert!(
+                parse_formatted(text, 18).is_err(),
+                "{text:?} should be rejected"
+            );
+        }
+        assert!(parse_formatted("1.0000001", 6).is_err());
+        assert_eq!(
+            parse_formatted("1.0000000", 6).unwrap(),
+            U256::from(1_000_000u64)
+        );
+        assert_eq!(parse_formatted(".5", 1).unwrap(), U256::from(5u64));
+        assert_eq!(parse_formatted("5.", 1).unwrap(), U256::from(50u64));
+
+        let above_max = U256::MAX.to_string().replacen('1', "2", 1);
+        assert!(parse_formatted(&above_max, 0).is_err());
+        assert!(parse_formatted("1", 78).is_err());
+        assert!(parse_with_decimals(Decimal::NEGATIVE_ONE, 18).is_err());
+    }
+
+    fn any_u256() -> impl Strategy<Value = U256> {
+        prop_oneof![
+            any::<u64>().prop_map(U256::from),
+            any::<[u64; 4]>().prop_map(U256),
+            Just(U256::MAX),
+        ]
+    }
+
+    fn any_decimals() -> impl Strategy<Value = u32> {
+        let extremes = prop::sample::select(vec![0u32, 6, 18, 24, 36, 77, 78]);
+        prop_oneof![extremes, 0..=100u32]
+    }
+
+    proptest! {
+        #[test]
+        fn formatted_amounts_round_trip(raw in any_u256(), decimals in any_decimals()) {
+            let text = format_with_decimals(&raw, decimals);
+            prop_assert!(!text.ends_with('0') || !text.contains('.'), "{text}");
+            prop_assert_eq!(parse_formatted(&text, decimals).unwrap(), raw);
+        }
+
+        #[test]
+        fn decimals_parse_to_their_truncation(
+            mantissa in 0i64..=i64::MAX,
+            scale in 0u32..=28,
+            decimals in 0u32..=36,
+        ) {
+            let amount = Decimal::new(mantissa, scale);
+            let expected = amount.trunc_with_scale(decimals).normalize();
+            let raw = parse_with_decimals(amount, decimals).unwrap();
+            let formatted = format_with_decimals(&raw, decimals);
+            prop_assert_eq!(Decimal::from_str(&formatted).unwrap(), expected);
+        }
+    }
+
     #[tokio::test]
     async fn resolve_eth_balance_formats_expected_output() {
         let mock = MockProvider::new();
@@ -135,7 +274,9 @@ mod tests {
         let provider = Arc::new(Provider::new(mock));
         let address = Address::from_low_u64_be(1);
 
-        let balance = super::resolve_eth_balance(provider, address).await.unwrap();
+        let balance = super::resolve_eth_balance(provider, address, "ETH")
+            .await
+            .unwrap();
 
         assert_eq!(balance.symbol, "ETH");
         assert_eq!(balance.decimals, 18);
@@ -183,7 +324,7 @@ mod tests {
             Provider::<Http>::try_from(rpc_url.as_str()).expect("failed to create provider"),
         );
 
-        let balance = super::resolve_balance(provider, address, None)
+        let balance = super::resolve_balance(provider, address, None, "ETH")
             .await
             .expect("balance lookup failed");
         println!("Live ETH balance: {:?}", balance);
@@ -209,7 +350,7 @@ mod tests {
             Provider::<Http>::try_from(rpc_url.as_str()).expect("failed to create provider"),
         );
 
-        let balance = super::resolve_balance(provider, address, Some(token_address))
+        let balance = super::resolve_balance(provider, address, Some(token_address), "ETH")
             .await
             .expect("token balance lookup failed");
         println!("Live ERC-20 balance: {:?}", balance);
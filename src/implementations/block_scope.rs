@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use ethers::{
+    providers::{Middleware, MiddlewareError},
+    types::{
+        BlockId, BlockNumber, Bytes, GethDebugTracingCallOptions, GethTrace, H256, NameOrAddress,
+        U64, U256,
+        transaction::{eip2718::TypedTransaction, eip2930::AccessListWithGasUsed},
+    },
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::error::{AppError, AppResult};
+
+/// Block tag applied to reads that do not name a block.
+///
+/// `safe` and `finalized` trade a few blocks (or ~2 epochs) of staleness for immunity to reorgs.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultBlockTag {
+    #[default]
+    Latest,
+    Safe,
+    Finalized,
+}
+
+impl DefaultBlockTag {
+    pub fn block_id(self) -> BlockId {
+        BlockId::Number(match self {
+            DefaultBlockTag::Latest => BlockNumber::Latest,
+            DefaultBlockTag::Safe => BlockNumber::Safe,
+            DefaultBlockTag::Finalized => BlockNumber::Finalized,
+        })
+    }
+}
+
+impl FromStr for DefaultBlockTag {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "latest" => Ok(DefaultBlockTag::Latest),
+            "safe" => Ok(DefaultBlockTag::Safe),
+            "finalized" => Ok(DefaultBlockTag::Finalized),
+            other => Err(AppError::Config(format!(
+                "default block tag must be latest, safe or finalized, got {other}"
+            ))),
+        }
+    }
+}
+
+/// Parse a user-supplied block selector: a decimal or `0x` number, a 32-byte block hash,
+/// or one of `latest`, `finalized`, `safe`, `earliest`, `pending`.
+pub fn parse_block_id(input: &str) -> AppResult<BlockId> {
+    let value = input.trim();
+    let tag = match value.to_ascii_lowercase().as_str() {
+        "latest" => Some(BlockNumber::Latest),
+        "finalized" => Some(BlockNumber::Finalized),
+        "safe" => Some(BlockNumber::Safe),
+        "earliest" => Some(BlockNumber::Earliest),
+        "pending" => Some(BlockNumber::Pending),
+        _ => None,
+    };
+    if let Some(tag) = tag {
+        return Ok(BlockId::Number(tag));
+    }
+
+    let invalid = || AppError::InvalidInput(format!("invalid block: {input}"));
+    match value.strip_prefix("0x") {
+        Some(hex) if hex.len() == 64 => value
+            .parse::<H256>()
+            .map(BlockId::Hash)
+            .map_err(|_| invalid()),
+        Some(hex) => u64::from_str_radix(hex, 16)
+            .map(|number| BlockId::Number(BlockNumber::Number(number.into())))
+            .map_err(|_| invalid()),
+        None => value
+            .parse::<u64>()
+            .map(|number| BlockId::Number(BlockNumber::Number(number.into())))
+            .map_err(|_| invalid()),
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BlockScopedError<M: Middleware> {
+    #[error("{0}")]
+    MiddlewareError(M::Error),
+    #[error("cache codec error: {0}")]
+    Cache(#[from] serde_json::Error),
+}
+
+impl<M: Middleware> MiddlewareError for BlockScopedError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        BlockScopedError::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            BlockScopedError::MiddlewareError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Request-scoped middleware that pins state reads to one block and deduplicates them.
+///
+/// Reads issued without an explicit block (`eth_call`, `eth_estimateGas`,
+/// `eth_createAccessList`, `eth_getCode`, `eth_getStorageAt`, `eth_getBalance`) run against
+/// the pinned block, so every value in a response describes the same chain state. Identical
+/// reads within the scope are answered from memory.
+pub struct BlockScoped<M> {
+    inner: Arc<M>,
+    block_number: u64,
+    cache: Mutex<HashMap<String, Value>>,
+}
+
+impl<M> fmt::Debug for BlockScoped<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockScoped")
+            .field("block_number", &self.block_number)
+            .finish()
+    }
+}
+
+impl<M: Middleware + 'static> BlockScoped<M> {
+    pub fn new(inner: Arc<M>, block_number: u64) -> Self {
+        Self {
+            inner,
+            block_number,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve `requested` (or the chain head) to a concrete block number and scope reads to it.
+    pub async fn pin(inner: Arc<M>, requested: Option<BlockId>) -> AppResult<Self> {
+        let number = match requested {
+            None | Some(BlockId::Number(BlockNumber::Latest)) => inner
+                .get_block_number()
+                .await
+                .map_err(|err| AppError::Rpc(format!("failed to fetch block number: {err}")))?
+                .as_u64(),
+            Some(BlockId::Number(BlockNumber::Number(number))) => number.as_u64(),
+            Some(id) => inner
+                .get_block(id)
+                .await
+                .map_err(|err| AppError::Rpc(format!("failed to resolve block {id:?}: {err}")))?
+                .and_then(|block| block.number)
+                .ok_or_else(|| AppError::InvalidInput(format!("block not found: {id:?}")))?
+                .as_u64(),
+        };
+        Ok(Self::new(inner, number))
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    fn scoped(&self, block: Option<BlockId>) -> Option<BlockId> {
+        Some(
+            block.unwrap_or_else(|| BlockId::Number(BlockNumber::Number(self.block_number.into()))),
+        )
+    }
+
+    async fn cached<T, F>(&self, key: String, fetch: F) -> Result<T, BlockScopedError<M>>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, M::Error>>,
+    {
+        let hit = self
+            .cache
+            .lock()
+            .expect("block cache poisoned")
+            .get(&key)
+            .cloned();
+        if let Some(value) = hit {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        let fetched = fetch.await.map_err(BlockScopedError::MiddlewareError)?;
+        let value = serde_json::to_value(&fetched)?;
+        self.cache
+            .lock()
+            .expect("block cache poisoned")
+            .insert(key, value);
+        Ok(fetched)
+    }
+}
+
+fn cache_key(method: &str, parts: impl Serialize) -> String {
+    format!(
+        "{method}:{}",
+        serde_json::to_string(&parts).unwrap_or_default()
+    )
+}
+
+#[async_trait]
+impl<M> Middleware for BlockScoped<M>
+where
+    M: Middleware + 'static,
+{
+    type Error = BlockScopedError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    async fn get_block_number(&self) -> Result<U64, Self::Error> {
+        Ok(self.block_number.into())
+    }
+
+    async fn call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_call", (tx, block)),
+            self.inner.call(tx, block),
+        )
+        .await
+    }
+
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_estimateGas", (tx, block)),
+            self.inner.estimate_gas(tx, block),
+        )
+        .await
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<AccessListWithGasUsed, Self::Error> {
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_createAccessList", (tx, block)),
+            self.inner.create_access_list(tx, block),
+        )
+        .await
+    }
+
+    async fn get_code<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        at: T,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, Self::Error> {
+        let at = at.into();
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_getCode", (&at, block)),
+            self.inner.get_code(at.clone(), block),
+        )
+        .await
+    }
+
+    async fn get_storage_at<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        location: H256,
+        block: Option<BlockId>,
+    ) -> Result<H256, Self::Error> {
+        let from = from.into();
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_getStorageAt", (&from, location, block)),
+            self.inner.get_storage_at(from.clone(), location, block),
+        )
+        .await
+    }
+
+    async fn get_balance<T: Into<NameOrAddress> + Send + Sync>(
+        &self,
+        from: T,
+        block: Option<BlockId>,
+    ) -> Result<U256, Self::Error> {
+        let from = from.into();
+        let block = self.scoped(block);
+        self.cached(
+            cache_key("eth_getBalance", (&from, block)),
+            self.inner.get_balance(from.clone(), block),
+        )
+        .await
+    }
+
+    /// Traces are pinned like every other read but not cached; they are large and rarely repeated.
+    async fn debug_trace_call<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        req: T,
+        block: Option<BlockId>,
+        trace_options: GethDebugTracingCallOptions,
+    ) -> Result<GethTrace, Self::Error> {
+        self.inner
+            .debug_trace_call(req, self.scoped(block), trace_options)
+            .await
+            .map_err(BlockScopedError::MiddlewareError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{Address, TransactionRequest},
+    };
+
+    #[test]
+    fn parses_block_selectors() {
+        assert_eq!(
+            parse_block_id("finalized").unwrap(),
+            BlockId::Number(BlockNumber::Finalized)
+        );
+        assert_eq!(
+            parse_block_id("19000000").unwrap(),
+            BlockId::Number(BlockNumber::Number(19_000_000u64.into()))
+        );
+        assert_eq!(
+            parse_block_id("0x10").unwrap(),
+            BlockId::Number(BlockNumber::Number(16u64.into()))
+        );
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert!(matches!(parse_block_id(&hash).unwrap(), BlockId::Hash(_)));
+        assert!(parse_block_id("yesterday").is_err());
+        assert_eq!(
+            "Finalized".parse::<DefaultBlockTag>().unwrap().block_id(),
+            BlockId::Number(BlockNumber::Finalized)
+        );
+        assert!("pending".parse::<DefaultBlockTag>().is_err());
+    }
+
+    #[tokio::test]
+    async fn pins_reads_to_block_and_dedupes() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Bytes, _>(Bytes::from(vec![0x2a])).unwrap();
+
+        let scoped = BlockScoped::new(Arc::new(provider), 16);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::from_low_u64_be(1))
+            .into();
+
+        // The mock holds a single response; the repeat must come from the cache.
+        let first = scoped.call(&tx, None).await.unwrap();
+        let second = scoped.call(&tx, None).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(scoped.get_block_number().await.unwrap(), U64::from(16));
+
+        mock.assert_request("eth_call", (&tx, "0x10")).unwrap();
+    }
+}
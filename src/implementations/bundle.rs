@@ -0,0 +1,258 @@
+use ethers::{
+    providers::{Middleware, MiddlewareError},
+    types::{Address, Bytes, Log, U64, U256},
+    utils::keccak256,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, deposits::TRANSFER_EVENT, price::TokenRegistry, revert},
+    types::{BundleOut, BundleStepOut, BundleStepStatus, BundleTokenEventOut},
+};
+
+/// Steps one `simulate_bundle` call may chain.
+pub const MAX_BUNDLE_STEPS: usize = 16;
+
+const APPROVAL_EVENT: &str = "Approval(address,address,uint256)";
+
+/// JSON-RPC code nodes return for a method they do not expose.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// One transaction of a bundle, resolved.
+#[derive(Debug, Clone)]
+pub struct BundleCall {
+    pub label: Option<String>,
+    pub from: Address,
+    pub to: Address,
+    pub data: Bytes,
+    pub value: U256,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulatedBlock {
+    calls: Vec<SimulatedCall>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedCall {
+    status: U64,
+    #[serde(default)]
+    return_data: Bytes,
+    gas_used: U64,
+    #[serde(default)]
+    logs: Vec<Log>,
+    #[serde(default)]
+    error: Option<SimulatedError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SimulatedError {
+    message: String,
+}
+
+/// Run `calls` in order as the transactions of one block on top of `block_number` through
+/// `eth_simulateV1`, so each step sees the state the steps before it left behind. Validation
+/// is off: fees are not charged and nonces are not checked, but balances and allowances are.
+/// A reverted step leaves no state behind and the steps after it still run.
+pub async fn simulate<M: Middleware>(
+    provider: &M,
+    registry: &TokenRegistry,
+    calls: &[BundleCall],
+    block_number: u64,
+) -> AppResult<BundleOut> {
+    let payload = json!({
+        "blockStateCalls": [{
+            "calls": calls
+                .iter()
+                .map(|call| json!({
+                    "from": call.from,
+                    "to": call.to,
+                    "input": call.data,
+                    "value": call.value,
+                }))
+                .collect::<Vec<_>>(),
+        }],
+        "validation": false,
+    });
+    let blocks: Value = provider
+        .provider()
+        .request("eth_simulateV1", (payload, U64::from(block_number)))
+        .await
+        .map_err(|err| {
+            let unsupported = err
+                .as_error_response()
+                .is_some_and(|response| response.code == METHOD_NOT_FOUND);
+            if unsupported {
+                AppError::Rpc("the configured endpoint does not support eth_simulateV1".into())
+            } else {
+                AppError::Rpc(format!("eth_simulateV1 failed: {err}"))
+            }
+        })?;
+    let blocks: Vec<SimulatedBlock> = serde_json::from_value(blocks)
+        .map_err(|err| AppError::Rpc(format!("unexpected eth_simulateV1 result: {err}")))?;
+    let results = blocks
+        .into_iter()
+        .next()
+        .map(|block| block.calls)
+        .unwrap_or_default();
+    if results.len() != calls.len() {
+        return Err(AppError::Rpc(format!(
+            "eth_simulateV1 returned {} results for {} steps",
+            results.len(),
+            calls.len()
+        )));
+    }
+
+    let mut gas_used = U256::zero();
+    let steps: Vec<BundleStepOut> = calls
+        .iter()
+        .zip(results)
+        .enumerate()
+        .map(|(index, (call, result))| {
+            gas_used += U256::from(result.gas_used.as_u64());
+            step_out(index, call, result, registry)
+        })
+        .collect();
+    let first_failure = steps
+        .iter()
+        .find(|step| step.status == BundleStepStatus::Reverted)
+        .map(|step| step.index);
+    Ok(BundleOut {
+        block_number,
+        succeeded: first_failure.is_none(),
+        first_failure,
+        gas_used: gas_used.to_string(),
+        steps,
+        warnings: Vec::new(),
+    })
+}
+
+fn step_out(
+    index: usize,
+    call: &BundleCall,
+    result: SimulatedCall,
+    registry: &TokenRegistry,
+) -> BundleStepOut {
+    let succeeded = result.status == U64::one();
+    let revert_reason = (!succeeded && !result.return_data.is_empty())
+        .then(|| revert::decode_revert(&result.return_data));
+    BundleStepOut {
+        index,
+        label: call.label.clone(),
+        from: format!("{:#x}", call.from),
+        to: format!("{:#x}", call.to),
+        status: if succeeded {
+            BundleStepStatus::Success
+        } else {
+            BundleStepStatus::Reverted
+        },
+        gas_used: result.gas_used.to_string(),
+        return_data: format!("{}", result.return_data),
+        error: result.error.map(|error| error.message),
+        revert_reason,
+        token_events: result
+            .logs
+            .iter()
+            .filter_map(|log| token_event(log, registry))
+            .collect(),
+    }
+}
+
+/// An ERC-20 `Transfer` or `Approval` log, with the amount in human units for registry tokens.
+fn token_event(log: &Log, registry: &TokenRegistry) -> Option<BundleTokenEventOut> {
+    // ERC-721 shares both signatures but indexes the token id instead of carrying data.
+    if log.topics.len() != 3 || log.data.len() < 32 {
+        return None;
+    }
+    let kind = if log.topics[0] == keccak256(TRANSFER_EVENT).into() {
+        "transfer"
+    } else if log.topics[0] == keccak256(APPROVAL_EVENT).into() {
+        "approval"
+    } else {
+        return None;
+    };
+    let amount = U256::from_big_endian(&log.data[..32]);
+    let token = registry.info_by_address(log.address);
+    Some(BundleTokenEventOut {
+        kind: kind.to_string(),
+        token: format!("{:#x}", log.address),
+        symbol: token.map(|token| token.symbol.clone()),
+        from: format!("{:#x}", Address::from(log.topics[1])),
+        to: format!("{:#x}", Address::from(log.topics[2])),
+        amount_raw: amount.to_string(),
+        amount: token.map(|token| balance::format_with_decimals(&amount, token.decimals as u32)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{providers::Provider, types::H256};
+
+    #[tokio::test]
+    async fn steps_report_their_own_outcome_and_token_events() {
+        let registry = TokenRegistry::with_defaults();
+        let usdc = registry
+            .resolve_symbol("USDC")
+            .expect("USDC is a default token");
+        let wallet = Address::from_low_u64_be(0xaa);
+        let router = Address::from_low_u64_be(0xbb);
+        let mut amount = [0u8; 32];
+        U256::from(2_500_000u64).to_big_endian(&mut amount);
+        let approval = json!({
+            "address": usdc,
+            "topics": [
+                H256::from(keccak256(APPROVAL_EVENT)),
+                H256::from(wallet),
+                H256::from(router),
+            ],
+            "data": Bytes::from(amount.to_vec()),
+        });
+        // `Error(string)` with "STF".
+        let revert_data = "0x08c379a0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000035354460000000000000000000000000000000000000000000000000000000000";
+
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Value, _>(json!([{
+            "calls": [
+                { "status": "0x1", "returnData": "0x", "gasUsed": "0xb5f0", "logs": [approval] },
+                {
+                    "status": "0x0",
+                    "returnData": revert_data,
+                    "gasUsed": "0x7530",
+                    "logs": [],
+                    "error": { "code": 3, "message": "execution reverted" },
+                },
+            ],
+        }]))
+        .unwrap();
+
+        let call = |label: &str| BundleCall {
+            label: Some(label.into()),
+            from: wallet,
+            to: router,
+            data: Bytes::new(),
+            value: U256::zero(),
+        };
+        let out = simulate(&provider, &registry, &[call("approve"), call("swap")], 100)
+            .await
+            .unwrap();
+        assert!(!out.succeeded);
+        assert_eq!(out.first_failure, Some(1));
+        assert_eq!(out.gas_used, (0xb5f0 + 0x7530).to_string());
+
+        let approve = &out.steps[0];
+        assert_eq!(approve.status, BundleStepStatus::Success);
+        assert_eq!(approve.token_events.len(), 1);
+        assert_eq!(approve.token_events[0].kind, "approval");
+        assert_eq!(approve.token_events[0].symbol.as_deref(), Some("USDC"));
+        assert_eq!(approve.token_events[0].amount.as_deref(), Some("2.5"));
+
+        let swap = &out.steps[1];
+        assert_eq!(swap.status, BundleStepStatus::Reverted);
+        assert_eq!(swap.error.as_deref(), Some("execution reverted"));
+        assert!(swap.revert_reason.as_deref().unwrap().contains("STF"));
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use ethers::{
+    core::rand::thread_rng,
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, BlockNumber, Bytes, Eip1559TransactionRequest, U256,
+        transaction::eip2718::TypedTransaction,
+    },
+};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        erc20::{self, Erc20Token},
+        plan, preflight,
+        price::TokenInfo,
+        swap::SwapSettings,
+        warnings,
+    },
+    secrets::{self, BURNER_PASSWORD_SECRET, SecretString},
+    types::{PlanStepOut, SweepOut},
+};
+
+/// Gas of a plain native transfer to an externally owned account.
+const NATIVE_TRANSFER_GAS: u64 = 21_000;
+
+/// Throwaway wallets for interactions with unknown tokens and contracts, kept apart from the
+/// main signer. Each key lives in a standard Ethereum keystore, encrypted with the
+/// `burner_password` secret and named after its address, so any wallet can import it.
+pub struct BurnerVault {
+    dir: PathBuf,
+    password: SecretString,
+    /// Keys created or decrypted this session, by address.
+    unlocked: Mutex<HashMap<Address, LocalWallet>>,
+}
+
+impl BurnerVault {
+    /// `None` unless `burner_keystore_dir` is set; the password comes from the secrets backend.
+    pub fn from_config(config: &AppConfig) -> AppResult<Option<Self>> {
+        let Some(dir) = config.burner_keystore_dir.as_deref() else {
+            return Ok(None);
+        };
+        let password = secrets::provider_from_config(config)?
+            .get(BURNER_PASSWORD_SECRET)?
+            .ok_or_else(|| {
+                AppError::Config("burner_keystore_dir requires a burner_password secret".into())
+            })?;
+        Self::new(dir, password).map(Some)
+    }
+
+    pub fn new(dir: impl Into<PathBuf>, password: SecretString) -> AppResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|err| {
+            AppError::Config(format!(
+                "failed to create burner keystore dir {}: {err}",
+                dir.display()
+            ))
+        })?;
+        Ok(Self {
+            dir,
+            password,
+            unlocked: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Generate a burner and write its encrypted keystore, returning the wallet and the path.
+    /// Key derivation is deliberately slow, so async callers should run this on a blocking thread.
+    pub fn create(&self, chain_id: u64) -> AppResult<(LocalWallet, PathBuf)> {
+        let mut rng = thread_rng();
+        let key = LocalWallet::new(&mut rng);
+        let name = keystore_name(key.address());
+        let (wallet, _) = LocalWallet::encrypt_keystore(
+            &self.dir,
+            &mut rng,
+            key.signer().to_bytes(),
+            self.password.expose(),
+            Some(&name),
+        )
+        .map_err(|err| AppError::Wallet(format!("failed to write burner keystore: {err}")))?;
+        let wallet = wallet.with_chain_id(chain_id);
+        self.unlocked
+            .lock()
+            .expect("burner lock poisoned")
+            .insert(wallet.address(), wallet.clone());
+        Ok((wallet, self.dir.join(name)))
+    }
+
+    /// The burner at `address`, decrypting its keystore on first use; blocking like [`create`].
+    ///
+    /// [`create`]: Self::create
+    pub fn unlock(&self, address: Address, chain_id: u64) -> AppResult<LocalWallet> {
+        if let Some(wallet) = self
+            .unlocked
+            .lock()
+            .expect("burner lock poisoned")
+            .get(&address)
+        {
+            return Ok(wallet.clone());
+        }
+        let path = self.dir.join(keystore_name(address));
+        if !path.exists() {
+            return Err(AppError::InvalidInput(format!(
+                "{address:#x} is not a burner wallet in {}",
+                self.dir.display()
+            )));
+        }
+        let wallet = LocalWallet::decrypt_keystore(&path, self.password.expose())
+            .map_err(|err| {
+                AppError::Wallet(format!(
+                    "failed to decrypt burner keystore {}: {err}",
+                    path.display()
+                ))
+            })?
+            .with_chain_id(chain_id);
+        if wallet.address() != address {
+            return Err(AppError::Wallet(format!(
+                "burner keystore {} holds {:#x}",
+                path.display(),
+                wallet.address()
+            )));
+        }
+        self.unlocked
+            .lock()
+            .expect("burner lock poisoned")
+            .insert(address, wallet.clone());
+        Ok(wallet)
+    }
+}
+
+fn keystore_name(address: Address) -> String {
+    format!("burner-{address:#x}.json")
+}
+
+/// Transactions moving each of `tokens`, then the native balance less gas, from `burner` to
+/// `to`, signed by the burner. Token transfers are dry-run with `eth_estimateGas`; nothing is
+/// broadcast. Gas for every step is held back at `max_fee_per_gas`, so a little dust stays.
+pub async fn plan_sweep<M>(
+    provider: Arc<M>,
+    burner: &LocalWallet,
+    to: Address,
+    tokens: &[&TokenInfo],
+    native_symbol: &str,
+    settings: &SwapSettings,
+) -> AppResult<SweepOut>
+where
+    M: Middleware + 'static,
+{
+    let owner = burner.address();
+    let first_nonce = provider
+        .get_transaction_count(owner, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch nonce: {err}")))?;
+    let (max_fee_per_gas, max_priority_fee_per_gas) = provider
+        .estimate_eip1559_fees(None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to estimate EIP-1559 fees: {err}")))?;
+
+    let mut steps = Vec::new();
+    let mut warnings = Vec::new();
+    for token in tokens {
+        let amount = erc20::fetch_balance_of(provider.clone(), token.address, owner).await?;
+        if amount.is_zero() {
+            warnings.push(warnings::warning(
+                "empty_balance",
+                format!("the burner holds no {}", token.symbol),
+            ));
+            continue;
+        }
+        let calldata = Erc20Token::new(token.address, provider.clone())
+            .transfer(to, amount)
+            .calldata();
+        let description = format!(
+            "transfer {} {} to the main wallet",
+            balance::format_with_decimals(&amount, token.decimals as u32),
+            token.symbol
+        );
+        steps.push(
+            plan::estimated_step(
+                &provider,
+                owner,
+                "transfer",
+                description,
+                token.address,
+                U256::zero(),
+                calldata,
+                settings,
+            )
+            .await?,
+        );
+    }
+
+    let token_gas = steps.iter().try_fold(U256::zero(), |total, step| {
+        parse_u256(&step.gas_limit).map(|gas| total + gas)
+    })?;
+    let token_reserve = token_gas * max_fee_per_gas;
+    let native_reserve = token_reserve + U256::from(NATIVE_TRANSFER_GAS) * max_fee_per_gas;
+    let native = provider
+        .get_balance(owner, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch native balance: {err}")))?;
+    let gas_reserve = if native > native_reserve {
+        let value = native - native_reserve;
+        steps.push(PlanStepOut {
+            kind: "transfer_native".into(),
+            description: format!(
+                "send {} {native_symbol} to the main wallet",
+                balance::format_with_decimals(&value, 18)
+            ),
+            to: format!("{to:#x}"),
+            value_wei: value.to_string(),
+            calldata_hex: "0x".into(),
+            nonce: String::new(),
+            gas_estimate: NATIVE_TRANSFER_GAS.to_string(),
+            gas_limit: NATIVE_TRANSFER_GAS.to_string(),
+            gas_estimate_source: "transfer".into(),
+            access_list: None,
+        });
+        native_reserve
+    } else {
+        preflight::check_funds(native, token_reserve, "the sweep", native_symbol)?;
+        if !native.is_zero() {
+            warnings.push(warnings::warning(
+                "dust_left",
+                format!("{native} wei of {native_symbol} is too little to sweep after gas"),
+            ));
+        }
+        token_reserve
+    };
+
+    let mut signed_transactions = Vec::with_capacity(steps.len());
+    for (offset, step) in steps.iter_mut().enumerate() {
+        let nonce = first_nonce + offset;
+        step.nonce = nonce.to_string();
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(owner)
+            .to(to_address(&step.to)?)
+            .value(parse_u256(&step.value_wei)?)
+            .data(parse_bytes(&step.calldata_hex)?)
+            .nonce(nonce)
+            .gas(parse_u256(&step.gas_limit)?)
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas)
+            .chain_id(burner.chain_id())
+            .into();
+        let signature = burner
+            .sign_transaction_sync(&tx)
+            .map_err(|err| AppError::Wallet(format!("failed to sign sweep: {err}")))?;
+        signed_transactions.push(format!("0x{}", hex::encode(tx.rlp_signed(&signature))));
+    }
+
+    Ok(SweepOut {
+        burner: format!("{owner:#x}"),
+        to: format!("{to:#x}"),
+        block_number: None,
+        steps,
+        signed_transactions,
+        max_fee_per_gas: max_fee_per_gas.to_string(),
+        max_priority_fee_per_gas: max_priority_fee_per_gas.to_string(),
+        gas_reserve_wei: gas_reserve.to_string(),
+        warnings,
+    })
+}
+
+fn parse_u256(value: &str) -> AppResult<U256> {
+    U256::from_dec_str(value)
+        .map_err(|_| AppError::Internal(format!("invalid sweep amount {value}")))
+}
+
+fn parse_bytes(value: &str) -> AppResult<Bytes> {
+    value
+        .parse()
+        .map_err(|_| AppError::Internal(format!("invalid sweep calldata {value}")))
+}
+
+fn to_address(value: &str) -> AppResult<Address> {
+    value
+        .parse()
+        .map_err(|_| AppError::Internal(format!("invalid sweep target {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{Token, encode},
+        providers::Provider,
+        types::Signature,
+        utils::rlp::Rlp,
+    };
+    use serde_json::json;
+
+    fn burner() -> LocalWallet {
+        "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(1u64)
+    }
+
+    #[test]
+    fn keystores_round_trip() {
+        let dir = std::env::temp_dir().join(format!("burners-{}", std::process::id()));
+        let vault = BurnerVault::new(&dir, SecretString::new("hunter2")).unwrap();
+        let (wallet, path) = vault.create(10).unwrap();
+        assert!(path.ends_with(keystore_name(wallet.address())));
+
+        let reopened = BurnerVault::new(&dir, SecretString::new("hunter2")).unwrap();
+        let unlocked = reopened.unlock(wallet.address(), 10).unwrap();
+        assert_eq!(unlocked.address(), wallet.address());
+        assert_eq!(unlocked.chain_id(), 10);
+
+        let stranger = reopened
+            .unlock(Address::from_low_u64_be(1), 10)
+            .unwrap_err();
+        assert!(matches!(stranger, AppError::InvalidInput(_)));
+        let wrong = BurnerVault::new(&dir, SecretString::new("wrong")).unwrap();
+        assert!(wrong.unlock(wallet.address(), 10).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sweeps_tokens_then_the_native_rest() {
+        let (provider, mock) = Provider::mocked();
+        let main = Address::from_low_u64_be(0xa11ce);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(0xc0), 6);
+
+        // Responses are consumed in reverse order.
+        mock.push::<String, _>("0xde0b6b3a7640000".to_string())
+            .unwrap(); // 1 ETH
+        mock.push::<String, _>("0xc350".to_string()).unwrap(); // transfer gas 50_000
+        let balance = encode(&[Token::Uint(U256::from(2_500_000u64))]);
+        mock.push::<String, _>(format!("0x{}", hex::encode(balance)))
+            .unwrap();
+        mock.push(json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x2540be400"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]]
+        }))
+        .unwrap(); // fee_history
+        mock.push(json!({"number": "0x1", "baseFeePerGas": "0x2540be400"}))
+            .unwrap(); // latest block, 10 gwei base fee
+        mock.push::<String, _>("0x7".to_string()).unwrap(); // pending nonce
+
+        let settings = SwapSettings {
+            gas_buffer_percent: 20,
+            uniswap: Default::default(),
+        };
+        let out = plan_sweep(
+            Arc::new(provider),
+            &burner(),
+            main,
+            &[&usdc],
+            "ETH",
+            &settings,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.steps.len(), 2);
+        assert_eq!(
+            out.steps[0].description,
+            "transfer 2.5 USDC to the main wallet"
+        );
+        assert_eq!(
+            (out.steps[0].nonce.as_str(), out.steps[1].nonce.as_str()),
+            ("7", "8")
+        );
+        let max_fee = U256::from_dec_str(&out.max_fee_per_gas).unwrap();
+        let reserve = U256::from(60_000u64 + 21_000) * max_fee;
+        assert_eq!(out.gas_reserve_wei, reserve.to_string());
+        assert_eq!(
+            out.steps[1].value_wei,
+            (U256::exp10(18) - reserve).to_string()
+        );
+
+        let raw = hex::decode(&out.signed_transactions[1][2..]).unwrap();
+        let (tx, signature): (TypedTransaction, Signature) =
+            TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+        assert_eq!(signature.recover(tx.sighash()).unwrap(), burner().address());
+        assert_eq!(tx.to_addr(), Some(&main));
+        assert!(out.warnings.is_empty());
+    }
+}
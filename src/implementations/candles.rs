@@ -0,0 +1,297 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, Filter, I256, Log, U256},
+};
+use rust_decimal::Decimal;
+use tracing::warn;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, price::TokenInfo, uniswap::UniswapV3Factory},
+    types::{CandleOut, CandlesOut},
+};
+
+/// Widest block range scanned per call; one header is fetched per block that contains swaps.
+pub const MAX_CANDLE_BLOCK_RANGE: u64 = 2_000;
+
+const SWAP_EVENT: &str = "Swap(address,address,int256,int256,uint160,uint128,int24)";
+
+/// One swap, priced in `quote` per `base`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub timestamp: u64,
+    pub price: Decimal,
+    pub base_volume: Decimal,
+    pub quote_volume: Decimal,
+}
+
+/// Scan `Swap` events of the `base`/`quote` pool over `[from_block, to_block]` and bucket
+/// them into OHLCV candles of `interval_secs`.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_candles<M>(
+    provider: Arc<M>,
+    factory: Address,
+    base: &TokenInfo,
+    quote: &TokenInfo,
+    fee: u32,
+    from_block: u64,
+    to_block: u64,
+    interval_secs: u64,
+) -> AppResult<CandlesOut>
+where
+    M: Middleware + 'static,
+{
+    if interval_secs == 0 {
+        return Err(AppError::InvalidInput(
+            "interval_secs must be greater than zero".into(),
+        ));
+    }
+    if from_block > to_block || to_block - from_block >= MAX_CANDLE_BLOCK_RANGE {
+        return Err(AppError::InvalidInput(format!(
+            "block range must be ordered and span at most {MAX_CANDLE_BLOCK_RANGE} blocks"
+        )));
+    }
+
+    let pool = UniswapV3Factory::new(factory, provider.clone())
+        .get_pool(base.address, quote.address, fee)
+        .call()
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to look up uniswap pool: {err}")))?;
+    if pool.is_zero() {
+        return Err(AppError::InvalidInput(format!(
+            "no uniswap v3 pool for {}/{} at fee {fee}",
+            base.symbol, quote.symbol
+        )));
+    }
+
+    let filter = Filter::new()
+        .address(pool)
+        .event(SWAP_EVENT)
+        .from_block(from_block)
+        .to_block(to_block);
+    let logs = provider
+        .get_logs(&filter)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch swap logs: {err}")))?;
+
+    // Uniswap orders pool tokens by address.
+    let base_is_token0 = base.address < quote.address;
+    let mut timestamps: HashMap<u64, u64> = HashMap::new();
+    let mut trades = Vec::with_capacity(logs.len());
+    for log in &logs {
+        let Some(block) = log.block_number.map(|number| number.as_u64()) else {
+            continue;
+        };
+        let timestamp = match timestamps.get(&block) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = provider
+                    .get_block(block)
+                    .await
+                    .map_err(|err| AppError::Rpc(format!("failed to fetch block {block}: {err}")))?
+                    .ok_or_else(|| AppError::Rpc(format!("block {block} not found")))?
+                    .timestamp
+                    .as_u64();
+                timestamps.insert(block, timestamp);
+                timestamp
+            }
+        };
+
+        let decoded = decode_swap(log, base_is_token0, base.decimals, quote.decimals).and_then(
+            |(base_volume, quote_volume)| {
+                let price = quote_volume.checked_div(base_volume)?;
+                Some((price, base_volume, quote_volume))
+            },
+        );
+        match decoded {
+            Some((price, base_volume, quote_volume)) => trades.push(Trade {
+                timestamp,
+                price,
+                base_volume,
+                quote_volume,
+            }),
+            None => warn!("skipping undecodable swap log in block {block}"),
+        }
+    }
+
+    Ok(CandlesOut {
+        pool: format!("{pool:#x}"),
+        base: base.symbol.clone(),
+        quote: quote.symbol.clone(),
+        fee,
+        from_block,
+        to_block,
+        interval_secs,
+        trades: trades.len(),
+        candles: aggregate(&trades, interval_secs),
+        warnings: Vec::new(),
+    })
+}
+
+/// Absolute base and quote amounts moved by a `Swap` log, in human units.
+fn decode_swap(
+    log: &Log,
+    base_is_token0: bool,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Option<(Decimal, Decimal)> {
+    let data = log.data.as_ref();
+    if data.len() < 64 {
+        return None;
+    }
+    let amount0 = I256::from_raw(U256::from_big_endian(&data[..32])).unsigned_abs();
+    let amount1 = I256::from_raw(U256::from_big_endian(&data[32..64])).unsigned_abs();
+    let (base_raw, quote_raw) = if base_is_token0 {
+        (amount0, amount1)
+    } else {
+        (amount1, amount0)
+    };
+
+    let human = |raw: U256, decimals: u8| {
+        Decimal::from_str(&balance::format_with_decimals(&raw, decimals as u32)).ok()
+    };
+    Some((
+        human(base_raw, base_decimals)?,
+        human(quote_raw, quote_decimals)?,
+    ))
+}
+
+/// Bucket trades (in any order) into candles aligned to multiples of `interval_secs`.
+/// Intervals without trades are omitted.
+pub fn aggregate(trades: &[Trade], interval_secs: u64) -> Vec<CandleOut> {
+    let mut ordered: Vec<&Trade> = trades.iter().collect();
+    ordered.sort_by_key(|trade| trade.timestamp);
+
+    let mut candles: Vec<(u64, Candle)> = Vec::new();
+    for trade in ordered {
+        let open_time = trade.timestamp - trade.timestamp % interval_secs;
+        match candles.last_mut() {
+            Some((time, candle)) if *time == open_time => candle.push(trade),
+            _ => candles.push((open_time, Candle::open(trade))),
+        }
+    }
+
+    candles
+        .into_iter()
+        .map(|(open_time, candle)| candle.finish(open_time))
+        .collect()
+}
+
+struct Candle {
+    open: Decimal,
+    high: Decimal,
+    low: Decimal,
+    close: Decimal,
+    volume: Decimal,
+    quote_volume: Decimal,
+    trades: usize,
+}
+
+impl Candle {
+    fn open(trade: &Trade) -> Self {
+        Self {
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.base_volume,
+            quote_volume: trade.quote_volume,
+            trades: 1,
+        }
+    }
+
+    fn push(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume = self.volume.saturating_add(trade.base_volume);
+        self.quote_volume = self.quote_volume.saturating_add(trade.quote_volume);
+        self.trades += 1;
+    }
+
+    fn finish(self, open_time: u64) -> CandleOut {
+        let text = |value: Decimal| value.normalize().to_string();
+        CandleOut {
+            open_time,
+            open: text(self.open),
+            high: text(self.high),
+            low: text(self.low),
+            close: text(self.close),
+            volume: text(self.volume),
+            quote_volume: text(self.quote_volume),
+            trades: self.trades,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Bytes;
+
+    fn trade(timestamp: u64, price: i64, volume: i64) -> Trade {
+        Trade {
+            timestamp,
+            price: Decimal::from(price),
+            base_volume: Decimal::from(volume),
+            quote_volume: Decimal::from(price * volume),
+        }
+    }
+
+    #[test]
+    fn aggregates_trades_into_aligned_candles() {
+        let trades = vec![
+            trade(3_650, 2_010, 1),
+            trade(3_600, 2_000, 2),
+            trade(3_700, 1_990, 1),
+            trade(7_300, 2_050, 3),
+        ];
+        let candles = aggregate(&trades, 3_600);
+
+        assert_eq!(candles.len(), 2);
+        let first = &candles[0];
+        assert_eq!(first.open_time, 3_600);
+        assert_eq!(
+            (
+                first.open.as_str(),
+                first.high.as_str(),
+                first.low.as_str(),
+                first.close.as_str()
+            ),
+            ("2000", "2010", "1990", "1990")
+        );
+        assert_eq!(first.volume, "4");
+        assert_eq!(first.trades, 3);
+        assert_eq!(candles[1].open_time, 7_200);
+        assert_eq!(candles[1].quote_volume, "6150");
+    }
+
+    #[test]
+    fn decodes_swap_amounts_by_token_order() {
+        // token0 = USDC (6 decimals) paid in, token1 = WETH (18 decimals) paid out.
+        let mut data = Vec::new();
+        data.extend_from_slice(&{
+            let mut word = [0u8; 32];
+            U256::from(2_000_000_000u64).to_big_endian(&mut word);
+            word
+        });
+        data.extend_from_slice(&{
+            let mut word = [0u8; 32];
+            I256::from_raw(U256::exp10(18))
+                .wrapping_neg()
+                .into_raw()
+                .to_big_endian(&mut word);
+            word
+        });
+        let log = Log {
+            data: Bytes::from(data),
+            ..Default::default()
+        };
+
+        let (base, quote) = decode_swap(&log, false, 18, 6).unwrap();
+        assert_eq!(base, Decimal::ONE);
+        assert_eq!(quote, Decimal::from(2_000));
+    }
+}
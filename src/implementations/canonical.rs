@@ -0,0 +1,195 @@
+use std::str::FromStr;
+
+use ethers::types::Address;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use crate::{
+    error::{AppError, AppResult},
+    types::{TokenDeploymentOut, TokenDeploymentsOut},
+};
+
+const CANONICAL_JSON: &str = include_str!("../../config/canonical_tokens.json");
+
+/// The bundled cross-chain mapping, parsed once.
+pub static BUNDLED: Lazy<CanonicalTokens> = Lazy::new(|| {
+    CanonicalTokens::from_json(CANONICAL_JSON).expect("failed to parse canonical_tokens.json")
+});
+
+/// One asset's issuer-recognised deployments across chains, e.g. Circle's USDC on mainnet, Base
+/// and Arbitrum. Bridged variants (USDC.e) are different assets and are not listed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanonicalToken {
+    pub symbol: String,
+    pub deployments: Vec<Deployment>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct Deployment {
+    pub chain_id: u64,
+    #[serde(deserialize_with = "address")]
+    pub address: Address,
+    /// Per chain, since they differ (USDT has 18 on BNB Chain).
+    pub decimals: u8,
+}
+
+impl CanonicalToken {
+    pub fn on_chain(&self, chain_id: u64) -> Option<&Deployment> {
+        self.deployments
+            .iter()
+            .find(|deployment| deployment.chain_id == chain_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CanonicalTokens {
+    tokens: Vec<CanonicalToken>,
+}
+
+impl CanonicalTokens {
+    pub fn from_json(raw: &str) -> AppResult<Self> {
+        let tokens = serde_json::from_str(raw)
+            .map_err(|err| AppError::Config(format!("invalid canonical token mapping: {err}")))?;
+        Ok(Self { tokens })
+    }
+
+    /// The asset called `symbol`, case-insensitively.
+    pub fn by_symbol(&self, symbol: &str) -> Option<&CanonicalToken> {
+        self.tokens
+            .iter()
+            .find(|token| token.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// The asset deployed at `address` on `chain_id`, so balances on several chains can be
+    /// added up under one symbol.
+    pub fn by_address(&self, chain_id: u64, address: Address) -> Option<&CanonicalToken> {
+        self.tokens.iter().find(|token| {
+            token
+                .on_chain(chain_id)
+                .is_some_and(|deployment| deployment.address == address)
+        })
+    }
+
+    /// Where the asset at `address` on `from_chain` lives on `to_chain`, e.g. the destination
+    /// token of a bridge transfer.
+    pub fn counterpart(
+        &self,
+        from_chain: u64,
+        address: Address,
+        to_chain: u64,
+    ) -> Option<&Deployment> {
+        self.by_address(from_chain, address)?.on_chain(to_chain)
+    }
+
+    /// `token`'s deployments, only the one on `chain_id` when given; `active_chain` is marked.
+    pub fn deployments(
+        token: &CanonicalToken,
+        chain_id: Option<u64>,
+        active_chain: u64,
+    ) -> AppResult<TokenDeploymentsOut> {
+        let deployments: Vec<TokenDeploymentOut> = token
+            .deployments
+            .iter()
+            .filter(|deployment| chain_id.is_none_or(|chain_id| deployment.chain_id == chain_id))
+            .map(|deployment| TokenDeploymentOut {
+                chain_id: deployment.chain_id,
+                address: format!("{:#x}", deployment.address),
+                decimals: deployment.decimals,
+                active: deployment.chain_id == active_chain,
+            })
+            .collect();
+        if let Some(chain_id) = chain_id
+            && deployments.is_empty()
+        {
+            let chains: Vec<String> = token
+                .deployments
+                .iter()
+                .map(|deployment| deployment.chain_id.to_string())
+                .collect();
+            return Err(AppError::InvalidInput(format!(
+                "{} has no canonical deployment on chain {chain_id}; it is mapped on chains {}",
+                token.symbol,
+                chains.join(", ")
+            )));
+        }
+        Ok(TokenDeploymentsOut {
+            symbol: token.symbol.clone(),
+            chain_id: active_chain,
+            deployments,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+fn address<'de, D>(deserializer: D) -> Result<Address, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Address::from_str(&raw).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implementations::price::TokenRegistry;
+
+    #[test]
+    fn maps_usdc_across_chains() {
+        let mainnet: Address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+            .parse()
+            .unwrap();
+        let usdc = BUNDLED.by_address(1, mainnet).unwrap();
+        assert_eq!(usdc.symbol, "USDC");
+        assert_eq!(
+            BUNDLED.counterpart(1, mainnet, 8453).unwrap().address,
+            "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913"
+                .parse()
+                .unwrap()
+        );
+        // Mainnet's USDC address means nothing on Arbitrum.
+        assert!(BUNDLED.by_address(42_161, mainnet).is_none());
+        assert_eq!(
+            BUNDLED
+                .by_symbol("usdt")
+                .unwrap()
+                .on_chain(56)
+                .unwrap()
+                .decimals,
+            18
+        );
+
+        let out = CanonicalTokens::deployments(usdc, None, 42_161).unwrap();
+        assert!(out.deployments.len() >= 5);
+        assert_eq!(out.deployments.iter().filter(|d| d.active).count(), 1);
+        let err = CanonicalTokens::deployments(usdc, Some(56), 1).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("no canonical deployment on chain 56")
+        );
+    }
+
+    #[test]
+    fn bundled_mapping_is_consistent() {
+        let defaults = TokenRegistry::with_defaults();
+        for token in &BUNDLED.tokens {
+            for (index, deployment) in token.deployments.iter().enumerate() {
+                assert!(
+                    token.deployments[..index]
+                        .iter()
+                        .all(|seen| seen.chain_id != deployment.chain_id),
+                    "{} is mapped twice on chain {}",
+                    token.symbol,
+                    deployment.chain_id
+                );
+            }
+            // The mainnet entry must be the bundled default the registry resolves the symbol to.
+            if let (Some(deployment), Some(info)) =
+                (token.on_chain(1), defaults.info_by_symbol(&token.symbol))
+            {
+                assert_eq!(deployment.address, info.address, "{}", token.symbol);
+                assert_eq!(deployment.decimals, info.decimals, "{}", token.symbol);
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use ethers::{providers::Middleware, types::Chain};
+
+use crate::{
+    error::{AppError, AppResult},
+    types::ChainInfoOut,
+};
+
+/// Query `eth_chainId` and make sure the provider serves the chain the wallet is stamped with.
+pub async fn verify_chain_id<M>(provider: Arc<M>, expected: u64) -> AppResult<u64>
+where
+    M: Middleware + 'static,
+{
+    let actual = provider
+        .get_chainid()
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch chain id: {err}")))?
+        .as_u64();
+
+    if actual != expected {
+        return Err(AppError::Config(format!(
+            "provider chain id {actual} does not match configured default_chain_id {expected}"
+        )));
+    }
+
+    Ok(actual)
+}
+
+/// Re-verify the provider chain and report it together with the latest block.
+pub async fn resolve_chain_info<M>(
+    provider: Arc<M>,
+    verified_chain_id: u64,
+) -> AppResult<ChainInfoOut>
+where
+    M: Middleware + 'static,
+{
+    let chain_id = verify_chain_id(provider.clone(), verified_chain_id).await?;
+    let block_number = provider
+        .get_block_number()
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch block number: {err}")))?;
+
+    Ok(ChainInfoOut {
+        chain_id,
+        name: chain_name(chain_id),
+        block_number: block_number.as_u64(),
+        warnings: Vec::new(),
+    })
+}
+
+fn chain_name(chain_id: u64) -> String {
+    Chain::try_from(chain_id)
+        .map(|chain| chain.to_string())
+        .unwrap_or_else(|_| format!("chain-{chain_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    #[tokio::test]
+    async fn verify_chain_id_accepts_matching_chain() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<String, _>("0x1".to_string()).unwrap();
+
+        let chain_id = verify_chain_id(Arc::new(provider), 1).await.unwrap();
+        assert_eq!(chain_id, 1);
+    }
+
+    #[tokio::test]
+    async fn verify_chain_id_rejects_mismatch() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<String, _>("0xaa36a7".to_string()).unwrap(); // sepolia
+
+        let err = verify_chain_id(Arc::new(provider), 1).await.unwrap_err();
+        assert!(matches!(err, AppError::Config(msg) if msg.contains("11155111")));
+    }
+
+    #[tokio::test]
+    async fn resolve_chain_info_reports_block_and_name() {
+        let (provider, mock) = Provider::mocked();
+        // Responses are consumed in reverse order.
+        mock.push::<String, _>("0x1312d00".to_string()).unwrap(); // block 20_000_000
+        mock.push::<String, _>("0x1".to_string()).unwrap();
+
+        let info = resolve_chain_info(Arc::new(provider), 1).await.unwrap();
+        assert_eq!(info.chain_id, 1);
+        assert_eq!(info.name, "mainnet");
+        assert_eq!(info.block_number, 20_000_000);
+    }
+}
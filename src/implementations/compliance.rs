@@ -0,0 +1,326 @@
+use std::{collections::HashSet, fs, str::FromStr};
+
+use ethers::types::Address;
+#[cfg(feature = "sanctions-api")]
+use serde::Deserialize;
+use tracing::warn;
+
+#[cfg(feature = "sanctions-api")]
+use crate::secrets::SecretString;
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+};
+
+/// How long one sanctions lookup may take before the screened operation is refused.
+#[cfg(feature = "sanctions-api")]
+const SANCTIONS_API_TIMEOUT_SECS: u64 = 10;
+
+/// Chainalysis-style sanctions API: `GET {base_url}/{address}` returning identifications.
+#[cfg(feature = "sanctions-api")]
+#[derive(Debug, Clone)]
+struct SanctionsApi {
+    base_url: String,
+    api_key: Option<SecretString>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "sanctions-api")]
+#[derive(Debug, Deserialize)]
+struct SanctionsApiResponse {
+    #[serde(default)]
+    identifications: Vec<SanctionsIdentification>,
+}
+
+#[cfg(feature = "sanctions-api")]
+#[derive(Debug, Deserialize)]
+struct SanctionsIdentification {
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// A counterparty that matched the denylist or the sanctions API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplianceHit {
+    pub address: Address,
+    pub role: &'static str,
+    pub reason: String,
+}
+
+/// Screens counterparties (recipients, token contracts) before a transaction is built.
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceScreen {
+    denylist: HashSet<Address>,
+    #[cfg(feature = "sanctions-api")]
+    api: Option<SanctionsApi>,
+    allow_override: bool,
+}
+
+impl ComplianceScreen {
+    /// A screen with no sources configured; every check passes.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: &AppConfig) -> AppResult<Self> {
+        let denylist = match config.denylist_path.as_deref() {
+            Some(path) => {
+                let raw = fs::read_to_string(path).map_err(|err| {
+                    AppError::Config(format!("failed to read denylist {path}: {err}"))
+                })?;
+                parse_denylist(&raw)?
+            }
+            None => HashSet::new(),
+        };
+
+        #[cfg(not(feature = "sanctions-api"))]
+        if config.sanctions_api_url.is_some() {
+            return Err(AppError::Config(
+                "sanctions_api_url requires building with the `sanctions-api` feature".into(),
+            ));
+        }
+        #[cfg(feature = "sanctions-api")]
+        let api = config
+            .sanctions_api_url
+            .as_ref()
+            .map(|base_url| {
+                let client = reqwest::Client::builder()
+                    .timeout(std::time::Duration::from_secs(SANCTIONS_API_TIMEOUT_SECS))
+                    .build()
+                    .map_err(|err| {
+                        AppError::Config(format!("failed to build sanctions API client: {err}"))
+                    })?;
+                Ok::<_, AppError>(SanctionsApi {
+                    base_url: base_url.trim_end_matches('/').to_string(),
+                    api_key: config.sanctions_api_key.clone(),
+                    client,
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            denylist,
+            #[cfg(feature = "sanctions-api")]
+            api,
+            allow_override: config.allow_compliance_override,
+        })
+    }
+
+    pub fn with_denylist(mut self, addresses: impl IntoIterator<Item = Address>) -> Self {
+        self.denylist.extend(addresses);
+        self
+    }
+
+    pub fn with_override_allowed(mut self, allowed: bool) -> Self {
+        self.allow_override = allowed;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.denylist.is_empty() || self.has_api()
+    }
+
+    #[cfg(feature = "sanctions-api")]
+    fn has_api(&self) -> bool {
+        self.api.is_some()
+    }
+
+    #[cfg(not(feature = "sanctions-api"))]
+    fn has_api(&self) -> bool {
+        false
+    }
+
+    /// Screen `(role, address)` pairs and return every match.
+    pub async fn screen(
+        &self,
+        parties: &[(&'static str, Address)],
+    ) -> AppResult<Vec<ComplianceHit>> {
+        let mut hits = Vec::new();
+        for (role, address) in parties {
+            if self.denylist.contains(address) {
+                hits.push(ComplianceHit {
+                    address: *address,
+                    role,
+                    reason: "listed in local denylist".to_string(),
+                });
+                continue;
+            }
+            #[cfg(feature = "sanctions-api")]
+            if let Some(api) = &self.api
+                && let Some(reason) = api.check(*address).await?
+            {
+                hits.push(ComplianceHit {
+                    address: *address,
+                    role,
+                    reason,
+                });
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Fail on any hit unless the caller asked for an override and config permits one.
+    pub async fn enforce(
+        &self,
+        parties: &[(&'static str, Address)],
+        override_requested: bool,
+    ) -> AppResult<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let hits = self.screen(parties).await?;
+        if hits.is_empty() {
+            return Ok(());
+        }
+
+        let summary = hits
+            .iter()
+            .map(|hit| format!("{} {:#x} ({})", hit.role, hit.address, hit.reason))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if override_requested && self.allow_override {
+            warn!("compliance override used for flagged counterparties: {summary}");
+            return Ok(());
+        }
+        if override_requested {
+            return Err(AppError::Compliance(format!(
+                "flagged counterparties: {summary}; overrides are disabled by configuration"
+            )));
+        }
+        Err(AppError::Compliance(format!(
+            "flagged counterparties: {summary}"
+        )))
+    }
+}
+
+#[cfg(feature = "sanctions-api")]
+impl SanctionsApi {
+    async fn check(&self, address: Address) -> AppResult<Option<String>> {
+        let url = format!("{}/{address:#x}", self.base_url);
+        let mut request = self.client.get(&url);
+        if let Some(key) = &self.api_key {
+            request = request.header("X-API-Key", key.expose());
+        }
+
+        // Any failure refuses the operation: an unscreened counterparty is never let through.
+        let response = request
+            .send()
+            .await
+            .map_err(|err| {
+                if err.is_timeout() {
+                    AppError::Compliance(format!(
+                        "sanctions API did not answer within {SANCTIONS_API_TIMEOUT_SECS}s; refusing until {address:#x} can be screened"
+                    ))
+                } else {
+                    AppError::Compliance(format!("sanctions API request failed: {err}"))
+                }
+            })?
+            .error_for_status()
+            .map_err(|err| AppError::Compliance(format!("sanctions API error: {err}")))?
+            .json::<SanctionsApiResponse>()
+            .await
+            .map_err(|err| {
+                AppError::Compliance(format!("invalid sanctions API response: {err}"))
+            })?;
+
+        Ok(response.identifications.first().map(|id| {
+            let category = id.category.as_deref().unwrap_or("sanctions");
+            match &id.name {
+                Some(name) => format!("{category}: {name}"),
+                None => category.to_string(),
+            }
+        }))
+    }
+}
+
+/// Accept one address per line (with `#` comments) or a JSON array of addresses.
+fn parse_denylist(raw: &str) -> AppResult<HashSet<Address>> {
+    let entries: Vec<String> = if raw.trim_start().starts_with('[') {
+        serde_json::from_str(raw)
+            .map_err(|err| AppError::Config(format!("failed to parse denylist JSON: {err}")))?
+    } else {
+        raw.lines()
+            .map(|line| {
+                line.split('#')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            Address::from_str(entry)
+                .map_err(|_| AppError::Config(format!("invalid denylist address: {entry}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged() -> Address {
+        Address::from_low_u64_be(0xbad)
+    }
+
+    #[test]
+    fn parses_line_and_json_denylists() {
+        let lines = format!("# OFAC sample\n{:#x} # mixer\n\n", flagged());
+        assert!(parse_denylist(&lines).unwrap().contains(&flagged()));
+
+        let json = format!("[\"{:#x}\"]", flagged());
+        assert!(parse_denylist(&json).unwrap().contains(&flagged()));
+
+        assert!(parse_denylist("not-an-address").is_err());
+    }
+
+    #[tokio::test]
+    async fn disabled_screen_allows_everything() {
+        let screen = ComplianceScreen::disabled();
+        screen
+            .enforce(&[("recipient", flagged())], false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn denylisted_recipient_is_rejected() {
+        let screen = ComplianceScreen::disabled().with_denylist([flagged()]);
+        let err = screen
+            .enforce(
+                &[
+                    ("token", Address::from_low_u64_be(1)),
+                    ("recipient", flagged()),
+                ],
+                false,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Compliance(msg) if msg.contains("recipient")));
+    }
+
+    #[tokio::test]
+    async fn override_requires_config_permission() {
+        let screen = ComplianceScreen::disabled().with_denylist([flagged()]);
+        let err = screen
+            .enforce(&[("recipient", flagged())], true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Compliance(msg) if msg.contains("disabled")));
+
+        let screen = screen.with_override_allowed(true);
+        screen
+            .enforce(&[("recipient", flagged())], true)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, Filter, H256, Log, U256},
+};
+use tokio::time::{Instant, sleep};
+use tracing::debug;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, price::TokenRegistry},
+    progress::ProgressReporter,
+    types::{DepositOut, DepositWatchOut},
+};
+
+/// Widest block range inspected per poll; a long backlog is drained over several polls.
+pub const MAX_DEPOSIT_SCAN_BLOCKS: u64 = 200;
+
+pub const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+
+/// Poll new blocks for deposits into `wallet`, returning as soon as any arrive or when `window`
+/// elapses. Scanning starts at `from_block`, or at the next block when omitted. Each poll
+/// reports the seconds elapsed of the window to `progress`.
+pub async fn watch_deposits<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    wallet: Address,
+    from_block: Option<u64>,
+    window: Duration,
+    poll_interval: Duration,
+    progress: &ProgressReporter,
+) -> AppResult<DepositWatchOut>
+where
+    M: Middleware + 'static,
+{
+    let started = Instant::now();
+    let deadline = started + window;
+    let mut cursor = match from_block {
+        Some(number) => number,
+        None => head_block(&provider).await? + 1,
+    };
+    let first_block = cursor;
+
+    loop {
+        let head = head_block(&provider).await?;
+        let mut deposits = Vec::new();
+        while cursor <= head && deposits.is_empty() {
+            let to_block = head.min(cursor + MAX_DEPOSIT_SCAN_BLOCKS - 1);
+            deposits = scan_deposits(provider.clone(), registry, wallet, cursor, to_block).await?;
+            cursor = to_block + 1;
+        }
+        progress.report(
+            started.elapsed().as_secs(),
+            Some(window.as_secs()),
+            format!("scanned blocks {first_block}..={}", cursor - 1),
+        );
+
+        if !deposits.is_empty() || Instant::now() + poll_interval > deadline {
+            return Ok(DepositWatchOut {
+                wallet: format!("{wallet:#x}"),
+                from_block: first_block,
+                last_block: cursor - 1,
+                deposits,
+                warnings: Vec::new(),
+            });
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+/// Incoming ERC-20 transfers and ETH deposits to `wallet` in `[from_block, to_block]`.
+///
+/// Token deposits come from `Transfer` logs. ETH has no logs, so the wallet balance is compared
+/// across the range and blocks are only walked for senders when it went up; an increase with no
+/// matching direct transfer (e.g. a contract withdrawal) is reported without a sender.
+pub async fn scan_deposits<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    wallet: Address,
+    from_block: u64,
+    to_block: u64,
+) -> AppResult<Vec<DepositOut>>
+where
+    M: Middleware + 'static,
+{
+    let filter = Filter::new()
+        .event(TRANSFER_EVENT)
+        .topic2(H256::from(wallet))
+        .from_block(from_block)
+        .to_block(to_block);
+    let logs = provider
+        .get_logs(&filter)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch transfer logs: {err}")))?;
+    let mut deposits: Vec<DepositOut> = logs
+        .iter()
+        .filter_map(|log| decode_transfer(log, registry))
+        .collect();
+
+    let before = balance_at(&provider, wallet, from_block.saturating_sub(1)).await?;
+    let after = balance_at(&provider, wallet, to_block).await?;
+    if after > before {
+        let mut direct = U256::zero();
+        for number in from_block..=to_block {
+            let block = provider
+                .get_block_with_txs(number)
+                .await
+                .map_err(|err| AppError::Rpc(format!("failed to fetch block {number}: {err}")))?;
+            let Some(block) = block else {
+                continue;
+            };
+            for tx in block.transactions {
+                if tx.to == Some(wallet) && !tx.value.is_zero() {
+                    direct = direct.saturating_add(tx.value);
+                    deposits.push(eth_deposit(
+                        "eth",
+                        &registry.native().symbol,
+                        Some(tx.from),
+                        tx.value,
+                        number,
+                        Some(tx.hash),
+                    ));
+                }
+            }
+        }
+
+        // Without a direct transfer, the increase must have come from a contract call.
+        if direct.is_zero() {
+            let increase = after - before;
+            debug!("balance rose by {increase} wei without a direct transfer");
+            deposits.push(eth_deposit(
+                "eth_internal",
+                &registry.native().symbol,
+                None,
+                increase,
+                to_block,
+                None,
+            ));
+        }
+    }
+
+    deposits.sort_by_key(|deposit| deposit.block_number);
+    Ok(deposits)
+}
+
+fn decode_transfer(log: &Log, registry: &TokenRegistry) -> Option<DepositOut> {
+    // ERC-721 transfers share the signature but index the token id instead of carrying data.
+    if log.topics.len() != 3 || log.data.len() < 32 {
+        return None;
+    }
+    let from = Address::from(log.topics[1]);
+    let amount = U256::from_big_endian(&log.data[..32]);
+    let info = registry.info_by_address(log.address);
+
+    Some(DepositOut {
+        kind: "token".to_string(),
+        token: Some(format!("{:#x}", log.address)),
+        symbol: info.map(|info| info.symbol.clone()),
+        from: Some(format!("{from:#x}")),
+        amount_raw: amount.to_string(),
+        amount: info.map(|info| balance::format_with_decimals(&amount, info.decimals as u32)),
+        block_number: log
+            .block_number
+            .map(|number| number.as_u64())
+            .unwrap_or_default(),
+        tx_hash: log.transaction_hash.map(|hash| format!("{hash:#x}")),
+    })
+}
+
+fn eth_deposit(
+    kind: &str,
+    symbol: &str,
+    from: Option<Address>,
+    amount: U256,
+    block_number: u64,
+    tx_hash: Option<H256>,
+) -> DepositOut {
+    DepositOut {
+        kind: kind.to_string(),
+        token: None,
+        symbol: Some(symbol.to_string()),
+        from: from.map(|from| format!("{from:#x}")),
+        amount_raw: amount.to_string(),
+        amount: Some(balance::format_with_decimals(&amount, 18)),
+        block_number,
+        tx_hash: tx_hash.map(|hash| format!("{hash:#x}")),
+    }
+}
+
+pub async fn head_block<M: Middleware>(provider: &Arc<M>) -> AppResult<u64> {
+    provider
+        .get_block_number()
+        .await
+        .map(|number| number.as_u64())
+        .map_err(|err| AppError::Rpc(format!("failed to fetch block number: {err}")))
+}
+
+async fn balance_at<M: Middleware>(
+    provider: &Arc<M>,
+    wallet: Address,
+    block: u64,
+) -> AppResult<U256> {
+    provider
+        .get_balance(wallet, Some(BlockId::from(block)))
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch balance at block {block}: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::implementations::price::TokenInfo;
+    use ethers::{providers::Provider, types::Bytes, utils::keccak256};
+
+    #[tokio::test]
+    async fn scan_reports_token_transfers_into_wallet() {
+        let wallet = Address::from_low_u64_be(0xaa);
+        let sender = Address::from_low_u64_be(0xbb);
+        let usdc = Address::from_low_u64_be(0xcc);
+        let mut registry = TokenRegistry::new();
+        registry.add_token(TokenInfo::new("USDC", usdc, 6));
+
+        let mut amount = [0u8; 32];
+        U256::from(1_500_000u64).to_big_endian(&mut amount);
+        let log = Log {
+            address: usdc,
+            topics: vec![
+                H256::from(keccak256(TRANSFER_EVENT)),
+                sender.into(),
+                wallet.into(),
+            ],
+            data: Bytes::from(amount.to_vec()),
+            block_number: Some(101u64.into()),
+            ..Default::default()
+        };
+
+        let (provider, mock) = Provider::mocked();
+        // Responses are consumed in reverse order; an unchanged ETH balance skips the block walk.
+        mock.push::<U256, _>(U256::from(7u64)).unwrap(); // balance at to_block
+        mock.push::<U256, _>(U256::from(7u64)).unwrap(); // balance before from_block
+        mock.push::<Vec<Log>, _>(vec![log]).unwrap(); // eth_getLogs
+
+        let deposits = scan_deposits(Arc::new(provider), &registry, wallet, 100, 102)
+            .await
+            .unwrap();
+
+        assert_eq!(deposits.len(), 1);
+        let deposit = &deposits[0];
+        assert_eq!(deposit.kind, "token");
+        assert_eq!(deposit.symbol.as_deref(), Some("USDC"));
+        assert_eq!(deposit.amount.as_deref(), Some("1.5"));
+        assert_eq!(deposit.from, Some(format!("{sender:#x}")));
+        assert_eq!(deposit.block_number, 101);
+    }
+}
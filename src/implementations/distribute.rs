@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber, U256},
+};
+use ethers_contract::abigen;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        erc20::{self, Erc20Token},
+        plan::estimated_step,
+        swap::{self, SwapSettings, apply_gas_buffer},
+    },
+    types::{DistributionMode, DistributionOut, PlanStepOut},
+};
+
+pub const MAX_DISTRIBUTION_RECIPIENTS: usize = 200;
+/// Rough Disperse cost, used when the call cannot be estimated until its approval lands.
+const DISPERSE_BASE_GAS: u64 = 30_000;
+const DISPERSE_GAS_PER_RECIPIENT: u64 = 35_000;
+
+abigen!(
+    Disperse,
+    r#"[
+        function disperseToken(address token, address[] recipients, uint256[] values)
+    ]"#
+);
+
+/// Build the transactions paying every `(recipient, amount)` in `payouts` from `sender`.
+///
+/// `Transfers` sends one `transfer` per recipient with consecutive nonces; `Disperse` batches
+/// them into a single call to the configured Disperse contract, approving it first when needed.
+#[allow(clippy::too_many_arguments)]
+pub async fn plan_distribution<M>(
+    provider: Arc<M>,
+    sender: Address,
+    token: Address,
+    decimals: u8,
+    payouts: &[(Address, U256)],
+    mode: DistributionMode,
+    disperse: Option<Address>,
+    settings: SwapSettings,
+) -> AppResult<DistributionOut>
+where
+    M: Middleware + 'static,
+{
+    if payouts.is_empty() || payouts.len() > MAX_DISTRIBUTION_RECIPIENTS {
+        return Err(AppError::InvalidInput(format!(
+            "distributions need between 1 and {MAX_DISTRIBUTION_RECIPIENTS} recipients"
+        )));
+    }
+    if payouts.iter().any(|(_, amount)| amount.is_zero()) {
+        return Err(AppError::InvalidInput(
+            "every amount_wei must be greater than zero".into(),
+        ));
+    }
+    let disperse = match mode {
+        DistributionMode::Disperse => Some(disperse.ok_or_else(|| {
+            AppError::Config("disperse mode requires DISPERSE_ADDRESS / disperse_address".into())
+        })?),
+        DistributionMode::Transfers => None,
+    };
+    let total = payouts
+        .iter()
+        .try_fold(U256::zero(), |total, (_, amount)| {
+            total.checked_add(*amount)
+        })
+        .ok_or_else(|| AppError::InvalidInput("total amount overflows uint256".into()))?;
+
+    let first_nonce = provider
+        .get_transaction_count(sender, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch nonce: {err}")))?;
+    let sender_balance = erc20::fetch_balance_of(provider.clone(), token, sender).await?;
+    if sender_balance < total {
+        return Err(AppError::Wallet(format!(
+            "wallet holds {sender_balance} of {token:#x}, below the {total} to distribute"
+        )));
+    }
+
+    let erc20 = Erc20Token::new(token, provider.clone());
+    let mut steps = Vec::new();
+    match disperse {
+        None => {
+            for (recipient, amount) in payouts {
+                let description = format!(
+                    "transfer {} to {recipient:#x}",
+                    balance::format_with_decimals(amount, decimals as u32)
+                );
+                let calldata = erc20.transfer(*recipient, *amount).calldata();
+                steps.push(
+                    estimated_step(
+                        &provider,
+                        sender,
+                        "transfer",
+                        description,
+                        token,
+                        U256::zero(),
+                        calldata,
+                        &settings,
+                    )
+                    .await?,
+                );
+            }
+        }
+        Some(disperse) => {
+            let allowance =
+                erc20::fetch_allowance(provider.clone(), token, sender, disperse).await?;
+            if allowance < total {
+                let description = format!("approve Disperse to spend {total} of {token:#x}");
+                let calldata = erc20.approve(disperse, total).calldata();
+                steps.push(
+                    estimated_step(
+                        &provider,
+                        sender,
+                        "approve",
+                        description,
+                        token,
+                        U256::zero(),
+                        calldata,
+                        &settings,
+                    )
+                    .await?,
+                );
+            }
+
+            let (recipients, values): (Vec<Address>, Vec<U256>) = payouts.iter().copied().unzip();
+            let description = format!(
+                "disperse {total} of {token:#x} to {} recipients",
+                payouts.len()
+            );
+            let calldata = Disperse::new(disperse, provider.clone())
+                .disperse_token(token, recipients, values)
+                .calldata();
+            let step = if steps.is_empty() {
+                estimated_step(
+                    &provider,
+                    sender,
+                    "disperse",
+                    description,
+                    disperse,
+                    U256::zero(),
+                    calldata,
+                    &settings,
+                )
+                .await?
+            } else {
+                let calldata = calldata.ok_or_else(|| {
+                    AppError::Internal("failed to build disperse calldata".into())
+                })?;
+                let gas_estimate = U256::from(
+                    DISPERSE_BASE_GAS + DISPERSE_GAS_PER_RECIPIENT * payouts.len() as u64,
+                );
+                PlanStepOut {
+                    kind: "disperse".into(),
+                    description,
+                    to: format!("{disperse:#x}"),
+                    value_wei: "0".into(),
+                    calldata_hex: format!("0x{}", hex::encode(&calldata)),
+                    nonce: String::new(),
+                    gas_estimate: gas_estimate.to_string(),
+                    gas_limit: apply_gas_buffer(gas_estimate, settings.gas_buffer_percent)
+                        .to_string(),
+                    gas_estimate_source: "heuristic".into(),
+                    access_list: None,
+                }
+            };
+            steps.push(step);
+        }
+    }
+
+    let mut total_gas_estimate = U256::zero();
+    let mut total_gas_limit = U256::zero();
+    for (index, step) in steps.iter_mut().enumerate() {
+        step.nonce = (first_nonce + U256::from(index)).to_string();
+        total_gas_estimate += U256::from_dec_str(&step.gas_estimate).unwrap_or_default();
+        total_gas_limit += U256::from_dec_str(&step.gas_limit).unwrap_or_default();
+    }
+    let fees = swap::fee_preview(&provider, total_gas_estimate, total_gas_limit).await;
+
+    Ok(DistributionOut {
+        block_number: None,
+        token: format!("{token:#x}"),
+        mode,
+        recipients: payouts.len(),
+        total_amount_wei: total.to_string(),
+        total_amount: balance::format_with_decimals(&total, decimals as u32),
+        steps,
+        total_gas_estimate: total_gas_estimate.to_string(),
+        total_gas_limit: total_gas_limit.to_string(),
+        fees,
+        warnings: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{self, Token},
+        providers::Provider,
+    };
+
+    #[tokio::test]
+    async fn plans_one_transfer_per_recipient() {
+        let (provider, mock) = Provider::mocked();
+        let balance = abi::encode(&[Token::Uint(U256::from(10_000_000u64))]);
+
+        // Responses are consumed in reverse order; fee data is left unavailable.
+        mock.push::<U256, _>(U256::from(52_000u64)).unwrap(); // second transfer
+        mock.push::<U256, _>(U256::from(35_000u64)).unwrap(); // first transfer
+        mock.push::<String, _>(format!("0x{}", hex::encode(balance)))
+            .unwrap();
+        mock.push::<U256, _>(U256::from(3u64)).unwrap(); // nonce
+
+        let payouts = [
+            (Address::from_low_u64_be(0xa), U256::from(1_500_000u64)),
+            (Address::from_low_u64_be(0xb), U256::from(2_500_000u64)),
+        ];
+        let out = plan_distribution(
+            Arc::new(provider),
+            Address::from_low_u64_be(0x5),
+            Address::from_low_u64_be(0x7),
+            6,
+            &payouts,
+            DistributionMode::Transfers,
+            None,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.total_amount, "4");
+        assert_eq!(out.steps.len(), 2);
+        assert_eq!(out.steps[1].nonce, "4");
+        assert_eq!(out.total_gas_estimate, "87000");
+        assert!(out.fees.is_none());
+    }
+
+    #[tokio::test]
+    async fn disperse_mode_needs_a_contract() {
+        let (provider, _mock) = Provider::mocked();
+        let err = plan_distribution(
+            Arc::new(provider),
+            Address::from_low_u64_be(0x5),
+            Address::from_low_u64_be(0x7),
+            18,
+            &[(Address::from_low_u64_be(0xa), U256::one())],
+            DistributionMode::Disperse,
+            None,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AppError::Config(_)));
+    }
+}
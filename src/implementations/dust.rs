@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance, erc20,
+        gas_worth::{self, decimal},
+        plan::ROUTER_OVERHEAD_GAS,
+        price::{self, TokenInfo, TokenRegistry},
+        rebalance::usd,
+        swap::{self, SwapSettings, apply_gas_buffer, apply_slippage},
+        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+    },
+    types::{DustSkipOut, DustSwapOut, QuoteCurrency, SweepToOut},
+};
+
+/// Gas of an ERC-20 `approve` that sets an allowance from zero.
+const APPROVE_GAS: u64 = 46_000;
+const FULL_BPS: u32 = 10_000;
+/// `max_gas_bps` when neither the call nor `GAS_WORTHINESS_BPS` sets it.
+pub const DEFAULT_MAX_GAS_BPS: u32 = 2_500;
+
+/// Which balances `plan_sweep_to` counts as dust worth sweeping.
+#[derive(Debug, Clone)]
+pub struct DustFilter {
+    pub min_value_usd: Decimal,
+    pub max_value_usd: Option<Decimal>,
+    /// Most of a balance's value its gas may cost, in bps.
+    pub max_gas_bps: u32,
+}
+
+impl DustFilter {
+    /// Why a balance worth `value` USD is left alone, if it is.
+    fn out_of_range(&self, value: Decimal) -> Option<String> {
+        if value < self.min_value_usd {
+            return Some(format!(
+                "worth ${}, below min_value_usd ${}",
+                usd(value),
+                usd(self.min_value_usd)
+            ));
+        }
+        self.max_value_usd
+            .filter(|max| value > *max)
+            .map(|max| format!("worth ${}, above max_value_usd ${}", usd(value), usd(max)))
+    }
+
+    /// Why gas costing `gas_cost` USD is too much to sweep a balance worth `value` USD.
+    fn not_worth_gas(&self, value: Decimal, gas_cost: Decimal) -> Option<String> {
+        gas_worth::excess(value, gas_cost, self.max_gas_bps)
+    }
+}
+
+/// Plan swapping `owner`'s small balances of `tokens` into `target` on the `fee` pool.
+///
+/// Balances are valued in USD through the registry's price policy. One is swept whole when its
+/// value is within the filter's bounds and its gas, priced at the node's max fee and the native
+/// coin's USD price, stays within `max_gas_bps` of that value; gas includes an approval when the
+/// router's allowance is short. Other non-zero balances, and tokens that cannot be priced or
+/// quoted, are listed in `skipped`.
+#[allow(clippy::too_many_arguments)]
+pub async fn plan_sweep_to<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    owner: Address,
+    target: &TokenInfo,
+    tokens: &[&TokenInfo],
+    filter: &DustFilter,
+    slippage_bps: u32,
+    fee: u32,
+    settings: SwapSettings,
+) -> AppResult<SweepToOut>
+where
+    M: Middleware + 'static,
+{
+    if filter.max_gas_bps > FULL_BPS || slippage_bps > FULL_BPS {
+        return Err(AppError::InvalidInput(format!(
+            "max_gas_bps and slippage_bps cannot exceed {FULL_BPS}"
+        )));
+    }
+    let fees = provider
+        .estimate_eip1559_fees(None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to estimate EIP-1559 fees: {err}")))?;
+    let native_usd = gas_worth::native_usd_price(provider.clone(), registry).await?;
+    let quoter = UniswapQuoterV2::new(settings.uniswap.quoter, provider.clone());
+
+    let mut swaps = Vec::new();
+    let mut skipped = Vec::new();
+    for token in tokens {
+        if token.address == target.address {
+            continue;
+        }
+        let raw = erc20::fetch_balance_of(provider.clone(), token.address, owner).await?;
+        if raw.is_zero() {
+            continue;
+        }
+        let amount = balance::format_with_decimals(&raw, token.decimals as u32);
+        let skip = |value: Option<Decimal>, reason: String| DustSkipOut {
+            token: format!("{:#x}", token.address),
+            symbol: token.symbol.clone(),
+            balance: amount.clone(),
+            value_usd: value.map(usd),
+            reason,
+        };
+
+        let price = match price::resolve_token_price(
+            provider.clone(),
+            registry,
+            token.address,
+            QuoteCurrency::USD,
+        )
+        .await
+        {
+            Ok(price) => decimal(&price.price)?,
+            Err(err) => {
+                skipped.push(skip(None, format!("no USD price: {err}")));
+                continue;
+            }
+        };
+        let value = price * decimal(&amount)?;
+        if let Some(reason) = filter.out_of_range(value) {
+            skipped.push(skip(Some(value), reason));
+            continue;
+        }
+
+        let quote = quoter
+            .quote_exact_input_single(QuoteExactInputSingleParams {
+                token_in: token.address,
+                token_out: target.address,
+                amount_in: raw,
+                fee,
+                sqrt_price_limit_x96: U256::zero(),
+            })
+            .call()
+            .await;
+        let (amount_out, quoter_gas) = match quote {
+            Ok((amount_out, _, _, quoter_gas)) if !amount_out.is_zero() => (amount_out, quoter_gas),
+            Ok(_) => {
+                skipped.push(skip(Some(value), "quote returned zero output".into()));
+                continue;
+            }
+            Err(err) => {
+                skipped.push(skip(
+                    Some(value),
+                    format!(
+                        "no {}/{} quote at fee {fee}: {err}",
+                        token.symbol, target.symbol
+                    ),
+                ));
+                continue;
+            }
+        };
+
+        let allowance = erc20::fetch_allowance(
+            provider.clone(),
+            token.address,
+            owner,
+            settings.uniswap.router,
+        )
+        .await?;
+        let needs_approval = allowance < raw;
+        let mut gas_estimate = quoter_gas + U256::from(ROUTER_OVERHEAD_GAS);
+        if needs_approval {
+            gas_estimate += U256::from(APPROVE_GAS);
+        }
+        let gas_cost = gas_worth::gas_cost_usd(gas_estimate * fees.0, native_usd)?;
+        if let Some(reason) = filter.not_worth_gas(value, gas_cost) {
+            skipped.push(skip(Some(value), reason));
+            continue;
+        }
+
+        let target_decimals = target.decimals as u32;
+        swaps.push((
+            value,
+            amount_out,
+            gas_estimate,
+            DustSwapOut {
+                token: format!("{:#x}", token.address),
+                symbol: token.symbol.clone(),
+                amount_in_wei: raw.to_string(),
+                amount_in: amount,
+                value_usd: usd(value),
+                amount_out_estimate: balance::format_with_decimals(&amount_out, target_decimals),
+                amount_out_min: balance::format_with_decimals(
+                    &apply_slippage(amount_out, slippage_bps)?,
+                    target_decimals,
+                ),
+                gas_estimate: gas_estimate.to_string(),
+                gas_cost_usd: usd(gas_cost),
+                needs_approval,
+            },
+        ));
+    }
+    swaps.sort_by_key(|(value, ..)| std::cmp::Reverse(*value));
+
+    let total_value: Decimal = swaps.iter().map(|(value, ..)| *value).sum();
+    let total_amount_out = swaps
+        .iter()
+        .fold(U256::zero(), |total, (_, amount_out, ..)| {
+            total + amount_out
+        });
+    let total_gas_estimate = swaps
+        .iter()
+        .fold(U256::zero(), |total, (_, _, gas, _)| total + gas);
+    let fees = (!swaps.is_empty()).then(|| {
+        let total_gas_limit = apply_gas_buffer(total_gas_estimate, settings.gas_buffer_percent);
+        swap::fees_out(fees, total_gas_estimate, total_gas_limit)
+    });
+
+    Ok(SweepToOut {
+        block_number: None,
+        wallet: format!("{owner:#x}"),
+        target: target.symbol.clone(),
+        target_address: format!("{:#x}", target.address),
+        swaps: swaps.into_iter().map(|(.., swap)| swap).collect(),
+        skipped,
+        total_value_usd: usd(total_value),
+        total_amount_out_estimate: balance::format_with_decimals(
+            &total_amount_out,
+            target.decimals as u32,
+        ),
+        total_gas_estimate: total_gas_estimate.to_string(),
+        fees,
+        warnings: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> DustFilter {
+        DustFilter {
+            min_value_usd: Decimal::ONE,
+            max_value_usd: Some(Decimal::from(50)),
+            max_gas_bps: 2_500,
+        }
+    }
+
+    #[test]
+    fn dust_is_bounded_by_value_and_gas() {
+        let filter = filter();
+        assert!(filter.out_of_range(Decimal::from(10)).is_none());
+        assert_eq!(
+            filter.out_of_range(Decimal::new(5, 1)).unwrap(),
+            "worth $0.5, below min_value_usd $1"
+        );
+        assert!(filter.out_of_range(Decimal::from(51)).is_some());
+
+        assert!(
+            filter
+                .not_worth_gas(Decimal::from(10), Decimal::new(25, 1))
+                .is_none()
+        );
+        assert_eq!(
+            filter
+                .not_worth_gas(Decimal::from(10), Decimal::new(251, 2))
+                .unwrap(),
+            "gas of ~$2.51 is more than 25% of its $10 value"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use ethers::{providers::Middleware, types::Address};
+use tracing::debug;
+
+use crate::{
+    config::AppConfig,
+    implementations::retention::{self, DEFAULT_CACHE_MAX_ENTRIES},
+    types::MaintenanceItemOut,
+};
+
+pub const DEFAULT_ENS_CACHE_TTL_SECS: u64 = 3_600;
+
+/// Reverse-resolved ENS primary names, cached per address for `ttl_secs`.
+///
+/// Misses are cached too, so addresses without a primary name cost one lookup per TTL. Past
+/// `max_entries` the least recently used name is evicted.
+#[derive(Debug)]
+pub struct EnsNames {
+    enabled: bool,
+    ttl_secs: u64,
+    max_entries: usize,
+    cache: Mutex<EnsCache>,
+}
+
+#[derive(Debug, Default)]
+struct EnsCache {
+    /// Name (or miss) and when it was fetched.
+    entries: HashMap<Address, (Option<String>, u64)>,
+    last_used: HashMap<Address, u64>,
+    tick: u64,
+}
+
+impl Default for EnsNames {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 0,
+            max_entries: DEFAULT_CACHE_MAX_ENTRIES,
+            cache: Mutex::default(),
+        }
+    }
+}
+
+impl EnsNames {
+    pub fn new(ttl_secs: u64, max_entries: usize) -> Self {
+        Self {
+            enabled: true,
+            ttl_secs,
+            max_entries: max_entries.max(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        if config.ens_reverse_lookup {
+            Self::new(config.ens_cache_ttl_secs, config.cache_max_entries)
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Drop expired names; returns how many went.
+    pub fn prune(&self, now: u64) -> usize {
+        let mut cache = self.cache.lock().expect("ens cache lock poisoned");
+        let EnsCache {
+            entries, last_used, ..
+        } = &mut *cache;
+        let before = entries.len();
+        entries.retain(|_, (_, fetched_at)| *fetched_at + self.ttl_secs > now);
+        last_used.retain(|address, _| entries.contains_key(address));
+        before - entries.len()
+    }
+
+    pub fn status(&self, removed: usize) -> MaintenanceItemOut {
+        let cache = self.cache.lock().expect("ens cache lock poisoned");
+        MaintenanceItemOut::cache("ens_names", cache.entries.len(), self.max_entries, removed)
+    }
+
+    /// Primary name of `address`, or `None` when disabled, unset or the lookup fails.
+    pub async fn name<M: Middleware>(
+        &self,
+        provider: &M,
+        address: Address,
+        now: u64,
+    ) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(cached) = self.cached(address, now) {
+            return cached;
+        }
+        // `lookup_address` also checks the forward record, so spoofed reverse records are ignored.
+        let name = match provider.lookup_address(address).await {
+            Ok(name) => Some(name),
+            Err(err) => {
+                debug!("no ENS name for {address:#x}: {err}");
+                None
+            }
+        };
+        self.insert(address, name.clone(), now);
+        name
+    }
+
+    fn cached(&self, address: Address, now: u64) -> Option<Option<String>> {
+        let mut cache = self.cache.lock().expect("ens cache lock poisoned");
+        let name = cache
+            .entries
+            .get(&address)
+            .filter(|(_, fetched_at)| fetched_at + self.ttl_secs > now)
+            .map(|(name, _)| name.clone())?;
+        cache.tick += 1;
+        let tick = cache.tick;
+        cache.last_used.insert(address, tick);
+        Some(name)
+    }
+
+    fn insert(&self, address: Address, name: Option<String>, now: u64) {
+        let mut cache = self.cache.lock().expect("ens cache lock poisoned");
+        cache.tick += 1;
+        let tick = cache.tick;
+        cache.entries.insert(address, (name, now));
+        cache.last_used.insert(address, tick);
+        for evicted in retention::evict_lru(&mut cache.last_used, self.max_entries) {
+            cache.entries.remove(&evicted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    #[tokio::test]
+    async fn cached_names_expire_after_ttl() {
+        let (provider, _mock) = Provider::mocked();
+        let names = EnsNames::new(60, 10);
+        let address = Address::from_low_u64_be(0xd);
+        names.insert(address, Some("vitalik.eth".into()), 1_000);
+
+        assert_eq!(
+            names.name(&provider, address, 1_059).await.as_deref(),
+            Some("vitalik.eth")
+        );
+        // Expired, and the mock has no response queued, so the refresh misses.
+        assert_eq!(names.name(&provider, address, 1_060).await, None);
+        assert_eq!(names.cached(address, 1_061), Some(None));
+    }
+
+    #[test]
+    fn cache_keeps_the_most_recently_used_names() {
+        let names = EnsNames::new(60, 2);
+        let (a, b, c) = (
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            Address::from_low_u64_be(3),
+        );
+        names.insert(a, Some("a.eth".into()), 1_000);
+        names.insert(b, None, 1_000);
+        assert!(names.cached(a, 1_001).is_some());
+        names.insert(c, Some("c.eth".into()), 1_030);
+        assert_eq!(names.cached(b, 1_031), None);
+        assert!(names.cached(a, 1_031).is_some());
+
+        // a and b were fetched at 1_000 and expire at 1_060; c lasts until 1_090.
+        assert_eq!(names.prune(1_060), 1);
+        assert_eq!(names.status(1).entries, 1);
+    }
+
+    #[tokio::test]
+    async fn disabled_never_looks_up() {
+        let (provider, _mock) = Provider::mocked();
+        let names = EnsNames::default();
+        assert_eq!(names.name(&provider, Address::zero(), 0).await, None);
+        assert_eq!(names.cached(Address::zero(), 0), None);
+    }
+}
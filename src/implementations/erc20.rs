@@ -14,6 +14,17 @@ abigen!(
         function balanceOf(address) view returns (uint256)
         function decimals() view returns (uint8)
         function symbol() view returns (string)
+        function allowance(address owner, address spender) view returns (uint256)
+        function approve(address spender, uint256 amount) returns (bool)
+        function transfer(address to, uint256 amount) returns (bool)
+        function transferFrom(address from, address to, uint256 amount) returns (bool)
+    ]"#
+);
+
+abigen!(
+    Weth9,
+    r#"[
+        function deposit() payable
     ]"#
 );
 
@@ -57,3 +68,20 @@ where
         .await
         .map_err(|err| AppError::Rpc(format!("failed to fetch token balance: {err}")))
 }
+
+pub async fn fetch_allowance<M>(
+    provider: Arc<M>,
+    token: Address,
+    owner: Address,
+    spender: Address,
+) -> AppResult<U256>
+where
+    M: Middleware + 'static,
+{
+    let contract = Erc20Token::new(token, provider);
+    contract
+        .allowance(owner, spender)
+        .call()
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch token allowance: {err}")))
+}
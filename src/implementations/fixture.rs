@@ -0,0 +1,277 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    str::FromStr,
+    sync::Mutex,
+};
+
+use ethers::providers::JsonRpcError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+};
+
+/// Code returned when a replayed session asks for something that was never recorded.
+const NOT_RECORDED: i64 = -32_000;
+
+/// What [`RpcFixture`] does with the JSON-RPC traffic passing through it.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FixtureMode {
+    /// Forward to the endpoints and append every answer to the fixture file.
+    Record,
+    /// Answer from the fixture file without touching the network.
+    Replay,
+}
+
+impl FromStr for FixtureMode {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "record" => Ok(FixtureMode::Record),
+            "replay" => Ok(FixtureMode::Replay),
+            other => Err(AppError::Config(format!(
+                "unknown RPC fixture mode: {other}"
+            ))),
+        }
+    }
+}
+
+/// One recorded exchange, stored as a line of the fixture file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FixtureEntry {
+    method: String,
+    params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RecordedError>,
+}
+
+/// Serializable copy of a node's [`JsonRpcError`]; reverts keep their data for replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedError {
+    code: i64,
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+#[derive(Debug)]
+struct Replay {
+    entries: Vec<FixtureEntry>,
+    served: Vec<bool>,
+}
+
+/// Record-and-replay of a session's JSON-RPC traffic, for offline demos, downstream CI and
+/// reproducible bug reports. The fixture is JSON Lines: `{ method, params, result | error }`.
+#[derive(Debug)]
+pub struct RpcFixture(State);
+
+#[derive(Debug)]
+enum State {
+    Recording(Mutex<File>),
+    Replaying(Mutex<Replay>),
+}
+
+impl RpcFixture {
+    /// `None` unless `rpc_fixture_mode` is set; the mode needs `rpc_fixture_path`.
+    pub fn from_config(config: &AppConfig) -> AppResult<Option<Self>> {
+        let Some(mode) = config.rpc_fixture_mode else {
+            return Ok(None);
+        };
+        let path = config
+            .rpc_fixture_path
+            .as_deref()
+            .ok_or_else(|| AppError::Config("rpc_fixture_mode requires rpc_fixture_path".into()))?;
+        let fixture = match mode {
+            FixtureMode::Record => Self::record(path)?,
+            FixtureMode::Replay => {
+                let raw = fs::read_to_string(path).map_err(|err| {
+                    AppError::Config(format!("failed to read RPC fixture {path}: {err}"))
+                })?;
+                Self::replay(&raw)?
+            }
+        };
+        Ok(Some(fixture))
+    }
+
+    /// Start a fresh recording at `path`, replacing any earlier one.
+    pub fn record(path: &str) -> AppResult<Self> {
+        let file = File::create(path).map_err(|err| {
+            AppError::Config(format!("failed to create RPC fixture {path}: {err}"))
+        })?;
+        Ok(Self(State::Recording(Mutex::new(file))))
+    }
+
+    /// Serve the exchanges in `raw`, a recorded fixture.
+    pub fn replay(raw: &str) -> AppResult<Self> {
+        let entries = raw
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_str::<FixtureEntry>(line).map_err(|err| {
+                    AppError::Config(format!("invalid RPC fixture line {}: {err}", index + 1))
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        let served = vec![false; entries.len()];
+        Ok(Self(State::Replaying(Mutex::new(Replay {
+            entries,
+            served,
+        }))))
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.0, State::Replaying(_))
+    }
+
+    /// Append one exchange to a recording. A failed write only loses that line.
+    pub fn record_exchange(
+        &self,
+        method: &str,
+        params: &Value,
+        outcome: Result<&Value, &JsonRpcError>,
+    ) {
+        let State::Recording(file) = &self.0 else {
+            return;
+        };
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result.clone()), None),
+            Err(err) => (
+                None,
+                Some(RecordedError {
+                    code: err.code,
+                    message: err.message.clone(),
+                    data: err.data.clone(),
+                }),
+            ),
+        };
+        let entry = FixtureEntry {
+            method: method.to_string(),
+            params: params.clone(),
+            result,
+            error,
+        };
+        let written = serde_json::to_string(&entry).map(|line| {
+            let mut file = file.lock().expect("fixture lock poisoned");
+            writeln!(file, "{line}").and_then(|_| file.flush())
+        });
+        if !matches!(written, Ok(Ok(()))) {
+            warn!("failed to record {method} in the RPC fixture");
+        }
+    }
+
+    /// Recorded answer for a request, matched in order of preference:
+    /// 1. the next unserved exchange with the same method and params;
+    /// 2. the next unserved exchange with the same method, for params that drift between runs
+    ///    (swap deadlines, for one);
+    /// 3. the last exchange with the same method and params again, for repeated reads.
+    pub fn serve(&self, method: &str, params: &Value) -> Result<Value, JsonRpcError> {
+        let State::Replaying(replay) = &self.0 else {
+            return Err(not_recorded(method));
+        };
+        let mut replay = replay.lock().expect("fixture lock poisoned");
+        let Replay { entries, served } = &mut *replay;
+        let same_method = |index: &usize| entries[*index].method == method;
+        let same_request = |index: &usize| same_method(index) && entries[*index].params == *params;
+
+        let index = (0..entries.len())
+            .filter(|index| !served[*index])
+            .find(same_request)
+            .or_else(|| {
+                (0..entries.len())
+                    .filter(|index| !served[*index])
+                    .find(same_method)
+            })
+            .or_else(|| (0..entries.len()).rev().find(same_request))
+            .ok_or_else(|| not_recorded(method))?;
+        served[index] = true;
+
+        let entry = &entries[index];
+        match (&entry.result, &entry.error) {
+            (_, Some(error)) => Err(JsonRpcError {
+                code: error.code,
+                message: error.message.clone(),
+                data: error.data.clone(),
+            }),
+            (Some(result), None) => Ok(result.clone()),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
+
+fn not_recorded(method: &str) -> JsonRpcError {
+    JsonRpcError {
+        code: NOT_RECORDED,
+        message: format!("no recorded response for {method} in the RPC fixture"),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn replays_exact_then_same_method_then_repeats() {
+        let raw = [
+            json!({ "method": "eth_blockNumber", "params": [], "result": "0x10" }),
+            json!({ "method": "eth_call", "params": [{ "data": "0x01" }, "0x10"], "result": "0xaa" }),
+            json!({ "method": "eth_call", "params": [{ "data": "0x02" }, "0x10"], "result": "0xbb" }),
+            json!({
+                "method": "eth_estimateGas",
+                "params": [{ "data": "0x03" }],
+                "error": { "code": 3, "message": "execution reverted", "data": "0x08c379a0" },
+            }),
+        ]
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+        let fixture = RpcFixture::replay(&raw).unwrap();
+        assert!(fixture.is_replay());
+
+        let second = json!([{ "data": "0x02" }, "0x10"]);
+        assert_eq!(fixture.serve("eth_call", &second).unwrap(), json!("0xbb"));
+        let drifted = json!([{ "data": "0x09" }, "0x10"]);
+        assert_eq!(fixture.serve("eth_call", &drifted).unwrap(), json!("0xaa"));
+        assert_eq!(fixture.serve("eth_call", &second).unwrap(), json!("0xbb"));
+
+        let err = fixture
+            .serve("eth_estimateGas", &json!([{ "data": "0x04" }]))
+            .unwrap_err();
+        assert_eq!((err.code, err.data), (3, Some(json!("0x08c379a0"))));
+        let missing = fixture.serve("eth_getLogs", &json!([])).unwrap_err();
+        assert_eq!(missing.code, NOT_RECORDED);
+    }
+
+    #[test]
+    fn recordings_replay() {
+        let path = std::env::temp_dir().join(format!("rpc-fixture-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let recorder = RpcFixture::record(path).unwrap();
+        recorder.record_exchange("eth_chainId", &json!([]), Ok(&json!("0x1")));
+        let revert = JsonRpcError {
+            code: 3,
+            message: "execution reverted".into(),
+            data: None,
+        };
+        recorder.record_exchange("eth_call", &json!([{}]), Err(&revert));
+
+        let replay = RpcFixture::replay(&fs::read_to_string(path).unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(
+            replay.serve("eth_chainId", &json!([])).unwrap(),
+            json!("0x1")
+        );
+        assert_eq!(replay.serve("eth_call", &json!([{}])).unwrap_err().code, 3);
+    }
+}
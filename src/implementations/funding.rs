@@ -0,0 +1,351 @@
+use ethers::{
+    abi::AbiDecode,
+    types::{Address, Bytes, Signature, U256, transaction::eip2718::TypedTransaction},
+    utils::{rlp::Rlp, to_checksum},
+};
+use qrcode::{QrCode, render::unicode::Dense1x2};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, erc20::TransferCall, validation},
+};
+
+/// Where a [`Payment`] was decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentSource {
+    /// An EIP-681 `ethereum:` URI.
+    Uri,
+    /// A signed raw transaction.
+    RawTransaction,
+}
+
+/// A payment request or signed transaction decoded into who gets paid what.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub source: PaymentSource,
+    pub chain_id: Option<u64>,
+    /// Whoever receives the funds; for a token payment, the `transfer` recipient.
+    pub recipient: Address,
+    /// ERC-20 contract, for a token payment.
+    pub token: Option<Address>,
+    /// Base units, wei for the native coin; `None` when the request leaves it to the payer.
+    pub amount: Option<U256>,
+    pub gas_limit: Option<U256>,
+    /// Signer and nonce of a raw transaction.
+    pub from: Option<Address>,
+    pub nonce: Option<U256>,
+    /// Calldata of a raw transaction that is neither a plain transfer nor an ERC-20 `transfer`.
+    pub calldata: Option<Bytes>,
+}
+
+/// EIP-681 request to pay `recipient` on `chain_id`: a plain transfer of the native coin, or an
+/// ERC-20 `transfer` on `token`. `amount` is in base units, wei for the native coin.
+pub fn payment_uri(
+    recipient: Address,
+    chain_id: u64,
+    token: Option<Address>,
+    amount: Option<U256>,
+) -> String {
+    let recipient = to_checksum(&recipient, None);
+    match token {
+        None => {
+            let value = amount
+                .map(|amount| format!("?value={amount}"))
+                .unwrap_or_default();
+            format!("ethereum:{recipient}@{chain_id}{value}")
+        }
+        Some(token) => {
+            let amount = amount
+                .map(|amount| format!("&uint256={amount}"))
+                .unwrap_or_default();
+            format!(
+                "ethereum:{}@{chain_id}/transfer?address={recipient}{amount}",
+                to_checksum(&token, None)
+            )
+        }
+    }
+}
+
+/// Decode an EIP-681 `ethereum:` URI or a `0x` signed raw transaction. URIs may pay the native
+/// coin (`value`) or call ERC-20 `transfer` (`address`, `uint256`); numbers may use exponents
+/// such as `2.014e18`. ENS targets are refused rather than resolved.
+pub fn parse_payment_request(input: &str) -> AppResult<Payment> {
+    let input = input.trim();
+    if let Some(uri) = input.strip_prefix("ethereum:") {
+        parse_uri(uri)
+    } else if input.starts_with("0x") {
+        parse_raw_transaction(input)
+    } else {
+        Err(AppError::InvalidInput(
+            "expected an ethereum: payment URI or a 0x signed transaction".into(),
+        ))
+    }
+}
+
+fn parse_uri(uri: &str) -> AppResult<Payment> {
+    let uri = uri.strip_prefix("pay-").unwrap_or(uri);
+    let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let (target, function) = match path.split_once('/') {
+        Some((target, function)) => (target, Some(function)),
+        None => (path, None),
+    };
+    let (target, chain_id) = match target.split_once('@') {
+        Some((target, chain_id)) => {
+            let chain_id = u64::try_from(parse_uint("chain id", chain_id)?).map_err(|_| {
+                AppError::InvalidInput(format!("chain id {chain_id} is out of range"))
+            })?;
+            (target, Some(chain_id))
+        }
+        None => (target, None),
+    };
+    let target = parse_uri_address("target", target)?;
+    let params: Vec<(&str, &str)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .collect();
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    };
+    let gas_limit = param("gasLimit")
+        .or_else(|| param("gas"))
+        .map(|gas| parse_uint("gas limit", gas))
+        .transpose()?;
+
+    let (recipient, token, amount) = match function {
+        None => {
+            let value = param("value").map(|value| parse_uint("value", value));
+            (target, None, value.transpose()?)
+        }
+        Some("transfer") => {
+            let recipient = param("address").ok_or_else(|| {
+                AppError::InvalidInput("transfer request without an address parameter".into())
+            })?;
+            let amount = param("uint256").map(|amount| parse_uint("uint256", amount));
+            (
+                parse_uri_address("address", recipient)?,
+                Some(target),
+                amount.transpose()?,
+            )
+        }
+        Some(function) => {
+            return Err(AppError::InvalidInput(format!(
+                "unsupported payment function {function}; only native payments and ERC-20 transfer are decoded"
+            )));
+        }
+    };
+    Ok(Payment {
+        source: PaymentSource::Uri,
+        chain_id,
+        recipient,
+        token,
+        amount,
+        gas_limit,
+        from: None,
+        nonce: None,
+        calldata: None,
+    })
+}
+
+fn parse_raw_transaction(hex_tx: &str) -> AppResult<Payment> {
+    let raw = hex::decode(&hex_tx[2..])
+        .map_err(|err| AppError::InvalidInput(format!("invalid transaction hex: {err}")))?;
+    let (tx, signature): (TypedTransaction, Signature) =
+        TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .map_err(|err| AppError::InvalidInput(format!("not a signed transaction: {err}")))?;
+    let from = signature.recover(tx.sighash()).ok();
+    let to = tx.to_addr().copied().ok_or_else(|| {
+        AppError::InvalidInput("the transaction deploys a contract; it pays no one".into())
+    })?;
+    let data = tx.data().cloned().unwrap_or_default();
+    let transfer = TransferCall::decode(&data).ok();
+    let (recipient, token, amount, calldata) = match transfer {
+        Some(call) if tx.value().is_none_or(|value| value.is_zero()) => {
+            (call.to, Some(to), Some(call.amount), None)
+        }
+        _ => (
+            to,
+            None,
+            Some(tx.value().copied().unwrap_or_default()),
+            (!data.is_empty()).then_some(data),
+        ),
+    };
+    Ok(Payment {
+        source: PaymentSource::RawTransaction,
+        chain_id: tx.chain_id().map(|id| id.as_u64()),
+        recipient,
+        token,
+        amount,
+        gas_limit: tx.gas().copied(),
+        from,
+        nonce: tx.nonce().copied(),
+        calldata,
+    })
+}
+
+fn parse_uri_address(name: &str, value: &str) -> AppResult<Address> {
+    if value.contains('.') {
+        return Err(AppError::InvalidInput(format!(
+            "{name} {value} is an ENS name; ask for the 0x address instead"
+        )));
+    }
+    value.parse().map_err(|_| {
+        AppError::InvalidInput(format!(
+            "invalid {name} address: {value}{}",
+            validation::explain(value, [])
+        ))
+    })
+}
+
+/// An EIP-681 number: decimal digits with an optional fraction and `e` exponent, or `0x` hex.
+/// The result must be a whole number.
+fn parse_uint(name: &str, value: &str) -> AppResult<U256> {
+    let invalid = || AppError::InvalidInput(format!("invalid {name}: {value}"));
+    if let Some(hex) = value.strip_prefix("0x") {
+        return U256::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+    let (mantissa, exponent) = match value.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<u32>().map_err(|_| invalid())?),
+        None => (value, 0),
+    };
+    balance::parse_formatted(mantissa, exponent).map_err(|_| invalid())
+}
+
+/// `data` as a QR code drawn with half-height block characters, two modules per line, quiet
+/// zone included. Dark modules are drawn, so it scans on a light background.
+pub fn qr_text(data: &str) -> AppResult<String> {
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|err| AppError::Internal(format!("failed to encode QR code: {err}")))?;
+    Ok(code.render::<Dense1x2>().build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet() -> Address {
+        "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn builds_native_and_token_uris() {
+        assert_eq!(
+            payment_uri(wallet(), 1, None, None),
+            "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@1"
+        );
+        assert_eq!(
+            payment_uri(wallet(), 137, None, Some(U256::exp10(18))),
+            "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@137?value=1000000000000000000"
+        );
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            payment_uri(wallet(), 1, Some(usdc), Some(U256::from(5_000_000u64))),
+            "ethereum:0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48@1/transfer\
+             ?address=0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359&uint256=5000000"
+        );
+    }
+
+    #[test]
+    fn parses_native_and_token_uris() {
+        let usdc: Address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+            .parse()
+            .unwrap();
+        let built = payment_uri(wallet(), 1, Some(usdc), Some(U256::from(5_000_000u64)));
+        let payment = parse_payment_request(&built).unwrap();
+        assert_eq!(payment.source, PaymentSource::Uri);
+        assert_eq!(payment.chain_id, Some(1));
+        assert_eq!(
+            (payment.recipient, payment.token, payment.amount),
+            (wallet(), Some(usdc), Some(U256::from(5_000_000u64)))
+        );
+
+        let payment = parse_payment_request(
+            "ethereum:pay-0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@137?value=2.014e18&gas=21000",
+        )
+        .unwrap();
+        assert_eq!(payment.chain_id, Some(137));
+        assert_eq!(payment.token, None);
+        assert_eq!(
+            payment.amount,
+            Some(U256::from(2_014_000_000_000_000_000u64))
+        );
+        assert_eq!(payment.gas_limit, Some(U256::from(21_000u64)));
+
+        let open_amount =
+            parse_payment_request("ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359").unwrap();
+        assert_eq!((open_amount.chain_id, open_amount.amount), (None, None));
+
+        for (request, error) in [
+            ("ethereum:vitalik.eth?value=1e18", "ENS name"),
+            (
+                "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359?value=1.5",
+                "invalid value",
+            ),
+            (
+                "ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359/approve?address=0x1",
+                "unsupported payment function approve",
+            ),
+            ("https://example.com", "expected an ethereum: payment URI"),
+        ] {
+            let err = parse_payment_request(request).unwrap_err().to_string();
+            assert!(err.contains(error), "{request}: {err}");
+        }
+    }
+
+    #[test]
+    fn decodes_signed_transfers() {
+        use ethers::{
+            abi::AbiEncode,
+            signers::{LocalWallet, Signer},
+            types::TransactionRequest,
+        };
+
+        let signer: LocalWallet =
+            "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+                .parse()
+                .unwrap();
+        let token = Address::from_low_u64_be(0xc0);
+        let call = TransferCall {
+            to: wallet(),
+            amount: U256::from(42u64),
+        };
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(token)
+            .data(call.encode())
+            .nonce(3u64)
+            .gas(60_000u64)
+            .gas_price(1u64)
+            .chain_id(1u64)
+            .into();
+        let signature = signer.sign_transaction_sync(&tx).unwrap();
+        let raw = format!("0x{}", hex::encode(tx.rlp_signed(&signature)));
+
+        let payment = parse_payment_request(&raw).unwrap();
+        assert_eq!(payment.source, PaymentSource::RawTransaction);
+        assert_eq!(payment.from, Some(signer.address()));
+        assert_eq!(
+            (payment.recipient, payment.token, payment.amount),
+            (wallet(), Some(token), Some(U256::from(42u64)))
+        );
+        assert_eq!(payment.nonce, Some(U256::from(3u64)));
+        assert_eq!(payment.calldata, None);
+    }
+
+    #[test]
+    fn renders_a_square_qr_code() {
+        let qr = qr_text("ethereum:0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359@1").unwrap();
+        let lines: Vec<&str> = qr.lines().collect();
+        let width = lines[0].chars().count();
+        // Two modules per line, so half as many lines as columns, rounded up.
+        assert_eq!(lines.len(), width.div_ceil(2));
+        assert!(lines.iter().all(|line| line.chars().count() == width));
+        assert!(qr.contains('█'));
+    }
+}
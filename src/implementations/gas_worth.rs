@@ -0,0 +1,175 @@
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        price::{self, TokenRegistry},
+        rebalance::usd,
+        warnings,
+    },
+    types::{QuoteCurrency, WarningOut},
+};
+
+const FULL_BPS: u32 = 10_000;
+
+/// What a swap does when its gas costs more than the configured share of its output value.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GasWorthinessMode {
+    /// Return the result with a `gas_exceeds_value` warning.
+    #[default]
+    Warn,
+    /// Fail with a swap error.
+    Refuse,
+}
+
+impl FromStr for GasWorthinessMode {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "warn" => Ok(GasWorthinessMode::Warn),
+            "refuse" => Ok(GasWorthinessMode::Refuse),
+            other => Err(AppError::Config(format!(
+                "unknown gas worthiness mode: {other}"
+            ))),
+        }
+    }
+}
+
+/// The `GAS_WORTHINESS_BPS` check applied to swaps and swap plans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasPolicy {
+    pub max_gas_bps: u32,
+    pub mode: GasWorthinessMode,
+}
+
+impl GasPolicy {
+    /// `None` unless `gas_worthiness_bps` is set.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        config.gas_worthiness_bps.map(|max_gas_bps| Self {
+            max_gas_bps,
+            mode: config.gas_worthiness_mode,
+        })
+    }
+
+    /// Compare `operation`'s gas cost to its value, both in USD: a warning, or an error when
+    /// the policy refuses.
+    pub fn check(
+        &self,
+        operation: &str,
+        value_usd: Decimal,
+        gas_cost_usd: Decimal,
+    ) -> AppResult<Option<WarningOut>> {
+        let Some(excess) = excess(value_usd, gas_cost_usd, self.max_gas_bps) else {
+            return Ok(None);
+        };
+        match self.mode {
+            GasWorthinessMode::Warn => Ok(Some(warnings::warning(
+                "gas_exceeds_value",
+                format!("{operation}: {excess}"),
+            ))),
+            GasWorthinessMode::Refuse => Err(AppError::Swap(format!(
+                "{operation} refused: {excess} (GAS_WORTHINESS_BPS)"
+            ))),
+        }
+    }
+}
+
+/// Why gas costing `gas_cost_usd` is too much for something worth `value_usd`, when it costs
+/// more than `max_gas_bps` of that value.
+pub fn excess(value_usd: Decimal, gas_cost_usd: Decimal, max_gas_bps: u32) -> Option<String> {
+    let ceiling = value_usd * Decimal::from(max_gas_bps) / Decimal::from(FULL_BPS);
+    (gas_cost_usd > ceiling).then(|| {
+        format!(
+            "gas of ~${} is more than {}% of its ${} value",
+            usd(gas_cost_usd),
+            (Decimal::from(max_gas_bps) / Decimal::ONE_HUNDRED).normalize(),
+            usd(value_usd)
+        )
+    })
+}
+
+/// USD price of the native coin, through its wrapped token, to value gas with.
+pub async fn native_usd_price<M>(provider: Arc<M>, registry: &TokenRegistry) -> AppResult<Decimal>
+where
+    M: Middleware + 'static,
+{
+    let wrapped = registry.wrapped_native().ok_or_else(|| {
+        AppError::Price("no wrapped native token configured to price gas in USD".into())
+    })?;
+    let price =
+        price::resolve_token_price(provider, registry, wrapped.address, QuoteCurrency::USD).await?;
+    decimal(&price.price)
+}
+
+/// `gas_cost_wei` of the native coin in USD at `native_usd` per coin.
+pub fn gas_cost_usd(gas_cost_wei: U256, native_usd: Decimal) -> AppResult<Decimal> {
+    Ok(decimal(&balance::format_with_decimals(&gas_cost_wei, 18))? * native_usd)
+}
+
+/// USD value of `amount` (human units) of `token`, and of `gas_cost_wei` of the native coin.
+pub async fn usd_values<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    token: Address,
+    amount: &str,
+    gas_cost_wei: U256,
+) -> AppResult<(Decimal, Decimal)>
+where
+    M: Middleware + 'static,
+{
+    let price =
+        price::resolve_token_price(provider.clone(), registry, token, QuoteCurrency::USD).await?;
+    let value = decimal(&price.price)? * decimal(amount)?;
+    let native_usd = native_usd_price(provider, registry).await?;
+    Ok((value, gas_cost_usd(gas_cost_wei, native_usd)?))
+}
+
+pub fn decimal(value: &str) -> AppResult<Decimal> {
+    Decimal::from_str_exact(value)
+        .map_err(|err| AppError::Internal(format!("invalid decimal {value}: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_warns_or_refuses_past_the_share() {
+        let warn = GasPolicy {
+            max_gas_bps: 1_000,
+            mode: GasWorthinessMode::Warn,
+        };
+        let (value, cheap, dear) = (Decimal::from(20), Decimal::from(2), Decimal::new(25, 1));
+        assert!(warn.check("swap", value, cheap).unwrap().is_none());
+        let warning = warn.check("swap", value, dear).unwrap().unwrap();
+        assert_eq!(warning.code, "gas_exceeds_value");
+        assert_eq!(
+            warning.message,
+            "swap: gas of ~$2.5 is more than 10% of its $20 value"
+        );
+
+        let refuse = GasPolicy {
+            mode: GasWorthinessMode::Refuse,
+            ..warn
+        };
+        assert!(matches!(
+            refuse.check("swap", value, dear),
+            Err(AppError::Swap(_))
+        ));
+        assert_eq!(
+            "REFUSE".parse::<GasWorthinessMode>().unwrap(),
+            GasWorthinessMode::Refuse
+        );
+    }
+}
@@ -0,0 +1,710 @@
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, H256, Signature, U256,
+        transaction::eip712::{Eip712, TypedData},
+    },
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+#[cfg(feature = "gasless")]
+use crate::secrets::SecretString;
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{approvals::PERMIT2, balance, price::TokenInfo, warnings::warning},
+    types::{GaslessFeeOut, GaslessOrderOut, WarningOut},
+};
+
+/// 0x API base URL used when `gasless_api_url` is not set.
+pub const DEFAULT_GASLESS_API_URL: &str = "https://api.0x.org";
+/// How long a gasless quote may take before the call fails.
+#[cfg(feature = "gasless")]
+const GASLESS_API_TIMEOUT_SECS: u64 = 10;
+/// `signatureType` of an EIP-712 signature in a 0x submission.
+const EIP712_SIGNATURE_TYPE: u8 = 2;
+/// `approve(address,uint256)`, the call a meta-transaction approval wraps.
+const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+
+/// 0x Gasless API v2: quotes swaps the wallet pays for in the sold token while a relayer pays
+/// the gas. The orders it returns are EIP-712 meta-transactions the wallet signs.
+#[derive(Debug, Clone)]
+pub struct GaslessApi {
+    #[cfg(feature = "gasless")]
+    base_url: String,
+    #[cfg(feature = "gasless")]
+    api_key: SecretString,
+    #[cfg(feature = "gasless")]
+    client: reqwest::Client,
+    /// 0x Settler contracts trusted to spend the sold token through Permit2.
+    #[cfg(feature = "gasless")]
+    settlers: Vec<Address>,
+}
+
+/// One quote request; `taker` signs and receives the output.
+#[derive(Debug, Clone)]
+pub struct GaslessQuoteRequest<'a> {
+    pub chain_id: u64,
+    pub sell_token: &'a TokenInfo,
+    pub buy_token: &'a TokenInfo,
+    pub sell_amount: U256,
+    pub taker: Address,
+    pub slippage_bps: u32,
+}
+
+/// `GET /gasless/quote` response, keeping only what the tool reports or signs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GaslessQuote {
+    #[serde(default)]
+    pub liquidity_available: bool,
+    #[serde(default)]
+    pub sell_amount: Option<String>,
+    #[serde(default)]
+    pub buy_amount: Option<String>,
+    #[serde(default)]
+    pub min_buy_amount: Option<String>,
+    #[serde(default)]
+    pub fees: GaslessFees,
+    /// Gasless approval (EIP-2612 permit or meta-transaction `approve`), when one is needed.
+    #[serde(default)]
+    pub approval: Option<GaslessSignable>,
+    #[serde(default)]
+    pub trade: Option<GaslessSignable>,
+    #[serde(default)]
+    pub issues: GaslessIssues,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GaslessFees {
+    #[serde(default)]
+    pub gas_fee: Option<GaslessFee>,
+    #[serde(default)]
+    pub zero_ex_fee: Option<GaslessFee>,
+    #[serde(default)]
+    pub integrator_fee: Option<GaslessFee>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GaslessFee {
+    pub amount: String,
+    pub token: String,
+}
+
+/// Something the wallet signs: its kind, the hash 0x claims for it, and the typed data.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GaslessSignable {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub hash: H256,
+    pub eip712: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GaslessIssues {
+    /// Set when the sold token needs an on-chain approval that cannot be signed gaslessly.
+    #[serde(default)]
+    pub allowance: Option<AllowanceIssue>,
+    #[serde(default)]
+    pub balance: Option<BalanceIssue>,
+    #[serde(default)]
+    pub simulation_incomplete: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowanceIssue {
+    pub actual: String,
+    pub spender: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalanceIssue {
+    pub actual: String,
+    pub expected: String,
+}
+
+impl GaslessApi {
+    /// `None` unless a `gasless_api_key` is configured.
+    pub fn from_config(config: &AppConfig) -> AppResult<Option<Self>> {
+        let Some(api_key) = config.gasless_api_key.clone() else {
+            return Ok(None);
+        };
+        #[cfg(not(feature = "gasless"))]
+        {
+            let _ = api_key;
+            Err(AppError::Config(
+                "gasless_api_key requires building with the `gasless` feature".into(),
+            ))
+        }
+        #[cfg(feature = "gasless")]
+        Ok(Some(Self {
+            base_url: config
+                .gasless_api_url
+                .as_deref()
+                .unwrap_or(DEFAULT_GASLESS_API_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(GASLESS_API_TIMEOUT_SECS))
+                .build()
+                .map_err(|err| {
+                    AppError::Config(format!("failed to build gasless API client: {err}"))
+                })?,
+            settlers: config
+                .gasless_settlers
+                .iter()
+                .map(|raw| {
+                    raw.parse::<Address>().map_err(|_| {
+                        AppError::Config(format!("invalid gasless settler address: {raw}"))
+                    })
+                })
+                .collect::<AppResult<_>>()?,
+        }))
+    }
+
+    /// Settler contracts a gasless trade may name as its Permit2 spender.
+    #[cfg(feature = "gasless")]
+    pub fn settlers(&self) -> &[Address] {
+        &self.settlers
+    }
+
+    /// Where a signed order is posted; the server itself never submits one.
+    #[cfg(feature = "gasless")]
+    pub fn submit_url(&self) -> String {
+        format!("{}/gasless/submit", self.base_url)
+    }
+
+    #[cfg(feature = "gasless")]
+    pub async fn quote(&self, request: &GaslessQuoteRequest<'_>) -> AppResult<GaslessQuote> {
+        let response = self
+            .client
+            .get(format!("{}/gasless/quote", self.base_url))
+            .header("0x-api-key", self.api_key.expose())
+            .header("0x-version", "v2")
+            .query(&[
+                ("chainId", request.chain_id.to_string()),
+                ("sellToken", format!("{:#x}", request.sell_token.address)),
+                ("buyToken", format!("{:#x}", request.buy_token.address)),
+                ("sellAmount", request.sell_amount.to_string()),
+                ("taker", format!("{:#x}", request.taker)),
+                ("slippageBps", request.slippage_bps.to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|err| {
+                if err.is_timeout() {
+                    AppError::Swap(format!(
+                        "gasless quote did not answer within {GASLESS_API_TIMEOUT_SECS}s"
+                    ))
+                } else {
+                    AppError::Swap(format!("gasless quote request failed: {err}"))
+                }
+            })?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|err| AppError::Swap(format!("gasless quote is not JSON: {err}")))?;
+        if !status.is_success() {
+            let reason = body["message"]
+                .as_str()
+                .or_else(|| body["name"].as_str())
+                .unwrap_or("no reason given");
+            return Err(AppError::Swap(format!(
+                "gasless quote failed ({status}): {reason}"
+            )));
+        }
+        serde_json::from_value(body)
+            .map_err(|err| AppError::Swap(format!("unexpected gasless quote: {err}")))
+    }
+}
+
+impl GaslessSignable {
+    /// The typed data, after checking it hashes to what 0x says it does.
+    pub fn typed_data(&self) -> AppResult<TypedData> {
+        let typed: TypedData = serde_json::from_value(self.eip712.clone()).map_err(|err| {
+            AppError::Swap(format!(
+                "invalid EIP-712 data in gasless {}: {err}",
+                self.kind
+            ))
+        })?;
+        let hash = typed.encode_eip712().map_err(|err| {
+            AppError::Swap(format!(
+                "cannot hash gasless {} typed data: {err}",
+                self.kind
+            ))
+        })?;
+        if H256(hash) != self.hash {
+            return Err(AppError::Swap(format!(
+                "gasless {} typed data hashes to {:#x}, not the quoted {:#x}",
+                self.kind,
+                H256(hash),
+                self.hash
+            )));
+        }
+        Ok(typed)
+    }
+
+    /// Refuse typed data that does not commit to what was asked for in `request`: the chain, the
+    /// taker as owner and recipient, and for the trade a Permit2 transfer of exactly the sold
+    /// amount to an allow-listed Settler. Approvals must be signed for the sold token itself and
+    /// name Permit2 or a Settler as spender.
+    pub fn check_terms(
+        &self,
+        typed: &TypedData,
+        request: &GaslessQuoteRequest<'_>,
+        settlers: &[Address],
+    ) -> AppResult<()> {
+        let refuse = |what: String| {
+            AppError::Swap(format!("gasless {} {what}; refusing to sign it", self.kind))
+        };
+        let chain_id = typed.domain.chain_id;
+        if chain_id != Some(U256::from(request.chain_id)) {
+            return Err(refuse(format!(
+                "is for chain {}, not {}",
+                chain_id.map_or_else(|| "<none>".into(), |id| id.to_string()),
+                request.chain_id
+            )));
+        }
+        let contract = typed
+            .domain
+            .verifying_contract
+            .ok_or_else(|| refuse("names no verifying contract".into()))?;
+        let sell_token = request.sell_token.address;
+        let message = Value::Object(typed.message.clone().into_iter().collect());
+        let address_at = |pointer: &str| {
+            message
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .and_then(|raw| raw.parse::<Address>().ok())
+        };
+        let mut recipients = Vec::new();
+        collect_recipients(&message, &mut recipients);
+
+        let allowed_spender = |spender: Address| spender == *PERMIT2 || settlers.contains(&spender);
+        if message.get("permitted").is_some() {
+            if contract != *PERMIT2 {
+                return Err(refuse(format!("is verified by {contract:#x}, not Permit2")));
+            }
+            if address_at("/permitted/token") != Some(sell_token) {
+                return Err(refuse(format!(
+                    "does not transfer {}",
+                    request.sell_token.symbol
+                )));
+            }
+            let amount = message.pointer("/permitted/amount").and_then(uint_value);
+            if amount != Some(request.sell_amount) {
+                return Err(refuse(format!(
+                    "transfers {}, not the requested {}",
+                    amount.map_or_else(|| "<none>".into(), |amount| amount.to_string()),
+                    request.sell_amount
+                )));
+            }
+            match address_at("/spender") {
+                Some(spender) if settlers.contains(&spender) => {}
+                spender => {
+                    return Err(refuse(format!(
+                        "lets {} spend the tokens, which is not an allow-listed 0x Settler (GASLESS_SETTLERS)",
+                        describe(spender)
+                    )));
+                }
+            }
+            if recipients.is_empty() {
+                return Err(refuse("names no recipient".into()));
+            }
+        } else {
+            if contract != sell_token {
+                return Err(refuse(format!(
+                    "is verified by {contract:#x}, not {}",
+                    request.sell_token.symbol
+                )));
+            }
+            let spender = address_at("/spender").or_else(|| {
+                message
+                    .get("functionSignature")
+                    .and_then(Value::as_str)
+                    .and_then(approve_spender)
+            });
+            match spender {
+                Some(spender) if allowed_spender(spender) => {}
+                spender => {
+                    return Err(refuse(format!(
+                        "approves {}, which is neither Permit2 nor an allow-listed 0x Settler",
+                        describe(spender)
+                    )));
+                }
+            }
+        }
+
+        for owner in ["/owner", "/holder", "/from"] {
+            if let Some(value) = message.pointer(owner)
+                && value.as_str().and_then(|raw| raw.parse::<Address>().ok()) != Some(request.taker)
+            {
+                return Err(refuse(format!(
+                    "is signed on behalf of {value}, not the taker"
+                )));
+            }
+        }
+        if let Some(recipient) = recipients
+            .into_iter()
+            .find(|recipient| *recipient != Some(request.taker))
+        {
+            return Err(refuse(format!(
+                "pays {}, not the taker",
+                describe(recipient)
+            )));
+        }
+        Ok(())
+    }
+
+    /// The order for the response after [`Self::check_terms`], signed by `signer` when given.
+    pub async fn order(
+        &self,
+        request: &GaslessQuoteRequest<'_>,
+        settlers: &[Address],
+        signer: Option<&LocalWallet>,
+    ) -> AppResult<(GaslessOrderOut, Value)> {
+        let typed = self.typed_data()?;
+        self.check_terms(&typed, request, settlers)?;
+        let signature = match signer {
+            Some(signer) => Some(signer.sign_typed_data(&typed).await.map_err(|err| {
+                AppError::Wallet(format!("failed to sign gasless {}: {err}", self.kind))
+            })?),
+            None => None,
+        };
+        let submission = json!({
+            "type": self.kind,
+            "eip712": self.eip712,
+            "signature": signature.as_ref().map(submission_signature),
+        });
+        let order = GaslessOrderOut {
+            kind: self.kind.clone(),
+            hash: format!("{:#x}", self.hash),
+            typed_data: self.eip712.clone(),
+            signature: signature.map(|signature| format!("0x{signature}")),
+        };
+        Ok((order, submission))
+    }
+}
+
+fn describe(address: Option<Address>) -> String {
+    address.map_or_else(|| "<none>".into(), |address| format!("{address:#x}"))
+}
+
+/// Every `recipient` field in a typed-data message, however deeply nested.
+fn collect_recipients(value: &Value, out: &mut Vec<Option<Address>>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                if name == "recipient" {
+                    out.push(field.as_str().and_then(|raw| raw.parse().ok()));
+                } else {
+                    collect_recipients(field, out);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_recipients(item, out)),
+        _ => {}
+    }
+}
+
+/// A `uint256` field, which typed data may carry as a decimal or hex string or as a number.
+fn uint_value(value: &Value) -> Option<U256> {
+    match value {
+        Value::String(raw) => match raw.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(raw).ok(),
+        },
+        Value::Number(number) => number.as_u64().map(U256::from),
+        _ => None,
+    }
+}
+
+/// The spender of a meta-transaction's wrapped `approve(spender, amount)` call.
+fn approve_spender(function_signature: &str) -> Option<Address> {
+    let call = hex::decode(function_signature.trim_start_matches("0x")).ok()?;
+    (call.len() >= 36 && call[..4] == APPROVE_SELECTOR).then(|| Address::from_slice(&call[16..36]))
+}
+
+/// A signature as `POST /gasless/submit` takes it.
+fn submission_signature(signature: &Signature) -> Value {
+    let word = |value: U256| {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        format!("0x{}", hex::encode(bytes))
+    };
+    json!({
+        "signatureType": EIP712_SIGNATURE_TYPE,
+        "v": signature.v,
+        "r": word(signature.r),
+        "s": word(signature.s),
+    })
+}
+
+/// Fees the quote charges, in raw units of the token each is paid in.
+pub fn fees_out(fees: &GaslessFees) -> Vec<GaslessFeeOut> {
+    [
+        ("gas", &fees.gas_fee),
+        ("zero_ex", &fees.zero_ex_fee),
+        ("integrator", &fees.integrator_fee),
+    ]
+    .into_iter()
+    .filter_map(|(kind, fee)| {
+        fee.as_ref().map(|fee| GaslessFeeOut {
+            kind: kind.to_string(),
+            token: fee.token.clone(),
+            amount_raw: fee.amount.clone(),
+        })
+    })
+    .collect()
+}
+
+/// What stands between the quote and a relayed trade.
+pub fn issue_warnings(issues: &GaslessIssues, sell_token: &TokenInfo) -> Vec<WarningOut> {
+    let mut warnings = Vec::new();
+    if let Some(allowance) = &issues.allowance {
+        warnings.push(warning(
+            "approval_required",
+            format!(
+                "{} cannot be approved gaslessly; the wallet must approve {} on chain (which costs gas) before the trade can be relayed",
+                sell_token.symbol, allowance.spender
+            ),
+        ));
+    }
+    if let Some(balance) = &issues.balance {
+        let format = |raw: &str| {
+            U256::from_dec_str(raw)
+                .map(|raw| balance::format_with_decimals(&raw, sell_token.decimals as u32))
+                .unwrap_or_else(|_| raw.to_string())
+        };
+        warnings.push(warning(
+            "insufficient_balance",
+            format!(
+                "the wallet holds {} {} but the trade sells {}",
+                format(&balance.actual),
+                sell_token.symbol,
+                format(&balance.expected)
+            ),
+        ));
+    }
+    if issues.simulation_incomplete {
+        warnings.push(warning(
+            "simulation_incomplete",
+            "0x could not fully simulate the trade; the relayer may still reject it",
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNER_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn settler() -> Address {
+        Address::from_low_u64_be(0xdef)
+    }
+
+    /// A Permit2 witness transfer of 1 USDC from the taker, in 0x's trade shape.
+    fn trade(taker: Address) -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "PermitWitnessTransferFrom": [
+                    { "name": "permitted", "type": "TokenPermissions" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "deadline", "type": "uint256" },
+                    { "name": "slippageAndActions", "type": "SlippageAndActions" },
+                ],
+                "TokenPermissions": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint256" },
+                ],
+                "SlippageAndActions": [
+                    { "name": "recipient", "type": "address" },
+                    { "name": "minAmountOut", "type": "uint256" },
+                ],
+            },
+            "domain": {
+                "name": "Permit2",
+                "chainId": 1,
+                "verifyingContract": *PERMIT2,
+            },
+            "primaryType": "PermitWitnessTransferFrom",
+            "message": {
+                "permitted": { "token": Address::repeat_byte(0xcc), "amount": "1000000" },
+                "spender": settler(),
+                "nonce": "7",
+                "deadline": "1700000000",
+                "slippageAndActions": { "recipient": taker, "minAmountOut": "400000000000000" },
+            },
+        })
+    }
+
+    /// An EIP-2612 permit on USDC for `spender`.
+    fn permit(taker: Address, spender: Address) -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "Permit": [
+                    { "name": "owner", "type": "address" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                ],
+            },
+            "domain": {
+                "name": "USD Coin",
+                "chainId": 1,
+                "verifyingContract": Address::repeat_byte(0xcc),
+            },
+            "primaryType": "Permit",
+            "message": { "owner": taker, "spender": spender, "value": U256::MAX.to_string() },
+        })
+    }
+
+    fn signable(kind: &str, eip712: Value) -> GaslessSignable {
+        let typed: TypedData = serde_json::from_value(eip712.clone()).unwrap();
+        GaslessSignable {
+            kind: kind.into(),
+            hash: H256(typed.encode_eip712().unwrap()),
+            eip712,
+        }
+    }
+
+    fn tokens() -> (TokenInfo, TokenInfo) {
+        (
+            TokenInfo::new("USDC", Address::repeat_byte(0xcc), 6),
+            TokenInfo::new("WETH", Address::repeat_byte(0xee), 18),
+        )
+    }
+
+    fn request<'a>(
+        sell_token: &'a TokenInfo,
+        buy_token: &'a TokenInfo,
+        taker: Address,
+    ) -> GaslessQuoteRequest<'a> {
+        GaslessQuoteRequest {
+            chain_id: 1,
+            sell_token,
+            buy_token,
+            sell_amount: U256::from(1_000_000u64),
+            taker,
+            slippage_bps: 50,
+        }
+    }
+
+    #[tokio::test]
+    async fn orders_are_signed_only_when_their_hash_checks_out() {
+        let signer: LocalWallet = SIGNER_KEY.parse().unwrap();
+        let (usdc, weth) = tokens();
+        let request = request(&usdc, &weth, signer.address());
+        let settlers = [settler()];
+        let order = signable("settler_metatransaction", trade(signer.address()));
+        let hash = order.hash;
+
+        let (signed, submission) = order
+            .order(&request, &settlers, Some(&signer))
+            .await
+            .unwrap();
+        let signature: Signature = signed.signature.unwrap().parse().unwrap();
+        assert_eq!(signature.recover(hash).unwrap(), signer.address());
+        assert_eq!(submission["signature"]["signatureType"], 2);
+        assert_eq!(submission["signature"]["v"], signature.v);
+
+        let (unsigned, submission) = order.order(&request, &settlers, None).await.unwrap();
+        assert!(unsigned.signature.is_none());
+        assert!(submission["signature"].is_null());
+
+        let tampered = GaslessSignable {
+            hash: H256::repeat_byte(1),
+            ..order
+        };
+        assert!(
+            tampered
+                .order(&request, &settlers, Some(&signer))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn orders_must_commit_to_the_request() {
+        let signer: LocalWallet = SIGNER_KEY.parse().unwrap();
+        let taker = signer.address();
+        let (usdc, weth) = tokens();
+        let request = request(&usdc, &weth, taker);
+        let settlers = [settler()];
+        let check = |kind: &str, eip712: Value, settlers: &[Address]| {
+            let order = signable(kind, eip712);
+            let typed = order.typed_data().unwrap();
+            order.check_terms(&typed, &request, settlers)
+        };
+        let trade_with = |pointer: &str, value: Value| {
+            let mut eip712 = trade(taker);
+            *eip712.pointer_mut(pointer).unwrap() = value;
+            check("settler_metatransaction", eip712, &settlers)
+        };
+        let other = json!(Address::repeat_byte(0x66));
+
+        assert!(check("settler_metatransaction", trade(taker), &settlers).is_ok());
+        assert!(trade_with("/domain/chainId", json!(5)).is_err());
+        assert!(trade_with("/domain/verifyingContract", other.clone()).is_err());
+        assert!(trade_with("/message/permitted/token", other.clone()).is_err());
+        assert!(trade_with("/message/permitted/amount", json!("2000000")).is_err());
+        assert!(trade_with("/message/spender", other.clone()).is_err());
+        assert!(trade_with("/message/slippageAndActions/recipient", other.clone()).is_err());
+        let err = check("settler_metatransaction", trade(taker), &[]).unwrap_err();
+        assert!(err.to_string().contains("GASLESS_SETTLERS"), "{err}");
+
+        // Approvals are verified by the sold token and may only name Permit2 or a Settler.
+        assert!(check("permit", permit(taker, *PERMIT2), &settlers).is_ok());
+        assert!(
+            check(
+                "permit",
+                permit(taker, Address::repeat_byte(0x66)),
+                &settlers
+            )
+            .is_err()
+        );
+        let stranger = Address::repeat_byte(0x77);
+        assert!(check("permit", permit(stranger, *PERMIT2), &settlers).is_err());
+        let mut foreign = permit(taker, *PERMIT2);
+        foreign["domain"]["verifyingContract"] = other;
+        assert!(check("permit", foreign, &settlers).is_err());
+    }
+
+    #[test]
+    fn issues_become_warnings() {
+        let usdc = TokenInfo::new("USDC", Address::repeat_byte(0xcc), 6);
+        let quote: GaslessQuote = serde_json::from_value(json!({
+            "liquidityAvailable": true,
+            "buyAmount": "500000000000000",
+            "fees": { "gasFee": { "amount": "120000", "token": "0xcc", "type": "gas" } },
+            "issues": {
+                "allowance": null,
+                "balance": { "token": "0xcc", "actual": "1000000", "expected": "2500000" },
+                "simulationIncomplete": false,
+            },
+        }))
+        .unwrap();
+        let warnings = issue_warnings(&quote.issues, &usdc);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "insufficient_balance");
+        assert!(warnings[0].message.contains("holds 1 USDC"));
+        assert_eq!(fees_out(&quote.fees)[0].amount_raw, "120000");
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+    utils::{ParseUnits, parse_units},
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance, mev,
+        price::TokenInfo,
+        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+    },
+    types::{QuoteLadderOut, QuoteLevelOut},
+};
+
+/// Upper bound on rungs per call; each rung is one quoter `eth_call`.
+pub const MAX_LADDER_SIZES: usize = 20;
+
+/// Quote `from -> to` on one Uniswap V3 pool at each size in `sizes` (human units of `from`).
+///
+/// A size the pool cannot fill is reported on its own rung instead of failing the ladder, so
+/// the result shows where liquidity runs out. `price_impact_bps` is measured against the
+/// smallest size that quoted successfully.
+pub async fn quote_ladder<M>(
+    provider: Arc<M>,
+    quoter: Address,
+    from: &TokenInfo,
+    to: &TokenInfo,
+    sizes: &[String],
+    fee: u32,
+) -> AppResult<QuoteLadderOut>
+where
+    M: Middleware + 'static,
+{
+    if sizes.is_empty() || sizes.len() > MAX_LADDER_SIZES {
+        return Err(AppError::InvalidInput(format!(
+            "sizes must contain between 1 and {MAX_LADDER_SIZES} amounts"
+        )));
+    }
+
+    let mut amounts = sizes
+        .iter()
+        .map(|size| parse_size(size, from.decimals))
+        .collect::<AppResult<Vec<_>>>()?;
+    amounts.sort();
+    amounts.dedup();
+
+    let quoter = UniswapQuoterV2::new(quoter, provider);
+    let mut quotes = Vec::with_capacity(amounts.len());
+    for amount_in in amounts {
+        let quote = quoter
+            .quote_exact_input_single(QuoteExactInputSingleParams {
+                token_in: from.address,
+                token_out: to.address,
+                amount_in,
+                fee,
+                sqrt_price_limit_x96: U256::zero(),
+            })
+            .call()
+            .await
+            .map_err(|err| format!("uniswap quoter call failed: {err}"))
+            .and_then(|(amount_out, _, _, _)| {
+                (!amount_out.is_zero())
+                    .then_some(amount_out)
+                    .ok_or_else(|| "quote returned zero output amount".to_string())
+            });
+        quotes.push((amount_in, quote));
+    }
+
+    let reference = quotes
+        .iter()
+        .find_map(|(amount_in, quote)| quote.as_ref().ok().map(|out| (*amount_in, *out)));
+
+    let levels = quotes
+        .into_iter()
+        .map(|(amount_in, quote)| {
+            let amount_in_text = balance::format_with_decimals(&amount_in, from.decimals as u32);
+            match quote {
+                Ok(amount_out) => {
+                    let amount_out_text =
+                        balance::format_with_decimals(&amount_out, to.decimals as u32);
+                    QuoteLevelOut {
+                        price: effective_price(&amount_in_text, &amount_out_text),
+                        price_impact_bps: reference.and_then(|(reference_in, reference_out)| {
+                            mev::price_impact_bps(
+                                amount_in,
+                                amount_out,
+                                reference_in,
+                                reference_out,
+                            )
+                        }),
+                        amount_in: amount_in_text,
+                        amount_out: Some(amount_out_text),
+                        error: None,
+                    }
+                }
+                Err(err) => QuoteLevelOut {
+                    amount_in: amount_in_text,
+                    amount_out: None,
+                    price: None,
+                    price_impact_bps: None,
+                    error: Some(err),
+                },
+            }
+        })
+        .collect();
+
+    Ok(QuoteLadderOut {
+        from_token: from.symbol.clone(),
+        to_token: to.symbol.clone(),
+        fee,
+        block_number: None,
+        levels,
+        warnings: Vec::new(),
+    })
+}
+
+fn parse_size(size: &str, decimals: u8) -> AppResult<U256> {
+    let invalid = || AppError::InvalidInput(format!("invalid ladder size: {size}"));
+    let amount = match parse_units(size.trim(), decimals as u32).map_err(|_| invalid())? {
+        ParseUnits::U256(amount) => amount,
+        ParseUnits::I256(_) => return Err(invalid()),
+    };
+    if amount.is_zero() {
+        return Err(invalid());
+    }
+    Ok(amount)
+}
+
+/// Output units received per input unit, both already scaled to human decimals.
+fn effective_price(amount_in: &str, amount_out: &str) -> Option<String> {
+    let amount_in = Decimal::from_str_exact(amount_in).ok()?;
+    let amount_out = Decimal::from_str_exact(amount_out).ok()?;
+    amount_out
+        .checked_div(amount_in)
+        .map(|price| price.normalize().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{Token, encode},
+        providers::Provider,
+    };
+
+    fn quote_response(amount_out: u128) -> String {
+        format!(
+            "0x{}",
+            hex::encode(encode(&[
+                Token::Uint(amount_out.into()),
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::zero()),
+            ]))
+        )
+    }
+
+    #[test]
+    fn parses_human_sizes() {
+        assert_eq!(parse_size("0.1", 18).unwrap(), U256::exp10(17));
+        assert!(parse_size("0", 18).is_err());
+        assert!(parse_size("-1", 18).is_err());
+        assert!(parse_size("lots", 18).is_err());
+    }
+
+    #[tokio::test]
+    async fn ladder_reports_price_per_size() {
+        let (provider, mock) = Provider::mocked();
+        // Responses are consumed in reverse order: sizes are quoted smallest first.
+        mock.push::<String, _>(quote_response(19_800_000_000))
+            .unwrap(); // 10 WETH
+        mock.push::<String, _>(quote_response(2_000_000_000))
+            .unwrap(); // 1 WETH
+
+        let weth = TokenInfo::new("WETH", Address::from_low_u64_be(1), 18);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(2), 6);
+        let ladder = quote_ladder(
+            Arc::new(provider),
+            Address::from_low_u64_be(0x9),
+            &weth,
+            &usdc,
+            &["10".to_string(), "1".to_string()],
+            3_000,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ladder.levels.len(), 2);
+        assert_eq!(ladder.levels[0].amount_in, "1");
+        assert_eq!(ladder.levels[0].price.as_deref(), Some("2000"));
+        assert_eq!(ladder.levels[0].price_impact_bps, Some(0));
+        assert_eq!(ladder.levels[1].price.as_deref(), Some("1980"));
+        assert_eq!(ladder.levels[1].price_impact_bps, Some(100));
+    }
+}
@@ -0,0 +1,226 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::{
+    providers::{Middleware, Provider, StreamExt, Ws},
+    types::{Address, Transaction, U256},
+};
+use tokio::time::{Instant, timeout_at};
+use tracing::{debug, warn};
+
+use crate::{
+    error::{AppError, AppResult},
+    progress::ProgressReporter,
+    types::{MempoolMatch, MempoolWatchOut},
+};
+
+/// `transfer(address,uint256)` selector.
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// `transferFrom(address,address,uint256)` selector.
+const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Addresses a mempool watch filters on.
+#[derive(Debug, Clone)]
+pub struct MempoolFilter {
+    pub wallet: Address,
+    pub tokens: Vec<Address>,
+}
+
+/// Subscribe to pending transactions over WebSocket and collect the ones touching the wallet
+/// until `window` elapses or `max_results` matches were seen. Each match is reported to
+/// `progress`.
+pub async fn watch_pending(
+    provider: Arc<Provider<Ws>>,
+    filter: MempoolFilter,
+    window: Duration,
+    max_results: usize,
+    progress: &ProgressReporter,
+) -> AppResult<MempoolWatchOut> {
+    let mut stream = provider
+        .subscribe_pending_txs()
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to subscribe to pending txs: {err}")))?;
+
+    let deadline = Instant::now() + window;
+    let mut matches = Vec::new();
+    let mut scanned = 0usize;
+
+    while matches.len() < max_results {
+        let hash = match timeout_at(deadline, stream.next()).await {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                warn!("pending transaction subscription closed early");
+                break;
+            }
+            Err(_) => break,
+        };
+        scanned += 1;
+
+        // Pending transactions can be dropped or mined before we fetch them. A slow lookup
+        // must not keep the watch past its window either.
+        let tx = match timeout_at(deadline, provider.get_transaction(hash)).await {
+            Ok(Ok(Some(tx))) => tx,
+            Ok(Ok(None)) => continue,
+            Ok(Err(err)) => {
+                debug!("failed to fetch pending tx {hash:?}: {err}");
+                continue;
+            }
+            Err(_) => break,
+        };
+
+        if let Some(found) = classify_pending(&tx, &filter) {
+            matches.push(found);
+            progress.report(
+                matches.len() as u64,
+                Some(max_results as u64),
+                format!(
+                    "{} matches in {scanned} pending transactions",
+                    matches.len()
+                ),
+            );
+        }
+    }
+
+    Ok(MempoolWatchOut {
+        wallet: format!("{:#x}", filter.wallet),
+        window_secs: window.as_secs(),
+        scanned,
+        matches,
+        warnings: Vec::new(),
+    })
+}
+
+/// Decide whether a pending transaction is relevant to the watched wallet or tokens.
+pub fn classify_pending(tx: &Transaction, filter: &MempoolFilter) -> Option<MempoolMatch> {
+    let to = tx.to?;
+    let (kind, token, amount) = if let Some((recipient, amount)) = decode_token_transfer(tx) {
+        if recipient == filter.wallet {
+            ("incoming_token", Some(to), Some(amount))
+        } else if tx.from == filter.wallet {
+            ("outgoing_token", Some(to), Some(amount))
+        } else if filter.tokens.contains(&to) {
+            ("token_activity", Some(to), Some(amount))
+        } else {
+            return None;
+        }
+    } else if to == filter.wallet {
+        ("incoming_eth", None, Some(tx.value))
+    } else if tx.from == filter.wallet {
+        ("outgoing", None, Some(tx.value))
+    } else if filter.tokens.contains(&to) {
+        ("token_activity", Some(to), None)
+    } else {
+        return None;
+    };
+
+    Some(MempoolMatch {
+        hash: format!("{:#x}", tx.hash),
+        kind: kind.to_string(),
+        from: format!("{:#x}", tx.from),
+        to: format!("{to:#x}"),
+        token: token.map(|addr| format!("{addr:#x}")),
+        amount_raw: amount.map(|value| value.to_string()),
+        gas_price: tx
+            .max_fee_per_gas
+            .or(tx.gas_price)
+            .map(|value| value.to_string()),
+    })
+}
+
+/// Extract `(recipient, amount)` from `transfer` / `transferFrom` calldata.
+fn decode_token_transfer(tx: &Transaction) -> Option<(Address, U256)> {
+    let input = tx.input.as_ref();
+    if input.len() < 4 {
+        return None;
+    }
+    let (selector, args) = input.split_at(4);
+    let (recipient_word, amount_word) = if selector == TRANSFER_SELECTOR && args.len() >= 64 {
+        (&args[0..32], &args[32..64])
+    } else if selector == TRANSFER_FROM_SELECTOR && args.len() >= 96 {
+        (&args[32..64], &args[64..96])
+    } else {
+        return None;
+    };
+
+    Some((
+        Address::from_slice(&recipient_word[12..]),
+        U256::from_big_endian(amount_word),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{Token, encode};
+
+    fn filter() -> MempoolFilter {
+        MempoolFilter {
+            wallet: Address::from_low_u64_be(0xaa),
+            tokens: vec![Address::from_low_u64_be(0xcc)],
+        }
+    }
+
+    fn transfer_calldata(recipient: Address, amount: u64) -> Vec<u8> {
+        let mut data = TRANSFER_SELECTOR.to_vec();
+        data.extend(encode(&[
+            Token::Address(recipient),
+            Token::Uint(U256::from(amount)),
+        ]));
+        data
+    }
+
+    #[test]
+    fn classifies_incoming_eth() {
+        let tx = Transaction {
+            from: Address::from_low_u64_be(0x01),
+            to: Some(Address::from_low_u64_be(0xaa)),
+            value: U256::from(5u64),
+            ..Default::default()
+        };
+
+        let found = classify_pending(&tx, &filter()).expect("should match");
+        assert_eq!(found.kind, "incoming_eth");
+        assert_eq!(found.amount_raw.as_deref(), Some("5"));
+        assert!(found.token.is_none());
+    }
+
+    #[test]
+    fn classifies_incoming_token_transfer_for_any_token() {
+        let token = Address::from_low_u64_be(0xdd);
+        let tx = Transaction {
+            from: Address::from_low_u64_be(0x01),
+            to: Some(token),
+            input: transfer_calldata(Address::from_low_u64_be(0xaa), 1_000).into(),
+            ..Default::default()
+        };
+
+        let found = classify_pending(&tx, &filter()).expect("should match");
+        assert_eq!(found.kind, "incoming_token");
+        assert_eq!(found.token, Some(format!("{token:#x}")));
+        assert_eq!(found.amount_raw.as_deref(), Some("1000"));
+    }
+
+    #[test]
+    fn classifies_activity_on_watched_token() {
+        let tx = Transaction {
+            from: Address::from_low_u64_be(0x01),
+            to: Some(Address::from_low_u64_be(0xcc)),
+            input: transfer_calldata(Address::from_low_u64_be(0x02), 7).into(),
+            ..Default::default()
+        };
+
+        let found = classify_pending(&tx, &filter()).expect("should match");
+        assert_eq!(found.kind, "token_activity");
+    }
+
+    #[test]
+    fn ignores_unrelated_transactions() {
+        let tx = Transaction {
+            from: Address::from_low_u64_be(0x01),
+            to: Some(Address::from_low_u64_be(0x02)),
+            input: transfer_calldata(Address::from_low_u64_be(0x03), 7).into(),
+            ..Default::default()
+        };
+
+        assert!(classify_pending(&tx, &filter()).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use ethers::types::U256;
+
+use crate::types::MevRiskOut;
+
+/// Price impact (bps) above which a trade is considered an attractive sandwich target.
+const HIGH_IMPACT_BPS: u64 = 100;
+const MEDIUM_IMPACT_BPS: u64 = 30;
+/// Slippage tolerance (bps) that leaves a meaningful gap for a sandwich to extract.
+const HIGH_SLIPPAGE_BPS: u32 = 300;
+const MEDIUM_SLIPPAGE_BPS: u32 = 100;
+
+/// Coarse sandwich-attack exposure for a swap submitted through the public mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MevRating {
+    Low,
+    Medium,
+    High,
+}
+
+impl MevRating {
+    fn as_str(self) -> &'static str {
+        match self {
+            MevRating::Low => "low",
+            MevRating::Medium => "medium",
+            MevRating::High => "high",
+        }
+    }
+}
+
+/// Price impact of `amount_in -> amount_out` relative to a much smaller reference quote, in bps.
+pub fn price_impact_bps(
+    amount_in: U256,
+    amount_out: U256,
+    reference_in: U256,
+    reference_out: U256,
+) -> Option<u64> {
+    if amount_in.is_zero() || reference_in.is_zero() || reference_out.is_zero() {
+        return None;
+    }
+
+    // exec_rate / reference_rate, scaled to bps.
+    let numerator = amount_out
+        .checked_mul(reference_in)?
+        .checked_mul(U256::from(10_000u32))?;
+    let denominator = reference_out.checked_mul(amount_in)?;
+    let ratio_bps = numerator / denominator;
+    let ratio_bps = ratio_bps.min(U256::from(10_000u32)).as_u64();
+    Some(10_000 - ratio_bps)
+}
+
+/// Rate exposure from the pool's price impact and the slippage the caller is willing to accept.
+/// `max_extractable` is the quoted output minus the minimum output, i.e. the most a sandwich can
+/// take before the router reverts.
+pub fn assess_sandwich_risk(
+    price_impact_bps: Option<u64>,
+    slippage_bps: u32,
+    max_extractable: String,
+) -> MevRiskOut {
+    let impact = price_impact_bps.unwrap_or(0);
+    let rating = if impact >= HIGH_IMPACT_BPS || slippage_bps >= HIGH_SLIPPAGE_BPS {
+        MevRating::High
+    } else if impact >= MEDIUM_IMPACT_BPS || slippage_bps >= MEDIUM_SLIPPAGE_BPS {
+        MevRating::Medium
+    } else {
+        MevRating::Low
+    };
+
+    let recommendation = match rating {
+        MevRating::High if impact >= HIGH_IMPACT_BPS => {
+            "submit through a private relay (e.g. Flashbots Protect) and split the trade into smaller sizes"
+        }
+        MevRating::High => {
+            "submit through a private relay (e.g. Flashbots Protect) or tighten slippage_bps"
+        }
+        MevRating::Medium => "tighten slippage_bps or submit through a private relay",
+        MevRating::Low => "public mempool submission is acceptable",
+    };
+
+    MevRiskOut {
+        rating: rating.as_str().to_string(),
+        price_impact_bps,
+        max_extractable,
+        recommendation: recommendation.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_impact_matches_rate_difference() {
+        // Reference: 1 -> 2000, trade: 100 -> 198_000 (1% worse).
+        let impact = price_impact_bps(
+            U256::from(100u64),
+            U256::from(198_000u64),
+            U256::from(1u64),
+            U256::from(2_000u64),
+        );
+        assert_eq!(impact, Some(100));
+    }
+
+    #[test]
+    fn price_impact_never_negative() {
+        let impact = price_impact_bps(
+            U256::from(100u64),
+            U256::from(250_000u64),
+            U256::from(1u64),
+            U256::from(2_000u64),
+        );
+        assert_eq!(impact, Some(0));
+    }
+
+    #[test]
+    fn rating_escalates_with_impact_and_slippage() {
+        assert_eq!(assess_sandwich_risk(Some(5), 50, "0".into()).rating, "low");
+        assert_eq!(
+            assess_sandwich_risk(Some(5), 100, "0".into()).rating,
+            "medium"
+        );
+        assert_eq!(
+            assess_sandwich_risk(Some(40), 50, "0".into()).rating,
+            "medium"
+        );
+        let high = assess_sandwich_risk(Some(150), 50, "0".into());
+        assert_eq!(high.rating, "high");
+        assert!(high.recommendation.contains("split"));
+        assert_eq!(assess_sandwich_risk(None, 500, "0".into()).rating, "high");
+    }
+}
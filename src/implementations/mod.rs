@@ -1,5 +1,57 @@
+pub mod approvals;
+pub mod arbitrage;
+pub mod attestation;
+pub mod backtest;
 pub mod balance;
+pub mod block_scope;
+pub mod bundle;
+pub mod burner;
+pub mod candles;
+pub mod canonical;
+pub mod chain;
+pub mod compliance;
+pub mod delegation;
+pub mod deposits;
+pub mod distribute;
+pub mod dust;
+pub mod ens;
 pub mod erc20;
+pub mod fixture;
+pub mod funding;
+pub mod gas_worth;
+pub mod gasless;
+pub mod ladder;
+#[cfg(feature = "mempool")]
+pub mod mempool;
+pub mod mev;
+pub mod native;
+pub mod paper;
+pub mod param_defaults;
+pub mod plan;
+pub mod portfolio_risk;
+pub mod preflight;
 pub mod price;
+pub mod provider_pool;
+pub mod quote_id;
+pub mod quotes;
+pub mod rebalance;
+pub mod recipients;
+pub mod retention;
+pub mod revert;
+pub mod rpc;
+pub mod self_test;
+pub mod signatures;
+pub mod spread;
+pub mod stuck_tx;
 pub mod swap;
+pub mod timing;
+pub mod token_risk;
+pub mod token_watch;
+pub mod trace;
+pub mod transfer;
 pub mod uniswap;
+pub mod units;
+pub mod v2;
+pub mod validation;
+pub mod warnings;
+pub mod whitelist;
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use ethers::{providers::Middleware, types::Address};
+use rust_decimal::Decimal;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::price::{self, FeedDecimals},
+    types::{FiatPriceOut, NativePriceOut},
+};
+
+/// `(chain id, native symbol, wrapped native token, Chainlink native/USD feed)` for chains with a
+/// well-known wrapper.
+const WRAPPED_NATIVE: [(u64, &str, &str, &str); 7] = [
+    (
+        1,
+        "ETH",
+        "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+        "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419",
+    ),
+    (
+        10,
+        "ETH",
+        "0x4200000000000000000000000000000000000006",
+        "0x13e3Ee699D1909E989722E753853AE30b17e08c5",
+    ),
+    (
+        56,
+        "BNB",
+        "0xbb4CdB9CBd36B01bD1cBaEBF2De08d9173bc095c",
+        "0x0567F2323251f0Aab15c8dFb1967E4e8A7D42aeE",
+    ),
+    (
+        137,
+        "MATIC",
+        "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270",
+        "0xAB594600376Ec9fD91F8e885dADF0CE036862dE0",
+    ),
+    (
+        8_453,
+        "ETH",
+        "0x4200000000000000000000000000000000000006",
+        "0x71041dddad3595F9CEd3DcCFBe3D1F4b0a16Bb70",
+    ),
+    (
+        42_161,
+        "ETH",
+        "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+        "0x639Fe6ab55C921f74e7fac1ee960C0B6293ba612",
+    ),
+    (
+        43_114,
+        "AVAX",
+        "0xB31f66AA3C1e785363F0875A1B74E27b85FD66c7",
+        "0x0A77230d17318075983913bC2145DB16C7366156",
+    ),
+];
+
+/// `(chain id, currency, Chainlink currency/USD feed)` used to convert native/USD into other fiats.
+const FIAT_USD_FEEDS: [(u64, &str, &str); 3] = [
+    (1, "EUR", "0xb49f677943BC038e9857d61E7d053CaA2C1734C1"),
+    (1, "GBP", "0x5c0Ab2d9b5a7ed9f470386e82BB36A3613cDd4b5"),
+    (1, "JPY", "0xBcE206caE7f0ec07b545EddE332A47C2F75bbeb3"),
+];
+
+/// The active chain's gas coin and its ERC-20 wrapper, which stands in for it in pool quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeCurrency {
+    pub symbol: String,
+    pub wrapped: Option<Address>,
+    /// Chainlink native/USD aggregator, read directly by `get_native_price`.
+    pub usd_feed: Option<Address>,
+}
+
+impl Default for NativeCurrency {
+    fn default() -> Self {
+        Self::for_chain(1)
+    }
+}
+
+impl NativeCurrency {
+    /// Built-in native coin for `chain_id`; unknown chains report `ETH` with no wrapper.
+    pub fn for_chain(chain_id: u64) -> Self {
+        match WRAPPED_NATIVE.iter().find(|(id, _, _, _)| *id == chain_id) {
+            Some((_, symbol, wrapped, usd_feed)) => Self {
+                symbol: symbol.to_string(),
+                wrapped: Some(wrapped.parse().expect("valid wrapped native address")),
+                usd_feed: Some(usd_feed.parse().expect("valid native/USD feed address")),
+            },
+            None => Self {
+                symbol: "ETH".to_string(),
+                wrapped: None,
+                usd_feed: None,
+            },
+        }
+    }
+
+    /// [`Self::for_chain`] with `native_symbol` / `wrapped_native` / `native_usd_feed` overrides
+    /// applied.
+    pub fn from_config(config: &AppConfig, chain_id: u64) -> AppResult<Self> {
+        let mut native = Self::for_chain(chain_id);
+        if let Some(symbol) = config.native_symbol.as_deref() {
+            native.symbol = symbol.trim().to_uppercase();
+        }
+        if let Some(wrapped) = config.wrapped_native.as_deref() {
+            native.wrapped = Some(wrapped.trim().parse().map_err(|_| {
+                AppError::Config(format!("wrapped_native is not a valid address: {wrapped}"))
+            })?);
+        }
+        if let Some(feed) = config.native_usd_feed.as_deref() {
+            native.usd_feed = Some(feed.trim().parse().map_err(|_| {
+                AppError::Config(format!("native_usd_feed is not a valid address: {feed}"))
+            })?);
+        }
+        Ok(native)
+    }
+}
+
+/// Price the native coin in each of `currencies` straight from Chainlink, bypassing the registry.
+///
+/// USD comes from the native/USD feed; other fiats divide it by their own `<fiat>/USD` feed.
+pub async fn native_price<M>(
+    provider: Arc<M>,
+    decimals: &FeedDecimals,
+    native: &NativeCurrency,
+    chain_id: u64,
+    currencies: &[String],
+) -> AppResult<NativePriceOut>
+where
+    M: Middleware + 'static,
+{
+    let usd_feed = native.usd_feed.ok_or_else(|| {
+        AppError::Config(format!(
+            "no {}/USD feed for chain {chain_id}; set NATIVE_USD_FEED / native_usd_feed",
+            native.symbol
+        ))
+    })?;
+    let mut wanted: Vec<String> = Vec::new();
+    for currency in currencies {
+        let currency = currency.trim().to_uppercase();
+        if currency != "USD" && fiat_usd_feed(chain_id, &currency).is_none() {
+            return Err(AppError::InvalidInput(format!(
+                "no {currency}/USD feed on chain {chain_id}"
+            )));
+        }
+        if !wanted.contains(&currency) {
+            wanted.push(currency);
+        }
+    }
+    if wanted.is_empty() {
+        wanted.push("USD".into());
+    }
+
+    let round = price::read_chainlink_round(provider.clone(), decimals, usd_feed).await?;
+    let native_usd = positive(round.answer, usd_feed)?;
+    let mut prices = Vec::with_capacity(wanted.len());
+    for currency in wanted {
+        let (price, source) = match fiat_usd_feed(chain_id, &currency) {
+            None => (native_usd, "chainlink"),
+            Some(feed) => {
+                let round = price::read_chainlink_round(provider.clone(), decimals, feed).await?;
+                let fiat_usd = positive(round.answer, feed)?;
+                (native_usd / fiat_usd, "chainlink (via USD)")
+            }
+        };
+        prices.push(FiatPriceOut {
+            currency,
+            price: price.normalize().to_string(),
+            source: source.into(),
+        });
+    }
+
+    Ok(NativePriceOut {
+        symbol: native.symbol.clone(),
+        prices,
+        updated_at: round.updated_at.low_u64(),
+        block_number: None,
+        warnings: Vec::new(),
+    })
+}
+
+/// The `<fiat>/USD` feeds `native_price` can divide by on `chain_id`.
+pub fn fiat_usd_feeds(chain_id: u64) -> Vec<Address> {
+    FIAT_USD_FEEDS
+        .iter()
+        .filter(|(id, _, _)| *id == chain_id)
+        .map(|(_, _, feed)| feed.parse().expect("valid fiat/USD feed address"))
+        .collect()
+}
+
+/// Every fiat other than USD with a feed on some chain, in table order.
+pub fn fiat_currencies() -> Vec<&'static str> {
+    let mut currencies: Vec<&str> = Vec::new();
+    for (_, fiat, _) in FIAT_USD_FEEDS {
+        if !currencies.contains(&fiat) {
+            currencies.push(fiat);
+        }
+    }
+    currencies
+}
+
+pub fn fiat_usd_feed(chain_id: u64, currency: &str) -> Option<Address> {
+    FIAT_USD_FEEDS
+        .iter()
+        .find(|(id, fiat, _)| *id == chain_id && *fiat == currency)
+        .map(|(_, _, feed)| feed.parse().expect("valid fiat/USD feed address"))
+}
+
+fn positive(answer: Decimal, feed: Address) -> AppResult<Decimal> {
+    if answer <= Decimal::ZERO {
+        return Err(AppError::Price(format!(
+            "Chainlink price feed {feed:#x} returned non-positive answer {answer}"
+        )));
+    }
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{Token, encode},
+        providers::{MockProvider, Provider},
+        types::U256,
+    };
+
+    fn push_answer(mock: &MockProvider, answer: u64) {
+        let round = encode(&[
+            Token::Uint(U256::from(7u64)),
+            Token::Int(U256::from(answer)),
+            Token::Uint(U256::from(1_700_000_000u64)),
+            Token::Uint(U256::from(1_700_000_000u64)),
+            Token::Uint(U256::from(7u64)),
+        ]);
+        mock.push::<String, _>(format!("0x{}", hex::encode(round)))
+            .unwrap();
+    }
+
+    fn push_round(mock: &MockProvider, answer: u64, decimals: u8) {
+        // Responses are consumed in reverse order: the round is read after `decimals()`.
+        push_answer(mock, answer);
+        let decimals = encode(&[Token::Uint(U256::from(decimals))]);
+        mock.push::<String, _>(format!("0x{}", hex::encode(decimals)))
+            .unwrap();
+    }
+
+    #[test]
+    fn table_covers_non_eth_chains() {
+        let polygon = NativeCurrency::for_chain(137);
+        assert_eq!(polygon.symbol, "MATIC");
+        assert_eq!(
+            polygon.wrapped,
+            Some(
+                "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"
+                    .parse()
+                    .unwrap()
+            )
+        );
+
+        let unknown = NativeCurrency::for_chain(31_337);
+        assert_eq!(unknown.symbol, "ETH");
+        assert_eq!(unknown.wrapped, None);
+    }
+
+    #[tokio::test]
+    async fn converts_native_usd_into_other_fiats() {
+        let (provider, mock) = Provider::mocked();
+        push_round(&mock, 108_000_000, 8); // EUR/USD 1.08
+        push_round(&mock, 324_000_000_000, 8); // ETH/USD 3240
+
+        let out = native_price(
+            Arc::new(provider),
+            &FeedDecimals::default(),
+            &NativeCurrency::for_chain(1),
+            1,
+            &["usd".into(), "EUR".into(), "USD".into()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.symbol, "ETH");
+        assert_eq!(out.updated_at, 1_700_000_000);
+        assert_eq!(out.prices.len(), 2);
+        assert_eq!(out.prices[0].price, "3240");
+        assert_eq!(out.prices[1].currency, "EUR");
+        assert_eq!(out.prices[1].price, "3000");
+        assert_eq!(out.prices[1].source, "chainlink (via USD)");
+    }
+
+    #[tokio::test]
+    async fn feed_decimals_are_read_once() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let decimals = FeedDecimals::default();
+        let native = NativeCurrency::for_chain(1);
+        let usd = ["USD".to_string()];
+
+        push_round(&mock, 324_000_000_000, 8);
+        native_price(provider.clone(), &decimals, &native, 1, &usd)
+            .await
+            .unwrap();
+        assert_eq!(decimals.get(native.usd_feed.unwrap()), Some(8));
+
+        // Only the round this time; a second `decimals()` call would find no response.
+        push_answer(&mock, 330_000_000_000);
+        let out = native_price(provider, &decimals, &native, 1, &usd)
+            .await
+            .unwrap();
+        assert_eq!(out.prices[0].price, "3300");
+    }
+
+    #[tokio::test]
+    async fn unknown_fiat_is_rejected_before_any_call() {
+        let (provider, _mock) = Provider::mocked();
+        let err = native_price(
+            Arc::new(provider),
+            &FeedDecimals::default(),
+            &NativeCurrency::for_chain(137),
+            137,
+            &["EUR".into()],
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AppError::InvalidInput(_)));
+    }
+}
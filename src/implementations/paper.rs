@@ -0,0 +1,467 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        price::TokenRegistry,
+        retention::RetentionPolicy,
+        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+    },
+    types::{
+        MaintenanceItemOut, PaperBalanceOut, PaperPortfolioOut, PaperTradeKind, PaperTradeOut,
+    },
+};
+
+/// One change to the virtual balances, as journaled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperTrade {
+    pub kind: PaperTradeKind,
+    /// Unix time the trade was booked.
+    pub at: u64,
+    /// Token debited; absent for funding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_in: Option<Address>,
+    #[serde(default)]
+    pub amount_in: U256,
+    /// Token credited; absent for transfers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_out: Option<Address>,
+    #[serde(default)]
+    pub amount_out: U256,
+    /// Transfer recipient.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    /// Block the swap was quoted at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl PaperTrade {
+    pub fn fund(token: Address, amount: U256, at: u64) -> Self {
+        Self {
+            kind: PaperTradeKind::Fund,
+            at,
+            token_in: None,
+            amount_in: U256::zero(),
+            token_out: Some(token),
+            amount_out: amount,
+            to: None,
+            block_number: None,
+            memo: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn swap(
+        token_in: Address,
+        amount_in: U256,
+        token_out: Address,
+        amount_out: U256,
+        block_number: u64,
+        at: u64,
+    ) -> Self {
+        Self {
+            kind: PaperTradeKind::Swap,
+            at,
+            token_in: Some(token_in),
+            amount_in,
+            token_out: Some(token_out),
+            amount_out,
+            to: None,
+            block_number: Some(block_number),
+            memo: None,
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn transfer(token: Address, amount: U256, to: Address, at: u64) -> Self {
+        Self {
+            kind: PaperTradeKind::Transfer,
+            at,
+            token_in: Some(token),
+            amount_in: amount,
+            token_out: None,
+            amount_out: U256::zero(),
+            to: Some(to),
+            block_number: None,
+            memo: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Attach a `memo` and `tags` already checked by [`crate::activity::annotations`].
+    pub fn annotated(mut self, (memo, tags): (Option<String>, Vec<String>)) -> Self {
+        self.memo = memo;
+        self.tags = tags;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PaperBook {
+    chain_id: u64,
+    balances: BTreeMap<Address, U256>,
+    trades: VecDeque<PaperTrade>,
+}
+
+/// A virtual portfolio that paper-trading tools debit and credit at real quoted prices, so a
+/// strategy can be evaluated without signing anything.
+///
+/// Balances start empty and only change through `paper_fund`, `paper_swap` and
+/// `paper_transfer`. With a path configured the book persists as JSON and is rewritten after
+/// every trade; otherwise it lasts until restart. The trade history follows the log retention
+/// policy; balances are kept regardless.
+#[derive(Debug, Default)]
+pub struct PaperPortfolio {
+    path: Option<PathBuf>,
+    book: Mutex<PaperBook>,
+    policy: RetentionPolicy,
+}
+
+impl PaperPortfolio {
+    /// An empty portfolio held in memory only.
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            path: None,
+            book: Mutex::new(PaperBook {
+                chain_id,
+                ..PaperBook::default()
+            }),
+            policy: RetentionPolicy::default(),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig, chain_id: u64) -> AppResult<Self> {
+        let Some(path) = config.paper_portfolio_path.as_deref() else {
+            return Ok(Self {
+                policy: RetentionPolicy::from_config(config),
+                ..Self::new(chain_id)
+            });
+        };
+        // A missing file is an empty portfolio; it is created on the first trade.
+        let book: PaperBook = match fs::read_to_string(path) {
+            Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw).map_err(|err| {
+                AppError::Config(format!("failed to parse paper portfolio {path}: {err}"))
+            })?,
+            Ok(_) => PaperBook::default(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => PaperBook::default(),
+            Err(err) => {
+                return Err(AppError::Config(format!(
+                    "failed to read paper portfolio {path}: {err}"
+                )));
+            }
+        };
+        if book.chain_id != 0 && book.chain_id != chain_id {
+            return Err(AppError::Config(format!(
+                "paper portfolio {path} was kept on chain {}, not the active chain {chain_id}; point paper_portfolio_path elsewhere",
+                book.chain_id
+            )));
+        }
+
+        Ok(Self {
+            path: Some(PathBuf::from(path)),
+            book: Mutex::new(PaperBook { chain_id, ..book }),
+            policy: RetentionPolicy::from_config(config),
+        })
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Virtual balance of `token`; zero when it was never credited.
+    pub fn balance(&self, token: Address) -> U256 {
+        let book = self.book.lock().expect("paper portfolio lock poisoned");
+        book.balances.get(&token).copied().unwrap_or_default()
+    }
+
+    /// Book `trade`: debit `token_in`, credit `token_out`, and persist. Nothing changes when the
+    /// debit exceeds the virtual balance or the file cannot be written.
+    pub fn book(&self, trade: PaperTrade) -> AppResult<PaperTrade> {
+        let mut book = self.book.lock().expect("paper portfolio lock poisoned");
+        let mut next = book.clone();
+        if let Some(token) = trade.token_in {
+            let held = next.balances.get(&token).copied().unwrap_or_default();
+            if held < trade.amount_in {
+                return Err(AppError::Wallet(format!(
+                    "paper balance of {token:#x} is {held}, below the requested {}",
+                    trade.amount_in
+                )));
+            }
+            let left = held - trade.amount_in;
+            if left.is_zero() {
+                next.balances.remove(&token);
+            } else {
+                next.balances.insert(token, left);
+            }
+        }
+        if let Some(token) = trade.token_out {
+            let held = next.balances.entry(token).or_default();
+            *held = held.checked_add(trade.amount_out).ok_or_else(|| {
+                AppError::InvalidInput(format!("paper balance of {token:#x} would overflow"))
+            })?;
+        }
+        next.trades.push_back(trade.clone());
+        self.policy
+            .prune(&mut next.trades, |trade| trade.at, trade.at);
+        self.persist(&next)?;
+        *book = next;
+        Ok(trade)
+    }
+
+    /// Drop every balance and trade.
+    pub fn reset(&self) -> AppResult<()> {
+        let mut book = self.book.lock().expect("paper portfolio lock poisoned");
+        let next = PaperBook {
+            chain_id: book.chain_id,
+            ..PaperBook::default()
+        };
+        self.persist(&next)?;
+        *book = next;
+        Ok(())
+    }
+
+    /// Apply the retention policy to the trade history at `now`, rewriting the file when trades
+    /// were dropped; returns how many.
+    pub fn prune(&self, now: u64) -> AppResult<usize> {
+        let mut book = self.book.lock().expect("paper portfolio lock poisoned");
+        let mut next = book.clone();
+        let removed = self.policy.prune(&mut next.trades, |trade| trade.at, now);
+        if removed > 0 {
+            self.persist(&next)?;
+            *book = next;
+        }
+        Ok(removed)
+    }
+
+    pub fn status(&self, removed: usize) -> MaintenanceItemOut {
+        let book = self.book.lock().expect("paper portfolio lock poisoned");
+        MaintenanceItemOut::log("paper_trades", book.trades.len(), &self.policy, removed)
+    }
+
+    /// Balances and the `trade_limit` most recent trades, newest first, formatted with the
+    /// registry's decimals. Only trades tagged `tag` are listed when given.
+    pub fn snapshot(
+        &self,
+        registry: &TokenRegistry,
+        trade_limit: usize,
+        tag: Option<&str>,
+    ) -> PaperPortfolioOut {
+        let tag = tag.map(|tag| tag.trim().to_lowercase());
+        let book = self.book.lock().expect("paper portfolio lock poisoned");
+        PaperPortfolioOut {
+            chain_id: book.chain_id,
+            persistent: self.is_persistent(),
+            balances: book
+                .balances
+                .iter()
+                .map(|(token, amount)| balance_out(registry, *token, *amount))
+                .collect(),
+            trade: None,
+            trade_count: book.trades.len(),
+            trades: book
+                .trades
+                .iter()
+                .rev()
+                .filter(|trade| tag.as_ref().is_none_or(|tag| trade.tags.contains(tag)))
+                .take(trade_limit)
+                .map(|trade| trade_out(registry, trade))
+                .collect(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn persist(&self, book: &PaperBook) -> AppResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let raw = serde_json::to_string_pretty(book)?;
+        fs::write(path, raw).map_err(|err| {
+            AppError::Io(format!(
+                "failed to persist paper portfolio {}: {err}",
+                path.display()
+            ))
+        })
+    }
+}
+
+/// What `amount_in` of `from` buys of `to` on the `fee` pool, from the Uniswap V3 quoter: the
+/// fill a paper swap is booked at.
+pub async fn quote_fill<M>(
+    provider: Arc<M>,
+    quoter: Address,
+    from: Address,
+    to: Address,
+    amount_in: U256,
+    fee: u32,
+) -> AppResult<U256>
+where
+    M: Middleware + 'static,
+{
+    let (amount_out, _, _, _) = UniswapQuoterV2::new(quoter, provider)
+        .quote_exact_input_single(QuoteExactInputSingleParams {
+            token_in: from,
+            token_out: to,
+            amount_in,
+            fee,
+            sqrt_price_limit_x96: U256::zero(),
+        })
+        .call()
+        .await
+        .map_err(|err| AppError::Swap(format!("uniswap quoter call failed: {err}")))?;
+    if amount_out.is_zero() {
+        return Err(AppError::Swap("quote returned zero output amount".into()));
+    }
+    Ok(amount_out)
+}
+
+/// A positive integer amount in base units.
+pub fn parse_amount(field: &str, raw: &str) -> AppResult<U256> {
+    let amount = U256::from_dec_str(raw.trim())
+        .map_err(|_| AppError::InvalidInput(format!("invalid {field}: {raw}")))?;
+    if amount.is_zero() {
+        return Err(AppError::InvalidInput(format!(
+            "{field} must be greater than zero"
+        )));
+    }
+    Ok(amount)
+}
+
+fn format_amount(registry: &TokenRegistry, token: Address, amount: U256) -> Option<String> {
+    registry
+        .info_by_address(token)
+        .map(|info| balance::format_with_decimals(&amount, info.decimals as u32))
+}
+
+fn balance_out(registry: &TokenRegistry, token: Address, amount: U256) -> PaperBalanceOut {
+    PaperBalanceOut {
+        token: format!("{token:#x}"),
+        symbol: registry
+            .info_by_address(token)
+            .map(|info| info.symbol.clone()),
+        amount_wei: amount.to_string(),
+        amount: format_amount(registry, token, amount),
+    }
+}
+
+/// A journaled trade as returned to callers.
+pub fn trade_out(registry: &TokenRegistry, trade: &PaperTrade) -> PaperTradeOut {
+    let leg = |token: Option<Address>, amount: U256| {
+        token.map(|token| balance_out(registry, token, amount))
+    };
+    PaperTradeOut {
+        kind: trade.kind,
+        at: trade.at,
+        sold: leg(trade.token_in, trade.amount_in),
+        bought: leg(trade.token_out, trade.amount_out),
+        execution_price: execution_price(registry, trade),
+        to: trade.to.map(|to| format!("{to:#x}")),
+        block_number: trade.block_number,
+        memo: trade.memo.clone(),
+        tags: trade.tags.clone(),
+    }
+}
+
+fn execution_price(registry: &TokenRegistry, trade: &PaperTrade) -> Option<String> {
+    if trade.kind != PaperTradeKind::Swap {
+        return None;
+    }
+    let human = |token: Option<Address>, amount: U256| {
+        format_amount(registry, token?, amount).and_then(|text| Decimal::from_str(&text).ok())
+    };
+    let sold = human(trade.token_in, trade.amount_in)?;
+    let bought = human(trade.token_out, trade.amount_out)?;
+    bought
+        .checked_div(sold)
+        .map(|price| price.round_dp(18).normalize().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trades_move_virtual_balances() {
+        let portfolio = PaperPortfolio::new(1);
+        let (weth, usdc) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        portfolio
+            .book(PaperTrade::fund(weth, U256::from(10), 100))
+            .unwrap();
+        portfolio
+            .book(PaperTrade::swap(
+                weth,
+                U256::from(4),
+                usdc,
+                U256::from(12_000),
+                7,
+                101,
+            ))
+            .unwrap();
+        assert_eq!(portfolio.balance(weth), U256::from(6));
+        assert_eq!(portfolio.balance(usdc), U256::from(12_000));
+
+        // An overdraft books nothing.
+        let err = portfolio
+            .book(PaperTrade::transfer(weth, U256::from(7), usdc, 102))
+            .unwrap_err();
+        assert!(matches!(err, AppError::Wallet(_)));
+        assert_eq!(portfolio.balance(weth), U256::from(6));
+
+        portfolio
+            .book(PaperTrade::transfer(weth, U256::from(6), usdc, 103))
+            .unwrap();
+        let snapshot = portfolio.snapshot(&TokenRegistry::with_defaults(), 2, None);
+        assert_eq!(snapshot.balances.len(), 1);
+        assert_eq!(snapshot.trade_count, 3);
+        assert_eq!(snapshot.trades[0].kind, PaperTradeKind::Transfer);
+
+        portfolio.reset().unwrap();
+        assert!(portfolio.balance(usdc).is_zero());
+    }
+
+    #[test]
+    fn persists_every_trade() {
+        let path = std::env::temp_dir().join(format!("paper-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let portfolio = PaperPortfolio {
+            path: Some(path.clone()),
+            ..PaperPortfolio::new(1)
+        };
+        portfolio
+            .book(PaperTrade::fund(
+                Address::repeat_byte(1),
+                U256::from(5),
+                100,
+            ))
+            .unwrap();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        let reloaded: PaperBook = serde_json::from_str(&raw).unwrap();
+        assert_eq!(reloaded.chain_id, 1);
+        assert_eq!(reloaded.trades.len(), 1);
+        assert_eq!(
+            reloaded.balances.get(&Address::repeat_byte(1)),
+            Some(&U256::from(5))
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
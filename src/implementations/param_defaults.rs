@@ -0,0 +1,250 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    schemas,
+};
+
+/// Params that name the token(s) a call is about; a rule with `token` applies when any of them
+/// matches.
+const TOKEN_PARAMS: &[&str] = &["token", "from_token", "to_token", "base", "target"];
+
+/// Values used for params a caller leaves out, e.g. a tighter `slippage_bps` for stablecoin
+/// swaps. Either key may be omitted: without `tool` a rule covers every tool taking its params,
+/// without `token` every token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParamDefaultRule {
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Token symbol or address, compared with the call's token params as written.
+    #[serde(default)]
+    pub token: Option<String>,
+    pub params: Map<String, Value>,
+}
+
+impl ParamDefaultRule {
+    /// Tool and token rules beat token rules, which beat tool rules, which beat global ones.
+    fn specificity(&self) -> u8 {
+        u8::from(self.token.is_some()) * 2 + u8::from(self.tool.is_some())
+    }
+
+    fn applies(&self, method: &str, tokens: &[&str]) -> bool {
+        self.tool.as_deref().is_none_or(|tool| tool == method)
+            && self.token.as_deref().is_none_or(|token| {
+                tokens
+                    .iter()
+                    .any(|candidate| candidate.trim().eq_ignore_ascii_case(token.trim()))
+            })
+    }
+}
+
+/// Configured param defaults, checked against the tool schemas at startup.
+#[derive(Debug, Default)]
+pub struct ParamDefaults {
+    /// Most specific first; config order among equals.
+    rules: Vec<ParamDefaultRule>,
+    /// Each tool's param schemas by name.
+    properties: HashMap<String, Map<String, Value>>,
+}
+
+impl ParamDefaults {
+    pub fn from_config(config: &AppConfig) -> AppResult<Self> {
+        Self::new(config.param_defaults.clone())
+    }
+
+    pub fn new(mut rules: Vec<ParamDefaultRule>) -> AppResult<Self> {
+        let properties: HashMap<String, Map<String, Value>> = schemas::catalog()
+            .into_iter()
+            .map(|tool| {
+                let properties = tool.params["properties"]
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default();
+                (tool.name, properties)
+            })
+            .collect();
+
+        for rule in &rules {
+            let scope = match rule.tool.as_deref() {
+                Some(tool) if !properties.contains_key(tool) => {
+                    return Err(AppError::Config(format!(
+                        "param_defaults names unknown tool {tool}"
+                    )));
+                }
+                Some(tool) => vec![(tool, &properties[tool])],
+                None => properties
+                    .iter()
+                    .map(|(name, properties)| (name.as_str(), properties))
+                    .collect(),
+            };
+            for (param, value) in &rule.params {
+                let schemas: Vec<&Value> = scope
+                    .iter()
+                    .filter_map(|(_, properties)| properties.get(param))
+                    .collect();
+                if schemas.is_empty() {
+                    return Err(AppError::Config(format!(
+                        "param_defaults sets {param}, which {} does not take",
+                        rule.tool.as_deref().unwrap_or("no tool")
+                    )));
+                }
+                if !schemas.iter().any(|schema| type_matches(schema, value)) {
+                    return Err(AppError::Config(format!(
+                        "param_defaults sets {param} to {value}, which is not a valid {param}"
+                    )));
+                }
+            }
+        }
+        rules.sort_by_key(|rule| Reverse(rule.specificity()));
+        Ok(Self { rules, properties })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `params` with every param the caller left out (or set to `null`) filled from the most
+    /// specific matching rule.
+    pub fn apply(&self, method: &str, params: Value) -> Value {
+        let Some(properties) = self.properties.get(method) else {
+            return params;
+        };
+        if self.rules.is_empty() {
+            return params;
+        }
+        let was_null = params.is_null();
+        let mut object = match params {
+            Value::Object(object) => object,
+            Value::Null => Map::new(),
+            other => return other,
+        };
+        let tokens: Vec<&str> = TOKEN_PARAMS
+            .iter()
+            .filter_map(|param| object.get(*param)?.as_str())
+            .collect();
+        let mut defaults = Map::new();
+        for rule in self
+            .rules
+            .iter()
+            .filter(|rule| rule.applies(method, &tokens))
+        {
+            for (param, value) in &rule.params {
+                let given = object.get(param).is_some_and(|given| !given.is_null());
+                if !given && properties.contains_key(param) && !defaults.contains_key(param) {
+                    defaults.insert(param.clone(), value.clone());
+                }
+            }
+        }
+        if defaults.is_empty() && was_null {
+            return Value::Null;
+        }
+        object.extend(defaults);
+        Value::Object(object)
+    }
+}
+
+/// Whether `value` has one of the JSON types `schema` allows; schemas without a `type` allow
+/// anything.
+fn type_matches(schema: &Value, value: &Value) -> bool {
+    let allowed: Vec<&str> = match &schema["type"] {
+        Value::String(kind) => vec![kind.as_str()],
+        Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => return true,
+    };
+    allowed.iter().any(|kind| match *kind {
+        "integer" => value.is_u64() || value.is_i64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(tool: Option<&str>, token: Option<&str>, params: Value) -> ParamDefaultRule {
+        ParamDefaultRule {
+            tool: tool.map(str::to_string),
+            token: token.map(str::to_string),
+            params: params.as_object().cloned().unwrap(),
+        }
+    }
+
+    #[test]
+    fn most_specific_rule_fills_missing_params() {
+        let defaults = ParamDefaults::new(vec![
+            rule(None, None, json!({ "slippage_bps": 100 })),
+            rule(
+                Some("swap_tokens"),
+                None,
+                json!({ "slippage_bps": 50, "fee": 500 }),
+            ),
+            rule(
+                Some("swap_tokens"),
+                Some("USDC"),
+                json!({ "slippage_bps": 5 }),
+            ),
+        ])
+        .unwrap();
+
+        let stable = defaults.apply(
+            "swap_tokens",
+            json!({ "from_token": "usdc", "to_token": "DAI", "amount_in_wei": "1" }),
+        );
+        assert_eq!(stable["slippage_bps"], 5);
+        assert_eq!(stable["fee"], 500);
+
+        let explicit = defaults.apply(
+            "swap_tokens",
+            json!({ "from_token": "WETH", "to_token": "PEPE", "slippage_bps": 300, "fee": null }),
+        );
+        assert_eq!(explicit["slippage_bps"], 300);
+        assert_eq!(explicit["fee"], 500);
+
+        let rebalance = defaults.apply("plan_rebalance", json!({ "targets": [] }));
+        assert_eq!(rebalance["slippage_bps"], 100);
+        // Only params the tool takes are filled in.
+        assert_eq!(
+            defaults.apply("paper_swap", json!({ "from_token": "USDC" })),
+            json!({ "from_token": "USDC" })
+        );
+        assert_eq!(defaults.apply("get_chain_info", Value::Null), Value::Null);
+    }
+
+    #[test]
+    fn rejects_rules_the_schemas_do_not_allow() {
+        let unknown_tool = ParamDefaults::new(vec![rule(Some("swap"), None, json!({}))]);
+        assert!(
+            unknown_tool
+                .unwrap_err()
+                .to_string()
+                .contains("unknown tool")
+        );
+
+        let unknown_param =
+            ParamDefaults::new(vec![rule(Some("get_balance"), None, json!({ "fee": 500 }))]);
+        assert!(
+            unknown_param
+                .unwrap_err()
+                .to_string()
+                .contains("does not take")
+        );
+
+        let wrong_type = ParamDefaults::new(vec![rule(
+            Some("swap_tokens"),
+            None,
+            json!({ "slippage_bps": "30" }),
+        )]);
+        assert!(wrong_type.unwrap_err().to_string().contains("not a valid"));
+    }
+}
@@ -0,0 +1,372 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, BlockNumber, Bytes, TransactionRequest, U256,
+        transaction::eip2718::TypedTransaction,
+    },
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        erc20::{self, Erc20Token, Weth9},
+        revert,
+        swap::{self, QuotedSwap, SwapSettings, apply_gas_buffer},
+        timing::Timings,
+    },
+    types::{PlanStepOut, SwapPlanOut, SwapTokensParams},
+};
+
+/// Gas the router spends on top of the pool swap measured by the quoter (transfers, callback).
+pub(crate) const ROUTER_OVERHEAD_GAS: u64 = 60_000;
+
+/// Work out every transaction `signer` needs to perform the swap, in broadcast order.
+///
+/// Missing WETH is wrapped from ETH when `from_token` is `weth`, and the router is approved for
+/// exactly `amount_in` when the allowance is short. When nothing precedes it the swap is fully
+/// simulated; otherwise it cannot be dry-run yet, so its gas comes from the quoter plus router
+/// overhead.
+pub async fn plan_swap<M>(
+    provider: Arc<M>,
+    signer: LocalWallet,
+    from_token: Address,
+    to_token: Address,
+    weth: Option<Address>,
+    params: SwapTokensParams,
+    settings: SwapSettings,
+) -> AppResult<SwapPlanOut>
+where
+    M: Middleware + 'static,
+{
+    let owner = signer.address();
+    let amount_in = U256::from_dec_str(&params.amount_in_wei).map_err(|_| {
+        AppError::InvalidInput(format!("invalid numeric value: {}", params.amount_in_wei))
+    })?;
+    let first_nonce = provider
+        .get_transaction_count(owner, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch nonce: {err}")))?;
+    let mut steps = Vec::new();
+
+    let token_balance = erc20::fetch_balance_of(provider.clone(), from_token, owner).await?;
+    if token_balance < amount_in {
+        let shortfall = amount_in - token_balance;
+        let Some(weth) = weth.filter(|weth| *weth == from_token) else {
+            return Err(AppError::Swap(format!(
+                "insufficient balance of {from_token:#x}: have {token_balance}, need {amount_in}"
+            )));
+        };
+        let eth_balance = provider
+            .get_balance(owner, None)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to fetch ETH balance: {err}")))?;
+        if eth_balance < shortfall {
+            return Err(AppError::Swap(format!(
+                "insufficient balance: {shortfall} more wei of WETH needed but only {eth_balance} wei of ETH to wrap"
+            )));
+        }
+        let calldata = Weth9::new(weth, provider.clone()).deposit().calldata();
+        let description = format!(
+            "wrap {} ETH into WETH",
+            balance::format_with_decimals(&shortfall, 18)
+        );
+        steps.push(
+            estimated_step(
+                &provider,
+                owner,
+                "wrap",
+                description,
+                weth,
+                shortfall,
+                calldata,
+                &settings,
+            )
+            .await?,
+        );
+    }
+
+    let router = settings.uniswap.router;
+    let allowance = erc20::fetch_allowance(provider.clone(), from_token, owner, router).await?;
+    if allowance < amount_in {
+        let token = Erc20Token::new(from_token, provider.clone());
+        // Tokens such as USDT refuse to change a non-zero allowance, so reset it first.
+        if !allowance.is_zero() {
+            let calldata = token.approve(router, U256::zero()).calldata();
+            let description = "reset the router allowance to 0".to_string();
+            steps.push(
+                estimated_step(
+                    &provider,
+                    owner,
+                    "approve",
+                    description,
+                    from_token,
+                    U256::zero(),
+                    calldata,
+                    &settings,
+                )
+                .await?,
+            );
+        }
+        let calldata = token.approve(router, amount_in).calldata();
+        let description = format!("approve the router to spend {amount_in} of {from_token:#x}");
+        let approve = estimated_step(
+            &provider,
+            owner,
+            "approve",
+            description.clone(),
+            from_token,
+            U256::zero(),
+            calldata.clone(),
+            &settings,
+        )
+        .await;
+        let approve = match (approve, steps.last()) {
+            (Ok(step), _) => step,
+            // Those same tokens revert the estimate until the reset lands; it costs about the same.
+            (Err(_), Some(reset)) if !allowance.is_zero() => PlanStepOut {
+                description,
+                calldata_hex: format!("0x{}", hex::encode(calldata.unwrap_or_default())),
+                gas_estimate_source: "reset_step".into(),
+                ..reset.clone()
+            },
+            (Err(err), _) => return Err(err),
+        };
+        steps.push(approve);
+    }
+
+    let description = format!("swap {amount_in} of {from_token:#x} for {to_token:#x}");
+    let (swap_step, amount_out_estimate, amount_out_min, amount_out_min_raw) = if steps.is_empty() {
+        let simulated =
+            swap::simulate_swap(provider, signer, from_token, to_token, params, settings).await?;
+        let step = PlanStepOut {
+            kind: "swap".into(),
+            description,
+            to: simulated.router.clone(),
+            value_wei: "0".into(),
+            calldata_hex: simulated.calldata_hex.clone(),
+            nonce: String::new(),
+            gas_estimate: simulated.gas_estimate.clone(),
+            gas_limit: simulated.gas_limit.clone(),
+            gas_estimate_source: "eth_estimateGas".into(),
+            access_list: simulated.access_list.clone(),
+        };
+        (
+            step,
+            simulated.amount_out_estimate,
+            simulated.amount_out_min,
+            simulated.amount_out_min_raw,
+        )
+    } else {
+        let QuotedSwap {
+            amount_out,
+            amount_out_min,
+            quoter_gas_estimate,
+            calldata,
+            to_meta,
+            ..
+        } = swap::quote_swap(
+            provider,
+            owner,
+            from_token,
+            to_token,
+            &params,
+            settings.uniswap,
+            &mut Timings::default(),
+        )
+        .await?;
+        let gas_estimate = quoter_gas_estimate + U256::from(ROUTER_OVERHEAD_GAS);
+        let gas_limit = match params.gas_limit {
+            Some(limit) => U256::from(limit),
+            None => apply_gas_buffer(gas_estimate, settings.gas_buffer_percent),
+        };
+        let step = PlanStepOut {
+            kind: "swap".into(),
+            description,
+            to: format!("{router:#x}"),
+            value_wei: "0".into(),
+            calldata_hex: format!("0x{}", hex::encode(&calldata)),
+            nonce: String::new(),
+            gas_estimate: gas_estimate.to_string(),
+            gas_limit: gas_limit.to_string(),
+            gas_estimate_source: "quoter".into(),
+            access_list: None,
+        };
+        let decimals = to_meta.decimals as u32;
+        (
+            step,
+            balance::format_with_decimals(&amount_out, decimals),
+            balance::format_with_decimals(&amount_out_min, decimals),
+            amount_out_min.to_string(),
+        )
+    };
+    steps.push(swap_step);
+
+    let mut total_gas_limit = U256::zero();
+    for (index, step) in steps.iter_mut().enumerate() {
+        step.nonce = (first_nonce + U256::from(index)).to_string();
+        total_gas_limit += U256::from_dec_str(&step.gas_limit).unwrap_or_default();
+    }
+
+    Ok(SwapPlanOut {
+        block_number: None,
+        steps,
+        amount_out_estimate,
+        amount_out_min,
+        amount_out_min_raw,
+        total_gas_limit: total_gas_limit.to_string(),
+        warnings: Vec::new(),
+    })
+}
+
+/// Build a non-swap step, estimating its gas as a standalone transaction from `owner`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn estimated_step<M>(
+    provider: &Arc<M>,
+    owner: Address,
+    kind: &str,
+    description: String,
+    to: Address,
+    value: U256,
+    calldata: Option<Bytes>,
+    settings: &SwapSettings,
+) -> AppResult<PlanStepOut>
+where
+    M: Middleware + 'static,
+{
+    let calldata =
+        calldata.ok_or_else(|| AppError::Internal(format!("failed to build {kind} calldata")))?;
+    let tx: TypedTransaction = TransactionRequest::new()
+        .from(owner)
+        .to(to)
+        .value(value)
+        .data(calldata.clone())
+        .into();
+    let gas_estimate = provider.estimate_gas(&tx, None).await.map_err(|err| {
+        revert::annotate(
+            AppError::Swap(format!("{kind} gas estimation failed: {err}")),
+            &err,
+        )
+    })?;
+
+    Ok(PlanStepOut {
+        kind: kind.to_string(),
+        description,
+        to: format!("{to:#x}"),
+        value_wei: value.to_string(),
+        calldata_hex: format!("0x{}", hex::encode(&calldata)),
+        nonce: String::new(),
+        gas_estimate: gas_estimate.to_string(),
+        gas_limit: apply_gas_buffer(gas_estimate, settings.gas_buffer_percent).to_string(),
+        gas_estimate_source: "eth_estimateGas".into(),
+        access_list: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{self, Token},
+        providers::Provider,
+    };
+
+    fn encoded(tokens: &[Token]) -> String {
+        format!("0x{}", hex::encode(abi::encode(tokens)))
+    }
+
+    #[tokio::test]
+    async fn plans_approve_before_swap_when_allowance_is_short() {
+        let (provider, mock) = Provider::mocked();
+        let signer: LocalWallet =
+            "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+                .parse()
+                .unwrap();
+        let from_token = Address::from_low_u64_be(1);
+        let to_token = Address::from_low_u64_be(2);
+
+        // Responses are consumed in reverse order.
+        mock.push::<String, _>(encoded(&[
+            Token::Uint(U256::from(2_500_000u64)),
+            Token::Uint(U256::from(1u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(90_000u64)),
+        ]))
+        .unwrap(); // quote
+        mock.push::<String, _>(encoded(&[Token::String("USDC".into())]))
+            .unwrap();
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(6u8))]))
+            .unwrap();
+        mock.push::<U256, _>(U256::from(46_000u64)).unwrap(); // approve estimate_gas
+        mock.push::<U256, _>(U256::from(46_000u64)).unwrap(); // reset estimate_gas
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(5u64))]))
+            .unwrap(); // allowance
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(1_000u64))]))
+            .unwrap(); // balanceOf
+        mock.push::<U256, _>(U256::from(7u64)).unwrap(); // nonce
+
+        let params: SwapTokensParams = serde_json::from_value(serde_json::json!({
+            "from_token": format!("{from_token:#x}"),
+            "to_token": format!("{to_token:#x}"),
+            "amount_in_wei": "1000",
+        }))
+        .unwrap();
+
+        let plan = plan_swap(
+            Arc::new(provider),
+            signer,
+            from_token,
+            to_token,
+            None,
+            params,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap();
+
+        let kinds: Vec<&str> = plan.steps.iter().map(|step| step.kind.as_str()).collect();
+        assert_eq!(kinds, ["approve", "approve", "swap"]);
+        let nonces: Vec<&str> = plan.steps.iter().map(|step| step.nonce.as_str()).collect();
+        assert_eq!(nonces, ["7", "8", "9"]);
+        assert_eq!(plan.steps[2].gas_estimate, "150000");
+        assert_eq!(plan.steps[2].gas_estimate_source, "quoter");
+        assert_eq!(plan.amount_out_estimate, "2.5");
+        assert_eq!(plan.total_gas_limit, (55_200 * 2 + 180_000).to_string());
+    }
+
+    #[tokio::test]
+    async fn refuses_when_balance_is_short_and_token_is_not_weth() {
+        let (provider, mock) = Provider::mocked();
+        let signer: LocalWallet =
+            "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+                .parse()
+                .unwrap();
+
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(10u64))]))
+            .unwrap(); // balanceOf
+        mock.push::<U256, _>(U256::zero()).unwrap(); // nonce
+
+        let params: SwapTokensParams = serde_json::from_value(serde_json::json!({
+            "from_token": "0x0000000000000000000000000000000000000001",
+            "to_token": "0x0000000000000000000000000000000000000002",
+            "amount_in_wei": "1000",
+        }))
+        .unwrap();
+        let err = plan_swap(
+            Arc::new(provider),
+            signer,
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            None,
+            params,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AppError::Swap(msg) if msg.contains("insufficient balance")));
+    }
+}
@@ -0,0 +1,356 @@
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, BlockNumber, U256},
+};
+use rust_decimal::{
+    Decimal,
+    prelude::{FromPrimitive, ToPrimitive},
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        block_scope::BlockScoped,
+        erc20,
+        price::{self, TokenInfo, TokenRegistry},
+        rebalance::usd,
+        warnings,
+    },
+    progress::ProgressReporter,
+    types::{PortfolioRiskOut, QuoteCurrency, RiskHoldingOut},
+};
+
+/// Blocks in a day at Ethereum's 12-second slots; samples are this far apart.
+pub const BLOCKS_PER_DAY: u64 = 7_200;
+/// Longest lookback `get_portfolio_risk` samples; each day is a historical price lookup per asset.
+pub const MAX_RISK_LOOKBACK_DAYS: u32 = 90;
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// One non-zero balance, priced through `priced_as` (the wrapped token for the native coin).
+struct Holding {
+    token: Option<Address>,
+    symbol: String,
+    balance: String,
+    priced_as: Address,
+    stablecoin: bool,
+}
+
+/// Value `owner`'s non-zero balances of the native coin and `tokens` at `block_number`, and
+/// measure their risk over the `lookback_days` before it.
+///
+/// Prices come from the registry's Chainlink-first policy at one block per day, oldest first;
+/// the last sample is `block_number` itself and values the holdings. Volatility is the standard
+/// deviation of daily log returns, and the portfolio's is that of the current weights' combined
+/// daily return. A holding without a price at `block_number` is listed unvalued; one without
+/// enough history has no volatility. Both are warned about. Reports each sampled block to
+/// `progress`.
+pub async fn assess<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    owner: Address,
+    tokens: &[&TokenInfo],
+    block_number: u64,
+    lookback_days: u32,
+    progress: &ProgressReporter,
+) -> AppResult<PortfolioRiskOut>
+where
+    M: Middleware + 'static,
+{
+    if lookback_days == 0 || lookback_days > MAX_RISK_LOOKBACK_DAYS {
+        return Err(AppError::InvalidInput(format!(
+            "lookback_days must be between 1 and {MAX_RISK_LOOKBACK_DAYS}"
+        )));
+    }
+    let head = pin(provider.clone(), block_number).await?;
+    let holdings = read_holdings(head, registry, owner, tokens).await?;
+    if holdings.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "{owner:#x} holds none of the requested assets"
+        )));
+    }
+
+    // Daily samples ending on `block_number`; the earliest ones are dropped near genesis.
+    let blocks: Vec<u64> = (0..=u64::from(lookback_days))
+        .rev()
+        .filter_map(|day| block_number.checked_sub(day * BLOCKS_PER_DAY))
+        .collect();
+    let total = blocks.len() as u64;
+    // prices[holding][sample], with the native coin sharing its wrapped token's lookups.
+    let mut prices = vec![Vec::with_capacity(blocks.len()); holdings.len()];
+    let mut dex_priced = Vec::new();
+    for (index, block) in blocks.iter().copied().enumerate() {
+        let scoped = pin(provider.clone(), block).await?;
+        let mut looked_up: Vec<(Address, Option<Decimal>)> = Vec::new();
+        for (holding, series) in holdings.iter().zip(prices.iter_mut()) {
+            let known = looked_up
+                .iter()
+                .find(|(address, _)| *address == holding.priced_as)
+                .map(|(_, price)| *price);
+            let price = match known {
+                Some(price) => price,
+                None => {
+                    let out = price::resolve_token_price(
+                        scoped.clone(),
+                        registry,
+                        holding.priced_as,
+                        QuoteCurrency::USD,
+                    )
+                    .await
+                    .ok();
+                    if let Some(out) = &out
+                        && !out.source.contains("chainlink")
+                        && !dex_priced.contains(&holding.symbol)
+                    {
+                        dex_priced.push(holding.symbol.clone());
+                    }
+                    let price = out.and_then(|out| Decimal::from_str(&out.price).ok());
+                    looked_up.push((holding.priced_as, price));
+                    price
+                }
+            };
+            series.push(price);
+        }
+        progress.report(
+            index as u64 + 1,
+            Some(total),
+            format!("priced block {block}"),
+        );
+    }
+
+    let mut warnings = Vec::new();
+    let mut values = Vec::with_capacity(holdings.len());
+    for (holding, series) in holdings.iter().zip(&prices) {
+        let value = match series.last().copied().flatten() {
+            Some(price) => Some((
+                price,
+                price
+                    * Decimal::from_str(&holding.balance).map_err(|err| {
+                        AppError::Internal(format!("invalid {} balance: {err}", holding.symbol))
+                    })?,
+            )),
+            None => {
+                warnings.push(warnings::warning(
+                    "unpriced_holding",
+                    format!(
+                        "no USD price for {} at block {block_number}; it is left out of the totals",
+                        holding.symbol
+                    ),
+                ));
+                None
+            }
+        };
+        values.push(value);
+    }
+    let total_value: Decimal = values.iter().flatten().map(|(_, value)| *value).sum();
+    let weights: Vec<Decimal> = values
+        .iter()
+        .map(|value| match value {
+            Some((_, value)) if total_value > Decimal::ZERO => *value / total_value,
+            _ => Decimal::ZERO,
+        })
+        .collect();
+
+    let returns: Vec<Vec<Option<f64>>> = prices.iter().map(|series| log_returns(series)).collect();
+    let mut out = Vec::with_capacity(holdings.len());
+    for (((holding, value), weight), series) in
+        holdings.iter().zip(&values).zip(&weights).zip(&returns)
+    {
+        let daily = volatility(series.iter().flatten().copied());
+        if daily.is_none() && value.is_some() {
+            warnings.push(warnings::warning(
+                "no_price_history",
+                format!(
+                    "fewer than two daily returns for {} over the last {lookback_days} days; its volatility is unknown and historical blocks need an archive node",
+                    holding.symbol
+                ),
+            ));
+        }
+        out.push(RiskHoldingOut {
+            token: holding.token.map(|token| format!("{token:#x}")),
+            symbol: holding.symbol.clone(),
+            balance: holding.balance.clone(),
+            price_usd: value.map(|(price, _)| price.normalize().to_string()),
+            value_usd: value.map(|(_, value)| usd(value)),
+            weight_pct: percent(*weight),
+            stablecoin: holding.stablecoin,
+            daily_volatility_pct: daily.and_then(float_percent),
+            annualized_volatility_pct: daily.and_then(annualize),
+        });
+    }
+
+    // The portfolio's daily return on days every valued holding has one.
+    let portfolio_returns: Vec<f64> = (0..blocks.len().saturating_sub(1))
+        .filter_map(|day| {
+            holdings
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| values[*index].is_some())
+                .map(|(index, _)| Some(weights[index].to_f64()? * returns[index][day]?))
+                .sum::<Option<f64>>()
+        })
+        .collect();
+    let portfolio_daily = volatility(portfolio_returns.into_iter());
+
+    let largest = out
+        .iter()
+        .zip(&weights)
+        .filter(|(holding, _)| holding.value_usd.is_some())
+        .max_by_key(|(_, weight)| **weight);
+    let stablecoin_weight: Decimal = holdings
+        .iter()
+        .zip(&weights)
+        .filter(|(holding, _)| holding.stablecoin)
+        .map(|(_, weight)| *weight)
+        .sum();
+    if !dex_priced.is_empty() {
+        warnings.push(warnings::warning(
+            "dex_price",
+            format!(
+                "no Chainlink feed priced {} at some samples; those prices come from pool quotes, which thin liquidity or a single large trade can move",
+                dex_priced.join(", ")
+            ),
+        ));
+    }
+
+    Ok(PortfolioRiskOut {
+        wallet: format!("{owner:#x}"),
+        block_number,
+        lookback_days,
+        samples: blocks.len(),
+        total_value_usd: usd(total_value),
+        largest_holding: largest.map(|(holding, _)| holding.symbol.clone()),
+        concentration_pct: percent(largest.map(|(_, weight)| *weight).unwrap_or_default()),
+        stablecoin_pct: percent(stablecoin_weight),
+        portfolio_daily_volatility_pct: portfolio_daily.and_then(float_percent),
+        portfolio_annualized_volatility_pct: portfolio_daily.and_then(annualize),
+        holdings: out,
+        warnings,
+    })
+}
+
+async fn pin<M>(provider: Arc<M>, block: u64) -> AppResult<Arc<BlockScoped<M>>>
+where
+    M: Middleware + 'static,
+{
+    let requested = BlockId::Number(BlockNumber::Number(block.into()));
+    Ok(Arc::new(BlockScoped::pin(provider, Some(requested)).await?))
+}
+
+/// Non-zero balances of the native coin and `tokens`; USD stablecoins are the registry's USD
+/// quote tokens.
+async fn read_holdings<M>(
+    provider: Arc<BlockScoped<M>>,
+    registry: &TokenRegistry,
+    owner: Address,
+    tokens: &[&TokenInfo],
+) -> AppResult<Vec<Holding>>
+where
+    M: Middleware + 'static,
+{
+    let stablecoins: Vec<Address> = registry
+        .quote_tokens(QuoteCurrency::USD)
+        .iter()
+        .map(|token| token.address)
+        .collect();
+    let mut holdings = Vec::new();
+    let native = provider
+        .get_balance(owner, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch native balance: {err}")))?;
+    if !native.is_zero()
+        && let Some(wrapped) = registry.wrapped_native()
+    {
+        holdings.push(Holding {
+            token: None,
+            symbol: registry.native().symbol.clone(),
+            balance: balance::format_with_decimals(&native, 18),
+            priced_as: wrapped.address,
+            stablecoin: false,
+        });
+    }
+    for token in tokens {
+        let raw: U256 = erc20::fetch_balance_of(provider.clone(), token.address, owner).await?;
+        if raw.is_zero() {
+            continue;
+        }
+        holdings.push(Holding {
+            token: Some(token.address),
+            symbol: token.symbol.clone(),
+            balance: balance::format_with_decimals(&raw, token.decimals as u32),
+            priced_as: token.address,
+            stablecoin: stablecoins.contains(&token.address),
+        });
+    }
+    Ok(holdings)
+}
+
+/// Log return between each pair of consecutive samples; `None` where either price is missing.
+pub fn log_returns(prices: &[Option<Decimal>]) -> Vec<Option<f64>> {
+    prices
+        .windows(2)
+        .map(|pair| {
+            let (previous, current) = (pair[0]?.to_f64()?, pair[1]?.to_f64()?);
+            (previous > 0.0 && current > 0.0).then(|| (current / previous).ln())
+        })
+        .collect()
+}
+
+/// Sample standard deviation of `returns`; `None` with fewer than two.
+pub fn volatility(returns: impl Iterator<Item = f64>) -> Option<f64> {
+    let returns: Vec<f64> = returns.collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+fn annualize(daily: f64) -> Option<String> {
+    float_percent(daily * DAYS_PER_YEAR.sqrt())
+}
+
+fn float_percent(fraction: f64) -> Option<String> {
+    Decimal::from_f64(fraction).map(percent)
+}
+
+fn percent(fraction: Decimal) -> String {
+    (fraction * Decimal::ONE_HUNDRED)
+        .round_dp(2)
+        .normalize()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volatility_comes_from_daily_log_returns() {
+        let prices: Vec<Option<Decimal>> = ["100", "110", "99", "108.9"]
+            .iter()
+            .map(|price| Some(Decimal::from_str(price).unwrap()))
+            .collect();
+        let returns = log_returns(&prices);
+        assert_eq!(returns.len(), 3);
+        assert!((returns[0].unwrap() - 1.1f64.ln()).abs() < 1e-12);
+
+        // +10%, -10%, +10%: log returns with a mean of 0.0284 and a deviation of 0.1159.
+        let daily = volatility(returns.iter().flatten().copied()).unwrap();
+        assert!((daily - 0.1159).abs() < 1e-3, "{daily}");
+        assert_eq!(annualize(daily).as_deref(), Some("221.35"));
+
+        // A gap breaks the returns on either side of it.
+        let gapped = log_returns(&[prices[0], None, prices[2], prices[3]]);
+        assert_eq!(gapped[..2], [None, None]);
+        assert!(volatility(gapped.iter().flatten().copied()).is_none());
+        assert_eq!(volatility([0.01, 0.01, 0.01].into_iter()), Some(0.0));
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, warnings},
+    types::WarningOut,
+};
+
+/// The most a write can take from its sender: every gas unit at the fee cap, plus what it sends.
+pub fn max_cost(gas_limit: U256, max_fee_per_gas: U256, value: U256) -> U256 {
+    gas_limit
+        .saturating_mul(max_fee_per_gas)
+        .saturating_add(value)
+}
+
+/// Fail with [`AppError::InsufficientFunds`] when `balance` does not cover `required`, saying
+/// how much of the native coin `spend` (e.g. "the swap") is missing.
+pub fn check_funds(
+    balance: U256,
+    required: U256,
+    spend: &str,
+    native_symbol: &str,
+) -> AppResult<()> {
+    if balance >= required {
+        return Ok(());
+    }
+    let missing = required - balance;
+    let eth = |wei: &U256| format!("{} {native_symbol}", balance::format_with_decimals(wei, 18));
+    Err(AppError::InsufficientFunds {
+        message: format!(
+            "{spend} may cost up to {} (gas limit × max fee per gas + value) but the wallet holds {}; {} is missing",
+            eth(&required),
+            eth(&balance),
+            eth(&missing)
+        ),
+        required_wei: required.to_string(),
+        balance_wei: balance.to_string(),
+        missing_wei: missing.to_string(),
+    })
+}
+
+/// [`check_funds`] against `wallet`'s native balance, read through `provider`.
+pub async fn ensure_funds<M>(
+    provider: &Arc<M>,
+    wallet: Address,
+    required: U256,
+    spend: &str,
+    native_symbol: &str,
+) -> AppResult<()>
+where
+    M: Middleware + 'static,
+{
+    let balance = provider
+        .get_balance(wallet, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch native balance: {err}")))?;
+    check_funds(balance, required, spend, native_symbol)
+}
+
+/// Turn a shortfall from [`ensure_funds`] into an `insufficient_gas` warning, for calls that only
+/// simulate or plan a write; anything else still fails. Whatever signs a transaction for
+/// broadcast keeps the hard error.
+pub fn shortfall_warning(funded: AppResult<()>) -> AppResult<Option<WarningOut>> {
+    match funded {
+        Ok(()) => Ok(None),
+        Err(AppError::InsufficientFunds { message, .. }) => Ok(Some(warnings::warning(
+            "insufficient_gas",
+            format!("{message}; fund the wallet before broadcasting"),
+        ))),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortfalls_name_the_missing_amount() {
+        let gwei = U256::exp10(9);
+        let required = max_cost(U256::from(200_000), gwei * 50, U256::exp10(16));
+        assert_eq!(required, U256::exp10(16) * 2);
+        assert!(check_funds(required, required, "the swap", "ETH").is_ok());
+
+        let err = check_funds(U256::exp10(16), required, "the swap", "ETH").unwrap_err();
+        assert!(err.to_string().contains("0.01 ETH is missing"), "{err}");
+        let payload = err.to_json_rpc();
+        assert_eq!(payload.code, -32031);
+        assert_eq!(payload.data["missing_wei"], "10000000000000000");
+        assert_eq!(payload.data["required_wei"], "20000000000000000");
+
+        // Simulations only warn; other failures still propagate.
+        let warning = shortfall_warning(Err(err)).unwrap().unwrap();
+        assert_eq!(warning.code, "insufficient_gas");
+        assert!(warning.message.contains("0.01 ETH is missing"));
+        assert!(shortfall_warning(Ok(())).unwrap().is_none());
+        assert!(shortfall_warning(Err(AppError::Rpc("down".into()))).is_err());
+    }
+}
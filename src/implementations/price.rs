@@ -1,29 +1,52 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
 use ethers::{
     providers::Middleware,
-    types::{Address, U256},
+    types::{Address, I256, U256, U512},
 };
 use ethers_contract::abigen;
 use once_cell::sync::Lazy;
 use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::{info, warn};
 
 use crate::{
     error::{AppError, AppResult},
     implementations::{
         balance, erc20,
-        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+        timing::{Stage, Timings},
+        uniswap::{
+            self, UniswapQuoterV2, UniswapV3Pool, pool_address,
+            uniswap_quoter_v2::QuoteExactInputSingleParams,
+        },
+        native::NativeCurrency,
+        v2::V2Venue,
+        validation,
+    },
+    types::{
+        ListTokensOut, PriceOut, PriceSourceOut, QuoteCurrency, RegistryTokenOut,
+        SymbolDuplicateOut,
     },
-    types::{PriceOut, QuoteCurrency},
 };
 
 mod defaults;
 
+/// Most fractional digits a `Decimal` carries.
+const MAX_DECIMAL_SCALE: u32 = 28;
+/// Largest `Decimal` mantissa, 2^96 - 1.
+static MAX_DECIMAL_MANTISSA: Lazy<U256> = Lazy::new(|| (U256::one() << 96) - 1);
+
 // Addresses for mainnet reference contracts.
 pub static UNISWAP_QUOTER_V2: Lazy<Address> =
     Lazy::new(|| Address::from_str("0x61fFE014bA17989E743c5F6cB21bF9697530B21e").unwrap());
 pub static UNISWAP_SWAP_ROUTER: Lazy<Address> =
     Lazy::new(|| Address::from_str("0xE592427A0AEce92De3Edee1F18E0157C05861564").unwrap());
+pub static UNISWAP_V3_FACTORY: Lazy<Address> =
+    Lazy::new(|| Address::from_str("0x1F98431c8aD98523631AE4a59f267346ea31F984").unwrap());
 
 abigen!(
     ChainlinkAggregator,
@@ -40,7 +63,12 @@ pub struct TokenInfo {
     pub address: Address,
     pub decimals: u8,
     pub chainlink_feeds: HashMap<QuoteCurrency, Address>,
+    /// The feeds report a rate or spread rather than an asset price, so a zero or negative
+    /// answer is a valid reading instead of a broken feed.
+    pub derived_feeds: bool,
     pub default_fee: u32,
+    /// Chain the entry was registered for; `None` means the active chain.
+    pub chain_id: Option<u64>,
 }
 
 impl TokenInfo {
@@ -51,7 +79,9 @@ impl TokenInfo {
             address,
             decimals,
             chainlink_feeds: HashMap::new(),
+            derived_feeds: false,
             default_fee: 3_000,
+            chain_id: None,
         }
     }
 
@@ -60,17 +90,126 @@ impl TokenInfo {
         self
     }
 
+    pub fn with_derived_feeds(mut self) -> Self {
+        self.derived_feeds = true;
+        self
+    }
+
     pub fn with_fee(mut self, fee: u32) -> Self {
         self.default_fee = fee;
         self
     }
+
+    pub fn with_chain(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// `SYMBOL:address`, which resolves to this token even when the symbol is shared.
+    pub fn qualified_symbol(&self) -> String {
+        format!("{}:{:#x}", self.symbol, self.address)
+    }
+}
+
+/// Default tolerance before a USD stablecoin proxy counts as off-peg (0.5%).
+pub const DEFAULT_DEPEG_THRESHOLD_BPS: u32 = 50;
+
+/// Seconds averaged by the Uniswap TWAP source of `median` mode.
+const TWAP_WINDOW_SECS: u32 = 1_800;
+
+/// Places kept by [`invert_price`]; matches the widest common ERC-20 decimals.
+const INVERTED_PRICE_DECIMALS: u32 = 18;
+
+/// How Uniswap fallbacks combine quotes when several stablecoins proxy USD.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsdQuoteMode {
+    /// First configured stablecoin whose pool returns a quote.
+    #[default]
+    First,
+    /// Median across every stablecoin that quotes, damping a single depegged or thin pool.
+    Median,
+}
+
+impl FromStr for UsdQuoteMode {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "first" => Ok(UsdQuoteMode::First),
+            "median" => Ok(UsdQuoteMode::Median),
+            other => Err(AppError::Config(format!("unknown USD quote mode: {other}"))),
+        }
+    }
+}
+
+/// Which token a bare symbol resolves to when several registered tokens share it.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolCollisionPolicy {
+    /// Bundled defaults and token-list entries win over tokens discovered later.
+    #[default]
+    ListPriority,
+    /// Tokens of the active chain win over the bundled mainnet defaults.
+    ChainScoped,
+    /// Shared symbols are refused; callers must use `SYMBOL:address`.
+    Error,
+}
+
+impl SymbolCollisionPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolCollisionPolicy::ListPriority => "list_priority",
+            SymbolCollisionPolicy::ChainScoped => "chain_scoped",
+            SymbolCollisionPolicy::Error => "error",
+        }
+    }
+}
+
+impl FromStr for SymbolCollisionPolicy {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().replace('-', "_").as_str() {
+            "list_priority" => Ok(SymbolCollisionPolicy::ListPriority),
+            "chain_scoped" => Ok(SymbolCollisionPolicy::ChainScoped),
+            "error" => Ok(SymbolCollisionPolicy::Error),
+            other => Err(AppError::Config(format!(
+                "unknown symbol collision policy: {other}"
+            ))),
+        }
+    }
 }
 
 /// Registry of known tokens to ease symbol lookup and pricing fallbacks.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TokenRegistry {
-    by_symbol: HashMap<String, TokenInfo>,
+    /// Addresses registered under each symbol, in registration order.
+    by_symbol: HashMap<String, Vec<Address>>,
     by_address: HashMap<Address, TokenInfo>,
+    /// Decimals published by token lists, kept apart from what contracts report about themselves.
+    listed_decimals: HashMap<Address, u8>,
+    /// Stablecoin symbols used as the USD leg of Uniswap quotes, in preference order.
+    usd_quotes: Vec<String>,
+    usd_quote_mode: UsdQuoteMode,
+    depeg_threshold_bps: u32,
+    /// USD notional the Uniswap fallback quotes instead of one whole base token.
+    uniswap_reference_usd: Option<Decimal>,
+    /// QuoterV2 used for the Uniswap fallback on the active chain.
+    uniswap_quoter: Address,
+    /// V3 factory whose pools back the TWAP source of `median` mode.
+    uniswap_factory: Address,
+    /// V2-fork venues tried in order when Uniswap V3 cannot quote a pair.
+    v2_venues: Vec<V2Venue>,
+    /// Gas coin of the active chain; its wrapper is the `ETH` quote token.
+    native: NativeCurrency,
+    chain_id: u64,
+    collision_policy: SymbolCollisionPolicy,
+    /// Upper-cased alias to the symbol, `SYMBOL:address` or address it stands for.
+    aliases: HashMap<String, String>,
+    /// Resolve the native coin's symbol to its wrapped token when no token claims it.
+    wrap_native_symbol: bool,
+    feed_decimals: FeedDecimals,
 }
 
 impl TokenRegistry {
@@ -78,20 +217,159 @@ impl TokenRegistry {
         Self {
             by_symbol: HashMap::new(),
             by_address: HashMap::new(),
+            listed_decimals: HashMap::new(),
+            usd_quotes: vec!["USDC".to_string()],
+            usd_quote_mode: UsdQuoteMode::default(),
+            depeg_threshold_bps: DEFAULT_DEPEG_THRESHOLD_BPS,
+            uniswap_reference_usd: None,
+            uniswap_quoter: *UNISWAP_QUOTER_V2,
+            uniswap_factory: *UNISWAP_V3_FACTORY,
+            v2_venues: Vec::new(),
+            native: NativeCurrency::default(),
+            chain_id: 1,
+            collision_policy: SymbolCollisionPolicy::default(),
+            aliases: HashMap::new(),
+            wrap_native_symbol: true,
+            feed_decimals: FeedDecimals::default(),
         }
     }
 
+    /// Deviation from $1 (in bps) beyond which a stablecoin proxy is treated as depegged.
+    pub fn with_depeg_threshold(mut self, threshold_bps: u32) -> Self {
+        self.depeg_threshold_bps = threshold_bps;
+        self
+    }
+
+    /// Replace the USD proxy stablecoins, e.g. USDT/DAI on chains where USDC is thin.
+    pub fn with_usd_quotes(mut self, symbols: Vec<String>, mode: UsdQuoteMode) -> Self {
+        self.usd_quotes = symbols.into_iter().map(|s| s.to_uppercase()).collect();
+        self.usd_quote_mode = mode;
+        self
+    }
+
+    /// Size Uniswap fallback quotes at roughly `usd` of the quote token rather than one whole
+    /// base token, which is too coarse for very cheap tokens and moves thin pools for dear ones.
+    pub fn with_uniswap_reference_usd(mut self, usd: Option<u64>) -> Self {
+        self.uniswap_reference_usd = usd.map(Decimal::from);
+        self
+    }
+
+    /// Quote the Uniswap fallback through this chain's QuoterV2.
+    pub fn with_uniswap_quoter(mut self, quoter: Address) -> Self {
+        self.uniswap_quoter = quoter;
+        self
+    }
+
+    /// Read TWAPs from this chain's V3 pools.
+    pub fn with_uniswap_factory(mut self, factory: Address) -> Self {
+        self.uniswap_factory = factory;
+        self
+    }
+
+    pub fn with_v2_venues(mut self, venues: Vec<V2Venue>) -> Self {
+        self.v2_venues = venues;
+        self
+    }
+
+    /// Quote `ETH` requests in `native`'s coin through its wrapped token.
+    pub fn with_native(mut self, native: NativeCurrency) -> Self {
+        self.native = native;
+        self
+    }
+
+    /// Chain the server runs on; `chain_scoped` prefers entries registered for it.
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    pub fn with_collision_policy(mut self, policy: SymbolCollisionPolicy) -> Self {
+        self.collision_policy = policy;
+        self
+    }
+
+    /// Extra names for tokens, e.g. `BTC` for `WBTC`. Aliases are not followed transitively.
+    pub fn with_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.aliases = aliases
+            .into_iter()
+            .map(|(alias, target)| (alias.to_uppercase(), target))
+            .collect();
+        self
+    }
+
+    /// Whether token contexts (prices, swaps) read the native symbol as its wrapped token.
+    pub fn with_native_alias(mut self, enabled: bool) -> Self {
+        self.wrap_native_symbol = enabled;
+        self
+    }
+
     pub fn with_defaults() -> Self {
         let mut registry = Self::new();
         defaults::populate_defaults(&mut registry);
         registry
     }
 
+    /// Register `info`. A symbol already held by another address is kept by both; which one the
+    /// bare symbol resolves to is up to the collision policy.
     pub fn add_token(&mut self, info: TokenInfo) {
-        self.by_symbol.insert(info.symbol.clone(), info.clone());
+        if let Some(previous) = self.by_address.get(&info.address)
+            && previous.symbol != info.symbol
+            && let Some(addresses) = self.by_symbol.get_mut(&previous.symbol)
+        {
+            addresses.retain(|address| *address != info.address);
+            if addresses.is_empty() {
+                self.by_symbol.remove(&previous.symbol);
+            }
+        }
+        let addresses = self.by_symbol.entry(info.symbol.clone()).or_default();
+        if !addresses.contains(&info.address) {
+            addresses.push(info.address);
+        }
         self.by_address.insert(info.address, info);
     }
 
+    /// Forget a token learned at runtime; it is fetched again the next time it is used.
+    pub fn remove_token(&mut self, address: Address) {
+        let Some(info) = self.by_address.remove(&address) else {
+            return;
+        };
+        if let Some(addresses) = self.by_symbol.get_mut(&info.symbol) {
+            addresses.retain(|candidate| *candidate != address);
+            if addresses.is_empty() {
+                self.by_symbol.remove(&info.symbol);
+            }
+        }
+    }
+
+    /// Record decimals from an independent source such as a token list.
+    pub fn add_listed_decimals(&mut self, address: Address, decimals: u8) {
+        self.listed_decimals.insert(address, decimals);
+    }
+
+    /// Merge a Uniswap-style token list, keeping only entries for `chain_id`.
+    pub fn load_token_list(&mut self, raw: &str, chain_id: u64) -> AppResult<usize> {
+        let entries = defaults::parse_token_list(raw, chain_id)?;
+        let count = entries.len();
+        for (address, decimals) in entries {
+            self.add_listed_decimals(address, decimals);
+        }
+        Ok(count)
+    }
+
+    pub fn listed_decimals(&self, address: Address) -> Option<u8> {
+        self.listed_decimals.get(&address).copied()
+    }
+
+    /// Compare contract-reported decimals with token-list data; returns a warning on mismatch.
+    pub fn check_decimals(&self, address: Address, reported: u8) -> Option<String> {
+        let listed = self.listed_decimals(address)?;
+        (listed != reported).then(|| {
+            format!(
+                "{address:#x} reports {reported} decimals but token lists say {listed}; formatted amounts may be wrong"
+            )
+        })
+    }
+
     pub async fn ensure_token<M>(&mut self, provider: Arc<M>, address: Address) -> AppResult<()>
     where
         M: Middleware + 'static,
@@ -113,9 +391,185 @@ impl TokenRegistry {
     }
 
     pub fn resolve_symbol(&self, symbol: &str) -> Option<Address> {
+        self.info_by_symbol(symbol).map(|info| info.address)
+    }
+
+    /// Resolve an address, an alias, a registered symbol, or `SYMBOL:address`. Under the `error`
+    /// policy a symbol shared by several tokens is rejected with the qualified forms to choose
+    /// from. The native symbol falls back to the wrapped token unless a token claims it.
+    pub fn resolve_token(&self, input: &str) -> AppResult<Address> {
+        match self.aliases.get(&input.to_uppercase()) {
+            Some(target) => self.resolve_unaliased(target).map_err(|err| match err {
+                AppError::InvalidInput(message) => {
+                    AppError::InvalidInput(format!("alias {input} -> {target}: {message}"))
+                }
+                other => other,
+            }),
+            None => self.resolve_unaliased(input),
+        }
+    }
+
+    /// Whether `input` names the chain's gas coin rather than a token, e.g. `ETH` on mainnet.
+    pub fn is_native_symbol(&self, input: &str) -> bool {
+        input.eq_ignore_ascii_case(&self.native.symbol)
+    }
+
+    /// Every name [`Self::resolve_token`] accepts besides addresses.
+    pub fn known_names(&self) -> impl Iterator<Item = &str> {
+        let native = self
+            .wrap_native_symbol
+            .then_some(self.native.symbol.as_str());
         self.by_symbol
-            .get(&symbol.to_uppercase())
-            .map(|info| info.address)
+            .keys()
+            .chain(self.aliases.keys())
+            .map(String::as_str)
+            .chain(native)
+    }
+
+    fn resolve_unaliased(&self, input: &str) -> AppResult<Address> {
+        if let Ok(address) = input.parse::<Address>() {
+            return Ok(address);
+        }
+        if let Some((symbol, address)) = input.split_once(':') {
+            let address = address.parse::<Address>().map_err(|_| {
+                AppError::InvalidInput(format!(
+                    "invalid address in qualified symbol: {input}{}",
+                    validation::explain(address, [])
+                ))
+            })?;
+            return match self.info_by_address(address) {
+                Some(info) if !info.symbol.eq_ignore_ascii_case(symbol) => {
+                    Err(AppError::InvalidInput(format!(
+                        "{address:#x} is registered as {}, not {symbol}",
+                        info.symbol
+                    )))
+                }
+                _ => Ok(address),
+            };
+        }
+
+        let candidates = self.symbol_candidates(input);
+        match candidates.as_slice() {
+            [] => self
+                .native
+                .wrapped
+                .filter(|_| self.wrap_native_symbol && self.is_native_symbol(input))
+                .ok_or_else(|| {
+                    AppError::InvalidInput(format!(
+                        "unknown token symbol or address: {input}{}",
+                        validation::explain(input, self.known_names())
+                    ))
+                }),
+            [_, _, ..] if self.collision_policy == SymbolCollisionPolicy::Error => {
+                let qualified: Vec<String> = candidates
+                    .iter()
+                    .map(|info| info.qualified_symbol())
+                    .collect();
+                Err(AppError::InvalidInput(format!(
+                    "symbol {input} is shared by several tokens; use one of {}",
+                    qualified.join(", ")
+                )))
+            }
+            [preferred, ..] => Ok(preferred.address),
+        }
+    }
+
+    /// Tokens registered under `symbol`, the one a bare symbol resolves to first.
+    pub fn symbol_candidates(&self, symbol: &str) -> Vec<&TokenInfo> {
+        let Some(addresses) = self.by_symbol.get(&symbol.to_uppercase()) else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<&TokenInfo> = addresses
+            .iter()
+            .filter_map(|address| self.by_address.get(address))
+            .collect();
+        // Stable sort: registration order breaks ties.
+        candidates.sort_by_key(|info| {
+            let other_chain = self.collision_policy == SymbolCollisionPolicy::ChainScoped
+                && info
+                    .chain_id
+                    .is_some_and(|chain_id| chain_id != self.chain_id);
+            (other_chain, self.listed_decimals(info.address).is_none())
+        });
+        candidates
+    }
+
+    /// Registered tokens grouped by symbol, optionally narrowed to one symbol or to shared ones.
+    pub fn list(&self, symbol: Option<&str>, duplicates_only: bool) -> ListTokensOut {
+        let mut symbols: Vec<&String> = self
+            .by_symbol
+            .iter()
+            .filter(|(key, addresses)| {
+                symbol.is_none_or(|symbol| key.eq_ignore_ascii_case(symbol))
+                    && (!duplicates_only || addresses.len() > 1)
+            })
+            .map(|(key, _)| key)
+            .collect();
+        symbols.sort();
+
+        let mut tokens = Vec::new();
+        let mut duplicates = Vec::new();
+        for symbol in symbols {
+            let candidates = self.symbol_candidates(symbol);
+            let shared = candidates.len() > 1;
+            let resolves = !shared || self.collision_policy != SymbolCollisionPolicy::Error;
+            if shared {
+                duplicates.push(SymbolDuplicateOut {
+                    symbol: symbol.clone(),
+                    addresses: candidates
+                        .iter()
+                        .map(|info| format!("{:#x}", info.address))
+                        .collect(),
+                    resolves_to: resolves.then(|| format!("{:#x}", candidates[0].address)),
+                });
+            }
+            for (index, info) in candidates.iter().enumerate() {
+                tokens.push(RegistryTokenOut {
+                    symbol: info.symbol.clone(),
+                    address: format!("{:#x}", info.address),
+                    decimals: info.decimals,
+                    qualified_symbol: info.qualified_symbol(),
+                    chain_id: info.chain_id,
+                    listed: self.listed_decimals(info.address).is_some(),
+                    preferred: resolves && index == 0,
+                });
+            }
+        }
+
+        let mut aliases: BTreeMap<String, String> = self.aliases.clone().into_iter().collect();
+        if self.wrap_native_symbol
+            && self.symbol_candidates(&self.native.symbol).is_empty()
+            && let Some(wrapped) = self.wrapped_native()
+        {
+            aliases
+                .entry(self.native.symbol.to_uppercase())
+                .or_insert_with(|| wrapped.qualified_symbol());
+        }
+
+        ListTokensOut {
+            chain_id: self.chain_id,
+            collision_policy: self.collision_policy.as_str().to_string(),
+            tokens,
+            duplicates,
+            aliases,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Listed tokens deployed on the active chain plus its wrapped native token, by symbol.
+    pub fn listed_tokens(&self) -> Vec<&TokenInfo> {
+        let wrapped = self.wrapped_native().map(|info| info.address);
+        let mut tokens: Vec<&TokenInfo> = self
+            .by_address
+            .values()
+            .filter(|info| {
+                let listed = self.listed_decimals(info.address).is_some()
+                    && info.chain_id.is_none_or(|chain_id| chain_id == self.chain_id);
+                listed || wrapped == Some(info.address)
+            })
+            .collect();
+        tokens.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.address.cmp(&b.address)));
+        tokens
     }
 
     pub fn info_by_address(&self, address: Address) -> Option<&TokenInfo> {
@@ -123,13 +577,74 @@ impl TokenRegistry {
     }
 
     pub fn info_by_symbol(&self, symbol: &str) -> Option<&TokenInfo> {
-        self.by_symbol.get(&symbol.to_uppercase())
+        self.symbol_candidates(symbol).into_iter().next()
+    }
+
+    pub fn native(&self) -> &NativeCurrency {
+        &self.native
+    }
+
+    /// Decimals cache shared by every Chainlink read made through this registry.
+    pub fn feed_decimals(&self) -> &FeedDecimals {
+        &self.feed_decimals
+    }
+
+    /// Every Chainlink feed the registry can price through on the active chain, plus the
+    /// native/USD feed, deduplicated.
+    pub fn chainlink_feeds(&self) -> Vec<Address> {
+        let mut feeds: Vec<Address> = self
+            .by_address
+            .values()
+            .filter(|info| {
+                info.chain_id
+                    .is_none_or(|chain_id| chain_id == self.chain_id)
+            })
+            .flat_map(|info| info.chainlink_feeds.values().copied())
+            .chain(self.native.usd_feed)
+            .collect();
+        feeds.sort();
+        feeds.dedup();
+        feeds
+    }
+
+    /// V3 factory whose pools the Uniswap sources quote.
+    pub fn uniswap_factory(&self) -> Address {
+        self.uniswap_factory
+    }
+
+    /// V2-fork venues tried when Uniswap V3 cannot quote a pair.
+    pub fn v2_venues(&self) -> &[V2Venue] {
+        &self.v2_venues
+    }
+
+    /// Registry entry of the wrapped native token (WETH, WMATIC, WAVAX, ...).
+    pub fn wrapped_native(&self) -> Option<&TokenInfo> {
+        self.native
+            .wrapped
+            .and_then(|address| self.info_by_address(address))
+    }
+
+    /// How `quote` is labelled in responses: `USD`, or the native coin's symbol for `ETH`.
+    pub fn quote_symbol(&self, quote: QuoteCurrency) -> String {
+        match quote {
+            QuoteCurrency::USD => quote.to_string(),
+            QuoteCurrency::ETH => self.native.symbol.clone(),
+        }
     }
 
     pub fn quote_token(&self, quote: QuoteCurrency) -> Option<&TokenInfo> {
+        self.quote_tokens(quote).into_iter().next()
+    }
+
+    /// Candidate quote tokens known to the registry, in preference order.
+    pub fn quote_tokens(&self, quote: QuoteCurrency) -> Vec<&TokenInfo> {
         match quote {
-            QuoteCurrency::USD => self.info_by_symbol("USDC"),
-            QuoteCurrency::ETH => self.info_by_symbol("WETH"),
+            QuoteCurrency::USD => self
+                .usd_quotes
+                .iter()
+                .filter_map(|symbol| self.info_by_symbol(symbol))
+                .collect(),
+            QuoteCurrency::ETH => self.wrapped_native().into_iter().collect(),
         }
     }
 }
@@ -141,6 +656,20 @@ pub async fn resolve_token_price<M>(
     base: Address,
     quote: QuoteCurrency,
 ) -> AppResult<PriceOut>
+where
+    M: Middleware + 'static,
+{
+    resolve_token_price_timed(provider, registry, base, quote, &mut Timings::default()).await
+}
+
+/// [`resolve_token_price`] that records Chainlink and quoter latency into `timings`.
+pub async fn resolve_token_price_timed<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base: Address,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> AppResult<PriceOut>
 where
     M: Middleware + 'static,
 {
@@ -148,121 +677,559 @@ where
         .info_by_address(base)
         .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {base:?}")))?;
 
-    // Attempt direct Chainlink feed (base/quote).
-    if let Some(feed_addr) = base_info.chainlink_feeds.get(&quote) {
-        let price = fetch_chainlink_price(provider.clone(), *feed_addr).await?;
+    if let Some(chainlink) =
+        chainlink_price(provider.clone(), registry, base_info, quote, timings).await
+    {
+        let (price, source) = chainlink?;
         return Ok(PriceOut {
             base: base_info.symbol.clone(),
-            quote: quote.to_string(),
+            quote: registry.quote_symbol(quote),
             price: price.to_string(),
-            source: "chainlink".to_string(),
-            decimals: price.scale() as u32,
+            source: source.to_string(),
+            decimals: price.scale(),
+            block_number: None,
+            depeg_warning: None,
+            sources: None,
+            timings: None,
+            warnings: Vec::new(),
         });
     }
 
+    uniswap_price(provider, registry, base_info, quote, timings).await
+}
+
+/// Price `base` from Chainlink, Uniswap V3 spot and a Uniswap V3 TWAP at once and report the
+/// median of the sources that answered, each listed under `sources`.
+pub async fn resolve_median_price<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base: Address,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> AppResult<PriceOut>
+where
+    M: Middleware + 'static,
+{
+    let base_info = registry
+        .info_by_address(base)
+        .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {base:?}")))?;
+
+    let (mut chainlink_timings, mut spot_timings, mut twap_timings) =
+        (Timings::default(), Timings::default(), Timings::default());
+    let (chainlink, spot, twap) = tokio::join!(
+        chainlink_price(
+            provider.clone(),
+            registry,
+            base_info,
+            quote,
+            &mut chainlink_timings
+        ),
+        uniswap_price(
+            provider.clone(),
+            registry,
+            base_info,
+            quote,
+            &mut spot_timings
+        ),
+        twap_price(
+            provider.clone(),
+            registry,
+            base_info,
+            quote,
+            &mut twap_timings
+        ),
+    );
+    for branch in [&chainlink_timings, &spot_timings, &twap_timings] {
+        timings.absorb(branch);
+    }
+
+    let chainlink = chainlink.unwrap_or_else(|| {
+        Err(AppError::Price(format!(
+            "no Chainlink feed route for {} in {quote}",
+            base_info.symbol
+        )))
+    });
+    let depeg_warning = spot.as_ref().ok().and_then(|out| out.depeg_warning.clone());
+    let spot = spot.and_then(|out| {
+        Decimal::from_str(&out.price)
+            .map_err(|err| AppError::Price(format!("invalid uniswap price: {err}")))
+    });
+    let results = [
+        ("chainlink", chainlink.map(|(price, _)| price)),
+        ("uniswap_v3", spot),
+        ("uniswap_v3_twap", twap),
+    ];
+
+    let quotes: Vec<(&str, Decimal)> = results
+        .iter()
+        .filter_map(|(source, result)| result.as_ref().ok().map(|price| (*source, *price)))
+        .collect();
+    let sources = results
+        .iter()
+        .map(|(source, result)| PriceSourceOut {
+            source: source.to_string(),
+            price: result.as_ref().ok().map(Decimal::to_string),
+            error: result.as_ref().err().map(AppError::to_string),
+        })
+        .collect();
+    let Some(price) = median_price(&quotes) else {
+        return Err(AppError::Price(format!(
+            "no price source answered for {}",
+            base_info.symbol
+        )));
+    };
+    let via: Vec<&str> = quotes.iter().map(|(source, _)| *source).collect();
+
+    Ok(PriceOut {
+        base: base_info.symbol.clone(),
+        quote: registry.quote_symbol(quote),
+        price: price.to_string(),
+        source: format!("median ({})", via.join("/")),
+        decimals: price.scale(),
+        block_number: None,
+        depeg_warning,
+        sources: Some(sources),
+        timings: None,
+        warnings: Vec::new(),
+    })
+}
+
+/// Source label [`chainlink_price`] would report for `base` in `quote`, without reading any
+/// feed. `None` when no feed route exists.
+pub fn chainlink_route(
+    registry: &TokenRegistry,
+    base_info: &TokenInfo,
+    quote: QuoteCurrency,
+) -> Option<&'static str> {
+    if base_info.chainlink_feeds.contains_key(&quote) {
+        return Some("chainlink");
+    }
+    let native_usd = registry
+        .wrapped_native()
+        .is_some_and(|info| info.chainlink_feeds.contains_key(&QuoteCurrency::USD));
+    let pivot = match quote {
+        QuoteCurrency::ETH => (QuoteCurrency::USD, "chainlink (via USD)"),
+        QuoteCurrency::USD => (QuoteCurrency::ETH, "chainlink (via ETH)"),
+    };
+    (native_usd && base_info.chainlink_feeds.contains_key(&pivot.0)).then_some(pivot.1)
+}
+
+/// Chainlink price of `base` in `quote` with its source label: a direct feed, or two feeds
+/// pivoting through USD or ETH. `None` when no feed route exists.
+async fn chainlink_price<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base_info: &TokenInfo,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> Option<AppResult<(Decimal, &'static str)>>
+where
+    M: Middleware + 'static,
+{
+    // Attempt direct Chainlink feed (base/quote).
+    if let Some(feed_addr) = base_info.chainlink_feeds.get(&quote) {
+        let price = timings
+            .time(
+                Stage::Chainlink,
+                fetch_chainlink_price(
+                    provider.clone(),
+                    registry,
+                    *feed_addr,
+                    base_info.derived_feeds,
+                ),
+            )
+            .await;
+        return Some(price.map(|price| (price, "chainlink")));
+    }
+
     // Attempt Chainlink via USD pivot if quote is ETH.
-    if quote == QuoteCurrency::ETH {
-        if let Some(base_usd_feed) = base_info.chainlink_feeds.get(&QuoteCurrency::USD) {
-            if let Some(eth_info) = registry.info_by_symbol("WETH") {
-                if let Some(eth_usd_feed) = eth_info.chainlink_feeds.get(&QuoteCurrency::USD) {
-                    let base_usd = fetch_chainlink_price(provider.clone(), *base_usd_feed).await?;
-                    let eth_usd = fetch_chainlink_price(provider.clone(), *eth_usd_feed).await?;
-                    if eth_usd.is_zero() {
-                        return Err(AppError::Price(
-                            "received zero ETH/USD price from Chainlink".into(),
-                        ));
-                    }
-                    let price = base_usd / eth_usd;
-                    return Ok(PriceOut {
-                        base: base_info.symbol.clone(),
-                        quote: quote.to_string(),
-                        price: price.to_string(),
-                        source: "chainlink (via USD)".to_string(),
-                        decimals: price.scale() as u32,
-                    });
-                }
+    if quote == QuoteCurrency::ETH
+        && let Some(base_usd_feed) = base_info.chainlink_feeds.get(&QuoteCurrency::USD)
+        && let Some(eth_info) = registry.wrapped_native()
+        && let Some(eth_usd_feed) = eth_info.chainlink_feeds.get(&QuoteCurrency::USD)
+    {
+        let pivot = async {
+            let (base_usd, eth_usd) = timings
+                .time(
+                    Stage::Chainlink,
+                    fetch_chainlink_pair(
+                        provider.clone(),
+                        registry,
+                        (*base_usd_feed, base_info.derived_feeds),
+                        *eth_usd_feed,
+                    ),
+                )
+                .await;
+            let (base_usd, eth_usd) = (base_usd?, eth_usd?);
+            if eth_usd.is_zero() {
+                return Err(AppError::Price(
+                    "received zero ETH/USD price from Chainlink".into(),
+                ));
             }
-        }
+            Ok((base_usd / eth_usd, "chainlink (via USD)"))
+        };
+        return Some(pivot.await);
     }
 
     // Attempt Chainlink via ETH pivot if quote is USD.
-    if quote == QuoteCurrency::USD {
-        if let Some(base_eth_feed) = base_info.chainlink_feeds.get(&QuoteCurrency::ETH) {
-            if let Some(eth_info) = registry.info_by_symbol("WETH") {
-                if let Some(eth_usd_feed) = eth_info.chainlink_feeds.get(&QuoteCurrency::USD) {
-                    let base_eth = fetch_chainlink_price(provider.clone(), *base_eth_feed).await?;
-                    let eth_usd = fetch_chainlink_price(provider.clone(), *eth_usd_feed).await?;
-                    let price = base_eth * eth_usd;
-                    return Ok(PriceOut {
-                        base: base_info.symbol.clone(),
-                        quote: quote.to_string(),
-                        price: price.to_string(),
-                        source: "chainlink (via ETH)".to_string(),
-                        decimals: price.scale() as u32,
-                    });
+    if quote == QuoteCurrency::USD
+        && let Some(base_eth_feed) = base_info.chainlink_feeds.get(&QuoteCurrency::ETH)
+        && let Some(eth_info) = registry.wrapped_native()
+        && let Some(eth_usd_feed) = eth_info.chainlink_feeds.get(&QuoteCurrency::USD)
+    {
+        let pivot = async {
+            let (base_eth, eth_usd) = timings
+                .time(
+                    Stage::Chainlink,
+                    fetch_chainlink_pair(
+                        provider.clone(),
+                        registry,
+                        (*base_eth_feed, base_info.derived_feeds),
+                        *eth_usd_feed,
+                    ),
+                )
+                .await;
+            let (base_eth, eth_usd) = (base_eth?, eth_usd?);
+            Ok((base_eth * eth_usd, "chainlink (via ETH)"))
+        };
+        return Some(pivot.await);
+    }
+
+    None
+}
+
+/// Uniswap V3 quotes (V2 forks when V3 cannot quote) of `base` against `quote`'s proxy tokens.
+async fn uniswap_price<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base_info: &TokenInfo,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> AppResult<PriceOut>
+where
+    M: Middleware + 'static,
+{
+    // Fall back to Uniswap price quotes.
+    let quote_tokens = registry.quote_tokens(quote);
+    if quote_tokens.is_empty() {
+        return Err(AppError::Price("missing quote token configuration".into()));
+    }
+
+    let notional = reference_notional(provider.clone(), registry, quote, timings).await;
+    let mut quotes = Vec::with_capacity(quote_tokens.len());
+    let mut depeg_warnings = Vec::new();
+    let mut v2_sources = Vec::new();
+    let mut last_err = None;
+    for quote_token in quote_tokens {
+        // A token quoted against itself would read as exactly 1; skip it so stablecoins price off peers.
+        if quote_token.address == base_info.address {
+            continue;
+        }
+        let mut quoted = timings
+            .time(
+                Stage::Quoter,
+                fetch_uniswap_price(
+                    provider.clone(),
+                    registry.uniswap_quoter,
+                    base_info,
+                    quote_token,
+                    notional,
+                ),
+            )
+            .await;
+        // Thin or missing V3 pools: try the configured V2 forks on the same pair.
+        if let Err(err) = &quoted
+            && !registry.v2_venues.is_empty()
+        {
+            warn!("uniswap quote via {} failed: {err}", quote_token.symbol);
+            for venue in &registry.v2_venues {
+                quoted = timings
+                    .time(
+                        Stage::Quoter,
+                        venue.price(provider.clone(), base_info, quote_token),
+                    )
+                    .await;
+                if quoted.is_ok() {
+                    v2_sources.push(venue.name.as_str());
+                    break;
+                }
+            }
+        }
+        match quoted {
+            Ok(price) => {
+                let price = if quote == QuoteCurrency::USD {
+                    let (adjusted, warning) =
+                        adjust_for_depeg(provider.clone(), registry, quote_token, price, timings)
+                            .await;
+                    depeg_warnings.extend(warning);
+                    adjusted
+                } else {
+                    price
+                };
+                quotes.push((quote_token.symbol.as_str(), price));
+                if registry.usd_quote_mode == UsdQuoteMode::First || quote != QuoteCurrency::USD {
+                    break;
                 }
             }
+            Err(err) => {
+                warn!("quote via {} failed: {err}", quote_token.symbol);
+                last_err = Some(err);
+            }
         }
     }
+    let Some(decimal_price) = median_price(&quotes) else {
+        return Err(last_err
+            .unwrap_or_else(|| AppError::Price("no quote token other than the base".into())));
+    };
 
-    // Fall back to Uniswap price quotes.
-    let quote_token = registry
-        .quote_token(quote)
-        .ok_or_else(|| AppError::Price("missing quote token configuration".into()))?;
-
-    let decimal_price = fetch_uniswap_price(provider.clone(), base_info, quote_token).await?;
-    let source = format!("uniswap_v3 (fee {})", base_info.default_fee);
+    let via: Vec<&str> = quotes.iter().map(|(symbol, _)| *symbol).collect();
+    // Name the stablecoins only when there was a choice, keeping the single-proxy source stable.
+    let source = if !v2_sources.is_empty() {
+        v2_sources.dedup();
+        format!("{}_v2 (via {})", v2_sources.join("/"), via.join("/"))
+    } else if quote == QuoteCurrency::USD && registry.usd_quotes.len() > 1 {
+        format!(
+            "uniswap_v3 (fee {}, via {})",
+            base_info.default_fee,
+            via.join("/")
+        )
+    } else {
+        format!("uniswap_v3 (fee {})", base_info.default_fee)
+    };
 
     Ok(PriceOut {
         base: base_info.symbol.clone(),
-        quote: quote.to_string(),
+        quote: registry.quote_symbol(quote),
         price: decimal_price.to_string(),
         source,
-        decimals: decimal_price.scale() as u32,
+        decimals: decimal_price.scale(),
+        block_number: None,
+        depeg_warning: (!depeg_warnings.is_empty()).then(|| depeg_warnings.join("; ")),
+        sources: None,
+        timings: None,
+        warnings: Vec::new(),
     })
 }
 
-async fn fetch_chainlink_price<M>(provider: Arc<M>, feed_address: Address) -> AppResult<Decimal>
+/// Decoded `latestRoundData()` result with the answer scaled by the feed's decimals.
+#[derive(Debug, Clone)]
+pub struct ChainlinkRound {
+    pub round_id: u128,
+    /// Signed answer; rate and spread feeds can legitimately report zero or negative values.
+    pub answer: Decimal,
+    pub updated_at: U256,
+}
+
+/// Decimals of Chainlink aggregators by feed address. A feed's decimals are fixed when it is
+/// deployed, so each is read once per process instead of before every round. Clones share the
+/// cache, including the copies the registry makes on write.
+#[derive(Debug, Clone, Default)]
+pub struct FeedDecimals(Arc<RwLock<HashMap<Address, u8>>>);
+
+impl FeedDecimals {
+    pub fn get(&self, feed: Address) -> Option<u8> {
+        self.0
+            .read()
+            .expect("feed decimals lock poisoned")
+            .get(&feed)
+            .copied()
+    }
+
+    /// Cached decimals of `feed`, read from the aggregator on first use.
+    pub async fn read<M>(&self, provider: Arc<M>, feed: Address) -> AppResult<u8>
+    where
+        M: Middleware + 'static,
+    {
+        if let Some(decimals) = self.get(feed) {
+            return Ok(decimals);
+        }
+        let decimals = ChainlinkAggregator::new(feed, provider)
+            .decimals()
+            .call()
+            .await
+            .map_err(|err| AppError::Price(format!("failed to read feed decimals: {err}")))?;
+        self.0
+            .write()
+            .expect("feed decimals lock poisoned")
+            .insert(feed, decimals);
+        Ok(decimals)
+    }
+
+    /// Read the decimals of every feed in `feeds` up front, so price lookups only fetch rounds.
+    /// A feed that fails here is logged, returned and retried on its first lookup.
+    pub async fn prewarm<M>(
+        &self,
+        provider: Arc<M>,
+        feeds: impl IntoIterator<Item = Address>,
+    ) -> Vec<Address>
+    where
+        M: Middleware + 'static,
+    {
+        let mut cached = 0usize;
+        let mut failed = Vec::new();
+        for feed in feeds {
+            match self.read(provider.clone(), feed).await {
+                Ok(_) => cached += 1,
+                Err(err) => {
+                    failed.push(feed);
+                    warn!("could not pre-read decimals of Chainlink feed {feed:#x}: {err}");
+                }
+            }
+        }
+        info!(
+            "cached decimals of {cached} Chainlink feeds ({} failed)",
+            failed.len()
+        );
+        failed
+    }
+}
+
+/// Read the latest round of any Chainlink aggregator, rejecting only incomplete or stale rounds.
+/// The feed's decimals come from `decimals` and are only read on a miss.
+pub async fn read_chainlink_round<M>(
+    provider: Arc<M>,
+    decimals: &FeedDecimals,
+    feed_address: Address,
+) -> AppResult<ChainlinkRound>
 where
     M: Middleware + 'static,
 {
+    let decimals = decimals.read(provider.clone(), feed_address).await?;
     let contract = ChainlinkAggregator::new(feed_address, provider);
-    let decimals = contract
-        .decimals()
-        .call()
-        .await
-        .map_err(|err| AppError::Price(format!("failed to read feed decimals: {err}")))?;
 
-    let round = contract
+    let (round_id, answer, _started_at, updated_at, answered_in_round) = contract
         .latest_round_data()
         .call()
         .await
         .map_err(|err| AppError::Price(format!("failed to read latest round: {err}")))?;
 
-    let answer = round.1;
-    let price_i128 = i128::from_str(&answer.to_string())
-        .map_err(|err| AppError::Price(format!("invalid Chainlink answer: {err}")))?;
+    check_round_complete(round_id, updated_at, answered_in_round)?;
 
-    if price_i128 <= 0 {
-        return Err(AppError::Price(
-            "Chainlink returned non-positive price".into(),
-        ));
+    Ok(ChainlinkRound {
+        round_id,
+        answer: scale_answer(answer, decimals)?,
+        updated_at,
+    })
+}
+
+/// Asset price feeds must be strictly positive; anything else means the feed is broken. A
+/// `derived` feed (a rate or spread) passes its signed answer through.
+async fn fetch_chainlink_price<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    feed_address: Address,
+    derived: bool,
+) -> AppResult<Decimal>
+where
+    M: Middleware + 'static,
+{
+    let round = read_chainlink_round(provider, &registry.feed_decimals, feed_address).await?;
+    if !derived && round.answer <= Decimal::ZERO {
+        return Err(AppError::Price(format!(
+            "Chainlink price feed {feed_address:#x} returned non-positive answer {}",
+            round.answer
+        )));
+    }
+    Ok(round.answer)
+}
+
+/// Both legs of a pivot read concurrently: the base feed (and whether it is derived), then the
+/// native/USD asset feed. Within a request the ETH/USD leg is shared through the block-scoped
+/// provider cache, so repeated pivots do not read it twice.
+async fn fetch_chainlink_pair<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    (base, derived): (Address, bool),
+    native_usd: Address,
+) -> (AppResult<Decimal>, AppResult<Decimal>)
+where
+    M: Middleware + 'static,
+{
+    tokio::join!(
+        fetch_chainlink_price(provider.clone(), registry, base, derived),
+        fetch_chainlink_price(provider, registry, native_usd, false),
+    )
+}
+
+fn check_round_complete(
+    round_id: u128,
+    updated_at: U256,
+    answered_in_round: u128,
+) -> AppResult<()> {
+    if updated_at.is_zero() {
+        return Err(AppError::Price(format!(
+            "Chainlink round {round_id} is incomplete (updatedAt = 0)"
+        )));
+    }
+    if answered_in_round < round_id {
+        return Err(AppError::Price(format!(
+            "Chainlink round {round_id} carries a stale answer from round {answered_in_round}"
+        )));
     }
+    Ok(())
+}
 
-    Ok(Decimal::from_i128_with_scale(price_i128, decimals as u32))
+/// Scale an `int256` answer by `decimals` in `U256` arithmetic, so feeds with 18 or more
+/// decimals and magnitudes past `i128` convert exactly as far as `Decimal` allows: fractional
+/// digits beyond its 28 places are truncated, and only an integer part past 96 bits errors.
+fn scale_answer(answer: I256, decimals: u8) -> AppResult<Decimal> {
+    let scale = u32::from(decimals).min(MAX_DECIMAL_SCALE);
+    // 10^78 and up exceed every `int256`, so the answer scales to zero.
+    let Some(divisor) = balance::pow10(u32::from(decimals) - scale) else {
+        return Ok(Decimal::ZERO);
+    };
+    let mut mantissa = answer.unsigned_abs() / divisor;
+    let mut scale = scale;
+    while mantissa > *MAX_DECIMAL_MANTISSA && scale > 0 {
+        mantissa /= 10;
+        scale -= 1;
+    }
+    if mantissa > *MAX_DECIMAL_MANTISSA {
+        return Err(AppError::Price(format!(
+            "Chainlink answer {answer} with {decimals} decimals is out of range"
+        )));
+    }
+    let value = Decimal::from_i128_with_scale(mantissa.as_u128() as i128, scale);
+    Ok(if answer.is_negative() { -value } else { value })
 }
 
+/// Uniswap sell price of `base` in `quote`: one whole `base`, or with a `notional` (in `quote`
+/// units) as much `base` as that notional buys, normalized back to a unit price.
 async fn fetch_uniswap_price<M>(
     provider: Arc<M>,
+    quoter: Address,
     base: &TokenInfo,
     quote: &TokenInfo,
+    notional: Option<Decimal>,
 ) -> AppResult<Decimal>
 where
     M: Middleware + 'static,
 {
-    let quoter = UniswapQuoterV2::new(*UNISWAP_QUOTER_V2, provider.clone());
+    let quoter = UniswapQuoterV2::new(quoter, provider.clone());
 
-    let amount_in = ten_pow(base.decimals as u32);
+    let amount_in = match notional {
+        Some(notional) => {
+            let params = QuoteExactInputSingleParams {
+                token_in: quote.address,
+                token_out: base.address,
+                amount_in: balance::parse_with_decimals(notional, quote.decimals as u32)?,
+                fee: base.default_fee,
+                sqrt_price_limit_x96: U256::zero(),
+            };
+            let (base_out, _, _, _) = quoter
+                .quote_exact_input_single(params)
+                .call()
+                .await
+                .map_err(|err| AppError::Price(format!("uniswap sizing quote failed: {err}")))?;
+            if base_out.is_zero() {
+                return Err(AppError::Price(
+                    "uniswap sizing quote returned zero amount out".into(),
+                ));
+            }
+            base_out
+        }
+        None => ten_pow(base.decimals as u32),
+    };
     let params = QuoteExactInputSingleParams {
         token_in: base.address,
         token_out: quote.address,
@@ -282,8 +1249,243 @@ where
     }
 
     let formatted = balance::format_with_decimals(&amount_out, quote.decimals as u32);
-    Decimal::from_str_exact(&formatted)
-        .map_err(|err| AppError::Price(format!("failed to parse uniswap result: {err}")))
+    let out = Decimal::from_str_exact(&formatted)
+        .map_err(|err| AppError::Price(format!("failed to parse uniswap result: {err}")))?;
+    if notional.is_none() {
+        return Ok(out);
+    }
+    let sized = balance::format_with_decimals(&amount_in, base.decimals as u32);
+    let sized = Decimal::from_str_exact(&sized)
+        .map_err(|err| AppError::Price(format!("failed to parse uniswap sizing: {err}")))?;
+    out.checked_div(sized)
+        .map(|price| price.round_dp(18).normalize())
+        .ok_or_else(|| AppError::Price("uniswap unit price out of range".into()))
+}
+
+/// The configured USD notional in `quote` units; `ETH` quotes convert it with the native
+/// coin's Chainlink USD feed and fall back to one-token quotes without one.
+async fn reference_notional<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> Option<Decimal>
+where
+    M: Middleware + 'static,
+{
+    let usd = registry.uniswap_reference_usd?;
+    match quote {
+        QuoteCurrency::USD => Some(usd),
+        QuoteCurrency::ETH => {
+            let feed = registry
+                .wrapped_native()?
+                .chainlink_feeds
+                .get(&QuoteCurrency::USD)?;
+            match timings
+                .time(
+                    Stage::Chainlink,
+                    fetch_chainlink_price(provider, registry, *feed, false),
+                )
+                .await
+            {
+                Ok(native_usd) if !native_usd.is_zero() => Some(usd / native_usd),
+                Ok(_) => None,
+                Err(err) => {
+                    warn!("native USD price for quote sizing failed: {err}");
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Time-weighted average of `base` in `quote`'s first proxy token over [`TWAP_WINDOW_SECS`],
+/// read from the pool at `base`'s default fee.
+async fn twap_price<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base_info: &TokenInfo,
+    quote: QuoteCurrency,
+    timings: &mut Timings,
+) -> AppResult<Decimal>
+where
+    M: Middleware + 'static,
+{
+    let quote_token = registry
+        .quote_tokens(quote)
+        .into_iter()
+        .find(|token| token.address != base_info.address)
+        .ok_or_else(|| AppError::Price("no quote token other than the base".into()))?;
+    let pool = UniswapV3Pool::new(
+        pool_address(
+            registry.uniswap_factory,
+            base_info.address,
+            quote_token.address,
+            base_info.default_fee,
+        ),
+        provider.clone(),
+    );
+    let observe = pool.observe(vec![TWAP_WINDOW_SECS, 0]);
+    let (cumulatives, _) = timings
+        .time(Stage::Quoter, observe.call())
+        .await
+        .map_err(|err| AppError::Price(format!("uniswap TWAP observation failed: {err}")))?;
+    let [older, newer] = cumulatives[..] else {
+        return Err(AppError::Price(
+            "uniswap TWAP returned an unexpected observation count".into(),
+        ));
+    };
+    let price = twap_from_ticks(older, newer, TWAP_WINDOW_SECS, base_info, quote_token)?;
+    if quote == QuoteCurrency::USD {
+        let (adjusted, _) = adjust_for_depeg(provider, registry, quote_token, price, timings).await;
+        return Ok(adjusted);
+    }
+    Ok(price)
+}
+
+/// `base` price in `quote` from two tick cumulatives `window` seconds apart.
+fn twap_from_ticks(
+    older: i64,
+    newer: i64,
+    window: u32,
+    base: &TokenInfo,
+    quote: &TokenInfo,
+) -> AppResult<Decimal> {
+    let window = i64::from(window);
+    let delta = newer - older;
+    // Round toward negative infinity like Uniswap's OracleLibrary.
+    let mut tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        tick -= 1;
+    }
+    let out_of_range = || AppError::Price(format!("uniswap TWAP tick {tick} is out of range"));
+    let sqrt_price = i32::try_from(tick)
+        .map_err(|_| out_of_range())
+        .and_then(uniswap::sqrt_ratio_at_tick)?;
+
+    // token1 per token0 in raw units is sqrtPriceX96^2 / 2^192; decimals turn it into whole
+    // tokens, exactly as `sqrt_price_limit_from_price` goes the other way.
+    let base_is_token0 = base.address < quote.address;
+    let (decimals0, decimals1) = if base_is_token0 {
+        (base.decimals, quote.decimals)
+    } else {
+        (quote.decimals, base.decimals)
+    };
+    let shift = |decimals: u8| {
+        balance::pow10(u32::from(decimals))
+            .map(U512::from)
+            .ok_or_else(out_of_range)
+    };
+    let num = sqrt_price
+        .full_mul(sqrt_price)
+        .checked_mul(shift(decimals0)?)
+        .ok_or_else(out_of_range)?;
+    let den = (U512::one() << 192)
+        .checked_mul(shift(decimals1)?)
+        .ok_or_else(out_of_range)?;
+    let (num, den) = if base_is_token0 {
+        (num, den)
+    } else {
+        (den, num)
+    };
+    ratio_to_decimal(num, den)
+        .filter(|price| !price.is_zero())
+        .map(|price| price.round_sf(12).unwrap_or(price).normalize())
+        .ok_or_else(out_of_range)
+}
+
+/// `num / den` with as many of `Decimal`'s 28 fractional digits as fit; `None` when even the
+/// integer part is past 96 bits.
+fn ratio_to_decimal(num: U512, den: U512) -> Option<Decimal> {
+    let max_mantissa = U512::from(*MAX_DECIMAL_MANTISSA);
+    (0..=MAX_DECIMAL_SCALE).rev().find_map(|scale| {
+        let scaled = num.checked_mul(U512::exp10(scale as usize))? / den;
+        (scaled <= max_mantissa)
+            .then(|| Decimal::from_i128_with_scale(scaled.as_u128() as i128, scale))
+    })
+}
+
+/// Rescale a price denominated in a stablecoin by that stablecoin's own Chainlink USD price
+/// when it strays from $1 by more than the configured threshold.
+async fn adjust_for_depeg<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    stable: &TokenInfo,
+    price: Decimal,
+    timings: &mut Timings,
+) -> (Decimal, Option<String>)
+where
+    M: Middleware + 'static,
+{
+    let Some(feed) = stable.chainlink_feeds.get(&QuoteCurrency::USD) else {
+        return (price, None);
+    };
+    let stable_usd = timings
+        .time(
+            Stage::Chainlink,
+            fetch_chainlink_price(provider, registry, *feed, false),
+        )
+        .await;
+    match stable_usd {
+        Ok(stable_usd) if exceeds_peg_threshold(stable_usd, registry.depeg_threshold_bps) => {
+            let warning = format!(
+                "{} trades at ${stable_usd} on Chainlink; price rescaled from {price} {}",
+                stable.symbol, stable.symbol
+            );
+            warn!("{warning}");
+            (price * stable_usd, Some(warning))
+        }
+        Ok(_) => (price, None),
+        Err(err) => {
+            let warning = format!("could not verify {} peg: {err}", stable.symbol);
+            warn!("{warning}");
+            (price, Some(warning))
+        }
+    }
+}
+
+fn exceeds_peg_threshold(stable_usd: Decimal, threshold_bps: u32) -> bool {
+    let deviation_bps = (stable_usd - Decimal::ONE).abs() * Decimal::from(10_000u32);
+    deviation_bps > Decimal::from(threshold_bps)
+}
+
+/// Flip a resolved price to the other orientation: `base` per one `quote` (e.g. USDC per ETH).
+///
+/// Feeds and pools only exist one way round, so the inverse is derived rather than looked up.
+pub fn invert_price(price: PriceOut) -> AppResult<PriceOut> {
+    let value = Decimal::from_str(&price.price)
+        .map_err(|err| AppError::Price(format!("cannot invert price {}: {err}", price.price)))?;
+    let inverted = Decimal::ONE
+        .checked_div(value)
+        .ok_or_else(|| AppError::Price(format!("cannot invert price {}", price.price)))?
+        .round_dp(INVERTED_PRICE_DECIMALS)
+        .normalize();
+    if inverted.is_zero() {
+        return Err(AppError::Price(format!(
+            "inverse of {} {} per {} is below {INVERTED_PRICE_DECIMALS} decimals",
+            price.price, price.quote, price.base
+        )));
+    }
+    Ok(PriceOut {
+        base: price.quote,
+        quote: price.base,
+        price: inverted.to_string(),
+        source: format!("{} (inverted)", price.source),
+        decimals: inverted.scale(),
+        ..price
+    })
+}
+
+/// Median of the collected quotes; even counts average the middle pair.
+fn median_price(quotes: &[(&str, Decimal)]) -> Option<Decimal> {
+    let mut prices: Vec<Decimal> = quotes.iter().map(|(_, price)| *price).collect();
+    prices.sort();
+    let mid = prices.len() / 2;
+    match prices.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(prices[mid]),
+        _ => Some((prices[mid - 1] + prices[mid]) / Decimal::TWO),
+    }
 }
 
 fn ten_pow(decimals: u32) -> U256 {
@@ -308,6 +1510,409 @@ mod tests {
         Arc::new(provider)
     }
 
+    #[test]
+    fn defaults_seed_listed_decimals() {
+        let registry = TokenRegistry::with_defaults();
+        let usdc = registry.resolve_symbol("USDC").unwrap();
+        assert_eq!(registry.listed_decimals(usdc), Some(6));
+        assert!(registry.check_decimals(usdc, 6).is_none());
+        assert!(registry.check_decimals(usdc, 18).is_some());
+        assert!(registry.check_decimals(Address::from_low_u64_be(1), 9).is_none());
+    }
+
+    #[test]
+    fn token_list_filters_by_chain() {
+        let raw = r#"{"name":"test","tokens":[
+            {"chainId":1,"address":"0x0000000000000000000000000000000000000001","decimals":8,"symbol":"A"},
+            {"chainId":10,"address":"0x0000000000000000000000000000000000000002","decimals":6,"symbol":"B"}
+        ]}"#;
+        let mut registry = TokenRegistry::new();
+        assert_eq!(registry.load_token_list(raw, 1).unwrap(), 1);
+        assert_eq!(registry.listed_decimals(Address::from_low_u64_be(1)), Some(8));
+        assert_eq!(registry.listed_decimals(Address::from_low_u64_be(2)), None);
+        assert!(registry.load_token_list("[]", 1).is_err());
+    }
+
+    #[test]
+    fn eth_quotes_use_the_chains_wrapped_native() {
+        let wmatic = Address::from_low_u64_be(0x1270);
+        let mut registry = TokenRegistry::with_defaults().with_native(NativeCurrency {
+            symbol: "MATIC".into(),
+            wrapped: Some(wmatic),
+            usd_feed: None,
+        });
+        assert!(registry.quote_token(QuoteCurrency::ETH).is_none());
+
+        registry.add_token(TokenInfo::new("WMATIC", wmatic, 18));
+        assert_eq!(
+            registry.quote_token(QuoteCurrency::ETH).map(|info| info.address),
+            Some(wmatic)
+        );
+        assert_eq!(registry.quote_symbol(QuoteCurrency::ETH), "MATIC");
+        assert_eq!(registry.quote_symbol(QuoteCurrency::USD), "USD");
+    }
+
+    #[test]
+    fn usd_quotes_follow_configured_order() {
+        let registry = TokenRegistry::with_defaults()
+            .with_usd_quotes(vec!["usdt".into(), "FOO".into(), "dai".into()], UsdQuoteMode::Median);
+        let symbols: Vec<_> = registry
+            .quote_tokens(QuoteCurrency::USD)
+            .iter()
+            .map(|info| info.symbol.as_str())
+            .collect();
+        assert_eq!(symbols, vec!["USDT", "DAI"]);
+        assert_eq!(registry.quote_token(QuoteCurrency::USD).unwrap().symbol, "USDT");
+        assert_eq!("MEDIAN".parse::<UsdQuoteMode>().unwrap(), UsdQuoteMode::Median);
+    }
+
+    #[test]
+    fn shared_symbols_follow_collision_policy() {
+        let bridged = TokenRegistry::with_defaults()
+            .resolve_symbol("USDC")
+            .unwrap();
+        let native = Address::from_low_u64_be(0xaf88);
+        let mut registry = TokenRegistry::with_defaults().with_chain_id(42_161);
+        registry.add_token(TokenInfo::new("USDC", native, 6));
+
+        assert_eq!(registry.resolve_token("usdc").unwrap(), bridged);
+        let qualified = format!("USDC:{native:#x}");
+        assert_eq!(registry.resolve_token(&qualified).unwrap(), native);
+        assert!(registry.resolve_token(&format!("DAI:{native:#x}")).is_err());
+
+        let listing = registry.list(None, true);
+        assert_eq!(listing.duplicates.len(), 1);
+        assert_eq!(listing.duplicates[0].addresses.len(), 2);
+        let preferred = listing.tokens.iter().filter(|token| token.preferred);
+        assert_eq!(preferred.count(), 1);
+
+        let scoped = registry
+            .clone()
+            .with_collision_policy(SymbolCollisionPolicy::ChainScoped);
+        assert_eq!(scoped.resolve_token("USDC").unwrap(), native);
+
+        let strict = registry.with_collision_policy("error".parse().unwrap());
+        let err = strict.resolve_token("USDC").unwrap_err();
+        assert!(err.to_string().contains(&qualified));
+        let listing = strict.list(Some("usdc"), false);
+        assert_eq!(listing.duplicates[0].resolves_to, None);
+        assert!(strict.resolve_token("DAI").is_ok());
+    }
+
+    #[test]
+    fn aliases_and_native_symbol_resolve_to_tokens() {
+        let registry = TokenRegistry::with_defaults();
+        let weth = registry.resolve_symbol("WETH").unwrap();
+        let wbtc = registry.resolve_symbol("WBTC").unwrap();
+        let registry = registry
+            .with_native(NativeCurrency {
+                symbol: "ETH".into(),
+                wrapped: Some(weth),
+                usd_feed: None,
+            })
+            .with_aliases(HashMap::from([
+                ("btc".to_string(), "WBTC".to_string()),
+                ("OOPS".to_string(), "NOPE".to_string()),
+            ]));
+
+        assert_eq!(registry.resolve_token("eth").unwrap(), weth);
+        assert!(registry.is_native_symbol("Eth"));
+        assert_eq!(registry.resolve_token("BTC").unwrap(), wbtc);
+        let err = registry.resolve_token("oops").unwrap_err();
+        assert!(err.to_string().contains("alias oops -> NOPE"));
+        let err = registry.resolve_token("wbtcc").unwrap_err();
+        assert!(err.to_string().ends_with("did you mean one of WBTC, BTC?"));
+
+        let listing = registry.list(Some("WETH"), false);
+        assert_eq!(listing.aliases["ETH"], format!("WETH:{weth:#x}"));
+        assert_eq!(listing.aliases["BTC"], "WBTC");
+
+        let registry = registry.with_native_alias(false);
+        assert!(registry.resolve_token("ETH").is_err());
+    }
+
+    #[test]
+    fn scale_answer_handles_sign_and_width() {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        assert_eq!(scale_answer(I256::from(250_000_000i64), 8).unwrap(), d("2.5"));
+        assert_eq!(scale_answer(I256::from(-125i64), 2).unwrap(), d("-1.25"));
+        assert_eq!(scale_answer(I256::from(7i64), 4).unwrap(), d("0.0007"));
+        assert_eq!(scale_answer(I256::zero(), 18).unwrap(), Decimal::ZERO);
+
+        // Wider than i128 but still representable once scaled.
+        let wide = I256::from_dec_str("1361129467683753853853498429727072845824").unwrap();
+        assert_eq!(scale_answer(wide, 18).unwrap(), d("1361129467683753853853.4984297"));
+        assert!(scale_answer(I256::MAX, 0).is_err());
+        assert!(scale_answer(-wide, 0).is_err());
+    }
+
+    #[test]
+    fn scale_answer_handles_exotic_feeds() {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        // An 18-decimal feed whose raw answer is far past `i128`.
+        let raw = I256::from_dec_str("-123456789012345678901234567890123456789012").unwrap();
+        assert_eq!(
+            scale_answer(raw, 18).unwrap(),
+            d("-123456789012345678901234.56789")
+        );
+        // Digits past 28 places are truncated, never rounded into range errors.
+        assert_eq!(
+            scale_answer(I256::from(123_456_789i64), 36).unwrap(),
+            d("0.0000000000000000000000000001")
+        );
+        assert_eq!(scale_answer(I256::MAX, 255).unwrap(), Decimal::ZERO);
+        assert_eq!(
+            scale_answer(I256::MAX, 77).unwrap(),
+            d("0.5789604461865809771178549250")
+        );
+    }
+
+    #[test]
+    fn incomplete_and_stale_rounds_are_rejected() {
+        assert!(check_round_complete(5, U256::from(1), 5).is_ok());
+        assert!(check_round_complete(5, U256::zero(), 5).is_err());
+        let err = check_round_complete(5, U256::from(1), 4).unwrap_err();
+        assert!(err.to_string().contains("stale"));
+    }
+
+    #[test]
+    fn peg_threshold_is_symmetric() {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        assert!(!exceeds_peg_threshold(d("0.9990"), 50));
+        assert!(!exceeds_peg_threshold(d("1.0050"), 50));
+        assert!(exceeds_peg_threshold(d("1.0051"), 50));
+        assert!(exceeds_peg_threshold(d("0.8800"), 50));
+    }
+
+    #[test]
+    fn median_price_handles_odd_and_even() {
+        let d = |v: &str| Decimal::from_str(v).unwrap();
+        assert_eq!(median_price(&[]), None);
+        assert_eq!(
+            median_price(&[("A", d("1.02")), ("B", d("0.99")), ("C", d("1.00"))]),
+            Some(d("1.00"))
+        );
+        assert_eq!(median_price(&[("A", d("1.02")), ("B", d("1.00"))]), Some(d("1.01")));
+    }
+
+    #[test]
+    fn inverts_price_orientation() {
+        let price = |value: &str| PriceOut {
+            base: "USDC".into(),
+            quote: "ETH".into(),
+            price: value.into(),
+            source: "chainlink".into(),
+            decimals: 18,
+            block_number: Some(1),
+            depeg_warning: None,
+            sources: None,
+            timings: None,
+            warnings: Vec::new(),
+        };
+
+        let inverted = invert_price(price("0.0004")).unwrap();
+        assert_eq!(inverted.base, "ETH");
+        assert_eq!(inverted.quote, "USDC");
+        assert_eq!(inverted.price, "2500");
+        assert_eq!(inverted.decimals, 0);
+        assert_eq!(inverted.source, "chainlink (inverted)");
+        assert_eq!(inverted.block_number, Some(1));
+
+        let third = invert_price(price("3")).unwrap();
+        assert_eq!(third.price, "0.333333333333333333");
+        assert_eq!(third.decimals, 18);
+        assert!(invert_price(price("0")).is_err());
+    }
+
+    #[tokio::test]
+    async fn uniswap_fallback_normalizes_sized_quotes() {
+        use ethers::abi::{self, Token};
+
+        let (provider, mock) = Provider::mocked();
+        let wbtc = TokenInfo::new("WBTC", Address::from_low_u64_be(1), 8);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(2), 6);
+        let quoted = |amount: u64| {
+            let data = abi::encode(&[
+                Token::Uint(U256::from(amount)),
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::one()),
+                Token::Uint(U256::from(100_000u64)),
+            ]);
+            format!("0x{}", hex::encode(data))
+        };
+        // Responses are consumed in reverse order: 1,000 USDC buys 0.01 WBTC, which sells for
+        // 999.5 USDC.
+        mock.push::<String, _>(quoted(999_500_000)).unwrap();
+        mock.push::<String, _>(quoted(1_000_000)).unwrap();
+
+        let price = fetch_uniswap_price(
+            Arc::new(provider),
+            *UNISWAP_QUOTER_V2,
+            &wbtc,
+            &usdc,
+            Some(Decimal::from(1_000)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(price, Decimal::from(99_950));
+    }
+
+    #[test]
+    fn twap_ticks_round_down_and_follow_token_order() {
+        let weth = TokenInfo::new("WETH", Address::from_low_u64_be(1), 18);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(2), 6);
+        let a = TokenInfo::new("A", Address::from_low_u64_be(1), 18);
+        let b = TokenInfo::new("B", Address::from_low_u64_be(2), 18);
+        // Tick -200_312 is ~1999.84 USDC per WETH with WETH as token0, computed from the
+        // integer sqrtPriceX96 rather than floats.
+        let price = twap_from_ticks(0, -200_312 * 1_800, 1_800, &weth, &usdc).unwrap();
+        assert_eq!(price.to_string(), "1999.84030562");
+        let inverse = twap_from_ticks(0, -200_312 * 1_800, 1_800, &usdc, &weth).unwrap();
+        assert_eq!(inverse.to_string(), "0.000500039926784");
+        assert_eq!(twap_from_ticks(0, 0, 1_800, &a, &b).unwrap(), Decimal::ONE);
+        assert!(twap_from_ticks(0, 900_000 * 1_800, 1_800, &weth, &usdc).is_err());
+
+        // -5 over 2 seconds averages to tick -3, not -2.
+        let floored = twap_from_ticks(0, -5, 2, &a, &b).unwrap();
+        assert_eq!(floored, twap_from_ticks(0, -6, 2, &a, &b).unwrap());
+    }
+
+    #[tokio::test]
+    async fn median_mode_reports_every_source() {
+        use ethers::abi::{self, Token};
+
+        let (provider, mock) = Provider::mocked();
+        let tkn = Address::from_low_u64_be(0x10);
+        let weth = Address::from_low_u64_be(0x20);
+        let mut registry = TokenRegistry::new().with_native(NativeCurrency {
+            symbol: "ETH".into(),
+            wrapped: Some(weth),
+            usd_feed: None,
+        });
+        registry.add_token(TokenInfo::new("TKN", tkn, 18));
+        registry.add_token(TokenInfo::new("WETH", weth, 18));
+
+        let observed = abi::encode(&[
+            Token::Array(vec![
+                Token::Int(U256::zero()),
+                Token::Int(I256::from(-62_149i64 * 1_800).into_raw()),
+            ]),
+            Token::Array(vec![Token::Uint(U256::zero()), Token::Uint(U256::zero())]),
+        ]);
+        let quoted = abi::encode(&[
+            Token::Uint(U256::from(2_000_000_000_000_000u64)),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::one()),
+            Token::Uint(U256::from(100_000u64)),
+        ]);
+        // Responses are consumed in reverse order: the spot quote is requested before the TWAP.
+        mock.push::<String, _>(format!("0x{}", hex::encode(observed)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(quoted)))
+            .unwrap();
+
+        let out = resolve_median_price(
+            Arc::new(provider),
+            &registry,
+            tkn,
+            QuoteCurrency::ETH,
+            &mut Timings::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(out.source, "median (uniswap_v3/uniswap_v3_twap)");
+        let sources = out.sources.unwrap();
+        assert_eq!(sources.len(), 3);
+        assert!(sources[0].error.as_deref().unwrap().contains("no Chainlink feed"));
+        assert_eq!(sources[1].price.as_deref(), Some("0.002"));
+        let price = Decimal::from_str(&out.price).unwrap();
+        assert!(price > Decimal::new(2, 3) && price < Decimal::new(20_001, 7));
+    }
+
+    #[tokio::test]
+    async fn pivots_read_both_feeds() {
+        use ethers::abi::{self, Token};
+
+        let (provider, mock) = Provider::mocked();
+        let tkn = Address::from_low_u64_be(0x10);
+        let weth = Address::from_low_u64_be(0x20);
+        let mut registry = TokenRegistry::new().with_native(NativeCurrency {
+            symbol: "ETH".into(),
+            wrapped: Some(weth),
+            usd_feed: None,
+        });
+        registry.add_token(
+            TokenInfo::new("TKN", tkn, 18)
+                .with_feed(QuoteCurrency::USD, Address::from_low_u64_be(0xf1)),
+        );
+        registry.add_token(
+            TokenInfo::new("WETH", weth, 18)
+                .with_feed(QuoteCurrency::USD, Address::from_low_u64_be(0xf2)),
+        );
+
+        let encoded = |tokens: &[Token]| format!("0x{}", hex::encode(abi::encode(tokens)));
+        let round = |answer: u64| {
+            encoded(&[
+                Token::Uint(U256::from(7)),
+                Token::Int(U256::from(answer)),
+                Token::Uint(U256::one()),
+                Token::Uint(U256::one()),
+                Token::Uint(U256::from(7)),
+            ])
+        };
+        // Responses are consumed in reverse order: the TKN/USD leg, then ETH/USD.
+        mock.push::<String, _>(round(200_000_000_000)).unwrap();
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(8))]))
+            .unwrap();
+        mock.push::<String, _>(round(300_000_000)).unwrap();
+        mock.push::<String, _>(encoded(&[Token::Uint(U256::from(8))]))
+            .unwrap();
+
+        let out = resolve_token_price(Arc::new(provider), &registry, tkn, QuoteCurrency::ETH)
+            .await
+            .unwrap();
+        assert_eq!(out.source, "chainlink (via USD)");
+        assert_eq!(out.price, "0.0015");
+    }
+
+    #[tokio::test]
+    async fn derived_feeds_report_signed_answers() {
+        use ethers::abi::{self, Token};
+
+        let spread = Address::from_low_u64_be(0x30);
+        let feed = Address::from_low_u64_be(0xf3);
+        let encoded = |tokens: &[Token]| format!("0x{}", hex::encode(abi::encode(tokens)));
+        let negative_round = encoded(&[
+            Token::Uint(U256::from(7)),
+            Token::Int(I256::from(-25_000_000i64).into_raw()),
+            Token::Uint(U256::one()),
+            Token::Uint(U256::one()),
+            Token::Uint(U256::from(7)),
+        ]);
+        let price = |derived: bool| {
+            let (provider, mock) = Provider::mocked();
+            // Responses are consumed in reverse order: decimals, then the round.
+            mock.push::<String, _>(negative_round.clone()).unwrap();
+            mock.push::<String, _>(encoded(&[Token::Uint(U256::from(8))]))
+                .unwrap();
+            let mut info = TokenInfo::new("SPREAD", spread, 18).with_feed(QuoteCurrency::USD, feed);
+            if derived {
+                info = info.with_derived_feeds();
+            }
+            let mut registry = TokenRegistry::new();
+            registry.add_token(info);
+            async move {
+                resolve_token_price(Arc::new(provider), &registry, spread, QuoteCurrency::USD).await
+            }
+        };
+
+        let out = price(true).await.unwrap();
+        assert_eq!(out.source, "chainlink");
+        assert_eq!(out.price, "-0.25000000");
+        let err = price(false).await.unwrap_err();
+        assert!(err.to_string().contains("non-positive"), "{err}");
+    }
+
     #[test]
     fn ten_pow_works() {
         let result = ten_pow(18);
@@ -4,7 +4,10 @@ use ethers::types::Address;
 use serde::Deserialize;
 use serde_json::from_str;
 
-use crate::types::QuoteCurrency;
+use crate::{
+    error::{AppError, AppResult},
+    types::QuoteCurrency,
+};
 
 use super::{TokenInfo, TokenRegistry};
 
@@ -15,10 +18,27 @@ struct TokenDefaultsEntry {
     decimals: u8,
     #[serde(default)]
     chainlink_feeds: HashMap<QuoteCurrency, String>,
+    /// Rate or spread feeds, whose answers may be zero or negative.
+    #[serde(default)]
+    derived_feeds: bool,
     #[serde(default = "default_fee")]
     default_fee: u32,
 }
 
+/// Subset of the Uniswap token list schema needed for decimals cross-checks.
+#[derive(Debug, Deserialize)]
+struct TokenList {
+    tokens: Vec<TokenListEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenListEntry {
+    chain_id: u64,
+    address: String,
+    decimals: u8,
+}
+
 const DEFAULTS_JSON: &str = include_str!("../../../config/token_defaults.json");
 
 pub(crate) fn populate_defaults(registry: &mut TokenRegistry) {
@@ -29,6 +49,7 @@ pub(crate) fn populate_defaults(registry: &mut TokenRegistry) {
         let address = Address::from_str(&entry.address)
             .unwrap_or_else(|_| panic!("invalid token address for {}", entry.symbol));
 
+        registry.add_listed_decimals(address, entry.decimals);
         let mut info = TokenInfo::new(entry.symbol, address, entry.decimals);
 
         for (quote, feed_addr) in entry.chainlink_feeds {
@@ -36,12 +57,32 @@ pub(crate) fn populate_defaults(registry: &mut TokenRegistry) {
                 .unwrap_or_else(|_| panic!("invalid feed address for {:?}", quote));
             info = info.with_feed(quote, feed);
         }
+        if entry.derived_feeds {
+            info = info.with_derived_feeds();
+        }
 
-        info = info.with_fee(entry.default_fee);
+        // The bundled list holds mainnet deployments, whatever chain the server runs on.
+        info = info.with_fee(entry.default_fee).with_chain(1);
         registry.add_token(info);
     }
 }
 
+pub(crate) fn parse_token_list(raw: &str, chain_id: u64) -> AppResult<Vec<(Address, u8)>> {
+    let list: TokenList = from_str(raw)
+        .map_err(|err| AppError::Config(format!("invalid token list: {err}")))?;
+
+    list.tokens
+        .into_iter()
+        .filter(|entry| entry.chain_id == chain_id)
+        .map(|entry| {
+            let address = Address::from_str(&entry.address).map_err(|_| {
+                AppError::Config(format!("invalid token list address: {}", entry.address))
+            })?;
+            Ok((address, entry.decimals))
+        })
+        .collect()
+}
+
 fn default_fee() -> u32 {
     3_000
 }
@@ -0,0 +1,431 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+#[cfg(feature = "mempool")]
+use ethers::providers::Ws;
+use ethers::providers::{Middleware, Provider};
+use serde::Deserialize;
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{info, warn};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::rpc::{BalanceStrategy, BalancedHttp, RpcProvider},
+    redact::redact,
+    types::{ChainProviderOut, ProviderPoolOut},
+};
+
+/// Consecutive failures after which a chain's cached HTTP provider is dropped and rebuilt on
+/// next use.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// Endpoints of a chain the server can reach besides the active one (`[[chains]]`).
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ChainEndpoints {
+    pub chain_id: u64,
+    pub rpc_url: String,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+}
+
+impl ChainEndpoints {
+    /// Entries from `CHAIN_RPC_URLS` and `CHAIN_WS_URLS` pairs (`chain_id=url`), by chain id.
+    pub fn from_pairs(
+        rpc_urls: HashMap<String, String>,
+        mut ws_urls: HashMap<String, String>,
+    ) -> AppResult<Vec<Self>> {
+        let mut chains = BTreeMap::new();
+        for (chain_id, rpc_url) in rpc_urls {
+            let ws_url = ws_urls.remove(&chain_id);
+            let chain_id = parse_chain_id("CHAIN_RPC_URLS", &chain_id)?;
+            chains.insert(
+                chain_id,
+                Self {
+                    chain_id,
+                    rpc_url,
+                    ws_url,
+                },
+            );
+        }
+        if let Some(chain_id) = ws_urls.keys().next() {
+            return Err(AppError::Config(format!(
+                "CHAIN_WS_URLS has chain {chain_id} without a CHAIN_RPC_URLS entry"
+            )));
+        }
+        Ok(chains.into_values().collect())
+    }
+}
+
+fn parse_chain_id(var: &str, raw: &str) -> AppResult<u64> {
+    raw.parse::<u64>()
+        .ok()
+        .filter(|chain_id| *chain_id != 0)
+        .ok_or_else(|| AppError::Config(format!("{var} has an invalid chain id: {raw}")))
+}
+
+/// Which connection of a chain a success or failure is reported for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Http,
+    Ws,
+}
+
+#[derive(Debug, Default)]
+struct ChainHealth {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+struct ChainSlot {
+    endpoints: ChainEndpoints,
+    http: AsyncMutex<Option<Arc<RpcProvider>>>,
+    #[cfg(feature = "mempool")]
+    ws: AsyncMutex<Option<Arc<Provider<Ws>>>>,
+    health: Mutex<ChainHealth>,
+}
+
+impl ChainSlot {
+    fn new(endpoints: ChainEndpoints, http: Option<Arc<RpcProvider>>) -> Self {
+        Self {
+            endpoints,
+            http: AsyncMutex::new(http),
+            #[cfg(feature = "mempool")]
+            ws: AsyncMutex::new(None),
+            health: Mutex::new(ChainHealth::default()),
+        }
+    }
+}
+
+/// Providers per chain, built on first use and cached until they keep failing or the server
+/// shuts down.
+///
+/// The active chain's HTTP provider is the one verified at startup and is never rebuilt; it
+/// balances its own endpoints. Other chains are connected (and their chain id checked) only
+/// when a tool first needs them, so a slow or dead endpoint for one network does not hold up
+/// startup.
+pub struct ProviderPool {
+    active_chain: u64,
+    strategy: BalanceStrategy,
+    slots: HashMap<u64, ChainSlot>,
+    closed: AtomicBool,
+}
+
+impl ProviderPool {
+    /// A pool holding only the active chain, with its WebSocket endpoint if one is configured.
+    pub fn new(active_chain: u64, provider: Arc<RpcProvider>, ws_url: Option<String>) -> Self {
+        let active = ChainEndpoints {
+            chain_id: active_chain,
+            rpc_url: String::new(),
+            ws_url,
+        };
+        Self {
+            active_chain,
+            strategy: BalanceStrategy::default(),
+            slots: HashMap::from([(active_chain, ChainSlot::new(active, Some(provider)))]),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// The active chain plus every `[[chains]]` entry; nothing is connected yet.
+    pub fn from_config(
+        config: &AppConfig,
+        active_chain: u64,
+        provider: Arc<RpcProvider>,
+    ) -> AppResult<Self> {
+        let mut pool = Self::new(active_chain, provider, config.eth_ws_url.clone());
+        pool.strategy = config.rpc_strategy;
+        for endpoints in &config.chains {
+            if endpoints.chain_id == active_chain {
+                return Err(AppError::Config(format!(
+                    "chain {active_chain} is the active chain; configure it with eth_rpc_url"
+                )));
+            }
+            if pool.slots.contains_key(&endpoints.chain_id) {
+                return Err(AppError::Config(format!(
+                    "chain {} is configured twice",
+                    endpoints.chain_id
+                )));
+            }
+            pool.slots
+                .insert(endpoints.chain_id, ChainSlot::new(endpoints.clone(), None));
+        }
+        Ok(pool)
+    }
+
+    pub fn active_chain(&self) -> u64 {
+        self.active_chain
+    }
+
+    /// Configured chain ids, ascending.
+    pub fn chain_ids(&self) -> Vec<u64> {
+        let mut chain_ids: Vec<u64> = self.slots.keys().copied().collect();
+        chain_ids.sort_unstable();
+        chain_ids
+    }
+
+    fn slot(&self, chain_id: u64) -> AppResult<&ChainSlot> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(AppError::Rpc("provider pool is shut down".into()));
+        }
+        self.slots.get(&chain_id).ok_or_else(|| {
+            let configured: Vec<String> = self
+                .chain_ids()
+                .iter()
+                .map(|chain_id| chain_id.to_string())
+                .collect();
+            AppError::InvalidInput(format!(
+                "no RPC endpoint configured for chain {chain_id}; configured chains: {}",
+                configured.join(", ")
+            ))
+        })
+    }
+
+    /// HTTP provider for `chain_id`, connected and checked against the chain id on first use.
+    pub async fn http(&self, chain_id: u64) -> AppResult<Arc<RpcProvider>> {
+        let slot = self.slot(chain_id)?;
+        // Held while connecting so concurrent callers share one provider.
+        let mut cached = slot.http.lock().await;
+        if let Some(provider) = cached.as_ref() {
+            return Ok(provider.clone());
+        }
+
+        let url = &slot.endpoints.rpc_url;
+        let provider = Arc::new(Provider::new(BalancedHttp::new(url, &[], self.strategy)?));
+        let actual = match provider.get_chainid().await {
+            Ok(actual) => actual.as_u64(),
+            Err(err) => {
+                let err = AppError::Rpc(format!(
+                    "failed to fetch chain id of chain {chain_id}: {err}"
+                ));
+                self.record_failure(chain_id, Transport::Http, &err);
+                return Err(err);
+            }
+        };
+        if actual != chain_id {
+            return Err(AppError::Config(format!(
+                "RPC endpoint for chain {chain_id} serves chain {actual}"
+            )));
+        }
+        info!("connected HTTP provider for chain {chain_id}");
+        *cached = Some(provider.clone());
+        Ok(provider)
+    }
+
+    /// WebSocket provider for `chain_id`, connected on first use and reconnected after a failure.
+    #[cfg(feature = "mempool")]
+    pub async fn ws(&self, chain_id: u64) -> AppResult<Arc<Provider<Ws>>> {
+        let slot = self.slot(chain_id)?;
+        let url = slot.endpoints.ws_url.as_deref().ok_or_else(|| {
+            AppError::Config(format!(
+                "no WebSocket endpoint configured for chain {chain_id}"
+            ))
+        })?;
+        let mut cached = slot.ws.lock().await;
+        if let Some(provider) = cached.as_ref() {
+            return Ok(provider.clone());
+        }
+
+        let provider = match Provider::<Ws>::connect(url).await {
+            Ok(provider) => Arc::new(provider),
+            Err(err) => {
+                let err = AppError::Rpc(format!("failed to connect websocket provider: {err}"));
+                self.record_failure(chain_id, Transport::Ws, &err);
+                return Err(err);
+            }
+        };
+        info!("connected WebSocket provider for chain {chain_id}");
+        *cached = Some(provider.clone());
+        Ok(provider)
+    }
+
+    pub fn record_success(&self, chain_id: u64) {
+        if let Some(slot) = self.slots.get(&chain_id) {
+            let mut health = slot.health.lock().expect("chain health lock poisoned");
+            health.successes += 1;
+            health.consecutive_failures = 0;
+        }
+    }
+
+    /// Count a failed call on `chain_id`. A failed WebSocket is dropped straight away, since a
+    /// broken subscription connection does not recover; an HTTP provider other than the
+    /// active chain's is dropped after `MAX_CONSECUTIVE_FAILURES` in a row.
+    pub fn record_failure(&self, chain_id: u64, transport: Transport, err: &AppError) {
+        let Some(slot) = self.slots.get(&chain_id) else {
+            return;
+        };
+        let consecutive = {
+            let mut health = slot.health.lock().expect("chain health lock poisoned");
+            health.failures += 1;
+            health.consecutive_failures += 1;
+            health.last_error = Some(redact(&err.to_string()).into_owned());
+            health.consecutive_failures
+        };
+
+        let evict = match transport {
+            Transport::Ws => true,
+            Transport::Http => {
+                chain_id != self.active_chain && consecutive >= MAX_CONSECUTIVE_FAILURES
+            }
+        };
+        if !evict {
+            return;
+        }
+        // A caller holding the lock is mid-connect; its result supersedes the one to drop.
+        match transport {
+            Transport::Http => {
+                if let Ok(mut cached) = slot.http.try_lock()
+                    && cached.take().is_some()
+                {
+                    warn!(
+                        "dropped HTTP provider for chain {chain_id} after {consecutive} failures"
+                    );
+                }
+            }
+            #[cfg(feature = "mempool")]
+            Transport::Ws => {
+                if let Ok(mut cached) = slot.ws.try_lock()
+                    && cached.take().is_some()
+                {
+                    warn!(
+                        "dropped WebSocket provider for chain {chain_id}; reconnecting on next use"
+                    );
+                }
+            }
+            #[cfg(not(feature = "mempool"))]
+            Transport::Ws => {}
+        }
+    }
+
+    /// Connection state and failure counts of every configured chain.
+    pub async fn status(&self) -> ProviderPoolOut {
+        let mut chains = Vec::with_capacity(self.slots.len());
+        for chain_id in self.chain_ids() {
+            let slot = &self.slots[&chain_id];
+            let http_connected = slot.http.lock().await.is_some();
+            #[cfg(feature = "mempool")]
+            let ws_connected = slot.ws.lock().await.is_some();
+            #[cfg(not(feature = "mempool"))]
+            let ws_connected = false;
+            let health = slot.health.lock().expect("chain health lock poisoned");
+            chains.push(ChainProviderOut {
+                chain_id,
+                active: chain_id == self.active_chain,
+                http_connected,
+                ws_configured: slot.endpoints.ws_url.is_some(),
+                ws_connected,
+                healthy: health.consecutive_failures < MAX_CONSECUTIVE_FAILURES,
+                successes: health.successes,
+                failures: health.failures,
+                consecutive_failures: health.consecutive_failures,
+                last_error: health.last_error.clone(),
+            });
+        }
+        ProviderPoolOut {
+            active_chain_id: self.active_chain,
+            shut_down: self.closed.load(Ordering::Acquire),
+            chains,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Drop every cached provider, closing WebSocket connections; later lookups fail.
+    pub async fn shutdown(&self) {
+        self.closed.store(true, Ordering::Release);
+        for slot in self.slots.values() {
+            slot.http.lock().await.take();
+            #[cfg(feature = "mempool")]
+            slot.ws.lock().await.take();
+        }
+        info!("provider pool shut down");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline_provider() -> Arc<RpcProvider> {
+        Arc::new(Provider::new(
+            BalancedHttp::new("http://node.invalid", &[], BalanceStrategy::RoundRobin).unwrap(),
+        ))
+    }
+
+    fn pool() -> ProviderPool {
+        let mut pool = ProviderPool::new(1, offline_provider(), None);
+        pool.slots.insert(
+            8453,
+            ChainSlot::new(
+                ChainEndpoints {
+                    chain_id: 8453,
+                    rpc_url: "http://base.invalid".into(),
+                    ws_url: None,
+                },
+                Some(offline_provider()),
+            ),
+        );
+        pool
+    }
+
+    #[test]
+    fn pairs_build_chain_endpoints() {
+        let rpc = HashMap::from([
+            ("8453".to_string(), "https://base.invalid".to_string()),
+            ("10".to_string(), "https://op.invalid".to_string()),
+        ]);
+        let ws = HashMap::from([("10".to_string(), "wss://op.invalid".to_string())]);
+        let chains = ChainEndpoints::from_pairs(rpc.clone(), ws).unwrap();
+        assert_eq!(chains[0].chain_id, 10);
+        assert_eq!(chains[0].ws_url.as_deref(), Some("wss://op.invalid"));
+        assert_eq!(chains[1].ws_url, None);
+
+        let orphan = HashMap::from([("137".to_string(), "wss://polygon.invalid".to_string())]);
+        assert!(ChainEndpoints::from_pairs(rpc, orphan).is_err());
+        let bad = HashMap::from([("base".to_string(), "https://base.invalid".to_string())]);
+        assert!(ChainEndpoints::from_pairs(bad, HashMap::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn failing_chain_is_evicted_but_active_chain_stays() {
+        let pool = pool();
+        let err = AppError::Rpc("connection refused".into());
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            pool.record_failure(1, Transport::Http, &err);
+            pool.record_failure(8453, Transport::Http, &err);
+        }
+
+        let status = pool.status().await;
+        let (active, base) = (&status.chains[0], &status.chains[1]);
+        assert!(active.active && active.http_connected && !active.healthy);
+        assert!(!base.http_connected);
+        assert_eq!(base.consecutive_failures, MAX_CONSECUTIVE_FAILURES);
+        assert_eq!(
+            base.last_error.as_deref(),
+            Some("rpc error: connection refused")
+        );
+
+        pool.record_success(8453);
+        assert!(pool.status().await.chains[1].healthy);
+
+        let unknown = pool.http(137).await.unwrap_err().to_string();
+        assert!(unknown.contains("configured chains: 1, 8453"), "{unknown}");
+    }
+
+    #[tokio::test]
+    async fn shutdown_drops_providers() {
+        let pool = pool();
+        assert!(pool.http(1).await.is_ok());
+        pool.shutdown().await;
+        let status = pool.status().await;
+        assert!(status.shut_down);
+        assert!(status.chains.iter().all(|chain| !chain.http_connected));
+        assert!(pool.http(1).await.is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use ethers::{
+    core::rand::{RngCore, thread_rng},
+    types::{Address, U256},
+    utils::keccak256,
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    secrets::constant_time_eq,
+    types::SwapTokensParams,
+};
+
+/// Seconds a `quote_id` stays valid when `QUOTE_TTL_SECS` is unset.
+pub const DEFAULT_QUOTE_TTL_SECS: u64 = 60;
+const DOMAIN: &[u8] = b"walletmcp-quote-v1";
+
+/// Everything a swap quote commits to besides its minimum output, with the recipient and price
+/// limit as resolved for the calldata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteTerms {
+    pub chain_id: u64,
+    pub wallet: Address,
+    pub from_token: Address,
+    pub to_token: Address,
+    pub amount_in_wei: String,
+    pub fee: u32,
+    pub slippage_bps: u32,
+    pub recipient: Option<String>,
+    pub sqrt_price_limit: Option<String>,
+}
+
+impl QuoteTerms {
+    /// Terms of `params` after `authorize_swap` resolved its recipient and price limit.
+    pub fn new(
+        chain_id: u64,
+        wallet: Address,
+        from_token: Address,
+        to_token: Address,
+        params: &SwapTokensParams,
+    ) -> Self {
+        Self {
+            chain_id,
+            wallet,
+            from_token,
+            to_token,
+            amount_in_wei: canonical_uint(&params.amount_in_wei),
+            fee: params.fee,
+            slippage_bps: params.slippage_bps,
+            recipient: params.recipient.as_deref().map(str::to_ascii_lowercase),
+            sqrt_price_limit: params.sqrt_price_limit.as_deref().map(canonical_uint),
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "{}|{:#x}|{:#x}|{:#x}|{}|{}|{}|{}|{}",
+            self.chain_id,
+            self.wallet,
+            self.from_token,
+            self.to_token,
+            self.amount_in_wei,
+            self.fee,
+            self.slippage_bps,
+            self.recipient.as_deref().unwrap_or_default(),
+            self.sqrt_price_limit.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// `"0100"` and `"100"` are the same amount.
+fn canonical_uint(raw: &str) -> String {
+    U256::from_dec_str(raw.trim())
+        .map(|value| value.to_string())
+        .unwrap_or_else(|_| raw.trim().to_string())
+}
+
+/// Issues and checks `quote_id`s: opaque tokens binding a swap simulation's terms and minimum
+/// output until they expire.
+///
+/// An id is the expiry and minimum output, authenticated with keccak256 over a key drawn at
+/// startup. Only this process can issue or check ids, and they do not survive a restart.
+pub struct QuoteSigner {
+    key: [u8; 32],
+    ttl_secs: u64,
+}
+
+impl QuoteSigner {
+    pub fn new(ttl_secs: u64) -> Self {
+        let mut key = [0u8; 32];
+        thread_rng().fill_bytes(&mut key);
+        Self { key, ttl_secs }
+    }
+
+    /// A `quote_id` for `terms` with `amount_out_min` (base units), and its expiry.
+    pub fn issue(&self, terms: &QuoteTerms, amount_out_min: &str, now: u64) -> (String, u64) {
+        let expires_at = now + self.ttl_secs;
+        let payload = format!("{expires_at}|{amount_out_min}");
+        let mac = self.mac(terms, &payload);
+        (
+            format!("{}.{}", hex::encode(payload), hex::encode(mac)),
+            expires_at,
+        )
+    }
+
+    /// The minimum output `quote_id` promised, if it was issued here for exactly `terms` and
+    /// has not expired.
+    pub fn check(&self, quote_id: &str, terms: &QuoteTerms, now: u64) -> AppResult<U256> {
+        let malformed = || AppError::InvalidInput("quote_id is malformed".into());
+        let (payload, mac) = quote_id.trim().split_once('.').ok_or_else(malformed)?;
+        let payload = hex::decode(payload)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(malformed)?;
+        let mac = hex::decode(mac).map_err(|_| malformed())?;
+        let (expires_at, amount_out_min) = payload.split_once('|').ok_or_else(malformed)?;
+        let expires_at = expires_at.parse::<u64>().map_err(|_| malformed())?;
+        let amount_out_min = U256::from_dec_str(amount_out_min).map_err(|_| malformed())?;
+
+        if !constant_time_eq(&mac, &self.mac(terms, &payload)) {
+            return Err(AppError::InvalidInput(
+                "quote_id does not match these swap parameters (or was issued by another server run); request a new quote"
+                    .into(),
+            ));
+        }
+        if now > expires_at {
+            return Err(AppError::InvalidInput(format!(
+                "quote_id expired {}s ago; request a new quote",
+                now - expires_at
+            )));
+        }
+        Ok(amount_out_min)
+    }
+
+    fn mac(&self, terms: &QuoteTerms, payload: &str) -> [u8; 32] {
+        let encoded = terms.encode();
+        let mut message =
+            Vec::with_capacity(self.key.len() + DOMAIN.len() + encoded.len() + payload.len() + 1);
+        message.extend_from_slice(&self.key);
+        message.extend_from_slice(DOMAIN);
+        message.extend_from_slice(encoded.as_bytes());
+        message.push(b'|');
+        message.extend_from_slice(payload.as_bytes());
+        keccak256(message)
+    }
+}
+
+/// Fail when a fresh `amount_out_min` is below the one the quote showed, both in base units.
+pub fn ensure_min_out(quoted: U256, fresh: &str) -> AppResult<()> {
+    let fresh = U256::from_dec_str(fresh)
+        .map_err(|err| AppError::Internal(format!("invalid amount {fresh}: {err}")))?;
+    if fresh < quoted {
+        return Err(AppError::Swap(format!(
+            "price moved since the quote: minimum output is now {fresh} base units, quoted {quoted}; request a new quote"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms() -> QuoteTerms {
+        QuoteTerms {
+            chain_id: 1,
+            wallet: Address::repeat_byte(1),
+            from_token: Address::repeat_byte(2),
+            to_token: Address::repeat_byte(3),
+            amount_in_wei: canonical_uint("01000"),
+            fee: 3_000,
+            slippage_bps: 100,
+            recipient: None,
+            sqrt_price_limit: None,
+        }
+    }
+
+    #[test]
+    fn quote_id_binds_terms_and_expires() {
+        let signer = QuoteSigner::new(60);
+        let (id, expires_at) = signer.issue(&terms(), "1500", 1_000);
+        assert_eq!(expires_at, 1_060);
+        assert_eq!(
+            signer.check(&id, &terms(), 1_060).unwrap(),
+            U256::from(1_500)
+        );
+
+        let expired = signer.check(&id, &terms(), 1_061).unwrap_err();
+        assert!(expired.to_string().contains("expired 1s ago"));
+        let other = QuoteTerms {
+            slippage_bps: 500,
+            ..terms()
+        };
+        assert!(
+            signer
+                .check(&id, &other, 1_000)
+                .unwrap_err()
+                .to_string()
+                .contains("does not match")
+        );
+        // Another run's key, or a payload edited to promise more.
+        assert!(QuoteSigner::new(60).check(&id, &terms(), 1_000).is_err());
+        let (_, mac) = id.split_once('.').unwrap();
+        let forged = format!("{}.{mac}", hex::encode("1060|2500"));
+        assert!(signer.check(&forged, &terms(), 1_000).is_err());
+        assert!(signer.check("nonsense", &terms(), 1_000).is_err());
+    }
+
+    #[test]
+    fn fresh_minimum_must_cover_the_quote() {
+        let quoted = U256::from(1_500);
+        assert!(ensure_min_out(quoted, "1500").is_ok());
+        assert!(ensure_min_out(quoted, "1600").is_ok());
+        assert!(matches!(
+            ensure_min_out(quoted, "1499"),
+            Err(AppError::Swap(_))
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use ethers::{providers::Middleware, types::Address};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        native,
+        price::{self, TokenInfo, TokenRegistry},
+        uniswap::pool_address,
+    },
+    types::{QuoteCurrenciesOut, QuoteCurrency, QuoteCurrencyOut},
+};
+
+/// Uniswap V3 fee tiers checked for token quotes and arbitrage.
+pub(crate) const FEE_TIERS: [u32; 4] = [100, 500, 3_000, 10_000];
+
+/// Quotes `base` can be priced in on the active chain: USD and the native coin through
+/// `get_token_price`, other fiats through `get_native_price`, and each of `tokens` through
+/// `get_quote_ladder`. Routes come from configured feeds and from pools deployed on chain;
+/// nothing is quoted, so a listed route can still fail on thin liquidity.
+pub async fn quote_currencies<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    chain_id: u64,
+    base: &TokenInfo,
+    tokens: &[&TokenInfo],
+) -> AppResult<QuoteCurrenciesOut>
+where
+    M: Middleware + 'static,
+{
+    let mut quotes = Vec::with_capacity(2 + tokens.len());
+    for quote in [QuoteCurrency::USD, QuoteCurrency::ETH] {
+        quotes.push(price_quote(provider.clone(), registry, base, quote).await?);
+    }
+    quotes.extend(fiat_quotes(registry, chain_id, base));
+    for token in tokens {
+        quotes.push(swap_quote(provider.clone(), registry, base, token).await?);
+    }
+    Ok(QuoteCurrenciesOut {
+        base: base.symbol.clone(),
+        chain_id,
+        quotes,
+        warnings: Vec::new(),
+    })
+}
+
+/// Routes `get_token_price` has for `base` in `quote`: the Chainlink feed route, then a pool
+/// against each quote token, V3 at `base`'s default fee or else a V2 venue's pair.
+async fn price_quote<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base: &TokenInfo,
+    quote: QuoteCurrency,
+) -> AppResult<QuoteCurrencyOut>
+where
+    M: Middleware + 'static,
+{
+    let mut routes: Vec<String> = price::chainlink_route(registry, base, quote)
+        .map(String::from)
+        .into_iter()
+        .collect();
+    let quote_tokens: Vec<&TokenInfo> = registry
+        .quote_tokens(quote)
+        .into_iter()
+        .filter(|token| token.address != base.address)
+        .collect();
+    for quote_token in &quote_tokens {
+        let fee = base.default_fee;
+        if v3_pool_exists(
+            provider.clone(),
+            registry,
+            base.address,
+            quote_token.address,
+            fee,
+        )
+        .await?
+        {
+            routes.push(format!(
+                "uniswap_v3 (fee {fee}, via {})",
+                quote_token.symbol
+            ));
+            continue;
+        }
+        for venue in registry.v2_venues() {
+            if venue
+                .has_pair(provider.clone(), base.address, quote_token.address)
+                .await?
+            {
+                routes.push(format!("{}_v2 (via {})", venue.name, quote_token.symbol));
+                break;
+            }
+        }
+    }
+
+    let reason = routes.is_empty().then(|| {
+        let symbols: Vec<&str> = quote_tokens
+            .iter()
+            .map(|token| token.symbol.as_str())
+            .collect();
+        if symbols.is_empty() {
+            format!(
+                "no Chainlink feed route and no quote token other than {}",
+                base.symbol
+            )
+        } else {
+            format!(
+                "no Chainlink feed route and no pool against {}",
+                symbols.join(", ")
+            )
+        }
+    });
+    Ok(QuoteCurrencyOut {
+        quote: registry.quote_symbol(quote),
+        tool: "get_token_price".into(),
+        available: reason.is_none(),
+        routes,
+        reason,
+    })
+}
+
+/// Fiats other than USD, which only `get_native_price` quotes, and only for the native coin.
+fn fiat_quotes(registry: &TokenRegistry, chain_id: u64, base: &TokenInfo) -> Vec<QuoteCurrencyOut> {
+    let native = registry.native();
+    native::fiat_currencies()
+        .into_iter()
+        .map(|fiat| {
+            let reason = if native.wrapped != Some(base.address) {
+                Some(format!(
+                    "only the native coin ({}) is priced in {fiat}",
+                    native.symbol
+                ))
+            } else if native.usd_feed.is_none() {
+                Some(format!("no {}/USD feed on chain {chain_id}", native.symbol))
+            } else if native::fiat_usd_feed(chain_id, fiat).is_none() {
+                Some(format!("no {fiat}/USD feed on chain {chain_id}"))
+            } else {
+                None
+            };
+            QuoteCurrencyOut {
+                quote: fiat.to_string(),
+                tool: "get_native_price".into(),
+                available: reason.is_none(),
+                routes: match reason {
+                    None => vec!["chainlink (via USD)".into()],
+                    Some(_) => Vec::new(),
+                },
+                reason,
+            }
+        })
+        .collect()
+}
+
+/// Uniswap V3 pools between `base` and `token` at the standard fee tiers.
+async fn swap_quote<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    base: &TokenInfo,
+    token: &TokenInfo,
+) -> AppResult<QuoteCurrencyOut>
+where
+    M: Middleware + 'static,
+{
+    let mut routes = Vec::new();
+    if token.address != base.address {
+        for fee in FEE_TIERS {
+            if v3_pool_exists(provider.clone(), registry, base.address, token.address, fee).await? {
+                routes.push(format!("uniswap_v3 (fee {fee})"));
+            }
+        }
+    }
+
+    let reason = if token.address == base.address {
+        Some(format!("{} is the base token", token.symbol))
+    } else if routes.is_empty() {
+        let tiers: Vec<String> = FEE_TIERS.iter().map(u32::to_string).collect();
+        Some(format!(
+            "no Uniswap V3 {}/{} pool at fee tiers {}",
+            base.symbol,
+            token.symbol,
+            tiers.join(", ")
+        ))
+    } else {
+        None
+    };
+    Ok(QuoteCurrencyOut {
+        quote: token.symbol.clone(),
+        tool: "get_quote_ladder".into(),
+        available: reason.is_none(),
+        routes,
+        reason,
+    })
+}
+
+async fn v3_pool_exists<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    a: Address,
+    b: Address,
+    fee: u32,
+) -> AppResult<bool>
+where
+    M: Middleware + 'static,
+{
+    let pool = pool_address(registry.uniswap_factory(), a, b, fee);
+    let code = provider
+        .get_code(pool, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch pool {pool:#x} code: {err}")))?;
+    Ok(!code.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{providers::Provider, types::Bytes};
+
+    fn quote<'a>(out: &'a QuoteCurrenciesOut, name: &str) -> &'a QuoteCurrencyOut {
+        out.quotes.iter().find(|quote| quote.quote == name).unwrap()
+    }
+
+    #[tokio::test]
+    async fn reports_feed_and_pool_routes() {
+        let (provider, mock) = Provider::mocked();
+        let registry = TokenRegistry::with_defaults();
+        let weth = registry.info_by_symbol("WETH").unwrap();
+        let wbtc = registry.info_by_symbol("WBTC").unwrap();
+        let deployed = Bytes::from(vec![0x60, 0x80]);
+        // Consumed in reverse: the WETH/USDC pool, then WETH/WBTC at each fee tier.
+        for code in [
+            Bytes::new(),
+            deployed.clone(),
+            deployed.clone(),
+            Bytes::new(),
+        ] {
+            mock.push::<Bytes, _>(code).unwrap();
+        }
+        mock.push::<Bytes, _>(deployed).unwrap();
+
+        let out = quote_currencies(Arc::new(provider), &registry, 1, weth, &[wbtc, weth])
+            .await
+            .unwrap();
+
+        let usd = quote(&out, "USD");
+        assert!(usd.available);
+        assert_eq!(usd.routes, ["chainlink", "uniswap_v3 (fee 3000, via USDC)"]);
+        // WETH/USD over ETH/USD, which reads as 1.
+        assert_eq!(quote(&out, "ETH").routes, ["chainlink (via USD)"]);
+        assert!(quote(&out, "EUR").available);
+        assert_eq!(quote(&out, "JPY").tool, "get_native_price");
+
+        let btc = quote(&out, "WBTC");
+        assert_eq!(
+            btc.routes,
+            ["uniswap_v3 (fee 500)", "uniswap_v3 (fee 3000)"]
+        );
+        assert_eq!(btc.tool, "get_quote_ladder");
+        assert_eq!(
+            quote(&out, "WETH").reason.as_deref(),
+            Some("WETH is the base token")
+        );
+    }
+
+    #[tokio::test]
+    async fn explains_missing_routes() {
+        let (provider, mock) = Provider::mocked();
+        let mut registry = TokenRegistry::with_defaults();
+        let pepe = Address::from_low_u64_be(0xbeef);
+        registry.add_token(TokenInfo::new("PEPE", pepe, 18));
+        mock.push::<Bytes, _>(Bytes::new()).unwrap(); // PEPE/WETH
+        mock.push::<Bytes, _>(Bytes::new()).unwrap(); // PEPE/USDC
+
+        let base = registry.info_by_address(pepe).unwrap();
+        let out = quote_currencies(Arc::new(provider), &registry, 1, base, &[])
+            .await
+            .unwrap();
+
+        assert!(out.quotes.iter().all(|quote| !quote.available));
+        assert_eq!(
+            quote(&out, "USD").reason.as_deref(),
+            Some("no Chainlink feed route and no pool against USDC")
+        );
+        assert_eq!(
+            quote(&out, "EUR").reason.as_deref(),
+            Some("only the native coin (ETH) is priced in EUR")
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance, erc20,
+        plan::ROUTER_OVERHEAD_GAS,
+        price::{self, TokenInfo, TokenRegistry},
+        swap::{self, SwapSettings, apply_gas_buffer, apply_slippage},
+        uniswap::{UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams},
+    },
+    types::{QuoteCurrency, RebalanceHoldingOut, RebalancePlanOut, RebalanceSwapOut},
+};
+
+pub const MAX_REBALANCE_TARGETS: usize = 10;
+const FULL_WEIGHT_BPS: u32 = 10_000;
+
+/// Plan the swaps that move `owner`'s holdings of the target tokens to their weights.
+///
+/// Holdings are valued in USD through the registry's price policy. Nothing is planned while
+/// every holding is within `tolerance_bps` of its target; otherwise the largest surplus is
+/// repeatedly sold into the largest deficit, which needs at most `targets - 1` swaps. Each leg
+/// is quoted on the `fee` pool.
+#[allow(clippy::too_many_arguments)]
+pub async fn plan_rebalance<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    owner: Address,
+    targets: &[(Address, u32)],
+    tolerance_bps: u32,
+    slippage_bps: u32,
+    fee: u32,
+    settings: SwapSettings,
+) -> AppResult<RebalancePlanOut>
+where
+    M: Middleware + 'static,
+{
+    validate_targets(targets, tolerance_bps, slippage_bps)?;
+
+    let mut holdings = Vec::with_capacity(targets.len());
+    for (token, target_bps) in targets {
+        let info = registry
+            .info_by_address(*token)
+            .cloned()
+            .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {token:#x}")))?;
+        let raw = erc20::fetch_balance_of(provider.clone(), *token, owner).await?;
+        let amount =
+            Decimal::from_str_exact(&balance::format_with_decimals(&raw, info.decimals as u32))
+                .map_err(|err| {
+                    AppError::Internal(format!("invalid {} balance: {err}", info.symbol))
+                })?;
+        let price =
+            price::resolve_token_price(provider.clone(), registry, *token, QuoteCurrency::USD)
+                .await?;
+        let price = Decimal::from_str_exact(&price.price)
+            .map_err(|err| AppError::Price(format!("invalid {} price: {err}", info.symbol)))?;
+        holdings.push(Holding {
+            info,
+            raw,
+            amount,
+            price,
+            value: amount * price,
+            target_bps: *target_bps,
+        });
+    }
+
+    let total: Decimal = holdings.iter().map(|holding| holding.value).sum();
+    if total <= Decimal::ZERO {
+        return Err(AppError::InvalidInput(format!(
+            "{owner:#x} holds none of the target tokens"
+        )));
+    }
+    let bps = Decimal::from(FULL_WEIGHT_BPS);
+    let weights: Vec<Decimal> = holdings
+        .iter()
+        .map(|holding| holding.value / total * bps)
+        .collect();
+    let within_tolerance = holdings.iter().zip(&weights).all(|(holding, weight)| {
+        (*weight - Decimal::from(holding.target_bps)).abs() <= Decimal::from(tolerance_bps)
+    });
+
+    let mut swaps = Vec::new();
+    let mut total_gas_estimate = U256::zero();
+    if !within_tolerance {
+        let surpluses: Vec<Decimal> = holdings
+            .iter()
+            .map(|holding| holding.value - total * Decimal::from(holding.target_bps) / bps)
+            .collect();
+        let quoter = UniswapQuoterV2::new(settings.uniswap.quoter, provider.clone());
+        for (seller, buyer, value) in match_legs(&surpluses) {
+            let (from, to) = (&holdings[seller], &holdings[buyer]);
+            let amount_in =
+                balance::parse_with_decimals(value / from.price, from.info.decimals as u32)?
+                    .min(from.raw);
+            if amount_in.is_zero() {
+                continue;
+            }
+            let (amount_out, _, _, quoter_gas) = quoter
+                .quote_exact_input_single(QuoteExactInputSingleParams {
+                    token_in: from.info.address,
+                    token_out: to.info.address,
+                    amount_in,
+                    fee,
+                    sqrt_price_limit_x96: U256::zero(),
+                })
+                .call()
+                .await
+                .map_err(|err| {
+                    AppError::Swap(format!(
+                        "uniswap quoter call failed for {} -> {}: {err}",
+                        from.info.symbol, to.info.symbol
+                    ))
+                })?;
+            if amount_out.is_zero() {
+                return Err(AppError::Swap(format!(
+                    "quote returned zero output amount for {} -> {}",
+                    from.info.symbol, to.info.symbol
+                )));
+            }
+            let gas_estimate = quoter_gas + U256::from(ROUTER_OVERHEAD_GAS);
+            total_gas_estimate += gas_estimate;
+            swaps.push(RebalanceSwapOut {
+                from_token: from.info.symbol.clone(),
+                to_token: to.info.symbol.clone(),
+                amount_in_wei: amount_in.to_string(),
+                amount_in: balance::format_with_decimals(&amount_in, from.info.decimals as u32),
+                value_usd: usd(value),
+                amount_out_estimate: balance::format_with_decimals(
+                    &amount_out,
+                    to.info.decimals as u32,
+                ),
+                amount_out_min: balance::format_with_decimals(
+                    &apply_slippage(amount_out, slippage_bps)?,
+                    to.info.decimals as u32,
+                ),
+                gas_estimate: gas_estimate.to_string(),
+            });
+        }
+    }
+    let fees = if swaps.is_empty() {
+        None
+    } else {
+        let total_gas_limit = apply_gas_buffer(total_gas_estimate, settings.gas_buffer_percent);
+        swap::fee_preview(&provider, total_gas_estimate, total_gas_limit).await
+    };
+
+    Ok(RebalancePlanOut {
+        block_number: None,
+        wallet: format!("{owner:#x}"),
+        total_value_usd: usd(total),
+        tolerance_bps,
+        within_tolerance,
+        holdings: holdings
+            .iter()
+            .zip(weights)
+            .map(|(holding, weight)| RebalanceHoldingOut {
+                token: format!("{:#x}", holding.info.address),
+                symbol: holding.info.symbol.clone(),
+                balance: holding.amount.normalize().to_string(),
+                price_usd: holding.price.normalize().to_string(),
+                value_usd: usd(holding.value),
+                weight_bps: weight.round().to_u32().unwrap_or_default(),
+                target_bps: holding.target_bps,
+                drift_bps: (weight - Decimal::from(holding.target_bps))
+                    .round()
+                    .to_i64()
+                    .unwrap_or_default(),
+            })
+            .collect(),
+        swaps,
+        total_gas_estimate: total_gas_estimate.to_string(),
+        fees,
+        warnings: Vec::new(),
+    })
+}
+
+struct Holding {
+    info: TokenInfo,
+    raw: U256,
+    amount: Decimal,
+    price: Decimal,
+    value: Decimal,
+    target_bps: u32,
+}
+
+fn validate_targets(
+    targets: &[(Address, u32)],
+    tolerance_bps: u32,
+    slippage_bps: u32,
+) -> AppResult<()> {
+    if targets.len() < 2 || targets.len() > MAX_REBALANCE_TARGETS {
+        return Err(AppError::InvalidInput(format!(
+            "rebalancing needs between 2 and {MAX_REBALANCE_TARGETS} targets"
+        )));
+    }
+    for (index, (token, _)) in targets.iter().enumerate() {
+        if targets[..index].iter().any(|(seen, _)| seen == token) {
+            return Err(AppError::InvalidInput(format!(
+                "{token:#x} appears in targets more than once"
+            )));
+        }
+    }
+    let total: u32 = targets.iter().map(|(_, weight)| *weight).sum();
+    if total != FULL_WEIGHT_BPS {
+        return Err(AppError::InvalidInput(format!(
+            "target weights must add up to {FULL_WEIGHT_BPS} bps, got {total}"
+        )));
+    }
+    if tolerance_bps > FULL_WEIGHT_BPS || slippage_bps > FULL_WEIGHT_BPS {
+        return Err(AppError::InvalidInput(format!(
+            "tolerance_bps and slippage_bps cannot exceed {FULL_WEIGHT_BPS}"
+        )));
+    }
+    Ok(())
+}
+
+/// Pair the largest surplus with the largest deficit until both sides are settled.
+///
+/// `surpluses` are USD values over (positive) or under (negative) target and sum to zero; each
+/// leg is `(seller, buyer, usd)`. Every leg settles at least one side, so there are fewer legs
+/// than entries.
+fn match_legs(surpluses: &[Decimal]) -> Vec<(usize, usize, Decimal)> {
+    let mut remaining = surpluses.to_vec();
+    let mut legs = Vec::new();
+    loop {
+        let largest = |sign: Decimal| {
+            remaining
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value * sign > Decimal::ZERO)
+                .max_by_key(|(_, value)| **value * sign)
+                .map(|(index, _)| index)
+        };
+        let (Some(seller), Some(buyer)) = (largest(Decimal::ONE), largest(Decimal::NEGATIVE_ONE))
+        else {
+            return legs;
+        };
+        let value = remaining[seller].min(-remaining[buyer]);
+        remaining[seller] -= value;
+        remaining[buyer] += value;
+        legs.push((seller, buyer, value));
+    }
+}
+
+pub(crate) fn usd(value: Decimal) -> String {
+    value.round_dp(2).normalize().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legs_settle_largest_imbalances_first() {
+        // 60/30/10 portfolio worth 1000 rebalanced to 40/40/20.
+        let surpluses = [Decimal::from(200), Decimal::from(-100), Decimal::from(-100)];
+        let legs = match_legs(&surpluses);
+
+        assert_eq!(legs.len(), 2);
+        assert!(
+            legs.iter()
+                .all(|(seller, _, value)| { *seller == 0 && *value == Decimal::from(100) })
+        );
+        assert!(match_legs(&[Decimal::ZERO, Decimal::ZERO]).is_empty());
+    }
+
+    #[test]
+    fn targets_must_cover_the_whole_portfolio() {
+        let weth = Address::from_low_u64_be(1);
+        let usdc = Address::from_low_u64_be(2);
+
+        assert!(validate_targets(&[(weth, 5_000), (usdc, 5_000)], 100, 100).is_ok());
+        assert!(validate_targets(&[(weth, 5_000), (usdc, 4_000)], 100, 100).is_err());
+        assert!(validate_targets(&[(weth, 5_000), (weth, 5_000)], 100, 100).is_err());
+        assert!(validate_targets(&[(weth, 10_000)], 100, 100).is_err());
+    }
+}
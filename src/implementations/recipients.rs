@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use ethers::types::Address;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::validation,
+};
+
+/// Named swap recipients from config (`treasury` -> address) plus the default recipient.
+///
+/// Aliases are operator-defined, so they count as pre-approved destinations: with
+/// `restrict_recipients_to_aliases` only the signer and alias addresses may receive output.
+#[derive(Debug, Clone, Default)]
+pub struct RecipientBook {
+    aliases: HashMap<String, Address>,
+    default: Option<Address>,
+    restrict: bool,
+}
+
+impl RecipientBook {
+    pub fn from_config(config: &AppConfig) -> AppResult<Self> {
+        let aliases = config
+            .recipient_aliases
+            .iter()
+            .map(|(name, address)| {
+                let address = address.trim().parse::<Address>().map_err(|_| {
+                    AppError::Config(format!(
+                        "recipient alias {name} has an invalid address: {address}"
+                    ))
+                })?;
+                Ok((name.trim().to_ascii_lowercase(), address))
+            })
+            .collect::<AppResult<HashMap<_, _>>>()?;
+
+        let mut book = Self {
+            aliases,
+            default: None,
+            restrict: config.restrict_recipients_to_aliases,
+        };
+        book.default = config
+            .default_recipient
+            .as_deref()
+            .map(|value| {
+                book.lookup(value).ok_or_else(|| {
+                    AppError::Config(format!(
+                        "default_recipient must be an address or a recipient alias, got {value}"
+                    ))
+                })
+            })
+            .transpose()?;
+        Ok(book)
+    }
+
+    pub fn with_alias(mut self, name: &str, address: Address) -> Self {
+        self.aliases.insert(name.to_ascii_lowercase(), address);
+        self
+    }
+
+    pub fn with_restriction(mut self, restrict: bool) -> Self {
+        self.restrict = restrict;
+        self
+    }
+
+    /// Resolve a requested recipient (alias or address), falling back to the configured default
+    /// and then to `signer`, and enforce the alias-only policy.
+    pub fn resolve(&self, requested: Option<&str>, signer: Address) -> AppResult<Address> {
+        let recipient = match requested {
+            Some(value) => self.lookup(value).ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "recipient must be an address or a configured alias, got {value}{}",
+                    validation::explain(value.trim(), self.aliases.keys().map(String::as_str))
+                ))
+            })?,
+            None => self.default.unwrap_or(signer),
+        };
+
+        if self.restrict && recipient != signer && !self.is_alias(recipient) {
+            return Err(AppError::Compliance(format!(
+                "recipient {recipient:#x} is not a configured alias; only aliases are allowed"
+            )));
+        }
+        Ok(recipient)
+    }
+
+    /// Whether `address` is the target of a configured alias.
+    pub fn is_alias(&self, address: Address) -> bool {
+        self.aliases.values().any(|alias| *alias == address)
+    }
+
+    fn lookup(&self, value: &str) -> Option<Address> {
+        let value = value.trim();
+        value
+            .parse::<Address>()
+            .ok()
+            .or_else(|| self.aliases.get(&value.to_ascii_lowercase()).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_aliases_case_insensitively() {
+        let treasury = Address::from_low_u64_be(0x7e);
+        let signer = Address::from_low_u64_be(0x51);
+        let book = RecipientBook::default().with_alias("treasury", treasury);
+
+        assert_eq!(book.resolve(Some("Treasury"), signer).unwrap(), treasury);
+        assert_eq!(book.resolve(None, signer).unwrap(), signer);
+        assert!(book.resolve(Some("unknown"), signer).is_err());
+    }
+
+    #[test]
+    fn restriction_allows_only_aliases_and_signer() {
+        let treasury = Address::from_low_u64_be(0x7e);
+        let signer = Address::from_low_u64_be(0x51);
+        let book = RecipientBook::default()
+            .with_alias("treasury", treasury)
+            .with_restriction(true);
+
+        assert!(
+            book.resolve(Some(&format!("{treasury:#x}")), signer)
+                .is_ok()
+        );
+        assert!(book.resolve(None, signer).is_ok());
+        assert!(matches!(
+            book.resolve(Some(&format!("{:#x}", Address::from_low_u64_be(9))), signer),
+            Err(AppError::Compliance(_))
+        ));
+    }
+}
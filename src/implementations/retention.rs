@@ -0,0 +1,204 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use ethers::types::Address;
+
+use crate::{
+    config::AppConfig,
+    types::{MaintenanceItemOut, MaintenanceKind},
+};
+
+/// Entries each bounded cache keeps before evicting the least recently used.
+pub const DEFAULT_CACHE_MAX_ENTRIES: usize = 10_000;
+/// Rows each log keeps before dropping the oldest.
+pub const DEFAULT_LOG_MAX_ROWS: usize = 10_000;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// How much of a log to keep: at most `max_rows` rows, none older than `max_age_secs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_rows: usize,
+    pub max_age_secs: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_rows: DEFAULT_LOG_MAX_ROWS,
+            max_age_secs: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            max_rows: config.log_max_rows.max(1),
+            max_age_secs: config
+                .log_retention_days
+                .map(|days| days.saturating_mul(SECS_PER_DAY)),
+        }
+    }
+
+    /// Drop rows (oldest first) past the row limit or older than the age limit at `now`;
+    /// returns how many went.
+    pub fn prune<T>(&self, rows: &mut VecDeque<T>, at: impl Fn(&T) -> u64, now: u64) -> usize {
+        let before = rows.len();
+        let excess = rows.len().saturating_sub(self.max_rows);
+        rows.drain(..excess);
+        if let Some(max_age) = self.max_age_secs {
+            let cutoff = now.saturating_sub(max_age);
+            while rows.front().is_some_and(|row| at(row) < cutoff) {
+                rows.pop_front();
+            }
+        }
+        before - rows.len()
+    }
+}
+
+impl MaintenanceItemOut {
+    pub fn cache(store: &str, entries: usize, max_entries: usize, removed: usize) -> Self {
+        Self {
+            store: store.to_string(),
+            kind: MaintenanceKind::Cache,
+            entries,
+            max_entries,
+            max_age_days: None,
+            removed,
+        }
+    }
+
+    pub fn log(store: &str, entries: usize, policy: &RetentionPolicy, removed: usize) -> Self {
+        Self {
+            store: store.to_string(),
+            kind: MaintenanceKind::Log,
+            entries,
+            max_entries: policy.max_rows,
+            max_age_days: policy.max_age_secs.map(|secs| secs / SECS_PER_DAY),
+            removed,
+        }
+    }
+}
+
+/// Recency of the tokens the registry learned at runtime, so the least recently used can be
+/// evicted once there are more than `capacity`. Seeded and configured tokens are never tracked,
+/// so they are never evicted.
+#[derive(Debug)]
+pub struct TokenCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    tick: u64,
+    last_used: HashMap<Address, u64>,
+}
+
+impl Default for TokenCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_MAX_ENTRIES)
+    }
+}
+
+impl TokenCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(config.cache_max_entries)
+    }
+
+    /// Mark a tracked token as just used; untracked ones are ignored.
+    pub fn touch(&self, token: Address) {
+        let mut state = self.state.lock().expect("token cache lock poisoned");
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some(last_used) = state.last_used.get_mut(&token) {
+            *last_used = tick;
+        }
+    }
+
+    /// Track a newly learned token; returns the tokens to evict to stay within capacity.
+    pub fn insert(&self, token: Address) -> Vec<Address> {
+        let mut state = self.state.lock().expect("token cache lock poisoned");
+        state.tick += 1;
+        let tick = state.tick;
+        state.last_used.insert(token, tick);
+        evict_lru(&mut state.last_used, self.capacity)
+    }
+
+    pub fn status(&self, removed: usize) -> MaintenanceItemOut {
+        let state = self.state.lock().expect("token cache lock poisoned");
+        MaintenanceItemOut::cache(
+            "token_metadata",
+            state.last_used.len(),
+            self.capacity,
+            removed,
+        )
+    }
+}
+
+/// Remove the least recently used keys until at most `capacity` remain; returns them.
+pub fn evict_lru<K: Copy + Eq + std::hash::Hash>(
+    last_used: &mut HashMap<K, u64>,
+    capacity: usize,
+) -> Vec<K> {
+    let excess = last_used.len().saturating_sub(capacity);
+    if excess == 0 {
+        return Vec::new();
+    }
+    let mut by_age: Vec<(u64, K)> = last_used.iter().map(|(key, used)| (*used, *key)).collect();
+    by_age.sort_unstable_by_key(|(used, _)| *used);
+    by_age
+        .into_iter()
+        .take(excess)
+        .map(|(_, key)| {
+            last_used.remove(&key);
+            key
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_applies_row_and_age_limits() {
+        let policy = RetentionPolicy {
+            max_rows: 3,
+            max_age_secs: Some(100),
+        };
+        let mut rows: VecDeque<u64> = (1..=5).map(|n| n * 50).collect();
+        // Rows 50 and 100 exceed the row limit; 150 is older than 300 - 100.
+        assert_eq!(policy.prune(&mut rows, |at| *at, 300), 3);
+        assert_eq!(rows, [200, 250]);
+        assert_eq!(
+            RetentionPolicy::default().prune(&mut rows, |at| *at, 1_000_000),
+            0
+        );
+    }
+
+    #[test]
+    fn token_cache_evicts_the_least_recently_used() {
+        let cache = TokenCache::new(2);
+        let (a, b, c) = (
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            Address::from_low_u64_be(3),
+        );
+        assert!(cache.insert(a).is_empty());
+        assert!(cache.insert(b).is_empty());
+        cache.touch(a);
+        assert_eq!(cache.insert(c), [b]);
+        assert_eq!(cache.status(1).entries, 2);
+    }
+}
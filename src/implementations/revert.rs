@@ -0,0 +1,212 @@
+use ethers::{
+    abi::{self, ParamType, Token},
+    providers::MiddlewareError,
+    utils::id,
+};
+
+use crate::error::AppError;
+
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Custom errors worth naming: OpenZeppelin ERC-20 (ERC-6093), Pausable/Ownable, and the
+/// Uniswap Universal Router / Permit2.
+const CUSTOM_ERRORS: [(&str, &[ParamType]); 16] = [
+    (
+        "ERC20InsufficientBalance(address,uint256,uint256)",
+        &[
+            ParamType::Address,
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+        ],
+    ),
+    (
+        "ERC20InsufficientAllowance(address,uint256,uint256)",
+        &[
+            ParamType::Address,
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+        ],
+    ),
+    ("ERC20InvalidSender(address)", &[ParamType::Address]),
+    ("ERC20InvalidReceiver(address)", &[ParamType::Address]),
+    ("ERC20InvalidApprover(address)", &[ParamType::Address]),
+    ("ERC20InvalidSpender(address)", &[ParamType::Address]),
+    ("EnforcedPause()", &[]),
+    ("OwnableUnauthorizedAccount(address)", &[ParamType::Address]),
+    ("V3TooLittleReceived()", &[]),
+    ("V3TooMuchRequested()", &[]),
+    ("V3InvalidSwap()", &[]),
+    ("TransactionDeadlinePassed()", &[]),
+    ("InsufficientToken()", &[]),
+    ("InsufficientETH()", &[]),
+    ("AllowanceExpired(uint256)", &[ParamType::Uint(256)]),
+    ("InsufficientAllowance(uint256)", &[ParamType::Uint(256)]),
+];
+
+/// Terse Uniswap V3 `require` messages and what they mean.
+const UNISWAP_MESSAGES: [(&str, &str); 7] = [
+    (
+        "STF",
+        "token transferFrom failed: check balance and allowance",
+    ),
+    ("TF", "token transfer failed"),
+    (
+        "SPL",
+        "sqrt price limit is on the wrong side of the current price",
+    ),
+    ("AS", "amount specified is zero"),
+    ("LOK", "pool is locked"),
+    ("Too little received", "output below amount_out_min"),
+    ("Transaction too old", "deadline passed"),
+];
+
+/// Human-readable reason for revert `data`: `Error(string)`, `Panic(uint256)`, a known custom
+/// error, or the bare selector of an unknown one.
+pub fn decode_revert(data: &[u8]) -> String {
+    if data.len() < 4 {
+        return "execution reverted without a reason".into();
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_STRING_SELECTOR {
+        if let Ok(tokens) = abi::decode(&[ParamType::String], args)
+            && let Some(Token::String(message)) = tokens.into_iter().next()
+        {
+            return match UNISWAP_MESSAGES.iter().find(|(short, _)| *short == message) {
+                Some((_, meaning)) => format!("reverted: {message} ({meaning})"),
+                None => format!("reverted: {message}"),
+            };
+        }
+    } else if selector == PANIC_SELECTOR {
+        if let Ok(tokens) = abi::decode(&[ParamType::Uint(256)], args)
+            && let Some(Token::Uint(code)) = tokens.into_iter().next()
+        {
+            let code = code.low_u64();
+            return format!("panic {code:#04x} ({})", panic_meaning(code));
+        }
+    } else if let Some((signature, params)) = CUSTOM_ERRORS
+        .iter()
+        .find(|(signature, _)| id(signature) == selector)
+    {
+        let name = &signature[..signature.find('(').unwrap_or(signature.len())];
+        return match abi::decode(params, args) {
+            Ok(tokens) => {
+                let args: Vec<String> = tokens.iter().map(format_token).collect();
+                format!("reverted: {name}({})", args.join(", "))
+            }
+            Err(_) => format!("reverted: {name}"),
+        };
+    }
+    format!("reverted with custom error 0x{}", hex::encode(selector))
+}
+
+/// Wrap `error` with the decoded revert reason when `source` is a revert carrying data.
+pub fn annotate<E: MiddlewareError>(error: AppError, source: &E) -> AppError {
+    match source
+        .as_error_response()
+        .and_then(|response| response.as_revert_data())
+    {
+        Some(data) => AppError::Reverted {
+            error: Box::new(error),
+            revert_reason: decode_revert(&data),
+            revert_data: format!("0x{}", hex::encode(&data)),
+        },
+        None => error,
+    }
+}
+
+fn panic_meaning(code: u64) -> &'static str {
+    match code {
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "corrupt storage byte array",
+        0x31 => "pop on an empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to an uninitialized function",
+        _ => "unknown panic code",
+    }
+}
+
+fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => format!("{address:#x}"),
+        Token::Uint(value) => value.to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::{JsonRpcError, Middleware, MockResponse, Provider, ProviderError},
+        types::{Address, TransactionRequest, U256, transaction::eip2718::TypedTransaction},
+    };
+    use serde_json::json;
+
+    fn revert_data(selector: [u8; 4], args: &[Token]) -> Vec<u8> {
+        [selector.to_vec(), abi::encode(args)].concat()
+    }
+
+    #[test]
+    fn decodes_standard_and_custom_errors() {
+        let stf = revert_data(ERROR_STRING_SELECTOR, &[Token::String("STF".into())]);
+        assert_eq!(
+            decode_revert(&stf),
+            "reverted: STF (token transferFrom failed: check balance and allowance)"
+        );
+
+        let panic = revert_data(PANIC_SELECTOR, &[Token::Uint(U256::from(0x11))]);
+        assert_eq!(
+            decode_revert(&panic),
+            "panic 0x11 (arithmetic overflow or underflow)"
+        );
+
+        let selector = id("ERC20InsufficientBalance(address,uint256,uint256)");
+        let balance = revert_data(
+            selector,
+            &[
+                Token::Address(Address::from_low_u64_be(0xa)),
+                Token::Uint(U256::from(5)),
+                Token::Uint(U256::from(10)),
+            ],
+        );
+        assert_eq!(
+            decode_revert(&balance),
+            "reverted: ERC20InsufficientBalance(0x000000000000000000000000000000000000000a, 5, 10)"
+        );
+
+        assert_eq!(
+            decode_revert(&[0xde, 0xad, 0xbe, 0xef]),
+            "reverted with custom error 0xdeadbeef"
+        );
+        assert_eq!(decode_revert(&[]), "execution reverted without a reason");
+    }
+
+    #[tokio::test]
+    async fn annotates_reverting_estimates() {
+        let (provider, mock) = Provider::mocked();
+        let data = revert_data(ERROR_STRING_SELECTOR, &[Token::String("TF".into())]);
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: 3,
+            message: "execution reverted: TF".into(),
+            data: Some(json!(format!("0x{}", hex::encode(&data)))),
+        }));
+        let tx: TypedTransaction = TransactionRequest::new().into();
+        let err = provider.estimate_gas(&tx, None).await.unwrap_err();
+
+        let payload = annotate(AppError::Swap("gas estimation failed".into()), &err).to_json_rpc();
+        assert_eq!(payload.code, -32020);
+        assert_eq!(
+            payload.data["revert_reason"],
+            "reverted: TF (token transfer failed)"
+        );
+
+        let unreachable = ProviderError::CustomError("connection refused".into());
+        let plain = annotate(AppError::Swap("x".into()), &unreachable);
+        assert!(matches!(plain, AppError::Swap(_)));
+    }
+}
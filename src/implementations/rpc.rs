@@ -0,0 +1,544 @@
+use std::{
+    fmt,
+    future::Future,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, Provider};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::fixture::RpcFixture,
+    redact::redact,
+};
+
+/// Provider type shared by the service layer; reads are spread across every configured endpoint.
+pub type RpcProvider = Provider<BalancedHttp>;
+
+/// Consecutive transport failures before an endpoint is benched.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// How long a benched endpoint sits out before it is tried again.
+const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+/// Weight of the newest sample in the latency moving average (1/N).
+const LATENCY_EWMA_DIVISOR: u64 = 5;
+/// Name of the `eth_rpc_url` endpoint.
+pub const PRIMARY_ENDPOINT: &str = "primary";
+
+tokio::task_local! {
+    /// Endpoint the current request was pinned to with `_meta.rpcEndpoint`.
+    static PINNED_ENDPOINT: String;
+}
+
+/// Run `call` with its reads sent only to the endpoint named `name`, without failover, on every
+/// provider that has an endpoint of that name; providers without one route as usual.
+pub async fn with_pinned_endpoint<F: Future>(name: String, call: F) -> F::Output {
+    PINNED_ENDPOINT.scope(name, call).await
+}
+
+/// Methods that depend on one node's view of account state or server-side filters.
+/// Routing them anywhere but the primary risks nonce gaps or "filter not found" errors.
+const PINNED_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "eth_getTransactionCount",
+    "eth_sign",
+    "eth_signTransaction",
+    "eth_signTypedData_v4",
+    "eth_newFilter",
+    "eth_newBlockFilter",
+    "eth_newPendingTransactionFilter",
+    "eth_getFilterChanges",
+    "eth_getFilterLogs",
+    "eth_uninstallFilter",
+];
+
+/// How read traffic is spread across healthy endpoints.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceStrategy {
+    #[default]
+    RoundRobin,
+    /// Round robin where each endpoint receives `weight` consecutive slots.
+    Weighted,
+    /// Lowest moving-average latency divided by weight; unmeasured endpoints go first.
+    Latency,
+}
+
+impl FromStr for BalanceStrategy {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "round_robin" | "round-robin" => Ok(BalanceStrategy::RoundRobin),
+            "weighted" => Ok(BalanceStrategy::Weighted),
+            "latency" => Ok(BalanceStrategy::Latency),
+            other => Err(AppError::Config(format!(
+                "unknown RPC balance strategy: {other}"
+            ))),
+        }
+    }
+}
+
+/// Additional read endpoint from configuration.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct RpcEndpointConfig {
+    /// What `_meta.rpcEndpoint` calls it; defaults to `endpoint<N>`, counting extras from 1.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub url: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl FromStr for RpcEndpointConfig {
+    type Err = AppError;
+
+    /// Parses `url`, `url|weight`, `name=url` or `name=url|weight`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // A URL's scheme puts a `:` before any `=` in it, so a bare URL never looks named.
+        let (name, value) = match value.split_once('=') {
+            Some((name, url)) if is_endpoint_name(name.trim()) => {
+                (Some(name.trim().to_string()), url)
+            }
+            _ => (None, value),
+        };
+        let (url, weight) = match value.rsplit_once('|') {
+            Some((url, weight)) => {
+                let weight = weight.trim().parse::<u32>().map_err(|_| {
+                    AppError::Config(format!("invalid RPC endpoint weight: {weight}"))
+                })?;
+                (url, weight)
+            }
+            None => (value, default_weight()),
+        };
+        Ok(Self {
+            name,
+            url: url.trim().to_string(),
+            weight,
+        })
+    }
+}
+
+fn is_endpoint_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Rolling health data for one endpoint.
+#[derive(Debug, Default)]
+struct EndpointHealth {
+    /// Exponential moving average of request latency in microseconds; 0 means unmeasured.
+    latency_micros: AtomicU64,
+    consecutive_failures: AtomicU32,
+    /// Offset from `BalancedHttp::started` (ms) until which the endpoint is benched.
+    benched_until_ms: AtomicU64,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now_ms: u64) -> bool {
+        self.benched_until_ms.load(Ordering::Relaxed) <= now_ms
+    }
+
+    fn record_success(&self, latency: Duration) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        let sample = latency.as_micros().min(u64::MAX as u128) as u64;
+        let previous = self.latency_micros.load(Ordering::Relaxed);
+        let updated = if previous == 0 {
+            sample.max(1)
+        } else {
+            (previous * (LATENCY_EWMA_DIVISOR - 1) + sample) / LATENCY_EWMA_DIVISOR
+        };
+        self.latency_micros.store(updated.max(1), Ordering::Relaxed);
+    }
+
+    fn record_failure(&self, now_ms: u64) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= MAX_CONSECUTIVE_FAILURES {
+            self.benched_until_ms.store(
+                now_ms + UNHEALTHY_COOLDOWN.as_millis() as u64,
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+struct Endpoint {
+    name: String,
+    client: Http,
+    weight: u32,
+    health: EndpointHealth,
+}
+
+/// `JsonRpcClient` that load-balances reads across several HTTP endpoints, fails over on
+/// transport errors, and pins nonce/filter-sensitive calls to the primary (first) endpoint.
+#[derive(Clone)]
+pub struct BalancedHttp {
+    endpoints: Arc<Vec<Endpoint>>,
+    strategy: BalanceStrategy,
+    cursor: Arc<AtomicUsize>,
+    started: Instant,
+    /// Records every exchange, or answers from a recording instead of the endpoints.
+    fixture: Option<Arc<RpcFixture>>,
+}
+
+impl fmt::Debug for BalancedHttp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BalancedHttp")
+            .field("endpoints", &self.endpoints.len())
+            .field("strategy", &self.strategy)
+            .field(
+                "fixture",
+                &self.fixture.as_ref().map(|fixture| fixture.is_replay()),
+            )
+            .finish()
+    }
+}
+
+impl BalancedHttp {
+    /// The primary URL always comes first and receives every pinned method.
+    pub fn new(
+        primary: &str,
+        extra: &[RpcEndpointConfig],
+        strategy: BalanceStrategy,
+    ) -> AppResult<Self> {
+        let primary = RpcEndpointConfig {
+            name: Some(PRIMARY_ENDPOINT.to_string()),
+            url: primary.to_string(),
+            weight: default_weight(),
+        };
+        let endpoints = std::iter::once(&primary)
+            .chain(extra)
+            .enumerate()
+            .map(|(index, config)| {
+                let client = Http::from_str(&config.url).map_err(|err| {
+                    AppError::Config(format!("invalid RPC url {}: {err}", redact(&config.url)))
+                })?;
+                let name = match &config.name {
+                    Some(name) if !is_endpoint_name(name) => {
+                        return Err(AppError::Config(format!(
+                            "RPC endpoint name {name} may only use letters, digits, _ and -"
+                        )));
+                    }
+                    Some(name) => name.clone(),
+                    None => format!("endpoint{index}"),
+                };
+                Ok(Endpoint {
+                    name,
+                    client,
+                    weight: config.weight.max(1),
+                    health: EndpointHealth::default(),
+                })
+            })
+            .collect::<AppResult<Vec<Endpoint>>>()?;
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            if endpoints[..index]
+                .iter()
+                .any(|other| other.name == endpoint.name)
+            {
+                return Err(AppError::Config(format!(
+                    "RPC endpoint name {} is used twice",
+                    endpoint.name
+                )));
+            }
+        }
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            strategy,
+            cursor: Arc::new(AtomicUsize::new(0)),
+            started: Instant::now(),
+            fixture: None,
+        })
+    }
+
+    pub fn from_config(config: &AppConfig) -> AppResult<Self> {
+        let balanced = Self::new(
+            &config.eth_rpc_url,
+            &config.rpc_endpoints,
+            config.rpc_strategy,
+        )?;
+        Ok(balanced.with_fixture(RpcFixture::from_config(config)?))
+    }
+
+    pub fn with_fixture(mut self, fixture: Option<RpcFixture>) -> Self {
+        self.fixture = fixture.map(Arc::new);
+        self
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// Endpoint names, primary first.
+    pub fn endpoint_names(&self) -> Vec<&str> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.name.as_str())
+            .collect()
+    }
+
+    fn endpoint_index(&self, name: &str) -> Option<usize> {
+        self.endpoints
+            .iter()
+            .position(|endpoint| endpoint.name == name)
+    }
+
+    /// Endpoint indices to try in order: the strategy's pick first, then the rest as failover.
+    /// A request pinned to one of these endpoints gets just that one, so a slow or failing
+    /// endpoint shows up as such instead of being masked by failover.
+    fn route(&self, method: &str) -> Vec<usize> {
+        if PINNED_METHODS.contains(&method) {
+            return vec![0];
+        }
+        if let Ok(Some(index)) = PINNED_ENDPOINT.try_with(|name| self.endpoint_index(name)) {
+            return vec![index];
+        }
+
+        let now_ms = self.now_ms();
+        let healthy: Vec<usize> = (0..self.endpoints.len())
+            .filter(|&index| self.endpoints[index].health.is_healthy(now_ms))
+            .collect();
+        // With everything benched, trying something beats failing outright.
+        let candidates = if healthy.is_empty() {
+            (0..self.endpoints.len()).collect()
+        } else {
+            healthy
+        };
+
+        let first = self.pick(&candidates);
+        let benched = (0..self.endpoints.len()).filter(|index| !candidates.contains(index));
+        std::iter::once(first)
+            .chain(candidates.iter().copied().filter(|&index| index != first))
+            .chain(benched)
+            .collect()
+    }
+
+    fn pick(&self, candidates: &[usize]) -> usize {
+        match self.strategy {
+            BalanceStrategy::RoundRobin => {
+                let tick = self.cursor.fetch_add(1, Ordering::Relaxed);
+                candidates[tick % candidates.len()]
+            }
+            BalanceStrategy::Weighted => {
+                let total: usize = candidates
+                    .iter()
+                    .map(|&index| self.endpoints[index].weight as usize)
+                    .sum();
+                let mut slot = self.cursor.fetch_add(1, Ordering::Relaxed) % total;
+                for &index in candidates {
+                    let weight = self.endpoints[index].weight as usize;
+                    if slot < weight {
+                        return index;
+                    }
+                    slot -= weight;
+                }
+                candidates[0]
+            }
+            BalanceStrategy::Latency => *candidates
+                .iter()
+                .min_by_key(|&&index| {
+                    let endpoint = &self.endpoints[index];
+                    endpoint.health.latency_micros.load(Ordering::Relaxed) / endpoint.weight as u64
+                })
+                .expect("route always has at least one candidate"),
+        }
+    }
+
+    /// Send `method` to the routed endpoints, failing over on transport errors.
+    async fn forward<R>(&self, method: &str, params: Value) -> Result<R, HttpClientError>
+    where
+        R: DeserializeOwned + Send,
+    {
+        let mut last_err = None;
+        for index in self.route(method) {
+            let endpoint = &self.endpoints[index];
+            let started = Instant::now();
+            match endpoint
+                .client
+                .request::<Value, R>(method, params.clone())
+                .await
+            {
+                Ok(response) => {
+                    endpoint.health.record_success(started.elapsed());
+                    return Ok(response);
+                }
+                // Only transport failures move on; a node answering with an error is healthy
+                // and another node would answer the same way.
+                Err(err @ HttpClientError::ReqwestError(_)) => {
+                    endpoint.health.record_failure(self.now_ms());
+                    warn!(
+                        "rpc endpoint {index} failed for {method}: {}",
+                        redact(&err.to_string())
+                    );
+                    last_err = Some(err);
+                }
+                Err(err) => {
+                    endpoint.health.record_success(started.elapsed());
+                    return Err(err);
+                }
+            }
+        }
+
+        Err(last_err.expect("route always yields at least one endpoint"))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for BalancedHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Serialise once so the same params can be replayed against a failover endpoint.
+        let params = serde_json::to_value(&params).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: format!("{params:?}"),
+        })?;
+
+        let Some(fixture) = &self.fixture else {
+            return self.forward(method, params).await;
+        };
+        let response = if fixture.is_replay() {
+            fixture.serve(method, &params)?
+        } else {
+            let outcome = self.forward::<Value>(method, params.clone()).await;
+            match &outcome {
+                Ok(response) => fixture.record_exchange(method, &params, Ok(response)),
+                Err(HttpClientError::JsonRpcError(err)) => {
+                    fixture.record_exchange(method, &params, Err(err))
+                }
+                Err(_) => {}
+            }
+            outcome?
+        };
+        serde_json::from_value(response.clone()).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: response.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(weights: &[u32], strategy: BalanceStrategy) -> BalancedHttp {
+        let extra: Vec<_> = weights[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| RpcEndpointConfig {
+                name: None,
+                url: format!("http://node{}.invalid", i + 1),
+                weight: *weight,
+            })
+            .collect();
+        BalancedHttp::new("http://node0.invalid", &extra, strategy).unwrap()
+    }
+
+    #[test]
+    fn pinned_methods_stay_on_primary() {
+        let balanced = pool(&[1, 1, 1], BalanceStrategy::RoundRobin);
+        for _ in 0..4 {
+            assert_eq!(balanced.route("eth_getTransactionCount"), vec![0]);
+            assert_eq!(balanced.route("eth_sendRawTransaction"), vec![0]);
+        }
+    }
+
+    #[test]
+    fn round_robin_rotates_and_keeps_failover_order() {
+        let balanced = pool(&[1, 1, 1], BalanceStrategy::RoundRobin);
+        let firsts: Vec<_> = (0..6).map(|_| balanced.route("eth_call")[0]).collect();
+        assert_eq!(firsts, vec![0, 1, 2, 0, 1, 2]);
+        assert_eq!(balanced.route("eth_call").len(), 3);
+    }
+
+    #[test]
+    fn weighted_follows_weights() {
+        let balanced = pool(&[1, 3], BalanceStrategy::Weighted);
+        let firsts: Vec<_> = (0..8).map(|_| balanced.route("eth_call")[0]).collect();
+        assert_eq!(firsts.iter().filter(|&&i| i == 1).count(), 6);
+    }
+
+    #[test]
+    fn benched_endpoint_is_skipped_then_used_as_last_resort() {
+        let balanced = pool(&[1, 1], BalanceStrategy::Latency);
+        balanced.endpoints[0]
+            .health
+            .record_success(Duration::from_millis(5));
+        balanced.endpoints[1]
+            .health
+            .record_success(Duration::from_millis(50));
+        assert_eq!(balanced.route("eth_call"), vec![0, 1]);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            balanced.endpoints[0]
+                .health
+                .record_failure(balanced.now_ms());
+        }
+        assert_eq!(balanced.route("eth_call"), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn pinned_requests_use_only_the_named_endpoint() {
+        let balanced = pool(&[1, 1, 1], BalanceStrategy::RoundRobin);
+        assert_eq!(
+            balanced.endpoint_names(),
+            ["primary", "endpoint1", "endpoint2"]
+        );
+        with_pinned_endpoint("endpoint2".into(), async {
+            for _ in 0..3 {
+                assert_eq!(balanced.route("eth_call"), vec![2]);
+            }
+            // Nonce and filter reads stay on the primary regardless.
+            assert_eq!(balanced.route("eth_getTransactionCount"), vec![0]);
+        })
+        .await;
+        // A name this pool does not have leaves routing alone.
+        with_pinned_endpoint("archive".into(), async {
+            assert_eq!(balanced.route("eth_call").len(), 3);
+        })
+        .await;
+    }
+
+    #[test]
+    fn parses_endpoint_weights() {
+        let parsed: RpcEndpointConfig = "https://rpc.example.org|4".parse().unwrap();
+        assert_eq!(parsed.weight, 4);
+        let parsed: RpcEndpointConfig = "https://rpc.example.org/?key=abc".parse().unwrap();
+        assert_eq!(parsed.weight, 1);
+        assert_eq!(parsed.name, None);
+        assert_eq!(parsed.url, "https://rpc.example.org/?key=abc");
+        let parsed: RpcEndpointConfig = "archive=https://archive.example.org|2".parse().unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("archive"));
+        assert_eq!(parsed.url, "https://archive.example.org");
+        assert_eq!(parsed.weight, 2);
+        assert!(
+            "https://rpc.example.org|x"
+                .parse::<RpcEndpointConfig>()
+                .is_err()
+        );
+        assert_eq!(
+            "LATENCY".parse::<BalanceStrategy>().unwrap(),
+            BalanceStrategy::Latency
+        );
+    }
+}
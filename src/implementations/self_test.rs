@@ -0,0 +1,626 @@
+use std::{fmt::Write as _, sync::Arc, time::Instant};
+
+use ethers::{
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, RecoveryMessage, TransactionRequest, U256, transaction::eip2718::TypedTransaction,
+    },
+    utils::hash_message,
+};
+
+use crate::{
+    config::AppConfig,
+    implementations::{
+        native,
+        price::{self, TokenRegistry},
+        uniswap::{
+            UniswapDeployment, UniswapQuoterV2, uniswap_quoter_v2::QuoteExactInputSingleParams,
+        },
+    },
+    types::{QuoteCurrency, SelfTestCheckOut, SelfTestOut, SelfTestStatus},
+};
+
+/// Round trips slower than this are reported as a warning.
+pub const SLOW_RPC_MS: u64 = 1_500;
+/// Feeds not updated for this long are reported as a warning; the slowest heartbeats are 24h.
+const STALE_FEED_SECS: u64 = 26 * 3_600;
+/// Fee tiers tried for the quoter check, deepest first.
+const QUOTER_FEES: [u32; 2] = [500, 3_000];
+
+/// What the checks need from the running server.
+pub struct SelfTestTarget<'a, M> {
+    pub provider: Arc<M>,
+    pub config: &'a AppConfig,
+    /// Owned, so the wrapped native check can register the token the way startup does.
+    pub registry: TokenRegistry,
+    pub chain_id: u64,
+    pub signer: Option<LocalWallet>,
+    pub now: u64,
+}
+
+/// Run every check in order, including the steps startup refuses to serve without, so a
+/// deployment that would not start still gets a full report. Nothing is signed for broadcast or
+/// sent; the write path is only estimated and dry-run.
+pub async fn run<M>(mut target: SelfTestTarget<'_, M>) -> SelfTestOut
+where
+    M: Middleware + 'static,
+{
+    let mut checks = vec![rpc(&target).await, chain_id(&target).await];
+    let (deployment_check, deployment) = uniswap_deployment(&target).await;
+    checks.push(deployment_check);
+    checks.push(v2_venues(&target).await);
+    checks.push(wrapped_native(&mut target).await);
+    checks.push(feed_decimals(&target).await);
+    checks.push(wallet(&target));
+    checks.push(chainlink_feeds(&target).await);
+    checks.push(uniswap_quoter(&target, deployment).await);
+    checks.push(write_path(&target).await);
+    report(target.chain_id, checks)
+}
+
+/// Assemble checks into a report with its plain-text rendering.
+pub fn report(chain_id: u64, checks: Vec<SelfTestCheckOut>) -> SelfTestOut {
+    let passed = checks
+        .iter()
+        .all(|check| check.status != SelfTestStatus::Fail);
+    let count = |status| checks.iter().filter(|check| check.status == status).count();
+    let mut summary = format!("walletmcp self-test on chain {chain_id}\n");
+    for check in &checks {
+        let latency = check
+            .latency_ms
+            .map(|ms| format!(" ({ms} ms)"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            summary,
+            "{:<4}  {:<16} {}{latency}",
+            check.status.label(),
+            check.name,
+            check.detail
+        );
+    }
+    let _ = write!(
+        summary,
+        "{} checks: {} passed, {} warnings, {} failed, {} skipped",
+        checks.len(),
+        count(SelfTestStatus::Pass),
+        count(SelfTestStatus::Warn),
+        count(SelfTestStatus::Fail),
+        count(SelfTestStatus::Skip)
+    );
+    SelfTestOut {
+        chain_id,
+        passed,
+        checks,
+        summary,
+    }
+}
+
+impl SelfTestStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            SelfTestStatus::Pass => "PASS",
+            SelfTestStatus::Warn => "WARN",
+            SelfTestStatus::Fail => "FAIL",
+            SelfTestStatus::Skip => "SKIP",
+        }
+    }
+}
+
+fn check(
+    name: &str,
+    status: SelfTestStatus,
+    detail: impl Into<String>,
+    started: Option<Instant>,
+) -> SelfTestCheckOut {
+    SelfTestCheckOut {
+        name: name.to_string(),
+        status,
+        detail: detail.into(),
+        latency_ms: started.map(|started| started.elapsed().as_millis() as u64),
+    }
+}
+
+async fn rpc<M: Middleware + 'static>(target: &SelfTestTarget<'_, M>) -> SelfTestCheckOut {
+    let started = Instant::now();
+    match target.provider.get_block_number().await {
+        Ok(block) => {
+            let elapsed = started.elapsed().as_millis() as u64;
+            let status = if elapsed > SLOW_RPC_MS {
+                SelfTestStatus::Warn
+            } else {
+                SelfTestStatus::Pass
+            };
+            let slow = if status == SelfTestStatus::Warn {
+                format!("; slower than {SLOW_RPC_MS} ms")
+            } else {
+                String::new()
+            };
+            check(
+                "rpc",
+                status,
+                format!("latest block {block}{slow}"),
+                Some(started),
+            )
+        }
+        Err(err) => check(
+            "rpc",
+            SelfTestStatus::Fail,
+            format!("eth_blockNumber failed: {err}"),
+            Some(started),
+        ),
+    }
+}
+
+async fn chain_id<M: Middleware + 'static>(target: &SelfTestTarget<'_, M>) -> SelfTestCheckOut {
+    let started = Instant::now();
+    match target.provider.get_chainid().await {
+        Ok(actual) if actual.as_u64() == target.chain_id => check(
+            "chain_id",
+            SelfTestStatus::Pass,
+            format!("provider serves chain {actual}"),
+            Some(started),
+        ),
+        Ok(actual) => check(
+            "chain_id",
+            SelfTestStatus::Fail,
+            format!(
+                "provider serves chain {actual}, configured for {}",
+                target.chain_id
+            ),
+            Some(started),
+        ),
+        Err(err) => check(
+            "chain_id",
+            SelfTestStatus::Fail,
+            format!("eth_chainId failed: {err}"),
+            Some(started),
+        ),
+    }
+}
+
+/// The configured or built-in Uniswap V3 router and quoter exist and share a factory.
+async fn uniswap_deployment<M: Middleware + 'static>(
+    target: &SelfTestTarget<'_, M>,
+) -> (SelfTestCheckOut, Option<UniswapDeployment>) {
+    let started = Instant::now();
+    match UniswapDeployment::resolve(target.provider.clone(), target.chain_id, target.config).await
+    {
+        Ok(deployment) => (
+            check(
+                "uniswap_deployment",
+                SelfTestStatus::Pass,
+                format!(
+                    "router {:#x} and quoter {:#x} use factory {:#x}",
+                    deployment.router, deployment.quoter, deployment.factory
+                ),
+                Some(started),
+            ),
+            Some(deployment),
+        ),
+        Err(err) => (
+            check(
+                "uniswap_deployment",
+                SelfTestStatus::Fail,
+                err.to_string(),
+                Some(started),
+            ),
+            None,
+        ),
+    }
+}
+
+/// Every configured V2 venue has its router on this chain, created by the configured factory.
+async fn v2_venues<M: Middleware + 'static>(target: &SelfTestTarget<'_, M>) -> SelfTestCheckOut {
+    let venues = &target.config.v2_venues;
+    if venues.is_empty() {
+        return check(
+            "v2_venues",
+            SelfTestStatus::Skip,
+            "no V2 venues configured",
+            None,
+        );
+    }
+    let started = Instant::now();
+    let mut problems = Vec::new();
+    for venue in venues {
+        if let Err(err) = venue.verify(target.provider.clone()).await {
+            problems.push(err.to_string());
+        }
+    }
+    if problems.is_empty() {
+        let names: Vec<&str> = venues.iter().map(|venue| venue.name.as_str()).collect();
+        check(
+            "v2_venues",
+            SelfTestStatus::Pass,
+            format!("{} verified", names.join(", ")),
+            Some(started),
+        )
+    } else {
+        check(
+            "v2_venues",
+            SelfTestStatus::Fail,
+            problems.join("; "),
+            Some(started),
+        )
+    }
+}
+
+/// The wrapped native token is registered, reading its metadata when the defaults lack it.
+async fn wrapped_native<M: Middleware + 'static>(
+    target: &mut SelfTestTarget<'_, M>,
+) -> SelfTestCheckOut {
+    let Some(wrapped) = target.registry.native().wrapped else {
+        return check(
+            "wrapped_native",
+            SelfTestStatus::Skip,
+            "no wrapped native token known on this chain",
+            None,
+        );
+    };
+    let started = Instant::now();
+    match target
+        .registry
+        .ensure_token(target.provider.clone(), wrapped)
+        .await
+    {
+        Ok(()) => {
+            let symbol = target
+                .registry
+                .wrapped_native()
+                .map(|info| info.symbol.clone())
+                .unwrap_or_default();
+            check(
+                "wrapped_native",
+                SelfTestStatus::Pass,
+                format!("{symbol} at {wrapped:#x}"),
+                Some(started),
+            )
+        }
+        Err(err) => check(
+            "wrapped_native",
+            SelfTestStatus::Fail,
+            format!("{wrapped:#x}: {err}"),
+            Some(started),
+        ),
+    }
+}
+
+/// The decimals of every configured and fiat/USD feed can be read and cached.
+async fn feed_decimals<M: Middleware + 'static>(
+    target: &SelfTestTarget<'_, M>,
+) -> SelfTestCheckOut {
+    let feeds: Vec<Address> = target
+        .registry
+        .chainlink_feeds()
+        .into_iter()
+        .chain(native::fiat_usd_feeds(target.chain_id))
+        .collect();
+    if feeds.is_empty() {
+        return check(
+            "feed_decimals",
+            SelfTestStatus::Skip,
+            "no Chainlink feeds configured on this chain",
+            None,
+        );
+    }
+    let started = Instant::now();
+    let failed = target
+        .registry
+        .feed_decimals()
+        .prewarm(target.provider.clone(), feeds.iter().copied())
+        .await;
+    let mut detail = format!(
+        "decimals of {}/{} feeds cached",
+        feeds.len() - failed.len(),
+        feeds.len()
+    );
+    for feed in &failed {
+        let _ = write!(detail, "; {feed:#x} unreadable");
+    }
+    let status = if failed.is_empty() {
+        SelfTestStatus::Pass
+    } else if failed.len() == feeds.len() {
+        SelfTestStatus::Fail
+    } else {
+        SelfTestStatus::Warn
+    };
+    check("feed_decimals", status, detail, Some(started))
+}
+
+/// The key parses, and a signature made with it recovers to the derived address.
+fn wallet<M>(target: &SelfTestTarget<'_, M>) -> SelfTestCheckOut {
+    let Some(signer) = &target.signer else {
+        return check(
+            "wallet",
+            SelfTestStatus::Skip,
+            "no signer configured; swap simulation and signing tools are unavailable",
+            None,
+        );
+    };
+    let message = b"walletmcp self-test";
+    let recovered = signer
+        .sign_hash(hash_message(message))
+        .map_err(|err| err.to_string())
+        .and_then(|signature| {
+            signature
+                .recover(RecoveryMessage::Data(message.to_vec()))
+                .map_err(|err| err.to_string())
+        });
+    match recovered {
+        Ok(address) if address == signer.address() => check(
+            "wallet",
+            SelfTestStatus::Pass,
+            format!("{address:#x}, verified by signing and recovering"),
+            None,
+        ),
+        Ok(address) => check(
+            "wallet",
+            SelfTestStatus::Fail,
+            format!(
+                "signature recovers to {address:#x}, not the derived {:#x}",
+                signer.address()
+            ),
+            None,
+        ),
+        Err(err) => check(
+            "wallet",
+            SelfTestStatus::Fail,
+            format!("signing failed: {err}"),
+            None,
+        ),
+    }
+}
+
+/// Every configured feed returns a complete, positive, recent round.
+async fn chainlink_feeds<M: Middleware + 'static>(
+    target: &SelfTestTarget<'_, M>,
+) -> SelfTestCheckOut {
+    let feeds = target.registry.chainlink_feeds();
+    if feeds.is_empty() {
+        return check(
+            "chainlink_feeds",
+            SelfTestStatus::Skip,
+            "no Chainlink feeds configured on this chain",
+            None,
+        );
+    }
+    let started = Instant::now();
+    let mut problems = Vec::new();
+    let mut stale = Vec::new();
+    for feed in &feeds {
+        match price::read_chainlink_round(
+            target.provider.clone(),
+            target.registry.feed_decimals(),
+            *feed,
+        )
+        .await
+        {
+            Ok(round) if round.answer.is_sign_negative() || round.answer.is_zero() => {
+                problems.push(format!("{feed:#x} answered {}", round.answer));
+            }
+            Ok(round) => {
+                let age = target
+                    .now
+                    .saturating_sub(round.updated_at.min(U256::from(u64::MAX)).as_u64());
+                if age > STALE_FEED_SECS {
+                    stale.push(format!("{feed:#x} last updated {}h ago", age / 3_600));
+                }
+            }
+            Err(err) => problems.push(format!("{feed:#x}: {err}")),
+        }
+    }
+    let healthy = feeds.len() - problems.len() - stale.len();
+    let mut detail = format!("{healthy}/{} feeds healthy", feeds.len());
+    for problem in problems.iter().chain(&stale) {
+        let _ = write!(detail, "; {problem}");
+    }
+    let status = if problems.len() == feeds.len() {
+        SelfTestStatus::Fail
+    } else if problems.is_empty() && stale.is_empty() {
+        SelfTestStatus::Pass
+    } else {
+        SelfTestStatus::Warn
+    };
+    check("chainlink_feeds", status, detail, Some(started))
+}
+
+/// QuoterV2 answers a small wrapped-native to USD-stablecoin quote.
+async fn uniswap_quoter<M: Middleware + 'static>(
+    target: &SelfTestTarget<'_, M>,
+    deployment: Option<UniswapDeployment>,
+) -> SelfTestCheckOut {
+    let Some(UniswapDeployment { quoter, .. }) = deployment else {
+        return check(
+            "uniswap_quoter",
+            SelfTestStatus::Skip,
+            "no verified Uniswap V3 deployment to quote against",
+            None,
+        );
+    };
+    let (Some(base), Some(quote)) = (
+        target.registry.wrapped_native(),
+        target.registry.quote_token(QuoteCurrency::USD),
+    ) else {
+        return check(
+            "uniswap_quoter",
+            SelfTestStatus::Skip,
+            "no wrapped native token or USD stablecoin registered to quote",
+            None,
+        );
+    };
+    let started = Instant::now();
+    let contract = UniswapQuoterV2::new(quoter, target.provider.clone());
+    // A thousandth of a whole token: small enough to quote in any live pool.
+    let amount_in = U256::exp10(usize::from(base.decimals)) / 1_000;
+    let mut last_error = String::new();
+    for fee in QUOTER_FEES {
+        let params = QuoteExactInputSingleParams {
+            token_in: base.address,
+            token_out: quote.address,
+            amount_in,
+            fee,
+            sqrt_price_limit_x96: U256::zero(),
+        };
+        match contract.quote_exact_input_single(params).call().await {
+            Ok((amount_out, ..)) if !amount_out.is_zero() => {
+                return check(
+                    "uniswap_quoter",
+                    SelfTestStatus::Pass,
+                    format!(
+                        "{quoter:#x} quoted {} {} -> {} {} raw at fee {fee}",
+                        amount_in, base.symbol, amount_out, quote.symbol
+                    ),
+                    Some(started),
+                );
+            }
+            Ok(_) => last_error = format!("zero output at fee {fee}"),
+            Err(err) => last_error = err.to_string(),
+        }
+    }
+    check(
+        "uniswap_quoter",
+        SelfTestStatus::Fail,
+        format!(
+            "{quoter:#x} could not quote {} -> {}: {last_error}",
+            base.symbol, quote.symbol
+        ),
+        Some(started),
+    )
+}
+
+/// Gas estimation and a dry run of a zero-value transfer to self, the cheapest call that goes
+/// through the same path as every simulated write.
+async fn write_path<M: Middleware + 'static>(target: &SelfTestTarget<'_, M>) -> SelfTestCheckOut {
+    let from = target
+        .signer
+        .as_ref()
+        .map(|signer| signer.address())
+        .unwrap_or_else(Address::zero);
+    let tx: TypedTransaction = TransactionRequest::new()
+        .from(from)
+        .to(from)
+        .value(U256::zero())
+        .into();
+    let started = Instant::now();
+    let estimate = match target.provider.estimate_gas(&tx, None).await {
+        Ok(estimate) => estimate,
+        Err(err) => {
+            return check(
+                "write_path",
+                SelfTestStatus::Fail,
+                format!("eth_estimateGas failed: {err}"),
+                Some(started),
+            );
+        }
+    };
+    if let Err(err) = target.provider.call(&tx, None).await {
+        return check(
+            "write_path",
+            SelfTestStatus::Fail,
+            format!("eth_call failed: {err}"),
+            Some(started),
+        );
+    }
+    let sender = if target.signer.is_some() {
+        "the wallet"
+    } else {
+        "the zero address (no signer)"
+    };
+    check(
+        "write_path",
+        SelfTestStatus::Pass,
+        format!("zero-value transfer from {sender} estimates {estimate} gas and dry-runs"),
+        Some(started),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    #[test]
+    fn report_fails_only_on_failed_checks() {
+        let checks = vec![
+            check("rpc", SelfTestStatus::Pass, "latest block 1", None),
+            check("wallet", SelfTestStatus::Skip, "no signer configured", None),
+            check(
+                "chainlink_feeds",
+                SelfTestStatus::Warn,
+                "1/2 feeds healthy",
+                None,
+            ),
+        ];
+        let report = report(1, checks);
+        assert!(report.passed);
+        assert!(
+            report
+                .summary
+                .contains("WARN  chainlink_feeds  1/2 feeds healthy")
+        );
+        assert!(
+            report
+                .summary
+                .ends_with("3 checks: 1 passed, 1 warnings, 0 failed, 1 skipped")
+        );
+
+        let failed = super::report(
+            1,
+            vec![check("rpc", SelfTestStatus::Fail, "unreachable", None)],
+        );
+        assert!(!failed.passed);
+    }
+
+    #[tokio::test]
+    async fn unreachable_provider_is_reported_not_fatal() {
+        // An empty mock answers every call with an error, like an unreachable RPC.
+        let (provider, _mock) = Provider::mocked();
+        let config: AppConfig = toml::from_str("eth_rpc_url = \"http://localhost:8545\"").unwrap();
+        let out = run(SelfTestTarget {
+            provider: Arc::new(provider),
+            config: &config,
+            registry: TokenRegistry::with_defaults(),
+            chain_id: 1,
+            signer: None,
+            now: 0,
+        })
+        .await;
+        assert!(!out.passed);
+        let status = |name: &str| {
+            out.checks
+                .iter()
+                .find(|check| check.name == name)
+                .map(|check| check.status)
+        };
+        assert_eq!(status("chain_id"), Some(SelfTestStatus::Fail));
+        assert_eq!(status("uniswap_deployment"), Some(SelfTestStatus::Fail));
+        assert_eq!(status("v2_venues"), Some(SelfTestStatus::Skip));
+        assert_eq!(status("uniswap_quoter"), Some(SelfTestStatus::Skip));
+    }
+
+    #[test]
+    fn wallet_check_signs_with_the_configured_key() {
+        let (provider, _mock) = Provider::mocked();
+        let config: AppConfig = toml::from_str("eth_rpc_url = \"http://localhost:8545\"").unwrap();
+        let mut target = SelfTestTarget {
+            provider: Arc::new(provider),
+            config: &config,
+            registry: TokenRegistry::new(),
+            chain_id: 1,
+            signer: None,
+            now: 0,
+        };
+        assert_eq!(wallet(&target).status, SelfTestStatus::Skip);
+
+        let signer: LocalWallet =
+            "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+                .parse()
+                .unwrap();
+        let address = signer.address();
+        target.signer = Some(signer);
+        let out = wallet(&target);
+        assert_eq!(out.status, SelfTestStatus::Pass);
+        assert!(out.detail.starts_with(&format!("{address:#x}")));
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::{Middleware, MiddlewareError},
+    types::{Address, Bytes, H256, Signature},
+    utils::hash_message,
+};
+use ethers_contract::{ContractError, abigen};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::revert,
+    types::{SignatureMethod, VerifySignatureOut},
+};
+
+abigen!(
+    Erc1271Wallet,
+    r#"[
+        function isValidSignature(bytes32 hash, bytes signature) view returns (bytes4)
+    ]"#
+);
+
+/// What `isValidSignature` returns for a valid signature: its own selector.
+pub const ERC1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
+
+/// What a signature signs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignedPayload {
+    /// Text signed as an EIP-191 personal message (`personal_sign`).
+    Message(String),
+    /// A 32-byte digest signed as-is, e.g. an EIP-712 typed-data hash.
+    Hash(H256),
+}
+
+impl SignedPayload {
+    /// The hash the signer committed to, which is also what `isValidSignature` is given.
+    pub fn digest(&self) -> H256 {
+        match self {
+            SignedPayload::Message(message) => hash_message(message),
+            SignedPayload::Hash(hash) => *hash,
+        }
+    }
+}
+
+/// The address a 65-byte ECDSA `signature` over `digest` recovers to; `None` for anything else,
+/// such as the concatenated owner signatures a Safe takes.
+pub fn recover(digest: H256, signature: &[u8]) -> Option<Address> {
+    Signature::try_from(signature).ok()?.recover(digest).ok()
+}
+
+/// Check that `signer` signed `digest`. ECDSA recovery comes first, which covers externally owned
+/// accounts, including ones with delegated code; an account with code is then asked through
+/// ERC-1271 `isValidSignature`, which is how Safe and ERC-4337 wallets validate. A call that
+/// reverts or answers anything but the magic value means invalid; only transport failures error.
+pub async fn verify<M: Middleware + 'static>(
+    provider: Arc<M>,
+    signer: Address,
+    digest: H256,
+    signature: Bytes,
+    block_number: u64,
+) -> AppResult<VerifySignatureOut> {
+    let recovered = recover(digest, &signature);
+    let code = provider
+        .get_code(signer, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to read signer code: {err}")))?;
+    let is_contract = !code.is_empty();
+
+    let (method, detail) = if recovered == Some(signer) {
+        (
+            Some(SignatureMethod::Ecdsa),
+            "signature recovers to the signer".to_string(),
+        )
+    } else if is_contract {
+        erc1271(provider, signer, digest, signature).await?
+    } else {
+        let detail = match recovered {
+            Some(other) => format!("signature recovers to {other:#x}, not the signer"),
+            None => "not a valid 65-byte ECDSA signature, and the signer has no code".to_string(),
+        };
+        (None, detail)
+    };
+
+    Ok(VerifySignatureOut {
+        signer: format!("{signer:#x}"),
+        digest: format!("{digest:#x}"),
+        valid: method.is_some(),
+        method,
+        is_contract,
+        recovered: recovered.map(|address| format!("{address:#x}")),
+        detail,
+        block_number,
+    })
+}
+
+async fn erc1271<M: Middleware + 'static>(
+    provider: Arc<M>,
+    wallet: Address,
+    digest: H256,
+    signature: Bytes,
+) -> AppResult<(Option<SignatureMethod>, String)> {
+    let call = Erc1271Wallet::new(wallet, provider).is_valid_signature(digest.0, signature);
+    let outcome = match call.call().await {
+        Ok(ERC1271_MAGIC_VALUE) => (
+            Some(SignatureMethod::Erc1271),
+            "isValidSignature returned the ERC-1271 magic value".to_string(),
+        ),
+        Ok(other) => (
+            None,
+            format!(
+                "isValidSignature returned 0x{}, not 0x{}",
+                hex::encode(other),
+                hex::encode(ERC1271_MAGIC_VALUE)
+            ),
+        ),
+        Err(ContractError::Revert(data)) => (
+            None,
+            format!("isValidSignature {}", revert::decode_revert(&data)),
+        ),
+        Err(ContractError::MiddlewareError { e }) if e.as_error_response().is_none() => {
+            return Err(AppError::Rpc(format!("isValidSignature call failed: {e}")));
+        }
+        Err(ContractError::ProviderError { e }) if e.as_error_response().is_none() => {
+            return Err(AppError::Rpc(format!("isValidSignature call failed: {e}")));
+        }
+        // Node-side errors and undecodable answers: the contract does not implement ERC-1271.
+        Err(err) => (
+            None,
+            format!("signer is a contract without a working isValidSignature: {err}"),
+        ),
+    };
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        signers::{LocalWallet, Signer},
+    };
+
+    fn wallet() -> LocalWallet {
+        "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn externally_owned_signers_verify_by_recovery() {
+        let wallet = wallet();
+        let payload = SignedPayload::Message("order #1".into());
+        let signature = wallet.sign_message("order #1").await.unwrap();
+        let signature = Bytes::from(signature.to_vec());
+
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        mock.push::<Bytes, _>(Bytes::new()).unwrap();
+        let out = verify(
+            provider.clone(),
+            wallet.address(),
+            payload.digest(),
+            signature.clone(),
+            1,
+        )
+        .await
+        .unwrap();
+        assert!(out.valid);
+        assert_eq!(out.method, Some(SignatureMethod::Ecdsa));
+        assert!(!out.is_contract);
+
+        mock.push::<Bytes, _>(Bytes::new()).unwrap();
+        let out = verify(
+            provider,
+            Address::repeat_byte(1),
+            payload.digest(),
+            signature,
+            1,
+        )
+        .await
+        .unwrap();
+        assert!(!out.valid);
+        assert_eq!(out.recovered, Some(format!("{:#x}", wallet.address())));
+    }
+
+    #[tokio::test]
+    async fn contract_signers_verify_through_erc1271() {
+        let safe = Address::repeat_byte(0x5a);
+        let digest = H256::repeat_byte(7);
+        let signature = Bytes::from(vec![0xab; 130]);
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+
+        // Responses are served last-pushed first: the call's answer, then the code.
+        let mut magic = [0u8; 32];
+        magic[..4].copy_from_slice(&ERC1271_MAGIC_VALUE);
+        mock.push::<Bytes, _>(Bytes::from(magic.to_vec())).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(vec![0x60, 0x00]))
+            .unwrap();
+        let out = verify(provider.clone(), safe, digest, signature.clone(), 1)
+            .await
+            .unwrap();
+        assert!(out.valid);
+        assert_eq!(out.method, Some(SignatureMethod::Erc1271));
+        assert!(out.is_contract);
+        assert_eq!(out.recovered, None);
+
+        mock.push::<Bytes, _>(Bytes::from([0u8; 32].to_vec()))
+            .unwrap();
+        mock.push::<Bytes, _>(Bytes::from(vec![0x60, 0x00]))
+            .unwrap();
+        let out = verify(provider, safe, digest, signature, 1).await.unwrap();
+        assert!(!out.valid);
+        assert!(out.detail.contains("0x00000000"));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use ethers::{providers::Middleware, types::Address};
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use tokio::time::{Instant, sleep};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        block_scope::BlockScoped,
+        price::{self, TokenRegistry},
+        warnings,
+    },
+    progress::ProgressReporter,
+    types::{QuoteCurrency, SpreadSampleOut, SpreadStatus, SpreadWatchOut},
+};
+
+const FULL_BPS: u32 = 10_000;
+
+/// What the base token is measured against: another token, or a Chainlink aggregator reporting
+/// a reference asset in USD (e.g. BTC/USD for WBTC).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadLeg {
+    Token(Address),
+    Feed(Address),
+}
+
+/// The ratio `base / quote` is expected to hold, and how far from it (in bps) is tolerated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpreadBand {
+    pub target_ratio: Decimal,
+    pub band_bps: u32,
+}
+
+impl SpreadBand {
+    pub fn parse(target_ratio: &str, band_bps: u32) -> AppResult<Self> {
+        let target_ratio = Decimal::from_str(target_ratio)
+            .ok()
+            .filter(|ratio| *ratio > Decimal::ZERO)
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "target_ratio must be a positive decimal, got {target_ratio}"
+                ))
+            })?;
+        if band_bps == 0 || band_bps > FULL_BPS {
+            return Err(AppError::InvalidInput(format!(
+                "band_bps must be between 1 and {FULL_BPS}"
+            )));
+        }
+        Ok(Self {
+            target_ratio,
+            band_bps,
+        })
+    }
+
+    /// How far `ratio` is from the target, in bps of the target; positive when `base` is rich.
+    pub fn spread_bps(&self, ratio: Decimal) -> AppResult<i64> {
+        (ratio / self.target_ratio - Decimal::ONE)
+            .checked_mul(Decimal::from(FULL_BPS))
+            .and_then(|bps| bps.round().to_i64())
+            .ok_or_else(|| AppError::Price(format!("spread of ratio {ratio} out of range")))
+    }
+
+    pub fn contains(&self, spread_bps: i64) -> bool {
+        spread_bps.unsigned_abs() <= u64::from(self.band_bps)
+    }
+}
+
+/// A pair to watch, with the names it is reported under.
+#[derive(Debug, Clone)]
+pub struct SpreadPair {
+    pub base: Address,
+    pub base_symbol: String,
+    pub quote: SpreadLeg,
+    pub quote_name: String,
+    pub band: SpreadBand,
+}
+
+/// Sample the pair's spread once per new block until it leaves the band or `window` elapses.
+/// A sample outside the band is handed to `on_breach` and ends the watch. Each poll reports the
+/// seconds elapsed of the window to `progress`.
+pub async fn watch_spread<M>(
+    provider: Arc<M>,
+    registry: &TokenRegistry,
+    pair: &SpreadPair,
+    window: Duration,
+    poll_interval: Duration,
+    progress: &ProgressReporter,
+    mut on_breach: impl FnMut(&SpreadSampleOut),
+) -> AppResult<SpreadWatchOut>
+where
+    M: Middleware + 'static,
+{
+    let started = Instant::now();
+    let deadline = started + window;
+    let mut samples: Vec<SpreadSampleOut> = Vec::new();
+    let mut dex_priced = false;
+
+    loop {
+        let scoped = Arc::new(BlockScoped::pin(provider.clone(), None).await?);
+        let fresh = samples
+            .last()
+            .is_none_or(|last| last.block_number < scoped.block_number());
+        if fresh {
+            let (sample, from_pool) = sample_spread(scoped, registry, pair).await?;
+            dex_priced |= from_pool;
+            progress.report(
+                started.elapsed().as_secs(),
+                Some(window.as_secs()),
+                format!(
+                    "{}/{} spread {} bps at block {}",
+                    pair.base_symbol, pair.quote_name, sample.spread_bps, sample.block_number
+                ),
+            );
+            let breached = !pair.band.contains(sample.spread_bps);
+            if breached {
+                on_breach(&sample);
+            }
+            samples.push(sample);
+            if breached {
+                return Ok(summarize(pair, SpreadStatus::Breached, samples, dex_priced));
+            }
+        }
+
+        if Instant::now() + poll_interval > deadline {
+            return Ok(summarize(
+                pair,
+                SpreadStatus::WithinBand,
+                samples,
+                dex_priced,
+            ));
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+/// Both legs in USD at the pinned block, and whether either came from pool quotes.
+async fn sample_spread<M>(
+    provider: Arc<BlockScoped<M>>,
+    registry: &TokenRegistry,
+    pair: &SpreadPair,
+) -> AppResult<(SpreadSampleOut, bool)>
+where
+    M: Middleware + 'static,
+{
+    let block_number = provider.block_number();
+    let base =
+        price::resolve_token_price(provider.clone(), registry, pair.base, QuoteCurrency::USD)
+            .await?;
+    let mut from_pool = !base.source.contains("chainlink");
+    let base_usd = parse_price(&base.price)?;
+    let quote_usd = match pair.quote {
+        SpreadLeg::Token(token) => {
+            let quote =
+                price::resolve_token_price(provider, registry, token, QuoteCurrency::USD).await?;
+            from_pool |= !quote.source.contains("chainlink");
+            parse_price(&quote.price)?
+        }
+        SpreadLeg::Feed(feed) => {
+            let round = price::read_chainlink_round(provider, registry.feed_decimals(), feed)
+                .await?
+                .answer;
+            if round <= Decimal::ZERO {
+                return Err(AppError::Price(format!(
+                    "Chainlink feed {feed:#x} returned non-positive answer {round}"
+                )));
+            }
+            round
+        }
+    };
+    if base_usd <= Decimal::ZERO || quote_usd <= Decimal::ZERO {
+        return Err(AppError::Price(format!(
+            "cannot take the ratio of {base_usd} to {quote_usd} USD"
+        )));
+    }
+    let ratio = base_usd / quote_usd;
+    let sample = SpreadSampleOut {
+        block_number,
+        base_usd: base_usd.normalize().to_string(),
+        quote_usd: quote_usd.normalize().to_string(),
+        ratio: ratio.round_dp(8).normalize().to_string(),
+        spread_bps: pair.band.spread_bps(ratio)?,
+    };
+    Ok((sample, from_pool))
+}
+
+fn parse_price(raw: &str) -> AppResult<Decimal> {
+    Decimal::from_str(raw).map_err(|err| AppError::Internal(format!("invalid price {raw}: {err}")))
+}
+
+fn summarize(
+    pair: &SpreadPair,
+    status: SpreadStatus,
+    samples: Vec<SpreadSampleOut>,
+    dex_priced: bool,
+) -> SpreadWatchOut {
+    let spreads = samples.iter().map(|sample| sample.spread_bps);
+    SpreadWatchOut {
+        base: pair.base_symbol.clone(),
+        quote: pair.quote_name.clone(),
+        target_ratio: pair.band.target_ratio.normalize().to_string(),
+        band_bps: pair.band.band_bps,
+        status,
+        first_block: samples.first().map(|sample| sample.block_number),
+        last_block: samples.last().map(|sample| sample.block_number),
+        min_spread_bps: spreads.clone().min(),
+        max_spread_bps: spreads.max(),
+        samples,
+        warnings: if dex_priced {
+            vec![warnings::warning(
+                "dex_price",
+                format!(
+                    "no Chainlink feed priced {} or {} at some samples; those come from pool quotes, which thin liquidity or a single large trade can move",
+                    pair.base_symbol, pair.quote_name
+                ),
+            )]
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_are_measured_against_the_target_ratio() {
+        let band = SpreadBand::parse("1", 50).unwrap();
+        let steth = Decimal::from_str("0.9931").unwrap();
+        assert_eq!(band.spread_bps(steth).unwrap(), -69);
+        assert!(!band.contains(-69));
+        assert!(
+            band.contains(
+                band.spread_bps(Decimal::from_str("1.004").unwrap())
+                    .unwrap()
+            )
+        );
+
+        let band = SpreadBand::parse("0.05", 100).unwrap();
+        assert_eq!(
+            band.spread_bps(Decimal::from_str("0.0506").unwrap())
+                .unwrap(),
+            120
+        );
+
+        assert!(SpreadBand::parse("0", 100).is_err());
+        assert!(SpreadBand::parse("1", 0).is_err());
+        assert!(SpreadBand::parse("1", 10_001).is_err());
+    }
+}
@@ -0,0 +1,445 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use ethers::{
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{
+        BlockNumber, Eip1559TransactionRequest, H256, Transaction, U64, U256,
+        transaction::eip2718::TypedTransaction,
+    },
+};
+use serde::Deserialize;
+use tokio::time::{Instant, sleep};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{deposits, warnings::warning},
+    progress::ProgressReporter,
+    types::{SpeedUpOut, TxFeesOut, TxWatchOut, TxWatchStatus, WarningOut},
+};
+
+/// Blocks a transaction may stay pending below the market fee before it counts as stuck.
+pub const DEFAULT_STUCK_TX_BLOCKS: u64 = 3;
+pub const DEFAULT_FEE_BUMP_PERCENT: u32 = 15;
+/// Smallest fee increase nodes accept for a same-nonce replacement (geth's `txpool.pricebump`).
+pub const MIN_FEE_BUMP_PERCENT: u32 = 10;
+/// Polls in a row the transaction must be missing before it counts as dropped. Reads are spread
+/// across providers, and one that has not seen or has already evicted it proves nothing alone.
+const DROPPED_AFTER_MISSES: u32 = 3;
+
+/// What `watch_transaction` does once a transaction is stuck.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StuckTxPolicy {
+    /// Report it with the fees a replacement needs.
+    #[default]
+    Notify,
+    /// Also sign that replacement when the configured wallet sent the transaction.
+    Bump,
+}
+
+impl FromStr for StuckTxPolicy {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "notify" => Ok(StuckTxPolicy::Notify),
+            "bump" => Ok(StuckTxPolicy::Bump),
+            other => Err(AppError::Config(format!(
+                "unknown stuck tx policy: {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StuckTxSettings {
+    pub policy: StuckTxPolicy,
+    pub stuck_after_blocks: u64,
+    /// Percent both fees are raised by over the stuck transaction's, at least.
+    pub bump_percent: u32,
+    /// Write operations are halted, so no replacement is signed even under `Bump`.
+    pub halted: bool,
+}
+
+impl StuckTxSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            policy: config.stuck_tx_policy,
+            stuck_after_blocks: config.stuck_tx_blocks.max(1),
+            // Anything lower would only produce replacements the network refuses.
+            bump_percent: config.fee_bump_percent.max(MIN_FEE_BUMP_PERCENT),
+            halted: false,
+        }
+    }
+}
+
+/// A transaction's `(max_fee_per_gas, max_priority_fee_per_gas)`; legacy ones only have a gas
+/// price.
+type Fees = (U256, Option<U256>);
+
+/// Follow a broadcast transaction until it is mined, replaced or dropped (missing from
+/// [`DROPPED_AFTER_MISSES`] polls in a row, its nonce unused), or has been pending
+/// for `settings.stuck_after_blocks` with fees below what the node now suggests, in which case
+/// it returns at once with the fees a replacement needs. Under the `bump` policy the replacement
+/// is signed with `signer` when that is the sender; nothing is broadcast. Each poll reports the
+/// seconds elapsed of the window to `progress`.
+#[allow(clippy::too_many_arguments)]
+pub async fn watch_transaction<M>(
+    provider: Arc<M>,
+    hash: H256,
+    settings: StuckTxSettings,
+    signer: Option<&LocalWallet>,
+    chain_id: u64,
+    window: Duration,
+    poll_interval: Duration,
+    progress: &ProgressReporter,
+) -> AppResult<TxWatchOut>
+where
+    M: Middleware + 'static,
+{
+    let tx = fetch_transaction(&provider, hash).await?.ok_or_else(|| {
+        AppError::InvalidInput(format!("transaction {hash:#x} is unknown to the node"))
+    })?;
+    let fees = own_fees(&tx);
+    let started = Instant::now();
+    let deadline = started + window;
+    let first_block = deposits::head_block(&provider).await?;
+    let mut out = TxWatchOut {
+        tx_hash: format!("{hash:#x}"),
+        from: format!("{:#x}", tx.from),
+        nonce: tx.nonce.to_string(),
+        status: TxWatchStatus::Pending,
+        first_block,
+        last_block: first_block,
+        blocks_pending: 0,
+        fees: fees_out(fees),
+        market_fees: None,
+        block_number: None,
+        success: None,
+        gas_used: None,
+        speed_up: None,
+        warnings: Vec::new(),
+    };
+    let mut misses = 0;
+
+    loop {
+        let head = deposits::head_block(&provider).await?;
+        out.last_block = head;
+        // Read the nonce before the receipt: a transaction mined in between still shows as mined.
+        let mined_nonce = provider
+            .get_transaction_count(tx.from, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to fetch nonce: {err}")))?;
+        let receipt = provider
+            .get_transaction_receipt(hash)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to fetch receipt: {err}")))?;
+        if let Some(receipt) = receipt {
+            let mined_at = receipt.block_number.map(|number| number.as_u64());
+            out.status = TxWatchStatus::Mined;
+            out.blocks_pending = mined_at.unwrap_or(head).saturating_sub(first_block);
+            out.block_number = mined_at;
+            out.success = receipt.status.map(|status| status == U64::one());
+            out.gas_used = receipt.gas_used.map(|gas| gas.to_string());
+            return Ok(out);
+        }
+        out.blocks_pending = head.saturating_sub(first_block);
+        if mined_nonce > tx.nonce {
+            out.status = TxWatchStatus::Replaced;
+            return Ok(out);
+        }
+        if fetch_transaction(&provider, hash).await?.is_none() {
+            misses += 1;
+            if misses >= DROPPED_AFTER_MISSES {
+                out.status = TxWatchStatus::Dropped;
+                return Ok(out);
+            }
+        } else if out.blocks_pending >= settings.stuck_after_blocks
+            && let Some((market, base_fee)) = market_fees(&provider).await?
+        {
+            out.market_fees = Some(fees_out((market.0, Some(market.1))));
+            if let Some(reason) = below_market(fees, base_fee, market.1) {
+                out.status = TxWatchStatus::Stuck;
+                out.speed_up = Some(speed_up(
+                    &tx,
+                    market,
+                    settings,
+                    signer,
+                    chain_id,
+                    &mut out.warnings,
+                )?);
+                out.warnings.push(warning(
+                    "tx_stuck",
+                    format!(
+                        "pending for {} blocks: {reason}; replace it with the speed_up fees and the same nonce",
+                        out.blocks_pending
+                    ),
+                ));
+                return Ok(out);
+            }
+        }
+
+        progress.report(
+            started.elapsed().as_secs(),
+            Some(window.as_secs()),
+            format!("pending for {} blocks", out.blocks_pending),
+        );
+        if Instant::now() + poll_interval > deadline {
+            return Ok(out);
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+async fn fetch_transaction<M: Middleware>(
+    provider: &Arc<M>,
+    hash: H256,
+) -> AppResult<Option<Transaction>> {
+    provider
+        .get_transaction(hash)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch transaction: {err}")))
+}
+
+/// The node's `(max_fee_per_gas, max_priority_fee_per_gas)` suggestion and the latest base fee;
+/// `None` on chains without EIP-1559, where there is no market to compare against.
+async fn market_fees<M: Middleware>(provider: &Arc<M>) -> AppResult<Option<((U256, U256), U256)>> {
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch latest block: {err}")))?;
+    let Some(base_fee) = block.and_then(|block| block.base_fee_per_gas) else {
+        return Ok(None);
+    };
+    let suggested = provider
+        .estimate_eip1559_fees(None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to estimate fees: {err}")))?;
+    Ok(Some((suggested, base_fee)))
+}
+
+fn own_fees(tx: &Transaction) -> Fees {
+    match tx.max_fee_per_gas {
+        Some(max_fee) => (max_fee, tx.max_priority_fee_per_gas),
+        None => (tx.gas_price.unwrap_or_default(), None),
+    }
+}
+
+fn fees_out((max_fee, priority): Fees) -> TxFeesOut {
+    TxFeesOut {
+        max_fee_per_gas: max_fee.to_string(),
+        max_priority_fee_per_gas: priority.map(|priority| priority.to_string()),
+    }
+}
+
+/// Why `fees` keep a transaction out of blocks at `base_fee`, if they do: a fee cap under the
+/// base fee, or a tip under what the node suggests.
+fn below_market(
+    (max_fee, priority): Fees,
+    base_fee: U256,
+    market_priority: U256,
+) -> Option<String> {
+    if max_fee < base_fee {
+        return Some(format!(
+            "its max fee of {max_fee} wei is below the base fee of {base_fee} wei"
+        ));
+    }
+    let tip = (max_fee - base_fee).min(priority.unwrap_or(max_fee));
+    (tip < market_priority)
+        .then(|| format!("it tips {tip} wei per gas where the node suggests {market_priority} wei"))
+}
+
+/// Fees for a replacement: the market suggestion, or `bump_percent` over the stuck
+/// transaction's own fees when that is higher, since nodes refuse smaller replacements.
+fn speed_up_fees(
+    (max_fee, priority): Fees,
+    market: (U256, U256),
+    bump_percent: u32,
+) -> (U256, U256) {
+    let bump = |value: U256| (value * (100 + bump_percent) + 99) / 100;
+    // A legacy gas price counts as both the fee cap and the tip.
+    let priority = bump(priority.unwrap_or(max_fee)).max(market.1);
+    let max_fee = bump(max_fee).max(market.0).max(priority);
+    (max_fee, priority)
+}
+
+fn speed_up(
+    tx: &Transaction,
+    market: (U256, U256),
+    settings: StuckTxSettings,
+    signer: Option<&LocalWallet>,
+    chain_id: u64,
+    warnings: &mut Vec<WarningOut>,
+) -> AppResult<SpeedUpOut> {
+    let (max_fee, priority) = speed_up_fees(own_fees(tx), market, settings.bump_percent);
+    let signed_tx = match (settings.policy, signer) {
+        (StuckTxPolicy::Bump, Some(signer)) if !settings.halted && signer.address() == tx.from => {
+            Some(sign_replacement(tx, (max_fee, priority), signer, chain_id)?)
+        }
+        (StuckTxPolicy::Bump, _) => {
+            let reason = if settings.halted {
+                "write operations are halted, so no replacement was signed"
+            } else if signer.is_none() {
+                "no signer is configured, so no replacement was signed"
+            } else {
+                "the transaction was not sent by the configured wallet, so no replacement was signed"
+            };
+            warnings.push(warning("bump_skipped", reason));
+            None
+        }
+        (StuckTxPolicy::Notify, _) => None,
+    };
+    Ok(SpeedUpOut {
+        fees: fees_out((max_fee, Some(priority))),
+        signed_tx,
+    })
+}
+
+/// `tx` again with the same nonce, gas and payload at the given fees, as a signed EIP-1559
+/// transaction.
+fn sign_replacement(
+    tx: &Transaction,
+    (max_fee, priority): (U256, U256),
+    signer: &LocalWallet,
+    chain_id: u64,
+) -> AppResult<String> {
+    let mut request = Eip1559TransactionRequest::new()
+        .from(tx.from)
+        .value(tx.value)
+        .data(tx.input.clone())
+        .nonce(tx.nonce)
+        .gas(tx.gas)
+        .access_list(tx.access_list.clone().unwrap_or_default())
+        .max_fee_per_gas(max_fee)
+        .max_priority_fee_per_gas(priority)
+        .chain_id(chain_id);
+    if let Some(to) = tx.to {
+        request = request.to(to);
+    }
+    let replacement: TypedTransaction = request.into();
+    let signature = signer
+        .sign_transaction_sync(&replacement)
+        .map_err(|err| AppError::Wallet(format!("failed to sign replacement: {err}")))?;
+    Ok(format!(
+        "0x{}",
+        hex::encode(replacement.rlp_signed(&signature))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::{
+        keccak256,
+        rlp::{Decodable, Rlp},
+    };
+
+    fn gwei(value: u64) -> U256 {
+        U256::from(value) * U256::exp10(9)
+    }
+
+    #[test]
+    fn only_fees_under_the_market_are_stuck() {
+        let base_fee = gwei(20);
+        let market_tip = gwei(2);
+        assert!(below_market((gwei(15), Some(gwei(2))), base_fee, market_tip).is_some());
+        assert!(below_market((gwei(40), Some(gwei(1))), base_fee, market_tip).is_some());
+        // The tip is capped by what the fee cap leaves above the base fee.
+        assert!(below_market((gwei(21), Some(gwei(5))), base_fee, market_tip).is_some());
+        assert!(below_market((gwei(40), Some(gwei(2))), base_fee, market_tip).is_none());
+        // Legacy gas prices tip everything above the base fee.
+        assert!(below_market((gwei(23), None), base_fee, market_tip).is_none());
+
+        let market = (gwei(42), gwei(2));
+        assert_eq!(
+            speed_up_fees((gwei(15), Some(gwei(2))), market, 15),
+            (gwei(42), gwei(2) * 115 / 100)
+        );
+        assert_eq!(
+            speed_up_fees((gwei(50), Some(gwei(3))), market, 10),
+            (gwei(55), gwei(3) * 110 / 100)
+        );
+    }
+
+    #[test]
+    fn replacements_keep_the_nonce_and_payload() {
+        let signer: LocalWallet =
+            "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+                .parse()
+                .unwrap();
+        let tx = Transaction {
+            from: signer.address(),
+            to: Some(ethers::types::Address::repeat_byte(0x11)),
+            nonce: 7.into(),
+            gas: 21_000.into(),
+            value: gwei(1),
+            max_fee_per_gas: Some(gwei(15)),
+            max_priority_fee_per_gas: Some(gwei(1)),
+            ..Default::default()
+        };
+        let settings = StuckTxSettings {
+            policy: StuckTxPolicy::Bump,
+            stuck_after_blocks: DEFAULT_STUCK_TX_BLOCKS,
+            bump_percent: DEFAULT_FEE_BUMP_PERCENT,
+            halted: false,
+        };
+        let mut warnings = Vec::new();
+        let out = speed_up(
+            &tx,
+            (gwei(42), gwei(2)),
+            settings,
+            Some(&signer),
+            1,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+        let raw = hex::decode(out.signed_tx.unwrap().trim_start_matches("0x")).unwrap();
+        let decoded = Transaction::decode(&Rlp::new(&raw)).unwrap();
+        assert_eq!(decoded.hash, H256(keccak256(&raw)));
+        assert_eq!(decoded.recover_from().unwrap(), signer.address());
+        assert_eq!(decoded.nonce, tx.nonce);
+        assert_eq!(decoded.max_fee_per_gas, Some(gwei(42)));
+
+        let stranger = Transaction {
+            from: ethers::types::Address::repeat_byte(0x22),
+            ..tx.clone()
+        };
+        let out = speed_up(
+            &stranger,
+            (gwei(42), gwei(2)),
+            settings,
+            Some(&signer),
+            1,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(out.signed_tx.is_none());
+        assert_eq!(warnings[0].code, "bump_skipped");
+        assert!(
+            warnings[0]
+                .message
+                .contains("not sent by the configured wallet")
+        );
+
+        let halted = StuckTxSettings {
+            halted: true,
+            ..settings
+        };
+        let mut warnings = Vec::new();
+        let out = speed_up(
+            &tx,
+            (gwei(42), gwei(2)),
+            halted,
+            Some(&signer),
+            1,
+            &mut warnings,
+        )
+        .unwrap();
+        assert!(out.signed_tx.is_none());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("halted"));
+    }
+}
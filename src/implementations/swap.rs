@@ -6,22 +6,70 @@ use std::{
 
 use ethers::{
     providers::Middleware,
-    types::{Address, TransactionRequest, U256, transaction::eip2718::TypedTransaction},
+    types::{
+        Address, Bytes, TransactionRequest, U256, U512,
+        transaction::{
+            eip2718::TypedTransaction,
+            eip2930::{AccessList, Eip2930TransactionRequest},
+        },
+    },
 };
+use tracing::warn;
 
 use crate::{
+    config::{AppConfig, DEFAULT_GAS_BUFFER_PERCENT},
     error::{AppError, AppResult},
     implementations::{
-        balance, erc20,
-        price::{UNISWAP_QUOTER_V2, UNISWAP_SWAP_ROUTER},
+        balance, erc20, mev,
+        price::TokenInfo,
+        revert,
+        timing::{Stage, Timings},
+        trace,
         uniswap::{
-            UniswapQuoterV2, UniswapRouter, uniswap_quoter_v2::QuoteExactInputSingleParams,
-            uniswap_router::ExactInputSingleParams,
+            UniswapDeployment, UniswapQuoterV2, UniswapRouter, pool_address,
+            uniswap_quoter_v2::QuoteExactInputSingleParams, uniswap_router::ExactInputSingleParams,
         },
     },
-    types::SwapTokensParams,
+    types::{RouteHopOut, SwapFeesOut, SwapTokensParams},
 };
 use ethers::signers::Signer;
+use rust_decimal::Decimal;
+
+/// The reference quote used for price impact trades `amount_in / MEV_REFERENCE_DIVISOR`.
+const MEV_REFERENCE_DIVISOR: u64 = 1_000;
+
+/// Bounds of `sqrtPriceX96` enforced by Uniswap V3 pools (exclusive).
+const MIN_SQRT_RATIO: u64 = 4_295_128_739;
+const MAX_SQRT_RATIO: &str = "1461446703485210103287273052203988822378723970342";
+
+/// Most decimals a token can use while one whole unit still fits in a U256.
+const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// Server-side knobs applied to every swap simulation.
+#[derive(Debug, Clone, Copy)]
+pub struct SwapSettings {
+    pub gas_buffer_percent: u32,
+    /// Contracts quoted and routed through on the active chain.
+    pub uniswap: UniswapDeployment,
+}
+
+impl SwapSettings {
+    pub fn from_config(config: &AppConfig, uniswap: UniswapDeployment) -> Self {
+        Self {
+            gas_buffer_percent: config.gas_buffer_percent,
+            uniswap,
+        }
+    }
+}
+
+impl Default for SwapSettings {
+    fn default() -> Self {
+        Self {
+            gas_buffer_percent: DEFAULT_GAS_BUFFER_PERCENT,
+            uniswap: UniswapDeployment::default(),
+        }
+    }
+}
 
 /// Simulate a Uniswap V3 single-hop swap and return calldata plus gas/amount estimates.
 pub async fn simulate_swap<M>(
@@ -30,7 +78,217 @@ pub async fn simulate_swap<M>(
     from_token: Address,
     to_token: Address,
     params: SwapTokensParams,
+    settings: SwapSettings,
+) -> AppResult<crate::types::SwapSimOut>
+where
+    M: Middleware + 'static,
+{
+    simulate_swap_timed(
+        provider,
+        signer,
+        from_token,
+        to_token,
+        params,
+        settings,
+        &mut Timings::default(),
+    )
+    .await
+}
+
+/// [`simulate_swap`] that records quoter, gas estimation and dry-run latency into `timings`.
+pub async fn simulate_swap_timed<M>(
+    provider: Arc<M>,
+    signer: ethers::signers::LocalWallet,
+    from_token: Address,
+    to_token: Address,
+    params: SwapTokensParams,
+    settings: SwapSettings,
+    timings: &mut Timings,
 ) -> AppResult<crate::types::SwapSimOut>
+where
+    M: Middleware + 'static,
+{
+    let QuotedSwap {
+        amount_in,
+        amount_out,
+        amount_out_min,
+        calldata,
+        to_meta,
+        recipient,
+        ..
+    } = quote_swap(
+        provider.clone(),
+        signer.address(),
+        from_token,
+        to_token,
+        &params,
+        settings.uniswap,
+        timings,
+    )
+    .await?;
+    let SwapTokensParams {
+        fee,
+        slippage_bps,
+        gas_limit: gas_limit_override,
+        trace_on_failure,
+        ..
+    } = params;
+
+    // A much smaller quote on the same pool approximates the pre-trade spot rate.
+    let reference_in = amount_in / U256::from(MEV_REFERENCE_DIVISOR);
+    let price_impact = if reference_in.is_zero() {
+        None
+    } else {
+        let quoter = UniswapQuoterV2::new(settings.uniswap.quoter, provider.clone());
+        let reference_call = quoter.quote_exact_input_single(QuoteExactInputSingleParams {
+            token_in: from_token,
+            token_out: to_token,
+            amount_in: reference_in,
+            fee,
+            sqrt_price_limit_x96: U256::zero(),
+        });
+        match timings.time(Stage::Quoter, reference_call.call()).await {
+            Ok((reference_out, _, _, _)) => {
+                mev::price_impact_bps(amount_in, amount_out, reference_in, reference_out)
+            }
+            Err(err) => {
+                warn!("reference quote for price impact failed: {err}");
+                None
+            }
+        }
+    };
+
+    let request = TransactionRequest::new()
+        .to(settings.uniswap.router)
+        .from(signer.address())
+        .data(calldata.clone())
+        .value(U256::zero());
+    let mut tx: TypedTransaction = request.clone().into();
+
+    let plain_estimate = match timings
+        .time(Stage::EstimateGas, provider.estimate_gas(&tx, None))
+        .await
+    {
+        Ok(estimate) => estimate,
+        Err(err) => {
+            let error = revert::annotate(
+                AppError::Swap(format!("gas estimation failed: {err}")),
+                &err,
+            );
+            return Err(failed_simulation(provider.as_ref(), &tx, trace_on_failure, error).await);
+        }
+    };
+
+    // Switch to an EIP-2930 transaction only when the access list actually lowers gas.
+    let found = timings
+        .time(
+            Stage::EstimateGas,
+            find_access_list(&provider, &request, plain_estimate),
+        )
+        .await;
+    let (gas_estimate, access_list) = match found {
+        Some((list, estimate)) => {
+            tx = Eip2930TransactionRequest::new(request, list.clone()).into();
+            (estimate, Some(list))
+        }
+        None => (plain_estimate, None),
+    };
+
+    let gas_limit = match gas_limit_override {
+        Some(limit) => {
+            let limit = U256::from(limit);
+            if limit < gas_estimate {
+                return Err(AppError::Swap(format!(
+                    "gas_limit {limit} is below the estimated {gas_estimate} gas"
+                )));
+            }
+            limit
+        }
+        None => apply_gas_buffer(gas_estimate, settings.gas_buffer_percent),
+    };
+    // Dry-run with the limit a broadcast would carry so the padded value is exercised too.
+    tx.set_gas(gas_limit);
+
+    if let Err(err) = timings.time(Stage::Call, provider.call(&tx, None)).await {
+        let error = revert::annotate(
+            AppError::Swap(format!("eth_call simulation failed: {err}")),
+            &err,
+        );
+        return Err(failed_simulation(provider.as_ref(), &tx, trace_on_failure, error).await);
+    }
+
+    // Fee data lets the caller build the final transaction without another round trip.
+    let fees = fee_preview(&provider, gas_estimate, gas_limit).await;
+
+    let amount_out_decimal = balance::format_with_decimals(&amount_out, to_meta.decimals as u32);
+    let amount_out_min_decimal =
+        balance::format_with_decimals(&amount_out_min, to_meta.decimals as u32);
+    let mev_risk = mev::assess_sandwich_risk(
+        price_impact,
+        slippage_bps,
+        balance::format_with_decimals(&(amount_out - amount_out_min), to_meta.decimals as u32),
+    );
+
+    Ok(crate::types::SwapSimOut {
+        block_number: None,
+        amount_out_estimate: amount_out_decimal,
+        to_decimals: to_meta.decimals as u32,
+        decimals_warning: None,
+        gas_estimate: gas_estimate.to_string(),
+        gas_limit: gas_limit.to_string(),
+        fees,
+        access_list_gas_savings: access_list
+            .as_ref()
+            .map(|_| (plain_estimate - gas_estimate).to_string()),
+        access_list,
+        calldata_hex: format!("0x{}", hex::encode(&calldata)),
+        router: format!("{:#x}", settings.uniswap.router),
+        recipient: format!("{recipient:#x}"),
+        recipient_ens: None,
+        route: vec![RouteHopOut {
+            token_in: format!("{from_token:#x}"),
+            token_out: format!("{to_token:#x}"),
+            fee,
+            pool: format!(
+                "{:#x}",
+                pool_address(settings.uniswap.factory, from_token, to_token, fee)
+            ),
+        }],
+        execution_price: None,
+        amount_out_min: amount_out_min_decimal,
+        amount_out_min_raw: amount_out_min.to_string(),
+        mev_risk,
+        quote_id: None,
+        quote_expires_at: None,
+        timings: None,
+        warnings: Vec::new(),
+    })
+}
+
+/// A quoted single-hop swap with router calldata, before any gas estimation or dry-run.
+#[derive(Debug, Clone)]
+pub struct QuotedSwap {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub amount_out_min: U256,
+    /// Gas the quoter measured for the pool swap alone, without router overhead.
+    pub quoter_gas_estimate: U256,
+    pub calldata: Bytes,
+    pub to_meta: erc20::Erc20Metadata,
+    pub recipient: Address,
+}
+
+/// Validate `params`, quote the pool and build `exactInputSingle` calldata paying `recipient`
+/// (or `owner` when unset).
+pub async fn quote_swap<M>(
+    provider: Arc<M>,
+    owner: Address,
+    from_token: Address,
+    to_token: Address,
+    params: &SwapTokensParams,
+    uniswap: UniswapDeployment,
+    timings: &mut Timings,
+) -> AppResult<QuotedSwap>
 where
     M: Middleware + 'static,
 {
@@ -42,6 +300,7 @@ where
         sqrt_price_limit,
         ..
     } = params;
+    let (slippage_bps, fee) = (*slippage_bps, *fee);
 
     if slippage_bps > 10_000 {
         return Err(AppError::Swap(
@@ -49,7 +308,7 @@ where
         ));
     }
 
-    let amount_in = parse_amount(&amount_in_wei)?;
+    let amount_in = parse_amount(amount_in_wei)?;
     if amount_in.is_zero() {
         return Err(AppError::Swap(
             "amount_in_wei must be greater than zero".into(),
@@ -66,7 +325,7 @@ where
         .transpose()?
         .unwrap_or_else(U256::zero);
 
-    let quoter = UniswapQuoterV2::new(*UNISWAP_QUOTER_V2, provider.clone());
+    let quoter = UniswapQuoterV2::new(uniswap.quoter, provider.clone());
     let quote_params = QuoteExactInputSingleParams {
         token_in: from_token,
         token_out: to_token,
@@ -75,9 +334,11 @@ where
         sqrt_price_limit_x96: sqrt_price_limit_value,
     };
 
-    let (amount_out, _, _, _) = quoter
-        .quote_exact_input_single(quote_params)
-        .call()
+    let (amount_out, _, _, quoter_gas_estimate) = timings
+        .time(
+            Stage::Quoter,
+            quoter.quote_exact_input_single(quote_params).call(),
+        )
         .await
         .map_err(|err| AppError::Swap(format!("uniswap quoter call failed: {err}")))?;
 
@@ -87,11 +348,12 @@ where
 
     let amount_out_min = apply_slippage(amount_out, slippage_bps)?;
 
-    let router = UniswapRouter::new(*UNISWAP_SWAP_ROUTER, provider.clone());
+    let router = UniswapRouter::new(uniswap.router, provider.clone());
     let deadline = current_unix_timestamp() + 900; // 15 minute validity window keeps calldata realistic.
     let recipient = recipient
-        .and_then(|value| Address::from_str(&value).ok())
-        .unwrap_or_else(|| signer.address());
+        .as_deref()
+        .and_then(|value| Address::from_str(value).ok())
+        .unwrap_or(owner);
     // Build swap calldata using the same parameters we quoted with above.
     let call = router
         .exact_input_single(ExactInputSingleParams {
@@ -111,45 +373,226 @@ where
         .ok_or_else(|| AppError::Internal("failed to build swap calldata".into()))?
         .clone();
 
-    let tx: TypedTransaction = TransactionRequest::new()
-        .to(*UNISWAP_SWAP_ROUTER)
-        .from(signer.address())
-        .data(calldata.clone())
-        .value(U256::zero())
-        .into();
+    Ok(QuotedSwap {
+        amount_in,
+        amount_out,
+        amount_out_min,
+        quoter_gas_estimate,
+        calldata,
+        to_meta,
+        recipient,
+    })
+}
 
-    let gas_estimate = provider
-        .estimate_gas(&tx, None)
-        .await
-        .map_err(|err| AppError::Swap(format!("gas estimation failed: {err}")))?;
+/// Price `gas_estimate` and `gas_limit` with the node's EIP-1559 fee suggestion. Nodes without
+/// fee history only log a warning, so callers still get gas units.
+pub async fn fee_preview<M>(
+    provider: &Arc<M>,
+    gas_estimate: U256,
+    gas_limit: U256,
+) -> Option<SwapFeesOut>
+where
+    M: Middleware + 'static,
+{
+    match provider.estimate_eip1559_fees(None).await {
+        Ok(fees) => Some(fees_out(fees, gas_estimate, gas_limit)),
+        Err(err) => {
+            warn!("EIP-1559 fee estimation failed, returning gas units only: {err}");
+            None
+        }
+    }
+}
 
-    provider
-        .call(&tx, None)
-        .await
-        .map_err(|err| AppError::Swap(format!("eth_call simulation failed: {err}")))?;
+/// [`fee_preview`] for an already fetched `(max_fee_per_gas, max_priority_fee_per_gas)`.
+pub fn fees_out(
+    (max_fee_per_gas, max_priority_fee_per_gas): (U256, U256),
+    gas_estimate: U256,
+    gas_limit: U256,
+) -> SwapFeesOut {
+    let estimated_cost = gas_estimate * max_fee_per_gas;
+    let max_cost = gas_limit * max_fee_per_gas;
+    SwapFeesOut {
+        max_fee_per_gas: max_fee_per_gas.to_string(),
+        max_priority_fee_per_gas: max_priority_fee_per_gas.to_string(),
+        estimated_cost_wei: estimated_cost.to_string(),
+        estimated_cost_eth: balance::format_with_decimals(&estimated_cost, 18),
+        max_cost_wei: max_cost.to_string(),
+        max_cost_eth: balance::format_with_decimals(&max_cost, 18),
+    }
+}
 
-    let amount_out_decimal = balance::format_with_decimals(&amount_out, to_meta.decimals as u32);
-    let amount_out_min_decimal =
-        balance::format_with_decimals(&amount_out_min, to_meta.decimals as u32);
+/// Ask the node for an access list and return it with the re-estimated gas when it saves gas.
+/// Not every endpoint supports `eth_createAccessList`, so failures only log a warning.
+async fn find_access_list<M>(
+    provider: &Arc<M>,
+    request: &TransactionRequest,
+    plain_estimate: U256,
+) -> Option<(AccessList, U256)>
+where
+    M: Middleware + 'static,
+{
+    let tx: TypedTransaction = request.clone().into();
+    let created = match provider.create_access_list(&tx, None).await {
+        Ok(created) => created,
+        Err(err) => {
+            warn!("eth_createAccessList failed, simulating without access list: {err}");
+            return None;
+        }
+    };
 
-    Ok(crate::types::SwapSimOut {
-        amount_out_estimate: amount_out_decimal,
-        gas_estimate: gas_estimate.to_string(),
-        calldata_hex: format!("0x{}", hex::encode(&calldata)),
-        router: format!("{:#x}", *UNISWAP_SWAP_ROUTER),
-        amount_out_min: amount_out_min_decimal,
-    })
+    if created.access_list.0.is_empty() {
+        return None;
+    }
+
+    let tx: TypedTransaction =
+        Eip2930TransactionRequest::new(request.clone(), created.access_list.clone()).into();
+    match provider.estimate_gas(&tx, None).await {
+        Ok(estimate) if estimate < plain_estimate => Some((created.access_list, estimate)),
+        Ok(_) => None,
+        Err(err) => {
+            warn!("gas estimation with access list failed: {err}");
+            None
+        }
+    }
 }
 
+/// Convert a human price limit into `sqrtPriceLimitX96` for the `from`/`to` pool.
+///
+/// `raw` is a decimal price of `to` per `from` (`"2600"` when selling WETH for USDC), optionally
+/// followed by the pair as `"2600 USDC/WETH"` or `"2600 USDC per WETH"`; naming the pair the other
+/// way round (`from` per `to`) is accepted too, and so is a leading `max` or `min`, which the
+/// swap direction already implies. Pool ordering and decimals are applied here.
+pub fn sqrt_price_limit_from_price(raw: &str, from: &TokenInfo, to: &TokenInfo) -> AppResult<U256> {
+    let mut parts = raw.split_whitespace().peekable();
+    parts.next_if(|word| word.eq_ignore_ascii_case("max") || word.eq_ignore_ascii_case("min"));
+    let price_raw = parts.next().unwrap_or_default();
+    let pair = parts.collect::<Vec<_>>().join(" ");
+    let price = Decimal::from_str(price_raw)
+        .map_err(|_| AppError::InvalidInput(format!("invalid price_limit: {raw}")))?;
+    if price <= Decimal::ZERO {
+        return Err(AppError::InvalidInput(
+            "price_limit must be greater than zero".into(),
+        ));
+    }
+
+    // `inverted` means the price was given as `from` per `to`.
+    let inverted = if pair.is_empty() {
+        false
+    } else {
+        let (quote, base) = pair
+            .split_once('/')
+            .or_else(|| pair.split_once(" per "))
+            .map(|(quote, base)| (quote.trim(), base.trim()))
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "price_limit pair must look like QUOTE/BASE or QUOTE per BASE, got {pair}"
+                ))
+            })?;
+        let is = |token: &TokenInfo, symbol: &str| token.symbol.eq_ignore_ascii_case(symbol);
+        if is(to, quote) && is(from, base) {
+            false
+        } else if is(from, quote) && is(to, base) {
+            true
+        } else {
+            return Err(AppError::InvalidInput(format!(
+                "price_limit pair {pair} does not match {}/{}",
+                to.symbol, from.symbol
+            )));
+        }
+    };
+
+    // No U256 amount has more digits than this, and beyond it the scaling below overflows.
+    if let Some(token) = [from, to]
+        .into_iter()
+        .find(|token| token.decimals > MAX_TOKEN_DECIMALS)
+    {
+        return Err(AppError::InvalidInput(format!(
+            "{} reports {} decimals; price_limit supports at most {MAX_TOKEN_DECIMALS}",
+            token.symbol, token.decimals
+        )));
+    }
+
+    // Human price `to` per `from` as mantissa / 10^scale; flip it when given the other way.
+    let mantissa = U512::from(price.mantissa().unsigned_abs());
+    let scale = U512::exp10(price.scale() as usize);
+    let (to_per_from_num, to_per_from_den) = if inverted {
+        (scale, mantissa)
+    } else {
+        (mantissa, scale)
+    };
+    // Pools price token1 in token0 raw units: token1_raw / token0_raw.
+    let (num, den) = if from.address < to.address {
+        (
+            to_per_from_num * U512::exp10(to.decimals as usize),
+            to_per_from_den * U512::exp10(from.decimals as usize),
+        )
+    } else {
+        (
+            to_per_from_den * U512::exp10(from.decimals as usize),
+            to_per_from_num * U512::exp10(to.decimals as usize),
+        )
+    };
+
+    let out_of_range = || {
+        AppError::InvalidInput(format!(
+            "price_limit {raw} is outside the range a Uniswap V3 pool can reach"
+        ))
+    };
+    let sqrt_price = num
+        .checked_mul(U512::one() << 192)
+        .ok_or_else(out_of_range)?
+        .checked_div(den)
+        .ok_or_else(out_of_range)?
+        .integer_sqrt();
+    let max = U512::from_dec_str(MAX_SQRT_RATIO).expect("valid max sqrt ratio");
+    if sqrt_price <= U512::from(MIN_SQRT_RATIO) || sqrt_price >= max {
+        return Err(out_of_range());
+    }
+    U256::try_from(sqrt_price).map_err(|_| AppError::Internal("sqrt price limit overflowed".into()))
+}
+
+/// `error` with the call tree of `tx` attached when the caller asked for `trace_on_failure`.
+async fn failed_simulation<M: Middleware>(
+    provider: &M,
+    tx: &TypedTransaction,
+    trace_on_failure: bool,
+    error: AppError,
+) -> AppError {
+    if trace_on_failure {
+        trace::attach_trace(provider, tx, error).await
+    } else {
+        error
+    }
+}
+
+/// Raw base-unit amount: decimal digits only, at most `U256::MAX`.
 fn parse_amount(raw: &str) -> AppResult<U256> {
-    U256::from_dec_str(raw)
-        .map_err(|_| AppError::InvalidInput(format!("invalid numeric value: {raw}")))
+    if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::InvalidInput(format!(
+            "invalid numeric value: {raw}"
+        )));
+    }
+    U256::from_dec_str(raw).map_err(|_| {
+        AppError::InvalidInput(format!("numeric value exceeds the largest uint256: {raw}"))
+    })
 }
 
-fn apply_slippage(amount: U256, slippage_bps: u32) -> AppResult<U256> {
+/// `amount` less `slippage_bps`, rounded down. Exact for every `U256`: the amount is split
+/// around the basis so the product cannot overflow.
+pub(crate) fn apply_slippage(amount: U256, slippage_bps: u32) -> AppResult<U256> {
+    if slippage_bps > 10_000 {
+        return Err(AppError::Swap(
+            "slippage cannot exceed 100% (10_000 bps)".into(),
+        ));
+    }
     let basis = U256::from(10_000u32);
     let numerator = U256::from(10_000u32 - slippage_bps);
-    Ok((amount * numerator) / basis)
+    Ok(amount / basis * numerator + amount % basis * numerator / basis)
+}
+
+pub(crate) fn apply_gas_buffer(estimate: U256, buffer_percent: u32) -> U256 {
+    // Widened so no configured buffer overflows.
+    estimate.saturating_mul(U256::from(100 + u64::from(buffer_percent))) / U256::from(100u32)
 }
 
 fn current_unix_timestamp() -> u64 {
@@ -170,8 +613,9 @@ mod tests {
         abi::{self, Token},
         providers::{Http, Provider},
         signers::{LocalWallet, Signer},
-        types::{Address, U256},
+        types::{Address, U256, U512},
     };
+    use proptest::prelude::*;
     use serde_json::json;
     use std::{env, str::FromStr, sync::Arc, time::Duration};
 
@@ -182,6 +626,80 @@ mod tests {
         assert_eq!(result, U256::from(990_000u64));
     }
 
+    #[test]
+    fn slippage_rejects_more_than_everything() {
+        assert_eq!(apply_slippage(U256::MAX, 10_000).unwrap(), U256::zero());
+        assert_eq!(apply_slippage(U256::MAX, 0).unwrap(), U256::MAX);
+        assert!(apply_slippage(U256::one(), 10_001).is_err());
+    }
+
+    #[test]
+    fn raw_amounts_are_plain_digits_within_uint256() {
+        assert_eq!(parse_amount(&U256::MAX.to_string()).unwrap(), U256::MAX);
+        assert_eq!(parse_amount("007").unwrap(), U256::from(7u64));
+        for raw in ["", " 1", "-1", "1.5", "1e18", "0x10"] {
+            assert!(parse_amount(raw).is_err(), "{raw:?} should be rejected");
+        }
+        let above_max = U256::MAX.to_string().replacen('1', "2", 1);
+        assert!(parse_amount(&above_max).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn slippage_matches_full_precision(limbs in any::<[u64; 4]>(), bps in 0u32..=10_000) {
+            let amount = U256(limbs);
+            let exact = amount.full_mul(U256::from(10_000 - bps)) / U512::from(10_000u32);
+            let min_out = apply_slippage(amount, bps).unwrap();
+            prop_assert_eq!(U512::from(min_out), exact);
+            prop_assert!(min_out <= amount);
+        }
+    }
+
+    #[test]
+    fn gas_buffer_calculation() {
+        let estimate = U256::from(100_000u64);
+        assert_eq!(apply_gas_buffer(estimate, 20), U256::from(120_000u64));
+        assert_eq!(apply_gas_buffer(estimate, 0), estimate);
+        assert_eq!(
+            apply_gas_buffer(estimate, u32::MAX),
+            U256::from(4_294_967_395_000u64)
+        );
+    }
+
+    #[test]
+    fn price_limit_converts_to_sqrt_x96() {
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(1), 6);
+        let weth = TokenInfo::new("WETH", Address::from_low_u64_be(2), 18);
+
+        // USDC is token0: the pool price is 1/2600 WETH per USDC scaled by 10^12.
+        let sell = sqrt_price_limit_from_price("2600", &weth, &usdc).unwrap();
+        assert_eq!(
+            sell,
+            U256::from_dec_str("1553792102639747119534487568531906").unwrap()
+        );
+
+        // Every spelling of the same price gives the same limit.
+        assert_eq!(
+            sqrt_price_limit_from_price("2600 usdc/weth", &weth, &usdc).unwrap(),
+            sell
+        );
+        assert_eq!(
+            sqrt_price_limit_from_price("2600 USDC per WETH", &usdc, &weth).unwrap(),
+            sell
+        );
+        assert_eq!(
+            sqrt_price_limit_from_price("max 2600 USDC per WETH", &weth, &usdc).unwrap(),
+            sell
+        );
+        assert!(sqrt_price_limit_from_price("2600 DAI/WETH", &weth, &usdc).is_err());
+        assert!(sqrt_price_limit_from_price("0", &weth, &usdc).is_err());
+
+        // Absurd decimals are refused instead of overflowing the scaling.
+        let wide = TokenInfo::new("WIDE", Address::from_low_u64_be(3), 200);
+        assert!(sqrt_price_limit_from_price("1", &weth, &wide).is_err());
+        assert!(sqrt_price_limit_from_price("1", &wide, &usdc).is_err());
+    }
+
     #[tokio::test]
     async fn simulate_swap_unit_happy_path() {
         let (mocked_provider, mock) = Provider::mocked();
@@ -205,10 +723,20 @@ mod tests {
             Token::Uint(U256::from(25u32)),
             Token::Uint(U256::from(150_000u64)),
         ]);
+        // Same rate as the full-size quote, i.e. no price impact.
+        let reference_quote_data = abi::encode(&[
+            Token::Uint(U256::from_dec_str("250000000000000").unwrap()),
+            Token::Uint(U256::from(1_000_000u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(150_000u64)),
+        ]);
 
         // Responses are consumed in reverse order.
         mock.push::<String, _>("0x".to_string()).unwrap(); // provider.call
+        mock.push(json!({"accessList": [], "gasUsed": "0x5208"})).unwrap(); // create_access_list
         mock.push::<String, _>("0x5208".to_string()).unwrap(); // estimate_gas -> 21000
+        mock.push::<String, _>(format!("0x{}", hex::encode(&reference_quote_data)))
+            .unwrap();
         mock.push::<String, _>(format!("0x{}", hex::encode(&quote_data)))
             .unwrap();
         mock.push::<String, _>(format!("0x{}", hex::encode(&symbol_data)))
@@ -224,10 +752,27 @@ mod tests {
             fee: 3_000,
             recipient: None,
             sqrt_price_limit: None,
+            price_limit: None,
+            gas_limit: None,
+            compliance_override: false,
+            block: None,
+            debug: false,
+            memo: None,
+            tags: Vec::new(),
+            trace_on_failure: false,
+            quote_id: None,
         };
 
-        let output =
-            simulate_swap(provider, wallet, from_token, to_token, params).await.unwrap();
+        let output = simulate_swap(
+            provider,
+            wallet,
+            from_token,
+            to_token,
+            params,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap();
 
         let expected_amount = balance::format_with_decimals(&amount_out, 18);
         let expected_min =
@@ -236,7 +781,19 @@ mod tests {
         assert_eq!(output.amount_out_estimate, expected_amount);
         assert_eq!(output.amount_out_min, expected_min);
         assert_eq!(output.gas_estimate, U256::from(0x5208u64).to_string());
-        assert_eq!(output.router, format!("{:#x}", *UNISWAP_SWAP_ROUTER));
+        assert_eq!(output.gas_limit, apply_gas_buffer(U256::from(0x5208u64), 20).to_string());
+        assert_eq!(
+            output.router,
+            format!("{:#x}", UniswapDeployment::default().router)
+        );
+        assert_eq!(output.route.len(), 1);
+        assert_eq!(output.route[0].fee, 3_000);
+        assert_eq!(output.route[0].token_in, format!("{from_token:#x}"));
+        assert!(output.access_list.is_none());
+        assert_eq!(output.mev_risk.rating, "medium");
+        assert_eq!(output.mev_risk.price_impact_bps, Some(0));
+        assert_eq!(output.mev_risk.max_extractable, "0.0025");
+        assert!(output.access_list_gas_savings.is_none());
         assert!(output.calldata_hex.starts_with("0x"));
         assert!(
             !output.calldata_hex.trim_start_matches("0x").is_empty(),
@@ -244,6 +801,174 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn simulate_swap_uses_access_list_when_it_saves_gas() {
+        let (mocked_provider, mock) = Provider::mocked();
+        let provider = Arc::new(mocked_provider);
+
+        let wallet: LocalWallet = "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+            .parse()
+            .unwrap();
+
+        let decimals_data = abi::encode(&[Token::Uint(U256::from(6u8))]);
+        let symbol_data = abi::encode(&[Token::String("USDC".into())]);
+        let quote_data = abi::encode(&[
+            Token::Uint(U256::from(2_500_000_000u64)),
+            Token::Uint(U256::from(1_000_000u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(150_000u64)),
+        ]);
+        // Spot rate is ~4% better than the full-size execution rate.
+        let reference_quote_data = abi::encode(&[
+            Token::Uint(U256::from(2_600_000u64)),
+            Token::Uint(U256::from(1_000_000u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(150_000u64)),
+        ]);
+        let pool = format!("{:#x}", Address::from_low_u64_be(0xbeef));
+
+        // Responses are consumed in reverse order.
+        mock.push(json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x2540be400"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x3b9aca00"]]
+        }))
+        .unwrap(); // fee_history
+        mock.push(json!({"number": "0x1", "baseFeePerGas": "0x2540be400"}))
+            .unwrap(); // latest block, 10 gwei base fee
+        mock.push::<String, _>("0x".to_string()).unwrap(); // provider.call
+        mock.push::<String, _>("0x1d4c0".to_string()).unwrap(); // estimate_gas with list -> 120000
+        mock.push(json!({
+            "accessList": [{"address": pool, "storageKeys": [format!("0x{}", "00".repeat(32))]}],
+            "gasUsed": "0x1d4c0"
+        }))
+        .unwrap();
+        mock.push::<String, _>("0x1e848".to_string()).unwrap(); // estimate_gas -> 125000
+        mock.push::<String, _>(format!("0x{}", hex::encode(&reference_quote_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&quote_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&symbol_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&decimals_data)))
+            .unwrap();
+
+        let params = SwapTokensParams {
+            from_token: format!("{:#x}", Address::from_low_u64_be(1)),
+            to_token: format!("{:#x}", Address::from_low_u64_be(2)),
+            amount_in_wei: "1000000000000000000".to_string(),
+            slippage_bps: 50,
+            fee: 500,
+            recipient: None,
+            sqrt_price_limit: None,
+            price_limit: None,
+            gas_limit: None,
+            compliance_override: false,
+            block: None,
+            debug: false,
+            memo: None,
+            tags: Vec::new(),
+            trace_on_failure: false,
+            quote_id: None,
+        };
+
+        let output = simulate_swap(
+            provider,
+            wallet,
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            params,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output.gas_estimate, "120000");
+        assert_eq!(output.gas_limit, "144000");
+        assert_eq!(output.access_list_gas_savings.as_deref(), Some("5000"));
+        // 10 gwei base fee doubles to a 20 gwei max fee.
+        let fees = output.fees.expect("fee data should be attached");
+        assert_eq!(fees.max_fee_per_gas, "20000000000");
+        assert_eq!(fees.max_priority_fee_per_gas, "3000000000");
+        assert_eq!(fees.estimated_cost_wei, "2400000000000000");
+        assert_eq!(fees.max_cost_eth, "0.00288");
+        assert_eq!(output.mev_risk.rating, "high");
+        assert_eq!(output.mev_risk.price_impact_bps, Some(385));
+        let list = output.access_list.expect("access list should be attached");
+        assert_eq!(list.0.len(), 1);
+        assert_eq!(list.0[0].address, Address::from_low_u64_be(0xbeef));
+    }
+
+    #[tokio::test]
+    async fn simulate_swap_rejects_gas_limit_below_estimate() {
+        let (mocked_provider, mock) = Provider::mocked();
+        let provider = Arc::new(mocked_provider);
+
+        let wallet: LocalWallet = "0x59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02"
+            .parse()
+            .unwrap();
+
+        let decimals_data = abi::encode(&[Token::Uint(U256::from(18u8))]);
+        let symbol_data = abi::encode(&[Token::String("TKN".into())]);
+        let quote_data = abi::encode(&[
+            Token::Uint(U256::from(1_000u64)),
+            Token::Uint(U256::from(1_000_000u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(150_000u64)),
+        ]);
+        let reference_quote_data = abi::encode(&[
+            Token::Uint(U256::from(1u64)),
+            Token::Uint(U256::from(1_000_000u64)),
+            Token::Uint(U256::from(1u32)),
+            Token::Uint(U256::from(150_000u64)),
+        ]);
+
+        // Responses are consumed in reverse order; the eth_call is never reached.
+        mock.push(json!({"accessList": [], "gasUsed": "0x5208"})).unwrap(); // create_access_list
+        mock.push::<String, _>("0x5208".to_string()).unwrap(); // estimate_gas -> 21000
+        mock.push::<String, _>(format!("0x{}", hex::encode(&reference_quote_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&quote_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&symbol_data)))
+            .unwrap();
+        mock.push::<String, _>(format!("0x{}", hex::encode(&decimals_data)))
+            .unwrap();
+
+        let params = SwapTokensParams {
+            from_token: format!("{:#x}", Address::from_low_u64_be(1)),
+            to_token: format!("{:#x}", Address::from_low_u64_be(2)),
+            amount_in_wei: "1000".to_string(),
+            slippage_bps: 100,
+            fee: 3_000,
+            recipient: None,
+            sqrt_price_limit: None,
+            price_limit: None,
+            gas_limit: Some(20_000),
+            compliance_override: false,
+            block: None,
+            debug: false,
+            memo: None,
+            tags: Vec::new(),
+            trace_on_failure: false,
+            quote_id: None,
+        };
+
+        let err = simulate_swap(
+            provider,
+            wallet,
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            params,
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AppError::Swap(msg) if msg.contains("below the estimated")));
+    }
+
     /// Talks to the real network using credentials from `.env`.
     /// Run manually: `cargo test simulate_swap_real_network_smoke -- --ignored`
     #[ignore]
@@ -328,8 +1053,15 @@ mod tests {
             params.amount_in_wei
         );
 
-        let sim_out = simulate_swap(provider, wallet.clone(), from_token, to_token, params)
-            .await
+        let sim_out = simulate_swap(
+            provider,
+            wallet.clone(),
+            from_token,
+            to_token,
+            params,
+            SwapSettings::default(),
+        )
+        .await
             .expect("simulate_swap failed");
 
         assert!(
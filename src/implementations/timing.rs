@@ -0,0 +1,103 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use tracing::debug;
+
+use crate::types::TimingsOut;
+
+/// Pipeline stage whose RPC latency is reported separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Chainlink,
+    Quoter,
+    EstimateGas,
+    Call,
+}
+
+impl Stage {
+    fn as_str(self) -> &'static str {
+        match self {
+            Stage::Chainlink => "chainlink",
+            Stage::Quoter => "quoter",
+            Stage::EstimateGas => "estimate_gas",
+            Stage::Call => "call",
+        }
+    }
+}
+
+/// Accumulates time spent per backend stage while a price or swap pipeline runs.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    chainlink: Option<Duration>,
+    quoter: Option<Duration>,
+    estimate_gas: Option<Duration>,
+    call: Option<Duration>,
+}
+
+impl Timings {
+    /// Await `fut`, adding its wall time to `stage` and emitting a debug event.
+    pub async fn time<F: Future>(&mut self, stage: Stage, fut: F) -> F::Output {
+        let started = Instant::now();
+        let output = fut.await;
+        let elapsed = started.elapsed();
+        debug!(
+            stage = stage.as_str(),
+            elapsed_ms = elapsed.as_millis() as u64,
+            "backend stage finished"
+        );
+
+        let slot = match stage {
+            Stage::Chainlink => &mut self.chainlink,
+            Stage::Quoter => &mut self.quoter,
+            Stage::EstimateGas => &mut self.estimate_gas,
+            Stage::Call => &mut self.call,
+        };
+        *slot = Some(slot.unwrap_or_default() + elapsed);
+        output
+    }
+
+    /// Add the stage times `other` recorded, e.g. for a branch that ran concurrently.
+    pub fn absorb(&mut self, other: &Timings) {
+        let add = |slot: &mut Option<Duration>, extra: Option<Duration>| {
+            if let Some(extra) = extra {
+                *slot = Some(slot.unwrap_or_default() + extra);
+            }
+        };
+        add(&mut self.chainlink, other.chainlink);
+        add(&mut self.quoter, other.quoter);
+        add(&mut self.estimate_gas, other.estimate_gas);
+        add(&mut self.call, other.call);
+    }
+
+    /// Stage breakdown with `total` measured by the caller around the whole request.
+    pub fn finish(&self, total: Duration) -> TimingsOut {
+        let ms = |duration: Option<Duration>| duration.map(|d| d.as_millis() as u64);
+        TimingsOut {
+            chainlink_ms: ms(self.chainlink),
+            quoter_ms: ms(self.quoter),
+            estimate_gas_ms: ms(self.estimate_gas),
+            call_ms: ms(self.call),
+            total_ms: total.as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn accumulates_per_stage() {
+        let mut timings = Timings::default();
+        let value = timings.time(Stage::Quoter, async { 7 }).await;
+        timings.time(Stage::Quoter, async {}).await;
+        assert_eq!(value, 7);
+
+        let out = timings.finish(Duration::from_millis(12));
+        assert!(out.quoter_ms.is_some());
+        assert_eq!(out.chainlink_ms, None);
+        assert_eq!(out.total_ms, 12);
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, Bytes, H256},
+    utils::id,
+};
+use ethers_contract::abigen;
+use once_cell::sync::Lazy;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::erc20,
+    types::{TokenRiskFinding, TokenRiskOut},
+};
+
+abigen!(
+    Ownable,
+    r#"[
+        function owner() view returns (address)
+    ]"#
+);
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`.
+static EIP1967_IMPLEMENTATION_SLOT: Lazy<H256> = Lazy::new(|| {
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc"
+        .parse()
+        .unwrap()
+});
+
+/// EVM `PUSH4` opcode; dispatchers compare calldata selectors against `PUSH4` immediates.
+const PUSH4: u8 = 0x63;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+/// Findings about the token's own behaviour that a renounced owner does not defuse.
+const OWNERLESS_CATEGORIES: &[&str] = &["non_standard", "decimals_mismatch"];
+
+/// Function signatures whose presence hints at transfer restrictions or privileged control.
+const RISKY_SIGNATURES: &[(&str, &str, Severity)] = &[
+    ("blacklist", "blacklist(address)", Severity::High),
+    ("blacklist", "addBlackList(address)", Severity::High),
+    ("blacklist", "addToBlacklist(address)", Severity::High),
+    ("blacklist", "setBlacklist(address,bool)", Severity::High),
+    ("blacklist", "isBlackListed(address)", Severity::Medium),
+    ("blacklist", "isBlacklisted(address)", Severity::Medium),
+    ("pausable", "pause()", Severity::Medium),
+    ("pausable", "unpause()", Severity::Medium),
+    ("owner_mint", "mint(address,uint256)", Severity::High),
+    ("owner_mint", "mint(uint256)", Severity::High),
+    ("max_tx", "setMaxTxAmount(uint256)", Severity::Medium),
+    ("max_tx", "setMaxTxPercent(uint256)", Severity::Medium),
+    ("max_tx", "setMaxWalletSize(uint256)", Severity::Medium),
+    ("max_tx", "_maxTxAmount()", Severity::Low),
+    ("max_tx", "maxTransactionAmount()", Severity::Low),
+    (
+        "fee_on_transfer",
+        "setTaxFeePercent(uint256)",
+        Severity::High,
+    ),
+    ("fee_on_transfer", "setFee(uint256)", Severity::High),
+    (
+        "fee_on_transfer",
+        "setFees(uint256,uint256)",
+        Severity::High,
+    ),
+    ("fee_on_transfer", "_taxFee()", Severity::Medium),
+    ("trading_switch", "enableTrading()", Severity::Medium),
+    (
+        "trading_switch",
+        "setTradingEnabled(bool)",
+        Severity::Medium,
+    ),
+    ("upgradeable", "upgradeTo(address)", Severity::Medium),
+    (
+        "upgradeable",
+        "upgradeToAndCall(address,bytes)",
+        Severity::Medium,
+    ),
+];
+
+/// Fetch token bytecode (following EIP-1967 proxies) and flag risky functions and ownership.
+pub async fn assess_token<M>(
+    provider: Arc<M>,
+    token: Address,
+    listed_decimals: Option<u8>,
+) -> AppResult<TokenRiskOut>
+where
+    M: Middleware + 'static,
+{
+    let code = provider
+        .get_code(token, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to fetch token bytecode: {err}")))?;
+    if code.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "{token:#x} has no contract code"
+        )));
+    }
+
+    let mut findings = scan_bytecode(&code);
+
+    let implementation = read_eip1967_implementation(provider.clone(), token).await?;
+    if let Some(implementation) = implementation {
+        findings.push(TokenRiskFinding {
+            category: "upgradeable".to_string(),
+            detail: format!("EIP-1967 proxy pointing at {implementation:#x}"),
+            severity: Severity::Medium.as_str().to_string(),
+        });
+        let implementation_code = provider
+            .get_code(implementation, None)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to fetch implementation code: {err}")))?;
+        findings.extend(scan_bytecode(&implementation_code));
+    }
+
+    // Ownership only matters when privileged functions exist; a renounced owner defuses them.
+    let owner = Ownable::new(token, provider.clone())
+        .owner()
+        .call()
+        .await
+        .ok();
+    let owner_renounced = owner.map(|owner| owner.is_zero());
+
+    let (symbol, decimals) = match erc20::fetch_metadata(provider, token).await {
+        Ok(meta) => {
+            // A token lying about decimals skews every formatted amount shown to the user.
+            if let Some(listed) = listed_decimals
+                && listed != meta.decimals
+            {
+                findings.push(TokenRiskFinding {
+                    category: "decimals_mismatch".to_string(),
+                    detail: format!(
+                        "decimals() returns {} but token lists say {listed}",
+                        meta.decimals
+                    ),
+                    severity: Severity::High.as_str().to_string(),
+                });
+            }
+            (Some(meta.symbol), Some(meta.decimals as u32))
+        }
+        Err(_) => {
+            findings.push(TokenRiskFinding {
+                category: "non_standard".to_string(),
+                detail: "decimals() reverted; token may not be ERC-20 compliant".to_string(),
+                severity: Severity::High.as_str().to_string(),
+            });
+            (None, None)
+        }
+    };
+
+    let risk_level = overall_risk(&findings, owner_renounced.unwrap_or(false));
+
+    Ok(TokenRiskOut {
+        token: format!("{token:#x}"),
+        symbol,
+        decimals,
+        proxy_implementation: implementation.map(|addr| format!("{addr:#x}")),
+        owner: owner.map(|addr| format!("{addr:#x}")),
+        owner_renounced,
+        risk_level: risk_level.as_str().to_string(),
+        findings,
+        warnings: Vec::new(),
+    })
+}
+
+/// Look for `PUSH4 <selector>` sequences of known risky functions in raw bytecode.
+pub fn scan_bytecode(code: &Bytes) -> Vec<TokenRiskFinding> {
+    let code = code.as_ref();
+    RISKY_SIGNATURES
+        .iter()
+        .filter(|(_, signature, _)| {
+            let selector = id(signature);
+            code.windows(5)
+                .any(|window| window[0] == PUSH4 && window[1..] == selector)
+        })
+        .map(|(category, signature, severity)| TokenRiskFinding {
+            category: category.to_string(),
+            detail: format!("exposes {signature}"),
+            severity: severity.as_str().to_string(),
+        })
+        .collect()
+}
+
+async fn read_eip1967_implementation<M>(
+    provider: Arc<M>,
+    token: Address,
+) -> AppResult<Option<Address>>
+where
+    M: Middleware + 'static,
+{
+    let slot = provider
+        .get_storage_at(token, *EIP1967_IMPLEMENTATION_SLOT, None)
+        .await
+        .map_err(|err| AppError::Rpc(format!("failed to read proxy slot: {err}")))?;
+    let implementation = Address::from_slice(&slot.as_bytes()[12..]);
+    Ok((!implementation.is_zero()).then_some(implementation))
+}
+
+fn overall_risk(findings: &[TokenRiskFinding], owner_renounced: bool) -> Severity {
+    // Privileged hooks cannot be called once ownership is renounced (proxies aside).
+    let upgradeable = findings.iter().any(|f| f.category == "upgradeable");
+    let defused = owner_renounced && !upgradeable;
+
+    findings
+        .iter()
+        .map(|finding| {
+            let severity = match finding.severity.as_str() {
+                "high" => Severity::High,
+                "medium" => Severity::Medium,
+                _ => Severity::Low,
+            };
+            if defused && !OWNERLESS_CATEGORIES.contains(&finding.category.as_str()) {
+                severity.min(Severity::Low)
+            } else {
+                severity
+            }
+        })
+        .max()
+        .unwrap_or(Severity::Low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{Token, encode},
+        providers::Provider,
+    };
+
+    fn bytecode_with(signatures: &[&str]) -> Bytes {
+        let mut code = vec![0x60, 0x80, 0x60, 0x40, 0x52];
+        for signature in signatures {
+            code.push(PUSH4);
+            code.extend_from_slice(&id(signature));
+            code.push(0x14); // EQ
+        }
+        code.into()
+    }
+
+    #[test]
+    fn scan_flags_known_selectors() {
+        let code = bytecode_with(&[
+            "pause()",
+            "addBlackList(address)",
+            "transfer(address,uint256)",
+        ]);
+        let findings = scan_bytecode(&code);
+        let categories: Vec<_> = findings.iter().map(|f| f.category.as_str()).collect();
+        assert_eq!(categories, vec!["blacklist", "pausable"]);
+    }
+
+    #[test]
+    fn selector_without_push4_is_ignored() {
+        let mut code = vec![0x00];
+        code.extend_from_slice(&id("pause()"));
+        assert!(scan_bytecode(&code.into()).is_empty());
+    }
+
+    #[test]
+    fn renounced_owner_downgrades_risk() {
+        let findings = scan_bytecode(&bytecode_with(&["mint(address,uint256)"]));
+        assert_eq!(overall_risk(&findings, false), Severity::High);
+        assert_eq!(overall_risk(&findings, true), Severity::Low);
+    }
+
+    #[test]
+    fn renounced_owner_keeps_decimals_mismatch() {
+        let findings = vec![TokenRiskFinding {
+            category: "decimals_mismatch".to_string(),
+            detail: "decimals() returns 18 but token lists say 6".to_string(),
+            severity: Severity::High.as_str().to_string(),
+        }];
+        assert_eq!(overall_risk(&findings, true), Severity::High);
+    }
+
+    #[tokio::test]
+    async fn assess_token_reports_findings_and_owner() {
+        let (provider, mock) = Provider::mocked();
+        let code = bytecode_with(&["mint(address,uint256)", "setMaxTxAmount(uint256)"]);
+        let owner = Address::from_low_u64_be(0x0a);
+
+        // Responses are consumed in reverse order.
+        mock.push::<String, _>(format!(
+            "0x{}",
+            hex::encode(encode(&[Token::String("RISK".into())]))
+        ))
+        .unwrap(); // symbol
+        mock.push::<String, _>(format!(
+            "0x{}",
+            hex::encode(encode(&[Token::Uint(18u8.into())]))
+        ))
+        .unwrap(); // decimals
+        mock.push::<String, _>(format!(
+            "0x{}",
+            hex::encode(encode(&[Token::Address(owner)]))
+        ))
+        .unwrap(); // owner
+        mock.push::<H256, _>(H256::zero()).unwrap(); // implementation slot
+        mock.push::<Bytes, _>(code).unwrap(); // get_code
+
+        let report = assess_token(Arc::new(provider), Address::from_low_u64_be(0x77), None)
+            .await
+            .unwrap();
+
+        assert_eq!(report.symbol.as_deref(), Some("RISK"));
+        assert_eq!(report.decimals, Some(18));
+        assert_eq!(report.owner_renounced, Some(false));
+        assert!(report.proxy_implementation.is_none());
+        assert_eq!(report.risk_level, "high");
+        assert_eq!(report.findings.len(), 2);
+    }
+}
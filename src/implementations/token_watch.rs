@@ -0,0 +1,235 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, Filter, H256, Log, U256},
+};
+use tokio::time::{Instant, sleep};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        deposits::{self, MAX_DEPOSIT_SCAN_BLOCKS, TRANSFER_EVENT},
+        price::TokenInfo,
+    },
+    progress::ProgressReporter,
+    types::{TokenTransferOut, TokenWatchOut, TransferDirection},
+};
+
+/// What to watch: `Transfer` events of `token` on the `direction` side of `address`.
+#[derive(Debug, Clone, Copy)]
+pub struct TransferFilter<'a> {
+    pub token: &'a TokenInfo,
+    pub address: Address,
+    pub direction: TransferDirection,
+}
+
+/// Poll new blocks for transfers matching `filter` until `window` elapses or `max_results` have
+/// been seen, handing each to `on_transfer` as it is found. Scanning starts at `from_block`, or
+/// at the next block when omitted. Each poll reports the seconds elapsed of the window to
+/// `progress`.
+#[allow(clippy::too_many_arguments)]
+pub async fn watch_token<M>(
+    provider: Arc<M>,
+    filter: TransferFilter<'_>,
+    from_block: Option<u64>,
+    window: Duration,
+    poll_interval: Duration,
+    max_results: usize,
+    progress: &ProgressReporter,
+    mut on_transfer: impl FnMut(&TokenTransferOut),
+) -> AppResult<TokenWatchOut>
+where
+    M: Middleware + 'static,
+{
+    let started = Instant::now();
+    let deadline = started + window;
+    let mut cursor = match from_block {
+        Some(number) => number,
+        None => deposits::head_block(&provider).await? + 1,
+    };
+    let first_block = cursor;
+    let mut transfers = Vec::new();
+    let mut truncated = false;
+
+    loop {
+        let head = deposits::head_block(&provider).await?;
+        while cursor <= head && transfers.len() < max_results {
+            let to_block = head.min(cursor + MAX_DEPOSIT_SCAN_BLOCKS - 1);
+            let found = scan_transfers(provider.clone(), filter, cursor, to_block).await?;
+            for transfer in found {
+                if transfers.len() == max_results {
+                    truncated = true;
+                    break;
+                }
+                on_transfer(&transfer);
+                transfers.push(transfer);
+            }
+            cursor = to_block + 1;
+        }
+        progress.report(
+            started.elapsed().as_secs(),
+            Some(window.as_secs()),
+            format!(
+                "{} {} transfers up to block {}",
+                transfers.len(),
+                filter.token.symbol,
+                cursor - 1
+            ),
+        );
+
+        if transfers.len() >= max_results || Instant::now() + poll_interval > deadline {
+            return Ok(TokenWatchOut {
+                token: format!("{:#x}", filter.token.address),
+                symbol: filter.token.symbol.clone(),
+                address: format!("{:#x}", filter.address),
+                direction: filter.direction,
+                from_block: first_block,
+                last_block: cursor - 1,
+                transfers,
+                truncated,
+            });
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+/// Transfers matching `filter` in `[from_block, to_block]`, oldest first.
+pub async fn scan_transfers<M>(
+    provider: Arc<M>,
+    filter: TransferFilter<'_>,
+    from_block: u64,
+    to_block: u64,
+) -> AppResult<Vec<TokenTransferOut>>
+where
+    M: Middleware + 'static,
+{
+    let base = Filter::new()
+        .address(filter.token.address)
+        .event(TRANSFER_EVENT)
+        .from_block(from_block)
+        .to_block(to_block);
+    let topic = H256::from(filter.address);
+    // Topics are ANDed, so each side of the address needs its own query.
+    let mut queries = Vec::new();
+    if filter.direction != TransferDirection::Out {
+        queries.push(base.clone().topic2(topic));
+    }
+    if filter.direction != TransferDirection::In {
+        queries.push(base.topic1(topic));
+    }
+
+    let mut logs: Vec<Log> = Vec::new();
+    for query in &queries {
+        let found = provider
+            .get_logs(query)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to fetch transfer logs: {err}")))?;
+        for log in found {
+            // A transfer to itself matches both queries.
+            if !logs.iter().any(|seen| same_log(seen, &log)) {
+                logs.push(log);
+            }
+        }
+    }
+    logs.sort_by_key(|log| (log.block_number, log.log_index));
+    Ok(logs
+        .iter()
+        .filter_map(|log| decode_transfer(log, filter))
+        .collect())
+}
+
+fn same_log(a: &Log, b: &Log) -> bool {
+    a.transaction_hash == b.transaction_hash && a.log_index == b.log_index
+}
+
+fn decode_transfer(log: &Log, filter: TransferFilter<'_>) -> Option<TokenTransferOut> {
+    // ERC-721 transfers share the signature but index the token id instead of carrying data.
+    if log.topics.len() != 3 || log.data.len() < 32 {
+        return None;
+    }
+    let from = Address::from(log.topics[1]);
+    let to = Address::from(log.topics[2]);
+    let amount = U256::from_big_endian(&log.data[..32]);
+    Some(TokenTransferOut {
+        direction: if to == filter.address {
+            TransferDirection::In
+        } else {
+            TransferDirection::Out
+        },
+        from: format!("{from:#x}"),
+        to: format!("{to:#x}"),
+        amount_raw: amount.to_string(),
+        amount: balance::format_with_decimals(&amount, filter.token.decimals as u32),
+        block_number: log
+            .block_number
+            .map(|number| number.as_u64())
+            .unwrap_or_default(),
+        tx_hash: log.transaction_hash.map(|hash| format!("{hash:#x}")),
+        log_index: log.log_index.map(|index| index.as_u64()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{providers::Provider, types::Bytes, utils::keccak256};
+
+    fn transfer_log(token: Address, from: Address, to: Address, raw: u64, index: u64) -> Log {
+        let mut amount = [0u8; 32];
+        U256::from(raw).to_big_endian(&mut amount);
+        Log {
+            address: token,
+            topics: vec![
+                H256::from(keccak256(TRANSFER_EVENT)),
+                from.into(),
+                to.into(),
+            ],
+            data: Bytes::from(amount.to_vec()),
+            block_number: Some(101u64.into()),
+            transaction_hash: Some(H256::repeat_byte(index as u8)),
+            log_index: Some(index.into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_merges_both_directions_in_order() {
+        let treasury = Address::from_low_u64_be(0xaa);
+        let other = Address::from_low_u64_be(0xbb);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(0xcc), 6);
+        let inflow = transfer_log(usdc.address, other, treasury, 2_500_000, 3);
+        let outflow = transfer_log(usdc.address, treasury, other, 1_000_000, 1);
+        let to_self = transfer_log(usdc.address, treasury, treasury, 5, 7);
+
+        let (provider, mock) = Provider::mocked();
+        // Responses are consumed in reverse order: the `out` query answers after the `in` one.
+        mock.push::<Vec<Log>, _>(vec![outflow, to_self.clone()])
+            .unwrap();
+        mock.push::<Vec<Log>, _>(vec![inflow, to_self]).unwrap();
+
+        let filter = TransferFilter {
+            token: &usdc,
+            address: treasury,
+            direction: TransferDirection::Both,
+        };
+        let transfers = scan_transfers(Arc::new(provider), filter, 100, 102)
+            .await
+            .unwrap();
+
+        let seen: Vec<(TransferDirection, &str)> = transfers
+            .iter()
+            .map(|transfer| (transfer.direction, transfer.amount.as_str()))
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                (TransferDirection::Out, "1"),
+                (TransferDirection::In, "2.5"),
+                (TransferDirection::In, "0.000005"),
+            ]
+        );
+        assert_eq!(transfers[1].from, format!("{other:#x}"));
+    }
+}
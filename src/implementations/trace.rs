@@ -0,0 +1,242 @@
+use ethers::{
+    providers::{Middleware, MiddlewareError},
+    types::{
+        CallFrame, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingCallOptions,
+        GethDebugTracingOptions, GethTrace, GethTraceFrame, NameOrAddress,
+        transaction::eip2718::TypedTransaction,
+    },
+};
+use tracing::warn;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::revert,
+    types::{TraceCallOut, TraceFrameOut},
+};
+
+/// JSON-RPC code nodes return for a method they do not expose.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Run `tx` through `debug_traceCall` with the call tracer and return the pruned call tree.
+///
+/// Unless `full` is set, a frame's children are only kept for the root and for frames with a
+/// revert somewhere below them; other subtrees collapse into `omitted_calls`.
+pub async fn trace_call<M: Middleware>(
+    provider: &M,
+    tx: &TypedTransaction,
+    full: bool,
+) -> AppResult<TraceCallOut> {
+    let options = GethDebugTracingCallOptions {
+        tracing_options: GethDebugTracingOptions {
+            tracer: Some(GethDebugTracerType::BuiltInTracer(
+                GethDebugBuiltInTracerType::CallTracer,
+            )),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let trace = provider
+        .debug_trace_call(tx.clone(), None, options)
+        .await
+        .map_err(|err| {
+            let unsupported = err
+                .as_error_response()
+                .is_some_and(|response| response.code == METHOD_NOT_FOUND);
+            if unsupported {
+                AppError::Rpc("the configured endpoint does not support debug_traceCall".into())
+            } else {
+                AppError::Rpc(format!("debug_traceCall failed: {err}"))
+            }
+        })?;
+    let frame = match trace {
+        GethTrace::Known(GethTraceFrame::CallTracer(frame)) => frame,
+        GethTrace::Unknown(value) => serde_json::from_value::<CallFrame>(value).map_err(|err| {
+            AppError::Rpc(format!("debug_traceCall returned no call tree: {err}"))
+        })?,
+        GethTrace::Known(_) => {
+            return Err(AppError::Rpc(
+                "debug_traceCall returned no call tree".into(),
+            ));
+        }
+    };
+
+    let total_calls = count_frames(&frame);
+    let reverted = frame.error.is_some();
+    let gas_used = frame.gas_used.to_string();
+    let root = prune(frame, 0, full);
+    Ok(TraceCallOut {
+        block_number: None,
+        reverted,
+        revert_reason: first_revert_reason(&root),
+        gas_used,
+        total_calls,
+        root,
+        warnings: Vec::new(),
+    })
+}
+
+/// Attach the call tree of `tx` to `error` when it is a revert. Other errors, and endpoints that
+/// cannot trace, leave `error` unchanged.
+pub async fn attach_trace<M: Middleware>(
+    provider: &M,
+    tx: &TypedTransaction,
+    error: AppError,
+) -> AppError {
+    if !matches!(error, AppError::Reverted { .. }) {
+        return error;
+    }
+    let trace = match trace_call(provider, tx, false).await {
+        Ok(trace) => trace,
+        Err(err) => {
+            warn!("could not trace failed simulation: {err}");
+            return error;
+        }
+    };
+    match serde_json::to_value(&trace) {
+        Ok(trace) => AppError::Traced {
+            error: Box::new(error),
+            trace,
+        },
+        Err(_) => error,
+    }
+}
+
+fn prune(frame: CallFrame, depth: usize, full: bool) -> TraceFrameOut {
+    let children = frame.calls.unwrap_or_default();
+    let expand = full || depth == 0 || children.iter().any(has_revert);
+    let (calls, omitted_calls) = if expand {
+        let calls = children
+            .into_iter()
+            .map(|child| prune(child, depth + 1, full))
+            .collect();
+        (calls, 0)
+    } else {
+        (Vec::new(), children.iter().map(count_frames).sum())
+    };
+
+    let reverted = frame.error.is_some();
+    let revert_reason = match &frame.output {
+        Some(output) if reverted && !output.is_empty() => Some(revert::decode_revert(output)),
+        _ => None,
+    };
+    TraceFrameOut {
+        kind: frame.typ,
+        from: format!("{:#x}", frame.from),
+        to: frame.to.map(|to| match to {
+            NameOrAddress::Address(address) => format!("{address:#x}"),
+            NameOrAddress::Name(name) => name,
+        }),
+        value_wei: frame.value.map(|value| value.to_string()),
+        gas_used: frame.gas_used.to_string(),
+        selector: (frame.input.len() >= 4).then(|| format!("0x{}", hex::encode(&frame.input[..4]))),
+        reverted,
+        error: frame.error,
+        revert_reason,
+        calls,
+        omitted_calls,
+    }
+}
+
+fn has_revert(frame: &CallFrame) -> bool {
+    frame.error.is_some() || frame.calls.iter().flatten().any(has_revert)
+}
+
+fn count_frames(frame: &CallFrame) -> usize {
+    1 + frame
+        .calls
+        .iter()
+        .flatten()
+        .map(count_frames)
+        .sum::<usize>()
+}
+
+/// Decoded reason of the first reverted frame with revert data, outermost first.
+fn first_revert_reason(frame: &TraceFrameOut) -> Option<String> {
+    frame
+        .revert_reason
+        .clone()
+        .or_else(|| frame.calls.iter().find_map(first_revert_reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{self, Token},
+        providers::{JsonRpcError, MockResponse, Provider},
+        types::TransactionRequest,
+    };
+    use serde_json::json;
+
+    fn frame(to: &str, input: &str, calls: Vec<serde_json::Value>) -> serde_json::Value {
+        json!({
+            "type": "CALL",
+            "from": "0x0000000000000000000000000000000000000001",
+            "to": to,
+            "gas": "0x30d40",
+            "gasUsed": "0x5208",
+            "input": input,
+            "calls": calls,
+        })
+    }
+
+    #[tokio::test]
+    async fn prunes_successful_subtrees_and_highlights_reverts() {
+        let (provider, mock) = Provider::mocked();
+        let reason = [
+            vec![0x08, 0xc3, 0x79, 0xa0],
+            abi::encode(&[Token::String("STF".into())]),
+        ]
+        .concat();
+        let token = "0x00000000000000000000000000000000000000aa";
+        let mut failing = frame(token, "0x23b872dd", vec![]);
+        failing["error"] = json!("execution reverted");
+        failing["output"] = json!(format!("0x{}", hex::encode(&reason)));
+        let mut pool = frame(
+            "0x00000000000000000000000000000000000000bb",
+            "0x128acb08",
+            vec![failing],
+        );
+        pool["error"] = json!("execution reverted");
+        let quiet = frame(token, "0x70a08231", vec![frame(token, "0x", vec![])]);
+        let mut root = frame(
+            "0x00000000000000000000000000000000000000cc",
+            "0x3593564c",
+            vec![quiet, pool],
+        );
+        root["error"] = json!("execution reverted");
+        mock.push::<serde_json::Value, _>(root).unwrap();
+
+        let tx: TypedTransaction = TransactionRequest::new().into();
+        let trace = trace_call(&provider, &tx, false).await.unwrap();
+        assert!(trace.reverted);
+        assert_eq!(trace.total_calls, 5);
+        assert_eq!(
+            trace.revert_reason.as_deref(),
+            Some("reverted: STF (token transferFrom failed: check balance and allowance)")
+        );
+        let quiet = &trace.root.calls[0];
+        assert!(!quiet.reverted && quiet.calls.is_empty());
+        assert_eq!(quiet.omitted_calls, 1);
+        let failing = &trace.root.calls[1].calls[0];
+        assert!(failing.reverted);
+        assert_eq!(failing.selector.as_deref(), Some("0x23b872dd"));
+        assert_eq!(failing.to.as_deref(), Some(token));
+    }
+
+    #[tokio::test]
+    async fn reports_endpoints_without_debug_namespace() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: METHOD_NOT_FOUND,
+            message: "the method debug_traceCall does not exist/is not available".into(),
+            data: None,
+        }));
+        let tx: TypedTransaction = TransactionRequest::new().into();
+        let err = trace_call(&provider, &tx, false).await.unwrap_err();
+        assert!(err.to_string().contains("does not support debug_traceCall"));
+
+        let plain = attach_trace(&provider, &tx, AppError::Swap("x".into())).await;
+        assert!(matches!(plain, AppError::Swap(_)));
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, TransactionRequest, U256, transaction::eip2718::TypedTransaction},
+};
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{
+        balance,
+        erc20::{self, Erc20Token},
+        revert,
+        swap::{SwapSettings, apply_gas_buffer},
+    },
+    types::TransferFromOut,
+};
+
+/// Simulate `spender` moving `amount` of `token` from `owner` to `recipient` with `transferFrom`.
+///
+/// The allowance `owner` granted `spender` and `owner`'s balance are checked up front so the
+/// caller gets a clear error instead of an opaque revert from the dry-run.
+#[allow(clippy::too_many_arguments)]
+pub async fn simulate_transfer_from<M>(
+    provider: Arc<M>,
+    spender: Address,
+    token: Address,
+    decimals: u8,
+    owner: Address,
+    recipient: Address,
+    amount: U256,
+    settings: SwapSettings,
+) -> AppResult<TransferFromOut>
+where
+    M: Middleware + 'static,
+{
+    if amount.is_zero() {
+        return Err(AppError::InvalidInput(
+            "amount_wei must be greater than zero".into(),
+        ));
+    }
+
+    let allowance = erc20::fetch_allowance(provider.clone(), token, owner, spender).await?;
+    if allowance < amount {
+        return Err(AppError::Wallet(format!(
+            "allowance from {owner:#x} to {spender:#x} is {allowance}, below the requested {amount}"
+        )));
+    }
+    let owner_balance = erc20::fetch_balance_of(provider.clone(), token, owner).await?;
+    if owner_balance < amount {
+        return Err(AppError::Wallet(format!(
+            "{owner:#x} holds {owner_balance}, below the requested {amount}"
+        )));
+    }
+
+    let calldata = Erc20Token::new(token, provider.clone())
+        .transfer_from(owner, recipient, amount)
+        .calldata()
+        .ok_or_else(|| AppError::Internal("failed to build transferFrom calldata".into()))?;
+    let mut tx: TypedTransaction = TransactionRequest::new()
+        .from(spender)
+        .to(token)
+        .data(calldata.clone())
+        .value(U256::zero())
+        .into();
+
+    let gas_estimate = provider.estimate_gas(&tx, None).await.map_err(|err| {
+        revert::annotate(
+            AppError::Wallet(format!("transferFrom gas estimation failed: {err}")),
+            &err,
+        )
+    })?;
+    let gas_limit = apply_gas_buffer(gas_estimate, settings.gas_buffer_percent);
+    tx.set_gas(gas_limit);
+    provider.call(&tx, None).await.map_err(|err| {
+        revert::annotate(
+            AppError::Wallet(format!("transferFrom simulation failed: {err}")),
+            &err,
+        )
+    })?;
+
+    Ok(TransferFromOut {
+        block_number: None,
+        token: format!("{token:#x}"),
+        from: format!("{owner:#x}"),
+        from_ens: None,
+        to: format!("{recipient:#x}"),
+        to_ens: None,
+        amount_wei: amount.to_string(),
+        amount: balance::format_with_decimals(&amount, decimals as u32),
+        allowance_after: (allowance - amount).to_string(),
+        calldata_hex: format!("0x{}", hex::encode(&calldata)),
+        gas_estimate: gas_estimate.to_string(),
+        gas_limit: gas_limit.to_string(),
+        warnings: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{self, Token},
+        providers::Provider,
+    };
+
+    fn encoded(value: u64) -> String {
+        format!(
+            "0x{}",
+            hex::encode(abi::encode(&[Token::Uint(U256::from(value))]))
+        )
+    }
+
+    #[tokio::test]
+    async fn simulates_within_allowance() {
+        let (provider, mock) = Provider::mocked();
+
+        // Responses are consumed in reverse order.
+        mock.push::<String, _>(encoded(1)).unwrap(); // eth_call -> true
+        mock.push::<U256, _>(U256::from(50_000u64)).unwrap(); // estimate_gas
+        mock.push::<String, _>(encoded(5_000_000)).unwrap(); // balanceOf
+        mock.push::<String, _>(encoded(3_000_000)).unwrap(); // allowance
+
+        let out = simulate_transfer_from(
+            Arc::new(provider),
+            Address::from_low_u64_be(0x5),
+            Address::from_low_u64_be(0x7),
+            6,
+            Address::from_low_u64_be(0xa),
+            Address::from_low_u64_be(0xb),
+            U256::from(1_000_000u64),
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.amount, "1");
+        assert_eq!(out.allowance_after, "2000000");
+        assert_eq!(out.gas_limit, "60000");
+        assert!(out.calldata_hex.starts_with("0x23b872dd"));
+    }
+
+    #[tokio::test]
+    async fn rejects_amount_above_allowance() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<String, _>(encoded(10)).unwrap(); // allowance
+
+        let err = simulate_transfer_from(
+            Arc::new(provider),
+            Address::from_low_u64_be(0x5),
+            Address::from_low_u64_be(0x7),
+            18,
+            Address::from_low_u64_be(0xa),
+            Address::from_low_u64_be(0xb),
+            U256::from(11u64),
+            SwapSettings::default(),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(err, AppError::Wallet(msg) if msg.contains("allowance")));
+    }
+}
@@ -1,5 +1,19 @@
+use std::sync::Arc;
+
+use ethers::{
+    abi::{self, Token},
+    providers::Middleware,
+    types::{Address, U256},
+    utils::{get_create2_address_from_hash, keccak256},
+};
 use ethers_contract::abigen;
 
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::price::{UNISWAP_QUOTER_V2, UNISWAP_SWAP_ROUTER, UNISWAP_V3_FACTORY},
+};
+
 // Type-safe bindings for Uniswap V3 helper contracts used for pricing and swaps.
 abigen!(
     UniswapQuoterV2,
@@ -62,3 +76,299 @@ abigen!(
         }
     ]"#
 );
+
+abigen!(
+    UniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) view returns (address pool)
+    ]"#
+);
+
+abigen!(
+    UniswapV3Pool,
+    r#"[
+        function observe(uint32[] secondsAgos) view returns (int56[] tickCumulatives, uint160[] secondsPerLiquidityCumulativeX128s)
+    ]"#
+);
+
+// Both the router and the quoter expose the factory they were deployed against.
+abigen!(
+    UniswapPeriphery,
+    r#"[
+        function factory() view returns (address)
+    ]"#
+);
+
+/// Chains where Uniswap V3 sits at the canonical mainnet addresses with the original SwapRouter.
+const CANONICAL_DEPLOYMENT_CHAINS: [u64; 4] = [1, 10, 137, 42_161];
+
+/// Uniswap V3 contracts used for quoting, routing and pool lookups on the active chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniswapDeployment {
+    pub factory: Address,
+    pub quoter: Address,
+    pub router: Address,
+}
+
+impl Default for UniswapDeployment {
+    fn default() -> Self {
+        Self {
+            factory: *UNISWAP_V3_FACTORY,
+            quoter: *UNISWAP_QUOTER_V2,
+            router: *UNISWAP_SWAP_ROUTER,
+        }
+    }
+}
+
+impl UniswapDeployment {
+    /// Built-in deployment for `chain_id`, if this server knows one.
+    pub fn for_chain(chain_id: u64) -> Option<Self> {
+        CANONICAL_DEPLOYMENT_CHAINS
+            .contains(&chain_id)
+            .then(Self::default)
+    }
+
+    /// Pick the deployment for `chain_id` and confirm it exists on-chain.
+    ///
+    /// `uniswap_factory`/`uniswap_quoter`/`uniswap_router` override the built-in table. A
+    /// missing factory is read from the router's `factory()`, and the router and quoter must
+    /// both report the same factory, so a chain switch fails at startup instead of quoting
+    /// against contracts that are not there.
+    pub async fn resolve<M>(provider: Arc<M>, chain_id: u64, config: &AppConfig) -> AppResult<Self>
+    where
+        M: Middleware + 'static,
+    {
+        let known = Self::for_chain(chain_id);
+        let factory = config_address("uniswap_factory", config.uniswap_factory.as_deref())?
+            .or(known.map(|deployment| deployment.factory));
+        let quoter = config_address("uniswap_quoter", config.uniswap_quoter.as_deref())?
+            .or(known.map(|deployment| deployment.quoter));
+        let router = config_address("uniswap_router", config.uniswap_router.as_deref())?
+            .or(known.map(|deployment| deployment.router));
+        let (Some(quoter), Some(router)) = (quoter, router) else {
+            return Err(AppError::Config(format!(
+                "no Uniswap V3 deployment known for chain {chain_id}; set uniswap_router and \
+                 uniswap_quoter"
+            )));
+        };
+
+        let detected = periphery_factory(provider.clone(), router, "router").await?;
+        if let Some(factory) = factory
+            && factory != detected
+        {
+            return Err(AppError::Config(format!(
+                "router {router:#x} belongs to factory {detected:#x}, not {factory:#x}"
+            )));
+        }
+        let quoter_factory = periphery_factory(provider, quoter, "quoter").await?;
+        if quoter_factory != detected {
+            return Err(AppError::Config(format!(
+                "quoter {quoter:#x} belongs to factory {quoter_factory:#x}, but router \
+                 {router:#x} uses {detected:#x}"
+            )));
+        }
+
+        Ok(Self {
+            factory: detected,
+            quoter,
+            router,
+        })
+    }
+}
+
+fn config_address(name: &str, value: Option<&str>) -> AppResult<Option<Address>> {
+    value
+        .map(|value| {
+            value
+                .trim()
+                .parse::<Address>()
+                .map_err(|_| AppError::Config(format!("{name} is not a valid address: {value}")))
+        })
+        .transpose()
+}
+
+async fn periphery_factory<M>(provider: Arc<M>, contract: Address, role: &str) -> AppResult<Address>
+where
+    M: Middleware + 'static,
+{
+    UniswapPeriphery::new(contract, provider)
+        .factory()
+        .call()
+        .await
+        .map_err(|err| {
+            AppError::Config(format!(
+                "no Uniswap V3 {role} at {contract:#x} on this chain: {err}"
+            ))
+        })
+}
+
+/// `keccak256` of the Uniswap V3 pool creation code, used to derive pool addresses offline.
+const POOL_INIT_CODE_HASH: &str =
+    "e34f199b19b2b4f47f68442619d555527d244f78a3297ea89325f843f87b8b54";
+
+/// CREATE2 address of the `token_a`/`token_b` pool at `fee`, whether or not it is deployed.
+pub fn pool_address(factory: Address, token_a: Address, token_b: Address, fee: u32) -> Address {
+    let (token0, token1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+    let salt = keccak256(abi::encode(&[
+        Token::Address(token0),
+        Token::Address(token1),
+        Token::Uint(U256::from(fee)),
+    ]));
+    let init_code_hash = hex::decode(POOL_INIT_CODE_HASH).expect("valid init code hash");
+    get_create2_address_from_hash(factory, salt, init_code_hash)
+}
+
+/// Ticks a Uniswap V3 pool can reach, either way round.
+pub const MAX_TICK: i32 = 887_272;
+
+/// `1 / sqrt(1.0001)^2^i` as Q128.128 for each bit `i` of a tick, from Uniswap's `TickMath`.
+const TICK_RATIOS: [&str; 19] = [
+    "fff97272373d413259a46990580e213a",
+    "fff2e50f5f656932ef12357cf3c7fdcc",
+    "ffe5caca7e10e4e61c3624eaa0941cd0",
+    "ffcb9843d60f6159c9db58835c926644",
+    "ff973b41fa98c081472e6896dfb254c0",
+    "ff2ea16466c96a3843ec78b326b52861",
+    "fe5dee046a99a2a811c461f1969c3053",
+    "fcbe86c7900a88aedcffc83b479aa3a4",
+    "f987a7253ac413176f2b074cf7815e54",
+    "f3392b0822b70005940c7a398e4b70f3",
+    "e7159475a2c29b7443b29c7fa6e889d9",
+    "d097f3bdfd2022b8845ad8f792aa5825",
+    "a9f746462d870fdf8a65dc1f90e061e5",
+    "70d869a156d2a1b890bb3df62baf32f7",
+    "31be135f97d08fd981231505542fcfa6",
+    "9aa508b5b7a84e1c677de54f3e99bc9",
+    "5d6af8dedb81196699c329225ee604",
+    "2216e584f5fa1ea926041bedfe98",
+    "48a170391f7dc42444e8fa2",
+];
+
+/// `sqrtPriceX96` at `tick`, bit for bit as `TickMath.getSqrtRatioAtTick` computes it.
+pub fn sqrt_ratio_at_tick(tick: i32) -> AppResult<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK.unsigned_abs() {
+        return Err(AppError::Price(format!(
+            "tick {tick} is outside a Uniswap V3 pool's range"
+        )));
+    }
+    let word = |hex: &str| U256::from_str_radix(hex, 16).expect("valid tick ratio");
+    let mut ratio = if abs_tick & 1 != 0 {
+        word("fffcb933bd6fad37aa2d162d1a594001")
+    } else {
+        U256::one() << 128
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate() {
+        if abs_tick & (2 << bit) != 0 {
+            ratio = (ratio * word(factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    // Q128.128 to Q64.96, rounding up so the result is never below the true price.
+    let rounded_up = !(ratio & U256::from(u32::MAX)).is_zero();
+    Ok((ratio >> 32) + U256::from(u8::from(rounded_up)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    fn config(extra: &str) -> AppConfig {
+        toml::from_str(&format!("eth_rpc_url = \"http://localhost:8545\"\n{extra}")).unwrap()
+    }
+
+    fn encoded_address(address: Address) -> String {
+        format!("0x{}", hex::encode(abi::encode(&[Token::Address(address)])))
+    }
+
+    #[tokio::test]
+    async fn detects_factory_from_configured_router() {
+        let (provider, mock) = Provider::mocked();
+        let factory = Address::from_low_u64_be(0xfa);
+        // Responses are consumed in reverse order: router first, then quoter.
+        mock.push::<String, _>(encoded_address(factory)).unwrap();
+        mock.push::<String, _>(encoded_address(factory)).unwrap();
+
+        let config = config(&format!(
+            "uniswap_router = \"{:#x}\"\nuniswap_quoter = \"{:#x}\"",
+            Address::from_low_u64_be(0x10),
+            Address::from_low_u64_be(0x20)
+        ));
+        let deployment = UniswapDeployment::resolve(Arc::new(provider), 8_453, &config)
+            .await
+            .unwrap();
+
+        assert_eq!(deployment.factory, factory);
+        assert_eq!(deployment.router, Address::from_low_u64_be(0x10));
+    }
+
+    #[tokio::test]
+    async fn unknown_chain_without_overrides_is_rejected() {
+        let (provider, _mock) = Provider::mocked();
+        let config = config("");
+        let err = UniswapDeployment::resolve(Arc::new(provider), 8_453, &config)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Config(msg) if msg.contains("8453")));
+    }
+
+    #[tokio::test]
+    async fn mismatched_quoter_is_rejected() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<String, _>(encoded_address(Address::from_low_u64_be(0xbad)))
+            .unwrap();
+        mock.push::<String, _>(encoded_address(*UNISWAP_V3_FACTORY))
+            .unwrap();
+
+        let config = config("");
+        let err = UniswapDeployment::resolve(Arc::new(provider), 1, &config)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Config(msg) if msg.contains("quoter")));
+    }
+
+    #[test]
+    fn pool_address_matches_deployed_pool() {
+        let factory: Address = "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+            .parse()
+            .unwrap();
+        let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            .parse()
+            .unwrap();
+        let weth: Address = "0xC02aaa39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            .parse()
+            .unwrap();
+        let expected: Address = "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"
+            .parse()
+            .unwrap();
+
+        assert_eq!(pool_address(factory, weth, usdc, 500), expected);
+        assert_eq!(pool_address(factory, usdc, weth, 500), expected);
+    }
+
+    #[test]
+    fn tick_math_matches_the_pool_bounds() {
+        // TickMath.MIN_SQRT_RATIO and MAX_SQRT_RATIO, and 2^96 at tick zero.
+        assert_eq!(
+            sqrt_ratio_at_tick(-MAX_TICK).unwrap(),
+            U256::from(4_295_128_739u64)
+        );
+        assert_eq!(
+            sqrt_ratio_at_tick(MAX_TICK).unwrap(),
+            U256::from_dec_str("1461446703485210103287273052203988822378723970342").unwrap()
+        );
+        assert_eq!(sqrt_ratio_at_tick(0).unwrap(), U256::one() << 96);
+        assert_eq!(
+            sqrt_ratio_at_tick(1).unwrap(),
+            U256::from_dec_str("79232123823359799118286999568").unwrap()
+        );
+        assert!(sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+    }
+}
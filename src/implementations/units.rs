@@ -0,0 +1,96 @@
+use ethers::types::U256;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::balance,
+    types::AmountUnit,
+};
+
+impl AmountUnit {
+    /// Whether the unit measures a token (`raw`, `human`) rather than the native coin.
+    pub fn is_token_unit(self) -> bool {
+        matches!(self, AmountUnit::Raw | AmountUnit::Human)
+    }
+
+    /// Decimal places between this unit and base units; `token_decimals` is needed for `human`.
+    fn decimals(self, token_decimals: Option<u32>) -> AppResult<u32> {
+        match self {
+            AmountUnit::Wei | AmountUnit::Raw => Ok(0),
+            AmountUnit::Gwei => Ok(9),
+            AmountUnit::Ether => Ok(18),
+            AmountUnit::Human => token_decimals.ok_or_else(|| {
+                AppError::InvalidInput("converting human token amounts requires token".into())
+            }),
+        }
+    }
+}
+
+/// `amount` in `from` units as base units and as `to` units. Hex input (`0x…`) is read as a
+/// whole number of `from` units, as RPC results are.
+pub fn convert(
+    amount: &str,
+    from: AmountUnit,
+    to: AmountUnit,
+    token_decimals: Option<u32>,
+) -> AppResult<(U256, String)> {
+    if from.is_token_unit() != to.is_token_unit() {
+        return Err(AppError::InvalidInput(format!(
+            "cannot convert {from} to {to}: convert wei, gwei and ether among themselves, and token raw and human amounts among themselves"
+        )));
+    }
+    let amount = amount.trim().replace('_', "");
+    let amount = match amount
+        .strip_prefix("0x")
+        .or_else(|| amount.strip_prefix("0X"))
+    {
+        Some(hex) => U256::from_str_radix(hex, 16)
+            .map_err(|_| AppError::InvalidInput(format!("invalid hex amount: 0x{hex}")))?
+            .to_string(),
+        None => amount,
+    };
+    let base_units = balance::parse_formatted(&amount, from.decimals(token_decimals)?)?;
+    let converted = balance::format_with_decimals(&base_units, to.decimals(token_decimals)?);
+    Ok((base_units, converted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_native_units_exactly() {
+        let (wei, gwei) = convert("1.5", AmountUnit::Ether, AmountUnit::Gwei, None).unwrap();
+        assert_eq!(wei, U256::from(1_500_000_000_000_000_000u64));
+        assert_eq!(gwei, "1500000000");
+        assert_eq!(
+            convert("1", AmountUnit::Wei, AmountUnit::Ether, None)
+                .unwrap()
+                .1,
+            "0.000000000000000001"
+        );
+        assert_eq!(
+            convert("0x3b9aca00", AmountUnit::Wei, AmountUnit::Gwei, None)
+                .unwrap()
+                .1,
+            "1"
+        );
+        // Fractions of a wei do not exist.
+        assert!(convert("0.5", AmountUnit::Gwei, AmountUnit::Wei, None).is_ok());
+        assert!(convert("0.0000000005", AmountUnit::Gwei, AmountUnit::Wei, None).is_err());
+    }
+
+    #[test]
+    fn converts_token_units_with_decimals() {
+        let (raw, human) = convert("2500000", AmountUnit::Raw, AmountUnit::Human, Some(6)).unwrap();
+        assert_eq!(raw, U256::from(2_500_000));
+        assert_eq!(human, "2.5");
+        assert_eq!(
+            convert("1_000.25", AmountUnit::Human, AmountUnit::Raw, Some(6))
+                .unwrap()
+                .1,
+            "1000250000"
+        );
+        assert!(convert("1", AmountUnit::Human, AmountUnit::Raw, None).is_err());
+        assert!(convert("1", AmountUnit::Human, AmountUnit::Wei, Some(6)).is_err());
+    }
+}
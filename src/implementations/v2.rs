@@ -0,0 +1,184 @@
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use ethers_contract::abigen;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    error::{AppError, AppResult},
+    implementations::{balance, price::TokenInfo},
+};
+
+abigen!(
+    UniswapV2Router,
+    r#"[
+        function factory() view returns (address)
+        function getAmountsOut(uint256 amountIn, address[] path) view returns (uint256[] amounts)
+    ]"#
+);
+
+abigen!(
+    UniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) view returns (address pair)
+    ]"#
+);
+
+/// A Uniswap V2 fork (SushiSwap, PancakeSwap, ...) used as a price source when V3 cannot quote.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct V2Venue {
+    pub name: String,
+    pub factory: Address,
+    pub router: Address,
+}
+
+impl FromStr for V2Venue {
+    type Err = AppError;
+
+    /// Parses `name=factory|router`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            AppError::Config(format!(
+                "V2 venues must be name=factory|router, got {value}"
+            ))
+        };
+        let (name, pair) = value.split_once('=').ok_or_else(invalid)?;
+        let (factory, router) = pair.split_once('|').ok_or_else(invalid)?;
+        Ok(Self {
+            name: name.trim().to_string(),
+            factory: factory.trim().parse().map_err(|_| invalid())?,
+            router: router.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl V2Venue {
+    /// Confirm the router is deployed and belongs to the configured factory.
+    pub async fn verify<M>(&self, provider: Arc<M>) -> AppResult<()>
+    where
+        M: Middleware + 'static,
+    {
+        let factory = UniswapV2Router::new(self.router, provider)
+            .factory()
+            .call()
+            .await
+            .map_err(|err| {
+                AppError::Config(format!(
+                    "no {} router at {:#x} on this chain: {err}",
+                    self.name, self.router
+                ))
+            })?;
+        if factory != self.factory {
+            return Err(AppError::Config(format!(
+                "{} router {:#x} belongs to factory {factory:#x}, not {:#x}",
+                self.name, self.router, self.factory
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the factory has created the `a`/`b` pair.
+    pub async fn has_pair<M>(&self, provider: Arc<M>, a: Address, b: Address) -> AppResult<bool>
+    where
+        M: Middleware + 'static,
+    {
+        let pair = UniswapV2Factory::new(self.factory, provider)
+            .get_pair(a, b)
+            .call()
+            .await
+            .map_err(|err| AppError::Rpc(format!("{} getPair failed: {err}", self.name)))?;
+        Ok(!pair.is_zero())
+    }
+
+    /// `quote` received for one whole `base` through the direct pair.
+    pub async fn price<M>(
+        &self,
+        provider: Arc<M>,
+        base: &TokenInfo,
+        quote: &TokenInfo,
+    ) -> AppResult<Decimal>
+    where
+        M: Middleware + 'static,
+    {
+        let amount_in = U256::exp10(base.decimals as usize);
+        let amount_out = self
+            .amount_out(provider, amount_in, base.address, quote.address)
+            .await?;
+        let formatted = balance::format_with_decimals(&amount_out, quote.decimals as u32);
+        Decimal::from_str_exact(&formatted)
+            .map_err(|err| AppError::Price(format!("invalid {} price: {err}", self.name)))
+    }
+
+    /// `token_out` received for `amount_in` of `token_in` through the direct pair.
+    pub async fn amount_out<M>(
+        &self,
+        provider: Arc<M>,
+        amount_in: U256,
+        token_in: Address,
+        token_out: Address,
+    ) -> AppResult<U256>
+    where
+        M: Middleware + 'static,
+    {
+        let amounts = UniswapV2Router::new(self.router, provider)
+            .get_amounts_out(amount_in, vec![token_in, token_out])
+            .call()
+            .await
+            .map_err(|err| AppError::Price(format!("{} quote failed: {err}", self.name)))?;
+        let amount_out = amounts.last().copied().unwrap_or_default();
+        if amount_out.is_zero() {
+            return Err(AppError::Price(format!(
+                "{} returned zero amount out",
+                self.name
+            )));
+        }
+        Ok(amount_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{Token, encode},
+        providers::Provider,
+    };
+
+    #[test]
+    fn parses_env_entries() {
+        let venue: V2Venue = "sushiswap=0x00000000000000000000000000000000000000f1|\
+                              0x00000000000000000000000000000000000000a1"
+            .parse()
+            .unwrap();
+        assert_eq!(venue.name, "sushiswap");
+        assert_eq!(venue.factory, Address::from_low_u64_be(0xf1));
+        assert_eq!(venue.router, Address::from_low_u64_be(0xa1));
+        assert!("sushiswap=0x01".parse::<V2Venue>().is_err());
+    }
+
+    #[tokio::test]
+    async fn prices_through_the_router() {
+        let (provider, mock) = Provider::mocked();
+        let amounts = encode(&[Token::Array(vec![
+            Token::Uint(U256::exp10(18)),
+            Token::Uint(U256::from(2_500_500_000u64)),
+        ])]);
+        mock.push::<String, _>(format!("0x{}", hex::encode(amounts)))
+            .unwrap();
+
+        let venue = V2Venue {
+            name: "sushiswap".into(),
+            factory: Address::from_low_u64_be(0xf1),
+            router: Address::from_low_u64_be(0xa1),
+        };
+        let weth = TokenInfo::new("WETH", Address::from_low_u64_be(1), 18);
+        let usdc = TokenInfo::new("USDC", Address::from_low_u64_be(2), 6);
+        let price = venue.price(Arc::new(provider), &weth, &usdc).await.unwrap();
+
+        assert_eq!(price, Decimal::from_str("2500.5").unwrap());
+    }
+}
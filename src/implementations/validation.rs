@@ -0,0 +1,117 @@
+/// Hex digits in an address after the `0x` prefix.
+const ADDRESS_HEX_DIGITS: usize = 40;
+
+/// Suggestions offered for one unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Hint appended to an "unknown name" error: what is wrong with an attempted address, or the
+/// `known` names closest to `input`. Empty when there is nothing useful to say.
+pub fn explain<'a>(input: &str, known: impl IntoIterator<Item = &'a str>) -> String {
+    if let Some(problem) = address_problem(input) {
+        return format!("; {problem}");
+    }
+    match suggest(input, known).as_slice() {
+        [] => String::new(),
+        [only] => format!("; did you mean {only}?"),
+        several => format!("; did you mean one of {}?", several.join(", ")),
+    }
+}
+
+/// Why `input` is not an address, when it looks like an attempt at one: `0x`-prefixed, or a
+/// long run of hex digits.
+pub fn address_problem(input: &str) -> Option<String> {
+    let (prefix, digits) = match input.get(..2) {
+        Some("0x" | "0X") => (2, &input[2..]),
+        _ if input.len() >= ADDRESS_HEX_DIGITS / 2
+            && input.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            (0, input)
+        }
+        _ => return None,
+    };
+    if let Some((index, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Some(format!(
+            "'{c}' at position {} is not a hex digit; addresses are 0x followed by {ADDRESS_HEX_DIGITS} hex characters",
+            prefix + index
+        ));
+    }
+    (digits.len() != ADDRESS_HEX_DIGITS).then(|| {
+        format!(
+            "addresses are 0x followed by {ADDRESS_HEX_DIGITS} hex characters, got {}",
+            digits.len()
+        )
+    })
+}
+
+/// Up to three `known` names within typo distance of `input`, closest first and spelled as
+/// known. Comparison ignores case; short inputs tolerate one edit, longer ones two.
+pub fn suggest<'a>(input: &str, known: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let input = input.to_uppercase();
+    let max_distance = if input.chars().count() <= 4 { 1 } else { 2 };
+    let mut matches: Vec<(usize, String)> = known
+        .into_iter()
+        .filter(|name| name.to_uppercase() != input)
+        .filter_map(|name| {
+            let distance = edit_distance(&input, &name.to_uppercase());
+            (distance <= max_distance).then(|| (distance, name.to_string()))
+        })
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_near_matches_closest_first() {
+        let known = ["WBTC", "WETH", "USDC", "USDT", "DAI"];
+        assert_eq!(suggest("wbtcc", known), vec!["WBTC"]);
+        assert_eq!(suggest("USDX", known), vec!["USDC", "USDT"]);
+        assert!(suggest("PEPE", known).is_empty());
+        assert_eq!(explain("wbtcc", known), "; did you mean WBTC?");
+        assert_eq!(explain("PEPE", known), "");
+    }
+
+    #[test]
+    fn explains_malformed_addresses() {
+        assert_eq!(
+            address_problem("0x1234").unwrap(),
+            "addresses are 0x followed by 40 hex characters, got 4"
+        );
+        let typo = format!("0x{}g", "a".repeat(39));
+        assert!(
+            address_problem(&typo)
+                .unwrap()
+                .starts_with("'g' at position 41")
+        );
+        assert!(
+            address_problem(&"a".repeat(39))
+                .unwrap()
+                .ends_with("got 39")
+        );
+        assert!(address_problem(&format!("0x{}", "a".repeat(40))).is_none());
+        assert!(address_problem("WETH").is_none());
+    }
+}
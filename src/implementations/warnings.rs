@@ -0,0 +1,212 @@
+use ethers::types::Address;
+
+use crate::{
+    implementations::price::TokenRegistry,
+    types::{MevRiskOut, PriceOut, WarningOut},
+};
+
+/// Age past which a Chainlink round is worth mentioning: the longest heartbeat of the common
+/// mainnet feeds (ETH/USD, BTC/USD).
+pub const STALE_FEED_SECS: u64 = 3_600;
+
+pub fn warning(code: &str, message: impl Into<String>) -> WarningOut {
+    WarningOut {
+        code: code.to_string(),
+        message: message.into(),
+    }
+}
+
+/// A contract whose `decimals()` disagrees with token-list data, from `check_decimals`.
+pub fn decimals_mismatch(detail: Option<&String>) -> Option<WarningOut> {
+    detail.map(|detail| warning("decimals_mismatch", detail.clone()))
+}
+
+/// A token that is in neither the bundled defaults nor a configured token list, i.e. one only
+/// known from its own contract. The configured wrapped native token is trusted.
+pub fn unverified_token(registry: &TokenRegistry, address: Address) -> Option<WarningOut> {
+    let wrapped_native = registry.wrapped_native().map(|info| info.address);
+    if registry.listed_decimals(address).is_some() || wrapped_native == Some(address) {
+        return None;
+    }
+    let name = registry
+        .info_by_address(address)
+        .map(|info| format!("{} ({address:#x})", info.symbol))
+        .unwrap_or_else(|| format!("{address:#x}"));
+    Some(warning(
+        "unverified_token",
+        format!(
+            "{name} is not in the bundled defaults or a token list; confirm the address with the user"
+        ),
+    ))
+}
+
+/// [`unverified_token`] for each distinct token in `tokens`.
+pub fn unverified_tokens(registry: &TokenRegistry, tokens: &[Address]) -> Vec<WarningOut> {
+    let mut seen = Vec::with_capacity(tokens.len());
+    tokens
+        .iter()
+        .filter(|token| {
+            let first = !seen.contains(*token);
+            seen.push(**token);
+            first
+        })
+        .filter_map(|token| unverified_token(registry, *token))
+        .collect()
+}
+
+/// Caveats on a price: a depegged USD proxy, a DEX-only price, or median sources that failed.
+pub fn price(out: &PriceOut) -> Vec<WarningOut> {
+    let mut warnings: Vec<WarningOut> = out
+        .depeg_warning
+        .iter()
+        .map(|detail| warning("stablecoin_depeg", detail.clone()))
+        .collect();
+    if !out.source.contains("chainlink") {
+        warnings.push(warning(
+            "dex_price",
+            format!(
+                "no Chainlink feed prices {}; {} comes from pool quotes, which thin liquidity or a single large trade can move",
+                out.base, out.price
+            ),
+        ));
+    }
+    let failed: Vec<&str> = out
+        .sources
+        .iter()
+        .flatten()
+        .filter(|source| source.error.is_some())
+        .map(|source| source.source.as_str())
+        .collect();
+    if !failed.is_empty() {
+        warnings.push(warning(
+            "sources_failed",
+            format!(
+                "the median leaves out {}, which did not answer",
+                failed.join(", ")
+            ),
+        ));
+    }
+    warnings
+}
+
+/// A Chainlink round older than [`STALE_FEED_SECS`] at `now`.
+pub fn stale_feed(updated_at: u64, now: u64) -> Option<WarningOut> {
+    let age = now.saturating_sub(updated_at);
+    (age > STALE_FEED_SECS).then(|| {
+        warning(
+            "stale_feed",
+            format!(
+                "the Chainlink round is {age}s old, past the {STALE_FEED_SECS}s heartbeat; the price may lag the market"
+            ),
+        )
+    })
+}
+
+/// A swap rated a high sandwich risk, with the recommendation attached.
+pub fn mev_risk(risk: &MevRiskOut) -> Option<WarningOut> {
+    (risk.rating == "high").then(|| {
+        let impact = risk
+            .price_impact_bps
+            .map(|bps| format!("{bps} bps price impact, "))
+            .unwrap_or_default();
+        warning(
+            "high_mev_risk",
+            format!(
+                "{impact}up to {} extractable by a sandwich; {}",
+                risk.max_extractable, risk.recommendation
+            ),
+        )
+    })
+}
+
+/// Failed items of a batch, which still succeeded as a whole.
+pub fn partial_batch(failed: usize, total: usize) -> Option<WarningOut> {
+    (failed > 0).then(|| {
+        warning(
+            "partial_failure",
+            format!("{failed} of {total} items failed; see each item's error"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{implementations::price::TokenInfo, types::PriceSourceOut};
+
+    fn price_out(source: &str) -> PriceOut {
+        PriceOut {
+            base: "PEPE".into(),
+            quote: "USD".into(),
+            price: "0.00001".into(),
+            source: source.into(),
+            decimals: 5,
+            block_number: None,
+            depeg_warning: None,
+            sources: None,
+            timings: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn codes(warnings: &[WarningOut]) -> Vec<&str> {
+        warnings
+            .iter()
+            .map(|warning| warning.code.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn prices_warn_about_dex_sources_depegs_and_failed_sources() {
+        assert!(price(&price_out("chainlink")).is_empty());
+        assert_eq!(
+            codes(&price(&price_out("uniswap_v3 (via USDC)"))),
+            ["dex_price"]
+        );
+
+        let mut median = price_out("median (chainlink/uniswap_v3)");
+        median.depeg_warning = Some("USDC traded at 0.97".into());
+        median.sources = Some(vec![
+            PriceSourceOut {
+                source: "chainlink".into(),
+                price: Some("0.00001".into()),
+                error: None,
+            },
+            PriceSourceOut {
+                source: "uniswap_v3_twap".into(),
+                price: None,
+                error: Some("no pool".into()),
+            },
+        ]);
+        let warnings = price(&median);
+        assert_eq!(codes(&warnings), ["stablecoin_depeg", "sources_failed"]);
+        assert!(warnings[1].message.contains("uniswap_v3_twap"));
+    }
+
+    #[test]
+    fn only_unlisted_tokens_are_unverified() {
+        let registry = TokenRegistry::with_defaults();
+        let weth = registry.resolve_token("WETH").unwrap();
+        assert!(unverified_token(&registry, weth).is_none());
+
+        let mut registry = registry;
+        let stray = Address::from_low_u64_be(0xbeef);
+        registry.add_token(TokenInfo::new("STRAY", stray, 18));
+        let warning = unverified_token(&registry, stray).unwrap();
+        assert_eq!(warning.code, "unverified_token");
+        assert!(warning.message.starts_with("STRAY (0x"));
+    }
+
+    #[test]
+    fn feeds_are_stale_past_the_heartbeat() {
+        assert!(stale_feed(1_000, 1_000 + STALE_FEED_SECS).is_none());
+        let stale = stale_feed(1_000, 2_000 + STALE_FEED_SECS).unwrap();
+        assert!(
+            stale
+                .message
+                .starts_with("the Chainlink round is 4600s old")
+        );
+        assert!(partial_batch(0, 3).is_none());
+        assert_eq!(partial_batch(1, 3).unwrap().code, "partial_failure");
+    }
+}
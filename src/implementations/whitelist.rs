@@ -0,0 +1,215 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    types::{WhitelistEntryOut, WhitelistOut},
+};
+
+pub const DEFAULT_WHITELIST_DELAY_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WhitelistEntry {
+    address: Address,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// Unix time the address was added; it becomes usable `delay_secs` later.
+    added_at: u64,
+}
+
+/// Destinations the wallet may send funds to other than itself.
+///
+/// New addresses only become usable after `delay_secs`, so a compromised agent cannot add and
+/// pay out to an address in one step. Entries persist as a JSON array in `path`.
+#[derive(Debug, Default)]
+pub struct RecipientWhitelist {
+    enabled: bool,
+    path: Option<PathBuf>,
+    delay_secs: u64,
+    entries: Mutex<Vec<WhitelistEntry>>,
+}
+
+impl RecipientWhitelist {
+    /// No whitelist configured; every recipient is allowed.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// An enforced whitelist held in memory only.
+    pub fn new(delay_secs: u64) -> Self {
+        Self {
+            enabled: true,
+            delay_secs,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> AppResult<Self> {
+        let Some(path) = config.recipient_whitelist_path.as_deref() else {
+            return Ok(Self::disabled());
+        };
+        // A missing file is an empty whitelist; it is created on the first addition.
+        let entries = match fs::read_to_string(path) {
+            Ok(raw) if !raw.trim().is_empty() => serde_json::from_str(&raw).map_err(|err| {
+                AppError::Config(format!("failed to parse recipient whitelist {path}: {err}"))
+            })?,
+            Ok(_) => Vec::new(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(AppError::Config(format!(
+                    "failed to read recipient whitelist {path}: {err}"
+                )));
+            }
+        };
+
+        Ok(Self {
+            enabled: true,
+            path: Some(PathBuf::from(path)),
+            delay_secs: config.whitelist_delay_secs,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Reject `recipient` unless it was whitelisted at least `delay_secs` before `now`.
+    pub fn check(&self, recipient: Address, now: u64) -> AppResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let entries = self.entries.lock().expect("whitelist lock poisoned");
+        match entries.iter().find(|entry| entry.address == recipient) {
+            Some(entry) if entry.added_at + self.delay_secs <= now => Ok(()),
+            Some(entry) => Err(AppError::Compliance(format!(
+                "recipient {recipient:#x} is whitelisted but not active until unix time {}",
+                entry.added_at + self.delay_secs
+            ))),
+            None => Err(AppError::Compliance(format!(
+                "recipient {recipient:#x} is not whitelisted; add it with whitelist_address and wait {}s",
+                self.delay_secs
+            ))),
+        }
+    }
+
+    /// Add `address`, starting its time lock at `now`. Re-adding an address keeps its original
+    /// timestamp so the delay cannot be reset or skipped.
+    pub fn add(
+        &self,
+        address: Address,
+        label: Option<String>,
+        now: u64,
+    ) -> AppResult<WhitelistEntryOut> {
+        if !self.enabled {
+            return Err(AppError::Config(
+                "whitelist_address requires RECIPIENT_WHITELIST_PATH / recipient_whitelist_path"
+                    .into(),
+            ));
+        }
+        let mut entries = self.entries.lock().expect("whitelist lock poisoned");
+        if let Some(existing) = entries.iter().find(|entry| entry.address == address) {
+            return Ok(self.describe(existing, now));
+        }
+
+        let entry = WhitelistEntry {
+            address,
+            label,
+            added_at: now,
+        };
+        entries.push(entry.clone());
+        if let Some(path) = &self.path {
+            let raw = serde_json::to_string_pretty(&*entries)?;
+            if let Err(err) = fs::write(path, raw) {
+                entries.pop();
+                return Err(AppError::Io(format!(
+                    "failed to persist recipient whitelist {}: {err}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(self.describe(&entry, now))
+    }
+
+    pub fn snapshot(&self, now: u64) -> WhitelistOut {
+        let entries = self.entries.lock().expect("whitelist lock poisoned");
+        WhitelistOut {
+            enabled: self.enabled,
+            delay_secs: self.delay_secs,
+            entries: entries
+                .iter()
+                .map(|entry| self.describe(entry, now))
+                .collect(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn describe(&self, entry: &WhitelistEntry, now: u64) -> WhitelistEntryOut {
+        let active_at = entry.added_at + self.delay_secs;
+        WhitelistEntryOut {
+            address: format!("{:#x}", entry.address),
+            ens_name: None,
+            label: entry.label.clone(),
+            added_at: entry.added_at,
+            active_at,
+            active: active_at <= now,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn additions_activate_after_delay() {
+        let whitelist = RecipientWhitelist::new(3_600);
+        let recipient = Address::from_low_u64_be(0x42);
+
+        assert!(whitelist.check(recipient, 1_000).is_err());
+        let entry = whitelist
+            .add(recipient, Some("cold wallet".into()), 1_000)
+            .unwrap();
+        assert_eq!(entry.active_at, 4_600);
+        assert!(!entry.active);
+
+        assert!(matches!(
+            whitelist.check(recipient, 4_599),
+            Err(AppError::Compliance(_))
+        ));
+        assert!(whitelist.check(recipient, 4_600).is_ok());
+    }
+
+    #[test]
+    fn re_adding_keeps_original_timestamp() {
+        let whitelist = RecipientWhitelist::new(3_600);
+        let recipient = Address::from_low_u64_be(0x42);
+        whitelist.add(recipient, None, 1_000).unwrap();
+        let again = whitelist.add(recipient, None, 2_000).unwrap();
+        assert_eq!(again.added_at, 1_000);
+        assert_eq!(whitelist.snapshot(2_000).entries.len(), 1);
+    }
+
+    #[test]
+    fn disabled_allows_any_recipient() {
+        let whitelist = RecipientWhitelist::disabled();
+        assert!(whitelist.check(Address::from_low_u64_be(1), 0).is_ok());
+        assert!(whitelist.add(Address::from_low_u64_be(1), None, 0).is_err());
+    }
+}
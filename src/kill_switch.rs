@@ -0,0 +1,122 @@
+use std::sync::Mutex;
+
+use crate::{
+    error::{AppError, AppResult},
+    secrets::{SecretString, constant_time_eq},
+    types::KillSwitchOut,
+};
+
+#[derive(Debug, Clone)]
+struct Halt {
+    reason: String,
+    halted_at: u64,
+}
+
+/// Emergency stop for every tool that builds or changes anything.
+///
+/// `stop` needs no credentials so an operator (or the agent itself) can pull it instantly;
+/// `resume` requires the configured admin secret, and without one the halt lasts until restart.
+#[derive(Debug, Default)]
+pub struct KillSwitch {
+    halt: Mutex<Option<Halt>>,
+    admin_secret: Option<SecretString>,
+}
+
+impl KillSwitch {
+    pub fn new(admin_secret: Option<SecretString>) -> Self {
+        Self {
+            halt: Mutex::new(None),
+            admin_secret,
+        }
+    }
+
+    /// Suspend write operations. A second stop keeps the original reason and time.
+    pub fn stop(&self, reason: impl Into<String>, now: u64) -> KillSwitchOut {
+        let mut halt = self.halt.lock().expect("kill switch lock poisoned");
+        if halt.is_none() {
+            *halt = Some(Halt {
+                reason: reason.into(),
+                halted_at: now,
+            });
+        }
+        describe(halt.as_ref())
+    }
+
+    pub fn resume(&self, secret: &SecretString) -> AppResult<KillSwitchOut> {
+        let expected = self.admin_secret.as_ref().ok_or_else(|| {
+            AppError::Config("resume requires ADMIN_SECRET / admin_secret to be configured".into())
+        })?;
+        if !constant_time_eq(expected.expose().as_bytes(), secret.expose().as_bytes()) {
+            return Err(AppError::InvalidInput("invalid admin secret".into()));
+        }
+        let mut halt = self.halt.lock().expect("kill switch lock poisoned");
+        *halt = None;
+        Ok(describe(None))
+    }
+
+    pub fn status(&self) -> KillSwitchOut {
+        describe(
+            self.halt
+                .lock()
+                .expect("kill switch lock poisoned")
+                .as_ref(),
+        )
+    }
+
+    /// Fail with [`AppError::Halted`] while the switch is engaged.
+    pub fn ensure_running(&self) -> AppResult<()> {
+        match self
+            .halt
+            .lock()
+            .expect("kill switch lock poisoned")
+            .as_ref()
+        {
+            Some(halt) => Err(AppError::Halted(format!(
+                "write operations suspended by panic_stop: {}",
+                halt.reason
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn describe(halt: Option<&Halt>) -> KillSwitchOut {
+    KillSwitchOut {
+        halted: halt.is_some(),
+        reason: halt.map(|halt| halt.reason.clone()),
+        halted_at: halt.map(|halt| halt.halted_at),
+        warnings: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_blocks_until_resumed_with_secret() {
+        let switch = KillSwitch::new(Some(SecretString::new("hunter2")));
+        assert!(switch.ensure_running().is_ok());
+
+        let status = switch.stop("drawdown limit", 100);
+        assert!(status.halted);
+        switch.stop("second stop", 200);
+        assert_eq!(switch.status().reason.as_deref(), Some("drawdown limit"));
+        assert!(matches!(switch.ensure_running(), Err(AppError::Halted(_))));
+
+        assert!(switch.resume(&SecretString::new("wrong")).is_err());
+        assert!(switch.ensure_running().is_err());
+        assert!(!switch.resume(&SecretString::new("hunter2")).unwrap().halted);
+        assert!(switch.ensure_running().is_ok());
+    }
+
+    #[test]
+    fn resume_without_configured_secret_is_refused() {
+        let switch = KillSwitch::default();
+        switch.stop("test", 0);
+        assert!(matches!(
+            switch.resume(&SecretString::new("")),
+            Err(AppError::Config(_))
+        ));
+    }
+}
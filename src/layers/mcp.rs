@@ -1,17 +1,54 @@
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
-use serde_json::{Value, json};
-use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tracing::{error, warn};
+use serde_json::{Map, Value, json};
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
+    sync::{broadcast, mpsc},
+};
+use tracing::{debug, error, info, warn};
 
 use crate::{
     error::{AppError, AppResult},
+    events::{EventRecord, ServerEvent},
+    implementations::{rpc, whitelist},
     layers::service::ServiceLayer,
+    progress::{ProgressReporter, ProgressUpdate, ToolEvent},
+    redact::redact,
+    schemas,
     types::{
-        BalanceOut, GetBalanceParams, GetTokenPriceParams, PriceOut, SwapSimOut, SwapTokensParams,
+        ActivityHistoryOut, ActivityReportOut, ApprovalTemplatesOut, ArbPlanOut, ArbScanOut,
+        AuthorizationOut, BacktestOut, BacktestSwapStrategyParams, BalanceOut, BatchOut, BundleOut,
+        BurnerWalletOut, CandlesOut, ChainInfoOut, ConvertUnitsOut, ConvertUnitsParams,
+        CreateBurnerWalletParams, DelegationOut, DepositWatchOut, DistributeTokensParams,
+        DistributionOut, EventsOut, GaslessSwapOut, GaslessSwapParams, GetActivityHistoryParams,
+        GetActivityReportParams, GetArbitragePlanParams, GetBalanceParams, GetBalancesParams,
+        GetCandlesParams, GetDelegationParams, GetEventsParams, GetNativePriceParams,
+        GetPaperPortfolioParams, GetPortfolioRiskParams, GetQuoteLadderParams, GetSchemasParams,
+        GetTokenDeploymentsParams, GetTokenPriceParams, GetTokenPricesParams, GetWalletInfoParams,
+        KillSwitchOut, ListApprovalTemplatesParams, ListQuoteCurrenciesParams, ListTokensOut,
+        ListTokensParams, MaintenanceOut, MempoolWatchOut, NativePriceOut, PanicStopParams,
+        PaperFundParams, PaperPortfolioOut, PaperSwapParams, PaperTransferParams,
+        ParsePaymentRequestParams, PaymentRequestOut, PlanRebalanceParams, PortfolioRiskOut,
+        PriceOut, ProviderPoolOut, QuoteCurrenciesOut, QuoteLadderOut, RebalancePlanOut,
+        ResponseMetaOut, ResumeParams, ScanArbitrageParams, SchemasOut, SelfTestOut,
+        SignAuthorizationParams, SimulateBundleParams, SpreadWatchOut, SwapPlanOut, SwapSimOut,
+        SwapTokensParams, SweepOut, SweepToOut, SweepToParams, SweepWalletParams,
+        TokenDeploymentsOut, TokenRiskOut, TokenRiskParams, TokenWatchOut, TraceCallOut,
+        TraceCallParams, TransferFromOut, TransferFromParams, TxWatchOut, VerifySignatureOut,
+        VerifySignatureParams, WalletInfoOut, WatchDepositsParams, WatchMempoolParams,
+        WatchSpreadParams, WatchTokenParams, WatchTransactionParams, WhitelistAddressParams,
+        WhitelistEntryOut, WhitelistOut,
     },
 };
 
+/// Serialized messages waiting for stdout; producers wait once this many are queued.
+const OUTGOING_QUEUE_CAPACITY: usize = 64;
+
+/// MCP protocol revisions `initialize` accepts, newest first; any other is answered with the
+/// newest.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
 /// Runtime that speaks JSON-RPC 2.0 over stdin/stdout as required by MCP hosts.
+#[derive(Clone)]
 pub struct McpServer {
     service: ServiceLayer,
 }
@@ -24,9 +61,14 @@ impl McpServer {
     /// Start processing JSON-RPC requests until EOF on stdin.
     pub async fn run_stdio(self) -> AppResult<()> {
         let stdin = io::stdin();
-        let stdout = io::stdout();
         let mut reader = BufReader::new(stdin);
-        let mut writer = BufWriter::new(stdout);
+        let (outgoing, queue) = mpsc::channel(OUTGOING_QUEUE_CAPACITY);
+        let writer = tokio::spawn(write_messages(queue, io::stdout()));
+        let notifier = self
+            .service
+            .event_notifications()
+            .map(|events| tokio::spawn(forward_events(events, outgoing.clone())));
+        let logging = notifier.is_some();
         let mut line = String::new();
 
         loop {
@@ -41,35 +83,176 @@ impl McpServer {
             }
 
             let request: Result<RpcRequest, _> = serde_json::from_str(&line);
-            match request {
-                Ok(req) => {
-                    let response = self.handle_request(req).await;
-                    let payload = serde_json::to_vec(&response).map_err(AppError::from)?;
-                    writer.write_all(&payload).await?;
-                    writer.write_all(b"\n").await?;
-                    writer.flush().await?;
+            let mut shutdown = false;
+            let response = match request {
+                // Notifications get no response, not even an error.
+                Ok(req) if req.method.starts_with("notifications/") => {
+                    debug!("received {}", req.method);
+                    continue;
                 }
+                Ok(req) if is_session_method(&req.method) => {
+                    shutdown = req.method == "shutdown";
+                    session_response(req, logging)
+                }
+                Ok(req) => match progress_token(&req.params) {
+                    // Calls tracked with progress are long; they must not hold up the ones behind.
+                    Some(token) => {
+                        tokio::spawn(self.clone().handle_with_progress(
+                            req,
+                            token,
+                            outgoing.clone(),
+                        ));
+                        continue;
+                    }
+                    None => self.handle_request(req, ProgressReporter::disabled()).await,
+                },
                 Err(err) => {
                     warn!("failed to parse JSON-RPC request: {err}");
-                    let response =
-                        RpcResponse::error(Value::Null, -32700, format!("parse error: {err}"));
-                    let payload = serde_json::to_vec(&response).map_err(AppError::from)?;
-                    writer.write_all(&payload).await?;
-                    writer.write_all(b"\n").await?;
-                    writer.flush().await?;
+                    RpcResponse::error(Value::Null, -32700, format!("parse error: {err}"))
                 }
+            };
+            let payload = serde_json::to_vec(&response).map_err(AppError::from)?;
+            // Waits while the queue is full, so a slow reader throttles request handling.
+            if outgoing.send(payload).await.is_err() || shutdown {
+                break;
             }
         }
 
+        drop(outgoing);
+        if let Some(notifier) = notifier {
+            notifier.abort();
+        }
+        writer
+            .await
+            .map_err(|err| AppError::Internal(format!("stdout writer task failed: {err}")))??;
         Ok(())
     }
 
-    async fn handle_request(&self, req: RpcRequest) -> RpcResponse {
+    async fn handle_request(&self, req: RpcRequest, progress: ProgressReporter) -> RpcResponse {
         let RpcRequest {
             method, params, id, ..
         } = req;
+        if method != "tools/call" {
+            return self.call(&method, params, id, &progress).await;
+        }
+
+        let call = match parse_params::<ToolCallParams>(params) {
+            Ok(call) => call,
+            Err(err) => return RpcResponse::error(id, -32602, err.to_string()),
+        };
+        let params = call_params(call.arguments, call.meta);
+        let response = self.call(&call.name, params, id, &progress).await;
+        match &response.error {
+            // An unknown tool is the client's protocol error; any other failure is the tool's.
+            Some(error) if error.code == -32601 => {
+                RpcResponse::error(response.id, -32602, format!("unknown tool: {}", call.name))
+            }
+            _ => tool_result(response),
+        }
+    }
+
+    /// Run one tool, attaching `meta` to its result and recording the call.
+    async fn call(
+        &self,
+        method: &str,
+        params: Value,
+        id: Value,
+        progress: &ProgressReporter,
+    ) -> RpcResponse {
+        let params = self.service.apply_param_defaults(method, params);
+        let rpc_endpoint = self.pinned_endpoint(&params);
+        let mut response = match &rpc_endpoint {
+            Ok(Some(endpoint)) => {
+                let call = self.route(method, params, id, progress);
+                rpc::with_pinned_endpoint(endpoint.clone(), call).await
+            }
+            Ok(None) => self.route(method, params, id, progress).await,
+            Err(err) => RpcResponse::app_error(id, err),
+        };
+        if let Some(result) = response.result.as_mut() {
+            let server_time = whitelist::unix_now();
+            let pinned = rpc_endpoint.ok().flatten();
+            attach_meta(
+                method,
+                self.service.chain_id(),
+                server_time,
+                pinned.as_deref(),
+                result,
+            );
+            self.service.attest(server_time, result);
+        }
+        self.service.record_call(
+            method,
+            response
+                .error
+                .as_ref()
+                .map(|err| (err.code, err.message.as_str())),
+        );
+        response
+    }
+
+    /// The RPC endpoint a client pinned the call to with `params._meta.rpcEndpoint`, checked
+    /// against the configured ones.
+    fn pinned_endpoint(&self, params: &Value) -> AppResult<Option<String>> {
+        let Some(requested) = params
+            .get("_meta")
+            .and_then(|meta| meta.get("rpcEndpoint"))
+            .filter(|requested| !requested.is_null())
+        else {
+            return Ok(None);
+        };
+        let name = requested
+            .as_str()
+            .ok_or_else(|| AppError::InvalidInput("_meta.rpcEndpoint must be a string".into()))?;
+        self.service.check_rpc_endpoint(name)?;
+        Ok(Some(name.to_string()))
+    }
 
-        match method.as_str() {
+    /// Run a call whose params carry `_meta.progressToken`: its progress goes out as
+    /// `notifications/progress` while it runs, followed by its response and nothing after.
+    async fn handle_with_progress(
+        self,
+        req: RpcRequest,
+        token: Value,
+        outgoing: mpsc::Sender<Vec<u8>>,
+    ) {
+        let (events, mut queue) = mpsc::unbounded_channel();
+        let progress = ProgressReporter::new(events.clone());
+        let call = async move {
+            let response = self.handle_request(req, progress).await;
+            match serde_json::to_value(&response) {
+                Ok(response) => {
+                    let _ = events.send(ToolEvent::Finished(response));
+                }
+                Err(err) => error!("serialization error: {err}"),
+            }
+        };
+        let relay = async {
+            while let Some(event) = queue.recv().await {
+                let finished = event.is_finished();
+                let message = match event {
+                    ToolEvent::Progress(update) => progress_notification(&token, update),
+                    ToolEvent::Finished(response) => response,
+                };
+                let Ok(payload) = serde_json::to_vec(&message) else {
+                    continue;
+                };
+                if outgoing.send(payload).await.is_err() || finished {
+                    break;
+                }
+            }
+        };
+        tokio::join!(call, relay);
+    }
+
+    async fn route(
+        &self,
+        method: &str,
+        params: Value,
+        id: Value,
+        progress: &ProgressReporter,
+    ) -> RpcResponse {
+        match method {
             "get_balance" => {
                 self.dispatch::<GetBalanceParams, BalanceOut, _, _>(
                     id,
@@ -78,6 +261,14 @@ impl McpServer {
                 )
                 .await
             }
+            "get_balances" => {
+                self.dispatch::<GetBalancesParams, BatchOut<BalanceOut>, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_balances(parsed).await },
+                )
+                .await
+            }
             "get_token_price" => {
                 self.dispatch::<GetTokenPriceParams, PriceOut, _, _>(
                     id,
@@ -86,6 +277,22 @@ impl McpServer {
                 )
                 .await
             }
+            "get_token_prices" => {
+                self.dispatch::<GetTokenPricesParams, BatchOut<PriceOut>, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_token_prices(parsed).await },
+                )
+                .await
+            }
+            "get_native_price" => {
+                self.dispatch::<GetNativePriceParams, NativePriceOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_native_price(parsed).await },
+                )
+                .await
+            }
             "swap_tokens" => {
                 self.dispatch::<SwapTokensParams, SwapSimOut, _, _>(
                     id,
@@ -94,6 +301,405 @@ impl McpServer {
                 )
                 .await
             }
+            "gasless_swap" => {
+                self.dispatch::<GaslessSwapParams, GaslessSwapOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.gasless_swap(parsed).await },
+                )
+                .await
+            }
+            "plan_swap" => {
+                self.dispatch::<SwapTokensParams, SwapPlanOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.plan_swap(parsed).await },
+                )
+                .await
+            }
+            "transfer_from" => {
+                self.dispatch::<TransferFromParams, TransferFromOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.transfer_from(parsed).await },
+                )
+                .await
+            }
+            "distribute_tokens" => {
+                self.dispatch::<DistributeTokensParams, DistributionOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.distribute_tokens(parsed).await },
+                )
+                .await
+            }
+            "plan_rebalance" => {
+                self.dispatch::<PlanRebalanceParams, RebalancePlanOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.plan_rebalance(parsed).await },
+                )
+                .await
+            }
+            "sweep_to" => {
+                self.dispatch::<SweepToParams, SweepToOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.sweep_to(parsed).await },
+                )
+                .await
+            }
+            "get_quote_ladder" => {
+                self.dispatch::<GetQuoteLadderParams, QuoteLadderOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_quote_ladder(parsed).await },
+                )
+                .await
+            }
+            "get_candles" => {
+                self.dispatch::<GetCandlesParams, CandlesOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_candles(parsed).await },
+                )
+                .await
+            }
+            "backtest_swap_strategy" => {
+                self.dispatch::<BacktestSwapStrategyParams, BacktestOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.backtest_swap_strategy(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "get_portfolio_risk" => {
+                self.dispatch::<GetPortfolioRiskParams, PortfolioRiskOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.get_portfolio_risk(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "scan_arbitrage" => {
+                self.dispatch::<ScanArbitrageParams, ArbScanOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.scan_arbitrage(parsed).await },
+                )
+                .await
+            }
+            "get_arbitrage_plan" => {
+                self.dispatch::<GetArbitragePlanParams, ArbPlanOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_arbitrage_plan(parsed).await },
+                )
+                .await
+            }
+            "get_chain_info" => {
+                self.dispatch::<Value, ChainInfoOut, _, _>(
+                    id,
+                    params,
+                    |service, _| async move { service.get_chain_info().await },
+                )
+                .await
+            }
+            "get_provider_pool" => {
+                self.dispatch::<Value, ProviderPoolOut, _, _>(id, params, |service, _| async move {
+                    service.get_provider_pool().await
+                })
+                .await
+            }
+            "watch_mempool" => {
+                self.dispatch::<WatchMempoolParams, MempoolWatchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.watch_mempool(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "watch_deposits" => {
+                self.dispatch::<WatchDepositsParams, DepositWatchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.watch_deposits(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "watch_token" => {
+                self.dispatch::<WatchTokenParams, TokenWatchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.watch_token(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "watch_transaction" => {
+                self.dispatch::<WatchTransactionParams, TxWatchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.watch_transaction(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "watch_spread" => {
+                self.dispatch::<WatchSpreadParams, SpreadWatchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| {
+                        let progress = progress.clone();
+                        async move { service.watch_spread(parsed, progress).await }
+                    },
+                )
+                .await
+            }
+            "whitelist_address" => {
+                self.dispatch::<WhitelistAddressParams, WhitelistEntryOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.whitelist_address(parsed).await },
+                )
+                .await
+            }
+            "get_whitelist" => {
+                self.dispatch::<Value, WhitelistOut, _, _>(
+                    id,
+                    params,
+                    |service, _| async move { service.get_whitelist().await },
+                )
+                .await
+            }
+            "panic_stop" => {
+                self.dispatch::<PanicStopParams, KillSwitchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.panic_stop(parsed).await },
+                )
+                .await
+            }
+            "resume" => {
+                self.dispatch::<ResumeParams, KillSwitchOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.resume(parsed).await },
+                )
+                .await
+            }
+            "get_events" => {
+                self.dispatch::<GetEventsParams, EventsOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_events(parsed).await },
+                )
+                .await
+            }
+            "self_test" => {
+                self.dispatch::<Value, SelfTestOut, _, _>(id, params, |service, _| async move {
+                    service.self_test().await
+                })
+                .await
+            }
+            "maintenance" => {
+                self.dispatch::<Value, MaintenanceOut, _, _>(id, params, |service, _| async move {
+                    service.maintenance().await
+                })
+                .await
+            }
+            "get_activity_report" => {
+                self.dispatch::<GetActivityReportParams, ActivityReportOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_activity_report(parsed).await },
+                )
+                .await
+            }
+            "get_activity_history" => {
+                self.dispatch::<GetActivityHistoryParams, ActivityHistoryOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_activity_history(parsed).await },
+                )
+                .await
+            }
+            "token_risk" => {
+                self.dispatch::<TokenRiskParams, TokenRiskOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.token_risk(parsed).await },
+                )
+                .await
+            }
+            "simulate_bundle" => {
+                self.dispatch::<SimulateBundleParams, BundleOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.simulate_bundle(parsed).await },
+                )
+                .await
+            }
+            "trace_call" => {
+                self.dispatch::<TraceCallParams, TraceCallOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.trace_call(parsed).await },
+                )
+                .await
+            }
+            "verify_signature" => {
+                self.dispatch::<VerifySignatureParams, VerifySignatureOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.verify_signature(parsed).await },
+                )
+                .await
+            }
+            "get_delegation" => {
+                self.dispatch::<GetDelegationParams, DelegationOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_delegation(parsed).await },
+                )
+                .await
+            }
+            "sign_authorization" => {
+                self.dispatch::<SignAuthorizationParams, AuthorizationOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.sign_authorization(parsed).await },
+                )
+                .await
+            }
+            "list_tokens" => {
+                self.dispatch::<ListTokensParams, ListTokensOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.list_tokens(parsed).await },
+                )
+                .await
+            }
+            "list_approval_templates" => {
+                self.dispatch::<ListApprovalTemplatesParams, ApprovalTemplatesOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.list_approval_templates(parsed).await },
+                )
+                .await
+            }
+            "get_wallet_info" => {
+                self.dispatch::<GetWalletInfoParams, WalletInfoOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_wallet_info(parsed).await },
+                )
+                .await
+            }
+            "list_quote_currencies" => {
+                self.dispatch::<ListQuoteCurrenciesParams, QuoteCurrenciesOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.list_quote_currencies(parsed).await },
+                )
+                .await
+            }
+            "convert_units" => {
+                self.dispatch::<ConvertUnitsParams, ConvertUnitsOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.convert_units(parsed).await },
+                )
+                .await
+            }
+            "get_token_deployments" => {
+                self.dispatch::<GetTokenDeploymentsParams, TokenDeploymentsOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_token_deployments(parsed).await },
+                )
+                .await
+            }
+            "parse_payment_request" => {
+                self.dispatch::<ParsePaymentRequestParams, PaymentRequestOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.parse_payment_request(parsed).await },
+                )
+                .await
+            }
+            "create_burner_wallet" => {
+                self.dispatch::<CreateBurnerWalletParams, BurnerWalletOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.create_burner_wallet(parsed).await },
+                )
+                .await
+            }
+            "sweep_wallet" => {
+                self.dispatch::<SweepWalletParams, SweepOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.sweep_wallet(parsed).await },
+                )
+                .await
+            }
+            "paper_fund" => {
+                self.dispatch::<PaperFundParams, PaperPortfolioOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.paper_fund(parsed).await },
+                )
+                .await
+            }
+            "paper_swap" => {
+                self.dispatch::<PaperSwapParams, PaperPortfolioOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.paper_swap(parsed).await },
+                )
+                .await
+            }
+            "paper_transfer" => {
+                self.dispatch::<PaperTransferParams, PaperPortfolioOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.paper_transfer(parsed).await },
+                )
+                .await
+            }
+            "get_paper_portfolio" => {
+                self.dispatch::<GetPaperPortfolioParams, PaperPortfolioOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_paper_portfolio(parsed).await },
+                )
+                .await
+            }
+            "get_schemas" => {
+                self.dispatch::<GetSchemasParams, SchemasOut, _, _>(
+                    id,
+                    params,
+                    |service, parsed| async move { service.get_schemas(parsed).await },
+                )
+                .await
+            }
             other => {
                 warn!("received unknown method {other}");
                 RpcResponse::error(id, -32601, format!("method not found: {other}"))
@@ -124,16 +730,291 @@ impl McpServer {
                 },
                 Err(err) => {
                     error!("handler error: {err}");
-                    let payload = err.to_json_rpc();
-                    RpcResponse::error_with_data(id, payload.code, payload.message, payload.data)
+                    RpcResponse::app_error(id, &err)
                 }
             },
             Err(err) => {
                 warn!("invalid params: {err}");
-                RpcResponse::error(id, -32602, err.to_string())
+                RpcResponse::error(id, -32602, redact(&err.to_string()).into_owned())
+            }
+        }
+    }
+}
+
+/// Methods answered by the session itself. `shutdown` is not an MCP method, since MCP ends a
+/// stdio session by closing stdin; it is a server extension for clients that want the server to
+/// exit once its reply is written.
+fn is_session_method(method: &str) -> bool {
+    matches!(method, "initialize" | "ping" | "shutdown" | "tools/list")
+}
+
+/// Answer an MCP session method. These are not tools: they carry no `meta` and are not logged
+/// as calls.
+fn session_response(req: RpcRequest, logging: bool) -> RpcResponse {
+    match req.method.as_str() {
+        "initialize" => {}
+        "tools/list" => return RpcResponse::success(req.id, tools_list()),
+        _ => return RpcResponse::success(req.id, json!({})),
+    }
+    match parse_params::<InitializeParams>(req.params) {
+        Ok(params) => {
+            if let Some(client) = &params.client_info {
+                info!("initialized by {} {}", client.name, client.version);
+            }
+            RpcResponse::success(req.id, initialize_result(&params.protocol_version, logging))
+        }
+        Err(err) => RpcResponse::error(req.id, -32602, err.to_string()),
+    }
+}
+
+/// The negotiated protocol version, the server's capabilities and who it is. Log messages are
+/// only offered when events are forwarded as `notifications/message`.
+fn initialize_result(requested: &str, logging: bool) -> Value {
+    let protocol_version = PROTOCOL_VERSIONS
+        .into_iter()
+        .find(|version| *version == requested)
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    if protocol_version != requested {
+        warn!("client asked for MCP protocol {requested}; offering {protocol_version}");
+    }
+    let mut capabilities = json!({ "tools": { "listChanged": false } });
+    if logging {
+        capabilities["logging"] = json!({});
+    }
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": capabilities,
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// `tools/call` arguments as the tool's params, with the request's `_meta` (progress token,
+/// pinned RPC endpoint) carried along as a direct call would send it.
+fn call_params(arguments: Value, meta: Option<Value>) -> Value {
+    let Some(meta) = meta else {
+        return arguments;
+    };
+    let mut params = match arguments {
+        Value::Object(params) => params,
+        Value::Null => Map::new(),
+        other => return other,
+    };
+    params.insert("_meta".to_string(), meta);
+    Value::Object(params)
+}
+
+/// A tool's response as an MCP `CallToolResult`: the result as text and structured content, or
+/// its error flagged with `isError` so the model sees the failure rather than the host.
+fn tool_result(response: RpcResponse) -> RpcResponse {
+    let RpcResponse {
+        result, error, id, ..
+    } = response;
+    let (text, structured, is_error) = match (result, error) {
+        (Some(result), _) => (result.to_string(), result, false),
+        (None, Some(error)) => (error.message.clone(), json!({ "error": error }), true),
+        (None, None) => (String::new(), json!({}), false),
+    };
+    RpcResponse::success(
+        id,
+        json!({
+            "content": [{ "type": "text", "text": text }],
+            "structuredContent": structured,
+            "isError": is_error,
+        }),
+    )
+}
+
+/// Every tool in the `get_schemas` catalog as an MCP tool, in one page. MCP wants an object
+/// `inputSchema`, so params that may also be omitted are listed as an object.
+fn tools_list() -> Value {
+    let tools: Vec<Value> = schemas::catalog()
+        .into_iter()
+        .map(|tool| {
+            let mut input_schema = tool.params;
+            if input_schema["type"].is_array() {
+                input_schema["type"] = json!("object");
             }
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": input_schema,
+            })
+        })
+        .collect();
+    json!({ "tools": tools })
+}
+
+/// Single consumer of every outgoing message: writes each payload as one line, so messages from
+/// concurrent producers never interleave. Returns the writer once every sender is dropped.
+async fn write_messages<W>(mut queue: mpsc::Receiver<Vec<u8>>, writer: W) -> io::Result<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut writer = BufWriter::new(writer);
+    while let Some(payload) = queue.recv().await {
+        writer.write_all(&payload).await?;
+        writer.write_all(b"\n").await?;
+        // Batch whatever is already queued into one flush.
+        if queue.is_empty() {
+            writer.flush().await?;
         }
     }
+    writer.flush().await?;
+    Ok(writer.into_inner())
+}
+
+/// Add a `meta` object to an object `result`: the chain, the block the data reflects (its
+/// `block_number`, or the end of a scanned range), the server time, the data sources, and the
+/// RPC endpoint when the call was pinned to one.
+fn attach_meta(
+    method: &str,
+    chain_id: u64,
+    server_time: u64,
+    rpc_endpoint: Option<&str>,
+    result: &mut Value,
+) {
+    if !result.is_object() {
+        return;
+    }
+    let block_number = ["block_number", "to_block", "last_block"]
+        .iter()
+        .find_map(|key| result.get(*key).and_then(Value::as_u64));
+    let mut sources: Vec<String> = default_sources(method)
+        .iter()
+        .map(|source| source.to_string())
+        .collect();
+    collect_sources(result, &mut sources);
+
+    let meta = ResponseMetaOut {
+        chain_id,
+        block_number,
+        server_time,
+        sources,
+        rpc_endpoint: rpc_endpoint.map(str::to_string),
+    };
+    if let (Some(object), Ok(meta)) = (result.as_object_mut(), serde_json::to_value(meta)) {
+        object.insert("meta".to_string(), meta);
+    }
+}
+
+/// Sources a tool always draws on; prices add the `source` they report.
+fn default_sources(method: &str) -> &'static [&'static str] {
+    match method {
+        "get_token_price" | "get_token_prices" => &[],
+        "get_native_price" => &["chainlink"],
+        "gasless_swap" => &["0x_gasless", "rpc"],
+        "self_test" => &["rpc", "chainlink", "uniswap_v3"],
+        "swap_tokens" | "plan_swap" | "plan_rebalance" | "sweep_to" | "get_quote_ladder"
+        | "get_candles" | "paper_swap" | "scan_arbitrage" | "get_arbitrage_plan" => {
+            &["uniswap_v3", "rpc"]
+        }
+        "list_tokens"
+        | "list_approval_templates"
+        | "parse_payment_request"
+        | "get_token_deployments"
+        | "convert_units" => &["registry"],
+        "list_quote_currencies" => &["registry", "rpc"],
+        "whitelist_address"
+        | "get_whitelist"
+        | "panic_stop"
+        | "resume"
+        | "get_events"
+        | "maintenance"
+        | "get_activity_report"
+        | "get_activity_history"
+        | "get_wallet_info"
+        | "create_burner_wallet"
+        | "get_provider_pool"
+        | "paper_fund"
+        | "paper_transfer"
+        | "get_paper_portfolio"
+        | "get_schemas" => &["server"],
+        _ => &["rpc"],
+    }
+}
+
+/// Every distinct `source` string anywhere in `value`.
+fn collect_sources(value: &Value, sources: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                match value {
+                    Value::String(source) if key == "source" => {
+                        if !sources.contains(source) {
+                            sources.push(source.clone());
+                        }
+                    }
+                    _ => collect_sources(value, sources),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_sources(item, sources);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The `progressToken` a client put in `params._meta` to receive progress notifications.
+fn progress_token(params: &Value) -> Option<Value> {
+    params
+        .get("_meta")
+        .and_then(|meta| meta.get("progressToken"))
+        .filter(|token| token.is_string() || token.is_i64() || token.is_u64())
+        .cloned()
+}
+
+fn progress_notification(token: &Value, update: ProgressUpdate) -> Value {
+    let mut params = json!({ "progressToken": token, "progress": update.progress });
+    if let Some(total) = update.total {
+        params["total"] = json!(total);
+    }
+    if let Some(message) = update.message {
+        params["message"] = json!(message);
+    }
+    json!({ "jsonrpc": "2.0", "method": "notifications/progress", "params": params })
+}
+
+/// Relay bus events to the client as MCP log messages for as long as it is connected.
+async fn forward_events(
+    mut events: broadcast::Receiver<EventRecord>,
+    outgoing: mpsc::Sender<Vec<u8>>,
+) {
+    loop {
+        let record = match events.recv().await {
+            Ok(record) => record,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(
+                    "event notifications fell {skipped} events behind; get_events still has them"
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let Ok(payload) = serde_json::to_vec(&event_notification(&record)) else {
+            continue;
+        };
+        if outgoing.send(payload).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn event_notification(record: &EventRecord) -> Value {
+    let level = match record.event {
+        ServerEvent::Halted { .. } => "warning",
+        _ => "info",
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/message",
+        "params": { "level": level, "logger": "events", "data": record },
+    })
 }
 
 fn parse_params<T: DeserializeOwned>(value: Value) -> Result<T, AppError> {
@@ -157,6 +1038,30 @@ struct RpcRequest {
     id: Value,
 }
 
+#[derive(Debug, Deserialize)]
+struct ToolCallParams {
+    name: String,
+    #[serde(default = "default_null")]
+    arguments: Value,
+    #[serde(default, rename = "_meta")]
+    meta: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InitializeParams {
+    #[serde(rename = "protocolVersion")]
+    protocol_version: String,
+    #[serde(default, rename = "clientInfo")]
+    client_info: Option<ClientInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientInfo {
+    name: String,
+    #[serde(default)]
+    version: String,
+}
+
 #[derive(Debug, Serialize)]
 struct RpcResponse {
     jsonrpc: &'static str,
@@ -190,6 +1095,11 @@ impl RpcResponse {
         }
     }
 
+    fn app_error(id: Value, err: &AppError) -> Self {
+        let payload = err.to_json_rpc();
+        Self::error_with_data(id, payload.code, payload.message, payload.data)
+    }
+
     fn error_with_data(id: Value, code: i32, message: String, data: Value) -> Self {
         Self {
             jsonrpc: "2.0",
@@ -210,3 +1120,187 @@ struct RpcError {
     message: String,
     data: Value,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_reports_block_and_sources() {
+        let mut price = json!({
+            "base": "WETH",
+            "price": "3000",
+            "source": "uniswap_v3 (via USDC)",
+            "block_number": 19_000_000,
+            "sources": [{ "source": "chainlink" }, { "source": "uniswap_v3 (via USDC)" }],
+        });
+        attach_meta("get_token_price", 1, 1_700_000_000, None, &mut price);
+        assert_eq!(price["meta"]["chain_id"], 1);
+        assert_eq!(price["meta"]["block_number"], 19_000_000);
+        assert_eq!(price["meta"]["server_time"], 1_700_000_000);
+        let mut sources: Vec<&str> = price["meta"]["sources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        sources.sort();
+        assert_eq!(sources, ["chainlink", "uniswap_v3 (via USDC)"]);
+
+        let mut deposits = json!({ "from_block": 10, "last_block": 12, "deposits": [] });
+        attach_meta("watch_deposits", 8_453, 0, Some("archive"), &mut deposits);
+        assert_eq!(deposits["meta"]["block_number"], 12);
+        assert_eq!(deposits["meta"]["rpc_endpoint"], "archive");
+        assert_eq!(deposits["meta"]["sources"], json!(["rpc"]));
+
+        let mut scalar = json!(true);
+        attach_meta("get_balance", 1, 0, None, &mut scalar);
+        assert_eq!(scalar, json!(true));
+    }
+
+    #[test]
+    fn progress_token_becomes_notifications() {
+        let params = json!({ "duration_secs": 30, "_meta": { "progressToken": "watch-1" } });
+        let token = progress_token(&params).unwrap();
+        assert!(progress_token(&json!({ "_meta": { "progressToken": null } })).is_none());
+        assert!(progress_token(&Value::Null).is_none());
+
+        let update = ProgressUpdate {
+            progress: 4,
+            total: Some(30),
+            message: None,
+        };
+        assert_eq!(
+            progress_notification(&token, update),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": { "progressToken": "watch-1", "progress": 4, "total": 30 },
+            })
+        );
+    }
+
+    #[test]
+    fn events_become_log_notifications() {
+        let record = EventRecord {
+            seq: 7,
+            at: 1_700_000_000,
+            event: ServerEvent::Halted {
+                reason: "drill".into(),
+            },
+        };
+        assert_eq!(
+            event_notification(&record),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/message",
+                "params": {
+                    "level": "warning",
+                    "logger": "events",
+                    "data": { "seq": 7, "at": 1_700_000_000, "type": "halted", "reason": "drill" },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn initialize_negotiates_the_protocol_version() {
+        let request = |params: Value| RpcRequest {
+            jsonrpc: "2.0".into(),
+            method: "initialize".into(),
+            params,
+            id: json!(0),
+        };
+        let response = session_response(
+            request(json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "host", "version": "1.0" },
+            })),
+            false,
+        );
+        let result = response.result.unwrap();
+        assert_eq!(result["protocolVersion"], "2024-11-05");
+        assert_eq!(
+            result["capabilities"],
+            json!({ "tools": { "listChanged": false } })
+        );
+        assert_eq!(result["serverInfo"]["name"], "walletmcp");
+
+        let result = initialize_result("1999-01-01", true);
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSIONS[0]);
+        assert_eq!(result["capabilities"]["logging"], json!({}));
+
+        let response = session_response(request(json!({ "capabilities": {} })), false);
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn tools_list_covers_the_catalog() {
+        let listed = tools_list();
+        let tools = listed["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), schemas::catalog().len());
+        for tool in tools {
+            assert_eq!(tool["inputSchema"]["type"], "object", "{}", tool["name"]);
+        }
+        let balance = tools
+            .iter()
+            .find(|tool| tool["name"] == "get_balance")
+            .unwrap();
+        assert_eq!(
+            balance["description"],
+            "ETH or ERC-20 balance of an address."
+        );
+        assert_eq!(balance["inputSchema"]["required"], json!(["address"]));
+    }
+
+    #[test]
+    fn tool_calls_answer_with_content() {
+        let params = call_params(
+            json!({ "address": "vitalik.eth" }),
+            Some(json!({ "progressToken": 7 })),
+        );
+        assert_eq!(params["address"], "vitalik.eth");
+        assert_eq!(progress_token(&params), Some(json!(7)));
+        assert_eq!(call_params(Value::Null, None), Value::Null);
+
+        let ok = tool_result(RpcResponse::success(json!(1), json!({ "symbol": "ETH" })));
+        let result = ok.result.unwrap();
+        assert_eq!(result["isError"], false);
+        assert_eq!(result["structuredContent"]["symbol"], "ETH");
+        assert_eq!(result["content"][0]["type"], "text");
+        assert_eq!(result["content"][0]["text"], r#"{"symbol":"ETH"}"#);
+
+        let failed = tool_result(RpcResponse::error(
+            json!(2),
+            -32000,
+            "insufficient funds".into(),
+        ));
+        assert!(failed.error.is_none());
+        let result = failed.result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert_eq!(result["content"][0]["text"], "insufficient funds");
+        assert_eq!(result["structuredContent"]["error"]["code"], -32000);
+    }
+
+    #[tokio::test]
+    async fn writer_emits_one_line_per_message_in_order() {
+        let (outgoing, queue) = mpsc::channel(2);
+        let writer = tokio::spawn(write_messages(queue, Vec::new()));
+        for id in 0..5 {
+            let response = RpcResponse::success(json!(id), json!({ "ok": true }));
+            outgoing
+                .send(serde_json::to_vec(&response).unwrap())
+                .await
+                .unwrap();
+        }
+        drop(outgoing);
+
+        let written = String::from_utf8(writer.await.unwrap().unwrap()).unwrap();
+        let ids: Vec<Value> = written
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].clone())
+            .collect();
+        assert_eq!(ids, (0..5).map(|id| json!(id)).collect::<Vec<_>>());
+    }
+}
@@ -1,96 +1,2764 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use crate::{
+    activity::{self, ActivityLog, SwapRecord, WriteRecord},
+    config::AppConfig,
     error::{AppError, AppResult},
+    events::{EVENT_KINDS, EVENT_LOG_CAPACITY, EventBus, EventRecord, ServerEvent},
     implementations::{
+        approvals::ApprovalTemplates,
+        arbitrage::{self, ArbContracts, ArbMarket, ArbitrageBook, PairScan, Venue},
+        attestation::ResponseAttestor,
+        backtest::{self, Position, ThresholdRule},
         balance,
-        price::{self, TokenRegistry},
-        swap,
+        block_scope::{BlockScoped, parse_block_id},
+        bundle::{self, BundleCall},
+        burner::{self, BurnerVault},
+        candles,
+        canonical::{self, CanonicalTokens},
+        chain,
+        compliance::ComplianceScreen,
+        delegation, deposits, distribute,
+        dust::{self, DustFilter},
+        ens::EnsNames,
+        funding::{self, PaymentSource},
+        gas_worth::{self, GasPolicy},
+        gasless::GaslessApi,
+        ladder, native,
+        paper::{self, PaperPortfolio, PaperTrade},
+        param_defaults::ParamDefaults,
+        plan, portfolio_risk, preflight,
+        price::{self, TokenInfo, TokenRegistry},
+        provider_pool::ProviderPool,
+        quote_id::{self, QuoteSigner, QuoteTerms},
+        quotes, rebalance,
+        recipients::RecipientBook,
+        retention::{RetentionPolicy, TokenCache},
+        rpc::{BalancedHttp, RpcProvider},
+        self_test::{self, SelfTestTarget},
+        signatures::{self, SignedPayload},
+        spread::{self, SpreadBand, SpreadLeg, SpreadPair},
+        stuck_tx::{self, StuckTxSettings},
+        swap::{self, SwapSettings},
+        timing::Timings,
+        token_risk,
+        token_watch::{self, TransferFilter},
+        trace, transfer,
+        uniswap::UniswapDeployment,
+        units, validation, warnings,
+        whitelist::{self, RecipientWhitelist},
     },
+    kill_switch::KillSwitch,
+    progress::ProgressReporter,
+    schemas,
     types::{
-        BalanceOut, GetBalanceParams, GetTokenPriceParams, PriceOut, SwapSimOut, SwapTokensParams,
+        ActivityHistoryOut, ActivityReportOut, ApprovalTemplatesOut, ArbPlanOut, ArbScanOut,
+        ArbSkipOut, AuthorizationOut, BacktestOut, BacktestSwapStrategyParams, BalanceOut,
+        BatchErrorOut, BatchItemOut, BatchOut, BundleOut, BurnerWalletOut, CandlesOut,
+        ChainInfoOut, ConvertUnitsOut, ConvertUnitsParams, CreateBurnerWalletParams, DelegationOut,
+        DepositWatchOut, DistributeTokensParams, DistributionOut, EventsOut, GaslessSwapOut,
+        GaslessSwapParams, GetActivityHistoryParams, GetActivityReportParams,
+        GetArbitragePlanParams, GetBalanceParams, GetBalancesParams, GetCandlesParams,
+        GetDelegationParams, GetEventsParams, GetNativePriceParams, GetPaperPortfolioParams,
+        GetPortfolioRiskParams, GetQuoteLadderParams, GetSchemasParams, GetTokenDeploymentsParams,
+        GetTokenPriceParams, GetTokenPricesParams, GetWalletInfoParams, KillSwitchOut,
+        ListApprovalTemplatesParams, ListQuoteCurrenciesParams, ListTokensOut, ListTokensParams,
+        MaintenanceOut, MempoolWatchOut, NativePriceOut, PanicStopParams, PaperFundParams,
+        PaperPortfolioOut, PaperSwapParams, PaperTransferParams, ParsePaymentRequestParams,
+        PaymentRequestOut, PayoutParams, PlanRebalanceParams, PortfolioRiskOut, PriceMode,
+        PriceOut, ProviderPoolOut, QuoteCurrenciesOut, QuoteLadderOut, RebalancePlanOut,
+        ResumeParams, ScanArbitrageParams, SchemasOut, SelfTestOut, SignAuthorizationParams,
+        SimulateBundleParams, SpreadWatchOut, SwapFeesOut, SwapPlanOut, SwapSimOut,
+        SwapTokensParams, SweepOut, SweepToOut, SweepToParams, SweepWalletParams,
+        TokenDeploymentsOut, TokenRiskOut, TokenRiskParams, TokenWatchOut, TraceCallOut,
+        TraceCallParams, TransferFromOut, TransferFromParams, TxWatchOut, VerifySignatureOut,
+        VerifySignatureParams, WalletInfoOut, WarningOut, WatchDepositsParams, WatchMempoolParams,
+        WatchSpreadParams, WatchTokenParams, WatchTransactionParams, WhitelistAddressParams,
+        WhitelistEntryOut, WhitelistOut,
     },
     wallet::WalletManager,
 };
+#[cfg(feature = "gasless")]
+use crate::implementations::gasless::{self, GaslessQuoteRequest};
+#[cfg(feature = "mempool")]
+use crate::implementations::{
+    mempool::{self, MempoolFilter},
+    provider_pool::Transport,
+};
 use ethers::{
-    providers::{Http, Provider},
-    types::Address,
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, BlockNumber, Bytes, H256, TransactionRequest, U256,
+        transaction::eip2718::TypedTransaction,
+    },
 };
-use tokio::sync::RwLock;
-use tracing::{info, instrument};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::str::FromStr;
+use tokio::sync::{RwLock, broadcast};
+use tracing::{info, instrument, warn};
+
+/// Blocks scanned by `get_candles` when no `from_block` is given (~1 hour on mainnet).
+const DEFAULT_CANDLE_LOOKBACK_BLOCKS: u64 = 300;
+const MAX_DEPOSIT_WATCH_SECS: u64 = 300;
+/// Roughly one poll per mainnet block.
+const DEPOSIT_POLL_INTERVAL_SECS: u64 = 12;
+const MAX_TOKEN_WATCH_SECS: u64 = 600;
+const MAX_TOKEN_WATCH_RESULTS: usize = 500;
+const MAX_TX_WATCH_SECS: u64 = 600;
+const MAX_SPREAD_WATCH_SECS: u64 = 600;
+#[cfg(feature = "mempool")]
+const MAX_MEMPOOL_WATCH_SECS: u64 = 60;
+#[cfg(feature = "mempool")]
+const MAX_MEMPOOL_WATCH_RESULTS: usize = 500;
+/// Items accepted by `get_balances` / `get_token_prices` in one call.
+const MAX_BATCH_ITEMS: usize = 50;
+
+/// Shared context that higher layers pass around. Keeps provider, registry, wallet, and config handles.
+#[derive(Clone)]
+pub struct ServiceContext {
+    pub provider: Arc<RpcProvider>,
+    /// Current registry snapshot. Readers clone the inner `Arc`; writers copy on write so
+    /// in-flight requests keep the snapshot they started with.
+    pub registry: Arc<RwLock<Arc<TokenRegistry>>>,
+    pub wallet: Arc<WalletManager>,
+    pub config: Arc<AppConfig>,
+    /// Chain id confirmed against the provider at startup.
+    pub chain_id: u64,
+    pub compliance: Arc<ComplianceScreen>,
+    pub whitelist: Arc<RecipientWhitelist>,
+    pub recipients: Arc<RecipientBook>,
+    pub kill_switch: Arc<KillSwitch>,
+    pub activity: Arc<ActivityLog>,
+    pub ens: Arc<EnsNames>,
+    /// Uniswap V3 contracts resolved for `chain_id` at startup.
+    pub uniswap: UniswapDeployment,
+    /// Keystores behind `create_burner_wallet` / `sweep_wallet`, when configured.
+    pub burners: Option<Arc<BurnerVault>>,
+    /// Per-chain HTTP and WebSocket providers, connected on first use.
+    pub providers: Arc<ProviderPool>,
+    /// Issues and checks the `quote_id` of swap simulations.
+    pub quotes: Arc<QuoteSigner>,
+    /// Virtual balances behind the paper-trading tools.
+    pub paper: Arc<PaperPortfolio>,
+    /// Configured values for params callers leave out.
+    pub param_defaults: Arc<ParamDefaults>,
+    /// Signs tool results when `sign_responses` is on.
+    pub attestor: Option<Arc<ResponseAttestor>>,
+    /// Internal events every subsystem publishes to and observes.
+    pub events: Arc<EventBus>,
+    /// Recency of registry tokens learned at runtime, which bounds how many are kept.
+    pub token_cache: Arc<TokenCache>,
+    /// 0x Gasless API client, when an API key is configured.
+    pub gasless: Option<Arc<GaslessApi>>,
+    /// Opportunities from recent arbitrage scans, for `get_arbitrage_plan`.
+    pub arbitrage: Arc<ArbitrageBook>,
+}
+
+impl ServiceContext {
+    pub fn new(
+        provider: Arc<RpcProvider>,
+        registry: Arc<RwLock<Arc<TokenRegistry>>>,
+        wallet: Arc<WalletManager>,
+        config: Arc<AppConfig>,
+        chain_id: u64,
+    ) -> Self {
+        let kill_switch = Arc::new(KillSwitch::new(config.admin_secret.clone()));
+        let ens = Arc::new(EnsNames::from_config(&config));
+        let providers = Arc::new(ProviderPool::new(
+            chain_id,
+            provider.clone(),
+            config.eth_ws_url.clone(),
+        ));
+        let quotes = Arc::new(QuoteSigner::new(config.quote_ttl_secs));
+        let activity = Arc::new(ActivityLog::new(RetentionPolicy::from_config(&config)));
+        let token_cache = Arc::new(TokenCache::from_config(&config));
+        Self {
+            provider,
+            registry,
+            wallet,
+            config,
+            chain_id,
+            compliance: Arc::new(ComplianceScreen::disabled()),
+            whitelist: Arc::new(RecipientWhitelist::disabled()),
+            recipients: Arc::new(RecipientBook::default()),
+            kill_switch,
+            activity,
+            ens,
+            uniswap: UniswapDeployment::default(),
+            burners: None,
+            providers,
+            quotes,
+            paper: Arc::new(PaperPortfolio::new(chain_id)),
+            param_defaults: Arc::new(ParamDefaults::default()),
+            attestor: None,
+            events: Arc::new(EventBus::default()),
+            token_cache,
+            gasless: None,
+            arbitrage: Arc::new(ArbitrageBook::default()),
+        }
+    }
+
+    pub fn with_compliance(mut self, compliance: ComplianceScreen) -> Self {
+        self.compliance = Arc::new(compliance);
+        self
+    }
+
+    pub fn with_whitelist(mut self, whitelist: RecipientWhitelist) -> Self {
+        self.whitelist = Arc::new(whitelist);
+        self
+    }
+
+    pub fn with_recipients(mut self, recipients: RecipientBook) -> Self {
+        self.recipients = Arc::new(recipients);
+        self
+    }
+
+    pub fn with_uniswap(mut self, uniswap: UniswapDeployment) -> Self {
+        self.uniswap = uniswap;
+        self
+    }
+
+    pub fn with_burners(mut self, burners: Option<BurnerVault>) -> Self {
+        self.burners = burners.map(Arc::new);
+        self
+    }
+
+    pub fn with_providers(mut self, providers: ProviderPool) -> Self {
+        self.providers = Arc::new(providers);
+        self
+    }
+
+    pub fn with_paper(mut self, paper: PaperPortfolio) -> Self {
+        self.paper = Arc::new(paper);
+        self
+    }
+
+    pub fn with_param_defaults(mut self, param_defaults: ParamDefaults) -> Self {
+        self.param_defaults = Arc::new(param_defaults);
+        self
+    }
+
+    pub fn with_attestor(mut self, attestor: Option<ResponseAttestor>) -> Self {
+        self.attestor = attestor.map(Arc::new);
+        self
+    }
+
+    pub fn with_gasless(mut self, gasless: Option<GaslessApi>) -> Self {
+        self.gasless = gasless.map(Arc::new);
+        self
+    }
+}
+
+/// Middle layer that exposes business-level operations while delegating heavy work to implementation modules.
+#[derive(Clone)]
+pub struct ServiceLayer {
+    ctx: Arc<ServiceContext>,
+}
+
+impl ServiceLayer {
+    pub fn new(ctx: Arc<ServiceContext>) -> Self {
+        Self { ctx }
+    }
+
+    /// Balance lookup entry point. Handles optional ERC-20 parameter resolution.
+    #[instrument(skip(self), fields(address = %params.address, token = %params.token.as_deref().unwrap_or("ETH")))]
+    pub async fn get_balance(&self, params: GetBalanceParams) -> AppResult<BalanceOut> {
+        let registry_snapshot = self.snapshot_registry().await;
+        let address = parse_address_or_symbol(&params.address, &registry_snapshot)?;
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let result = balance_at(
+            &scoped,
+            &registry_snapshot,
+            address,
+            params.token.as_deref(),
+        )
+        .await?;
+        info!("balance lookup succeeded");
+        Ok(result)
+    }
+
+    /// Several balances of one holder at a single block; a bad token only fails its own item.
+    #[instrument(skip(self), fields(address = %params.address, tokens = params.tokens.len()))]
+    pub async fn get_balances(&self, params: GetBalancesParams) -> AppResult<BatchOut<BalanceOut>> {
+        check_batch_len(params.tokens.len())?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let address = parse_address_or_symbol(&params.address, &registry_snapshot)?;
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+
+        let mut items = Vec::with_capacity(params.tokens.len());
+        for (index, token) in params.tokens.iter().enumerate() {
+            let result = balance_at(&scoped, &registry_snapshot, address, token.as_deref()).await;
+            items.push(batch_item(index, result));
+        }
+        let out = batch_out(scoped.block_number(), items);
+        info!(
+            "balance batch finished: {} ok, {} failed",
+            out.succeeded, out.failed
+        );
+        Ok(out)
+    }
+
+    /// Price lookup with Chainlink-first policy and Uniswap fallback, or the median of all sources.
+    #[instrument(skip(self), fields(base = %params.base, quote = %params.quote))]
+    pub async fn get_token_price(&self, params: GetTokenPriceParams) -> AppResult<PriceOut> {
+        let started = Instant::now();
+        let base_address = self.resolve_input(&params.base).await?;
+
+        // Ensure registry knows about base token for metadata-driven pricing.
+        self.ensure_registry_token(base_address).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut timings = Timings::default();
+        let mut price = match params.mode {
+            PriceMode::Fallback => {
+                price::resolve_token_price_timed(
+                    scoped.clone(),
+                    &registry_snapshot,
+                    base_address,
+                    params.quote,
+                    &mut timings,
+                )
+                .await?
+            }
+            PriceMode::Median => {
+                price::resolve_median_price(
+                    scoped.clone(),
+                    &registry_snapshot,
+                    base_address,
+                    params.quote,
+                    &mut timings,
+                )
+                .await?
+            }
+        };
+        price.block_number = Some(scoped.block_number());
+        if params.invert {
+            price = price::invert_price(price)?;
+        }
+        if params.debug {
+            price.timings = Some(timings.finish(started.elapsed()));
+        }
+        price.warnings = warnings::price(&price);
+        price
+            .warnings
+            .extend(warnings::unverified_token(&registry_snapshot, base_address));
+
+        info!("price lookup succeeded via {}", price.source);
+        self.publish(ServerEvent::price_tick(&price));
+        Ok(price)
+    }
+
+    /// Prices for several bases in one quote at a single block; failures stay per item.
+    #[instrument(skip(self), fields(bases = params.bases.len(), quote = %params.quote))]
+    pub async fn get_token_prices(
+        &self,
+        params: GetTokenPricesParams,
+    ) -> AppResult<BatchOut<PriceOut>> {
+        check_batch_len(params.bases.len())?;
+        let mut bases = Vec::with_capacity(params.bases.len());
+        for base in &params.bases {
+            bases.push(self.registered_token(base).await);
+        }
+        let registry_snapshot = self.snapshot_registry().await;
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+
+        let mut items = Vec::with_capacity(bases.len());
+        for (index, base) in bases.into_iter().enumerate() {
+            let unverified = base
+                .as_ref()
+                .ok()
+                .and_then(|base| warnings::unverified_token(&registry_snapshot, *base));
+            let result = match base {
+                Ok(base) => {
+                    price::resolve_token_price(
+                        scoped.clone(),
+                        &registry_snapshot,
+                        base,
+                        params.quote,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            let result = result.and_then(|mut price| {
+                price.block_number = Some(scoped.block_number());
+                let mut price = if params.invert {
+                    price::invert_price(price)?
+                } else {
+                    price
+                };
+                price.warnings = warnings::price(&price);
+                price.warnings.extend(unverified);
+                Ok(price)
+            });
+            items.push(batch_item(index, result));
+        }
+        let out = batch_out(scoped.block_number(), items);
+        info!(
+            "price batch finished: {} ok, {} failed",
+            out.succeeded, out.failed
+        );
+        Ok(out)
+    }
+
+    /// Native coin price in fiat from Chainlink alone, never via registry tokens or DEX pools.
+    #[instrument(skip(self))]
+    pub async fn get_native_price(
+        &self,
+        params: GetNativePriceParams,
+    ) -> AppResult<NativePriceOut> {
+        let registry = self.snapshot_registry().await;
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut out = native::native_price(
+            scoped.clone(),
+            registry.feed_decimals(),
+            registry.native(),
+            self.ctx.chain_id,
+            &params.currencies,
+        )
+        .await?;
+        out.block_number = Some(scoped.block_number());
+        // Against the wall clock, so only meaningful for the latest block.
+        if params.block.is_none() {
+            out.warnings
+                .extend(warnings::stale_feed(out.updated_at, whitelist::unix_now()));
+        }
+        info!("native price lookup succeeded");
+        Ok(out)
+    }
+
+    /// Build and simulate Uniswap V3 calldata without broadcasting.
+    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
+    pub async fn swap_tokens(&self, mut params: SwapTokensParams) -> AppResult<SwapSimOut> {
+        let started = Instant::now();
+        let (memo, tags) =
+            activity::annotations(params.memo.take(), std::mem::take(&mut params.tags))?;
+        let (from_token, to_token, signer) = self.authorize_swap(&mut params).await?;
+        let terms = QuoteTerms::new(
+            self.ctx.chain_id,
+            signer.address(),
+            from_token,
+            to_token,
+            &params,
+        );
+        let wallet = signer.address();
+        let quoted_min = self.check_quote(params.quote_id.as_deref(), &terms)?;
+
+        // Pin every read to one block so quotes, gas and the dry-run agree, and dedupe repeats.
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+
+        let debug = params.debug;
+        let amount_in_wei = params.amount_in_wei.clone();
+        let mut timings = Timings::default();
+        let mut result = swap::simulate_swap_timed(
+            scoped.clone(),
+            signer,
+            from_token,
+            to_token,
+            params,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+            &mut timings,
+        )
+        .await?;
+        result.block_number = Some(scoped.block_number());
+        let registry_snapshot = self.snapshot_registry().await;
+        result.decimals_warning =
+            registry_snapshot.check_decimals(to_token, result.to_decimals as u8);
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &[from_token, to_token]);
+        result.warnings.extend(warnings::decimals_mismatch(
+            result.decimals_warning.as_ref(),
+        ));
+        result.warnings.extend(warnings::mev_risk(&result.mev_risk));
+        let gas_cost = result
+            .fees
+            .as_ref()
+            .and_then(|fees| U256::from_dec_str(&fees.estimated_cost_wei).ok());
+        result.warnings.extend(
+            self.check_gas_worth(
+                &scoped,
+                &registry_snapshot,
+                "swap",
+                to_token,
+                &result.amount_out_estimate,
+                gas_cost,
+            )
+            .await?,
+        );
+        let mut shortfall = self
+            .preflight_gas(
+                &scoped,
+                wallet,
+                result.fees.as_ref(),
+                U256::zero(),
+                "the swap",
+            )
+            .await?;
+        if self.ctx.gasless.is_some()
+            && let Some(warning) = &mut shortfall
+        {
+            warning
+                .message
+                .push_str("; gasless_swap can make the trade with a relayer paying the gas");
+        }
+        result.warnings.extend(shortfall);
+        let amount_in = registry_snapshot
+            .info_by_address(from_token)
+            .zip(U256::from_dec_str(&amount_in_wei).ok())
+            .map(|(from, raw)| balance::format_with_decimals(&raw, from.decimals as u32))
+            .and_then(|text| Decimal::from_str(&text).ok());
+        let amount_out = Decimal::from_str(&result.amount_out_estimate).ok();
+        result.execution_price = amount_in
+            .zip(amount_out)
+            .and_then(|(amount_in, amount_out)| amount_out.checked_div(amount_in))
+            .map(|price| price.round_dp(18).normalize().to_string());
+        if let Ok(recipient) = result.recipient.parse::<Address>() {
+            result.recipient_ens = self.ens_name(recipient).await;
+        }
+        if let Some(quoted) = quoted_min {
+            quote_id::ensure_min_out(quoted, &result.amount_out_min_raw)?;
+        }
+        let (quote_id, expires_at) =
+            self.ctx
+                .quotes
+                .issue(&terms, &result.amount_out_min_raw, whitelist::unix_now());
+        result.quote_id = Some(quote_id);
+        result.quote_expires_at = Some(expires_at);
+        if debug {
+            result.timings = Some(timings.finish(started.elapsed()));
+        }
+        self.record_swap(from_token, to_token, &amount_in_wei, &result, memo, tags)
+            .await;
+
+        info!("swap simulation succeeded");
+        Ok(result)
+    }
+
+    /// List every transaction (wrap, approve, swap) the wallet needs for a swap, in order.
+    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
+    pub async fn plan_swap(&self, mut params: SwapTokensParams) -> AppResult<SwapPlanOut> {
+        let annotations =
+            activity::annotations(params.memo.take(), std::mem::take(&mut params.tags))?;
+        let (from_token, to_token, signer) = self.authorize_swap(&mut params).await?;
+        let terms = QuoteTerms::new(
+            self.ctx.chain_id,
+            signer.address(),
+            from_token,
+            to_token,
+            &params,
+        );
+        let quoted_min = self.check_quote(params.quote_id.as_deref(), &terms)?;
+        let amount_in_wei = params.amount_in_wei.clone();
+        let wallet = signer.address();
+        let registry_snapshot = self.snapshot_registry().await;
+        let weth = registry_snapshot.wrapped_native().map(|info| info.address);
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut plan = plan::plan_swap(
+            scoped.clone(),
+            signer,
+            from_token,
+            to_token,
+            weth,
+            params,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        if let Some(quoted) = quoted_min {
+            quote_id::ensure_min_out(quoted, &plan.amount_out_min_raw)?;
+        }
+        plan.block_number = Some(scoped.block_number());
+        plan.warnings = warnings::unverified_tokens(&registry_snapshot, &[from_token, to_token]);
+        let gas_limit = U256::from_dec_str(&plan.total_gas_limit).unwrap_or_default();
+        let fees = swap::fee_preview(&scoped, gas_limit, gas_limit).await;
+        // Wrapping sends ETH, so the plan needs it on top of the gas.
+        let value = plan
+            .steps
+            .iter()
+            .filter_map(|step| U256::from_dec_str(&step.value_wei).ok())
+            .fold(U256::zero(), |total, value| total + value);
+        let shortfall = self
+            .preflight_gas(&scoped, wallet, fees.as_ref(), value, "the plan")
+            .await?;
+        plan.warnings.extend(shortfall);
+        let gas_cost = fees
+            .as_ref()
+            .and_then(|fees| U256::from_dec_str(&fees.estimated_cost_wei).ok());
+        plan.warnings.extend(
+            self.check_gas_worth(
+                &scoped,
+                &registry_snapshot,
+                "swap plan",
+                to_token,
+                &plan.amount_out_estimate,
+                gas_cost,
+            )
+            .await?,
+        );
+
+        self.record_write(
+            "plan_swap",
+            format!(
+                "swap {amount_in_wei} base units of {} for at least {} {}",
+                token_label(&registry_snapshot, from_token),
+                plan.amount_out_min,
+                token_label(&registry_snapshot, to_token)
+            ),
+            annotations,
+        );
+
+        info!("swap plan built with {} steps", plan.steps.len());
+        Ok(plan)
+    }
+
+    /// Quote a swap through the 0x Gasless API, where the relayer pays gas and takes its fee in
+    /// the sold token, and sign its orders when asked. Nothing is submitted.
+    #[cfg(feature = "gasless")]
+    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
+    pub async fn gasless_swap(&self, params: GaslessSwapParams) -> AppResult<GaslessSwapOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let api = self.ctx.gasless.clone().ok_or_else(|| {
+            AppError::Config("gasless_swap requires GASLESS_API_KEY / gasless_api_key".into())
+        })?;
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("gasless_swap requires PRIVATE_KEY/signing config".into())
+        })?;
+        let from_token = self.registered_token(&params.from_token).await?;
+        let to_token = self.registered_token(&params.to_token).await?;
+        let sell_amount = U256::from_dec_str(&params.amount_in_wei).map_err(|_| {
+            AppError::InvalidInput(format!("invalid numeric value: {}", params.amount_in_wei))
+        })?;
+        if sell_amount.is_zero() {
+            return Err(AppError::InvalidInput(
+                "amount_in_wei must be greater than zero".into(),
+            ));
+        }
+        if params.slippage_bps > 10_000 {
+            return Err(AppError::InvalidInput(
+                "slippage cannot exceed 100% (10_000 bps)".into(),
+            ));
+        }
+        self.ctx
+            .compliance
+            .enforce(
+                &[("from_token", from_token), ("to_token", to_token)],
+                params.compliance_override,
+            )
+            .await?;
+
+        let registry_snapshot = self.snapshot_registry().await;
+        let lookup = |address: Address| {
+            registry_snapshot
+                .info_by_address(address)
+                .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {address:#x}")))
+        };
+        let (sell_token, buy_token) = (lookup(from_token)?, lookup(to_token)?);
+        let request = GaslessQuoteRequest {
+            chain_id: self.ctx.chain_id,
+            sell_token,
+            buy_token,
+            sell_amount,
+            taker: signer.address(),
+            slippage_bps: params.slippage_bps,
+        };
+        let quote = api.quote(&request).await?;
+        if let Some(quoted) = quote.sell_amount.as_deref()
+            && U256::from_dec_str(quoted).ok() != Some(sell_amount)
+        {
+            return Err(AppError::Swap(format!(
+                "gasless quote sells {quoted}, not the requested {sell_amount}"
+            )));
+        }
+        if !quote.liquidity_available {
+            return Err(AppError::Swap(format!(
+                "no gasless liquidity for {} -> {}",
+                sell_token.symbol, buy_token.symbol
+            )));
+        }
+        let trade = quote
+            .trade
+            .as_ref()
+            .ok_or_else(|| AppError::Swap("gasless quote has no trade to sign".into()))?;
+
+        let signing = params.sign.then_some(&signer);
+        let approval = match &quote.approval {
+            Some(approval) => Some(approval.order(&request, api.settlers(), signing).await?),
+            None => None,
+        };
+        let (trade, trade_submission) = trade.order(&request, api.settlers(), signing).await?;
+        let submission = params.sign.then(|| {
+            let mut body = serde_json::json!({
+                "chainId": self.ctx.chain_id,
+                "trade": trade_submission,
+            });
+            if let Some((_, approval)) = &approval {
+                body["approval"] = approval.clone();
+            }
+            body
+        });
+
+        let native = balance::resolve_balance(
+            self.ctx.provider.clone(),
+            signer.address(),
+            None,
+            &registry_snapshot.native().symbol,
+        )
+        .await?;
+        let format_buy = |raw: &Option<String>| {
+            let raw = raw.clone().unwrap_or_default();
+            let formatted = U256::from_dec_str(&raw)
+                .map(|value| balance::format_with_decimals(&value, buy_token.decimals as u32))
+                .unwrap_or_default();
+            (raw, formatted)
+        };
+        let (buy_amount_raw, buy_amount) = format_buy(&quote.buy_amount);
+        let (min_buy_amount_raw, min_buy_amount) = format_buy(&quote.min_buy_amount);
+        let mut warnings = gasless::issue_warnings(&quote.issues, sell_token);
+        warnings.extend(warnings::unverified_tokens(
+            &registry_snapshot,
+            &[from_token, to_token],
+        ));
+
+        self.record_write(
+            "gasless_swap",
+            format!(
+                "swap {sell_amount} base units of {} for at least {min_buy_amount} {}",
+                sell_token.symbol, buy_token.symbol
+            ),
+            annotations,
+        );
+
+        info!("gasless quote built, signed: {}", params.sign);
+        Ok(GaslessSwapOut {
+            chain_id: self.ctx.chain_id,
+            taker: format!("{:#x}", signer.address()),
+            from_token: format!("{from_token:#x}"),
+            to_token: format!("{to_token:#x}"),
+            sell_amount_raw: sell_amount.to_string(),
+            buy_amount_raw,
+            buy_amount,
+            min_buy_amount_raw,
+            min_buy_amount,
+            fees: gasless::fees_out(&quote.fees),
+            approval: approval.map(|(order, _)| order),
+            trade,
+            submit_url: submission.is_some().then(|| api.submit_url()),
+            submission,
+            native_balance_wei: native.raw,
+            warnings,
+        })
+    }
+
+    #[cfg(not(feature = "gasless"))]
+    pub async fn gasless_swap(&self, _params: GaslessSwapParams) -> AppResult<GaslessSwapOut> {
+        Err(AppError::Config(
+            "gasless_swap requires building with the `gasless` feature".into(),
+        ))
+    }
+
+    /// Simulate moving tokens the wallet has been approved for (`transferFrom`).
+    #[instrument(skip(self), fields(token = %params.token, from = %params.from))]
+    pub async fn transfer_from(&self, params: TransferFromParams) -> AppResult<TransferFromOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let token = self.resolve_input(&params.token).await?;
+        self.ensure_registry_token(token).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let decimals = registry_snapshot
+            .info_by_address(token)
+            .map(|info| info.decimals)
+            .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {token:#x}")))?;
+        let owner = params.from.parse::<Address>().map_err(|_| {
+            AppError::InvalidInput(format!(
+                "invalid from address: {}{}",
+                params.from,
+                validation::explain(&params.from, [])
+            ))
+        })?;
+        let amount = U256::from_dec_str(&params.amount_wei).map_err(|_| {
+            AppError::InvalidInput(format!("invalid numeric value: {}", params.amount_wei))
+        })?;
+
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("transfer_from requires PRIVATE_KEY/signing config".into())
+        })?;
+        let recipient = self
+            .ctx
+            .recipients
+            .resolve(Some(&params.to), signer.address())?;
+        if recipient != signer.address() && !self.ctx.recipients.is_alias(recipient) {
+            self.ctx.whitelist.check(recipient, whitelist::unix_now())?;
+        }
+        self.ctx
+            .compliance
+            .enforce(
+                &[("token", token), ("from", owner), ("recipient", recipient)],
+                params.compliance_override,
+            )
+            .await?;
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut result = transfer::simulate_transfer_from(
+            scoped.clone(),
+            signer.address(),
+            token,
+            decimals,
+            owner,
+            recipient,
+            amount,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        let gas_limit = U256::from_dec_str(&result.gas_limit).unwrap_or_default();
+        let fees = swap::fee_preview(&scoped, gas_limit, gas_limit).await;
+        let shortfall = self
+            .preflight_gas(
+                &scoped,
+                signer.address(),
+                fees.as_ref(),
+                U256::zero(),
+                "the transfer",
+            )
+            .await?;
+        result.block_number = Some(scoped.block_number());
+        result.from_ens = self.ens_name(owner).await;
+        result.to_ens = self.ens_name(recipient).await;
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &[token]);
+        result.warnings.extend(shortfall);
+        self.record_write(
+            "transfer_from",
+            format!(
+                "transfer {} {} from {} to {}",
+                result.amount,
+                token_label(&registry_snapshot, token),
+                result.from,
+                result.to
+            ),
+            annotations,
+        );
+
+        info!("transferFrom simulation succeeded");
+        Ok(result)
+    }
+
+    /// Plan paying many recipients from the wallet, as separate transfers or one Disperse call.
+    #[instrument(skip(self), fields(token = %params.token, recipients = params.payouts.len()))]
+    pub async fn distribute_tokens(
+        &self,
+        params: DistributeTokensParams,
+    ) -> AppResult<DistributionOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let token = self.resolve_input(&params.token).await?;
+        self.ensure_registry_token(token).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let decimals = registry_snapshot
+            .info_by_address(token)
+            .map(|info| info.decimals)
+            .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {token:#x}")))?;
+        let disperse = self.disperse_address()?;
+
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("distribute_tokens requires PRIVATE_KEY/signing config".into())
+        })?;
+        let now = whitelist::unix_now();
+        let mut payouts = Vec::with_capacity(params.payouts.len());
+        for payout in &params.payouts {
+            let recipient = self
+                .ctx
+                .recipients
+                .resolve(Some(&payout.to), signer.address())?;
+            if recipient != signer.address() && !self.ctx.recipients.is_alias(recipient) {
+                self.ctx.whitelist.check(recipient, now)?;
+            }
+            let amount = U256::from_dec_str(&payout.amount_wei).map_err(|_| {
+                AppError::InvalidInput(format!("invalid numeric value: {}", payout.amount_wei))
+            })?;
+            payouts.push((recipient, amount));
+        }
+        let mut screened = vec![("token", token)];
+        screened.extend(
+            payouts
+                .iter()
+                .map(|(recipient, _)| ("recipient", *recipient)),
+        );
+        self.ctx
+            .compliance
+            .enforce(&screened, params.compliance_override)
+            .await?;
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut result = distribute::plan_distribution(
+            scoped.clone(),
+            signer.address(),
+            token,
+            decimals,
+            &payouts,
+            params.mode,
+            disperse,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        let shortfall = self
+            .preflight_gas(
+                &scoped,
+                signer.address(),
+                result.fees.as_ref(),
+                U256::zero(),
+                "the distribution",
+            )
+            .await?;
+        result.block_number = Some(scoped.block_number());
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &[token]);
+        result.warnings.extend(shortfall);
+        self.record_write(
+            "distribute_tokens",
+            format!(
+                "distribute {} {} to {} recipients",
+                result.total_amount,
+                token_label(&registry_snapshot, token),
+                result.recipients
+            ),
+            annotations,
+        );
+
+        info!("distribution planned with {} steps", result.steps.len());
+        Ok(result)
+    }
+
+    /// Swaps that bring the wallet's holdings back to target weights, quoted but not built.
+    #[instrument(skip(self), fields(targets = params.targets.len()))]
+    pub async fn plan_rebalance(&self, params: PlanRebalanceParams) -> AppResult<RebalancePlanOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let owner = match params.address.as_deref() {
+            Some(address) => self.resolve_input(address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let mut targets = Vec::with_capacity(params.targets.len());
+        for target in &params.targets {
+            let token = self.registered_token(&target.token).await?;
+            targets.push((token, target.weight_bps));
+        }
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let mut result = rebalance::plan_rebalance(
+            scoped.clone(),
+            &registry_snapshot,
+            owner,
+            &targets,
+            params.tolerance_bps,
+            params.slippage_bps,
+            params.fee,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        result.block_number = Some(scoped.block_number());
+        let tokens: Vec<Address> = targets.iter().map(|(token, _)| *token).collect();
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &tokens);
+        info!("rebalance plan built with {} swaps", result.swaps.len());
+        Ok(result)
+    }
+
+    /// Plan consolidating the wallet's small balances into one token, skipping dust not worth
+    /// its gas.
+    #[instrument(skip(self), fields(target = %params.target))]
+    pub async fn sweep_to(&self, params: SweepToParams) -> AppResult<SweepToOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let owner = match params.address.as_deref() {
+            Some(address) => self.resolve_input(address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let target = self.registered_token(&params.target).await?;
+        let mut requested = Vec::new();
+        for token in params.tokens.iter().flatten() {
+            requested.push(self.registered_token(token).await?);
+        }
+        let filter = DustFilter {
+            min_value_usd: parse_usd("min_value_usd", &params.min_value_usd)?,
+            max_value_usd: params
+                .max_value_usd
+                .as_deref()
+                .map(|max| parse_usd("max_value_usd", max))
+                .transpose()?,
+            max_gas_bps: params
+                .max_gas_bps
+                .or(self.ctx.config.gas_worthiness_bps)
+                .unwrap_or(dust::DEFAULT_MAX_GAS_BPS),
+        };
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let lookup = |address: Address| {
+            registry_snapshot
+                .info_by_address(address)
+                .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {address:#x}")))
+        };
+        let tokens = match params.tokens {
+            Some(_) => requested
+                .iter()
+                .map(|token| lookup(*token))
+                .collect::<AppResult<_>>()?,
+            None => registry_snapshot.listed_tokens(),
+        };
+        let mut result = dust::plan_sweep_to(
+            scoped.clone(),
+            &registry_snapshot,
+            owner,
+            lookup(target)?,
+            &tokens,
+            &filter,
+            params.slippage_bps,
+            params.fee,
+            SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        result.block_number = Some(scoped.block_number());
+        let swept: Vec<Address> = std::iter::once(target)
+            .chain(tokens.iter().map(|info| info.address))
+            .collect();
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &swept);
+        self.record_write(
+            "sweep_to",
+            format!(
+                "sweep {} balances into {}",
+                result.swaps.len(),
+                result.target
+            ),
+            annotations,
+        );
+        info!("dust sweep planned with {} swaps", result.swaps.len());
+        Ok(result)
+    }
+
+    /// Quote one pool at several sizes so callers can see how price degrades with size.
+    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
+    pub async fn get_quote_ladder(
+        &self,
+        params: GetQuoteLadderParams,
+    ) -> AppResult<QuoteLadderOut> {
+        let from_token = self.resolve_input(&params.from_token).await?;
+        let to_token = self.resolve_input(&params.to_token).await?;
+
+        // Sizes are given in human units, so both tokens need known decimals.
+        self.ensure_registry_token(from_token).await?;
+        self.ensure_registry_token(to_token).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let lookup = |address: Address| {
+            registry_snapshot
+                .info_by_address(address)
+                .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {address:#x}")))
+        };
+        let (from, to) = (lookup(from_token)?, lookup(to_token)?);
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut ladder = ladder::quote_ladder(
+            scoped.clone(),
+            self.ctx.uniswap.quoter,
+            from,
+            to,
+            &params.sizes,
+            params.fee,
+        )
+        .await?;
+        ladder.block_number = Some(scoped.block_number());
+        ladder.warnings = warnings::unverified_tokens(&registry_snapshot, &[from_token, to_token]);
+
+        info!("quote ladder built with {} levels", ladder.levels.len());
+        Ok(ladder)
+    }
+
+    /// Aggregate on-chain Uniswap swaps into OHLCV candles.
+    #[instrument(skip(self), fields(base = %params.base, quote = %params.quote))]
+    pub async fn get_candles(&self, params: GetCandlesParams) -> AppResult<CandlesOut> {
+        let base_token = self.resolve_input(&params.base).await?;
+        let quote_token = self.resolve_input(&params.quote).await?;
+        self.ensure_registry_token(base_token).await?;
+        self.ensure_registry_token(quote_token).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let lookup = |address: Address| {
+            registry_snapshot
+                .info_by_address(address)
+                .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {address:#x}")))
+        };
+        let (base, quote) = (lookup(base_token)?, lookup(quote_token)?);
+
+        let to_block = match params.to_block {
+            Some(number) => number,
+            None => self.pin_block(None).await?.block_number(),
+        };
+        let from_block = params
+            .from_block
+            .unwrap_or_else(|| to_block.saturating_sub(DEFAULT_CANDLE_LOOKBACK_BLOCKS - 1));
+
+        let mut result = candles::build_candles(
+            self.ctx.provider.clone(),
+            self.ctx.uniswap.factory,
+            base,
+            quote,
+            params.fee,
+            from_block,
+            to_block,
+            params.interval_secs,
+        )
+        .await?;
+        result.warnings =
+            warnings::unverified_tokens(&registry_snapshot, &[base_token, quote_token]);
+        info!(
+            "built {} candles from {} swaps",
+            result.candles.len(),
+            result.trades
+        );
+        Ok(result)
+    }
+
+    /// Replay a buy-below/sell-above rule over historical prices and report its hypothetical
+    /// trades and P&L.
+    #[instrument(skip(self, progress), fields(base = %params.base, from_block = params.from_block))]
+    pub async fn backtest_swap_strategy(
+        &self,
+        params: BacktestSwapStrategyParams,
+        progress: ProgressReporter,
+    ) -> AppResult<BacktestOut> {
+        let rule = ThresholdRule::parse(params.buy_below.as_deref(), params.sell_above.as_deref())?;
+        let start = Position {
+            base: params
+                .initial_base
+                .as_deref()
+                .map(|raw| backtest::parse_positive("initial_base", raw))
+                .transpose()?
+                .unwrap_or_default(),
+            quote: backtest::parse_positive("initial_quote", &params.initial_quote)?,
+        };
+        if start == Position::default() {
+            return Err(AppError::InvalidInput(
+                "initial_quote and initial_base cannot both be zero".into(),
+            ));
+        }
+        let base = self.registered_token(&params.base).await?;
+        let to_block = match params.to_block {
+            Some(number) => number,
+            None => self.pin_block(None).await?.block_number(),
+        };
+        let blocks = backtest::sample_blocks(params.from_block, to_block, params.step_blocks)?;
+
+        let registry_snapshot = self.snapshot_registry().await;
+        let history = backtest::price_history(
+            self.ctx.provider.clone(),
+            &registry_snapshot,
+            base,
+            params.quote,
+            &blocks,
+            &progress,
+        )
+        .await?;
+        let (end, trades) = backtest::run(&history, rule, start, params.fee_bps)?;
+        let symbol = registry_snapshot
+            .info_by_address(base)
+            .map(|info| info.symbol.clone())
+            .unwrap_or_else(|| format!("{base:#x}"));
+        let mut result = backtest::summarize(
+            symbol,
+            registry_snapshot.quote_symbol(params.quote),
+            params.step_blocks,
+            &history,
+            start,
+            end,
+            trades,
+        )?;
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &[base]);
+        if let Some(dex) = history
+            .iter()
+            .find(|point| !point.source.contains("chainlink"))
+        {
+            result.warnings.push(warnings::warning(
+                "dex_price",
+                format!(
+                    "no Chainlink feed priced {} at block {}; those samples come from pool quotes, which thin liquidity or a single large trade can move",
+                    result.base, dex.block_number
+                ),
+            ));
+        }
+
+        info!(
+            "backtest replayed {} samples with {} trades",
+            result.samples,
+            result.trades.len()
+        );
+        Ok(result)
+    }
+
+    /// Value a wallet's holdings and measure their concentration, stablecoin share and
+    /// volatility over daily historical prices.
+    #[instrument(skip(self, progress), fields(lookback_days = params.lookback_days))]
+    pub async fn get_portfolio_risk(
+        &self,
+        params: GetPortfolioRiskParams,
+        progress: ProgressReporter,
+    ) -> AppResult<PortfolioRiskOut> {
+        let owner = match params.address.as_deref() {
+            Some(address) => self.resolve_input(address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let mut requested = Vec::new();
+        for token in params.tokens.iter().flatten() {
+            requested.push(self.registered_token(token).await?);
+        }
+        let block_number = self
+            .pin_block(params.block.as_deref())
+            .await?
+            .block_number();
+
+        let registry_snapshot = self.snapshot_registry().await;
+        let tokens = match params.tokens {
+            Some(_) => requested
+                .iter()
+                .map(|token| {
+                    registry_snapshot.info_by_address(*token).ok_or_else(|| {
+                        AppError::InvalidInput(format!("unsupported token: {token:#x}"))
+                    })
+                })
+                .collect::<AppResult<_>>()?,
+            None => registry_snapshot.listed_tokens(),
+        };
+        let mut result = portfolio_risk::assess(
+            self.ctx.provider.clone(),
+            &registry_snapshot,
+            owner,
+            &tokens,
+            block_number,
+            params.lookback_days,
+            &progress,
+        )
+        .await?;
+        let held: Vec<Address> = result
+            .holdings
+            .iter()
+            .filter_map(|holding| holding.token.as_deref()?.parse().ok())
+            .collect();
+        let mut warnings = warnings::unverified_tokens(&registry_snapshot, &held);
+        warnings.append(&mut result.warnings);
+        result.warnings = warnings;
+
+        info!(
+            "portfolio risk assessed {} holdings over {} samples",
+            result.holdings.len(),
+            result.samples
+        );
+        Ok(result)
+    }
+
+    /// Compare round trips on each pair across the Uniswap V3 fee tiers and V2 forks, keeping
+    /// and publishing every one that nets more than its gas.
+    #[instrument(skip(self))]
+    pub async fn scan_arbitrage(&self, params: ScanArbitrageParams) -> AppResult<ArbScanOut> {
+        let pairs = params
+            .pairs
+            .unwrap_or_else(|| self.ctx.config.arb_pairs.clone());
+        if pairs.is_empty() {
+            return Err(AppError::InvalidInput(
+                "no pairs given and ARB_PAIRS is empty".into(),
+            ));
+        }
+        if pairs.len() > arbitrage::MAX_ARB_PAIRS {
+            return Err(AppError::InvalidInput(format!(
+                "at most {} pairs can be scanned at once",
+                arbitrage::MAX_ARB_PAIRS
+            )));
+        }
+        let trade_usd = params.trade_usd.unwrap_or(self.ctx.config.arb_trade_usd);
+        if trade_usd == 0 {
+            return Err(AppError::InvalidInput(
+                "trade_usd must be greater than zero".into(),
+            ));
+        }
+        let mut tokens = Vec::with_capacity(pairs.len());
+        for pair in &pairs {
+            let (base, quote) = arbitrage::split_pair(pair)?;
+            let base = self.registered_token(base).await?;
+            let quote = self.registered_token(quote).await?;
+            if base == quote {
+                return Err(AppError::InvalidInput(format!(
+                    "{pair} trades a token against itself"
+                )));
+            }
+            tokens.push((base, quote));
+        }
+
+        let scoped = self.pin_block(None).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let (max_fee_per_gas, _) = scoped
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to estimate EIP-1559 fees: {err}")))?;
+        let market = ArbMarket {
+            block_number: scoped.block_number(),
+            found_at: whitelist::unix_now(),
+            trade_usd: Decimal::from(trade_usd),
+            max_fee_per_gas,
+            native_usd: gas_worth::native_usd_price(scoped.clone(), &registry_snapshot).await?,
+        };
+        let contracts = ArbContracts {
+            quoter: self.ctx.uniswap.quoter,
+            router: self.ctx.uniswap.router,
+        };
+        let venues = Venue::all(registry_snapshot.v2_venues());
+
+        let mut opportunities = Vec::new();
+        let mut skipped = Vec::new();
+        for (pair, (base, quote)) in pairs.iter().zip(&tokens) {
+            let (Some(base), Some(quote)) = (
+                registry_snapshot.info_by_address(*base),
+                registry_snapshot.info_by_address(*quote),
+            ) else {
+                return Err(AppError::InvalidInput(format!("unsupported pair: {pair}")));
+            };
+            let scan = arbitrage::scan_pair(
+                scoped.clone(),
+                &registry_snapshot,
+                &contracts,
+                &venues,
+                &market,
+                base,
+                quote,
+            )
+            .await;
+            match scan {
+                Ok(PairScan::Found(plan)) => {
+                    let plan = self.ctx.arbitrage.record(*plan);
+                    self.publish(ServerEvent::ArbOpportunity {
+                        opportunity: plan.opportunity.clone(),
+                    });
+                    opportunities.push(plan.opportunity);
+                }
+                Ok(PairScan::NoEdge(reason)) => skipped.push(ArbSkipOut {
+                    pair: pair.clone(),
+                    reason,
+                }),
+                Err(err) => skipped.push(ArbSkipOut {
+                    pair: pair.clone(),
+                    reason: err.to_string(),
+                }),
+            }
+        }
+        let scanned: Vec<Address> = tokens
+            .iter()
+            .flat_map(|(base, quote)| [*base, *quote])
+            .collect();
+
+        info!(
+            "arbitrage scan found {} opportunities across {} pairs",
+            opportunities.len(),
+            pairs.len()
+        );
+        Ok(ArbScanOut {
+            block_number: market.block_number,
+            trade_usd,
+            venues: venues.iter().map(Venue::name).collect(),
+            opportunities,
+            skipped,
+            warnings: warnings::unverified_tokens(&registry_snapshot, &scanned),
+        })
+    }
+
+    /// The legs of an opportunity a recent scan found, flagged when the chain has moved on.
+    #[instrument(skip(self), fields(id = %params.id))]
+    pub async fn get_arbitrage_plan(
+        &self,
+        params: GetArbitragePlanParams,
+    ) -> AppResult<ArbPlanOut> {
+        let mut plan = self.ctx.arbitrage.get(&params.id).ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "unknown or expired arbitrage id {}; only the latest {} are kept",
+                params.id,
+                arbitrage::ARB_BOOK_CAPACITY
+            ))
+        })?;
+        let head = self.pin_block(None).await?.block_number();
+        let age = head.saturating_sub(plan.opportunity.block_number);
+        if age > 0 {
+            plan.warnings.push(warnings::warning(
+                "stale_quotes",
+                format!(
+                    "quoted {age} blocks ago at block {}; prices may have moved, so re-quote before trading",
+                    plan.opportunity.block_number
+                ),
+            ));
+        }
+        Ok(plan)
+    }
+
+    /// Run `scan_arbitrage` over `arb_pairs` every `arb_scan_interval_secs`; opportunities reach
+    /// clients as `arb_opportunity` events. `None` when no pairs are configured.
+    pub fn spawn_arbitrage_scanner(&self) -> Option<tokio::task::JoinHandle<()>> {
+        if self.ctx.config.arb_pairs.is_empty() {
+            return None;
+        }
+        let service = self.clone();
+        let period = std::time::Duration::from_secs(self.ctx.config.arb_scan_interval_secs);
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = service.scan_arbitrage(ScanArbitrageParams::default()).await {
+                    warn!("arbitrage scan failed: {err}");
+                }
+            }
+        }))
+    }
+
+    /// Report the verified chain, failing if the provider has drifted to another network.
+    #[instrument(skip(self))]
+    pub async fn get_chain_info(&self) -> AppResult<ChainInfoOut> {
+        let info = chain::resolve_chain_info(self.ctx.provider.clone(), self.ctx.chain_id).await?;
+        info!("chain info lookup succeeded");
+        Ok(info)
+    }
+
+    /// Connection state and health of the per-chain providers.
+    #[instrument(skip(self))]
+    pub async fn get_provider_pool(&self) -> AppResult<ProviderPoolOut> {
+        Ok(self.ctx.providers.status().await)
+    }
+
+    /// Watch pending transactions touching the wallet (or listed tokens) for a bounded window.
+    #[cfg(feature = "mempool")]
+    #[instrument(skip(self, progress), fields(duration_secs = params.duration_secs))]
+    pub async fn watch_mempool(
+        &self,
+        params: WatchMempoolParams,
+        progress: ProgressReporter,
+    ) -> AppResult<MempoolWatchOut> {
+        if self.ctx.config.eth_ws_url.is_none() {
+            return Err(AppError::Config(
+                "watch_mempool requires ETH_WS_URL / eth_ws_url".into(),
+            ));
+        }
+
+        if params.duration_secs == 0 || params.duration_secs > MAX_MEMPOOL_WATCH_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "duration_secs must be between 1 and {MAX_MEMPOOL_WATCH_SECS}"
+            )));
+        }
+        if params.max_results == 0 || params.max_results > MAX_MEMPOOL_WATCH_RESULTS {
+            return Err(AppError::InvalidInput(format!(
+                "max_results must be between 1 and {MAX_MEMPOOL_WATCH_RESULTS}"
+            )));
+        }
+
+        let wallet = match params.address {
+            Some(address) => self.resolve_input(&address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let mut tokens = Vec::with_capacity(params.tokens.len());
+        for token in &params.tokens {
+            tokens.push(self.resolve_input(token).await?);
+        }
+
+        let chain_id = self.ctx.chain_id;
+        let provider = self.ctx.providers.ws(chain_id).await?;
+        let result = mempool::watch_pending(
+            provider,
+            MempoolFilter { wallet, tokens },
+            std::time::Duration::from_secs(params.duration_secs),
+            params.max_results,
+            &progress,
+        )
+        .await;
+        let result = match result {
+            Ok(result) => {
+                self.ctx.providers.record_success(chain_id);
+                result
+            }
+            Err(err) => {
+                self.ctx
+                    .providers
+                    .record_failure(chain_id, Transport::Ws, &err);
+                return Err(err);
+            }
+        };
+
+        for tx in &result.matches {
+            self.publish(ServerEvent::PendingTx {
+                wallet: result.wallet.clone(),
+                tx: tx.clone(),
+            });
+        }
+        info!("mempool watch finished with {} matches", result.matches.len());
+        Ok(result)
+    }
+
+    #[cfg(not(feature = "mempool"))]
+    pub async fn watch_mempool(
+        &self,
+        _params: WatchMempoolParams,
+        _progress: ProgressReporter,
+    ) -> AppResult<MempoolWatchOut> {
+        Err(AppError::Config(
+            "watch_mempool requires building with the `mempool` feature".into(),
+        ))
+    }
+
+    /// Long-poll for ETH and ERC-20 deposits into the wallet so agents can wait for funding.
+    #[instrument(skip(self, progress), fields(duration_secs = params.duration_secs))]
+    pub async fn watch_deposits(
+        &self,
+        params: WatchDepositsParams,
+        progress: ProgressReporter,
+    ) -> AppResult<DepositWatchOut> {
+        if params.duration_secs == 0 || params.duration_secs > MAX_DEPOSIT_WATCH_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "duration_secs must be between 1 and {MAX_DEPOSIT_WATCH_SECS}"
+            )));
+        }
+
+        let wallet = match params.address {
+            Some(address) => self.resolve_input(&address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+
+        let registry_snapshot = self.snapshot_registry().await;
+        let result = deposits::watch_deposits(
+            self.ctx.provider.clone(),
+            &registry_snapshot,
+            wallet,
+            params.from_block,
+            std::time::Duration::from_secs(params.duration_secs),
+            std::time::Duration::from_secs(DEPOSIT_POLL_INTERVAL_SECS),
+            &progress,
+        )
+        .await?;
+
+        for deposit in &result.deposits {
+            self.publish(ServerEvent::Deposit {
+                wallet: result.wallet.clone(),
+                deposit: deposit.clone(),
+            });
+        }
+        info!(
+            "deposit watch saw {} deposits up to block {}",
+            result.deposits.len(),
+            result.last_block
+        );
+        Ok(result)
+    }
+
+    /// Watch one token's transfers to or from an address for a bounded window, publishing each
+    /// as an event when it is seen.
+    #[instrument(skip(self, progress), fields(token = %params.token, duration_secs = params.duration_secs))]
+    pub async fn watch_token(
+        &self,
+        params: WatchTokenParams,
+        progress: ProgressReporter,
+    ) -> AppResult<TokenWatchOut> {
+        if params.duration_secs == 0 || params.duration_secs > MAX_TOKEN_WATCH_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "duration_secs must be between 1 and {MAX_TOKEN_WATCH_SECS}"
+            )));
+        }
+        if params.max_results == 0 || params.max_results > MAX_TOKEN_WATCH_RESULTS {
+            return Err(AppError::InvalidInput(format!(
+                "max_results must be between 1 and {MAX_TOKEN_WATCH_RESULTS}"
+            )));
+        }
+
+        let address = match params.address {
+            Some(address) => self.resolve_input(&address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let token = self.registered_token(&params.token).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let info = registry_snapshot.info_by_address(token).ok_or_else(|| {
+            AppError::Internal(format!("token {token:#x} missing after registration"))
+        })?;
+
+        let filter = TransferFilter {
+            token: info,
+            address,
+            direction: params.direction,
+        };
+        let token_address = format!("{token:#x}");
+        let watched = format!("{address:#x}");
+        let result = token_watch::watch_token(
+            self.ctx.provider.clone(),
+            filter,
+            params.from_block,
+            std::time::Duration::from_secs(params.duration_secs),
+            std::time::Duration::from_secs(DEPOSIT_POLL_INTERVAL_SECS),
+            params.max_results,
+            &progress,
+            |transfer| {
+                self.publish(ServerEvent::TokenTransfer {
+                    token: token_address.clone(),
+                    symbol: info.symbol.clone(),
+                    address: watched.clone(),
+                    transfer: transfer.clone(),
+                })
+            },
+        )
+        .await?;
+        info!(
+            "token watch saw {} transfers up to block {}",
+            result.transfers.len(),
+            result.last_block
+        );
+        Ok(result)
+    }
+
+    /// Follow a broadcast transaction until it is mined or found stuck below the market fee,
+    /// publishing a stuck one as an event with the fees (and, under the `bump` policy, the signed
+    /// replacement) that would speed it up.
+    #[instrument(skip(self, progress), fields(tx_hash = %params.tx_hash, duration_secs = params.duration_secs))]
+    pub async fn watch_transaction(
+        &self,
+        params: WatchTransactionParams,
+        progress: ProgressReporter,
+    ) -> AppResult<TxWatchOut> {
+        if params.duration_secs == 0 || params.duration_secs > MAX_TX_WATCH_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "duration_secs must be between 1 and {MAX_TX_WATCH_SECS}"
+            )));
+        }
+        if params.stuck_after_blocks == Some(0) {
+            return Err(AppError::InvalidInput(
+                "stuck_after_blocks must be at least 1".into(),
+            ));
+        }
+        let hash: H256 = params.tx_hash.parse().map_err(|_| {
+            AppError::InvalidInput(format!(
+                "tx_hash must be 32 bytes of hex, got {}",
+                params.tx_hash
+            ))
+        })?;
+
+        let mut settings = StuckTxSettings::from_config(&self.ctx.config);
+        if let Some(blocks) = params.stuck_after_blocks {
+            settings.stuck_after_blocks = blocks;
+        }
+        // A halt stops replacements from being signed, not the watch itself.
+        settings.halted = self.ctx.kill_switch.ensure_running().is_err();
+        let signer = self.ctx.wallet.signer();
+        let result = stuck_tx::watch_transaction(
+            self.ctx.provider.clone(),
+            hash,
+            settings,
+            signer.as_ref(),
+            self.ctx.chain_id,
+            std::time::Duration::from_secs(params.duration_secs),
+            std::time::Duration::from_secs(DEPOSIT_POLL_INTERVAL_SECS),
+            &progress,
+        )
+        .await?;
+        if let Some(speed_up) = &result.speed_up {
+            self.publish(ServerEvent::StuckTx {
+                tx_hash: result.tx_hash.clone(),
+                from: result.from.clone(),
+                nonce: result.nonce.clone(),
+                blocks_pending: result.blocks_pending,
+                speed_up: speed_up.clone(),
+            });
+        }
+        info!(
+            "transaction watch ended {:?} after {} blocks",
+            result.status, result.blocks_pending
+        );
+        Ok(result)
+    }
+
+    /// Follow the price ratio of two tokens, or of a token and a Chainlink reference feed, until
+    /// it leaves the band around its target, publishing the breach as an event.
+    #[instrument(skip(self, progress), fields(base = %params.base, band_bps = params.band_bps))]
+    pub async fn watch_spread(
+        &self,
+        params: WatchSpreadParams,
+        progress: ProgressReporter,
+    ) -> AppResult<SpreadWatchOut> {
+        if params.duration_secs == 0 || params.duration_secs > MAX_SPREAD_WATCH_SECS {
+            return Err(AppError::InvalidInput(format!(
+                "duration_secs must be between 1 and {MAX_SPREAD_WATCH_SECS}"
+            )));
+        }
+        let band = SpreadBand::parse(&params.target_ratio, params.band_bps)?;
+        let base = self.registered_token(&params.base).await?;
+        let quote = match (params.quote.as_deref(), params.quote_feed.as_deref()) {
+            (Some(quote), None) => SpreadLeg::Token(self.registered_token(quote).await?),
+            (None, Some(feed)) => SpreadLeg::Feed(feed.parse::<Address>().map_err(|_| {
+                AppError::InvalidInput(format!(
+                    "invalid quote_feed address: {feed}{}",
+                    validation::explain(feed, [])
+                ))
+            })?),
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "set exactly one of quote and quote_feed".into(),
+                ));
+            }
+        };
+        if quote == SpreadLeg::Token(base) {
+            return Err(AppError::InvalidInput(
+                "base and quote must be different tokens".into(),
+            ));
+        }
+
+        let registry_snapshot = self.snapshot_registry().await;
+        let symbol = |address: Address| {
+            registry_snapshot
+                .info_by_address(address)
+                .map(|info| info.symbol.clone())
+                .unwrap_or_else(|| format!("{address:#x}"))
+        };
+        let pair = SpreadPair {
+            base,
+            base_symbol: symbol(base),
+            quote,
+            quote_name: match quote {
+                SpreadLeg::Token(token) => symbol(token),
+                SpreadLeg::Feed(feed) => format!("{feed:#x}"),
+            },
+            band,
+        };
+        let target_ratio = band.target_ratio.normalize().to_string();
+        let mut result = spread::watch_spread(
+            self.ctx.provider.clone(),
+            &registry_snapshot,
+            &pair,
+            std::time::Duration::from_secs(params.duration_secs),
+            std::time::Duration::from_secs(DEPOSIT_POLL_INTERVAL_SECS),
+            &progress,
+            |sample| {
+                self.publish(ServerEvent::SpreadAlert {
+                    base: pair.base_symbol.clone(),
+                    quote: pair.quote_name.clone(),
+                    target_ratio: target_ratio.clone(),
+                    band_bps: band.band_bps,
+                    sample: sample.clone(),
+                })
+            },
+        )
+        .await?;
+        let mut tokens = vec![base];
+        if let SpreadLeg::Token(token) = quote {
+            tokens.push(token);
+        }
+        let mut warnings = warnings::unverified_tokens(&registry_snapshot, &tokens);
+        warnings.append(&mut result.warnings);
+        result.warnings = warnings;
+        info!(
+            "spread watch ended {:?} after {} samples",
+            result.status,
+            result.samples.len()
+        );
+        Ok(result)
+    }
+
+    /// Queue a swap recipient for the whitelist; it becomes usable after the configured delay.
+    #[instrument(skip(self), fields(address = %params.address))]
+    pub async fn whitelist_address(
+        &self,
+        params: WhitelistAddressParams,
+    ) -> AppResult<WhitelistEntryOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let address = self.resolve_input(&params.address).await?;
+        let mut entry = self
+            .ctx
+            .whitelist
+            .add(address, params.label, whitelist::unix_now())?;
+        entry.ens_name = self.ens_name(address).await;
+        info!("recipient whitelisted, active at {}", entry.active_at);
+        Ok(entry)
+    }
+
+    /// Whitelisted recipients and when each becomes usable.
+    pub async fn get_whitelist(&self) -> AppResult<WhitelistOut> {
+        let mut whitelist = self.ctx.whitelist.snapshot(whitelist::unix_now());
+        for entry in &mut whitelist.entries {
+            if let Ok(address) = entry.address.parse::<Address>() {
+                entry.ens_name = self.ens_name(address).await;
+            }
+        }
+        Ok(whitelist)
+    }
+
+    /// Emergency stop: suspend swap simulation and whitelist changes until `resume`.
+    #[instrument(skip(self))]
+    pub async fn panic_stop(&self, params: PanicStopParams) -> AppResult<KillSwitchOut> {
+        let reason = params
+            .reason
+            .unwrap_or_else(|| "panic_stop called".to_string());
+        let status = self.ctx.kill_switch.stop(reason, whitelist::unix_now());
+        warn!(
+            "write operations suspended: {}",
+            status.reason.as_deref().unwrap_or_default()
+        );
+        self.publish(ServerEvent::Halted {
+            reason: status.reason.clone().unwrap_or_default(),
+        });
+        Ok(status)
+    }
+
+    /// Lift a `panic_stop`; requires the configured admin secret.
+    #[instrument(skip(self, params))]
+    pub async fn resume(&self, params: ResumeParams) -> AppResult<KillSwitchOut> {
+        let status = self.ctx.kill_switch.resume(&params.admin_secret)?;
+        warn!("write operations resumed");
+        self.publish(ServerEvent::Resumed);
+        Ok(status)
+    }
 
-/// Shared context that higher layers pass around. Keeps provider, registry, and wallet handles.
-#[derive(Clone)]
-pub struct ServiceContext {
-    pub provider: Arc<Provider<Http>>,
-    pub registry: Arc<RwLock<TokenRegistry>>,
-    pub wallet: Arc<WalletManager>,
-}
+    /// Internal events from the append-only log, oldest first.
+    pub async fn get_events(&self, params: GetEventsParams) -> AppResult<EventsOut> {
+        if params.limit == 0 || params.limit > EVENT_LOG_CAPACITY {
+            return Err(AppError::InvalidInput(format!(
+                "limit must be between 1 and {EVENT_LOG_CAPACITY}"
+            )));
+        }
+        if let Some(kind) = params
+            .kinds
+            .iter()
+            .find(|kind| !EVENT_KINDS.contains(&kind.as_str()))
+        {
+            return Err(AppError::InvalidInput(format!(
+                "unknown event kind {kind}; expected one of {}",
+                EVENT_KINDS.join(", ")
+            )));
+        }
+        Ok(self
+            .ctx
+            .events
+            .since(params.after_seq, params.limit, &params.kinds))
+    }
 
-impl ServiceContext {
-    pub fn new(
-        provider: Arc<Provider<Http>>,
-        registry: Arc<RwLock<TokenRegistry>>,
-        wallet: Arc<WalletManager>,
-    ) -> Self {
-        Self {
-            provider,
-            registry,
-            wallet,
+    /// Re-run the startup checks, then check the wallet, Chainlink feeds, the Uniswap quoter and
+    /// the write path, as `walletmcp doctor` does at the command line.
+    #[instrument(skip(self))]
+    pub async fn self_test(&self) -> AppResult<SelfTestOut> {
+        let registry_snapshot = self.snapshot_registry().await;
+        let out = self_test::run(SelfTestTarget {
+            provider: self.ctx.provider.clone(),
+            config: &self.ctx.config,
+            registry: registry_snapshot.as_ref().clone(),
+            chain_id: self.ctx.chain_id,
+            signer: self.ctx.wallet.signer(),
+            now: whitelist::unix_now(),
+        })
+        .await;
+        info!("self-test finished, passed: {}", out.passed);
+        Ok(out)
+    }
+
+    /// Apply every cache and log limit now, rather than as entries arrive, and report the size
+    /// of each store.
+    #[instrument(skip(self))]
+    pub async fn maintenance(&self) -> AppResult<MaintenanceOut> {
+        let now = whitelist::unix_now();
+        let ens_removed = self.ctx.ens.prune(now);
+        let activity_removed = self.ctx.activity.prune(now);
+        let paper_removed = self.ctx.paper.prune(now)?;
+
+        let mut stores = vec![
+            self.ctx.ens.status(ens_removed),
+            self.ctx.token_cache.status(0),
+        ];
+        stores.extend(self.ctx.activity.status(activity_removed));
+        stores.push(self.ctx.paper.status(paper_removed));
+        stores.push(self.ctx.events.status());
+        let removed = stores.iter().map(|store| store.removed).sum();
+        info!("maintenance removed {removed} entries");
+        Ok(MaintenanceOut {
+            ran_at: now,
+            removed,
+            stores,
+        })
+    }
+
+    /// Summarise recent tool calls, swap simulations and errors.
+    #[instrument(skip(self))]
+    pub async fn get_activity_report(
+        &self,
+        params: GetActivityReportParams,
+    ) -> AppResult<ActivityReportOut> {
+        let now = whitelist::unix_now();
+        Ok(self.ctx.activity.report(
+            now.saturating_sub(params.since_secs),
+            now,
+            params.tag.as_deref(),
+        ))
+    }
+
+    /// Journaled write-tool calls with their `memo` / `tags`, newest first.
+    #[instrument(skip(self))]
+    pub async fn get_activity_history(
+        &self,
+        params: GetActivityHistoryParams,
+    ) -> AppResult<ActivityHistoryOut> {
+        let now = whitelist::unix_now();
+        Ok(self.ctx.activity.history(
+            now.saturating_sub(params.since_secs),
+            now,
+            params.tag.as_deref(),
+            params.limit,
+        ))
+    }
+
+    /// Chain id verified against the provider at startup.
+    pub fn chain_id(&self) -> u64 {
+        self.ctx.chain_id
+    }
+
+    /// Fails unless `name` is one of the active chain's RPC endpoints.
+    pub fn check_rpc_endpoint(&self, name: &str) -> AppResult<()> {
+        let balanced: &BalancedHttp = self.ctx.provider.as_ref().as_ref();
+        let names = balanced.endpoint_names();
+        if names.contains(&name) {
+            return Ok(());
         }
+        Err(AppError::InvalidInput(format!(
+            "unknown RPC endpoint {name}; configured: {}",
+            names.join(", ")
+        )))
     }
-}
 
-/// Middle layer that exposes business-level operations while delegating heavy work to implementation modules.
-#[derive(Clone)]
-pub struct ServiceLayer {
-    ctx: Arc<ServiceContext>,
-}
+    /// `params` for `method` with configured defaults filled in for what the caller left out.
+    pub fn apply_param_defaults(&self, method: &str, params: Value) -> Value {
+        self.ctx.param_defaults.apply(method, params)
+    }
 
-impl ServiceLayer {
-    pub fn new(ctx: Arc<ServiceContext>) -> Self {
-        Self { ctx }
+    /// Sign `result` as of `timestamp` when response signing is on. A result that cannot be
+    /// signed goes out without an attestation, which verifiers treat as unsigned.
+    pub fn attest(&self, timestamp: u64, result: &mut Value) {
+        if let Some(attestor) = &self.ctx.attestor
+            && let Err(err) = attestor.attest(timestamp, result)
+        {
+            warn!("response not attested: {err}");
+        }
     }
 
-    /// Balance lookup entry point. Handles optional ERC-20 parameter resolution.
-    #[instrument(skip(self), fields(address = %params.address, token = %params.token.as_deref().unwrap_or("ETH")))]
-    pub async fn get_balance(&self, params: GetBalanceParams) -> AppResult<BalanceOut> {
+    /// Append `event` to the event log and hand it to every subscriber.
+    pub fn publish(&self, event: ServerEvent) {
+        self.ctx.events.publish(event, whitelist::unix_now());
+    }
+
+    /// Live events for the notification layer, when `event_notifications` is on.
+    pub fn event_notifications(&self) -> Option<broadcast::Receiver<EventRecord>> {
+        self.ctx
+            .config
+            .event_notifications
+            .then(|| self.ctx.events.subscribe())
+    }
+
+    /// Journal a finished tool call for `get_activity_report`.
+    pub fn record_call(&self, method: &str, error: Option<(i32, &str)>) {
+        self.ctx
+            .activity
+            .record_call(whitelist::unix_now(), method, error);
+    }
+
+    /// JSON Schemas for every tool's params and result, or only `method`'s.
+    pub async fn get_schemas(&self, params: GetSchemasParams) -> AppResult<SchemasOut> {
+        let mut tools = schemas::catalog();
+        if let Some(method) = params.method {
+            tools.retain(|tool| tool.name == method);
+            if tools.is_empty() {
+                return Err(AppError::InvalidInput(format!("unknown method: {method}")));
+            }
+        }
+        Ok(SchemasOut {
+            tools,
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Registered tokens, with symbols shared by several tokens called out.
+    pub async fn list_tokens(&self, params: ListTokensParams) -> AppResult<ListTokensOut> {
         let registry_snapshot = self.snapshot_registry().await;
-        let address = parse_address_or_symbol(&params.address, &registry_snapshot)?;
-        let token = match params.token {
-            Some(token_str) => Some(parse_address_or_symbol(&token_str, &registry_snapshot)?),
+        if let Some(symbol) = params.symbol.as_deref()
+            && registry_snapshot.symbol_candidates(symbol).is_empty()
+        {
+            return Err(AppError::InvalidInput(format!(
+                "unknown token symbol: {symbol}{}",
+                validation::explain(symbol, registry_snapshot.known_names())
+            )));
+        }
+        Ok(registry_snapshot.list(params.symbol.as_deref(), params.duplicates_only))
+    }
+
+    /// Curated `approve` templates for the spenders this server's tools route tokens through.
+    pub async fn list_approval_templates(
+        &self,
+        params: ListApprovalTemplatesParams,
+    ) -> AppResult<ApprovalTemplatesOut> {
+        let templates = ApprovalTemplates::new(self.ctx.uniswap, self.disperse_address()?);
+        let amount = params
+            .amount_wei
+            .as_deref()
+            .map(|raw| {
+                U256::from_dec_str(raw)
+                    .map_err(|_| AppError::InvalidInput(format!("invalid numeric value: {raw}")))
+            })
+            .transpose()?;
+        let token = match params.token.as_deref() {
+            Some(token) => Some(self.registered_token(token).await?),
             None => None,
         };
+        let registry_snapshot = self.snapshot_registry().await;
+        let tokens = token.map(|token| {
+            registry_snapshot
+                .info_by_address(token)
+                .into_iter()
+                .collect()
+        });
+        let mut out = templates.list(
+            &registry_snapshot,
+            self.ctx.chain_id,
+            tokens,
+            params.protocol.as_deref(),
+            amount,
+        )?;
+        out.warnings = warnings::unverified_tokens(&registry_snapshot, &Vec::from_iter(token));
+        Ok(out)
+    }
 
-        let result = balance::resolve_balance(self.ctx.provider.clone(), address, token).await?;
-        info!("balance lookup succeeded");
+    /// Quotes `base` can actually be priced in on this chain, and which tool prices each.
+    #[instrument(skip(self), fields(base = %params.base))]
+    pub async fn list_quote_currencies(
+        &self,
+        params: ListQuoteCurrenciesParams,
+    ) -> AppResult<QuoteCurrenciesOut> {
+        let base = self.registered_token(&params.base).await?;
+        let mut tokens = Vec::with_capacity(params.tokens.len());
+        for token in &params.tokens {
+            tokens.push(self.registered_token(token).await?);
+        }
+        let registry_snapshot = self.snapshot_registry().await;
+        let token_infos: Vec<&TokenInfo> = tokens
+            .iter()
+            .filter_map(|token| registry_snapshot.info_by_address(*token))
+            .collect();
+        let base_info = registry_snapshot
+            .info_by_address(base)
+            .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {base:?}")))?;
+        let scoped = self.pin_block(None).await?;
+        let mut out = quotes::quote_currencies(
+            scoped,
+            &registry_snapshot,
+            self.ctx.chain_id,
+            base_info,
+            &token_infos,
+        )
+        .await?;
+        tokens.insert(0, base);
+        out.warnings = warnings::unverified_tokens(&registry_snapshot, &tokens);
+        Ok(out)
+    }
+
+    /// The signer's address with an EIP-681 request to fund it, optionally as a QR code.
+    #[instrument(skip(self))]
+    pub async fn get_wallet_info(&self, params: GetWalletInfoParams) -> AppResult<WalletInfoOut> {
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("get_wallet_info requires PRIVATE_KEY/signing config".into())
+        })?;
+        let token = match params.token.as_deref() {
+            Some(token) => Some(self.registered_token(token).await?),
+            None => None,
+        };
+        let registry_snapshot = self.snapshot_registry().await;
+        let token_info = token.and_then(|token| registry_snapshot.info_by_address(token));
+        let decimals = token_info.map_or(18, |info| u32::from(info.decimals));
+        let amount = params
+            .amount
+            .as_deref()
+            .map(|amount| balance::parse_formatted(amount, decimals))
+            .transpose()?;
+
+        let payment_uri = funding::payment_uri(signer.address(), self.ctx.chain_id, token, amount);
+        let qr = params
+            .qr
+            .then(|| funding::qr_text(&payment_uri))
+            .transpose()?;
+        Ok(WalletInfoOut {
+            address: format!("{:#x}", signer.address()),
+            chain_id: self.ctx.chain_id,
+            native_symbol: registry_snapshot.native().symbol.clone(),
+            payment_uri,
+            token: token_info.map(|info| info.symbol.clone()),
+            amount_wei: amount.map(|amount| amount.to_string()),
+            qr,
+            warnings: warnings::unverified_tokens(&registry_snapshot, &Vec::from_iter(token)),
+        })
+    }
+
+    /// Convert an amount between wei, gwei and ether, or between a token's raw and human units.
+    #[instrument(skip(self), fields(from = %params.from, to = %params.to))]
+    pub async fn convert_units(&self, params: ConvertUnitsParams) -> AppResult<ConvertUnitsOut> {
+        let token_units = params.from.is_token_unit() || params.to.is_token_unit();
+        let token = match params.token.as_deref() {
+            Some(_) if !token_units => {
+                return Err(AppError::InvalidInput(
+                    "token only applies to raw and human units".into(),
+                ));
+            }
+            Some(token) => Some(self.registered_token(token).await?),
+            None => None,
+        };
+        let registry_snapshot = self.snapshot_registry().await;
+        let info = token.and_then(|token| registry_snapshot.info_by_address(token));
+        let (base_units, result) = units::convert(
+            &params.amount,
+            params.from,
+            params.to,
+            info.map(|info| u32::from(info.decimals)),
+        )?;
+        Ok(ConvertUnitsOut {
+            amount: params.amount,
+            from: params.from,
+            to: params.to,
+            result,
+            base_units: base_units.to_string(),
+            token: info.map(|info| info.symbol.clone()),
+            token_address: token.map(|token| format!("{token:#x}")),
+            decimals: info.map(|info| info.decimals),
+            warnings: warnings::unverified_tokens(&registry_snapshot, &Vec::from_iter(token)),
+        })
+    }
+
+    /// The canonical deployments of an asset across chains, to aggregate holdings or pick a
+    /// bridge destination token.
+    #[instrument(skip(self), fields(token = %params.token))]
+    pub async fn get_token_deployments(
+        &self,
+        params: GetTokenDeploymentsParams,
+    ) -> AppResult<TokenDeploymentsOut> {
+        let mapping = &*canonical::BUNDLED;
+        let token = match mapping.by_symbol(&params.token) {
+            Some(token) => token,
+            None => {
+                let address = self.resolve_input(&params.token).await?;
+                mapping
+                    .by_address(self.ctx.chain_id, address)
+                    .ok_or_else(|| {
+                        AppError::InvalidInput(format!(
+                            "{} is not in the canonical token mapping for chain {}",
+                            params.token, self.ctx.chain_id
+                        ))
+                    })?
+            }
+        };
+        CanonicalTokens::deployments(token, params.chain_id, self.ctx.chain_id)
+    }
+
+    /// Decode an EIP-681 payment link or signed transaction into recipient, token and amount,
+    /// with `distribute_tokens` params for token payments.
+    #[instrument(skip(self))]
+    pub async fn parse_payment_request(
+        &self,
+        params: ParsePaymentRequestParams,
+    ) -> AppResult<PaymentRequestOut> {
+        let payment = funding::parse_payment_request(&params.request)?;
+        let mut warnings = Vec::new();
+        let on_chain = payment
+            .chain_id
+            .is_none_or(|chain_id| chain_id == self.ctx.chain_id);
+        if !on_chain {
+            warnings.push(warnings::warning(
+                "chain_mismatch",
+                format!(
+                    "the request is for chain {}, but this server is on chain {}; do not pay it here",
+                    payment.chain_id.unwrap_or_default(),
+                    self.ctx.chain_id
+                ),
+            ));
+        }
+        // Token metadata is read from the active chain, so only when the request is for it.
+        if let Some(token) = payment.token
+            && on_chain
+        {
+            self.ensure_registry_token(token).await?;
+        }
+        let registry_snapshot = self.snapshot_registry().await;
+        let info = payment
+            .token
+            .and_then(|token| registry_snapshot.info_by_address(token));
+        let decimals = match payment.token {
+            None => Some(18),
+            Some(_) => info.map(|info| u32::from(info.decimals)),
+        };
+        if on_chain {
+            warnings.extend(warnings::unverified_tokens(
+                &registry_snapshot,
+                &Vec::from_iter(payment.token),
+            ));
+        }
+        if payment.calldata.is_some() {
+            warnings.push(warnings::warning(
+                "contract_call",
+                format!(
+                    "the transaction calls {:#x} with data, so it is not a plain payment; check calldata_hex before acting on it",
+                    payment.recipient
+                ),
+            ));
+        }
+
+        let distribute_tokens = match (payment.token, payment.amount) {
+            (Some(token), Some(amount)) if on_chain => Some(DistributeTokensParams {
+                token: format!("{token:#x}"),
+                payouts: vec![PayoutParams {
+                    to: format!("{:#x}", payment.recipient),
+                    amount_wei: amount.to_string(),
+                }],
+                mode: Default::default(),
+                compliance_override: false,
+                block: None,
+                memo: None,
+                tags: Vec::new(),
+            }),
+            _ => None,
+        };
+        Ok(PaymentRequestOut {
+            kind: match payment.source {
+                PaymentSource::Uri => "eip681",
+                PaymentSource::RawTransaction => "raw_transaction",
+            }
+            .into(),
+            chain_id: payment.chain_id,
+            to: format!("{:#x}", payment.recipient),
+            token: payment.token.map(|token| format!("{token:#x}")),
+            symbol: info.map(|info| info.symbol.clone()),
+            amount_wei: payment.amount.map(|amount| amount.to_string()),
+            amount: payment
+                .amount
+                .zip(decimals)
+                .map(|(amount, decimals)| balance::format_with_decimals(&amount, decimals)),
+            gas_limit: payment.gas_limit.map(|gas| gas.to_string()),
+            from: payment.from.map(|from| format!("{from:#x}")),
+            nonce: payment.nonce.map(|nonce| nonce.to_string()),
+            calldata_hex: payment
+                .calldata
+                .map(|data| format!("0x{}", hex::encode(data))),
+            distribute_tokens,
+            warnings,
+        })
+    }
+
+    /// Fresh burner keypair, encrypted to the keystore dir, with a request to fund it.
+    #[instrument(skip(self))]
+    pub async fn create_burner_wallet(
+        &self,
+        params: CreateBurnerWalletParams,
+    ) -> AppResult<BurnerWalletOut> {
+        let vault = self.burners("create_burner_wallet")?;
+        let amount = params
+            .amount
+            .as_deref()
+            .map(|amount| balance::parse_formatted(amount, 18))
+            .transpose()?;
+        let chain_id = self.ctx.chain_id;
+        let (burner, keystore_path) = tokio::task::spawn_blocking(move || vault.create(chain_id))
+            .await
+            .map_err(|err| AppError::Internal(format!("burner task failed: {err}")))??;
+
+        info!("created burner {:#x}", burner.address());
+        Ok(BurnerWalletOut {
+            address: format!("{:#x}", burner.address()),
+            chain_id,
+            keystore_path: keystore_path.display().to_string(),
+            payment_uri: funding::payment_uri(burner.address(), chain_id, None, amount),
+            warnings: Vec::new(),
+        })
+    }
+
+    /// Sign (never broadcast) transactions returning a burner's tokens and native coin to the
+    /// main wallet.
+    #[instrument(skip(self), fields(burner = %params.burner, tokens = params.tokens.len()))]
+    pub async fn sweep_wallet(&self, params: SweepWalletParams) -> AppResult<SweepOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let vault = self.burners("sweep_wallet")?;
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("sweep_wallet requires PRIVATE_KEY/signing config".into())
+        })?;
+        let address = params.burner.parse::<Address>().map_err(|_| {
+            AppError::InvalidInput(format!(
+                "invalid burner address: {}{}",
+                params.burner,
+                validation::explain(&params.burner, [])
+            ))
+        })?;
+        let mut tokens = Vec::with_capacity(params.tokens.len());
+        for token in &params.tokens {
+            tokens.push(self.registered_token(token).await?);
+        }
+        let registry_snapshot = self.snapshot_registry().await;
+        let infos = tokens
+            .iter()
+            .map(|token| {
+                registry_snapshot
+                    .info_by_address(*token)
+                    .ok_or_else(|| AppError::InvalidInput(format!("unsupported token: {token:#x}")))
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let chain_id = self.ctx.chain_id;
+        let burner = tokio::task::spawn_blocking(move || vault.unlock(address, chain_id))
+            .await
+            .map_err(|err| AppError::Internal(format!("burner task failed: {err}")))??;
+        let scoped = self.pin_block(None).await?;
+        let mut sweep = burner::plan_sweep(
+            scoped.clone(),
+            &burner,
+            signer.address(),
+            &infos,
+            &registry_snapshot.native().symbol,
+            &SwapSettings::from_config(&self.ctx.config, self.ctx.uniswap),
+        )
+        .await?;
+        sweep.block_number = Some(scoped.block_number());
+        sweep
+            .warnings
+            .extend(warnings::unverified_tokens(&registry_snapshot, &tokens));
+        self.record_write(
+            "sweep_wallet",
+            format!("sweep burner {} to {}", sweep.burner, sweep.to),
+            annotations,
+        );
+
+        info!("sweep signed with {} transactions", sweep.steps.len());
+        Ok(sweep)
+    }
+
+    /// Credit the paper portfolio with `amount_wei` of a token, optionally starting it over.
+    #[instrument(skip(self), fields(token = %params.token, reset = params.reset))]
+    pub async fn paper_fund(&self, params: PaperFundParams) -> AppResult<PaperPortfolioOut> {
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let token = self.registered_token(&params.token).await?;
+        let amount = paper::parse_amount("amount_wei", &params.amount_wei)?;
+        if params.reset {
+            self.ctx.paper.reset()?;
+        }
+        let trade = self
+            .ctx
+            .paper
+            .book(PaperTrade::fund(token, amount, whitelist::unix_now()).annotated(annotations))?;
+        Ok(self.paper_portfolio(Some(trade), 0).await)
+    }
+
+    /// Swap paper balances at the live Uniswap V3 quote; nothing is signed or sent.
+    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
+    pub async fn paper_swap(&self, params: PaperSwapParams) -> AppResult<PaperPortfolioOut> {
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let from_token = self.registered_token(&params.from_token).await?;
+        let to_token = self.registered_token(&params.to_token).await?;
+        if from_token == to_token {
+            return Err(AppError::InvalidInput(
+                "from_token and to_token must differ".into(),
+            ));
+        }
+        let amount_in = paper::parse_amount("amount_in_wei", &params.amount_in_wei)?;
+        // Fail before quoting when the paper balance cannot cover the swap.
+        let held = self.ctx.paper.balance(from_token);
+        if held < amount_in {
+            return Err(AppError::Wallet(format!(
+                "paper balance of {from_token:#x} is {held}, below the requested {amount_in}; fund it with paper_fund"
+            )));
+        }
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let amount_out = paper::quote_fill(
+            scoped.clone(),
+            self.ctx.uniswap.quoter,
+            from_token,
+            to_token,
+            amount_in,
+            params.fee,
+        )
+        .await?;
+        let trade = PaperTrade::swap(
+            from_token,
+            amount_in,
+            to_token,
+            amount_out,
+            scoped.block_number(),
+            whitelist::unix_now(),
+        )
+        .annotated(annotations);
+        let trade = self.ctx.paper.book(trade)?;
+        let mut result = self.paper_portfolio(Some(trade), 0).await;
+        result.warnings =
+            warnings::unverified_tokens(&*self.snapshot_registry().await, &[from_token, to_token]);
+
+        info!("paper swap booked");
         Ok(result)
     }
 
-    /// Price lookup with Chainlink-first policy and Uniswap fallback.
-    #[instrument(skip(self), fields(base = %params.base, quote = %params.quote))]
-    pub async fn get_token_price(&self, params: GetTokenPriceParams) -> AppResult<PriceOut> {
-        let base_address = self.resolve_input(&params.base).await?;
+    /// Move paper balance to another address; it leaves the portfolio.
+    #[instrument(skip(self), fields(token = %params.token, to = %params.to))]
+    pub async fn paper_transfer(
+        &self,
+        params: PaperTransferParams,
+    ) -> AppResult<PaperPortfolioOut> {
+        let annotations = activity::annotations(params.memo, params.tags)?;
+        let token = self.registered_token(&params.token).await?;
+        let amount = paper::parse_amount("amount_wei", &params.amount_wei)?;
+        let owner = self
+            .ctx
+            .wallet
+            .signer()
+            .map(|signer| signer.address())
+            .unwrap_or_default();
+        let to = self.ctx.recipients.resolve(Some(&params.to), owner)?;
+        let trade =
+            PaperTrade::transfer(token, amount, to, whitelist::unix_now()).annotated(annotations);
+        let trade = self.ctx.paper.book(trade)?;
+        Ok(self.paper_portfolio(Some(trade), 0).await)
+    }
 
-        // Ensure registry knows about base token for metadata-driven pricing.
-        self.ensure_registry_token(base_address).await?;
+    /// Paper balances and recent paper trades.
+    #[instrument(skip(self))]
+    pub async fn get_paper_portfolio(
+        &self,
+        params: GetPaperPortfolioParams,
+    ) -> AppResult<PaperPortfolioOut> {
         let registry_snapshot = self.snapshot_registry().await;
+        Ok(self.ctx.paper.snapshot(
+            &registry_snapshot,
+            params.trade_limit,
+            params.tag.as_deref(),
+        ))
+    }
 
-        let price = price::resolve_token_price(
-            self.ctx.provider.clone(),
+    /// Static bytecode heuristics for unfamiliar tokens, meant to run before approve/swap.
+    #[instrument(skip(self), fields(token = %params.token))]
+    pub async fn token_risk(&self, params: TokenRiskParams) -> AppResult<TokenRiskOut> {
+        let token = self.resolve_input(&params.token).await?;
+        let listed_decimals = self.snapshot_registry().await.listed_decimals(token);
+        let report =
+            token_risk::assess_token(self.ctx.provider.clone(), token, listed_decimals).await?;
+        info!("token risk assessed as {}", report.risk_level);
+        Ok(report)
+    }
+
+    /// Simulate dependent transactions in order against one state, e.g. approve → swap →
+    /// transfer, where each step sees what the ones before it did.
+    #[instrument(skip(self, params), fields(steps = params.steps.len()))]
+    pub async fn simulate_bundle(&self, params: SimulateBundleParams) -> AppResult<BundleOut> {
+        if params.steps.is_empty() || params.steps.len() > bundle::MAX_BUNDLE_STEPS {
+            return Err(AppError::InvalidInput(format!(
+                "a bundle takes between 1 and {} steps",
+                bundle::MAX_BUNDLE_STEPS
+            )));
+        }
+        let signer = self
+            .ctx
+            .wallet
+            .signer()
+            .map(|signer| signer.address())
+            .unwrap_or_default();
+        let mut calls = Vec::with_capacity(params.steps.len());
+        for (index, step) in params.steps.into_iter().enumerate() {
+            let invalid = |what: &str, value: &str| {
+                AppError::InvalidInput(format!("step {index}: invalid {what}: {value}"))
+            };
+            let from = match step.from.as_deref() {
+                Some(from) => self.resolve_input(from).await?,
+                None => signer,
+            };
+            let data = match step.data.as_deref() {
+                Some(data) => Bytes::from_str(data).map_err(|_| invalid("hex calldata", data))?,
+                None => Bytes::new(),
+            };
+            let value = match step.value_wei.as_deref() {
+                Some(value) => {
+                    U256::from_dec_str(value).map_err(|_| invalid("value_wei", value))?
+                }
+                None => U256::zero(),
+            };
+            calls.push(BundleCall {
+                label: step.label,
+                from,
+                to: self.resolve_input(&step.to).await?,
+                data,
+                value,
+            });
+        }
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let registry_snapshot = self.snapshot_registry().await;
+        let mut result = bundle::simulate(
+            scoped.as_ref(),
             &registry_snapshot,
-            base_address,
-            params.quote,
+            &calls,
+            scoped.block_number(),
         )
         .await?;
+        let tokens: Vec<Address> = result
+            .steps
+            .iter()
+            .flat_map(|step| &step.token_events)
+            .filter_map(|event| event.token.parse().ok())
+            .collect();
+        result.warnings = warnings::unverified_tokens(&registry_snapshot, &tokens);
+        info!(
+            "simulated a bundle of {} steps, first failure: {:?}",
+            result.steps.len(),
+            result.first_failure
+        );
+        Ok(result)
+    }
 
-        info!("price lookup succeeded via {}", price.source);
-        Ok(price)
+    /// `debug_traceCall` a transaction and return its pruned call tree, reverts highlighted.
+    #[instrument(skip(self), fields(to = %params.to))]
+    pub async fn trace_call(&self, params: TraceCallParams) -> AppResult<TraceCallOut> {
+        let to = self.resolve_input(&params.to).await?;
+        let from = match params.from.as_deref() {
+            Some(from) => self.resolve_input(from).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .unwrap_or_default(),
+        };
+        let data = match params.data.as_deref() {
+            Some(data) => Bytes::from_str(data)
+                .map_err(|_| AppError::InvalidInput(format!("invalid hex calldata: {data}")))?,
+            None => Bytes::new(),
+        };
+        let value = match params.value_wei.as_deref() {
+            Some(value) => U256::from_dec_str(value)
+                .map_err(|_| AppError::InvalidInput(format!("invalid value_wei: {value}")))?,
+            None => U256::zero(),
+        };
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .data(data)
+            .value(value)
+            .into();
+
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let mut result = trace::trace_call(scoped.as_ref(), &tx, params.full).await?;
+        result.block_number = Some(scoped.block_number());
+        info!(
+            "traced {} frames, reverted: {}",
+            result.total_calls, result.reverted
+        );
+        Ok(result)
     }
 
-    /// Build and simulate Uniswap V3 calldata without broadcasting.
-    #[instrument(skip(self), fields(from = %params.from_token, to = %params.to_token))]
-    pub async fn swap_tokens(&self, params: SwapTokensParams) -> AppResult<SwapSimOut> {
+    /// Check a signature by an externally owned account or, through ERC-1271, a contract wallet.
+    #[instrument(skip(self, params), fields(signer = %params.signer))]
+    pub async fn verify_signature(
+        &self,
+        params: VerifySignatureParams,
+    ) -> AppResult<VerifySignatureOut> {
+        let signer = self.resolve_input(&params.signer).await?;
+        let payload = match (params.message, params.hash.as_deref()) {
+            (Some(message), None) => SignedPayload::Message(message),
+            (None, Some(hash)) => SignedPayload::Hash(hash.parse().map_err(|_| {
+                AppError::InvalidInput(format!("hash must be 32 bytes of hex, got {hash}"))
+            })?),
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "pass exactly one of message and hash".into(),
+                ));
+            }
+        };
+        let signature = Bytes::from_str(&params.signature).map_err(|_| {
+            AppError::InvalidInput(format!("invalid hex signature: {}", params.signature))
+        })?;
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let out = signatures::verify(
+            scoped.clone(),
+            signer,
+            payload.digest(),
+            signature,
+            scoped.block_number(),
+        )
+        .await?;
+        info!("signature valid: {} ({:?})", out.valid, out.method);
+        Ok(out)
+    }
+
+    /// Whether an account (the wallet by default) is a plain account, runs a delegate's code
+    /// through EIP-7702, or is a contract.
+    #[instrument(skip(self))]
+    pub async fn get_delegation(&self, params: GetDelegationParams) -> AppResult<DelegationOut> {
+        let account = match params.address.as_deref() {
+            Some(address) => self.resolve_input(address).await?,
+            None => self
+                .ctx
+                .wallet
+                .signer()
+                .map(|signer| signer.address())
+                .ok_or_else(|| {
+                    AppError::InvalidInput(
+                        "address is required when no signer is configured".into(),
+                    )
+                })?,
+        };
+        let scoped = self.pin_block(params.block.as_deref()).await?;
+        let out = delegation::inspect(scoped.as_ref(), account, scoped.block_number()).await?;
+        info!("account code kind: {:?}", out.kind);
+        Ok(out)
+    }
+
+    /// Sign (never send) an EIP-7702 authorization delegating the wallet to a contract, or
+    /// clearing its delegation. A delegate can move everything the wallet holds, so signing is
+    /// off unless the operator opts in, and delegates must clear the time-locked whitelist.
+    #[instrument(skip(self))]
+    pub async fn sign_authorization(
+        &self,
+        params: SignAuthorizationParams,
+    ) -> AppResult<AuthorizationOut> {
+        self.ctx.kill_switch.ensure_running()?;
+        if !self.ctx.config.allow_delegation_signing {
+            return Err(AppError::Config(
+                "sign_authorization requires ALLOW_DELEGATION_SIGNING / allow_delegation_signing"
+                    .into(),
+            ));
+        }
+        let signer = self.ctx.wallet.signer().ok_or_else(|| {
+            AppError::Wallet("sign_authorization requires PRIVATE_KEY/signing config".into())
+        })?;
+        let delegate = match (params.delegate.as_deref(), params.revoke) {
+            (Some(raw), false) => raw.parse::<Address>().map_err(|_| {
+                AppError::InvalidInput(format!(
+                    "invalid delegate address: {raw}{}",
+                    validation::explain(raw, [])
+                ))
+            })?,
+            (None, true) => Address::zero(),
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "pass exactly one of delegate and revoke".into(),
+                ));
+            }
+        };
+        if !params.revoke {
+            // An unconfigured whitelist lets everything through, which is no gate for a delegate.
+            if !self.ctx.whitelist.is_enabled() {
+                return Err(AppError::Config(
+                    "sign_authorization requires RECIPIENT_WHITELIST_PATH / recipient_whitelist_path; delegates must be whitelisted".into(),
+                ));
+            }
+            self.ctx.whitelist.check(delegate, whitelist::unix_now())?;
+            self.ctx
+                .compliance
+                .enforce(&[("delegate", delegate)], params.compliance_override)
+                .await?;
+        }
+
+        let scoped = self.pin_block(None).await?;
+        let nonce = match params.nonce {
+            Some(nonce) => nonce,
+            None => {
+                let pending = scoped
+                    .get_transaction_count(signer.address(), Some(BlockNumber::Pending.into()))
+                    .await
+                    .map_err(|err| AppError::Rpc(format!("failed to fetch nonce: {err}")))?
+                    .as_u64();
+                pending + u64::from(params.self_submitted)
+            }
+        };
+        let mut out = delegation::sign_authorization(&signer, self.ctx.chain_id, delegate, nonce)?;
+        if params.revoke {
+            info!("signed delegation revocation at nonce {nonce}");
+            return Ok(out);
+        }
+        let code = scoped
+            .get_code(delegate, None)
+            .await
+            .map_err(|err| AppError::Rpc(format!("failed to read delegate code: {err}")))?;
+        if code.is_empty() {
+            out.warnings.push(warnings::warning(
+                "delegate_without_code",
+                format!(
+                    "{delegate:#x} has no code at block {}; the wallet would behave as a plain account",
+                    scoped.block_number()
+                ),
+            ));
+        }
+        info!("signed delegation to {delegate:#x} at nonce {nonce}");
+        Ok(out)
+    }
+
+    /// The minimum output a `quote_id` promised, once it is checked against `terms`.
+    fn check_quote(&self, quote_id: Option<&str>, terms: &QuoteTerms) -> AppResult<Option<U256>> {
+        quote_id
+            .map(|quote_id| {
+                self.ctx
+                    .quotes
+                    .check(quote_id, terms, whitelist::unix_now())
+            })
+            .transpose()
+    }
+
+    /// Checks shared by every tool that builds a swap: kill switch, token resolution, signer,
+    /// recipient policy and compliance. Resolves `params.recipient` and `params.price_limit` in
+    /// place so the builders see concrete values.
+    async fn authorize_swap(
+        &self,
+        params: &mut SwapTokensParams,
+    ) -> AppResult<(Address, Address, LocalWallet)> {
+        self.ctx.kill_switch.ensure_running()?;
         let from_token = self.resolve_input(&params.from_token).await?;
         let to_token = self.resolve_input(&params.to_token).await?;
 
@@ -102,52 +2770,345 @@ impl ServiceLayer {
             AppError::Wallet("swap simulation requires PRIVATE_KEY/signing config".into())
         })?;
 
-        let result = swap::simulate_swap(
-            self.ctx.provider.clone(),
-            signer,
-            from_token,
-            to_token,
-            params,
-        )
-        .await?;
+        let recipient = self
+            .ctx
+            .recipients
+            .resolve(params.recipient.as_deref(), signer.address())?;
+        // The wallet itself and operator-configured aliases are trusted; anything else must be
+        // whitelisted.
+        if recipient != signer.address() && !self.ctx.recipients.is_alias(recipient) {
+            self.ctx.whitelist.check(recipient, whitelist::unix_now())?;
+        }
+        // Hand the resolved address on so aliases and the default recipient reach the calldata.
+        params.recipient = Some(format!("{recipient:#x}"));
+        self.ctx
+            .compliance
+            .enforce(
+                &[
+                    ("from_token", from_token),
+                    ("to_token", to_token),
+                    ("recipient", recipient),
+                ],
+                params.compliance_override,
+            )
+            .await?;
 
-        info!("swap simulation succeeded");
-        Ok(result)
+        if let Some(price_limit) = params.price_limit.as_deref() {
+            if params.sqrt_price_limit.is_some() {
+                return Err(AppError::InvalidInput(
+                    "pass either price_limit or sqrt_price_limit, not both".into(),
+                ));
+            }
+            let registry_snapshot = self.snapshot_registry().await;
+            let lookup = |address: Address| {
+                registry_snapshot.info_by_address(address).ok_or_else(|| {
+                    AppError::InvalidInput(format!("unsupported token: {address:#x}"))
+                })
+            };
+            let limit = swap::sqrt_price_limit_from_price(
+                price_limit,
+                lookup(from_token)?,
+                lookup(to_token)?,
+            )?;
+            params.sqrt_price_limit = Some(limit.to_string());
+        }
+
+        Ok((from_token, to_token, signer))
+    }
+
+    async fn record_swap(
+        &self,
+        from_token: Address,
+        to_token: Address,
+        amount_in_wei: &str,
+        result: &SwapSimOut,
+        memo: Option<String>,
+        tags: Vec<String>,
+    ) {
+        let registry_snapshot = self.snapshot_registry().await;
+        let token = |address: Address| registry_snapshot.info_by_address(address);
+        let (Some(from), Some(to)) = (token(from_token), token(to_token)) else {
+            return;
+        };
+        let amount_in = U256::from_dec_str(amount_in_wei)
+            .map(|raw| balance::format_with_decimals(&raw, from.decimals as u32))
+            .ok()
+            .and_then(|text| Decimal::from_str(&text).ok())
+            .unwrap_or_default();
+        self.ctx.activity.record_swap(SwapRecord {
+            at: whitelist::unix_now(),
+            from_symbol: from.symbol.clone(),
+            to_symbol: to.symbol.clone(),
+            amount_in,
+            amount_out: Decimal::from_str(&result.amount_out_estimate).unwrap_or_default(),
+            gas_estimate: result.gas_estimate.parse().unwrap_or_default(),
+            memo,
+            tags,
+        });
+    }
+
+    /// Journal a write tool's successful call for `get_activity_history`.
+    fn record_write(
+        &self,
+        tool: &str,
+        summary: String,
+        (memo, tags): (Option<String>, Vec<String>),
+    ) {
+        self.ctx.activity.record_write(WriteRecord {
+            at: whitelist::unix_now(),
+            tool: tool.to_string(),
+            summary,
+            memo,
+            tags,
+        });
+    }
+
+    /// Configured Disperse contract for `distribute_tokens`, if any.
+    fn disperse_address(&self) -> AppResult<Option<Address>> {
+        self.ctx
+            .config
+            .disperse_address
+            .as_deref()
+            .map(|value| {
+                value.parse::<Address>().map_err(|_| {
+                    AppError::Config(format!("disperse_address is not an address: {value}"))
+                })
+            })
+            .transpose()
+    }
+
+    /// ENS primary name for `address` when reverse lookup is enabled.
+    async fn ens_name(&self, address: Address) -> Option<String> {
+        self.ctx
+            .ens
+            .name(self.ctx.provider.as_ref(), address, whitelist::unix_now())
+            .await
+    }
+
+    /// Apply `GAS_WORTHINESS_BPS` to an operation paying out `amount_out` of `to_token` for
+    /// `gas_cost_wei`. Without fee data or USD prices the check is skipped with a warning.
+    async fn check_gas_worth(
+        &self,
+        scoped: &Arc<BlockScoped<RpcProvider>>,
+        registry: &TokenRegistry,
+        operation: &str,
+        to_token: Address,
+        amount_out: &str,
+        gas_cost_wei: Option<U256>,
+    ) -> AppResult<Option<WarningOut>> {
+        let Some(policy) = GasPolicy::from_config(&self.ctx.config) else {
+            return Ok(None);
+        };
+        let Some(gas_cost_wei) = gas_cost_wei else {
+            return Ok(Some(warnings::warning(
+                "gas_unchecked",
+                format!(
+                    "{operation}: the node returned no fee data, so gas-worthiness was not checked"
+                ),
+            )));
+        };
+        match gas_worth::usd_values(scoped.clone(), registry, to_token, amount_out, gas_cost_wei)
+            .await
+        {
+            Ok((value, gas_cost)) => policy.check(operation, value, gas_cost),
+            Err(err) => {
+                warn!("gas-worthiness check skipped: {err}");
+                Ok(Some(warnings::warning(
+                    "gas_unchecked",
+                    format!(
+                        "{operation}: could not value the output or gas in USD ({err}), so gas-worthiness was not checked"
+                    ),
+                )))
+            }
+        }
     }
 
-    /// Resolve a symbol or raw address string into an Ethereum address.
+    fn burners(&self, tool: &str) -> AppResult<Arc<BurnerVault>> {
+        self.ctx.burners.clone().ok_or_else(|| {
+            AppError::Config(format!(
+                "{tool} requires BURNER_KEYSTORE_DIR/burner_keystore_dir"
+            ))
+        })
+    }
+
+    /// Resolve a symbol, `SYMBOL:address`, or raw address string into an Ethereum address.
     async fn resolve_input(&self, input: &str) -> AppResult<Address> {
         if let Ok(addr) = input.parse::<Address>() {
             return Ok(addr);
         }
 
-        let registry_snapshot = self.snapshot_registry().await;
-        registry_snapshot.resolve_symbol(input).ok_or_else(|| {
-            AppError::InvalidInput(format!("unknown token symbol or address: {input}"))
-        })
+        self.snapshot_registry().await.resolve_token(input)
+    }
+
+    /// [`Self::resolve_input`] followed by [`Self::ensure_registry_token`].
+    async fn registered_token(&self, input: &str) -> AppResult<Address> {
+        let address = self.resolve_input(input).await?;
+        self.ensure_registry_token(address).await?;
+        Ok(address)
     }
 
     async fn ensure_registry_token(&self, address: Address) -> AppResult<()> {
         let mut registry = self.ctx.registry.write().await;
+        // Known tokens must not trigger `make_mut`, which copies while snapshots are alive.
+        if registry.info_by_address(address).is_some() {
+            self.ctx.token_cache.touch(address);
+            return Ok(());
+        }
+        let registry = Arc::make_mut(&mut registry);
         registry
             .ensure_token(self.ctx.provider.clone(), address)
-            .await
+            .await?;
+        for evicted in self.ctx.token_cache.insert(address) {
+            registry.remove_token(evicted);
+        }
+        Ok(())
+    }
+
+    /// An `insufficient_gas` warning unless `wallet` can pay `fees.max_cost_wei` plus the `value`
+    /// the write sends, for writes that are only simulated; without fee data from the node there
+    /// is nothing to check against.
+    async fn preflight_gas(
+        &self,
+        scoped: &Arc<BlockScoped<RpcProvider>>,
+        wallet: Address,
+        fees: Option<&SwapFeesOut>,
+        value: U256,
+        spend: &str,
+    ) -> AppResult<Option<WarningOut>> {
+        let Some(max_cost) = fees.and_then(|fees| U256::from_dec_str(&fees.max_cost_wei).ok())
+        else {
+            return Ok(None);
+        };
+        let max_cost = max_cost.saturating_add(value);
+        let native_symbol = self.snapshot_registry().await.native().symbol.clone();
+        let funded = preflight::ensure_funds(scoped, wallet, max_cost, spend, &native_symbol).await;
+        preflight::shortfall_warning(funded)
+    }
+
+    /// Scope one request's reads to `block`, or to the configured default tag.
+    async fn pin_block(&self, block: Option<&str>) -> AppResult<Arc<BlockScoped<RpcProvider>>> {
+        let requested = match block {
+            Some(block) => parse_block_id(block)?,
+            None => self.ctx.config.default_block_tag.block_id(),
+        };
+        let scoped = BlockScoped::pin(self.ctx.provider.clone(), Some(requested)).await?;
+        Ok(Arc::new(scoped))
     }
 
-    /// Convenience helper to avoid holding locks while we await downstream futures.
-    async fn snapshot_registry(&self) -> TokenRegistry {
+    /// The paper portfolio with `trade` (just booked) and the `trade_limit` latest trades.
+    async fn paper_portfolio(
+        &self,
+        trade: Option<PaperTrade>,
+        trade_limit: usize,
+    ) -> PaperPortfolioOut {
+        let registry_snapshot = self.snapshot_registry().await;
+        let mut portfolio = self
+            .ctx
+            .paper
+            .snapshot(&registry_snapshot, trade_limit, None);
+        portfolio.trade = trade.map(|trade| paper::trade_out(&registry_snapshot, &trade));
+        portfolio
+    }
+
+    /// Shared snapshot of the registry, so no lock is held while downstream futures run.
+    async fn snapshot_registry(&self) -> Arc<TokenRegistry> {
         self.ctx.registry.read().await.clone()
     }
 }
 
-fn parse_address_or_symbol(input: &str, registry: &TokenRegistry) -> AppResult<Address> {
-    if let Ok(addr) = input.parse::<Address>() {
-        return Ok(addr);
+/// Balance of `address` in `token` (the native coin when `None` or its symbol) at the pinned
+/// block.
+async fn balance_at(
+    scoped: &Arc<BlockScoped<RpcProvider>>,
+    registry: &TokenRegistry,
+    address: Address,
+    token: Option<&str>,
+) -> AppResult<BalanceOut> {
+    // The native symbol means the gas coin here, not its wrapped token.
+    let token = token
+        .filter(|token| !registry.is_native_symbol(token))
+        .map(|token| parse_address_or_symbol(token, registry))
+        .transpose()?;
+    let mut result =
+        balance::resolve_balance(scoped.clone(), address, token, &registry.native().symbol).await?;
+    result.block_number = Some(scoped.block_number());
+    if let Some(token) = token {
+        result.decimals_warning = registry.check_decimals(token, result.decimals as u8);
+        result.warnings = warnings::unverified_tokens(registry, &[token]);
+        result.warnings.extend(warnings::decimals_mismatch(
+            result.decimals_warning.as_ref(),
+        ));
+    }
+    Ok(result)
+}
+
+fn check_batch_len(len: usize) -> AppResult<()> {
+    if len == 0 || len > MAX_BATCH_ITEMS {
+        return Err(AppError::InvalidInput(format!(
+            "batches need between 1 and {MAX_BATCH_ITEMS} items"
+        )));
+    }
+    Ok(())
+}
+
+/// Wrap one item's outcome, mapping errors exactly as a single-item call would report them.
+fn batch_item<T>(index: usize, result: AppResult<T>) -> BatchItemOut<T> {
+    match result {
+        Ok(result) => BatchItemOut {
+            index,
+            ok: true,
+            result: Some(result),
+            error: None,
+        },
+        Err(err) => {
+            warn!("batch item {index} failed: {err}");
+            let payload = err.to_json_rpc();
+            BatchItemOut {
+                index,
+                ok: false,
+                result: None,
+                error: Some(BatchErrorOut {
+                    code: payload.code,
+                    message: payload.message,
+                    data: payload.data,
+                }),
+            }
+        }
     }
+}
+
+fn batch_out<T>(block_number: u64, items: Vec<BatchItemOut<T>>) -> BatchOut<T> {
+    let succeeded = items.iter().filter(|item| item.ok).count();
+    let failed = items.len() - succeeded;
+    BatchOut {
+        block_number,
+        succeeded,
+        failed,
+        warnings: warnings::partial_batch(failed, items.len())
+            .into_iter()
+            .collect(),
+        items,
+    }
+}
 
+fn parse_address_or_symbol(input: &str, registry: &TokenRegistry) -> AppResult<Address> {
+    registry.resolve_token(input)
+}
+
+/// `token`'s registry symbol, or its address when the registry does not know it.
+fn token_label(registry: &TokenRegistry, token: Address) -> String {
     registry
-        .resolve_symbol(input)
-        .ok_or_else(|| AppError::InvalidInput(format!("unknown token symbol or address: {input}")))
+        .info_by_address(token)
+        .map(|info| info.symbol.clone())
+        .unwrap_or_else(|| format!("{token:#x}"))
+}
+
+/// A non-negative USD amount such as `"2.5"`.
+fn parse_usd(name: &str, value: &str) -> AppResult<Decimal> {
+    Decimal::from_str(value)
+        .ok()
+        .filter(|amount| !amount.is_sign_negative())
+        .ok_or_else(|| AppError::InvalidInput(format!("invalid {name}: {value}")))
 }
 
 #[cfg(test)]
@@ -183,4 +3144,30 @@ mod tests {
         let err = parse_address_or_symbol("FOO", &registry).unwrap_err();
         assert!(matches!(err, AppError::InvalidInput(_)));
     }
+
+    #[test]
+    fn batch_items_keep_order_and_error_codes() {
+        let items = vec![
+            batch_item(0, Ok(1u8)),
+            batch_item(1, Err(AppError::Price("no route".into()))),
+            batch_item(2, Ok(3u8)),
+        ];
+        let out = batch_out(42, items);
+
+        assert_eq!((out.succeeded, out.failed), (2, 1));
+        let value = serde_json::to_value(&out).unwrap();
+        assert_eq!(value["items"][1]["index"], 1);
+        assert_eq!(value["items"][1]["ok"], false);
+        assert_eq!(value["items"][1]["error"]["code"], -32010);
+        assert!(value["items"][1].get("result").is_none());
+        assert_eq!(value["items"][2]["result"], 3);
+        assert_eq!(value["warnings"][0]["code"], "partial_failure");
+    }
+
+    #[test]
+    fn batch_len_is_bounded() {
+        assert!(check_batch_len(0).is_err());
+        assert!(check_batch_len(MAX_BATCH_ITEMS).is_ok());
+        assert!(check_batch_len(MAX_BATCH_ITEMS + 1).is_err());
+    }
 }
@@ -1,7 +1,14 @@
+pub mod activity;
 pub mod config;
 pub mod error;
+pub mod events;
 pub mod implementations;
+pub mod kill_switch;
 pub mod layers;
+pub mod progress;
+pub mod redact;
+pub mod schemas;
+pub mod secrets;
 pub mod types;
 pub mod wallet;
 
@@ -1,54 +1,216 @@
-mod config;
-mod error;
-mod implementations;
-mod layers;
-mod types;
-mod wallet;
-
 use std::sync::Arc;
 
-use config::AppConfig;
-use error::{AppError, AppResult};
-use ethers::providers::{Http, Provider};
-use layers::{
-    mcp::McpServer,
-    service::{ServiceContext, ServiceLayer},
-};
+use ethers::providers::Provider;
 use tokio::sync::RwLock;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
+use walletmcp::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+    implementations::{
+        self,
+        native::NativeCurrency,
+        price::TokenRegistry,
+        rpc::BalancedHttp,
+        self_test::{self, SelfTestTarget},
+        whitelist::unix_now,
+    },
+    layers::{
+        mcp::McpServer,
+        service::{ServiceContext, ServiceLayer},
+    },
+    redact::{RedactingStderr, redact},
+    types::SelfTestOut,
+    wallet,
+};
 
 #[tokio::main]
 async fn main() {
     if let Err(err) = run().await {
         error!("fatal error: {err}");
-        eprintln!("error: {err}");
+        eprintln!("error: {}", redact(&err.to_string()));
         std::process::exit(1);
     }
 }
 
+/// What the binary was asked to do.
+enum Command {
+    /// Serve MCP over stdio (no arguments).
+    Serve,
+    /// `walletmcp doctor`: print a self-test report and exit non-zero if a check failed.
+    Doctor,
+}
+
+impl Command {
+    fn from_args() -> AppResult<Self> {
+        match std::env::args().nth(1).as_deref() {
+            None => Ok(Command::Serve),
+            Some("doctor") => Ok(Command::Doctor),
+            Some(other) => Err(AppError::Config(format!(
+                "unknown command {other}; run without arguments to serve MCP over stdio, or `walletmcp doctor` for a self-test"
+            ))),
+        }
+    }
+}
+
 async fn run() -> AppResult<()> {
+    let command = Command::from_args()?;
     init_tracing();
 
     info!("loading configuration");
-    let config = AppConfig::load()?;
+    let config = Arc::new(AppConfig::load()?);
 
     info!("connecting to provider");
-    let provider = build_provider(&config.eth_rpc_url)?;
+    let provider = Provider::new(BalancedHttp::from_config(&config)?);
     let provider = Arc::new(provider);
 
+    if let Command::Doctor = command {
+        let report = doctor(&config, provider).await?;
+        println!("{}", report.summary);
+        if !report.passed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    info!("verifying provider chain id");
+    let chain_id =
+        implementations::chain::verify_chain_id(provider.clone(), config.default_chain_id).await?;
+
+    info!("resolving Uniswap V3 deployment");
+    let uniswap =
+        implementations::uniswap::UniswapDeployment::resolve(provider.clone(), chain_id, &config)
+            .await?;
+
+    for venue in &config.v2_venues {
+        venue.verify(provider.clone()).await?;
+    }
+
+    let native = NativeCurrency::from_config(&config, chain_id)?;
+
     info!("initialising wallet manager");
     let wallet = Arc::new(wallet::WalletManager::from_config(&config)?);
 
-    let registry = implementations::price::TokenRegistry::with_defaults();
-    let registry = Arc::new(RwLock::new(registry));
+    let mut registry = build_registry(&config, chain_id, native.clone())?
+        .with_uniswap_quoter(uniswap.quoter)
+        .with_uniswap_factory(uniswap.factory);
+    // Off mainnet the wrapper is usually missing from the bundled defaults.
+    if let Some(wrapped) = native.wrapped {
+        registry.ensure_token(provider.clone(), wrapped).await?;
+    }
+    // Feed decimals never change, so price lookups after this only read rounds.
+    let feeds = registry
+        .chainlink_feeds()
+        .into_iter()
+        .chain(implementations::native::fiat_usd_feeds(chain_id));
+    registry
+        .feed_decimals()
+        .prewarm(provider.clone(), feeds)
+        .await;
+    let registry = Arc::new(RwLock::new(Arc::new(registry)));
 
-    let service_ctx = Arc::new(ServiceContext::new(provider.clone(), registry, wallet));
+    let compliance = implementations::compliance::ComplianceScreen::from_config(&config)?;
+    let whitelist = implementations::whitelist::RecipientWhitelist::from_config(&config)?;
+    let recipients = implementations::recipients::RecipientBook::from_config(&config)?;
+    let burners = implementations::burner::BurnerVault::from_config(&config)?;
+    let providers = implementations::provider_pool::ProviderPool::from_config(
+        &config,
+        chain_id,
+        provider.clone(),
+    )?;
+    let paper = implementations::paper::PaperPortfolio::from_config(&config, chain_id)?;
+    let param_defaults = implementations::param_defaults::ParamDefaults::from_config(&config)?;
+    let attestor = implementations::attestation::ResponseAttestor::from_config(&config, &wallet)?;
+    let gasless = implementations::gasless::GaslessApi::from_config(&config)?;
+    let service_ctx = Arc::new(
+        ServiceContext::new(provider.clone(), registry, wallet, config.clone(), chain_id)
+            .with_compliance(compliance)
+            .with_whitelist(whitelist)
+            .with_recipients(recipients)
+            .with_uniswap(uniswap)
+            .with_burners(burners)
+            .with_providers(providers)
+            .with_paper(paper)
+            .with_param_defaults(param_defaults)
+            .with_attestor(attestor)
+            .with_gasless(gasless),
+    );
+    #[cfg(unix)]
+    spawn_sigusr1_stop(service_ctx.kill_switch.clone())?;
+    let providers = service_ctx.providers.clone();
     let service = ServiceLayer::new(service_ctx);
 
+    let _arbitrage_scanner = service.spawn_arbitrage_scanner();
     info!("starting MCP stdio server");
     let server = McpServer::new(service);
-    server.run_stdio().await
+    let result = server.run_stdio().await;
+    providers.shutdown().await;
+    result
+}
+
+/// The registry as configured, before anything is read from the chain.
+fn build_registry(
+    config: &AppConfig,
+    chain_id: u64,
+    native: NativeCurrency,
+) -> AppResult<TokenRegistry> {
+    let mut registry = TokenRegistry::with_defaults()
+        .with_usd_quotes(config.usd_quote_tokens.clone(), config.usd_quote_mode)
+        .with_depeg_threshold(config.depeg_threshold_bps)
+        .with_uniswap_reference_usd(config.uniswap_reference_usd)
+        .with_chain_id(chain_id)
+        .with_collision_policy(config.symbol_collision_policy)
+        .with_aliases(config.symbol_aliases.clone())
+        .with_native_alias(config.native_symbol_alias)
+        .with_v2_venues(config.v2_venues.clone())
+        .with_native(native);
+    if let Some(path) = config.token_list_path.as_deref() {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|err| AppError::Config(format!("failed to read token list {path}: {err}")))?;
+        let loaded = registry.load_token_list(&raw, chain_id)?;
+        info!("loaded {loaded} token list entries for decimals cross-checks");
+    }
+    Ok(registry)
+}
+
+/// `walletmcp doctor` runs before any startup check, so the self-test reports an unreachable
+/// RPC, a wrong chain id or a missing contract instead of exiting on the first one.
+async fn doctor(
+    config: &AppConfig,
+    provider: Arc<Provider<BalancedHttp>>,
+) -> AppResult<SelfTestOut> {
+    let chain_id = config.default_chain_id;
+    let native = NativeCurrency::from_config(config, chain_id)?;
+    let wallet = wallet::WalletManager::from_config(config)?;
+    let registry = build_registry(config, chain_id, native)?;
+    Ok(self_test::run(SelfTestTarget {
+        provider,
+        config,
+        registry,
+        chain_id,
+        signer: wallet.signer(),
+        now: unix_now(),
+    })
+    .await)
+}
+
+/// `kill -USR1 <pid>` engages the kill switch without going through the MCP client.
+#[cfg(unix)]
+fn spawn_sigusr1_stop(kill_switch: Arc<walletmcp::kill_switch::KillSwitch>) -> AppResult<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+    use tracing::warn;
+
+    let mut signals = signal(SignalKind::user_defined1())?;
+    tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            let status = kill_switch.stop("SIGUSR1 received", unix_now());
+            warn!(
+                "write operations suspended: {}",
+                status.reason.as_deref().unwrap_or_default()
+            );
+        }
+    });
+    Ok(())
 }
 
 fn init_tracing() {
@@ -57,10 +219,6 @@ fn init_tracing() {
         .with_env_filter(env_filter)
         .with_target(false)
         .with_line_number(true)
+        .with_writer(RedactingStderr)
         .init();
 }
-
-fn build_provider(url: &str) -> AppResult<Provider<Http>> {
-    Provider::<Http>::try_from(url)
-        .map_err(|err| AppError::Config(format!("failed to create provider: {err}")))
-}
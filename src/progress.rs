@@ -0,0 +1,107 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// One step of a long-running tool call: the params of an MCP `notifications/progress` without
+/// the caller's token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProgressUpdate {
+    pub progress: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// What a tool call emits while it runs: any number of progress steps, then exactly one
+/// `Finished` carrying its outcome. Nothing follows `Finished`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum ToolEvent<T> {
+    Progress(ProgressUpdate),
+    Finished(T),
+}
+
+impl<T> ToolEvent<T> {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ToolEvent::Finished(_))
+    }
+}
+
+/// Where a long-running service call reports progress; does nothing unless the caller passed a
+/// `progressToken`.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressReporter {
+    events: Option<mpsc::UnboundedSender<ToolEvent<Value>>>,
+    /// Last progress sent, plus one; 0 before the first.
+    last: Arc<AtomicU64>,
+}
+
+impl ProgressReporter {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn new(events: mpsc::UnboundedSender<ToolEvent<Value>>) -> Self {
+        Self {
+            events: Some(events),
+            last: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Report `progress` out of `total`. MCP requires progress to increase with every
+    /// notification, so a step that does not is dropped.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: impl Into<String>) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        let next = progress.saturating_add(1);
+        if self.last.fetch_max(next, Ordering::Relaxed) >= next {
+            return;
+        }
+        // The receiver only goes away once the call has finished.
+        let _ = events.send(ToolEvent::Progress(ProgressUpdate {
+            progress,
+            total,
+            message: Some(message.into()),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reporter_sends_only_increasing_progress() {
+        let (events, mut queue) = mpsc::unbounded_channel();
+        let reporter = ProgressReporter::new(events);
+        reporter.report(0, Some(10), "started");
+        reporter.report(3, Some(10), "polled");
+        reporter.clone().report(3, Some(10), "polled again");
+        reporter.report(2, Some(10), "late");
+        reporter.report(10, Some(10), "done");
+
+        let mut sent = Vec::new();
+        while let Ok(ToolEvent::Progress(update)) = queue.try_recv() {
+            sent.push(update.progress);
+        }
+        assert_eq!(sent, [0, 3, 10]);
+
+        ProgressReporter::disabled().report(1, None, "ignored");
+        assert_eq!(
+            serde_json::to_value(ToolEvent::Finished(json!({ "ok": true }))).unwrap(),
+            json!({ "event": "finished", "data": { "ok": true } })
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use ethers::signers::coins_bip39::{English, Wordlist};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Hex payloads longer than this many characters (after `0x`) are truncated.
+pub const MAX_HEX_CHARS: usize = 256;
+/// Shortest run of BIP-39 words treated as a mnemonic.
+const MIN_MNEMONIC_WORDS: usize = 12;
+
+/// 32-byte hex values labelled as a key or secret. Unlabelled ones are left alone: hashes and
+/// log topics have the same shape.
+static PRIVATE_KEY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)(\b(?:private[-_ ]?key|secret|key)["']?\s*[:=]?\s*["']?)(?:0x)?[0-9a-fA-F]{64}\b"#,
+    )
+    .unwrap()
+});
+static LONG_HEX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"0x[0-9a-fA-F]+").unwrap());
+/// RPC providers embed API keys in URL paths and query strings.
+static URL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(https?|wss?)://([^/\s()"'<>]+)([^\s()"'<>]*)"#).unwrap());
+static API_KEY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)((?:(?:x-)?api[-_]?key|authorization|(?:access|api|auth|refresh)[-_]?token)["']?\s*[:=]\s*["']?(?:bearer\s+)?|\bbearer\s+)([^\s"',;&]+)"#,
+    )
+    .unwrap()
+});
+
+/// Strip secrets from free-form text before it reaches logs or JSON-RPC error payloads.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut out = Cow::Borrowed(text);
+
+    if LONG_HEX_RE.is_match(&out) {
+        let replaced = LONG_HEX_RE.replace_all(&out, |caps: &regex::Captures| {
+            let hex = &caps[0][2..];
+            if hex.len() > MAX_HEX_CHARS {
+                format!("0x{}…[{} bytes]", &hex[..8], hex.len() / 2)
+            } else {
+                caps[0].to_string()
+            }
+        });
+        out = Cow::Owned(replaced.into_owned());
+    }
+    if PRIVATE_KEY_RE.is_match(&out) {
+        out = Cow::Owned(
+            PRIVATE_KEY_RE
+                .replace_all(&out, "${1}[REDACTED_KEY]")
+                .into_owned(),
+        );
+    }
+    if URL_RE.is_match(&out) {
+        let replaced = URL_RE.replace_all(&out, |caps: &regex::Captures| {
+            if caps[3].is_empty() || caps[3] == *"/" {
+                caps[0].to_string()
+            } else {
+                format!("{}://{}/[REDACTED]", &caps[1], &caps[2])
+            }
+        });
+        out = Cow::Owned(replaced.into_owned());
+    }
+    if API_KEY_RE.is_match(&out) {
+        out = Cow::Owned(API_KEY_RE.replace_all(&out, "${1}[REDACTED]").into_owned());
+    }
+    if let Some(redacted) = redact_mnemonics(&out) {
+        out = Cow::Owned(redacted);
+    }
+
+    out
+}
+
+/// [`redact`] every string inside `value`, e.g. the `data` of a JSON-RPC error.
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            if let Cow::Owned(redacted) = redact(text) {
+                *text = redacted;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(object) => object.values_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Replace runs of at least [`MIN_MNEMONIC_WORDS`] BIP-39 words.
+fn redact_mnemonics(text: &str) -> Option<String> {
+    let words = English::get_all();
+    let mut spans = Vec::new();
+    let mut run_start: Option<usize> = None;
+    let mut run_end = 0;
+    let mut run_len = 0;
+
+    let mut offset = 0;
+    for token in text.split_inclusive(char::is_whitespace) {
+        let word = token.trim_end();
+        let start = offset;
+        offset += token.len();
+
+        let cleaned = word.trim_matches(|c: char| !c.is_ascii_alphabetic());
+        if !cleaned.is_empty() && words.binary_search(&cleaned).is_ok() {
+            run_start.get_or_insert(start);
+            run_end = start
+                + word
+                    .trim_end_matches(|c: char| !c.is_ascii_alphabetic())
+                    .len();
+            run_len += 1;
+            continue;
+        }
+        if run_len >= MIN_MNEMONIC_WORDS
+            && let Some(start) = run_start
+        {
+            spans.push((start, run_end));
+        }
+        run_start = None;
+        run_len = 0;
+    }
+    if run_len >= MIN_MNEMONIC_WORDS
+        && let Some(start) = run_start
+    {
+        spans.push((start, run_end));
+    }
+
+    if spans.is_empty() {
+        return None;
+    }
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (start, end) in spans {
+        redacted.push_str(&text[cursor..start]);
+        redacted.push_str("[REDACTED_MNEMONIC]");
+        cursor = end;
+    }
+    redacted.push_str(&text[cursor..]);
+    Some(redacted)
+}
+
+/// `MakeWriter` for `tracing_subscriber::fmt` that redacts every formatted event on stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactingStderr;
+
+impl<'a> MakeWriter<'a> for RedactingStderr {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { buffer: Vec::new() }
+    }
+}
+
+/// Buffers one formatted event and writes the redacted text to stderr on flush/drop.
+pub struct RedactingWriter {
+    buffer: Vec<u8>,
+}
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&self.buffer);
+        let mut stderr = io::stderr().lock();
+        stderr.write_all(redact(&text).as_bytes())?;
+        self.buffer.clear();
+        stderr.flush()
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_private_keys() {
+        let key = "59c6995e998f97a5a0044966f0945382d0b7adf99019cba46777e1fbbf3a1b02";
+        let text = format!("failed to parse private key 0x{key}");
+        assert_eq!(redact(&text), "failed to parse private key [REDACTED_KEY]");
+        assert_eq!(
+            redact(&format!("PRIVATE_KEY={key}")),
+            "PRIVATE_KEY=[REDACTED_KEY]"
+        );
+        assert_eq!(
+            redact(&format!(r#"{{"secret": "{key}"}}"#)),
+            r#"{"secret": "[REDACTED_KEY]"}"#
+        );
+
+        // Hashes and topics share the shape but carry no label.
+        let missing = format!("transaction 0x{key} not found");
+        assert_eq!(redact(&missing), missing);
+    }
+
+    #[test]
+    fn leaves_addresses_and_short_hex_alone() {
+        let text = "recipient 0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045 value 0x5208";
+        assert_eq!(redact(text), text);
+    }
+
+    #[test]
+    fn truncates_long_calldata() {
+        let calldata = format!("0x414bf389{}", "00".repeat(200));
+        let redacted = redact(&calldata);
+        assert_eq!(redacted, "0x414bf389…[204 bytes]");
+    }
+
+    #[test]
+    fn redacts_url_paths_and_api_keys() {
+        let text =
+            "error sending request for url (https://eth-mainnet.g.alchemy.com/v2/AbCdEf123456)";
+        assert_eq!(
+            redact(text),
+            "error sending request for url (https://eth-mainnet.g.alchemy.com/[REDACTED])"
+        );
+        assert_eq!(redact("X-API-Key: sk_live_123"), "X-API-Key: [REDACTED]");
+        assert_eq!(
+            redact("Authorization: Bearer abc.def"),
+            "Authorization: Bearer [REDACTED]"
+        );
+        assert_eq!(redact("access_token=abc123"), "access_token=[REDACTED]");
+        let unsupported = "unsupported token: 0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+        assert_eq!(redact(unsupported), unsupported);
+        assert_eq!(redact("https://rpc.example.org"), "https://rpc.example.org");
+    }
+
+    #[test]
+    fn error_data_is_redacted_like_messages() {
+        let err = crate::error::AppError::Reverted {
+            error: Box::new(crate::error::AppError::Swap("simulation failed".into())),
+            revert_reason: "STF".into(),
+            revert_data: format!("0x08c379a0{}", "00".repeat(200)),
+        };
+        let payload = err.to_json_rpc();
+        assert_eq!(payload.data["revert_data"], "0x08c379a0…[204 bytes]");
+        assert_eq!(payload.data["revert_reason"], "STF");
+
+        let leaked = format!("key: 0x{}", "ab".repeat(32));
+        let mut data = serde_json::json!({ "trace": { "calls": [{ "error": leaked }] } });
+        redact_value(&mut data);
+        assert_eq!(data["trace"]["calls"][0]["error"], "key: [REDACTED_KEY]");
+    }
+
+    #[test]
+    fn redacts_mnemonics() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let text = format!("loaded mnemonic: {phrase}.");
+        assert_eq!(redact(&text), "loaded mnemonic: [REDACTED_MNEMONIC].");
+        let prose = "swap simulation requires a signer to build a realistic transaction context";
+        assert_eq!(redact(prose), prose);
+    }
+}
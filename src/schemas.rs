@@ -0,0 +1,372 @@
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema, schema_for};
+
+use crate::types::{
+    ActivityHistoryOut, ActivityReportOut, ApprovalTemplatesOut, ArbPlanOut, ArbScanOut,
+    AuthorizationOut, BacktestOut, BacktestSwapStrategyParams, BalanceOut, BatchOut, BundleOut,
+    BurnerWalletOut, CandlesOut, ChainInfoOut, ConvertUnitsOut, ConvertUnitsParams,
+    CreateBurnerWalletParams, DelegationOut, DepositWatchOut, DistributeTokensParams,
+    DistributionOut, EventsOut, GaslessSwapOut, GaslessSwapParams, GetActivityHistoryParams,
+    GetActivityReportParams, GetArbitragePlanParams, GetBalanceParams, GetBalancesParams,
+    GetCandlesParams, GetDelegationParams, GetEventsParams, GetNativePriceParams,
+    GetPaperPortfolioParams, GetPortfolioRiskParams, GetQuoteLadderParams, GetSchemasParams,
+    GetTokenDeploymentsParams, GetTokenPriceParams, GetTokenPricesParams, GetWalletInfoParams,
+    KillSwitchOut, ListApprovalTemplatesParams, ListQuoteCurrenciesParams, ListTokensOut,
+    ListTokensParams, MaintenanceOut, MempoolWatchOut, NativePriceOut, PanicStopParams,
+    PaperFundParams, PaperPortfolioOut, PaperSwapParams, PaperTransferParams,
+    ParsePaymentRequestParams, PaymentRequestOut, PlanRebalanceParams, PortfolioRiskOut, PriceOut,
+    ProviderPoolOut, QuoteCurrenciesOut, QuoteLadderOut, RebalancePlanOut, ResponseMetaOut,
+    ResumeParams, ScanArbitrageParams, SchemasOut, SelfTestOut, SignAuthorizationParams,
+    SimulateBundleParams, SpreadWatchOut, SwapPlanOut, SwapSimOut, SwapTokensParams, SweepOut,
+    SweepToOut, SweepToParams, SweepWalletParams, TokenDeploymentsOut, TokenRiskOut,
+    TokenRiskParams, TokenWatchOut, ToolSchemaOut, TraceCallOut, TraceCallParams, TransferFromOut,
+    TransferFromParams, TxWatchOut, VerifySignatureOut, VerifySignatureParams, WalletInfoOut,
+    WatchDepositsParams, WatchMempoolParams, WatchSpreadParams, WatchTokenParams,
+    WatchTransactionParams, WhitelistAddressParams, WhitelistEntryOut, WhitelistOut,
+};
+
+/// JSON Schemas (draft 2020-12) for every tool's params and result, generated from the same
+/// types `mcp.rs` dispatches with, so they cannot drift from what the server accepts and returns.
+pub fn catalog() -> Vec<ToolSchemaOut> {
+    vec![
+        tool::<GetBalanceParams, BalanceOut>("get_balance", "ETH or ERC-20 balance of an address."),
+        tool::<GetBalancesParams, BatchOut<BalanceOut>>(
+            "get_balances",
+            "Several balances of one address at one block; failures are reported per item.",
+        ),
+        tool::<GetTokenPriceParams, PriceOut>(
+            "get_token_price",
+            "Token price in USD or ETH from Chainlink, falling back to Uniswap V3.",
+        ),
+        tool::<GetTokenPricesParams, BatchOut<PriceOut>>(
+            "get_token_prices",
+            "Prices for several tokens at one block; failures are reported per item.",
+        ),
+        tool::<GetNativePriceParams, NativePriceOut>(
+            "get_native_price",
+            "The chain's native coin price in fiat, straight from Chainlink.",
+        ),
+        tool::<SwapTokensParams, SwapSimOut>(
+            "swap_tokens",
+            "Simulate a Uniswap V3 exact-input swap without broadcasting.",
+        ),
+        tool::<SwapTokensParams, SwapPlanOut>(
+            "plan_swap",
+            "Ordered wrap, approve and swap transactions needed to execute a swap.",
+        ),
+        tool::<GaslessSwapParams, GaslessSwapOut>(
+            "gasless_swap",
+            "Quote a swap through the 0x Gasless API, where a relayer pays gas and takes its fee in the sold token, and optionally sign its orders. Nothing is submitted.",
+        ),
+        tool::<TransferFromParams, TransferFromOut>(
+            "transfer_from",
+            "Simulate moving approved ERC-20 tokens from another address.",
+        ),
+        tool::<DistributeTokensParams, DistributionOut>(
+            "distribute_tokens",
+            "Plan ERC-20 payouts to many recipients.",
+        ),
+        tool::<PlanRebalanceParams, RebalancePlanOut>(
+            "plan_rebalance",
+            "Quoted swaps that move a wallet's holdings back to target weights.",
+        ),
+        tool::<SweepToParams, SweepToOut>(
+            "sweep_to",
+            "Quoted swaps consolidating a wallet's small token balances into one target token, skipping balances not worth their gas.",
+        ),
+        tool::<GetQuoteLadderParams, QuoteLadderOut>(
+            "get_quote_ladder",
+            "Quotes for increasing input sizes to show price impact.",
+        ),
+        tool::<GetCandlesParams, CandlesOut>(
+            "get_candles",
+            "OHLCV candles built from a Uniswap V3 pool's swap logs.",
+        ),
+        tool::<BacktestSwapStrategyParams, BacktestOut>(
+            "backtest_swap_strategy",
+            "Replay a buy-below/sell-above rule over historical prices (archive node required) and report the hypothetical trades and P&L.",
+        ),
+        tool::<GetPortfolioRiskParams, PortfolioRiskOut>(
+            "get_portfolio_risk",
+            "Concentration, stablecoin share and 30-day volatility of a wallet's holdings, from daily historical prices (archive node required).",
+        ),
+        tool::<ScanArbitrageParams, ArbScanOut>(
+            "scan_arbitrage",
+            "Quote a round trip on each pair across the Uniswap V3 fee tiers and configured V2 forks, returning the ones that net more than their gas.",
+        ),
+        tool::<GetArbitragePlanParams, ArbPlanOut>(
+            "get_arbitrage_plan",
+            "The swaps that would capture an opportunity from a recent scan_arbitrage or arb_opportunity event.",
+        ),
+        tool::<NoParams, ChainInfoOut>("get_chain_info", "Chain id, name and latest block."),
+        tool::<NoParams, ProviderPoolOut>(
+            "get_provider_pool",
+            "Connection state and failure counts of the per-chain HTTP and WebSocket providers.",
+        ),
+        tool::<WatchMempoolParams, MempoolWatchOut>(
+            "watch_mempool",
+            "Pending transactions touching a wallet or tokens.",
+        ),
+        tool::<WatchDepositsParams, DepositWatchOut>(
+            "watch_deposits",
+            "Long-poll for incoming ETH and ERC-20 deposits.",
+        ),
+        tool::<WatchTokenParams, TokenWatchOut>(
+            "watch_token",
+            "Watch one token's Transfer events to or from an address for a window, each also published as a token_transfer event.",
+        ),
+        tool::<WatchTransactionParams, TxWatchOut>(
+            "watch_transaction",
+            "Follow a broadcast transaction until it is mined, or report it stuck below the market fee with replacement fees (a stuck_tx event).",
+        ),
+        tool::<WatchSpreadParams, SpreadWatchOut>(
+            "watch_spread",
+            "Follow the price ratio of two tokens, or of a token and a Chainlink USD reference feed, until it leaves a band around its target; the breach is also published as a spread_alert event.",
+        ),
+        tool::<WhitelistAddressParams, WhitelistEntryOut>(
+            "whitelist_address",
+            "Allow a recipient after the configured time lock.",
+        ),
+        tool::<NoParams, WhitelistOut>(
+            "get_whitelist",
+            "Whitelisted recipients and when each becomes usable.",
+        ),
+        tool::<PanicStopParams, KillSwitchOut>(
+            "panic_stop",
+            "Suspend every write operation until resume.",
+        ),
+        tool::<ResumeParams, KillSwitchOut>("resume", "Lift a panic_stop with the admin secret."),
+        tool::<GetEventsParams, EventsOut>(
+            "get_events",
+            "Internal events (price ticks, deposits, pending transactions, halts) from the server's append-only log.",
+        ),
+        tool::<NoParams, MaintenanceOut>(
+            "maintenance",
+            "Apply every cache and log limit now (expired ENS names, activity and paper trade retention) and report each store's size.",
+        ),
+        tool::<NoParams, SelfTestOut>(
+            "self_test",
+            "Check RPC, chain id, the startup contract checks, wallet signing, Chainlink feeds, the Uniswap quoter and the write path (estimated, never broadcast). Also available as `walletmcp doctor`.",
+        ),
+        tool::<GetActivityReportParams, ActivityReportOut>(
+            "get_activity_report",
+            "Recent tool calls, swap simulations and errors.",
+        ),
+        tool::<GetActivityHistoryParams, ActivityHistoryOut>(
+            "get_activity_history",
+            "Journaled write-tool calls with their memo and tags, filterable by tag.",
+        ),
+        tool::<TokenRiskParams, TokenRiskOut>(
+            "token_risk",
+            "Bytecode heuristics for unfamiliar tokens.",
+        ),
+        tool::<TraceCallParams, TraceCallOut>(
+            "trace_call",
+            "debug_traceCall call tree with reverted frames highlighted.",
+        ),
+        tool::<SimulateBundleParams, BundleOut>(
+            "simulate_bundle",
+            "Simulate dependent transactions in order against one state (eth_simulateV1), e.g. approve then swap then transfer, with per-step results.",
+        ),
+        tool::<VerifySignatureParams, VerifySignatureOut>(
+            "verify_signature",
+            "Check a signature by an externally owned account (ECDSA) or a contract wallet such as a Safe or ERC-4337 account (ERC-1271 isValidSignature).",
+        ),
+        tool::<GetDelegationParams, DelegationOut>(
+            "get_delegation",
+            "Whether an account (the wallet by default) is a plain account, runs another contract's code through an EIP-7702 delegation, or is a contract.",
+        ),
+        tool::<SignAuthorizationParams, AuthorizationOut>(
+            "sign_authorization",
+            "Sign (never send) an EIP-7702 authorization delegating the wallet to a contract, or revoking its delegation.",
+        ),
+        tool::<ListTokensParams, ListTokensOut>(
+            "list_tokens",
+            "Registered tokens, with symbols shared by several tokens called out.",
+        ),
+        tool::<ListApprovalTemplatesParams, ApprovalTemplatesOut>(
+            "list_approval_templates",
+            "Curated approve(spender, amount) templates for the contracts this server's tools route tokens through, so spender addresses never have to be typed by hand.",
+        ),
+        tool::<GetWalletInfoParams, WalletInfoOut>(
+            "get_wallet_info",
+            "The signer's address and an EIP-681 payment URI to fund it, optionally as a text QR code.",
+        ),
+        tool::<ListQuoteCurrenciesParams, QuoteCurrenciesOut>(
+            "list_quote_currencies",
+            "Which quotes a base token can be priced in on the active chain (USD, the native coin, other fiats, and tokens to swap into), the tool that prices each, and its routes.",
+        ),
+        tool::<ParsePaymentRequestParams, PaymentRequestOut>(
+            "parse_payment_request",
+            "Decode an EIP-681 ethereum: payment URI or a signed raw transaction into recipient, token and amount, with distribute_tokens params for token payments.",
+        ),
+        tool::<GetTokenDeploymentsParams, TokenDeploymentsOut>(
+            "get_token_deployments",
+            "Where a canonical asset (USDC, USDT, DAI, WETH, WBTC) is deployed on each chain, to aggregate holdings across networks or pick a bridge destination token.",
+        ),
+        tool::<ConvertUnitsParams, ConvertUnitsOut>(
+            "convert_units",
+            "Convert an amount exactly between wei, gwei and ether, or between a token's raw base units and human units.",
+        ),
+        tool::<CreateBurnerWalletParams, BurnerWalletOut>(
+            "create_burner_wallet",
+            "Generate a throwaway wallet for risky interactions, its key encrypted in the keystore dir, with an EIP-681 request to fund it.",
+        ),
+        tool::<SweepWalletParams, SweepOut>(
+            "sweep_wallet",
+            "Sign (without broadcasting) transactions moving a burner's tokens, then its native coin less gas, back to the main wallet.",
+        ),
+        tool::<PaperFundParams, PaperPortfolioOut>(
+            "paper_fund",
+            "Credit the paper-trading portfolio with virtual tokens, optionally starting it over.",
+        ),
+        tool::<PaperSwapParams, PaperPortfolioOut>(
+            "paper_swap",
+            "Swap paper balances at the live Uniswap V3 quote; nothing is signed or sent.",
+        ),
+        tool::<PaperTransferParams, PaperPortfolioOut>(
+            "paper_transfer",
+            "Send paper balance to an address or alias; it leaves the portfolio.",
+        ),
+        tool::<GetPaperPortfolioParams, PaperPortfolioOut>(
+            "get_paper_portfolio",
+            "Paper-trading balances and the most recent paper trades.",
+        ),
+        tool::<GetSchemasParams, SchemasOut>(
+            "get_schemas",
+            "JSON Schemas for every tool's params and result.",
+        ),
+    ]
+}
+
+/// Every result also carries the `meta` envelope added by the MCP layer.
+fn tool<P: JsonSchema, T: JsonSchema>(name: &str, description: &str) -> ToolSchemaOut {
+    let mut result = schema_for!(T);
+    let mut meta = schema_for!(ResponseMetaOut);
+    meta.remove("$schema");
+    if let Some(properties) = result
+        .get_mut("properties")
+        .and_then(|properties| properties.as_object_mut())
+    {
+        properties.insert("meta".to_string(), meta.to_value());
+    }
+    if let Some(required) = result
+        .get_mut("required")
+        .and_then(|required| required.as_array_mut())
+    {
+        required.push("meta".into());
+    }
+    ToolSchemaOut {
+        name: name.to_string(),
+        description: description.to_string(),
+        params: schema_for!(P).to_value(),
+        result: result.to_value(),
+    }
+}
+
+/// Params of the tools that take none: they accept an empty object or no params at all.
+struct NoParams;
+
+impl JsonSchema for NoParams {
+    fn schema_name() -> Cow<'static, str> {
+        "NoParams".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({ "type": ["object", "null"] })
+    }
+}
+
+/// An optional EIP-2930 access list, as ethers serializes it.
+pub fn access_list(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": ["array", "null"],
+        "items": {
+            "type": "object",
+            "properties": {
+                "address": { "type": "string" },
+                "storageKeys": { "type": "array", "items": { "type": "string" } },
+            },
+            "required": ["address", "storageKeys"],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use std::collections::HashSet;
+
+    #[test]
+    fn required_fields_are_declared() {
+        let catalog = catalog();
+        let names: HashSet<_> = catalog.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names.len(), catalog.len());
+
+        fn check(schema: &Value, path: &str) {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for field in required {
+                    let field = field.as_str().unwrap();
+                    assert!(
+                        schema["properties"].get(field).is_some(),
+                        "{path}: required field {field} has no schema"
+                    );
+                }
+            }
+            for (name, child) in schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                check(child, &format!("{path}.{name}"));
+            }
+            if let Some(items) = schema.get("items") {
+                check(items, &format!("{path}[]"));
+            }
+            for (name, definition) in schema
+                .get("$defs")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                check(definition, &format!("{path}.$defs.{name}"));
+            }
+        }
+        for tool in &catalog {
+            check(&tool.params, &format!("{}.params", tool.name));
+            check(&tool.result, &format!("{}.result", tool.name));
+        }
+    }
+
+    #[test]
+    fn params_schema_matches_deserializer() {
+        let swap = catalog()
+            .into_iter()
+            .find(|tool| tool.name == "swap_tokens")
+            .unwrap();
+        let required: Vec<&str> = swap.params["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(required, ["from_token", "to_token", "amount_in_wei"]);
+
+        // Exactly the required fields are enough for the real params type.
+        let minimal: crate::types::SwapTokensParams = serde_json::from_value(json!({
+            "from_token": "WETH",
+            "to_token": "USDC",
+            "amount_in_wei": "1",
+        }))
+        .unwrap();
+        assert_eq!(
+            minimal.slippage_bps as u64,
+            swap.params["properties"]["slippage_bps"]["default"]
+                .as_u64()
+                .unwrap()
+        );
+    }
+}
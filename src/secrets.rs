@@ -0,0 +1,197 @@
+use std::{env, fmt, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::{
+    config::AppConfig,
+    error::{AppError, AppResult},
+};
+
+/// Name under which the signer key is stored in every backend.
+pub const PRIVATE_KEY_SECRET: &str = "private_key";
+
+/// Name of the password that encrypts burner wallet keystores.
+pub const BURNER_PASSWORD_SECRET: &str = "burner_password";
+
+/// String wrapper for key material; `Debug` never prints the contents.
+#[derive(Clone, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Borrow the raw secret. Callers must not log the returned value.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+/// Compare without short-circuiting so response timing does not leak the secret prefix.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Where secrets such as the signer key are read from.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretsBackend {
+    /// `private_key` from `Config.toml` (or the env fallback when no file exists).
+    #[default]
+    Config,
+    /// Process environment, e.g. injected by a container orchestrator.
+    Env,
+    /// OS keyring (macOS Keychain, Windows Credential Manager, Linux kernel keyutils).
+    Keyring,
+}
+
+impl FromStr for SecretsBackend {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "config" => Ok(SecretsBackend::Config),
+            "env" => Ok(SecretsBackend::Env),
+            "keyring" => Ok(SecretsBackend::Keyring),
+            other => Err(AppError::Config(format!(
+                "unknown secrets backend: {other}"
+            ))),
+        }
+    }
+}
+
+/// Source of named secrets.
+pub trait SecretsProvider: Send + Sync {
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>>;
+}
+
+/// Secrets already present in the loaded configuration.
+pub struct ConfigSecrets {
+    private_key: Option<SecretString>,
+    burner_password: Option<SecretString>,
+}
+
+impl SecretsProvider for ConfigSecrets {
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>> {
+        match name {
+            PRIVATE_KEY_SECRET => Ok(self.private_key.clone()),
+            BURNER_PASSWORD_SECRET => Ok(self.burner_password.clone()),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Secrets injected through environment variables named after the upper-cased secret.
+pub struct EnvSecrets;
+
+impl SecretsProvider for EnvSecrets {
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>> {
+        Ok(env::var(name.to_ascii_uppercase())
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(SecretString::from))
+    }
+}
+
+/// Secrets stored in the OS keyring under `service`, one entry per secret name.
+#[cfg(feature = "keyring")]
+pub struct KeyringSecrets {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringSecrets {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl SecretsProvider for KeyringSecrets {
+    fn get(&self, name: &str) -> AppResult<Option<SecretString>> {
+        let entry = keyring::Entry::new(&self.service, name)
+            .map_err(|err| AppError::Wallet(format!("failed to open keyring entry: {err}")))?;
+        match entry.get_password() {
+            Ok(value) => Ok(Some(SecretString::from(value))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(AppError::Wallet(format!(
+                "failed to read {name} from keyring: {err}"
+            ))),
+        }
+    }
+}
+
+/// Build the provider selected by `secrets_backend`.
+pub fn provider_from_config(config: &AppConfig) -> AppResult<Box<dyn SecretsProvider>> {
+    match config.secrets_backend {
+        SecretsBackend::Config => Ok(Box::new(ConfigSecrets {
+            private_key: config.private_key.clone(),
+            burner_password: config.burner_password.clone(),
+        })),
+        SecretsBackend::Env => Ok(Box::new(EnvSecrets)),
+        #[cfg(feature = "keyring")]
+        SecretsBackend::Keyring => Ok(Box::new(KeyringSecrets::new(
+            config.keyring_service.clone(),
+        ))),
+        #[cfg(not(feature = "keyring"))]
+        SecretsBackend::Keyring => Err(AppError::Config(
+            "secrets_backend = \"keyring\" requires building with the `keyring` feature".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_debug_is_redacted() {
+        let secret = SecretString::new("0xdeadbeef");
+        assert_eq!(format!("{secret:?}"), "SecretString(***)");
+        assert_eq!(secret.expose(), "0xdeadbeef");
+    }
+
+    #[test]
+    fn config_secrets_only_serve_known_names() {
+        let provider = ConfigSecrets {
+            private_key: Some(SecretString::new("abc")),
+            burner_password: None,
+        };
+        assert_eq!(
+            provider.get(PRIVATE_KEY_SECRET).unwrap(),
+            Some(SecretString::new("abc"))
+        );
+        assert_eq!(provider.get(BURNER_PASSWORD_SECRET).unwrap(), None);
+        assert_eq!(provider.get("other").unwrap(), None);
+    }
+
+    #[test]
+    fn backend_parses_case_insensitively() {
+        assert_eq!(
+            "Keyring".parse::<SecretsBackend>().unwrap(),
+            SecretsBackend::Keyring
+        );
+        assert!("vault".parse::<SecretsBackend>().is_err());
+    }
+}
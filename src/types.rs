@@ -1,34 +1,89 @@
+use crate::{events::EventRecord, secrets::SecretString};
+use ethers::types::transaction::eip2930::AccessList;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetBalanceParams {
     pub address: String,
     #[serde(default)]
     pub token: Option<String>,
+    /// Historical block (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BalanceOut {
     pub symbol: String,
     pub raw: String,
     pub decimals: u32,
     pub formatted: String,
+    /// Block the balance was read at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Set when the contract's `decimals()` disagrees with token-list data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals_warning: Option<String>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetBalancesParams {
+    pub address: String,
+    /// Token addresses or symbols; `null` reads the native coin.
+    pub tokens: Vec<Option<String>>,
+    /// Historical block (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+/// Outcome of one batch item: `result` when `ok`, otherwise `error` in the JSON-RPC taxonomy.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchItemOut<T> {
+    /// Position of the item in the request.
+    pub index: usize,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchErrorOut>,
+}
+
+/// Same `code` / `message` / `data` a single-item call would have failed with.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchErrorOut {
+    pub code: i32,
+    pub message: String,
+    pub data: Value,
+}
+
+/// One entry per requested item, in request order, all read at `block_number`.
+///
+/// A failing item never fails the batch; only request-level problems (bad shared params, an
+/// unreachable block) do.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchOut<T> {
+    pub block_number: u64,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemOut<T>>,
+    pub warnings: Vec<WarningOut>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum QuoteCurrency {
+    #[default]
     USD,
+    /// The active chain's native coin (ETH, MATIC, AVAX, ...), quoted through its wrapper.
+    #[serde(alias = "NATIVE")]
     ETH,
 }
 
-impl Default for QuoteCurrency {
-    fn default() -> Self {
-        QuoteCurrency::USD
-    }
-}
-
 impl fmt::Display for QuoteCurrency {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -38,23 +93,122 @@ impl fmt::Display for QuoteCurrency {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// How `get_token_price` picks among its price sources.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceMode {
+    /// Chainlink when a feed route exists, otherwise Uniswap.
+    #[default]
+    Fallback,
+    /// Every available source at once, reporting their median.
+    Median,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetTokenPriceParams {
     pub base: String,
     #[serde(default)]
     pub quote: QuoteCurrency,
+    #[serde(default)]
+    pub mode: PriceMode,
+    /// Report `base` per one `quote` (e.g. USDC per ETH) instead of `quote` per `base`.
+    #[serde(default)]
+    pub invert: bool,
+    /// Historical block (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Attach a per-backend `timings` breakdown to the response.
+    #[serde(default)]
+    pub debug: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct PriceOut {
     pub base: String,
     pub quote: String,
     pub price: String,
     pub source: String,
     pub decimals: u32,
+    /// Block the feeds and pools were read at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Set when a USD stablecoin proxy traded off its peg and the price was rescaled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depeg_warning: Option<String>,
+    /// Every source consulted in `median` mode, including the ones that failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<PriceSourceOut>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<TimingsOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PriceSourceOut {
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetTokenPricesParams {
+    /// Token addresses or symbols.
+    pub bases: Vec<String>,
+    #[serde(default)]
+    pub quote: QuoteCurrency,
+    /// Report each base per one `quote` instead of `quote` per base.
+    #[serde(default)]
+    pub invert: bool,
+    /// Historical block (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetNativePriceParams {
+    /// Fiat currencies to price in; defaults to `["USD"]`.
+    #[serde(default)]
+    pub currencies: Vec<String>,
+    /// Historical block (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FiatPriceOut {
+    pub currency: String,
+    pub price: String,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NativePriceOut {
+    pub symbol: String,
+    pub prices: Vec<FiatPriceOut>,
+    /// `updatedAt` of the native/USD round, in unix seconds.
+    pub updated_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub warnings: Vec<WarningOut>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Latency breakdown returned when a request sets `debug: true`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct TimingsOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chainlink_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quoter_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate_gas_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call_ms: Option<u64>,
+    pub total_ms: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SwapTokensParams {
     pub from_token: String,
     pub to_token: String,
@@ -65,8 +219,38 @@ pub struct SwapTokensParams {
     pub fee: u32,
     #[serde(default)]
     pub recipient: Option<String>,
+    /// Raw `sqrtPriceLimitX96`; prefer `price_limit`.
     #[serde(default)]
     pub sqrt_price_limit: Option<String>,
+    /// Human price boundary such as `"2600"` or `"2600 USDC/WETH"`, converted to `sqrt_price_limit`.
+    #[serde(default)]
+    pub price_limit: Option<String>,
+    /// Explicit gas limit; skips the configured buffer on top of `eth_estimateGas`.
+    #[serde(default)]
+    pub gas_limit: Option<u64>,
+    /// Proceed despite compliance hits; only honoured when config allows overrides.
+    #[serde(default)]
+    pub compliance_override: bool,
+    /// Block to simulate against (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Attach a per-backend `timings` breakdown to the response.
+    #[serde(default)]
+    pub debug: bool,
+    /// Free-form note journaled with the simulation, e.g. the strategy behind it.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels journaled with the simulation; `get_activity_report` and `get_activity_history`
+    /// can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// On a reverted simulation, attach a `debug_traceCall` call tree under `error.data.trace`.
+    #[serde(default)]
+    pub trace_on_failure: bool,
+    /// `quote_id` of an earlier `swap_tokens` call; the call fails unless its parameters are the
+    /// quoted ones, the id has not expired, and the minimum output is at least the quoted one.
+    #[serde(default)]
+    pub quote_id: Option<String>,
 }
 
 fn default_slippage_bps() -> u32 {
@@ -77,11 +261,2149 @@ fn default_fee() -> u32 {
     3_000
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SwapFeesOut {
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    /// `gas_estimate * max_fee_per_gas`.
+    pub estimated_cost_wei: String,
+    pub estimated_cost_eth: String,
+    /// `gas_limit * max_fee_per_gas`, the most a broadcast can be charged.
+    pub max_cost_wei: String,
+    pub max_cost_eth: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RouteHopOut {
+    pub token_in: String,
+    pub token_out: String,
+    pub fee: u32,
+    pub pool: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SwapSimOut {
+    /// Block every read in the simulation was pinned to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
     pub amount_out_estimate: String,
+    /// Decimals reported by the output token, used to format the amounts.
+    pub to_decimals: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals_warning: Option<String>,
     pub gas_estimate: String,
+    pub gas_limit: String,
+    /// EIP-1559 fees used to price the estimate; absent when the node cannot provide fee data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<SwapFeesOut>,
+    /// EIP-2930 access list to attach when broadcasting; present only when it lowers gas.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "crate::schemas::access_list")]
+    pub access_list: Option<AccessList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_list_gas_savings: Option<String>,
     pub calldata_hex: String,
     pub router: String,
+    /// Address receiving the output, after alias and default resolution.
+    pub recipient: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient_ens: Option<String>,
+    /// Pools the swap goes through, in order.
+    pub route: Vec<RouteHopOut>,
+    /// Quoted `to_token` received per `from_token`, in human units.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_price: Option<String>,
+    pub amount_out_min: String,
+    /// `amount_out_min` in the output token's base units.
+    pub amount_out_min_raw: String,
+    pub mev_risk: MevRiskOut,
+    /// Pass to `plan_swap` or `swap_tokens` to hold them to these parameters and minimum output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_id: Option<String>,
+    /// Unix time after which `quote_id` is refused.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<TimingsOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GaslessSwapParams {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in_wei: String,
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u32,
+    /// Sign the orders with the wallet so they can be submitted to the relayer.
+    #[serde(default)]
+    pub sign: bool,
+    #[serde(default)]
+    pub compliance_override: bool,
+    /// Free-form note journaled with the call, e.g. the strategy behind it.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels journaled with the call; `get_activity_history` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GaslessSwapOut {
+    pub chain_id: u64,
+    /// The wallet that signs and receives the output.
+    pub taker: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub sell_amount_raw: String,
+    pub buy_amount_raw: String,
+    pub buy_amount: String,
+    pub min_buy_amount_raw: String,
+    pub min_buy_amount: String,
+    /// Relayer and 0x fees, taken from the sold token.
+    pub fees: Vec<GaslessFeeOut>,
+    /// Gasless approval to sign alongside the trade, when the sold token needs one.
+    pub approval: Option<GaslessOrderOut>,
+    pub trade: GaslessOrderOut,
+    /// Body for `POST /gasless/submit`, present when `sign` was set.
+    pub submission: Option<Value>,
+    pub submit_url: Option<String>,
+    /// The wallet's gas-coin balance, which the relayed trade does not touch.
+    pub native_balance_wei: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GaslessFeeOut {
+    /// `gas`, `zero_ex` or `integrator`.
+    pub kind: String,
+    pub token: String,
+    pub amount_raw: String,
+}
+
+/// An EIP-712 order of a gasless swap.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GaslessOrderOut {
+    /// 0x's order type, e.g. `permit` or `settler_metatransaction`.
+    pub kind: String,
+    /// EIP-712 hash, checked against the typed data.
+    pub hash: String,
+    pub typed_data: Value,
+    pub signature: Option<String>,
+}
+
+/// One transaction in a [`SwapPlanOut`], ready to sign and broadcast in order.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PlanStepOut {
+    /// `wrap`, `approve` or `swap`; `transfer` or `transfer_native` in a sweep.
+    pub kind: String,
+    pub description: String,
+    pub to: String,
+    pub value_wei: String,
+    pub calldata_hex: String,
+    /// Nonce to use, counting up from the wallet's pending nonce.
+    pub nonce: String,
+    pub gas_estimate: String,
+    pub gas_limit: String,
+    /// `eth_estimateGas`; `quoter` when earlier steps must land before the swap can be dry-run, or
+    /// `reset_step` when an approval can only be estimated after its allowance reset.
+    pub gas_estimate_source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "crate::schemas::access_list")]
+    pub access_list: Option<AccessList>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SwapPlanOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub steps: Vec<PlanStepOut>,
+    pub amount_out_estimate: String,
+    pub amount_out_min: String,
+    /// `amount_out_min` in the output token's base units.
+    pub amount_out_min_raw: String,
+    /// Sum of every step's `gas_limit`.
+    pub total_gas_limit: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PlanRebalanceParams {
+    /// Wallet to rebalance; defaults to the configured signer.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Target weights, which must add up to 10_000 bps.
+    pub targets: Vec<RebalanceTargetParams>,
+    /// Drift from a target, in bps of the portfolio, tolerated before any swap is planned.
+    #[serde(default = "default_rebalance_tolerance_bps")]
+    pub tolerance_bps: u32,
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u32,
+    #[serde(default = "default_fee")]
+    pub fee: u32,
+    /// Block to read balances, prices and quotes at; defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+fn default_rebalance_tolerance_bps() -> u32 {
+    100 // 1%
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RebalanceTargetParams {
+    pub token: String,
+    pub weight_bps: u32,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RebalanceHoldingOut {
+    pub token: String,
+    pub symbol: String,
+    pub balance: String,
+    pub price_usd: String,
+    pub value_usd: String,
+    pub weight_bps: u32,
+    pub target_bps: u32,
+    /// `weight_bps - target_bps`; positive means overweight.
+    pub drift_bps: i64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RebalanceSwapOut {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in_wei: String,
+    pub amount_in: String,
+    pub value_usd: String,
+    pub amount_out_estimate: String,
+    pub amount_out_min: String,
+    /// Quoter gas plus router overhead; approvals are not included.
+    pub gas_estimate: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RebalancePlanOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub wallet: String,
+    pub total_value_usd: String,
+    pub tolerance_bps: u32,
+    /// True when every holding is within `tolerance_bps` of its target and `swaps` is empty.
+    pub within_tolerance: bool,
+    pub holdings: Vec<RebalanceHoldingOut>,
+    /// At most one fewer than the number of targets, largest first.
+    pub swaps: Vec<RebalanceSwapOut>,
+    pub total_gas_estimate: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<SwapFeesOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SweepToParams {
+    /// Token every dust balance is swapped into, e.g. USDC.
+    pub target: String,
+    /// Wallet to sweep; defaults to the configured signer.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Tokens to consider; defaults to the listed tokens on the active chain.
+    #[serde(default)]
+    pub tokens: Option<Vec<String>>,
+    /// Balances worth less than this many USD are left alone.
+    #[serde(default = "default_sweep_min_value_usd")]
+    pub min_value_usd: String,
+    /// Balances worth more than this many USD are not dust and are left alone.
+    #[serde(default)]
+    pub max_value_usd: Option<String>,
+    /// Most of a balance's USD value its swap (and approval) gas may cost, in bps; defaults to
+    /// `gas_worthiness_bps`, else 2_500.
+    #[serde(default)]
+    pub max_gas_bps: Option<u32>,
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u32,
+    #[serde(default = "default_fee")]
+    pub fee: u32,
+    /// Block to read balances, prices and quotes at; defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Free-form note journaled with the call, e.g. the strategy behind it.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels journaled with the call; `get_activity_history` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_sweep_min_value_usd() -> String {
+    "1".into()
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DustSwapOut {
+    pub token: String,
+    pub symbol: String,
+    pub amount_in_wei: String,
+    pub amount_in: String,
+    pub value_usd: String,
+    pub amount_out_estimate: String,
     pub amount_out_min: String,
+    /// Quoter gas plus router overhead, and an approval when the router's allowance is short.
+    pub gas_estimate: String,
+    pub gas_cost_usd: String,
+    pub needs_approval: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DustSkipOut {
+    pub token: String,
+    pub symbol: String,
+    pub balance: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_usd: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SweepToOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub wallet: String,
+    pub target: String,
+    pub target_address: String,
+    /// Worth sweeping, largest first.
+    pub swaps: Vec<DustSwapOut>,
+    /// Non-zero balances left alone, with the reason.
+    pub skipped: Vec<DustSkipOut>,
+    pub total_value_usd: String,
+    pub total_amount_out_estimate: String,
+    pub total_gas_estimate: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<SwapFeesOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TransferFromParams {
+    pub token: String,
+    /// Address whose tokens are moved; it must have approved the configured wallet.
+    pub from: String,
+    /// Recipient address or configured alias.
+    pub to: String,
+    pub amount_wei: String,
+    /// Proceed despite compliance hits; only honoured when config allows overrides.
+    #[serde(default)]
+    pub compliance_override: bool,
+    /// Block to simulate against (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Free-form note journaled with the call, e.g. the strategy behind it.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels journaled with the call; `get_activity_history` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TransferFromOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub token: String,
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_ens: Option<String>,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_ens: Option<String>,
+    pub amount_wei: String,
+    /// `amount_wei` formatted with the token's decimals.
+    pub amount: String,
+    /// Allowance left for the wallet once this transfer lands.
+    pub allowance_after: String,
+    pub calldata_hex: String,
+    pub gas_estimate: String,
+    pub gas_limit: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DistributionMode {
+    /// One `transfer` per recipient with consecutive nonces.
+    #[default]
+    Transfers,
+    /// A single `disperseToken` call through the configured Disperse contract.
+    Disperse,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PayoutParams {
+    /// Recipient address or configured alias.
+    pub to: String,
+    pub amount_wei: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DistributeTokensParams {
+    pub token: String,
+    pub payouts: Vec<PayoutParams>,
+    #[serde(default)]
+    pub mode: DistributionMode,
+    /// Proceed despite compliance hits; only honoured when config allows overrides.
+    #[serde(default)]
+    pub compliance_override: bool,
+    /// Block to simulate against (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<String>,
+    /// Free-form note journaled with the call, e.g. the strategy behind it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Labels journaled with the call; `get_activity_history` can filter on them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DistributionOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub token: String,
+    pub mode: DistributionMode,
+    pub recipients: usize,
+    pub total_amount_wei: String,
+    pub total_amount: String,
+    pub steps: Vec<PlanStepOut>,
+    pub total_gas_estimate: String,
+    pub total_gas_limit: String,
+    /// Cost preview for every step together; absent when the node cannot provide fee data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fees: Option<SwapFeesOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetQuoteLadderParams {
+    pub from_token: String,
+    pub to_token: String,
+    /// Input amounts in human units of `from_token`, e.g. `["0.1", "1", "10"]`.
+    #[serde(default = "default_ladder_sizes")]
+    pub sizes: Vec<String>,
+    #[serde(default = "default_fee")]
+    pub fee: u32,
+    /// Block to quote against (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+fn default_ladder_sizes() -> Vec<String> {
+    ["0.1", "1", "10", "100"].map(String::from).to_vec()
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QuoteLadderOut {
+    pub from_token: String,
+    pub to_token: String,
+    pub fee: u32,
+    /// Block every rung was quoted at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// One rung per size, smallest first.
+    pub levels: Vec<QuoteLevelOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QuoteLevelOut {
+    pub amount_in: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_out: Option<String>,
+    /// Effective `to_token` received per `from_token` at this size.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    /// Worsening of `price` relative to the smallest rung that quoted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_impact_bps: Option<u64>,
+    /// Why this size could not be quoted (e.g. not enough liquidity in the pool).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetCandlesParams {
+    pub base: String,
+    pub quote: String,
+    #[serde(default = "default_fee")]
+    pub fee: u32,
+    /// First block to scan; defaults to a short lookback before `to_block`.
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    /// Last block to scan; defaults to the configured block tag.
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    #[serde(default = "default_candle_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_candle_interval_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CandlesOut {
+    pub pool: String,
+    pub base: String,
+    pub quote: String,
+    pub fee: u32,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub interval_secs: u64,
+    /// Swaps found in the range.
+    pub trades: usize,
+    /// Oldest first; intervals without swaps are omitted.
+    pub candles: Vec<CandleOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+/// OHLCV bucket; prices are `quote` per `base`, `volume` is in `base` units.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CandleOut {
+    /// Unix timestamp of the interval start.
+    pub open_time: u64,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+    pub quote_volume: String,
+    pub trades: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MevRiskOut {
+    pub rating: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_impact_bps: Option<u64>,
+    pub max_extractable: String,
+    pub recommendation: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChainInfoOut {
+    pub chain_id: u64,
+    pub name: String,
+    pub block_number: u64,
+    pub warnings: Vec<WarningOut>,
+}
+
+/// Connection state of one chain in the provider pool.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ChainProviderOut {
+    pub chain_id: u64,
+    /// The chain the server was started against.
+    pub active: bool,
+    pub http_connected: bool,
+    pub ws_configured: bool,
+    pub ws_connected: bool,
+    /// Fewer consecutive failures than it takes to drop the provider.
+    pub healthy: bool,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProviderPoolOut {
+    pub active_chain_id: u64,
+    pub shut_down: bool,
+    pub chains: Vec<ChainProviderOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchMempoolParams {
+    /// Wallet to watch; defaults to the configured signer address.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Extra token contracts whose pending activity should be reported.
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    #[serde(default = "default_watch_duration_secs")]
+    pub duration_secs: u64,
+    #[serde(default = "default_watch_max_results")]
+    pub max_results: usize,
+}
+
+fn default_watch_duration_secs() -> u64 {
+    10
+}
+
+fn default_watch_max_results() -> usize {
+    50
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct MempoolMatch {
+    pub hash: String,
+    pub kind: String,
+    pub from: String,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_raw: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MempoolWatchOut {
+    pub wallet: String,
+    pub window_secs: u64,
+    pub scanned: usize,
+    pub matches: Vec<MempoolMatch>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchDepositsParams {
+    /// Wallet to watch; defaults to the configured signer address.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Resume from this block (e.g. the previous `last_block + 1`); defaults to the next block.
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    #[serde(default = "default_deposit_watch_secs")]
+    pub duration_secs: u64,
+}
+
+fn default_deposit_watch_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct DepositOut {
+    /// `token`, `eth`, or `eth_internal` (balance rose without a direct transfer).
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub amount_raw: String,
+    /// Formatted amount; absent for tokens whose decimals are unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DepositWatchOut {
+    pub wallet: String,
+    pub from_block: u64,
+    /// Last block scanned; pass `last_block + 1` as `from_block` to continue without gaps.
+    pub last_block: u64,
+    pub deposits: Vec<DepositOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchTokenParams {
+    /// Token whose `Transfer` events are watched: address or known symbol.
+    pub token: String,
+    /// Address the transfers must involve; defaults to the configured signer address.
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub direction: TransferDirection,
+    /// Resume from this block (e.g. the previous `last_block + 1`); defaults to the next block.
+    #[serde(default)]
+    pub from_block: Option<u64>,
+    #[serde(default = "default_token_watch_secs")]
+    pub duration_secs: u64,
+    /// Stop once this many transfers have been seen.
+    #[serde(default = "default_watch_max_results")]
+    pub max_results: usize,
+}
+
+fn default_token_watch_secs() -> u64 {
+    60
+}
+
+/// Which side of a transfer the watched address is on.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferDirection {
+    /// Transfers to the address.
+    In,
+    /// Transfers from the address.
+    Out,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct TokenTransferOut {
+    /// `in` or `out` relative to the watched address; a transfer to itself is `in`.
+    pub direction: TransferDirection,
+    pub from: String,
+    pub to: String,
+    pub amount_raw: String,
+    pub amount: String,
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_index: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenWatchOut {
+    pub token: String,
+    pub symbol: String,
+    pub address: String,
+    pub direction: TransferDirection,
+    pub from_block: u64,
+    /// Last block scanned; pass `last_block + 1` as `from_block` to continue without gaps.
+    pub last_block: u64,
+    /// Oldest first.
+    pub transfers: Vec<TokenTransferOut>,
+    /// `max_results` was reached and later transfers up to `last_block` were left out.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchTransactionParams {
+    /// Hash of a transaction already broadcast.
+    pub tx_hash: String,
+    #[serde(default = "default_tx_watch_secs")]
+    pub duration_secs: u64,
+    /// Blocks the transaction may stay pending below the market fee before it counts as stuck;
+    /// defaults to `stuck_tx_blocks`.
+    #[serde(default)]
+    pub stuck_after_blocks: Option<u64>,
+}
+
+fn default_tx_watch_secs() -> u64 {
+    120
+}
+
+/// How a `watch_transaction` call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxWatchStatus {
+    Mined,
+    /// Pending for `stuck_after_blocks` with fees below what the node now suggests.
+    Stuck,
+    /// Still pending, at or above the market fee, when the window closed.
+    Pending,
+    /// Another transaction from the sender was mined with the same nonce.
+    Replaced,
+    /// The node no longer knows the transaction and its nonce is unused.
+    Dropped,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TxWatchOut {
+    pub tx_hash: String,
+    pub from: String,
+    pub nonce: String,
+    pub status: TxWatchStatus,
+    /// Head block when the watch started.
+    pub first_block: u64,
+    pub last_block: u64,
+    /// Blocks mined while the transaction stayed pending.
+    pub blocks_pending: u64,
+    /// The transaction's own fees; `max_fee_per_gas` is the gas price of a legacy transaction.
+    pub fees: TxFeesOut,
+    /// What the node suggested when the transaction was last checked against the market.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market_fees: Option<TxFeesOut>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Receipt status of a mined transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    /// Fees for a same-nonce replacement; present when the transaction is stuck.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed_up: Option<SpeedUpOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct TxFeesOut {
+    pub max_fee_per_gas: String,
+    /// Absent for legacy transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct SpeedUpOut {
+    pub fees: TxFeesOut,
+    /// The replacement signed by the configured wallet under the `bump` policy, for
+    /// `eth_sendRawTransaction`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_tx: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WatchSpreadParams {
+    /// Token whose price is measured: address or known symbol.
+    pub base: String,
+    /// Token `base` is measured against; set this or `quote_feed`.
+    #[serde(default)]
+    pub quote: Option<String>,
+    /// Chainlink aggregator reporting the reference asset in USD (e.g. BTC/USD for WBTC).
+    #[serde(default)]
+    pub quote_feed: Option<String>,
+    /// Expected `base / quote` price ratio, e.g. `"1"` for a peg.
+    #[serde(default = "default_spread_target_ratio")]
+    pub target_ratio: String,
+    /// Deviation from `target_ratio`, in bps, that ends the watch with an alert.
+    #[serde(default = "default_spread_band_bps")]
+    pub band_bps: u32,
+    #[serde(default = "default_spread_watch_secs")]
+    pub duration_secs: u64,
+}
+
+fn default_spread_target_ratio() -> String {
+    "1".to_string()
+}
+
+fn default_spread_band_bps() -> u32 {
+    100 // 1%
+}
+
+fn default_spread_watch_secs() -> u64 {
+    120
+}
+
+/// How a `watch_spread` call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SpreadStatus {
+    /// The last sample left the band.
+    Breached,
+    /// Every sample stayed within the band until the window closed.
+    WithinBand,
+}
+
+/// Both legs of a pair at one block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct SpreadSampleOut {
+    pub block_number: u64,
+    pub base_usd: String,
+    pub quote_usd: String,
+    /// `base_usd / quote_usd`.
+    pub ratio: String,
+    /// Deviation of `ratio` from the target in bps; positive when `base` trades rich.
+    pub spread_bps: i64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SpreadWatchOut {
+    pub base: String,
+    /// Symbol of the quote token, or the address of the quote feed.
+    pub quote: String,
+    pub target_ratio: String,
+    pub band_bps: u32,
+    pub status: SpreadStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_block: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_block: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_spread_bps: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_spread_bps: Option<i64>,
+    /// One per new block seen, oldest first.
+    pub samples: Vec<SpreadSampleOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct ScanArbitrageParams {
+    /// `BASE/QUOTE` pairs, e.g. `WETH/USDC`; defaults to `arb_pairs`.
+    #[serde(default)]
+    pub pairs: Option<Vec<String>>,
+    /// USD notional each pair is quoted at; defaults to `arb_trade_usd`.
+    #[serde(default)]
+    pub trade_usd: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetArbitragePlanParams {
+    /// `id` of an opportunity from `scan_arbitrage` or an `arb_opportunity` event.
+    pub id: String,
+}
+
+/// A round trip that sells `base` on one venue and buys it back on another for more than it
+/// started with, net of gas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ArbOpportunityOut {
+    pub id: String,
+    /// `BASE/QUOTE`.
+    pub pair: String,
+    pub block_number: u64,
+    /// Unix seconds the opportunity was found.
+    pub found_at: u64,
+    /// Venue `base` is sold on.
+    pub sell_venue: String,
+    /// Venue the proceeds buy `base` back on.
+    pub buy_venue: String,
+    /// `base` sold, in human units.
+    pub amount_in: String,
+    /// `base` bought back, in human units.
+    pub amount_back: String,
+    /// `amount_back - amount_in`, in bps of `amount_in`.
+    pub spread_bps: u64,
+    pub profit_usd: String,
+    /// Both legs' gas at the node's max fee per gas.
+    pub gas_cost_usd: String,
+    pub net_profit_usd: String,
+}
+
+/// One swap of an arbitrage round trip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ArbLegOut {
+    pub venue: String,
+    pub router: String,
+    /// Uniswap V3 pool fee; absent on V2 venues.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u32>,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in_wei: String,
+    pub amount_in: String,
+    pub amount_out_estimate: String,
+    /// `amount_out_estimate` less a 0.5% slippage tolerance.
+    pub amount_out_min: String,
+    pub gas_estimate: String,
+}
+
+/// An opportunity with the swaps that would capture it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct ArbPlanOut {
+    #[serde(flatten)]
+    pub opportunity: ArbOpportunityOut,
+    /// Sell leg first; the buy leg spends exactly the sell leg's estimated output.
+    pub legs: Vec<ArbLegOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+/// A pair a scan found nothing on, and why.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ArbSkipOut {
+    pub pair: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ArbScanOut {
+    pub block_number: u64,
+    pub trade_usd: u64,
+    /// Venues compared, e.g. `uniswap_v3 (fee 500)` or `sushiswap_v2`.
+    pub venues: Vec<String>,
+    pub opportunities: Vec<ArbOpportunityOut>,
+    pub skipped: Vec<ArbSkipOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetPortfolioRiskParams {
+    /// Wallet to assess; defaults to the configured signer.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Tokens to include besides the native coin; defaults to every listed token.
+    #[serde(default)]
+    pub tokens: Option<Vec<String>>,
+    /// Days of daily price samples volatility is measured over.
+    #[serde(default = "default_risk_lookback_days")]
+    pub lookback_days: u32,
+    /// Block to read balances at and end the lookback on; defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+fn default_risk_lookback_days() -> u32 {
+    30
+}
+
+/// Concentration, stablecoin share and volatility of a wallet's holdings. Percentages are of
+/// the portfolio's USD value; volatilities are standard deviations of daily log returns.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PortfolioRiskOut {
+    pub wallet: String,
+    pub block_number: u64,
+    pub lookback_days: u32,
+    /// Daily price samples taken, including `block_number`.
+    pub samples: usize,
+    pub total_value_usd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub largest_holding: Option<String>,
+    /// Weight of the largest holding.
+    pub concentration_pct: String,
+    /// Weight of the USD stablecoins in `usd_quote_tokens`.
+    pub stablecoin_pct: String,
+    /// Volatility of the current weights held over the lookback; correlations included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portfolio_daily_volatility_pct: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub portfolio_annualized_volatility_pct: Option<String>,
+    pub holdings: Vec<RiskHoldingOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RiskHoldingOut {
+    /// ERC-20 contract; absent for the native coin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    pub symbol: String,
+    pub balance: String,
+    /// Absent when the holding has no USD price; it is then left out of every weight.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_usd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_usd: Option<String>,
+    pub weight_pct: String,
+    pub stablecoin: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_volatility_pct: Option<String>,
+    /// Daily volatility scaled by the square root of 365.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annualized_volatility_pct: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestStatus {
+    Pass,
+    /// Works, but needs a look (slow RPC, stale feed).
+    Warn,
+    Fail,
+    /// Not applicable to this configuration, e.g. no signer.
+    Skip,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SelfTestCheckOut {
+    pub name: String,
+    pub status: SelfTestStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SelfTestOut {
+    pub chain_id: u64,
+    /// No check failed; warnings and skips still pass.
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheckOut>,
+    /// The report as plain text, one line per check.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceKind {
+    /// Evicts the least recently used entries.
+    Cache,
+    /// Drops the oldest rows.
+    Log,
+}
+
+/// One bounded cache or log after a `maintenance` pass.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MaintenanceItemOut {
+    pub store: String,
+    pub kind: MaintenanceKind,
+    pub entries: usize,
+    pub max_entries: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// Entries this pass dropped.
+    pub removed: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MaintenanceOut {
+    pub ran_at: u64,
+    pub removed: usize,
+    pub stores: Vec<MaintenanceItemOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetEventsParams {
+    /// Return events after this `seq`; pass the previous `next_seq` to continue.
+    #[serde(default)]
+    pub after_seq: u64,
+    #[serde(default = "default_events_limit")]
+    pub limit: usize,
+    /// Only these event types, e.g. `price_tick`, `deposit`; all when empty.
+    #[serde(default)]
+    pub kinds: Vec<String>,
+}
+
+fn default_events_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EventsOut {
+    pub events: Vec<EventRecord>,
+    /// `after_seq` for the next call.
+    pub next_seq: u64,
+    /// Events after `after_seq` already dropped from the log.
+    pub missed: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WhitelistAddressParams {
+    pub address: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WhitelistEntryOut {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ens_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub added_at: u64,
+    /// Unix time from which the address may receive funds.
+    pub active_at: u64,
+    pub active: bool,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WhitelistOut {
+    pub enabled: bool,
+    pub delay_secs: u64,
+    pub entries: Vec<WhitelistEntryOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PanicStopParams {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ResumeParams {
+    #[schemars(with = "String")]
+    pub admin_secret: SecretString,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct KillSwitchOut {
+    pub halted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Unix time the halt began.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub halted_at: Option<u64>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TokenRiskParams {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenRiskFinding {
+    pub category: String,
+    pub detail: String,
+    pub severity: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenRiskOut {
+    pub token: String,
+    pub symbol: Option<String>,
+    pub decimals: Option<u32>,
+    pub proxy_implementation: Option<String>,
+    pub owner: Option<String>,
+    pub owner_renounced: Option<bool>,
+    pub risk_level: String,
+    pub findings: Vec<TokenRiskFinding>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct VerifySignatureParams {
+    /// Account the signature claims to come from.
+    pub signer: String,
+    /// Text signed as an EIP-191 personal message; exactly one of `message` and `hash`.
+    #[serde(default)]
+    pub message: Option<String>,
+    /// 32-byte digest signed as-is, e.g. an EIP-712 typed-data hash.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Hex signature: 65 bytes from an externally owned account, any encoding a wallet accepts.
+    pub signature: String,
+    /// Block to check at (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+/// How a valid signature was validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureMethod {
+    /// ECDSA recovery yields the signer.
+    Ecdsa,
+    /// The signer contract's `isValidSignature` returned the ERC-1271 magic value.
+    Erc1271,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VerifySignatureOut {
+    pub signer: String,
+    /// Hash the signature was checked against.
+    pub digest: String,
+    pub valid: bool,
+    /// `None` when the signature is invalid.
+    pub method: Option<SignatureMethod>,
+    /// The signer has code: a smart-contract wallet, or an account with delegated code.
+    pub is_contract: bool,
+    /// Address an ECDSA recovery yields, when the signature is 65 bytes.
+    pub recovered: Option<String>,
+    pub detail: String,
+    pub block_number: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetDelegationParams {
+    /// Account to inspect; defaults to the configured wallet.
+    #[serde(default)]
+    pub address: Option<String>,
+    /// Block to read at (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+/// What an account's code says about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountCodeKind {
+    /// No code: a plain externally owned account.
+    Eoa,
+    /// An EIP-7702 delegation designator: the account runs another contract's code.
+    Delegated,
+    /// Any other code: a contract account.
+    Contract,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DelegationOut {
+    pub address: String,
+    pub kind: AccountCodeKind,
+    /// Contract whose code the account runs, when `kind` is `delegated`.
+    pub delegate: Option<String>,
+    pub block_number: u64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SignAuthorizationParams {
+    /// Contract the wallet should delegate to; leave out with `revoke`.
+    #[serde(default)]
+    pub delegate: Option<String>,
+    /// Delegate to the zero address, which clears the wallet's delegation.
+    #[serde(default)]
+    pub revoke: bool,
+    /// Authorization nonce; defaults to the wallet's pending nonce (plus one with
+    /// `self_submitted`).
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// The wallet sends the type-4 transaction itself, which uses up its current nonce first.
+    #[serde(default)]
+    pub self_submitted: bool,
+    #[serde(default)]
+    pub compliance_override: bool,
+}
+
+/// A signed EIP-7702 authorization tuple, ready for a type-4 transaction's authorization list.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AuthorizationOut {
+    /// The account granting the delegation: the configured wallet.
+    pub authority: String,
+    pub chain_id: u64,
+    pub delegate: String,
+    pub nonce: u64,
+    /// `keccak256(0x05 || rlp([chain_id, delegate, nonce]))`, the digest signed.
+    pub hash: String,
+    pub y_parity: u8,
+    pub r: String,
+    pub s: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TraceCallParams {
+    pub to: String,
+    /// Caller; defaults to the configured wallet, or the zero address without one.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Hex calldata.
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub value_wei: Option<String>,
+    /// Block to trace against (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Keep every frame instead of collapsing subtrees that completed without a revert.
+    #[serde(default)]
+    pub full: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TraceFrameOut {
+    /// `CALL`, `STATICCALL`, `DELEGATECALL`, `CREATE`, ...
+    pub kind: String,
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_wei: Option<String>,
+    pub gas_used: String,
+    /// First four bytes of the input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    pub reverted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub calls: Vec<TraceFrameOut>,
+    /// Frames below this one left out of `calls` by pruning.
+    pub omitted_calls: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TraceCallOut {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub reverted: bool,
+    /// Reason of the outermost reverted frame.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub gas_used: String,
+    /// Frames in the full trace, before pruning.
+    pub total_calls: usize,
+    pub root: TraceFrameOut,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SimulateBundleParams {
+    /// Transactions in the order they would be mined.
+    pub steps: Vec<BundleStepParams>,
+    /// Block the bundle runs on top of (number, hash, or tag); defaults to the configured block
+    /// tag.
+    #[serde(default)]
+    pub block: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BundleStepParams {
+    /// Echoed in the result, e.g. `approve`.
+    #[serde(default)]
+    pub label: Option<String>,
+    pub to: String,
+    /// Sender; defaults to the configured wallet, or the zero address without one.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Hex calldata.
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub value_wei: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStepStatus {
+    Success,
+    Reverted,
+}
+
+/// An ERC-20 `Transfer` or `Approval` a step emitted.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BundleTokenEventOut {
+    /// `transfer` or `approval`.
+    pub kind: String,
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Sender, or owner of an approval.
+    pub from: String,
+    /// Recipient, or spender of an approval.
+    pub to: String,
+    pub amount_raw: String,
+    /// In human units; registry tokens only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BundleStepOut {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub from: String,
+    pub to: String,
+    pub status: BundleStepStatus,
+    pub gas_used: String,
+    pub return_data: String,
+    /// The node's error message for a reverted step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub token_events: Vec<BundleTokenEventOut>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BundleOut {
+    /// Block the bundle ran on top of.
+    pub block_number: u64,
+    /// Every step succeeded.
+    pub succeeded: bool,
+    /// Index of the first reverted step.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_failure: Option<usize>,
+    /// Sum over the steps.
+    pub gas_used: String,
+    pub steps: Vec<BundleStepOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetActivityReportParams {
+    /// Length of the reporting period ending now.
+    #[serde(default = "default_report_period_secs")]
+    pub since_secs: u64,
+    /// Only count swaps journaled with this tag; tool-call stats are unaffected.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+fn default_report_period_secs() -> u64 {
+    86_400
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MethodStatsOut {
+    pub calls: usize,
+    pub errors: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ActivityErrorOut {
+    pub at: u64,
+    pub method: String,
+    pub code: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SwapEntryOut {
+    pub at: u64,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in: String,
+    pub amount_out: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SwapVolumeOut {
+    pub from_token: String,
+    pub to_token: String,
+    pub swaps: usize,
+    pub amount_in: String,
+    pub amount_out: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ActivityReportOut {
+    pub period_start: u64,
+    pub period_end: u64,
+    pub total_calls: usize,
+    pub errors: usize,
+    pub calls_by_method: BTreeMap<String, MethodStatsOut>,
+    /// Newest first.
+    pub recent_errors: Vec<ActivityErrorOut>,
+    pub simulated_swaps: usize,
+    pub swap_volume: Vec<SwapVolumeOut>,
+    /// Newest first, with the `memo` / `tags` they were journaled with.
+    pub recent_swaps: Vec<SwapEntryOut>,
+    /// Sum of `gas_estimate` over the simulated swaps.
+    pub estimated_gas: u64,
+    /// Always zero: this server simulates and never broadcasts.
+    pub broadcast_transactions: usize,
+    pub fees_paid_wei: String,
+    /// Human-readable rendering of the report.
+    pub summary: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetActivityHistoryParams {
+    /// Length of the period ending now.
+    #[serde(default = "default_report_period_secs")]
+    pub since_secs: u64,
+    /// Only entries journaled with this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Most entries to return, newest first.
+    #[serde(default = "default_history_limit")]
+    pub limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    100
+}
+
+/// A journaled write-tool call.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ActivityEntryOut {
+    pub at: u64,
+    pub tool: String,
+    /// What the call would do, in one line.
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ActivityHistoryOut {
+    pub period_start: u64,
+    pub period_end: u64,
+    /// Entries matching the period and tag, before `limit`.
+    pub total: usize,
+    /// Newest first.
+    pub entries: Vec<ActivityEntryOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+/// Provenance attached to every successful tool result under `meta`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ResponseMetaOut {
+    pub chain_id: u64,
+    /// Block the data reflects, when the tool read chain state at one.
+    pub block_number: Option<u64>,
+    /// Unix time the server produced the response.
+    pub server_time: u64,
+    /// Where the data came from, e.g. `chainlink`, `uniswap_v3 (via USDC)`, `rpc`, `registry`.
+    pub sources: Vec<String>,
+    /// Endpoint the call's reads were pinned to with `_meta.rpcEndpoint`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_endpoint: Option<String>,
+}
+
+/// Server signature over a tool result, added under `attestation` when `sign_responses` is on.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AttestationOut {
+    /// Server wallet address.
+    pub signer: String,
+    /// Unix time signed along with the result; equals `meta.server_time`.
+    pub timestamp: u64,
+    /// keccak256 of the result without `attestation`, as compact JSON with sorted keys.
+    pub result_hash: String,
+    /// EIP-191 signature over keccak256(`result_hash` ‖ `timestamp` as big-endian uint64).
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListApprovalTemplatesParams {
+    /// Only templates for this token (symbol, `SYMBOL:address` or address); any registered token
+    /// is accepted, not just the listed ones templates cover by default.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Only templates for this spender: `uniswap_v3_router`, `permit2` or `disperse`.
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Base units to approve; when set, each template carries ready `approve` calldata.
+    #[serde(default)]
+    pub amount_wei: Option<String>,
+}
+
+/// Contract this server's tools ask wallets to approve.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct ApprovalSpenderOut {
+    pub protocol: String,
+    pub name: String,
+    pub address: String,
+}
+
+/// One curated approval: `approve(spender, amount)` sent to `token_address`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApprovalTemplateOut {
+    /// `<protocol>:<SYMBOL>`, e.g. `permit2:WETH`.
+    pub id: String,
+    pub description: String,
+    pub protocol: String,
+    pub spender: String,
+    pub token: String,
+    pub token_address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_wei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calldata_hex: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApprovalTemplatesOut {
+    pub chain_id: u64,
+    pub spenders: Vec<ApprovalSpenderOut>,
+    pub templates: Vec<ApprovalTemplateOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetWalletInfoParams {
+    /// ERC-20 to request instead of the native coin.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Amount to request, in human units of the native coin or `token`.
+    #[serde(default)]
+    pub amount: Option<String>,
+    /// Attach the payment URI as a text QR code.
+    #[serde(default)]
+    pub qr: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WalletInfoOut {
+    /// Address derived from the configured signing key.
+    pub address: String,
+    pub chain_id: u64,
+    pub native_symbol: String,
+    /// EIP-681 payment request to this wallet.
+    pub payment_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_wei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qr: Option<String>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetTokenDeploymentsParams {
+    /// Symbol of a mapped asset, or a token on the active chain by symbol or address.
+    pub token: String,
+    /// Only this chain's deployment, e.g. a bridge destination.
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenDeploymentOut {
+    pub chain_id: u64,
+    pub address: String,
+    pub decimals: u8,
+    /// Deployed on the chain this server runs on.
+    pub active: bool,
+}
+
+/// Where one asset lives on each chain, from the bundled canonical mapping.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenDeploymentsOut {
+    pub symbol: String,
+    /// The chain this server runs on.
+    pub chain_id: u64,
+    pub deployments: Vec<TokenDeploymentOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ParsePaymentRequestParams {
+    /// EIP-681 `ethereum:` URI or `0x` signed raw transaction.
+    pub request: String,
+}
+
+/// A decoded payment request: who gets paid how much of what.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PaymentRequestOut {
+    /// `eip681` or `raw_transaction`.
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    pub to: String,
+    /// ERC-20 contract, for a token payment; absent for the native coin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Base units, wei for the native coin; absent when the request leaves it to the payer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_wei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<String>,
+    /// Signer recovered from a raw transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Calldata of a raw transaction that is not a plain payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calldata_hex: Option<String>,
+    /// Ready-made `distribute_tokens` params for a token payment with an amount.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribute_tokens: Option<DistributeTokensParams>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CreateBurnerWalletParams {
+    /// Amount to request in the funding URI, in human units of the native coin.
+    #[serde(default)]
+    pub amount: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BurnerWalletOut {
+    pub address: String,
+    pub chain_id: u64,
+    /// Encrypted keystore holding the burner's key.
+    pub keystore_path: String,
+    /// EIP-681 request to fund the burner from another wallet.
+    pub payment_uri: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SweepWalletParams {
+    /// Burner address, as returned by `create_burner_wallet`.
+    pub burner: String,
+    /// ERC-20s to sweep before the native coin.
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    /// Free-form note journaled with the call, e.g. the strategy behind it.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels journaled with the call; `get_activity_history` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Transactions returning a burner's funds to the main wallet, signed by the burner.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SweepOut {
+    pub burner: String,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub steps: Vec<PlanStepOut>,
+    /// Each step signed by the burner, in order, for `eth_sendRawTransaction`.
+    pub signed_transactions: Vec<String>,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    /// Native coin held back to pay for every step at `max_fee_per_gas`; what is not spent stays
+    /// in the burner.
+    pub gas_reserve_wei: String,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AmountUnit {
+    Wei,
+    Gwei,
+    #[serde(alias = "eth")]
+    Ether,
+    /// A token's base units.
+    Raw,
+    /// A token's units with its decimals applied.
+    Human,
+}
+
+impl fmt::Display for AmountUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AmountUnit::Wei => "wei",
+            AmountUnit::Gwei => "gwei",
+            AmountUnit::Ether => "ether",
+            AmountUnit::Raw => "raw",
+            AmountUnit::Human => "human",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConvertUnitsParams {
+    /// Decimal number, or `0x` hex for a whole number of `from` units.
+    pub amount: String,
+    pub from: AmountUnit,
+    pub to: AmountUnit,
+    /// Token whose decimals `raw`/`human` conversions use.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ConvertUnitsOut {
+    pub amount: String,
+    pub from: AmountUnit,
+    pub to: AmountUnit,
+    /// `amount` expressed in `to` units.
+    pub result: String,
+    /// `amount` in wei, or in the token's base units.
+    pub base_units: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BacktestSwapStrategyParams {
+    pub base: String,
+    #[serde(default)]
+    pub quote: QuoteCurrency,
+    pub from_block: u64,
+    /// Last block to replay; defaults to the configured block tag.
+    #[serde(default)]
+    pub to_block: Option<u64>,
+    /// Blocks between price samples.
+    #[serde(default = "default_backtest_step_blocks")]
+    pub step_blocks: u64,
+    /// Buy `base` with all `quote` once the price is at or below this.
+    #[serde(default)]
+    pub buy_below: Option<String>,
+    /// Sell all `base` once the price is at or above this.
+    #[serde(default)]
+    pub sell_above: Option<String>,
+    /// Starting `quote` holdings in human units.
+    #[serde(default = "default_backtest_initial_quote")]
+    pub initial_quote: String,
+    /// Starting `base` holdings in human units.
+    #[serde(default)]
+    pub initial_base: Option<String>,
+    /// Cost of each trade (pool fee plus slippage), in bps of its size.
+    #[serde(default = "default_backtest_fee_bps")]
+    pub fee_bps: u32,
+}
+
+fn default_backtest_step_blocks() -> u64 {
+    300
+}
+
+fn default_backtest_initial_quote() -> String {
+    "1000".to_string()
+}
+
+fn default_backtest_fee_bps() -> u32 {
+    30
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BacktestSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BacktestTradeOut {
+    pub block_number: u64,
+    pub side: BacktestSide,
+    pub price: String,
+    pub base_amount: String,
+    /// Quote spent on a buy, or received after fees on a sell.
+    pub quote_amount: String,
+    /// Fee paid, in quote units.
+    pub fee: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BacktestPositionOut {
+    pub base: String,
+    pub quote: String,
+    /// `base` at the price of the moment plus `quote`.
+    pub value: String,
+}
+
+/// Hypothetical trades of a threshold rule replayed over historical prices.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BacktestOut {
+    pub base: String,
+    pub quote: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub step_blocks: u64,
+    pub samples: usize,
+    pub first_price: String,
+    pub last_price: String,
+    pub min_price: String,
+    pub max_price: String,
+    /// Valued at the first sampled price.
+    pub start: BacktestPositionOut,
+    /// Valued at the last sampled price.
+    pub end: BacktestPositionOut,
+    /// `end.value - start.value`.
+    pub pnl: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pnl_pct: Option<String>,
+    /// The starting position left alone, valued at the last price.
+    pub hold_value: String,
+    pub hold_pnl: String,
+    pub trades: Vec<BacktestTradeOut>,
+    /// Price sources the samples came from.
+    pub sources: Vec<String>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PaperFundParams {
+    pub token: String,
+    pub amount_wei: String,
+    /// Drop every paper balance and trade before crediting.
+    #[serde(default)]
+    pub reset: bool,
+    /// Free-form note kept with the paper trade.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels kept with the paper trade; `get_paper_portfolio` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PaperSwapParams {
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in_wei: String,
+    #[serde(default = "default_fee")]
+    pub fee: u32,
+    /// Block to quote at (number, hash, or tag); defaults to the configured block tag.
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Free-form note kept with the paper trade.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels kept with the paper trade; `get_paper_portfolio` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PaperTransferParams {
+    pub token: String,
+    /// Recipient address or configured alias.
+    pub to: String,
+    pub amount_wei: String,
+    /// Free-form note kept with the paper trade.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels kept with the paper trade; `get_paper_portfolio` can filter on them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetPaperPortfolioParams {
+    /// Most recent trades to include, newest first.
+    #[serde(default = "default_paper_trade_limit")]
+    pub trade_limit: usize,
+    /// Only list trades booked with this tag.
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+fn default_paper_trade_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperTradeKind {
+    Fund,
+    Swap,
+    Transfer,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PaperBalanceOut {
+    pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub amount_wei: String,
+    /// `amount_wei` formatted with the token's decimals, when the registry knows them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PaperTradeOut {
+    pub kind: PaperTradeKind,
+    pub at: u64,
+    /// Debited leg; absent for funding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sold: Option<PaperBalanceOut>,
+    /// Credited leg; absent for transfers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bought: Option<PaperBalanceOut>,
+    /// `bought` per `sold` in human units, for swaps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Block the swap was quoted at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// The virtual portfolio behind the paper-trading tools.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PaperPortfolioOut {
+    pub chain_id: u64,
+    /// Whether the portfolio is kept in `paper_portfolio_path` across restarts.
+    pub persistent: bool,
+    pub balances: Vec<PaperBalanceOut>,
+    /// The trade just booked, on `paper_fund`, `paper_swap` and `paper_transfer`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trade: Option<PaperTradeOut>,
+    pub trade_count: usize,
+    /// Most recent trades, newest first.
+    pub trades: Vec<PaperTradeOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListQuoteCurrenciesParams {
+    pub base: String,
+    /// Tokens to check as swap quotes too, e.g. `["WBTC"]` for a BTC quote.
+    #[serde(default)]
+    pub tokens: Vec<String>,
+}
+
+/// Whether `base` can be priced in one quote, and by which tool.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QuoteCurrencyOut {
+    pub quote: String,
+    /// Tool that prices `base` in this quote.
+    pub tool: String,
+    pub available: bool,
+    /// Sources the tool can draw on, in the order it tries them.
+    pub routes: Vec<String>,
+    /// Why the quote is unavailable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QuoteCurrenciesOut {
+    pub base: String,
+    pub chain_id: u64,
+    pub quotes: Vec<QuoteCurrencyOut>,
+    pub warnings: Vec<WarningOut>,
+}
+
+/// Caveat on a successful tool result, listed under its `warnings`: worth telling the user,
+/// not worth failing the call. `code` is stable for agents to branch on; `message` is for people.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct WarningOut {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListTokensParams {
+    /// Only tokens registered under this symbol.
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Only symbols shared by more than one token.
+    #[serde(default)]
+    pub duplicates_only: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RegistryTokenOut {
+    pub symbol: String,
+    pub address: String,
+    pub decimals: u8,
+    /// `SYMBOL:address`, accepted wherever a token symbol is.
+    pub qualified_symbol: String,
+    /// Chain the entry was registered for, when it is not the active one by definition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// On the bundled defaults or the configured token list.
+    pub listed: bool,
+    /// The bare symbol resolves to this token.
+    pub preferred: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SymbolDuplicateOut {
+    pub symbol: String,
+    /// Preferred first under the collision policy.
+    pub addresses: Vec<String>,
+    /// `None` under the `error` policy, which refuses the bare symbol.
+    pub resolves_to: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListTokensOut {
+    pub chain_id: u64,
+    pub collision_policy: String,
+    pub tokens: Vec<RegistryTokenOut>,
+    pub duplicates: Vec<SymbolDuplicateOut>,
+    /// Alias to the symbol, `SYMBOL:address` or address it resolves through.
+    pub aliases: BTreeMap<String, String>,
+    pub warnings: Vec<WarningOut>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetSchemasParams {
+    /// Only return this tool's schemas; defaults to every tool.
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ToolSchemaOut {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema for the request `params`.
+    pub params: Value,
+    /// JSON Schema for the response `result`.
+    pub result: Value,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SchemasOut {
+    pub tools: Vec<ToolSchemaOut>,
+    pub warnings: Vec<WarningOut>,
 }
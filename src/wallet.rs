@@ -1,14 +1,15 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use ethers::signers::{LocalWallet, Signer};
 
 use crate::{
     config::AppConfig,
     error::{AppError, AppResult},
+    secrets::{self, PRIVATE_KEY_SECRET},
 };
 
-/// Thin wrapper responsible for loading an optional signer from configuration.
-#[derive(Debug, Clone)]
+/// Thin wrapper responsible for loading an optional signer from the configured secrets backend.
+#[derive(Clone)]
 pub struct WalletManager {
     signer: Option<LocalWallet>,
 }
@@ -19,8 +20,9 @@ impl WalletManager {
     }
 
     pub fn from_config(config: &AppConfig) -> AppResult<Self> {
-        if let Some(ref key) = config.private_key {
-            let trimmed = key.trim_start_matches("0x");
+        let provider = secrets::provider_from_config(config)?;
+        if let Some(key) = provider.get(PRIVATE_KEY_SECRET)? {
+            let trimmed = key.expose().trim_start_matches("0x");
             let wallet = LocalWallet::from_str(trimmed)
                 .map_err(|err| AppError::Wallet(format!("failed to parse private key: {err}")))?;
             let wallet = wallet.with_chain_id(config.default_chain_id);
@@ -34,3 +36,12 @@ impl WalletManager {
         self.signer.clone()
     }
 }
+
+// Only the derived address is printed; key material never reaches logs.
+impl fmt::Debug for WalletManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalletManager")
+            .field("address", &self.signer.as_ref().map(|signer| signer.address()))
+            .finish()
+    }
+}
@@ -8,7 +8,10 @@ use ethers::{
     types::{Address, U256},
 };
 
-use walletmcp::implementations::{erc20, swap::simulate_swap};
+use walletmcp::implementations::{
+    erc20,
+    swap::{SwapSettings, simulate_swap},
+};
 use walletmcp::types::SwapTokensParams;
 
 /// This test talks to a live network. It is ignored by default; run it manually with:
@@ -103,9 +106,16 @@ async fn swap_tokens_params_mainnet_smoke() -> Result<()> {
         params.amount_in_wei
     );
 
-    let sim_out = simulate_swap(provider, wallet, from_token, to_token, params)
-        .await
-        .map_err(|err| anyhow::anyhow!("simulate_swap failed: {err}"))?;
+    let sim_out = simulate_swap(
+        provider,
+        wallet,
+        from_token,
+        to_token,
+        params,
+        SwapSettings::default(),
+    )
+    .await
+    .map_err(|err| anyhow::anyhow!("simulate_swap failed: {err}"))?;
 
     ensure!(
         !sim_out.amount_out_estimate.is_empty(),